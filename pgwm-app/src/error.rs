@@ -1,5 +1,6 @@
 use alloc::ffi::NulError;
 use alloc::string::{FromUtf8Error, String};
+use alloc::vec::Vec;
 use core::fmt::Formatter;
 
 use tiny_std::Error as StdError;
@@ -18,17 +19,22 @@ pub(crate) enum Error {
     XcbProto(xcb_rust_protocol::Error),
     X11EventParse,
     GlyphMismatch,
-    BecomeWm,
+    /// Another WM is running, named if it could be found
+    BecomeWm(Option<String>),
     Tiling,
     NoAppropriateVisual,
     ContentToCstr(NulError),
     ConvertToUtf8(FromUtf8Error),
     ConvertCoreToUtf8(core::str::Utf8Error),
     StateInvalidated,
+    /// The X server closed the connection
+    XDisconnected,
     GracefulShutdown,
     FullRestart,
     ParseFloat,
     FontLoad(&'static str),
+    InvalidConfig(Vec<pgwm_core::config::cfg::Problem>),
+    SpawnNotFound(String),
     Uring(String),
     Syscall(StdError),
     Rusl(RuslError),
@@ -54,8 +60,11 @@ impl core::fmt::Display for Error {
             Error::GlyphMismatch => {
                 f.write_str("Number of glyph ids not corresponding to number of metrics")
             }
-            Error::BecomeWm => f.write_str(
-                "Could not become wm, access denied, there is likely another WM running",
+            Error::BecomeWm(Some(name)) => f.write_fmt(format_args!(
+                "Another window manager, {name}, is already running on this display, exit it before starting pgwm"
+            )),
+            Error::BecomeWm(None) => f.write_str(
+                "Another window manager is already running on this display, exit it before starting pgwm",
             ),
             Error::Tiling => f.write_str(
                 "Failed to calculate correct tiling dimensions (this is a programming error)",
@@ -67,10 +76,21 @@ impl core::fmt::Display for Error {
             Error::ConvertToUtf8(e) => core::fmt::Display::fmt(e, f),
             Error::ConvertCoreToUtf8(e) => core::fmt::Display::fmt(e, f),
             Error::StateInvalidated => f.write_str("State Invalidated"),
+            Error::XDisconnected => f.write_str("Lost the connection to the X server"),
             Error::GracefulShutdown => f.write_str("Exit triggered"),
             Error::FullRestart => f.write_str("Restart triggered"),
             Error::ParseFloat => f.write_str("Size not parseable as f32"),
             Error::FontLoad(s) => f.write_fmt(format_args!("Failed to load font {s}")),
+            Error::InvalidConfig(problems) => {
+                f.write_str("Invalid configuration")?;
+                for problem in problems {
+                    f.write_fmt(format_args!("\n  {problem}"))?;
+                }
+                Ok(())
+            }
+            Error::SpawnNotFound(s) => {
+                f.write_fmt(format_args!("No executable named {s} in $PATH"))
+            }
             Error::Syscall(e) => f.write_fmt(format_args!("Syscall error {e}")),
             Error::Rusl(e) => f.write_fmt(format_args!("Rusl error {e}")),
             Error::Uring(e) => f.write_fmt(format_args!("Uring error {e}")),
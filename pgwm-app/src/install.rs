@@ -0,0 +1,101 @@
+use alloc::format;
+use alloc::string::String;
+
+use rusl::platform::Mode;
+use rusl::string::unix_str::UnixStr;
+use tiny_std::io::Write;
+use tiny_std::{eprintln, println};
+
+use crate::error::Result;
+
+/// Where display managers look for X sessions
+const DEFAULT_SESSION_DIR: &str = "/usr/share/xsessions";
+/// The running binary, copied when installing to a prefix
+const SELF_EXE: &UnixStr = UnixStr::from_str_checked("/proc/self/exe\0");
+
+const HELP_MSG: &str = "Usage: pgwm --install-session [options]
+  Writes a pgwm.desktop session entry so that display managers can start pgwm.
+    --prefix=<directory>
+      Also install this binary as <directory>/bin/pgwm and start the session with it,
+      otherwise the session runs pgwm from the display manager's $PATH
+    --session-dir=<directory>
+      Where to write pgwm.desktop, defaults to /usr/share/xsessions
+    --help
+      Display this message
+";
+
+/// Parses the arguments following `--install-session` and installs the session entry,
+/// returns the exit code
+#[must_use]
+pub fn install_session<'a>(args: impl Iterator<Item = &'a str>) -> i32 {
+    let mut prefix = None;
+    let mut session_dir = DEFAULT_SESSION_DIR;
+    for arg in args {
+        if let Some(dir) = arg.strip_prefix("--prefix=") {
+            prefix = Some(dir.trim_end_matches('/'));
+        } else if let Some(dir) = arg.strip_prefix("--session-dir=") {
+            session_dir = dir.trim_end_matches('/');
+        } else if arg == "--help" {
+            println!("{HELP_MSG}");
+            return 0;
+        } else {
+            eprintln!("Unrecognized argument {arg}\n{HELP_MSG}");
+            return 1;
+        }
+    }
+    match install(prefix, session_dir) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Failed to install session: {e}");
+            1
+        }
+    }
+}
+
+fn install(prefix: Option<&str>, session_dir: &str) -> Result<()> {
+    let exec = if let Some(prefix) = prefix {
+        let bin_dir = format!("{prefix}/bin\0");
+        tiny_std::fs::create_dir_all(UnixStr::try_from_str(&bin_dir)?)?;
+        let bin = format!("{prefix}/bin/pgwm");
+        install_binary(&bin)?;
+        println!("Installed binary at {bin}");
+        bin
+    } else {
+        String::from("pgwm")
+    };
+    tiny_std::fs::create_dir_all(UnixStr::try_from_str(&format!("{session_dir}\0"))?)?;
+    let entry = format!("{session_dir}/pgwm.desktop");
+    write_file(&entry, session_entry(&exec).as_bytes(), Mode::from(0o644))?;
+    println!("Installed session entry at {entry}");
+    Ok(())
+}
+
+fn install_binary(bin: &str) -> Result<()> {
+    let content = tiny_std::fs::read(SELF_EXE)?;
+    write_file(bin, &content, Mode::from(0o755))
+}
+
+fn write_file(path: &str, content: &[u8], mode: Mode) -> Result<()> {
+    let mut file = tiny_std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(UnixStr::try_from_str(&format!("{path}\0"))?)?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+/// The xsessions desktop entry starting pgwm through `exec`
+fn session_entry(exec: &str) -> String {
+    format!(
+        "[Desktop Entry]
+Name=pgwm
+Comment=A DWM-inspired tiling window manager
+Exec={exec}
+TryExec={exec}
+Type=Application
+DesktopNames=pgwm
+"
+    )
+}
@@ -18,19 +18,32 @@
 
 extern crate alloc;
 
+use core::time::Duration;
+
+use pgwm_core::config::cfg::Cfg;
+use pgwm_core::config::X_RECONNECT_TIMEOUT_MS;
 use pgwm_utils::debug;
 use tiny_std::{eprintln, println};
 
 use crate::error::Error;
-use crate::wm::run_wm;
+use crate::manager::spawn::exec_self;
+use crate::wm::{await_x_server, run_wm};
+
+pub use crate::install::install_session;
 
 pub(crate) mod error;
+mod install;
 mod manager;
+mod signal;
 mod uring;
 pub(crate) mod util;
 mod wm;
 mod x11;
 
+/// Exit status when the connection to the X server is lost and it doesn't come back,
+/// so that whatever started the WM can tell it apart from a crash
+const X_DISCONNECTED_EXIT_CODE: i32 = 2;
+
 #[must_use]
 pub fn main_loop() -> i32 {
     debug!("Starting pgwm");
@@ -43,11 +56,61 @@ pub fn main_loop() -> i32 {
             Err(e) => {
                 if let Error::FullRestart = e {
                     debug!("Restarting WM");
+                    if let Err(e) = exec_self() {
+                        eprintln!("Failed to exec a new WM, restarting in place: {e}");
+                    }
                     continue;
                 }
-                eprintln!("Fatal error {e}");
+                if let Error::XDisconnected = e {
+                    eprintln!("{e}");
+                    if X_RECONNECT_TIMEOUT_MS > 0
+                        && await_x_server(Duration::from_millis(X_RECONNECT_TIMEOUT_MS))
+                    {
+                        println!("The X server is back, reconnecting");
+                        continue;
+                    }
+                    return X_DISCONNECTED_EXIT_CODE;
+                }
+                if let Error::BecomeWm(_) = e {
+                    eprintln!("{e}");
+                } else {
+                    eprintln!("Fatal error {e}");
+                }
                 1
             }
         };
     }
 }
+
+/// Checks the configuration the WM would start with and prints every problem found,
+/// returns the exit code
+#[must_use]
+pub fn check_config() -> i32 {
+    #[cfg(feature = "config-file")]
+    let cfg = match Cfg::read_file() {
+        Ok(Some(cfg)) => {
+            println!("Checking {}", Cfg::file_path().unwrap_or_default());
+            cfg
+        }
+        Ok(None) => {
+            println!("No config file found, checking the compiled in configuration");
+            Cfg::DEFAULT
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+    #[cfg(not(feature = "config-file"))]
+    let cfg = Cfg::DEFAULT;
+    let problems = cfg.validate();
+    if problems.is_empty() {
+        println!("No problems found");
+        return 0;
+    }
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    eprintln!("Found {} problems", problems.len());
+    1
+}
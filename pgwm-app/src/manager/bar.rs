@@ -1,9 +1,10 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use pgwm_core::colors::Color;
 #[cfg(feature = "status-bar")]
 use pgwm_core::config::_STATUS_BAR_CHECK_CONTENT_LIMIT;
-use pgwm_core::config::{
-    SHORTCUT_SECTION, STATUS_BAR_HEIGHT, WORKSPACE_BAR_WINDOW_NAME_PADDING, WORKSPACE_SECTION_FONTS,
-};
+use pgwm_core::config::{IDLE_INHIBITED_ICON, WORKSPACE_BAR_WINDOW_NAME_PADDING};
 use pgwm_core::geometry::Dimensions;
 use pgwm_core::state::State;
 
@@ -25,23 +26,43 @@ impl<'a> BarManager<'a> {
         let mon = &state.monitors[mon_ind];
         let section = &mon.bar_geometry.window_title_section;
         let title_position = section.position;
+        let (bg, text) = if state.title_notice_on(mon_ind).is_some() {
+            (
+                state.colors.workspace_bar_urgent_workspace_background(),
+                state.colors.workspace_bar_workspace_section_text(),
+            )
+        } else {
+            (
+                state.colors.workspace_bar_current_window_title_background(),
+                state.colors.workspace_bar_current_window_title_text(),
+            )
+        };
+        let inhibited_title;
+        let title = if let Some(notice) = state.title_notice_on(mon_ind) {
+            notice
+        } else if state.idle_inhibited {
+            inhibited_title = alloc::format!("{IDLE_INHIBITED_ICON} {}", section.display);
+            inhibited_title.as_str()
+        } else {
+            section.display.as_str()
+        };
         pgwm_utils::debug!("Starting window title draw");
-        let draw_width = self.font_drawer.draw(
+        let draw_width = self.font_drawer.at(mon.scale).draw(
             call_wrapper,
             &mon.bar_win,
-            &section.display,
-            WORKSPACE_SECTION_FONTS,
+            title,
+            state.cfg.fonts.workspace_section,
             Dimensions::new(
                 section.last_draw_width,
-                STATUS_BAR_HEIGHT,
+                mon.bar_height,
                 title_position.start,
                 0,
             ),
             title_position.length,
             WORKSPACE_BAR_WINDOW_NAME_PADDING as i16,
             0,
-            state.colors.workspace_bar_current_window_title_background(),
-            state.colors.workspace_bar_current_window_title_text(),
+            bg,
+            text,
         )?;
         state.monitors[mon_ind]
             .bar_geometry
@@ -50,6 +71,201 @@ impl<'a> BarManager<'a> {
         Ok(())
     }
 
+    /// Draws the window picker over the window title section, covering the entire section
+    pub(crate) fn draw_window_picker(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        if let Some(picker) = &state.window_picker {
+            self.draw_over_title(
+                call_wrapper,
+                mon_ind,
+                &picker.display(),
+                state.colors.workspace_bar_focused_workspace_background(),
+                state.colors.workspace_bar_current_window_title_text(),
+                state,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Shows the keybind help centered on a monitor, sized to fit its lines as far as the monitor allows
+    pub(crate) fn draw_keybind_help(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        lines: &[String],
+        state: &mut State,
+    ) -> Result<()> {
+        let padding = WORKSPACE_BAR_WINDOW_NAME_PADDING as i16;
+        let font_drawer = self.font_drawer.at(state.monitors[mon_ind].scale);
+        let line_height = state.monitors[mon_ind].bar_height;
+        let text_width = lines
+            .iter()
+            .map(|line| {
+                font_drawer
+                    .text_geometry(line, state.cfg.fonts.workspace_section)
+                    .0
+            })
+            .max()
+            .unwrap_or_default();
+        let mon_dimensions = state.monitors[mon_ind].dimensions;
+        let width = (text_width + 2 * padding).min(mon_dimensions.width);
+        let height = (line_height * lines.len() as i16).min(mon_dimensions.height);
+        let help_win = state.keybind_help_win.window.drawable;
+        call_wrapper.resize_window(help_win, height as u32, width as u32, state)?;
+        call_wrapper.move_window(
+            help_win,
+            (mon_dimensions.x + (mon_dimensions.width - width) / 2) as i32,
+            (mon_dimensions.y + (mon_dimensions.height - height) / 2) as i32,
+            state,
+        )?;
+        call_wrapper.send_map(help_win, state)?;
+        for (ind, line) in lines.iter().enumerate() {
+            let y = line_height * ind as i16;
+            if y >= height {
+                break;
+            }
+            font_drawer.draw(
+                call_wrapper,
+                &state.keybind_help_win,
+                line,
+                state.cfg.fonts.workspace_section,
+                Dimensions::new(width, line_height, 0, y),
+                width,
+                padding,
+                0,
+                state.colors.workspace_bar_current_window_title_background(),
+                state.colors.workspace_bar_current_window_title_text(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Shows each window hint's badge centered over its window
+    pub(crate) fn draw_window_hints(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        let Some(hints) = &state.window_hints else {
+            return Ok(());
+        };
+        let badges = hints
+            .labelled()
+            .map(|(label, target)| (label, target.dimensions))
+            .collect::<Vec<_>>();
+        let padding = WORKSPACE_BAR_WINDOW_NAME_PADDING as i16;
+        for (ind, (label, dimensions)) in badges.into_iter().enumerate() {
+            let hint_win = state.window_hint_wins[ind];
+            // Sized for the monitor the window is on
+            let mon = &state.monitors[state
+                .find_monitor_at((dimensions.x, dimensions.y))
+                .unwrap_or(state.focused_mon)];
+            let font_drawer = self.font_drawer.at(mon.scale);
+            let height = mon.bar_height;
+            let text_width = font_drawer
+                .text_geometry(label, state.cfg.fonts.workspace_section)
+                .0;
+            let width = text_width + 2 * padding;
+            call_wrapper.resize_window(
+                hint_win.window.drawable,
+                height as u32,
+                width as u32,
+                state,
+            )?;
+            call_wrapper.move_window(
+                hint_win.window.drawable,
+                (dimensions.x + (dimensions.width - width) / 2) as i32,
+                (dimensions.y + (dimensions.height - height) / 2) as i32,
+                state,
+            )?;
+            call_wrapper.send_map(hint_win.window.drawable, state)?;
+            font_drawer.draw(
+                call_wrapper,
+                &hint_win,
+                label,
+                state.cfg.fonts.workspace_section,
+                Dimensions::new(width, height, 0, 0),
+                width,
+                padding,
+                0,
+                state.colors.workspace_bar_focused_workspace_background(),
+                state.colors.workspace_bar_current_window_title_text(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws the hover text of the hovered shortcut in place of the window title
+    pub(crate) fn draw_shortcut_hover(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        let shortcuts = &state.monitors[mon_ind].bar_geometry.shortcuts;
+        if let Some(text) = shortcuts
+            .hovered
+            .and_then(|ind| shortcuts.components[ind].hover_text)
+        {
+            self.draw_over_title(
+                call_wrapper,
+                mon_ind,
+                text,
+                state.colors.shortcut_background(),
+                state.colors.shortcut_text(),
+                state,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn draw_over_title(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        text: &str,
+        bg: Color,
+        text_color: Color,
+        state: &State,
+    ) -> Result<()> {
+        let mon = &state.monitors[mon_ind];
+        let title_position = mon.bar_geometry.window_title_section.position;
+        self.font_drawer.at(mon.scale).draw(
+            call_wrapper,
+            &mon.bar_win,
+            text,
+            state.cfg.fonts.workspace_section,
+            Dimensions::new(
+                title_position.length,
+                mon.bar_height,
+                title_position.start,
+                0,
+            ),
+            title_position.length,
+            WORKSPACE_BAR_WINDOW_NAME_PADDING as i16,
+            0,
+            bg,
+            text_color,
+        )?;
+        Ok(())
+    }
+
+    /// Draws the window title again after something was drawn over it
+    pub(crate) fn restore_window_title(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        let section = &mut state.monitors[mon_ind].bar_geometry.window_title_section;
+        section.last_draw_width = section.position.length;
+        self.draw_focused_window_title(call_wrapper, mon_ind, state)
+    }
+
     pub(crate) fn set_workspace_focused(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -124,18 +340,30 @@ impl<'a> BarManager<'a> {
         bg_color: Color,
         state: &mut State,
     ) -> Result<()> {
+        // Workspaces of another monitor's set aren't on this monitor's bar
+        let set = state.workspaces.set_of_monitor(mon_ind);
+        let Some(component_ind) = state.workspaces.index_in_set(set, ws_ind) else {
+            return Ok(());
+        };
         let mon = &mut state.monitors[mon_ind];
-        let component = &mon.bar_geometry.workspace.components[ws_ind];
+        if !mon
+            .bar_geometry
+            .workspace
+            .set_drawn_background(component_ind, bg_color.pixel)
+        {
+            return Ok(());
+        }
+        let component = &mon.bar_geometry.workspace.components[component_ind];
         let name = &state.workspaces.get_ws(ws_ind).name;
         pgwm_utils::debug!("Starting workspace draw");
-        self.font_drawer.draw(
+        self.font_drawer.at(mon.scale).draw(
             call_wrapper,
             &mon.bar_win,
             name,
-            WORKSPACE_SECTION_FONTS,
+            state.cfg.fonts.workspace_section,
             Dimensions::new(
                 component.position.length,
-                STATUS_BAR_HEIGHT,
+                mon.bar_height,
                 component.position.start,
                 0,
             ),
@@ -158,7 +386,9 @@ impl<'a> BarManager<'a> {
         let mon = &mut state.monitors[mon_ind];
         let is_mon_focus = state.focused_mon == mon_ind;
         let wants_focus = state.workspaces.get_wants_focus_workspaces();
+        let set = state.workspaces.set_of_monitor(mon_ind);
         pgwm_utils::debug!("Running clean workspace redraw on mon {mon_ind}");
+        let font_drawer = self.font_drawer.at(mon.scale);
         for (ind, ws) in mon.bar_geometry.workspace.components.iter().enumerate() {
             let name = &ws.text;
             let bg = if name.contains(state.workspaces.get_ws(ws_ind).name) {
@@ -169,23 +399,30 @@ impl<'a> BarManager<'a> {
                         .colors
                         .workspace_bar_selected_unfocused_workspace_background()
                 }
-            } else if wants_focus[ind] {
+            } else if state
+                .workspaces
+                .in_set(set, ind)
+                .is_some_and(|set_ws_ind| wants_focus[set_ws_ind])
+            {
                 state.colors.workspace_bar_urgent_workspace_background()
             } else {
                 state.colors.workspace_bar_unfocused_workspace_background()
             };
-            self.font_drawer.draw(
+            font_drawer.draw(
                 call_wrapper,
                 &mon.bar_win,
                 name,
-                WORKSPACE_SECTION_FONTS,
-                Dimensions::new(ws.position.length, STATUS_BAR_HEIGHT, ws.position.start, 0),
+                state.cfg.fonts.workspace_section,
+                Dimensions::new(ws.position.length, mon.bar_height, ws.position.start, 0),
                 ws.position.length,
                 ws.write_offset,
                 0,
                 bg,
                 state.colors.workspace_bar_workspace_section_text(),
             )?;
+            if let Some(drawn) = mon.bar_geometry.workspace.drawn_backgrounds.get_mut(ind) {
+                *drawn = Some(bg.pixel);
+            }
         }
         Ok(())
     }
@@ -202,6 +439,7 @@ impl<'a> BarManager<'a> {
                 state.monitors[mon_ind].hosted_workspace,
                 state,
             )?;
+            self.draw_urgent(call_wrapper, mon_ind, state)?;
             self.draw_shortcuts(call_wrapper, mon_ind, state)?;
         }
         Ok(())
@@ -213,9 +451,10 @@ impl<'a> BarManager<'a> {
         call_wrapper: &mut CallWrapper,
         content: heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>,
         content_ind: usize,
+        alert: bool,
         state: &mut State,
     ) -> Result<()> {
-        self.draw_status(call_wrapper, content, content_ind, state)
+        self.draw_status(call_wrapper, content, content_ind, alert, state)
     }
 
     #[cfg(feature = "status-bar")]
@@ -224,22 +463,32 @@ impl<'a> BarManager<'a> {
         call_wrapper: &mut CallWrapper,
         content: heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>,
         content_ind: usize,
+        alert: bool,
         state: &mut State,
     ) -> Result<()> {
         let bg = state.colors.status_bar_background();
-        let text_col = state.colors.status_bar_text();
+        let text_col = if alert {
+            state.colors.status_bar_alert_text()
+        } else {
+            state.colors.status_bar_text()
+        };
         for mon_ind in 0..state.monitors.len() {
-            let (content, pos) = state.monitors[mon_ind]
+            let Some((content, pos)) = state.monitors[mon_ind]
                 .bar_geometry
                 .status
-                .update_and_get_section_line(content.clone(), content_ind);
-            let src_y = state.monitors[mon_ind].dimensions.y;
-            self.font_drawer.draw(
+                .update_and_get_section_line(content.clone(), content_ind, alert)
+            else {
+                // Unchanged, no need to redraw
+                continue;
+            };
+            let mon = &state.monitors[mon_ind];
+            let src_y = mon.dimensions.y;
+            self.font_drawer.at(mon.scale).draw(
                 call_wrapper,
-                &state.monitors[mon_ind].bar_win,
+                &mon.bar_win,
                 &content,
-                pgwm_core::config::STATUS_SECTION,
-                Dimensions::new(pos.length, STATUS_BAR_HEIGHT, pos.start, src_y),
+                state.cfg.fonts.status_section,
+                Dimensions::new(pos.length, mon.bar_height, pos.start, src_y),
                 pos.length,
                 0,
                 0,
@@ -257,20 +506,25 @@ impl<'a> BarManager<'a> {
         state: &State,
     ) -> Result<()> {
         let bg = state.colors.status_bar_background();
-        let text_col = state.colors.status_bar_text();
 
-        for i in 0..state.monitors.len() {
-            for section in &state.monitors[i].bar_geometry.status.components {
+        for mon in &state.monitors {
+            let font_drawer = self.font_drawer.at(mon.scale);
+            for section in &mon.bar_geometry.status.components {
                 let status_position = section.position;
-                let src_y = state.monitors[i].dimensions.y;
-                self.font_drawer.draw(
+                let text_col = if section.alert {
+                    state.colors.status_bar_alert_text()
+                } else {
+                    state.colors.status_bar_text()
+                };
+                let src_y = mon.dimensions.y;
+                font_drawer.draw(
                     call_wrapper,
-                    &state.monitors[i].bar_win,
+                    &mon.bar_win,
                     &section.display,
-                    pgwm_core::config::STATUS_SECTION,
+                    state.cfg.fonts.status_section,
                     Dimensions::new(
                         status_position.length,
-                        STATUS_BAR_HEIGHT,
+                        mon.bar_height,
                         status_position.start,
                         src_y,
                     ),
@@ -297,14 +551,15 @@ impl<'a> BarManager<'a> {
         let mut offset = pos.start;
         let bg = state.colors.shortcut_background();
         let text = state.colors.shortcut_text();
+        let font_drawer = self.font_drawer.at(mon.scale);
         for shortcut in &mon.bar_geometry.shortcuts.components {
             let name = &shortcut.text;
-            self.font_drawer.draw(
+            font_drawer.draw(
                 call_wrapper,
                 &mon.bar_win,
                 name,
-                SHORTCUT_SECTION,
-                Dimensions::new(shortcut.position.length, STATUS_BAR_HEIGHT, offset, 0),
+                state.cfg.fonts.shortcut_section,
+                Dimensions::new(shortcut.position.length, mon.bar_height, offset, 0),
                 shortcut.position.length,
                 shortcut.write_offset,
                 0,
@@ -316,12 +571,52 @@ impl<'a> BarManager<'a> {
         Ok(())
     }
 
+    /// Draws the workspaces containing urgent windows, blends in with the window title if there are none
+    pub(crate) fn draw_urgent(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        let mon = &state.monitors[mon_ind];
+        let pos = mon.bar_geometry.urgent.position;
+        if pos.length == 0 {
+            return Ok(());
+        }
+        let (bg, text) = if state.urgent_windows.is_empty() {
+            (
+                state.colors.workspace_bar_current_window_title_background(),
+                state.colors.workspace_bar_current_window_title_text(),
+            )
+        } else {
+            (
+                state.colors.workspace_bar_urgent_workspace_background(),
+                state.colors.workspace_bar_workspace_section_text(),
+            )
+        };
+        self.font_drawer.at(mon.scale).draw(
+            call_wrapper,
+            &mon.bar_win,
+            &state.urgent_display(),
+            state.cfg.fonts.workspace_section,
+            Dimensions::new(pos.length, mon.bar_height, pos.start, 0),
+            pos.length,
+            WORKSPACE_BAR_WINDOW_NAME_PADDING as i16 / 2,
+            0,
+            bg,
+            text,
+        )?;
+        Ok(())
+    }
+
     pub(crate) fn redraw_on(
         &self,
         call_wrapper: &mut CallWrapper,
         mon_ind: usize,
         state: &mut State,
     ) -> Result<()> {
+        // The bar was remapped or uncovered, cells drawn before may be gone
+        state.monitors[mon_ind].bar_geometry.workspace.invalidate();
         self.init_workspace(
             call_wrapper,
             mon_ind,
@@ -340,6 +635,7 @@ impl<'a> BarManager<'a> {
             .position
             .length;
         self.draw_focused_window_title(call_wrapper, mon_ind, state)?;
+        self.draw_urgent(call_wrapper, mon_ind, state)?;
         self.draw_shortcuts(call_wrapper, mon_ind, state)?;
         Ok(())
     }
@@ -354,7 +650,12 @@ impl<'a> BarManager<'a> {
             call_wrapper.send_unmap(state.monitors[mon_ind].bar_win.window.drawable, state)?;
             Ok(false)
         } else {
-            call_wrapper.send_map(state.monitors[mon_ind].bar_win.window.drawable, state)?;
+            let bar_win = state.monitors[mon_ind].bar_win.window.drawable;
+            call_wrapper.send_map(bar_win, state)?;
+            if state.monitors[mon_ind].autohide_bar {
+                // Nothing makes room for an autohiding bar, it goes over the windows
+                call_wrapper.push_window_to_top(bar_win, state)?;
+            }
             state.monitors[mon_ind].show_bar = true;
             Ok(true)
         }
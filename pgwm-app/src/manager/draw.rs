@@ -1,14 +1,14 @@
 use xcb_rust_protocol::proto::xproto::Window;
 
 use pgwm_core::config::{
-    PAD_WHILE_TABBED, STATUS_BAR_HEIGHT, TAB_BAR_HEIGHT, TAB_BAR_SECTION, WS_WINDOW_LIMIT,
+    HideStrategy, DUPLICATE_TITLE_TAB_LABEL, HIDE_STRATEGY, SHOW_MARKS_IN_TAB_BAR, WS_WINDOW_LIMIT,
     _WM_NAME_LIMIT,
 };
 use pgwm_core::geometry::draw::{Mode, OldDrawMode};
 use pgwm_core::geometry::{layout::Layout, Dimensions};
-use pgwm_core::push_heapless;
 use pgwm_core::state::workspace::{ArrangeKind, ManagedWindow};
 use pgwm_core::state::State;
+use pgwm_core::{format_heapless, push_heapless};
 
 use crate::error::{Error, Result};
 use crate::manager::font::FontDrawer;
@@ -36,30 +36,51 @@ impl<'a> Drawer<'a> {
         state: &mut State,
     ) -> Result<()> {
         pgwm_utils::debug!("Drawing floating {window} at {dimensions:?}");
-        call_wrapper.configure_window(window, dimensions, state.window_border_width, state)?;
+        let border_width = state.border_width_of_window(window);
+        call_wrapper.configure_window(window, dimensions, border_width, state)?;
         call_wrapper.send_map(window, state)?;
         Ok(())
     }
 
     fn move_floating(
         call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
         window: Window,
         x: i32,
         y: i32,
         state: &mut State,
     ) -> Result<()> {
         pgwm_utils::debug!("Drawing floating {window} at ({x}, {y})");
+        let (x, y) = state.slide_position(mon_ind, window, x, y);
         call_wrapper.move_window(window, x, y, state)?;
         call_wrapper.send_map(window, state)?;
         Ok(())
     }
 
+    /// Configures and draws all windows on a monitor, the requests are batched into a single socket write
     pub(crate) fn draw_on(
         &self,
         call_wrapper: &mut CallWrapper,
         mon_ind: usize,
         map_windows: bool,
         state: &mut State,
+    ) -> Result<()> {
+        if state.monitors[mon_ind].showing_desktop {
+            state.monitors[mon_ind].showing_desktop = false;
+            call_wrapper.set_showing_desktop(state)?;
+        }
+        call_wrapper.begin_batch();
+        let res = self.draw_on_batched(call_wrapper, mon_ind, map_windows, state);
+        call_wrapper.end_batch()?;
+        res
+    }
+
+    fn draw_on_batched(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        map_windows: bool,
+        state: &mut State,
     ) -> Result<()> {
         let ws_ind = state.monitors[mon_ind].hosted_workspace;
         let mut tiled = heapless::Vec::<&ManagedWindow, WS_WINDOW_LIMIT>::new();
@@ -67,16 +88,39 @@ impl<'a> Drawer<'a> {
         for mw in state.workspaces.iter_all_managed_windows_in_ws(ws_ind) {
             if mw.arrange == ArrangeKind::NoFloat {
                 push_heapless!(tiled, mw)?;
-            } else {
+            }
+        }
+        // Floating windows are raised as they're drawn, attached windows have to end up above their parent
+        for window in state.workspaces.stacking_order_in_ws(ws_ind) {
+            if let Some(mw) = state
+                .workspaces
+                .get_managed_win(window)
+                .filter(|mw| mw.arrange != ArrangeKind::NoFloat)
+            {
                 push_heapless!(floating, (mw.window, mw.arrange))?;
             }
         }
         let targets = tiled
             .iter()
-            .map(|win| Drawtarget {
-                window: win.window,
-                map: map_windows,
-                name: win.properties.name.get_cloned(),
+            .map(|win| {
+                // Identical titles can't be told apart, the template usually adds the class
+                let title = win.properties.name.as_str();
+                let label = if tiled.iter().any(|other| {
+                    other.window != win.window && other.properties.name.as_str() == title
+                }) {
+                    win.properties.label(DUPLICATE_TITLE_TAB_LABEL)
+                } else {
+                    win.properties.name.get_cloned()
+                };
+                Drawtarget {
+                    window: win.window,
+                    map: map_windows,
+                    name: match state.workspaces.get_mark(win.window) {
+                        Some(mark) if SHOW_MARKS_IN_TAB_BAR => format_heapless!("[{mark}] {label}"),
+                        _ => label,
+                    },
+                    skip_taskbar: win.properties.net_wm_state.skip_taskbar,
+                }
             })
             .collect();
         drop(tiled);
@@ -86,11 +130,11 @@ impl<'a> Drawer<'a> {
         for (win, arrange) in floating {
             if let ArrangeKind::FloatingInactive(rel_x, rel_y) = arrange {
                 let dimensions = state.monitors[mon_ind].dimensions;
+                let bar_height = state.monitors[mon_ind].bar_height;
                 let x = (dimensions.x as f32 + dimensions.width as f32 * rel_x) as i32;
-                let y = (dimensions.y as f32
-                    + STATUS_BAR_HEIGHT as f32
-                    + dimensions.height as f32 * rel_y) as i32;
-                Self::move_floating(call_wrapper, win, x, y, state)?;
+                let y = (dimensions.y as f32 + bar_height as f32 + dimensions.height as f32 * rel_y)
+                    as i32;
+                Self::move_floating(call_wrapper, mon_ind, win, x, y, state)?;
             }
         }
         Ok(())
@@ -130,12 +174,13 @@ impl<'a> Drawer<'a> {
                         self.draw_tabbed(call_wrapper, mon_ind, targets, target, state)?;
                     }
                 }
-                call_wrapper.configure_window(
-                    window,
-                    state.monitors[mon_ind].dimensions,
-                    0,
-                    state,
-                )?;
+                let dimensions = state.fullscreen_dimensions(mon_ind, window);
+                let slid = Self::slide(mon_ind, window, dimensions, state);
+                call_wrapper.configure_window(window, slid, 0, state)?;
+                if dimensions != state.monitors[mon_ind].dimensions {
+                    // Spans other monitors, has to stay above what's drawn there
+                    call_wrapper.push_window_to_top(window, state)?;
+                }
                 call_wrapper.send_map(window, state)?;
             }
         }
@@ -154,16 +199,13 @@ impl<'a> Drawer<'a> {
         call_wrapper.send_unmap(state.monitors[mon_ind].tab_bar_win.window.drawable, state)?;
         let mon_dimensions = state.monitors[mon_ind].dimensions;
         let tiling_modifiers = &state.workspaces.get_ws(ws_ind).tiling_modifiers;
+        let border_width = state.tiled_border_width_of(ws_ind);
         let dimensions = layout.calculate_dimensions(
             mon_dimensions.width as u32,
             mon_dimensions.height as u32,
-            state.window_padding,
-            state.window_border_width,
-            if state.monitors[mon_ind].show_bar {
-                STATUS_BAR_HEIGHT
-            } else {
-                0
-            },
+            state.tiled_padding_of(ws_ind),
+            border_width,
+            state.bar_reserved_height(mon_ind),
             true,
             targets.len(),
             tiling_modifiers.vertically_tiled.as_slice(),
@@ -184,8 +226,8 @@ impl<'a> Drawer<'a> {
                 y: dim.y + mon_y,
             };
             let win = target.window;
-
-            call_wrapper.configure_window(win, new_dimensions, state.window_border_width, state)?;
+            let new_dimensions = Self::slide(mon_ind, win, new_dimensions, state);
+            Self::configure_tiled(call_wrapper, win, new_dimensions, border_width, state)?;
             if target.map {
                 call_wrapper.send_map(win, state)?;
             }
@@ -204,21 +246,22 @@ impl<'a> Drawer<'a> {
         let dt = &targets[target];
         let win = dt.window;
         let mon = &state.monitors[mon_ind];
-
-        let padding = if PAD_WHILE_TABBED {
-            state.window_padding
+        let border_width = state.tiled_border_width_of(mon.hosted_workspace);
+        let padding = if state.cfg.pad_while_tabbed {
+            state.tiled_padding_of(mon.hosted_workspace)
         } else {
             0
         };
         let x = mon.dimensions.x + padding;
-        let y = mon.dimensions.y + STATUS_BAR_HEIGHT + TAB_BAR_HEIGHT + padding;
+        let bar_height = state.bar_reserved_height(mon_ind);
+        let y = mon.dimensions.y + bar_height + mon.tab_bar_height + padding;
         let new_win_dims = Dimensions {
             height: mon.dimensions.height
-                - STATUS_BAR_HEIGHT
-                - TAB_BAR_HEIGHT
+                - bar_height
+                - mon.tab_bar_height
                 - padding * 2
-                - state.window_border_width as i16 * 2,
-            width: mon.dimensions.width - state.window_border_width as i16 * 2 - padding * 2,
+                - border_width as i16 * 2,
+            width: mon.dimensions.width - border_width as i16 * 2 - padding * 2,
             x,
             y,
         };
@@ -227,21 +270,68 @@ impl<'a> Drawer<'a> {
                 call_wrapper.send_map(dt.window, state)?;
             }
         }
-        call_wrapper.configure_window(win, new_win_dims, state.window_border_width, state)?;
-        let found_names = targets
-            .into_iter()
-            .map(|mw| mw.name)
-            .collect::<heapless::Vec<heapless::String<_WM_NAME_LIMIT>, WS_WINDOW_LIMIT>>();
+        let new_win_dims = Self::slide(mon_ind, win, new_win_dims, state);
+        Self::configure_tiled(call_wrapper, win, new_win_dims, border_width, state)?;
+        // The tab bar is part of the frame
+        call_wrapper.set_extents(win, state.frame_extents(win))?;
+        let mut selected = 0;
+        let mut found_names =
+            heapless::Vec::<heapless::String<_WM_NAME_LIMIT>, WS_WINDOW_LIMIT>::new();
+        for (ind, dt) in targets.into_iter().enumerate() {
+            // Windows skipping the taskbar only get a tab while they're the one shown
+            if ind == target {
+                selected = found_names.len();
+            } else if dt.skip_taskbar {
+                continue;
+            }
+            push_heapless!(found_names, dt.name)?;
+        }
         self.draw_tab_bar(
             call_wrapper,
             mon_ind,
             found_names.as_slice(),
-            target,
+            selected,
             padding,
             state,
         )
     }
 
+    /// Client-side-decorated windows get their invisible shadow placed outside the tile and no border,
+    /// so that what's visible lines up with the other tiles
+    fn configure_tiled(
+        call_wrapper: &mut CallWrapper,
+        window: Window,
+        dimensions: Dimensions,
+        border_width: u32,
+        state: &mut State,
+    ) -> Result<()> {
+        if let Some(csd) = state
+            .workspaces
+            .get_managed_win(window)
+            .and_then(|mw| mw.properties.csd_dimensions(dimensions, border_width))
+        {
+            call_wrapper.configure_window(window, csd, 0, state)
+        } else {
+            call_wrapper.configure_window(window, dimensions, border_width, state)
+        }
+    }
+
+    /// Where to draw a window, off to the side while its workspace slides into place
+    fn slide(
+        mon_ind: usize,
+        window: Window,
+        dimensions: Dimensions,
+        state: &mut State,
+    ) -> Dimensions {
+        let (x, y) =
+            state.slide_position(mon_ind, window, dimensions.x as i32, dimensions.y as i32);
+        Dimensions {
+            x: x as i16,
+            y: y as i16,
+            ..dimensions
+        }
+    }
+
     pub(crate) fn undraw(
         call_wrapper: &mut CallWrapper,
         mon_ind: usize,
@@ -255,11 +345,30 @@ impl<'a> Drawer<'a> {
             // Annoying having to collect after move but whatever
             .collect::<heapless::Vec<Window, WS_WINDOW_LIMIT>>()
         {
-            call_wrapper.send_unmap(win, state)?;
+            Self::hide(call_wrapper, win, state)?;
         }
         Ok(())
     }
 
+    /// Hides a window on a workspace that isn't shown according to [`HIDE_STRATEGY`],
+    /// drawing its workspace shows it again
+    pub(crate) fn hide(
+        call_wrapper: &mut CallWrapper,
+        window: Window,
+        state: &mut State,
+    ) -> Result<()> {
+        match HIDE_STRATEGY {
+            HideStrategy::Unmap => call_wrapper.send_unmap(window, state),
+            HideStrategy::MoveOffscreen => {
+                // Further left than the screen is wide, windows are hardly ever wider than that
+                let x = -2 * i32::from(state.screen.width_in_pixels);
+                call_wrapper.reposition_window(window, x, 0, state)?;
+                // Windows managed straight onto a hidden workspace haven't been mapped yet
+                call_wrapper.send_map(window, state)
+            }
+        }
+    }
+
     fn draw_tab_bar(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -270,6 +379,8 @@ impl<'a> Drawer<'a> {
         state: &mut State,
     ) -> Result<()> {
         let dimensions = state.monitors[mon_ind].dimensions;
+        let tab_bar_height = state.monitors[mon_ind].tab_bar_height;
+        let font_manager = self.font_manager.at(state.monitors[mon_ind].scale);
         let split = (dimensions.width - 2 * padding) as usize / ws_names.len();
         let mut rounding_err =
             dimensions.width as usize - 2 * padding as usize - ws_names.len() * split;
@@ -278,9 +389,9 @@ impl<'a> Drawer<'a> {
             win,
             Dimensions::new(
                 dimensions.width - 2 * padding,
-                TAB_BAR_HEIGHT,
+                tab_bar_height,
                 dimensions.x + padding,
-                STATUS_BAR_HEIGHT + padding + dimensions.y,
+                state.bar_reserved_height(mon_ind) + padding + dimensions.y,
             ),
             0,
             state,
@@ -299,17 +410,17 @@ impl<'a> Drawer<'a> {
             } else {
                 state.colors.tab_bar_unfocused_tab_background()
             };
-            let text_dimensions = self.font_manager.text_geometry(name, TAB_BAR_SECTION);
+            let text_dimensions = font_manager.text_geometry(name, state.cfg.fonts.tab_bar_section);
             let text_width = text_dimensions.0;
             let draw_name = if split_width >= text_width { name } else { "" };
             let center_offset = (split_width - text_width) / 2;
 
-            self.font_manager.draw(
+            font_manager.draw(
                 call_wrapper,
                 dbw,
                 draw_name,
-                TAB_BAR_SECTION,
-                Dimensions::new(split_width, TAB_BAR_HEIGHT, split_width * i as i16, 0),
+                state.cfg.fonts.tab_bar_section,
+                Dimensions::new(split_width, tab_bar_height, split_width * i as i16, 0),
                 split_width,
                 center_offset,
                 0,
@@ -330,4 +441,5 @@ struct Drawtarget {
     window: Window,
     map: bool,
     name: heapless::String<_WM_NAME_LIMIT>,
+    skip_taskbar: bool,
 }
@@ -9,24 +9,43 @@ use tiny_std::io::Read;
 use xcb_rust_protocol::proto::render::{Glyphinfo, Glyphset};
 
 use pgwm_core::colors::Color;
-use pgwm_core::config::{
-    FontCfg, CHAR_REMAP, CHAR_REMAP_FONTS, SHORTCUT_SECTION, TAB_BAR_SECTION,
-    WINDOW_NAME_DISPLAY_SECTION, WORKSPACE_SECTION_FONTS,
-};
+use pgwm_core::config::cfg::Cfg;
+use pgwm_core::config::{FontCfg, CHAR_REMAP, CHAR_REMAP_FONTS};
 use pgwm_core::geometry::Dimensions;
 use pgwm_core::render::{DoubleBufferedRenderPicture, RenderVisualInfo};
 
 use crate::error::{Error, Result};
 use crate::x11::call_wrapper::CallWrapper;
 
+#[derive(Copy, Clone)]
 pub(crate) struct FontDrawer<'a> {
+    /// Fonts loaded at each monitor's scale
+    scaled_render_fonts: &'a [LoadedFonts<'a>],
     loaded_render_fonts: &'a LoadedFonts<'a>,
 }
 
 impl<'a> FontDrawer<'a> {
-    pub(crate) fn new(loaded_xrender_fonts: &'a LoadedFonts<'a>) -> Self {
+    /// Draws with the fonts loaded at the first scale until told otherwise through [`FontDrawer::at`]
+    pub(crate) fn new(scaled_xrender_fonts: &'a [LoadedFonts<'a>]) -> Self {
         Self {
-            loaded_render_fonts: loaded_xrender_fonts,
+            scaled_render_fonts: scaled_xrender_fonts,
+            loaded_render_fonts: &scaled_xrender_fonts[0],
+        }
+    }
+
+    /// A drawer using the fonts loaded for `scale`.
+    /// Fonts are loaded for the monitors present at startup, monitors with a scale
+    /// that wasn't loaded get the closest one.
+    pub(crate) fn at(&self, scale: f32) -> Self {
+        let key = scale_key(scale);
+        let loaded = self
+            .scaled_render_fonts
+            .iter()
+            .min_by_key(|fonts| scale_key(fonts.scale).abs_diff(key))
+            .unwrap_or(self.loaded_render_fonts);
+        Self {
+            scaled_render_fonts: self.scaled_render_fonts,
+            loaded_render_fonts: loaded,
         }
     }
 
@@ -80,16 +99,20 @@ impl<'a> FontDrawer<'a> {
 pub(crate) fn load_alloc_fonts<'a>(
     call_wrapper: &mut CallWrapper,
     vis_info: &RenderVisualInfo,
+    scale: f32,
+    cfg: &Cfg,
 ) -> Result<HashMap<&'a FontCfg<'a>, LoadedFont, FontHasherBuilder>> {
     let mut map = HashMap::with_hasher(FontHasherBuilder);
-    let it = WORKSPACE_SECTION_FONTS
+    let it = cfg
+        .fonts
+        .workspace_section
         .iter()
-        .chain(WINDOW_NAME_DISPLAY_SECTION.iter())
-        .chain(SHORTCUT_SECTION.iter())
-        .chain(TAB_BAR_SECTION.iter())
+        .chain(cfg.fonts.window_name_display_section.iter())
+        .chain(cfg.fonts.shortcut_section.iter())
+        .chain(cfg.fonts.tab_bar_section.iter())
         .chain(CHAR_REMAP_FONTS);
     #[cfg(feature = "status-bar")]
-    let it = it.chain(pgwm_core::config::STATUS_SECTION.iter());
+    let it = it.chain(cfg.fonts.status_section.iter());
     // Reuse buffer
     let mut data = Vec::with_capacity(65536);
     for f_cfg in it {
@@ -108,7 +131,7 @@ pub(crate) fn load_alloc_fonts<'a>(
             let mut infos = vec![];
             let mut raw_data = vec![];
             let mut char_map = HashMap::with_hasher(FontHasherBuilder);
-            let size = f_cfg.size.parse::<f32>().map_err(|_| Error::ParseFloat)?;
+            let size = f_cfg.size.parse::<f32>().map_err(|_| Error::ParseFloat)? * scale;
             let raster_iter =
                 fontdue::RasterIterator::new(&data[..read_bytes], size, FontSettings::default())
                     .map_err(|_e| {
@@ -199,8 +222,15 @@ fn calculate_font_size(map_len: usize) -> usize {
         + (core::mem::size_of::<char>() + core::mem::size_of::<CharInfo>()) * map_len
 }
 
+#[inline]
+fn scale_key(scale: f32) -> u32 {
+    (scale * 100.0 + 0.5) as u32
+}
+
 pub struct LoadedFonts<'a> {
     pub(crate) fonts: HashMap<&'a FontCfg<'a>, LoadedFont, FontHasherBuilder>,
+    /// What the font sizes were scaled by when loading
+    pub(crate) scale: f32,
     // Simple key, use smallmap
     chars: Map<char, LoadedChar>,
 }
@@ -214,6 +244,7 @@ struct LoadedChar {
 impl<'a> LoadedFonts<'a> {
     pub(crate) fn new(
         fonts: HashMap<&'a FontCfg<'a>, LoadedFont, FontHasherBuilder>,
+        scale: f32,
     ) -> Result<Self> {
         let mut chars = Map::new();
         for (char, font) in CHAR_REMAP {
@@ -235,7 +266,11 @@ impl<'a> LoadedFonts<'a> {
                 None => return Err(Error::FontLoad("Font not loaded when expected")),
             }
         }
-        Ok(Self { fonts, chars })
+        Ok(Self {
+            fonts,
+            scale,
+            chars,
+        })
     }
 
     #[must_use]
@@ -1,43 +1,75 @@
 use alloc::vec::Vec;
+#[cfg(not(feature = "perf-test"))]
+use rusl::string::unix_str::UnixStr;
 use xcb_rust_protocol::cookie::FixedCookie;
 use xcb_rust_protocol::helpers::properties::WmHints;
 use xcb_rust_protocol::proto::xproto::{
-    ButtonPressEvent, ButtonReleaseEvent, ConfigureNotifyEvent, ConfigureRequestEvent,
-    DestroyNotifyEvent, EnterNotifyEvent, GetWindowAttributesReply, KeyPressEvent, MapRequestEvent,
-    MapStateEnum, MotionNotifyEvent, NotifyModeEnum, PropertyNotifyEvent, QueryPointerReply,
-    UnmapNotifyEvent, VisibilityEnum, VisibilityNotifyEvent, Window,
+    AtomEnum, ButtonIndexEnum, ButtonPressEvent, ButtonReleaseEvent, ColormapNotifyEvent,
+    ConfigureNotifyEvent, ConfigureRequestEvent, DestroyNotifyEvent, EnterNotifyEvent,
+    GetWindowAttributesReply, KeyPressEvent, KeyReleaseEvent, LeaveNotifyEvent, MapNotifyEvent,
+    MapRequestEvent, MapStateEnum, MappingEnum, MappingNotifyEvent, ModMask, MotionNotifyEvent,
+    NotifyModeEnum, PropertyNotifyEvent, QueryPointerReply, UnmapNotifyEvent, VisibilityEnum,
+    VisibilityNotifyEvent, Window,
 };
 use xcb_rust_protocol::util::AsIter32;
 
+use pgwm_core::colors::{Color, Rgba8};
+use pgwm_core::config::key_map::keybind_help_lines;
 use pgwm_core::config::mouse_map::MouseTarget;
+#[cfg(not(feature = "perf-test"))]
+use pgwm_core::config::_SPAWN_CWD_PROCESS_DEPTH;
 #[cfg(feature = "status-bar")]
 use pgwm_core::config::_STATUS_BAR_CHECK_CONTENT_LIMIT;
 use pgwm_core::config::{
-    Action, CLIENT_WINDOW_DESTROY_AFTER, CLIENT_WINDOW_KILL_AFTER, WS_WINDOW_LIMIT,
+    Action, BORDER_TRANSITION_FRAMES, BORDER_TRANSITION_FRAME_MS, CLIENT_WINDOW_DESTROY_AFTER,
+    CLIENT_WINDOW_KILL_AFTER, DYING_WINDOW_CACHE, EDGE_ACTIONS, FLOATING_SNAP_THRESHOLD,
+    FULLSCREEN_SCREENSAVER_RESET_SECS, ON_LAST_WINDOW_CLOSED, SHOW_MARKS_IN_TAB_BAR,
+    TITLE_NOTICE_MS, TRANSIENTS_FOLLOW_PARENT, XRESOURCE_COLORS, _IPC_REPLY_LIMIT,
     _WM_CLASS_NAME_LIMIT, _WM_NAME_LIMIT,
 };
+#[cfg(feature = "animations")]
+use pgwm_core::config::{
+    ANIMATE_WORKSPACE_SWITCH, WORKSPACE_SWITCH_FRAMES, WORKSPACE_SWITCH_FRAME_MS,
+    _WORKSPACE_SWITCH_SLIDE_DIVISOR,
+};
+#[cfg(feature = "idle")]
+use pgwm_core::config::{IDLE_ACTION, IDLE_AFTER_MINUTES};
 use pgwm_core::geometry::draw::Mode;
-use pgwm_core::geometry::layout::Layout;
 use pgwm_core::geometry::Dimensions;
+use pgwm_core::ipc::{IpcCommand, IpcPlacement, IpcTarget};
 use pgwm_core::push_heapless;
-use pgwm_core::state::properties::{Protocol, WindowProperties, WindowType, WmName, WmState};
+use pgwm_core::state::hints::{HintEvent, HintTarget, WindowHints};
+use pgwm_core::state::picker::{PickerEvent, WindowPicker};
+use pgwm_core::state::properties::{
+    NetWmState, Protocol, WindowProperties, WindowType, WmName, WmState,
+};
+#[cfg(feature = "animations")]
+use pgwm_core::state::slide::WorkspaceSlide;
+use pgwm_core::state::snapshot::StateSnapshot;
 use pgwm_core::state::workspace::{
-    ArrangeKind, DeleteResult, FocusStyle, ManagedWindow, Workspaces,
+    ArrangeKind, DeleteResult, FloatingGeometry, FocusStyle, ManagedWindow, Workspaces,
 };
 use pgwm_core::state::{DragPosition, State, WinMarkedForDeath};
+#[cfg(not(feature = "perf-test"))]
+use pgwm_core::util::proc::youngest_child;
 
 use crate::dbg_win;
 use crate::error::{Error, Result};
 use crate::manager::bar::BarManager;
 use crate::manager::draw::Drawer;
+use crate::wm::load_resource_db;
 use crate::x11::call_wrapper::{
-    CallWrapper, DimensionsCookie, SingleCardCookie, SupportedAtom, WindowFloatDeduction,
+    CallWrapper, DimensionsCookie, SupportedAtom, TransientForCookie, WindowFloatDeduction,
     WindowPropertiesCookie, WmStateCookie,
 };
+use crate::x11::colors::{alloc_colors, with_resource_colors};
+use crate::x11::manager_calls::ManagerCalls;
 
 pub(crate) mod bar;
 pub(crate) mod draw;
 pub(crate) mod font;
+#[cfg(not(feature = "perf-test"))]
+pub(crate) mod spawn;
 
 pub(crate) struct Manager<'a> {
     drawer: Drawer<'a>,
@@ -62,6 +94,9 @@ impl<'a> Manager<'a> {
         let ch_wa = call_wrapper.set_root_event_mask(&self.cursor_handle, state)?;
         ch_wa.check(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
         pgwm_utils::debug!("Set root event mask");
+        for mon_ind in 0..state.monitors.len() {
+            Self::paint_workspace_background(call_wrapper, mon_ind, state)?;
+        }
         self.bar_manager.draw_static(call_wrapper, state)?;
         pgwm_utils::debug!("Drew workspace sections");
         call_wrapper.set_default_manager_props(state)?;
@@ -109,7 +144,10 @@ impl<'a> Manager<'a> {
                     && (attr.map_state == MapStateEnum::VIEWABLE || wm_state.is_some())
                     && !state.intern_created_windows.contains_key(&window)
                 {
-                    if transient_cookie.await_card(call_wrapper)?.is_some() {
+                    if transient_cookie
+                        .await_transient_for(call_wrapper)?
+                        .is_some()
+                    {
                         transients.push((window, prop_cookie));
                     } else {
                         non_transients.push((window, prop_cookie));
@@ -141,9 +179,73 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         for mon_ind in 0..state.monitors.len() {
+            Self::paint_workspace_background(call_wrapper, mon_ind, state)?;
             self.drawer.draw_on(call_wrapper, mon_ind, true, state)?;
         }
         self.bar_manager.draw_static(call_wrapper, state)?;
+        // Monitors may have changed
+        call_wrapper.set_desktop_properties(state)?;
+        Ok(())
+    }
+
+    /// Paint the background image or color of the workspace hosted on a monitor onto the root window
+    /// behind it, workspaces without either are painted black
+    fn paint_workspace_background(
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &State,
+    ) -> Result<()> {
+        let Some(root_background) = &state.root_background else {
+            return Ok(());
+        };
+        let mon = &state.monitors[mon_ind];
+        if let Some(image) = state.background_image_of(mon.hosted_workspace) {
+            let covering = image.cover(mon.dimensions.width as u16, mon.dimensions.height as u16);
+            call_wrapper.put_image(
+                root_background.drawable,
+                state.screen.root_depth,
+                &covering,
+                mon.dimensions.x,
+                mon.dimensions.y,
+            )?;
+        } else {
+            let (red, green, blue, _) = state
+                .workspaces
+                .get_ws(mon.hosted_workspace)
+                .background
+                .unwrap_or((0, 0, 0, 0))
+                .to_rgba16();
+            call_wrapper.fill_xrender_rectangle(
+                root_background.picture,
+                xcb_rust_protocol::proto::render::Color {
+                    red,
+                    green,
+                    blue,
+                    alpha: 0xffff,
+                },
+                mon.dimensions,
+            )?;
+        }
+        call_wrapper.set_root_background(
+            state.screen.root,
+            root_background.drawable,
+            mon.dimensions,
+        )
+    }
+
+    /// Remembers the release so that an auto-repeated press following it can be told apart,
+    /// and closes the window hints if a modifier was released
+    pub(crate) fn handle_key_release(
+        call_wrapper: &mut CallWrapper,
+        event: KeyReleaseEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        state.last_key_release = Some((event.detail, event.time));
+        if let Some(hints) = &state.window_hints {
+            if hints.handle_key_release(event.detail) == HintEvent::Close {
+                Self::close_window_hints(call_wrapper, state)?;
+            }
+        }
         Ok(())
     }
 
@@ -154,7 +256,44 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         state.last_timestamp = event.time;
-        if let Some(action) = state.get_key_action(event.detail, event.state.0) {
+        if state.keybind_help_shown {
+            // The keyboard is grabbed while the help is shown, any key closes it
+            return Self::close_keybind_help(call_wrapper, state);
+        }
+        if let Some(hints) = &state.window_hints {
+            // The keyboard is grabbed while hints are shown, all keys go to the hints.
+            // The key that showed them repeats if it's held, that shouldn't close them.
+            if state.is_key_repeat(event.detail, event.time) {
+                return Ok(());
+            }
+            match hints.handle_key_press(event.detail) {
+                HintEvent::Ignore => {}
+                HintEvent::Close => Self::close_window_hints(call_wrapper, state)?,
+                HintEvent::Select(window) => {
+                    self.focus_or_show_window(call_wrapper, window, state)?;
+                }
+            }
+            return Ok(());
+        }
+        if let Some(picker) = state.window_picker.as_mut() {
+            // The keyboard is grabbed while the picker is open, all keys go to the picker
+            let shifted = event.state.0 & ModMask::SHIFT.0 != 0;
+            match picker.handle_key(event.detail, shifted) {
+                PickerEvent::Ignore => {}
+                PickerEvent::Redraw => {
+                    let mon_ind = picker.mon_ind;
+                    self.bar_manager
+                        .draw_window_picker(call_wrapper, mon_ind, state)?;
+                }
+                PickerEvent::Cancel => self.close_window_picker(call_wrapper, state)?,
+                PickerEvent::Select(window) => {
+                    self.close_window_picker(call_wrapper, state)?;
+                    self.focus_or_show_window(call_wrapper, window, state)?;
+                }
+            }
+            return Ok(());
+        }
+        if let Some(action) = state.get_key_action(event.detail, event.state.0, event.time) {
             self.exec_action(
                 call_wrapper,
                 event.event,
@@ -186,24 +325,61 @@ impl<'a> Manager<'a> {
                 return Err(Error::GracefulShutdown);
             }
             #[cfg_attr(feature = "perf-test", allow(unused_variables))]
-            Action::Spawn(cmd, args) => {
+            Action::Spawn(cmd, args) | Action::SpawnInFocusedCwd(cmd, args) => {
                 pgwm_utils::debug!("Spawning {:?} with args {:?}", cmd, args);
                 #[cfg(not(feature = "perf-test"))]
                 {
-                    tiny_std::process::Command::new(cmd)?
-                        .args(args.iter().copied())
-                        .stdin(tiny_std::process::Stdio::Null)
-                        .stdout(tiny_std::process::Stdio::Null)
-                        .stderr(tiny_std::process::Stdio::Null)
-                        .spawn()?;
+                    let cwd = if matches!(action, Action::SpawnInFocusedCwd(..)) {
+                        Self::focused_process_cwd(state)
+                    } else {
+                        None
+                    };
+                    let spawned = cwd
+                        .as_deref()
+                        .map(UnixStr::try_from_str)
+                        .transpose()
+                        .map_err(Error::from)
+                        .and_then(|cwd| spawn::spawn(cmd, args, cwd));
+                    if let Err(e) = spawned {
+                        let name = cmd.as_str().unwrap_or_default().trim_end_matches('\0');
+                        tiny_std::eprintln!("Failed to spawn {name}: {e}");
+                        let notice = alloc::format!("Failed to spawn {name}: {e}");
+                        let shown_for = core::time::Duration::from_millis(TITLE_NOTICE_MS);
+                        self.show_title_notice(call_wrapper, &notice, shown_for, state)?;
+                    }
                 }
             }
             Action::Close => {
                 let win = focus_fallback_origin(origin, state);
                 self.unmanage_and_kill(call_wrapper, win, state)?;
             }
+            Action::CloseGroup => {
+                let win = focus_fallback_origin(origin, state);
+                // Only as many as can be marked for death, the rest are left for the next time
+                let room = DYING_WINDOW_CACHE - state.dying_windows.len();
+                for member in state.workspaces.group_members(win).into_iter().take(room) {
+                    self.unmanage_and_kill(call_wrapper, member, state)?;
+                }
+            }
             Action::ToggleWorkspace(num) => {
-                self.toggle_workspace(call_wrapper, num, state.focused_mon, state)?;
+                let mon_ind = state.focused_mon;
+                let set = state.workspaces.set_of_monitor(mon_ind);
+                if let Some(ws_ind) = state.workspaces.in_set(set, num) {
+                    self.toggle_workspace(call_wrapper, ws_ind, mon_ind, state)?;
+                }
+            }
+            Action::ToggleLastWorkspace => {
+                let mon_ind = state.focused_mon;
+                if let Some(prev) = state.monitors[mon_ind].previous_workspace() {
+                    self.toggle_workspace(call_wrapper, prev, mon_ind, state)?;
+                }
+            }
+            Action::NextWorkspace | Action::PrevWorkspace => {
+                let mon_ind = state.focused_mon;
+                let forward = matches!(action, Action::NextWorkspace);
+                if let Some(ws_ind) = state.cycle_workspace(mon_ind, forward) {
+                    self.toggle_workspace(call_wrapper, ws_ind, mon_ind, state)?;
+                }
             }
             Action::NextTilingMode => {
                 let window = focus_fallback_origin(origin, state);
@@ -219,23 +395,30 @@ impl<'a> Manager<'a> {
                 let window = focus_fallback_origin(origin, state);
                 if let Some(ws_ind) = state.workspaces.find_ws_containing_window(window) {
                     if let Some(mon_ind) = state.find_monitor_hosting_workspace(ws_ind) {
-                        match state.workspaces.get_draw_mode(ws_ind) {
-                            Mode::Tiled(_) => {
-                                toggle_tabbed(mon_ind, ws_ind, state)?;
-                            }
-                            Mode::Tabbed(_) => {
-                                state
-                                    .workspaces
-                                    .set_draw_mode(ws_ind, Mode::Tiled(Layout::LeftLeader));
-                            }
-                            Mode::Fullscreen { last_draw_mode, .. } => {
-                                state
-                                    .workspaces
-                                    .set_draw_mode(ws_ind, last_draw_mode.to_draw_mode());
-                            }
+                        if let Mode::Fullscreen { last_draw_mode, .. } =
+                            state.workspaces.get_draw_mode(ws_ind)
+                        {
+                            state
+                                .workspaces
+                                .set_draw_mode(ws_ind, last_draw_mode.to_draw_mode());
+                        } else {
+                            state.workspaces.toggle_tabbed(ws_ind);
                         }
                         self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
-                        self.focus_mon(call_wrapper, mon_ind, state)?;
+                        // Focus would otherwise switch the shown tab to the focused window
+                        let shown_tab = match state.workspaces.get_draw_mode(ws_ind) {
+                            Mode::Tabbed(n) => state
+                                .workspaces
+                                .get_all_tiled_windows(ws_ind)
+                                .get(n)
+                                .map(|mw| mw.window),
+                            _ => None,
+                        };
+                        if let Some(window) = shown_tab {
+                            self.focus_window(call_wrapper, mon_ind, window, state)?;
+                        } else {
+                            self.focus_mon(call_wrapper, mon_ind, state)?;
+                        }
                     }
                 }
             }
@@ -262,13 +445,11 @@ impl<'a> Manager<'a> {
                     state.window_border_width = actual_width;
                 }
                 if updated {
+                    // Windows that aren't drawn need their extents updated too
+                    call_wrapper.bulk_set_extents(state)?;
                     for mon_ind in 0..state.monitors.len() {
                         self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
                     }
-                    call_wrapper.bulk_set_extents(
-                        state.workspaces.get_all_managed_windows().as_slice(),
-                        state.window_border_width,
-                    )?;
                 }
             }
             Action::ResetToDefaultSizeModifiers => {
@@ -293,41 +474,103 @@ impl<'a> Manager<'a> {
             }
             Action::SendToWorkspace(num) => {
                 let target_window = focus_fallback_origin(origin, state);
-                if let Some(ws) = state.workspaces.find_ws_containing_window(target_window) {
-                    if ws == num {
-                        pgwm_utils::debug!("Tried to send to same workspace {}", num);
-                    } else {
-                        let properties = if let Some(removed_mw) = self
-                            .remove_win_from_state_then_redraw_if_tiled(
-                                call_wrapper,
-                                target_window,
-                                state,
-                            )?
-                            .into_option()
-                        {
-                            call_wrapper.send_unmap(target_window, state)?;
-                            removed_mw.properties
-                        } else {
-                            call_wrapper
-                                .get_window_properties(target_window)?
-                                .await_properties(call_wrapper)?
-                        };
-                        state.workspaces.add_child_to_ws(
-                            target_window,
-                            num,
-                            ArrangeKind::NoFloat,
-                            Self::deduce_focus_style(&properties),
-                            &properties,
-                        )?;
-                        if let Some(target) = state.find_monitor_hosting_workspace(num) {
-                            self.drawer.draw_on(call_wrapper, target, true, state)?;
+                let set = state.workspaces.set_of_monitor(state.focused_mon);
+                if let Some(ws_ind) = state.workspaces.in_set(set, num) {
+                    self.send_to_workspace(call_wrapper, target_window, ws_ind, state)?;
+                }
+            }
+            Action::MarkWindow(mark) => {
+                let window = focus_fallback_origin(origin, state);
+                let _marked = state.workspaces.toggle_mark(window, mark);
+                pgwm_utils::debug!("Toggled mark {mark} on {window}, marked = {_marked}");
+                if SHOW_MARKS_IN_TAB_BAR {
+                    if let Some((mon_ind, ws_ind)) =
+                        state.find_monitor_and_ws_indices_of_window(window)
+                    {
+                        if matches!(state.workspaces.get_draw_mode(ws_ind), Mode::Tabbed(_)) {
+                            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
                         }
                     }
                 }
             }
+            Action::FocusMark(mark) => {
+                if let Some(window) = state.workspaces.find_marked(mark) {
+                    self.focus_or_show_window(call_wrapper, window, state)?;
+                }
+            }
+            Action::SendMarkedToWorkspace(num) => {
+                let set = state.workspaces.set_of_monitor(state.focused_mon);
+                if let Some(ws_ind) = state.workspaces.in_set(set, num) {
+                    for window in state.workspaces.take_marked() {
+                        self.send_to_workspace(call_wrapper, window, ws_ind, state)?;
+                    }
+                }
+            }
+            Action::WindowPicker => {
+                if state.window_picker.is_some() {
+                    self.close_window_picker(call_wrapper, state)?;
+                } else {
+                    let keysyms = call_wrapper.get_keysym_table()?;
+                    let mon_ind = state.focused_mon;
+                    state.window_picker = Some(WindowPicker::from_workspaces(
+                        &state.workspaces,
+                        mon_ind,
+                        keysyms,
+                    ));
+                    call_wrapper.grab_keyboard(state)?;
+                    self.bar_manager
+                        .draw_window_picker(call_wrapper, mon_ind, state)?;
+                }
+            }
+            Action::WindowHints => {
+                if state.window_hints.is_none() {
+                    let targets = Self::window_hint_targets(call_wrapper, state)?;
+                    if !targets.is_empty() {
+                        let keysyms = call_wrapper.get_keysym_table()?;
+                        state.window_hints = Some(WindowHints::new(targets, keysyms));
+                        call_wrapper.grab_keyboard(state)?;
+                        self.bar_manager.draw_window_hints(call_wrapper, state)?;
+                    }
+                }
+            }
+            Action::ShowKeybindHelp => {
+                if state.keybind_help_shown {
+                    Self::close_keybind_help(call_wrapper, state)?;
+                } else {
+                    // Generated from what's actually grabbed so that it always matches the config
+                    let keysyms = call_wrapper.get_keysym_table()?;
+                    let mut bindings = state
+                        .key_mapping
+                        .keys()
+                        .filter_map(|key| {
+                            let keysym =
+                                keysyms.keysym(key.code, key.mods & ModMask::SHIFT.0 != 0)?;
+                            let mapping = state.key_mapping.get(key)?;
+                            Some((key.mods, keysym, mapping.action))
+                        })
+                        .collect::<Vec<_>>();
+                    let lines = keybind_help_lines(&mut bindings);
+                    call_wrapper.grab_keyboard(state)?;
+                    state.keybind_help_shown = true;
+                    let mon_ind = state.focused_mon;
+                    self.bar_manager
+                        .draw_keybind_help(call_wrapper, mon_ind, &lines, state)?;
+                }
+            }
+            Action::FocusUrgent => {
+                if let Some(window) = state.urgent_windows.first().copied() {
+                    self.focus_or_show_window(call_wrapper, window, state)?;
+                }
+            }
             Action::UnFloat => {
                 if let Some(input_focus) = state.input_focus {
                     if let Some(mon_ind) = state.find_monitor_index_of_window(input_focus) {
+                        Self::remember_floating_geometry(
+                            call_wrapper,
+                            input_focus,
+                            mon_ind,
+                            state,
+                        )?;
                         if state.workspaces.un_float_window(input_focus).is_some() {
                             pgwm_utils::debug!("Unfloating on mon {:?}", mon_ind);
                             self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
@@ -351,6 +594,14 @@ impl<'a> Manager<'a> {
                     }
                 }
             }
+            Action::FocusNextInGroup => {
+                if let Some(next) = state
+                    .input_focus
+                    .and_then(|cur| state.workspaces.next_in_group(cur))
+                {
+                    self.focus_or_show_window(call_wrapper, next, state)?;
+                }
+            }
             Action::FocusNextMonitor => {
                 let len = state.monitors.len();
                 let next = (state.focused_mon + 1) % len;
@@ -361,7 +612,17 @@ impl<'a> Manager<'a> {
                 let next = (state.focused_mon as i8 - 1).rem_euclid(len as i8) as usize;
                 self.focus_mon(call_wrapper, next, state)?;
             }
-            Action::ToggleBar => {
+            Action::MoveWorkspaceToMonitor(direction) => {
+                let mon_ind = state.focused_mon;
+                let target = state.monitor_in_direction(mon_ind, direction);
+                if target != mon_ind {
+                    // Swaps the workspaces and focuses the receiving monitor
+                    let ws_ind = state.monitors[mon_ind].hosted_workspace;
+                    self.toggle_workspace(call_wrapper, ws_ind, target, state)?;
+                }
+            }
+            Action::RevealBar if state.monitors[state.focused_mon].show_bar => {}
+            Action::ToggleBar | Action::RevealBar => {
                 let mon_ind = state.focused_mon;
                 if BarManager::toggle_bar(call_wrapper, mon_ind, state)? {
                     self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
@@ -369,6 +630,55 @@ impl<'a> Manager<'a> {
                 } else {
                     self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
                 }
+                call_wrapper.set_desktop_properties(state)?;
+            }
+            Action::ToggleAutohideBar => {
+                let mon_ind = state.focused_mon;
+                let mon = &mut state.monitors[mon_ind];
+                mon.autohide_bar = !mon.autohide_bar;
+                if mon.autohide_bar {
+                    if mon.last_focus.is_some() {
+                        Self::hide_autohide_bar(call_wrapper, mon_ind, state)?;
+                    } else if mon.show_bar {
+                        let bar_win = mon.bar_win.window.drawable;
+                        call_wrapper.push_window_to_top(bar_win, state)?;
+                    }
+                } else if !mon.show_bar {
+                    BarManager::toggle_bar(call_wrapper, mon_ind, state)?;
+                }
+                self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+                if state.monitors[mon_ind].show_bar {
+                    self.bar_manager.redraw_on(call_wrapper, mon_ind, state)?;
+                }
+                call_wrapper.set_desktop_properties(state)?;
+            }
+            Action::NextKeyboardLayout => {
+                #[cfg(feature = "xkb")]
+                {
+                    let (group, layouts) = call_wrapper.get_keyboard_layouts(state.screen.root)?;
+                    // XKB has at most 4 groups, if the layouts weren't set by setxkbmap
+                    // the server wraps groups that the keymap doesn't have
+                    let num_groups = if layouts.is_empty() {
+                        4
+                    } else {
+                        layouts.len().min(4)
+                    };
+                    let next = (usize::from(group) + 1) % num_groups;
+                    pgwm_utils::debug!("Switching keyboard group {group} -> {next}");
+                    call_wrapper.lock_keyboard_group(next as u8)?;
+                }
+            }
+            #[cfg(feature = "idle")]
+            Action::InhibitIdle => {
+                state.idle_inhibited = !state.idle_inhibited;
+                for mon_ind in 0..state.monitors.len() {
+                    self.restore_uncovered_window_title(call_wrapper, mon_ind, state)?;
+                }
+            }
+            Action::ToggleShowDesktop => {
+                let mon_ind = state.focused_mon;
+                let show = !state.monitors[mon_ind].showing_desktop;
+                self.show_desktop(call_wrapper, mon_ind, show, state)?;
             }
             Action::ToggleFullscreen => {
                 let window = focus_fallback_origin(origin, state);
@@ -384,6 +694,34 @@ impl<'a> Manager<'a> {
                     }
                 }
             }
+            Action::TogglePinToMonitor => {
+                let window = state
+                    .workspaces
+                    .find_transient_root(focus_fallback_origin(origin, state));
+                if let Some((mon_ind, _)) = state.find_monitor_and_ws_indices_of_window(window) {
+                    if let Some(mw) = state.workspaces.get_managed_win_mut(window) {
+                        mw.pinned_monitor = match mw.pinned_monitor {
+                            Some(_) => None,
+                            None => Some(mon_ind),
+                        };
+                    }
+                }
+            }
+            Action::CycleStatusFormat(check_ind) => {
+                // The status checks are run from the event loop, which picks this up
+                state.status_format_cycle = Some(check_ind);
+            }
+            Action::ToggleFakeFullscreen => {
+                let window = focus_fallback_origin(origin, state);
+                self.toggle_fake_fullscreen(call_wrapper, window, state)?;
+            }
+            Action::ToggleMaximize => {
+                let window = focus_fallback_origin(origin, state);
+                if let Some((mon_ind, ws_ind)) = state.find_monitor_and_ws_indices_of_window(window)
+                {
+                    self.toggle_maximize(call_wrapper, mon_ind, ws_ind, window, state)?;
+                }
+            }
             Action::MoveWindow => {
                 if let InputSource::Mouse(x, y) = source {
                     let dimensions = call_wrapper.get_dimensions(origin)?;
@@ -400,8 +738,21 @@ impl<'a> Manager<'a> {
                             self.drawer.draw_on(call_wrapper, mon, false, state)?;
                         }
                         let dimensions = dimensions.await_dimensions(call_wrapper)?;
-                        state.drag_window =
-                            Some((origin, DragPosition::new(dimensions.x, dimensions.y, x, y)));
+                        let border = state.border_width_of_window(origin) as i16;
+                        let mut drag = DragPosition::new(
+                            Dimensions::new(
+                                dimensions.width + 2 * border,
+                                dimensions.height + 2 * border,
+                                dimensions.x,
+                                dimensions.y,
+                            ),
+                            x,
+                            y,
+                        );
+                        if FLOATING_SNAP_THRESHOLD > 0 {
+                            Self::add_snap_edges(call_wrapper, origin, &mut drag, state)?;
+                        }
+                        state.drag_window = Some((origin, drag));
                         pgwm_utils::debug!("Dragging window {}", origin);
                     } else {
                         dimensions.inner.forget(&mut call_wrapper.xcb_state);
@@ -419,24 +770,33 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         let props = call_wrapper.get_window_properties(event.window)?;
-        let attrs = call_wrapper.get_window_attributes(event.window)?;
         pgwm_utils::debug!("MapRequest incoming for sequence {}", event.sequence);
-        if let Ok(attrs) = attrs.reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state) {
-            pgwm_utils::debug!("Attributes {attrs:?}");
-            if attrs.override_redirect == 1 {
-                pgwm_utils::debug!("Override redirect, not managing");
-                props.forget(call_wrapper);
-                return Ok(());
-            }
-        } else {
-            pgwm_utils::debug!("No attributes, not managing");
+        if !Self::accept_map_request(call_wrapper, event.window)? {
             props.forget(call_wrapper);
             return Ok(());
         }
-        call_wrapper.set_state(event.window, WmState::Normal)?;
         self.manage_window(call_wrapper, event.window, props, state)
     }
 
+    /// Whether a window that asks to be mapped should be managed, setting its `WM_STATE` to normal if so.
+    /// Override-redirect windows aren't, and neither are windows that are gone before they're looked at
+    fn accept_map_request<X: ManagerCalls>(call_wrapper: &mut X, window: Window) -> Result<bool> {
+        match call_wrapper.is_override_redirect(window)? {
+            Some(false) => {
+                call_wrapper.set_state(window, WmState::Normal)?;
+                Ok(true)
+            }
+            Some(true) => {
+                pgwm_utils::debug!("Override redirect, not managing");
+                Ok(false)
+            }
+            None => {
+                pgwm_utils::debug!("No attributes, not managing");
+                Ok(false)
+            }
+        }
+    }
+
     /// Add a new window that should be managed by the WM
     fn manage_window(
         &self,
@@ -447,32 +807,81 @@ impl<'a> Manager<'a> {
     ) -> Result<()> {
         dbg_win!(call_wrapper, win);
         call_wrapper.set_base_client_event_mask(win)?;
+        #[cfg(feature = "frames")]
+        call_wrapper.frame(win, state)?;
         call_wrapper.set_base_client_properties(win)?;
+        call_wrapper.set_extents(win, state.frame_extents(win))?;
         let dimensions_cookie = call_wrapper.get_dimensions(win)?;
         let properties = window_properties_cookie.await_properties(call_wrapper)?;
+        call_wrapper.select_colormap_changes(win, &properties.colormap_windows)?;
         pgwm_utils::debug!("Managing window {:?}", win);
-        let ws_ind = if let Some(ws_ind) =
-            Self::map_window_class_to_workspace(call_wrapper, win, &state.workspaces)?
-        {
-            ws_ind
-        } else {
-            state.monitors[state.focused_mon].hosted_workspace
+        let set = state.workspaces.set_of_monitor(state.focused_mon);
+        let placement =
+            Self::map_window_class_to_workspace(call_wrapper, win, set, &state.workspaces)?;
+        let mapped_ws = placement.map(|(ws_ind, _)| ws_ind);
+        let deduction = float_status(&properties, state.screen.root);
+        let (WindowFloatDeduction::Floating { mut parent }
+        | WindowFloatDeduction::Docked { mut parent }) = deduction;
+        // A transient ends up wherever its transient root is, unless a class mapping takes it elsewhere
+        let parent_ws = parent.and_then(|parent| {
+            state
+                .workspaces
+                .find_ws_containing_window(state.workspaces.find_transient_root(parent))
+        });
+        let ws_ind = match (parent_ws, mapped_ws) {
+            (Some(parent_ws), Some(_)) if TRANSIENTS_FOLLOW_PARENT => parent_ws,
+            (Some(_), Some(mapped_ws)) => {
+                pgwm_utils::debug!("Class mapping {win} away from its parent");
+                parent = None;
+                mapped_ws
+            }
+            (Some(ws_ind), None) | (None, Some(ws_ind)) => ws_ind,
+            (None, None) => state.monitors[state.focused_mon].hosted_workspace,
         };
-        match float_status(&properties, state.screen.root) {
-            WindowFloatDeduction::Floating { parent } => {
+        // Put back where the user last left a window of its class floating
+        if let Some(floating) = placement
+            .and_then(|(_, floating)| floating)
+            .filter(|_| parent.is_none())
+        {
+            dimensions_cookie.inner.forget(&mut call_wrapper.xcb_state);
+            let mon_ind = state
+                .find_monitor_hosting_workspace(ws_ind)
+                .unwrap_or(state.focused_mon);
+            let mon_dimensions = state.monitors[mon_ind].dimensions;
+            let dims = Dimensions::new(
+                floating.width,
+                floating.height,
+                (mon_dimensions.x as f32 + mon_dimensions.width as f32 * floating.rel_x) as i16,
+                (mon_dimensions.y as f32 + mon_dimensions.height as f32 * floating.rel_y) as i16,
+            );
+            return self.manage_floating(
+                call_wrapper,
+                win,
+                properties,
+                None,
+                mon_ind,
+                ws_ind,
+                dims,
+                state,
+            );
+        }
+        match deduction {
+            WindowFloatDeduction::Floating { .. } => {
                 let dims = dimensions_cookie.await_dimensions(call_wrapper)?;
                 self.manage_floating(
                     call_wrapper,
                     win,
                     properties,
                     parent,
-                    state.focused_mon,
+                    state
+                        .find_monitor_hosting_workspace(ws_ind)
+                        .unwrap_or(state.focused_mon),
                     ws_ind,
                     dims,
                     state,
                 )?;
             }
-            WindowFloatDeduction::Docked { parent } => {
+            WindowFloatDeduction::Docked { .. } => {
                 dimensions_cookie.inner.forget(&mut call_wrapper.xcb_state);
                 self.manage_tiled(
                     call_wrapper,
@@ -531,7 +940,7 @@ impl<'a> Manager<'a> {
         if let Some(mon_ind) = draw_on_mon {
             self.drawer.draw_on(call_wrapper, mon_ind, true, state)?;
         } else {
-            call_wrapper.send_unmap(win, state)?;
+            Drawer::hide(call_wrapper, win, state)?;
         }
         call_wrapper.push_to_client_list(state.screen.root, win)?;
         self.try_focus_window(call_wrapper, win, state)?;
@@ -679,12 +1088,35 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
+    /// Tracks override-redirect windows being mapped, those never go through a map request
+    pub(crate) fn handle_map_notify(
+        call_wrapper: &mut CallWrapper,
+        event: MapNotifyEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        if event.override_redirect == 1
+            && event.event == state.screen.root
+            && !state.intern_created_windows.contains_key(&event.window)
+        {
+            // Popups are often gone as soon as they're up
+            if let Ok(dimensions) = call_wrapper
+                .get_dimensions(event.window)?
+                .await_dimensions(call_wrapper)
+            {
+                pgwm_utils::debug!("Tracking popup {} at {dimensions:?}", event.window);
+                state.track_popup(event.window, (dimensions.x, dimensions.y));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn handle_unmap_notify(
         &self,
         call_wrapper: &mut CallWrapper,
         event: UnmapNotifyEvent,
         state: &mut State,
     ) -> Result<()> {
+        state.untrack_popup(event.window);
         // Is a managed window, manually unmapped windows are not removed
         if state
             .workspaces
@@ -735,86 +1167,372 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
-    fn unset_fullscreen(
+    /// Switches a window between fake and real fullscreen. A fullscreen window going fake is put back
+    /// in the layout while the client keeps thinking it's fullscreen, and the other way around
+    fn toggle_fake_fullscreen(
         &self,
         call_wrapper: &mut CallWrapper,
-        mon_ind: usize,
-        ws_ind: usize,
+        window: Window,
         state: &mut State,
-    ) -> Result<bool> {
-        if let Some(old_fs_on_ws) = state.workspaces.unset_fullscreened(ws_ind) {
-            if let Some(old_fs) = state.workspaces.get_managed_win_mut(old_fs_on_ws) {
-                old_fs.properties.net_wm_state.fullscreen = false;
-                call_wrapper.set_net_wm_state(old_fs_on_ws, old_fs.properties.net_wm_state)?;
-            } else {
-                // We can fullscreen unmanaged windows
-                let mut net_wm_state = call_wrapper
-                    .get_net_wm_state(old_fs_on_ws)?
-                    .await_net_wm_state(call_wrapper)?
-                    .unwrap_or_default();
-                net_wm_state.fullscreen = false;
-                call_wrapper.set_net_wm_state(old_fs_on_ws, net_wm_state)?;
+    ) -> Result<()> {
+        let Some(mw) = state.workspaces.get_managed_win_mut(window) else {
+            return Ok(());
+        };
+        mw.fake_fullscreen = !mw.fake_fullscreen;
+        let fake_fullscreen = mw.fake_fullscreen;
+        if !mw.properties.net_wm_state.fullscreen {
+            return Ok(());
+        }
+        let Some((mon_ind, ws_ind)) = state.find_monitor_and_ws_indices_of_window(window) else {
+            return Ok(());
+        };
+        if !fake_fullscreen {
+            return self.set_fullscreen(call_wrapper, mon_ind, ws_ind, window, state);
+        }
+        if matches!(
+            state.workspaces.get_draw_mode(ws_ind),
+            Mode::Fullscreen { window: fullscreen, .. } if fullscreen == window
+        ) {
+            self.unset_fullscreen(call_wrapper, mon_ind, ws_ind, state)?;
+            if let Some(mw) = state.workspaces.get_managed_win_mut(window) {
+                mw.properties.net_wm_state.fullscreen = true;
+                call_wrapper.set_net_wm_state(window, mw.properties.net_wm_state)?;
             }
-            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
-            self.bar_manager.redraw_on(call_wrapper, mon_ind, state)?;
-            Ok(true)
-        } else {
-            Ok(false)
         }
+        Ok(())
     }
 
-    pub(crate) fn handle_destroy_notify(
+    fn send_to_workspace(
         &self,
         call_wrapper: &mut CallWrapper,
-        event: DestroyNotifyEvent,
+        target_window: Window,
+        num: usize,
         state: &mut State,
     ) -> Result<()> {
-        self.unmanage(call_wrapper, event.window, state)?;
-        if let Some(pos) = state
-            .dying_windows
-            .iter()
-            .position(|marked| marked.win == event.window)
-        {
-            pgwm_core::util::vec_ops::remove(&mut state.dying_windows, pos);
+        if let Some(ws) = state.workspaces.find_ws_containing_window(target_window) {
+            if ws == num {
+                pgwm_utils::debug!("Tried to send to same workspace {}", num);
+            } else {
+                let properties = if let Some(removed_mw) = self
+                    .remove_win_from_state_then_redraw_if_tiled(call_wrapper, target_window, state)?
+                    .into_option()
+                {
+                    Drawer::hide(call_wrapper, target_window, state)?;
+                    removed_mw.properties
+                } else {
+                    call_wrapper
+                        .get_window_properties(target_window)?
+                        .await_properties(call_wrapper)?
+                };
+                state.workspaces.add_child_to_ws(
+                    target_window,
+                    num,
+                    ArrangeKind::NoFloat,
+                    Self::deduce_focus_style(&properties),
+                    &properties,
+                )?;
+                Self::remember_placement(target_window, None, state);
+                if let Some(target) = state.find_monitor_hosting_workspace(num) {
+                    self.drawer.draw_on(call_wrapper, target, true, state)?;
+                }
+            }
         }
         Ok(())
     }
 
-    pub(crate) fn handle_configure_notify(
+    /// Unmaps the windows of the workspace hosted on a monitor without unmanaging them, or draws them again
+    fn show_desktop(
+        &self,
         call_wrapper: &mut CallWrapper,
-        event: ConfigureNotifyEvent,
+        mon_ind: usize,
+        show: bool,
         state: &mut State,
     ) -> Result<()> {
-        // Screen resize, have to invalidate monitor state
-        if event.event == state.screen.root && event.window == state.screen.root {
-            crate::debug!("Got state invalidation because of a configure notify on root");
-            for mon in 0..state.monitors.len() {
-                Drawer::undraw(call_wrapper, mon, state)?;
+        if state.monitors[mon_ind].showing_desktop == show {
+            return Ok(());
+        }
+        if show {
+            let ws_ind = state.monitors[mon_ind].hosted_workspace;
+            let windows = state
+                .workspaces
+                .iter_all_managed_windows_in_ws(ws_ind)
+                .map(|mw| mw.window)
+                .collect::<Vec<Window>>();
+            for window in windows {
+                call_wrapper.send_unmap(window, state)?;
             }
-            return Err(Error::StateInvalidated);
+            call_wrapper.send_unmap(state.monitors[mon_ind].tab_bar_win.window.drawable, state)?;
+            state.monitors[mon_ind].showing_desktop = true;
+            call_wrapper.set_showing_desktop(state)?;
+            self.focus_root_on_mon(call_wrapper, mon_ind, state)?;
+        } else {
+            // Drawing leaves the showing desktop mode
+            self.drawer.draw_on(call_wrapper, mon_ind, true, state)?;
+            self.focus_mon(call_wrapper, mon_ind, state)?;
         }
         Ok(())
     }
 
-    pub(crate) fn handle_configure_request(
+    /// Focuses a window, if it's not on a visible workspace its workspace is first
+    /// shown on the focused monitor, or the monitor owning its workspace set
+    fn focus_or_show_window(
+        &self,
         call_wrapper: &mut CallWrapper,
-        event: ConfigureRequestEvent,
+        window: Window,
         state: &mut State,
     ) -> Result<()> {
-        // Don't allow reconfiguration of tiled windows
-        if !state.workspaces.is_managed_tiled(event.window) {
-            call_wrapper.configure_from_request(&event)?;
+        if !self.try_focus_window(call_wrapper, window, state)? {
+            if let Some(ws_ind) = state.workspaces.find_ws_containing_window(window) {
+                if let Some(mon_ind) = state.monitor_for_workspace(ws_ind, state.focused_mon) {
+                    self.toggle_workspace(call_wrapper, ws_ind, mon_ind, state)?;
+                    self.focus_window(call_wrapper, mon_ind, window, state)?;
+                }
+            }
         }
         Ok(())
     }
 
-    pub(crate) fn handle_button_press(
-        &self,
-        call_wrapper: &mut CallWrapper,
-        event: ButtonPressEvent,
-        state: &mut State,
-    ) -> Result<()> {
-        state.last_timestamp = event.time;
+    /// The working directory of the focused window's process, or of the youngest process
+    /// started under it, as a null terminated `/proc/<pid>/cwd` link
+    #[cfg(not(feature = "perf-test"))]
+    fn focused_process_cwd(state: &State) -> Option<alloc::string::String> {
+        let window = state.input_focus?;
+        let mut pid = state.workspaces.get_managed_win(window)?.properties.pid?;
+        for _ in 0.._SPAWN_CWD_PROCESS_DEPTH {
+            let children = alloc::format!("/proc/{pid}/task/{pid}/children\0");
+            let Some(child) = UnixStr::try_from_str(&children)
+                .ok()
+                .and_then(|path| tiny_std::fs::read(path).ok())
+                .and_then(|children| youngest_child(&children))
+            else {
+                break;
+            };
+            pid = child;
+        }
+        Some(alloc::format!("/proc/{pid}/cwd\0"))
+    }
+
+    fn close_keybind_help(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        if state.keybind_help_shown {
+            state.keybind_help_shown = false;
+            call_wrapper.ungrab_keyboard()?;
+            call_wrapper.send_unmap(state.keybind_help_win.window.drawable, state)?;
+        }
+        Ok(())
+    }
+
+    fn close_window_hints(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        if let Some(hints) = state.window_hints.take() {
+            call_wrapper.ungrab_keyboard()?;
+            for ind in 0..hints.len() {
+                let hint_win = state.window_hint_wins[ind].window.drawable;
+                call_wrapper.send_unmap(hint_win, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The viewable windows on each monitor's hosted workspace and where they are, the first
+    /// [`pgwm_core::state::hints::MAX_WINDOW_HINTS`] of them get a hint
+    fn window_hint_targets(
+        call_wrapper: &mut CallWrapper,
+        state: &State,
+    ) -> Result<Vec<HintTarget>> {
+        let mut cookies = Vec::new();
+        for mon in state.monitors.iter().filter(|mon| !mon.showing_desktop) {
+            for window in state.workspaces.stacking_order_in_ws(mon.hosted_workspace) {
+                cookies.push((
+                    window,
+                    call_wrapper.get_window_attributes(window)?,
+                    call_wrapper.get_dimensions(window)?,
+                ));
+            }
+        }
+        let mut targets = Vec::new();
+        for (window, attributes, dimensions) in cookies {
+            // Both replies are awaited even if one fails, a window can be destroyed at any time
+            let attributes = attributes.reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state);
+            let dimensions = dimensions.await_dimensions(call_wrapper);
+            if let (Ok(attributes), Ok(dimensions)) = (attributes, dimensions) {
+                if attributes.map_state == MapStateEnum::VIEWABLE {
+                    targets.push(HintTarget { window, dimensions });
+                }
+            }
+        }
+        Ok(targets)
+    }
+
+    fn close_window_picker(&self, call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        if let Some(picker) = state.window_picker.take() {
+            call_wrapper.ungrab_keyboard()?;
+            self.bar_manager
+                .restore_window_title(call_wrapper, picker.mon_ind, state)?;
+        }
+        Ok(())
+    }
+
+    /// Maximizes a window to the usable area of its monitor, floating it if tiled,
+    /// or restores it to its pre-maximize geometry, or to being tiled, if already maximized
+    fn toggle_maximize(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        ws_ind: usize,
+        window: Window,
+        state: &mut State,
+    ) -> Result<()> {
+        let current = call_wrapper
+            .get_dimensions(window)?
+            .await_dimensions(call_wrapper)?;
+        let target = match state.workspaces.toggle_maximized(window, current) {
+            Some(Some(previous)) if previous.tiled => {
+                pgwm_utils::debug!("Restoring maximized {window} to tiled");
+                state.workspaces.un_float_window(window);
+                return self.drawer.draw_on(call_wrapper, mon_ind, false, state);
+            }
+            Some(Some(previous)) => previous.dimensions,
+            Some(None) => state.maximized_dimensions(mon_ind),
+            None => return Ok(()),
+        };
+        pgwm_utils::debug!("Toggling maximize on {window}, drawing at {target:?}");
+        let was_tiled = state.workspaces.is_managed_tiled(window);
+        // Floating windows are drawn offset by the bar height
+        let (x, y) = calculate_relative_placement(
+            state.monitors[mon_ind].dimensions,
+            target.x,
+            target.y - state.monitors[mon_ind].bar_height,
+        );
+        state
+            .workspaces
+            .toggle_floating(window, ws_ind, ArrangeKind::FloatingInactive(x, y));
+        if was_tiled {
+            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+        }
+        let border_width = state.border_width_of(ws_ind);
+        call_wrapper.configure_window(window, target, border_width, state)?;
+        call_wrapper.push_window_to_top(window, state)?;
+        Ok(())
+    }
+
+    fn unset_fullscreen(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        ws_ind: usize,
+        state: &mut State,
+    ) -> Result<bool> {
+        if let Some(old_fs_on_ws) = state.workspaces.unset_fullscreened(ws_ind) {
+            if let Some(old_fs) = state.workspaces.get_managed_win_mut(old_fs_on_ws) {
+                old_fs.properties.net_wm_state.fullscreen = false;
+                call_wrapper.set_net_wm_state(old_fs_on_ws, old_fs.properties.net_wm_state)?;
+            } else {
+                // We can fullscreen unmanaged windows
+                let mut net_wm_state = call_wrapper
+                    .get_net_wm_state(old_fs_on_ws)?
+                    .await_net_wm_state(call_wrapper)?
+                    .unwrap_or_default();
+                net_wm_state.fullscreen = false;
+                call_wrapper.set_net_wm_state(old_fs_on_ws, net_wm_state)?;
+            }
+            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+            self.bar_manager.redraw_on(call_wrapper, mon_ind, state)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub(crate) fn handle_destroy_notify(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        event: DestroyNotifyEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        state.untrack_popup(event.window);
+        self.unmanage(call_wrapper, event.window, state)?;
+        if let Some(pos) = state
+            .dying_windows
+            .iter()
+            .position(|marked| marked.win == event.window)
+        {
+            pgwm_core::util::vec_ops::remove(&mut state.dying_windows, pos);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn handle_configure_notify(
+        call_wrapper: &mut CallWrapper,
+        event: ConfigureNotifyEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        // Screen resize, have to invalidate monitor state
+        if event.event == state.screen.root && event.window == state.screen.root {
+            crate::debug!("Got state invalidation because of a configure notify on root");
+            for mon in 0..state.monitors.len() {
+                Drawer::undraw(call_wrapper, mon, state)?;
+            }
+            return Err(Error::StateInvalidated);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn handle_mapping_notify(
+        call_wrapper: &mut CallWrapper,
+        event: MappingNotifyEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        // Pointer mapping changes don't affect us
+        if event.request == MappingEnum::KEYBOARD || event.request == MappingEnum::MODIFIER {
+            crate::debug!("Keyboard mapping changed, re-grabbing keys");
+            crate::x11::state_lifecycle::reinit_keys(call_wrapper, state)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn handle_colormap_notify(
+        call_wrapper: &mut CallWrapper,
+        event: ColormapNotifyEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        // Only the colormap attribute changing matters, (un)installs are our own doing
+        if event.new == 0 {
+            return Ok(());
+        }
+        if let Some(focused) = state.input_focus {
+            if focused == event.window
+                || state
+                    .workspaces
+                    .get_managed_win(focused)
+                    .map_or(false, |mw| {
+                        mw.properties.colormap_windows.contains(&event.window)
+                    })
+            {
+                pgwm_utils::debug!("Colormap changed for focused {focused}, reinstalling");
+                Self::install_focused_colormaps(call_wrapper, focused, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn handle_configure_request(
+        call_wrapper: &mut CallWrapper,
+        event: ConfigureRequestEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        // Don't allow reconfiguration of tiled windows
+        if !state.workspaces.is_managed_tiled(event.window) {
+            call_wrapper.configure_from_request(&event)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn handle_button_press(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        event: ButtonPressEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        state.last_timestamp = event.time;
         let mon_ind = state
             .find_monitor_at((event.root_x, event.root_y))
             .unwrap_or(0);
@@ -824,29 +1542,32 @@ impl<'a> Manager<'a> {
             state.workspaces.get_draw_mode(mon.hosted_workspace),
             event.event == mon.tab_bar_win.window.drawable,
         ) {
-            let width = mon.dimensions.width;
             let hosted_ws = mon.hosted_workspace;
-            let stacked_children = state.workspaces.get_all_tiled_windows(hosted_ws).len();
-            let bar_width = width / stacked_children as i16;
-            for b in 0..stacked_children {
-                if event.event_x <= bar_width * (b + 1) as i16 {
-                    pgwm_utils::debug!("Selected bar number {}", b);
-                    if state.workspaces.switch_tab_focus_index(hosted_ws, b) {
-                        let dm = state.workspaces.get_draw_mode(hosted_ws);
-                        self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
-                        let tiled = state.workspaces.get_all_tiled_windows(hosted_ws);
-                        let focus = if let Mode::Tabbed(n) = dm {
-                            Some(tiled[n].window)
-                        } else {
-                            None
-                        };
-                        drop(tiled);
-                        if let Some(focus) = focus {
-                            self.focus_window(call_wrapper, mon_ind, focus, state)?;
-                        }
+            if let Some(b) = state.tab_at(mon_ind, event.event_x) {
+                pgwm_utils::debug!("Selected bar number {}", b);
+                if event.detail == ButtonIndexEnum::ONE.0 {
+                    // Dragging the tab moves it until the button is released
+                    state.dragged_tab = state
+                        .workspaces
+                        .get_all_tiled_windows(hosted_ws)
+                        .get(b)
+                        .map(|mw| (mon_ind, mw.window));
+                }
+                if state.workspaces.switch_tab_focus_index(hosted_ws, b) {
+                    let dm = state.workspaces.get_draw_mode(hosted_ws);
+                    self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+                    let tiled = state.workspaces.get_all_tiled_windows(hosted_ws);
+                    let focus = if let Mode::Tabbed(n) = dm {
+                        Some(tiled[n].window)
+                    } else {
+                        None
+                    };
+                    drop(tiled);
+                    if let Some(focus) = focus {
+                        self.focus_window(call_wrapper, mon_ind, focus, state)?;
                     }
-                    return Ok(());
                 }
+                return Ok(());
             }
         }
         // Priority, always accept clicks on bar
@@ -862,6 +1583,7 @@ impl<'a> Manager<'a> {
             match target {
                 MouseTarget::WorkspaceBarComponent(_)
                 | MouseTarget::WindowTitle
+                | MouseTarget::UrgentSection
                 | MouseTarget::ShortcutComponent(_)
                 | MouseTarget::StatusComponent(_) => {
                     // If we clicked on a monitor we need to focus it, other logic depends on
@@ -882,14 +1604,25 @@ impl<'a> Manager<'a> {
         };
 
         pgwm_utils::debug!("Button press for target {:?}", target);
-        if let Some(action) =
-            target.and_then(|tg| state.get_mouse_action(event.detail, event.state.0, tg))
-        {
+        if let Some(action) = target.and_then(|tg| {
+            state
+                .get_mouse_action(event.detail, event.state.0, tg)
+                .copied()
+                .or_else(|| match tg {
+                    MouseTarget::ShortcutComponent(ind) => state
+                        .get_shortcut_action(mon_ind, ind, event.detail, event.state.0)
+                        .copied(),
+                    MouseTarget::StatusComponent(ind) => {
+                        state.get_status_action(ind, event.detail, event.state.0)
+                    }
+                    _ => None,
+                })
+        }) {
             self.exec_action(
                 call_wrapper,
                 event.child.0,
                 InputSource::Mouse(event.event_x, event.event_y),
-                *action,
+                action,
                 state,
             )?;
         }
@@ -904,22 +1637,30 @@ impl<'a> Manager<'a> {
         window: Window,
         state: &mut State,
     ) -> Result<()> {
-        let dimensions = call_wrapper.get_dimensions(window)?;
         let diff = diff_percent as f32 / 100f32;
         if state.workspaces.update_size_modifier(window, diff) {
             if let Some(mon_ind) = state.find_monitor_index_of_window(window) {
                 self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
             }
-            dimensions.inner.forget(&mut call_wrapper.xcb_state);
         } else {
-            let dimensions = dimensions.await_dimensions(call_wrapper)?;
-            let height = (dimensions.height as f32 * (1f32 + diff)) as u32;
-            let width = (dimensions.width as f32 * (1f32 + diff)) as u32;
-            call_wrapper.resize_window(window, height, width, state)?;
+            Self::resize_untiled(call_wrapper, window, diff, state)?;
         }
         Ok(())
     }
 
+    /// Grows or shrinks a window that isn't tiled by `diff` of its current size
+    fn resize_untiled<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        window: Window,
+        diff: f32,
+        state: &mut State,
+    ) -> Result<()> {
+        let dimensions = call_wrapper.dimensions(window)?;
+        let height = (dimensions.height as f32 * (1f32 + diff)) as u32;
+        let width = (dimensions.width as f32 * (1f32 + diff)) as u32;
+        call_wrapper.resize_window(window, height, width, state)
+    }
+
     pub(crate) fn handle_button_release(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -927,7 +1668,10 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         state.last_timestamp = event.time;
-        if let Some((win, _drag)) = state.drag_window.take() {
+        if let Some((_, window)) = state.dragged_tab.take() {
+            pgwm_utils::debug!("Dropped tab of {window}");
+        }
+        if let Some((win, drag)) = state.drag_window.take() {
             let win_dims = call_wrapper.get_dimensions(win)?;
             pgwm_utils::debug!("Got button release and removed drag window {win}");
             let properties = self
@@ -942,27 +1686,120 @@ impl<'a> Manager<'a> {
                     |mw| Ok(mw.properties),
                 )?;
             let (x, y) = (event.root_x, event.root_y);
-            let mon = state.find_monitor_at((x, y)).unwrap_or(0);
-            let mon = &state.monitors[mon];
-            let new_ws = mon.hosted_workspace;
-            // Using different placement because one is pointer-relative and the other window-left corner relative
-            let (x, y) = if let Ok(dims) = win_dims.await_dimensions(call_wrapper) {
-                calculate_relative_placement(mon.dimensions, dims.x, dims.y)
+            let mon_ind = state.find_monitor_at((x, y)).unwrap_or(0);
+            let new_ws = state.monitors[mon_ind].hosted_workspace;
+            let focus_style = Self::deduce_focus_style(&properties);
+            // Dropped on another monitor's tiled windows, tile it where it was dropped
+            let drop_ind = if state.find_monitor_at(drag.event_origin()) == Some(mon_ind) {
+                None
             } else {
-                calculate_relative_placement(mon.dimensions, x, y)
+                state.tiling_drop_index(mon_ind, (x, y))?
             };
-            state.workspaces.add_child_to_ws(
-                win,
-                new_ws,
-                ArrangeKind::FloatingInactive(x, y),
-                Self::deduce_focus_style(&properties),
-                &properties,
-            )?;
+            if let Some(tiled_ind) = drop_ind {
+                win_dims.inner.forget(&mut call_wrapper.xcb_state);
+                state.workspaces.add_child_to_ws(
+                    win,
+                    new_ws,
+                    ArrangeKind::NoFloat,
+                    focus_style,
+                    &properties,
+                )?;
+                state.workspaces.move_to_tiling_index(win, tiled_ind);
+                pgwm_utils::debug!("Tiled dropped window {win} at {tiled_ind} on mon {mon_ind}");
+                self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+                Self::remember_placement(win, None, state);
+            } else {
+                let mon = &state.monitors[mon_ind];
+                // Using different placement because one is pointer-relative and the other window-left corner relative
+                let dims = win_dims.await_dimensions(call_wrapper).ok();
+                let (x, y) = if let Some(dims) = dims {
+                    calculate_relative_placement(mon.dimensions, dims.x, dims.y)
+                } else {
+                    calculate_relative_placement(mon.dimensions, x, y)
+                };
+                state.workspaces.add_child_to_ws(
+                    win,
+                    new_ws,
+                    ArrangeKind::FloatingInactive(x, y),
+                    focus_style,
+                    &properties,
+                )?;
+                let floating = dims.map(|dims| FloatingGeometry {
+                    rel_x: x,
+                    rel_y: y,
+                    width: dims.width,
+                    height: dims.height,
+                });
+                Self::remember_placement(win, floating, state);
+            }
             Self::conditional_ungrab_pointer(call_wrapper, state)?;
         }
         Ok(())
     }
 
+    /// Moves a tab dragged in the tab bar to where the pointer is, which also moves its window
+    /// in the tiling order. Redrawn as it moves so the tab follows the pointer.
+    fn drag_tab(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        window: Window,
+        x: i16,
+        state: &mut State,
+    ) -> Result<()> {
+        let ws_ind = state.monitors[mon_ind].hosted_workspace;
+        if state.workspaces.find_ws_containing_window(window) != Some(ws_ind) {
+            // Moved away mid-drag
+            state.dragged_tab = None;
+            return Ok(());
+        }
+        if let Some(target) = state.tab_at(mon_ind, x) {
+            if state.workspaces.move_to_tiling_index(window, target) {
+                pgwm_utils::debug!("Moved tab of {window} to {target}");
+                state.workspaces.switch_tab_focus_window(ws_ind, window)?;
+                self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects edges that a dragged window should snap to, monitor edges, the bottom of
+    /// shown bars, and the outer edges of other floating windows on visible workspaces
+    fn add_snap_edges(
+        call_wrapper: &mut CallWrapper,
+        dragged: Window,
+        drag: &mut DragPosition,
+        state: &State,
+    ) -> Result<()> {
+        let mut cookies = Vec::new();
+        for mon in &state.monitors {
+            drag.add_snap_area(mon.dimensions);
+            if mon.show_bar {
+                drag.add_snap_y(mon.dimensions.y + mon.bar_height);
+            }
+            for mw in state
+                .workspaces
+                .iter_all_managed_windows_in_ws(mon.hosted_workspace)
+                .filter(|mw| mw.window != dragged && mw.arrange != ArrangeKind::NoFloat)
+            {
+                let border = state.border_width_of(mon.hosted_workspace) as i16;
+                cookies.push((call_wrapper.get_dimensions(mw.window)?, border));
+            }
+        }
+        for (cookie, border) in cookies {
+            // Window might be gone, that's fine, nothing to snap to
+            if let Ok(dims) = cookie.await_dimensions(call_wrapper) {
+                drag.add_snap_area(Dimensions::new(
+                    dims.width + 2 * border,
+                    dims.height + 2 * border,
+                    dims.x,
+                    dims.y,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn handle_motion_notify(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -970,6 +1807,27 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         state.last_timestamp = event.time;
+        if state.drag_window.is_none() {
+            let position = (event.root_x, event.root_y);
+            if let Some(action) =
+                state.update_edge_dwell(EDGE_ACTIONS, position, tiny_std::time::Instant::now())
+            {
+                self.exec_edge_action(call_wrapper, action, position, state)?;
+            }
+        }
+        if let Some((mon_ind, window)) = state.dragged_tab {
+            if event.event == state.monitors[mon_ind].tab_bar_win.window.drawable {
+                return self.drag_tab(call_wrapper, mon_ind, window, event.event_x, state);
+            }
+        }
+        if let Some(mon_ind) = state.find_monitor_index_of_bar_win(event.event) {
+            return self.update_shortcut_hover(
+                call_wrapper,
+                mon_ind,
+                Some(event.root_x - state.monitors[mon_ind].dimensions.x),
+                state,
+            );
+        }
         if let Some((win, drag_pos)) = &state.drag_window {
             let (x, y) = drag_pos.current_position(event.event_x, event.event_y);
             // Sigh, X11 and its mixing up i16 and i32
@@ -999,11 +1857,315 @@ impl<'a> Manager<'a> {
                     self.focus_mon(call_wrapper, mon, state)?;
                     pgwm_utils::debug!("Updated focus to mon: {mon}");
                 }
+                if event.root_y < state.monitors[mon].dimensions.y + state.bar_reserved_height(mon)
+                {
+                    self.reveal_autohide_bar(call_wrapper, mon, state)?;
+                }
             }
         }
         Ok(())
     }
 
+    /// Draws the window title again unless the window picker or a shortcut's hover text is drawn over it,
+    /// in which case it's drawn when those are closed
+    fn restore_uncovered_window_title(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        let picker_open = state
+            .window_picker
+            .as_ref()
+            .is_some_and(|picker| picker.mon_ind == mon_ind);
+        if !picker_open
+            && state.monitors[mon_ind]
+                .bar_geometry
+                .shortcuts
+                .hovered
+                .is_none()
+        {
+            self.bar_manager
+                .restore_window_title(call_wrapper, mon_ind, state)?;
+        }
+        Ok(())
+    }
+
+    /// Runs a command sent through the root window's `_PGWM_COMMAND`, a bad command is only logged
+    fn exec_ipc_command(&self, call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        // Taking the command deletes it, which notifies again with nothing to take
+        let Some(command) = call_wrapper.take_command(state.screen.root)? else {
+            return Ok(());
+        };
+        match IpcCommand::parse(&command) {
+            Ok(IpcCommand::Notify { text, duration }) => {
+                let shown_for =
+                    duration.unwrap_or(core::time::Duration::from_millis(TITLE_NOTICE_MS));
+                self.show_title_notice(call_wrapper, text, shown_for, state)?;
+            }
+            Ok(IpcCommand::Query(query)) => {
+                let mut reply = query.answer(&StateSnapshot::capture(state));
+                if reply.len() > _IPC_REPLY_LIMIT {
+                    reply = alloc::string::String::from("{\"error\":\"reply too long\"}");
+                }
+                call_wrapper.set_reply(state.screen.root, &reply)?;
+            }
+            Ok(IpcCommand::Place { target, placement }) => {
+                let windows = match target {
+                    IpcTarget::Window(window) => state
+                        .workspaces
+                        .get_managed_win(window)
+                        .map(|mw| alloc::vec![mw.window])
+                        .unwrap_or_default(),
+                    IpcTarget::Class(class) => state.workspaces.with_class(class),
+                };
+                if windows.is_empty() {
+                    tiny_std::eprintln!("Ignoring command {command:?}: no managed window matches");
+                }
+                for window in windows {
+                    self.place_window(call_wrapper, window, placement, state)?;
+                }
+            }
+            Ok(IpcCommand::SetStatusInterval { position, interval }) => {
+                // The status checks are run from the event loop, which picks this up
+                state.status_interval_change = Some((position, interval.as_millis() as u64));
+            }
+            Err(e) => tiny_std::eprintln!("Ignoring command {command:?}: {e}"),
+        }
+        Ok(())
+    }
+
+    /// Floats a window and puts it where a command says, like dragging it would but without the pointer.
+    /// It stays on its workspace, which has to be shown
+    fn place_window(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        window: Window,
+        placement: IpcPlacement,
+        state: &mut State,
+    ) -> Result<()> {
+        let Some((mon_ind, ws_ind)) = state.find_monitor_and_ws_indices_of_window(window) else {
+            tiny_std::eprintln!("Not placing {window}, its workspace isn't shown");
+            return Ok(());
+        };
+        let current = call_wrapper
+            .get_dimensions(window)?
+            .await_dimensions(call_wrapper)?;
+        let placed = placement.apply(current);
+        // Floating windows are drawn offset by the bar height
+        let (rel_x, rel_y) = calculate_relative_placement(
+            state.monitors[mon_ind].dimensions,
+            placed.x,
+            placed.y - state.monitors[mon_ind].bar_height,
+        );
+        let was_tiled = !state.workspaces.is_managed_floating(window);
+        state.workspaces.toggle_floating(
+            window,
+            ws_ind,
+            ArrangeKind::FloatingInactive(rel_x, rel_y),
+        );
+        if was_tiled {
+            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+        }
+        Drawer::draw_floating(call_wrapper, window, placed, state)
+    }
+
+    /// Shows a message in place of the focused monitor's window title for `shown_for`
+    fn show_title_notice(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        text: &str,
+        shown_for: core::time::Duration,
+        state: &mut State,
+    ) -> Result<()> {
+        let mon_ind = state.focused_mon;
+        if let Some(replaced) =
+            state.show_title_notice_for(text, mon_ind, shown_for, tiny_std::time::Instant::now())
+        {
+            if replaced.mon_ind != mon_ind {
+                self.restore_uncovered_window_title(call_wrapper, replaced.mon_ind, state)?;
+            }
+        }
+        self.restore_uncovered_window_title(call_wrapper, mon_ind, state)
+    }
+
+    /// Puts the window title back once a notice has been shown long enough
+    pub(crate) fn clear_expired_title_notice(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        if let Some(notice) = state.take_expired_title_notice(tiny_std::time::Instant::now()) {
+            if notice.mon_ind < state.monitors.len() {
+                self.restore_uncovered_window_title(call_wrapper, notice.mon_ind, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Give focus back to the previously focused window if a globally active window didn't take it
+    pub(crate) fn verify_due_focus(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        let Some(request) = state.take_due_focus_request(tiny_std::time::Instant::now()) else {
+            return Ok(());
+        };
+        let focus = call_wrapper.input_focus()?;
+        if !state.focus_declined(&request, focus) {
+            return Ok(());
+        }
+        pgwm_utils::debug!("{} declined focus, focus is on {focus}", request.window);
+        if let Some(previous) = request.previous {
+            if self.try_focus_window(call_wrapper, previous, state)? {
+                // Don't bounce back if the previous window declines too
+                if let Some(pending) = state.pending_focus.as_mut() {
+                    pending.previous = None;
+                }
+                return Ok(());
+            }
+        }
+        self.focus_root_on_mon(call_wrapper, state.focused_mon, state)
+    }
+
+    /// Run the action of the screen edge the pointer rests against, if it's been there long enough
+    pub(crate) fn exec_due_edge_action(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        if let Some((action, position)) = state.pop_due_edge_action(tiny_std::time::Instant::now())
+        {
+            self.exec_edge_action(call_wrapper, action, position, state)?;
+        }
+        Ok(())
+    }
+
+    /// Reset the screensaver if it's due, which it is regularly while a fullscreen window is shown
+    pub(crate) fn reset_screensaver_if_fullscreen(
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        if state.next_screensaver_reset.is_none() && !state.shows_fullscreen() {
+            return Ok(());
+        }
+        let reset_every = core::time::Duration::from_secs(FULLSCREEN_SCREENSAVER_RESET_SECS);
+        if state.update_screensaver_reset(reset_every, tiny_std::time::Instant::now()) {
+            pgwm_utils::debug!("Resetting screensaver while fullscreen");
+            call_wrapper.reset_screensaver()?;
+        }
+        Ok(())
+    }
+
+    /// Have the windows of the workspace a monitor switched to slide into place as they're drawn,
+    /// pinned windows were already shown and stay put
+    #[cfg(feature = "animations")]
+    fn start_workspace_slide(
+        mon_ind: usize,
+        from_right: bool,
+        pinned: Vec<Window>,
+        state: &mut State,
+    ) {
+        if !ANIMATE_WORKSPACE_SWITCH || WORKSPACE_SWITCH_FRAMES == 0 {
+            return;
+        }
+        let distance = state.monitors[mon_ind].dimensions.width / _WORKSPACE_SWITCH_SLIDE_DIVISOR;
+        let offset = if from_right { distance } else { -distance };
+        state.workspace_slide = Some(WorkspaceSlide::new(
+            mon_ind,
+            i32::from(offset),
+            WORKSPACE_SWITCH_FRAMES,
+            pinned,
+            tiny_std::time::Instant::now(),
+            core::time::Duration::from_millis(WORKSPACE_SWITCH_FRAME_MS),
+        ));
+    }
+
+    /// Puts the windows of a sliding workspace where they end up right away
+    #[cfg(feature = "animations")]
+    fn finish_workspace_slide(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        if let Some(mut slide) = state.workspace_slide.take() {
+            for (window, x, y) in slide.finish() {
+                call_wrapper.reposition_window(window, x, y, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the windows of a sliding workspace along if a frame is due
+    #[cfg(feature = "animations")]
+    pub(crate) fn draw_due_slide_frame(
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        let Some(slide) = state.workspace_slide.as_mut() else {
+            return Ok(());
+        };
+        let frame = slide.take_due_frame(
+            tiny_std::time::Instant::now(),
+            core::time::Duration::from_millis(WORKSPACE_SWITCH_FRAME_MS),
+        );
+        if slide.is_finished() {
+            state.workspace_slide = None;
+        }
+        call_wrapper.begin_batch();
+        let res = frame
+            .into_iter()
+            .try_for_each(|(window, x, y)| call_wrapper.reposition_window(window, x, y, state));
+        call_wrapper.end_batch()?;
+        res
+    }
+
+    /// Run [`IDLE_ACTION`] if input has been idle for long enough, only asks the server when a check is due
+    #[cfg(feature = "idle")]
+    pub(crate) fn exec_due_idle_action(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        let now = tiny_std::time::Instant::now();
+        if state.next_idle_check.map_or(true, |due| due > now) {
+            return Ok(());
+        }
+        let idle_for = call_wrapper.query_idle_time(state.screen.root)?;
+        let idle_after = core::time::Duration::from_secs(IDLE_AFTER_MINUTES * 60);
+        if state.update_idle(idle_after, idle_for, now) {
+            pgwm_utils::debug!("Idle for {idle_for:?}, running idle action");
+            self.exec_action(
+                call_wrapper,
+                state.screen.root,
+                InputSource::Keyboard,
+                IDLE_ACTION,
+                state,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn exec_edge_action(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        action: Action,
+        position: (i16, i16),
+        state: &mut State,
+    ) -> Result<()> {
+        pgwm_utils::debug!("Running edge action {action:?} at {position:?}");
+        // Act on the monitor that the edge belongs to
+        if let Some(mon_ind) = state.find_monitor_at(position) {
+            if state.focused_mon != mon_ind {
+                self.focus_mon(call_wrapper, mon_ind, state)?;
+            }
+        }
+        self.exec_action(
+            call_wrapper,
+            state.screen.root,
+            InputSource::Mouse(position.0, position.1),
+            action,
+            state,
+        )
+    }
+
     pub(crate) fn handle_enter(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -1011,12 +2173,91 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         state.last_timestamp = event.time;
+        // Motion over client windows isn't seen, the pointer may have left an edge unnoticed
+        if event.event != state.screen.root
+            && state.find_monitor_index_of_bar_win(event.event).is_none()
+        {
+            state.edge_dwell = None;
+        }
         if event.event != state.screen.root && event.mode != NotifyModeEnum::GRAB {
             self.try_focus_window(call_wrapper, event.event, state)?;
         }
         Ok(())
     }
 
+    pub(crate) fn handle_leave(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        event: LeaveNotifyEvent,
+        state: &mut State,
+    ) -> Result<()> {
+        if let Some(mon_ind) = state.find_monitor_index_of_bar_win(event.event) {
+            self.update_shortcut_hover(call_wrapper, mon_ind, None, state)?;
+            // Keep an autohiding bar around while nothing is focused, there's nothing to give room to
+            if event.mode == NotifyModeEnum::NORMAL && state.monitors[mon_ind].last_focus.is_some()
+            {
+                Self::hide_autohide_bar(call_wrapper, mon_ind, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn hide_autohide_bar(
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        let mon = &state.monitors[mon_ind];
+        if mon.autohide_bar && mon.show_bar {
+            pgwm_utils::debug!("Hiding autohiding bar on mon = {mon_ind}");
+            BarManager::toggle_bar(call_wrapper, mon_ind, state)?;
+        }
+        Ok(())
+    }
+
+    fn reveal_autohide_bar(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        let mon = &state.monitors[mon_ind];
+        if mon.autohide_bar && !mon.show_bar {
+            pgwm_utils::debug!("Revealing autohiding bar on mon = {mon_ind}");
+            BarManager::toggle_bar(call_wrapper, mon_ind, state)?;
+            self.bar_manager.redraw_on(call_wrapper, mon_ind, state)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the hover text of the shortcut at `x` on the bar over the window title,
+    /// or restores the title if the pointer left the shortcut
+    fn update_shortcut_hover(
+        &self,
+        call_wrapper: &mut CallWrapper,
+        mon_ind: usize,
+        x: Option<i16>,
+        state: &mut State,
+    ) -> Result<()> {
+        let shortcuts = &mut state.monitors[mon_ind].bar_geometry.shortcuts;
+        let hovered = x.and_then(|x| shortcuts.hovered_at(x));
+        if hovered == shortcuts.hovered {
+            return Ok(());
+        }
+        shortcuts.hovered = hovered;
+        if state.window_picker.is_some() {
+            // The picker is drawn over the title
+            return Ok(());
+        }
+        if hovered.is_some() {
+            self.bar_manager
+                .draw_shortcut_hover(call_wrapper, mon_ind, state)
+        } else {
+            self.bar_manager
+                .restore_window_title(call_wrapper, mon_ind, state)
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
     pub(crate) fn handle_client_message(
         &self,
@@ -1033,11 +2274,39 @@ impl<'a> Manager<'a> {
         };
         match atom.intern_atom {
             SupportedAtom::NetRequestFrameExtents => {
-                call_wrapper.set_extents(event.window, state.window_border_width)?;
+                // Sent before the window is mapped, answered with the extents it'll likely get
+                call_wrapper.set_extents(event.window, state.frame_extents(event.window))?;
             }
             SupportedAtom::NetCloseWindow => {
                 self.unmanage_and_kill(call_wrapper, event.window, state)?;
             }
+            SupportedAtom::NetShowingDesktop => {
+                let show = event.data.0.as_iter_32().next() == Some(1);
+                pgwm_utils::debug!("Got showing desktop request {show}");
+                self.show_desktop(call_wrapper, state.focused_mon, show, state)?;
+            }
+            SupportedAtom::NetWmFullscreenMonitors => {
+                // Monitor indices as top, bottom, left, right, then the source indication
+                let mut monitors = [0; 4];
+                for (monitor, value) in monitors.iter_mut().zip(event.data.0.as_iter_32()) {
+                    *monitor = value;
+                }
+                pgwm_utils::debug!("Got fullscreen monitors {monitors:?} for {}", event.window);
+                if let Some(mw) = state.workspaces.get_managed_win_mut(event.window) {
+                    mw.fullscreen_monitors = Some(monitors.map(|ind| ind as usize));
+                    call_wrapper.set_fullscreen_monitors(event.window, monitors)?;
+                    if let Some((mon_ind, ws_ind)) =
+                        state.find_monitor_and_ws_indices_of_window(event.window)
+                    {
+                        if matches!(
+                            state.workspaces.get_draw_mode(ws_ind),
+                            Mode::Fullscreen { window, .. } if window == event.window
+                        ) {
+                            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+                        }
+                    }
+                }
+            }
             SupportedAtom::NetActiveWindow | SupportedAtom::NetWmStateDemandsAttention => {
                 self.make_window_urgent(call_wrapper, event.window, state)?;
             }
@@ -1109,7 +2378,20 @@ impl<'a> Manager<'a> {
                                     atom,
                                     event.window
                                 );
-                                if let Some((mon_ind, ws_ind)) =
+                                if state
+                                    .workspaces
+                                    .get_managed_win(event.window)
+                                    .is_some_and(|mw| mw.fake_fullscreen)
+                                {
+                                    // Only the client is told, the window stays in the layout
+                                    Self::update_net_wm_state(
+                                        call_wrapper,
+                                        event.window,
+                                        atom,
+                                        |net_wm_state| &mut net_wm_state.fullscreen,
+                                        state,
+                                    )?;
+                                } else if let Some((mon_ind, ws_ind)) =
                                     state.find_monitor_and_ws_indices_of_window(event.window)
                                 {
                                     match atom {
@@ -1150,6 +2432,40 @@ impl<'a> Manager<'a> {
                                     }
                                 }
                             }
+                            SupportedAtom::NetWmStateSkipTaskbar
+                            | SupportedAtom::NetWmStateSkipPager => {
+                                let flag: fn(&mut NetWmState) -> &mut bool = if resolved.intern_atom
+                                    == SupportedAtom::NetWmStateSkipTaskbar
+                                {
+                                    |net_wm_state| &mut net_wm_state.skip_taskbar
+                                } else {
+                                    |net_wm_state| &mut net_wm_state.skip_pager
+                                };
+                                if Self::update_net_wm_state(
+                                    call_wrapper,
+                                    event.window,
+                                    atom,
+                                    flag,
+                                    state,
+                                )? {
+                                    // Tabs are only listed for windows that don't skip the taskbar
+                                    if let Some((mon_ind, ws_ind)) =
+                                        state.find_monitor_and_ws_indices_of_window(event.window)
+                                    {
+                                        if matches!(
+                                            state.workspaces.get_draw_mode(ws_ind),
+                                            Mode::Tabbed(_)
+                                        ) {
+                                            self.drawer.draw_on(
+                                                call_wrapper,
+                                                mon_ind,
+                                                false,
+                                                state,
+                                            )?;
+                                        }
+                                    }
+                                }
+                            }
                             SupportedAtom::NetWmStateDemandsAttention => {
                                 if let Some(managed) =
                                     state.workspaces.get_managed_win(event.window)
@@ -1200,6 +2516,29 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
+    /// Removes (0), adds (1), or toggles (2) a `_NET_WM_STATE` flag of a managed window as a client message asks,
+    /// and sets the property to match. Returns whether the window is managed
+    fn update_net_wm_state<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        window: Window,
+        action: u32,
+        flag: fn(&mut NetWmState) -> &mut bool,
+        state: &mut State,
+    ) -> Result<bool> {
+        let Some(mw) = state.workspaces.get_managed_win_mut(window) else {
+            return Ok(false);
+        };
+        let flag = flag(&mut mw.properties.net_wm_state);
+        match action {
+            0 => *flag = false,
+            1 => *flag = true,
+            2 => *flag = !*flag,
+            _ => {}
+        }
+        call_wrapper.set_net_wm_state(window, mw.properties.net_wm_state)?;
+        Ok(true)
+    }
+
     fn float_window_redraw(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -1213,18 +2552,64 @@ impl<'a> Manager<'a> {
             dimensions.inner.forget(&mut call_wrapper.xcb_state);
             Ok(false)
         } else {
-            let dimensions = dimensions.await_dimensions(call_wrapper)?;
-            let (x, y) = calculate_relative_placement(
-                state.monitors[mon_ind].dimensions,
+            let last_floating = state
+                .workspaces
+                .get_managed_win(win)
+                .and_then(|mw| mw.last_floating);
+            let (x, y) = if let Some(last) = last_floating {
+                dimensions.inner.forget(&mut call_wrapper.xcb_state);
+                (last.rel_x, last.rel_y)
+            } else {
+                let dimensions = dimensions.await_dimensions(call_wrapper)?;
+                calculate_relative_placement(
+                    state.monitors[mon_ind].dimensions,
+                    dimensions.x,
+                    dimensions.y,
+                )
+            };
+            state
+                .workspaces
+                .toggle_floating(win, ws_ind, ArrangeKind::FloatingInactive(x, y));
+            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+            if let Some(last) = last_floating {
+                call_wrapper.resize_window(win, last.height as u32, last.width as u32, state)?;
+            }
+            Ok(true)
+        }
+    }
+
+    /// Remembers where a floating window is, so that it can be put back there if it's floated again
+    fn remember_floating_geometry(
+        call_wrapper: &mut CallWrapper,
+        window: Window,
+        mon_ind: usize,
+        state: &mut State,
+    ) -> Result<()> {
+        if !state.workspaces.is_managed_floating(window) {
+            return Ok(());
+        }
+        let current = call_wrapper
+            .get_dimensions(window)?
+            .await_dimensions(call_wrapper)?;
+        let mon_dimensions = state.monitors[mon_ind].dimensions;
+        let bar_height = state.monitors[mon_ind].bar_height;
+        if let Some(mw) = state.workspaces.get_managed_win_mut(window) {
+            // A maximized window should come back at its pre-maximize geometry
+            let dimensions = mw.pre_maximize.map_or(current, |pre| pre.dimensions);
+            // Floating windows are drawn offset by the bar height
+            let (rel_x, rel_y) = calculate_relative_placement(
+                mon_dimensions,
                 dimensions.x,
-                dimensions.y,
+                dimensions.y - bar_height,
             );
-            state
-                .workspaces
-                .toggle_floating(win, ws_ind, ArrangeKind::FloatingInactive(x, y));
-            self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
-            Ok(true)
+            mw.last_floating = Some(FloatingGeometry {
+                rel_x,
+                rel_y,
+                width: dimensions.width,
+                height: dimensions.height,
+            });
         }
+        Ok(())
     }
 
     fn unfloat_window_redraw(
@@ -1235,6 +2620,7 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         if state.workspaces.is_managed_floating(window) {
+            Self::remember_floating_geometry(call_wrapper, window, mon_ind, state)?;
             state.workspaces.un_float_window(window);
             self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
         }
@@ -1259,6 +2645,12 @@ impl<'a> Manager<'a> {
                         self.bar_manager
                             .set_workspace_urgent(call_wrapper, mon_ind, ws_ind, state)
                     })?;
+                    if state.push_urgent(win) {
+                        self.draw_urgent_on_all(call_wrapper, state)?;
+                    }
+                    for mon_ind in 0..state.monitors.len() {
+                        self.reveal_autohide_bar(call_wrapper, mon_ind, state)?;
+                    }
                     if let Some(mw) = state.workspaces.get_managed_win_mut(win) {
                         if !mw.properties.net_wm_state.demands_attention {
                             mw.properties.net_wm_state.demands_attention = true;
@@ -1282,7 +2674,10 @@ impl<'a> Manager<'a> {
         window: Window,
         state: &mut State,
     ) -> Result<()> {
-        Self::restore_normal_border(call_wrapper, window, state)?;
+        Self::clear_urgent_border(call_wrapper, window, state)?;
+        if state.remove_urgent(window) {
+            self.draw_urgent_on_all(call_wrapper, state)?;
+        }
         if let Some((ws_ind, changed)) = state.workspaces.set_wants_focus(window, false) {
             if changed {
                 let skip = if let Some(mon_ind) = state.find_monitor_hosting_workspace(ws_ind) {
@@ -1332,6 +2727,11 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
+    fn draw_urgent_on_all(&self, call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        (0..state.monitors.len())
+            .try_for_each(|mon_ind| self.bar_manager.draw_urgent(call_wrapper, mon_ind, state))
+    }
+
     // Resets borders and focuses root
     fn focus_root_on_mon(
         &self,
@@ -1356,6 +2756,7 @@ impl<'a> Manager<'a> {
                 state.monitors[mon_ind].hosted_workspace,
                 state,
             )?;
+            call_wrapper.set_desktop_properties(state)?;
         }
         call_wrapper.take_focus(
             state.screen.root,
@@ -1363,7 +2764,8 @@ impl<'a> Manager<'a> {
             FocusStyle::Passive,
             state,
         )?;
-        Self::conditional_grab_pointer(call_wrapper, state)?;
+        call_wrapper.install_default_colormap(state)?;
+        Self::grab_pointer_for_focus(call_wrapper, state)?;
         self.update_current_window_title_and_redraw(
             call_wrapper,
             mon_ind,
@@ -1411,70 +2813,53 @@ impl<'a> Manager<'a> {
         mon_ind: usize,
         win: Window,
         state: &mut State,
-    ) -> Result<()> {
-        if let Some(last_input_focus) = state.input_focus {
-            Self::restore_normal_border(call_wrapper, last_input_focus, state)?;
-            self.do_focus_window(call_wrapper, mon_ind, win, state)
-        } else {
-            self.do_focus_window(call_wrapper, mon_ind, win, state)
-        }
-    }
-
-    fn do_focus_window(
-        &self,
-        call_wrapper: &mut CallWrapper,
-        mon_ind: usize,
-        win: Window,
-        state: &mut State,
     ) -> Result<()> {
         if state.drag_window.is_some() {
             // Never refocus and mess with the pointer while dragging
             return Ok(());
         }
         let pointer_pos = call_wrapper.query_pointer(state)?;
+        // Everything attached to the window is raised above it, with focus going to the topmost
+        let transients = state.workspaces.transient_stacking_order(win);
         // ... borrow checker.
-        let (focus_target, focus_style, name) =
-            if let Some((focus_win, focus_style, focus_name, floating)) = state
-                .workspaces
-                .find_all_attached_managed(win)
-                .map(|attached| {
-                    let focus = &attached[0];
-                    let floating = attached
-                        .iter()
-                        .map(|mw| mw.window)
-                        .collect::<heapless::Vec<Window, WS_WINDOW_LIMIT>>();
-                    (
-                        focus.window,
-                        focus.focus_style,
-                        focus.properties.name.get_cloned(),
-                        floating,
-                    )
-                })
+        let top_transient = transients
+            .last()
+            .and_then(|top| state.workspaces.get_managed_win(*top))
+            .map(|focus| {
+                (
+                    focus.window,
+                    focus.focus_style,
+                    focus.properties.name.get_cloned(),
+                )
+            });
+        let (focus_target, focus_style, name) = if let Some(top_transient) = top_transient {
+            Drawer::send_floating_to_top(call_wrapper, transients, state)?;
+            top_transient
+        } else if let Some(mw) = state.workspaces.get_managed_win(win) {
+            (mw.window, mw.focus_style, mw.properties.name.get_cloned())
+        } else {
+            pgwm_utils::debug!("Focusing unmanaged window {win}");
+            // Unmanaged window
+            if let Ok(properties) = call_wrapper
+                .get_window_properties(win)?
+                .await_properties(call_wrapper)
             {
-                Drawer::send_floating_to_top(call_wrapper, floating, state)?;
-                (focus_win, focus_style, focus_name)
-            } else if let Some(mw) = state.workspaces.get_managed_win(win) {
-                (mw.window, mw.focus_style, mw.properties.name.get_cloned())
+                (
+                    win,
+                    Self::deduce_focus_style(&properties),
+                    properties.name.get_cloned(),
+                )
             } else {
-                pgwm_utils::debug!("Focusing unmanaged window {win}");
-                // Unmanaged window
-                if let Ok(properties) = call_wrapper
-                    .get_window_properties(win)?
-                    .await_properties(call_wrapper)
-                {
-                    (
-                        win,
-                        Self::deduce_focus_style(&properties),
-                        properties.name.get_cloned(),
-                    )
-                } else {
-                    pgwm_utils::debug!("Could not focus unmanaged window {win}");
-                    pointer_pos.forget(&mut call_wrapper.xcb_state);
-                    return Ok(());
-                }
-            };
+                pgwm_utils::debug!("Could not focus unmanaged window {win}");
+                pointer_pos.forget(&mut call_wrapper.xcb_state);
+                return Ok(());
+            }
+        };
+        if !Self::shift_focus(call_wrapper, win, focus_target, focus_style, state)? {
+            pointer_pos.forget(&mut call_wrapper.xcb_state);
+            return Ok(());
+        }
         self.make_window_not_urgent(call_wrapper, win, state)?;
-        Self::highlight_border(call_wrapper, win, state)?; // Highlighting the base window even if a top level transient is focused
         if let Some(old_focused_mon) = state.update_focused_mon(mon_ind) {
             pgwm_utils::debug!("Switched focus from {} to {}", old_focused_mon, mon_ind);
             self.bar_manager.set_workspace_selected_not_focused(
@@ -1489,14 +2874,12 @@ impl<'a> Manager<'a> {
                 state.monitors[mon_ind].hosted_workspace,
                 state,
             )?;
+            call_wrapper.set_desktop_properties(state)?;
         }
 
         self.redraw_if_tabbed(call_wrapper, mon_ind, focus_target, state)?;
         state.monitors[mon_ind].last_focus.replace(focus_target);
-
-        state.input_focus.replace(win);
-        pgwm_utils::debug!("Taking focus for {win}");
-        call_wrapper.take_focus(state.screen.root, win, focus_style, state)?;
+        Self::hide_autohide_bar(call_wrapper, mon_ind, state)?;
         pgwm_utils::debug!("Getting pointer position");
         let pointer_pos =
             pointer_pos.reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
@@ -1506,6 +2889,57 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
+    /// Moves keyboard focus and the border highlight from the previously focused window to `win`,
+    /// `focus_target` is `win` or its topmost transient.
+    /// Windows that take no input are only raised, returns whether focus moved
+    fn shift_focus<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        win: Window,
+        focus_target: Window,
+        focus_style: FocusStyle,
+        state: &mut State,
+    ) -> Result<bool> {
+        if focus_style == FocusStyle::NoInput {
+            // Never takes keyboard input, raise it so that it's seen but leave the keyboard,
+            // the highlight, and the active window with whatever had them before
+            pgwm_utils::debug!("Raising NoInput win {win} without taking focus");
+            call_wrapper.push_window_to_top(focus_target, state)?;
+            return Ok(false);
+        }
+        if let Some(last_input_focus) = state.input_focus {
+            Self::restore_normal_border(call_wrapper, last_input_focus, state)?;
+        }
+        // Highlighting the base window even if a top level transient is focused
+        Self::highlight_border(call_wrapper, win, state)?;
+        let previous_focus = state.input_focus.replace(win);
+        pgwm_utils::debug!("Taking focus for {win}");
+        call_wrapper.take_focus(state.screen.root, win, focus_style, state)?;
+        if focus_style == FocusStyle::GloballyActive {
+            // Free to decline, some never take focus and leave the keyboard going nowhere
+            state.request_focus_verification(
+                win,
+                previous_focus.filter(|prev| *prev != win),
+                tiny_std::time::Instant::now(),
+            );
+        }
+        Self::install_focused_colormaps(call_wrapper, win, state)?;
+        Ok(true)
+    }
+
+    /// Install the colormaps of the window that has input focus, legacy clients with private
+    /// colormaps display in the wrong colors otherwise
+    fn install_focused_colormaps<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        win: Window,
+        state: &State,
+    ) -> Result<()> {
+        let colormap_windows = state
+            .workspaces
+            .get_managed_win(win)
+            .map_or(&[][..], |mw| mw.properties.colormap_windows.as_slice());
+        call_wrapper.install_colormaps(win, colormap_windows)
+    }
+
     fn redraw_if_tabbed(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -1531,30 +2965,123 @@ impl<'a> Manager<'a> {
         }
     }
 
-    fn highlight_border(
-        call_wrapper: &mut CallWrapper,
+    /// Replaces the urgent border with the one the window would have without it,
+    /// highlighted if the window has input focus
+    fn clear_urgent_border<X: ManagerCalls>(
+        call_wrapper: &mut X,
         window: Window,
         state: &mut State,
     ) -> Result<()> {
-        call_wrapper.change_border_color(window, state.colors.window_border_highlighted().pixel)?;
-        Ok(())
+        if state.input_focus == Some(window) {
+            Self::highlight_border(call_wrapper, window, state)
+        } else {
+            Self::restore_normal_border(call_wrapper, window, state)
+        }
     }
 
-    fn restore_normal_border(
-        call_wrapper: &mut CallWrapper,
+    fn highlight_border<X: ManagerCalls>(
+        call_wrapper: &mut X,
         window: Window,
         state: &mut State,
     ) -> Result<()> {
-        call_wrapper.change_border_color(window, state.colors.window_border().pixel)?;
-        Ok(())
+        let (_, highlighted) = Self::window_borders(window, state);
+        Self::set_border(call_wrapper, window, highlighted, state)
     }
 
-    fn set_border_urgent(
-        call_wrapper: &mut CallWrapper,
+    fn restore_normal_border<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        window: Window,
+        state: &mut State,
+    ) -> Result<()> {
+        let (normal, _) = Self::window_borders(window, state);
+        Self::set_border(call_wrapper, window, normal, state)
+    }
+
+    fn window_borders(window: Window, state: &State) -> (Color, Color) {
+        let class = state
+            .workspaces
+            .get_managed_win(window)
+            .map_or(&[][..], |mw| mw.properties.class.as_slice());
+        state.colors.window_borders(class)
+    }
+
+    fn set_border_urgent<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        window: Window,
+        state: &mut State,
+    ) -> Result<()> {
+        let urgent = state.colors.window_border_urgent();
+        Self::set_border(call_wrapper, window, urgent, state)
+    }
+
+    /// Changes a window's border color, through a transition from the color it has if
+    /// [`BORDER_TRANSITION_FRAMES`] is set
+    fn set_border<X: ManagerCalls>(
+        call_wrapper: &mut X,
         window: Window,
+        color: Color,
+        state: &mut State,
+    ) -> Result<()> {
+        if BORDER_TRANSITION_FRAMES == 0 {
+            return call_wrapper.change_border_color(window, color.pixel);
+        }
+        match state.border_transitions.shown(window) {
+            Some(shown) if shown.bgra8 != color.bgra8 => {
+                let frames = Self::border_frames(call_wrapper, shown, color, state)?;
+                state.border_transitions.start(
+                    window,
+                    shown,
+                    frames,
+                    tiny_std::time::Instant::now(),
+                    core::time::Duration::from_millis(BORDER_TRANSITION_FRAME_MS),
+                );
+                Ok(())
+            }
+            // Nothing to transition from, unmanaged windows aren't tracked since they're never forgotten
+            _ => {
+                call_wrapper.change_border_color(window, color.pixel)?;
+                if state.workspaces.get_managed_win(window).is_some() {
+                    state.border_transitions.set(window, color);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The colors of a border transition, allocated unless the same transition has been shown recently
+    fn border_frames<X: ManagerCalls>(
+        call_wrapper: &mut X,
+        from: Color,
+        to: Color,
+        state: &mut State,
+    ) -> Result<Vec<Color>> {
+        if let Some(frames) = state.border_gradients.get(from.bgra8, to.bgra8) {
+            return Ok(frames.to_vec());
+        }
+        let frames = call_wrapper.alloc_gradient(
+            state.screen.default_colormap,
+            from,
+            to,
+            BORDER_TRANSITION_FRAMES,
+        )?;
+        state
+            .border_gradients
+            .insert(from.bgra8, to.bgra8, frames.clone());
+        Ok(frames)
+    }
+
+    /// Sets the next border color of running border transitions if it's due
+    pub(crate) fn draw_due_border_frames<X: ManagerCalls>(
+        call_wrapper: &mut X,
         state: &mut State,
     ) -> Result<()> {
-        call_wrapper.change_border_color(window, state.colors.window_border_urgent().pixel)?;
+        let frame_time = core::time::Duration::from_millis(BORDER_TRANSITION_FRAME_MS);
+        for (window, pixel) in state
+            .border_transitions
+            .take_due_frames(tiny_std::time::Instant::now(), frame_time)
+        {
+            call_wrapper.change_border_color(window, pixel)?;
+        }
         Ok(())
     }
 
@@ -1564,15 +3091,29 @@ impl<'a> Manager<'a> {
         query_pointer_reply: QueryPointerReply,
         state: &mut State,
     ) -> Result<()> {
-        let pointer_on_window = query_pointer_reply.child.0 == window;
+        let pointer_on_window = call_wrapper.client_of(query_pointer_reply.child.0) == window;
         if pointer_on_window {
             Self::conditional_ungrab_pointer(call_wrapper, state)?;
         } else {
-            Self::conditional_grab_pointer(call_wrapper, state)?;
+            Self::grab_pointer_for_focus(call_wrapper, state)?;
         }
         Ok(())
     }
 
+    fn reload_resource_colors(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        crate::debug!("Got state invalidation because the X resources changed");
+        let resource_db = load_resource_db(call_wrapper)?;
+        state.colors = alloc_colors(
+            call_wrapper,
+            state.screen.default_colormap,
+            &with_resource_colors(&state.cfg.colors, &resource_db),
+        )?;
+        for mon in 0..state.monitors.len() {
+            Drawer::undraw(call_wrapper, mon, state)?;
+        }
+        Err(Error::StateInvalidated)
+    }
+
     pub(crate) fn handle_property_notify(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -1580,10 +3121,21 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         state.last_timestamp = event.time;
-        // Wm's own events
         if event.window == state.screen.root {
+            // Resources reloaded through `xrdb`
+            if event.atom == AtomEnum::RESOURCE_MANAGER.0 && !XRESOURCE_COLORS.is_empty() {
+                return Self::reload_resource_colors(call_wrapper, state);
+            }
+            if call_wrapper
+                .resolve_atom(event.atom)
+                .is_some_and(|resolved| resolved.intern_atom == SupportedAtom::PgwmCommand)
+            {
+                return self.exec_ipc_command(call_wrapper, state);
+            }
+            // Wm's own events
             return Ok(());
         }
+        call_wrapper.invalidate_cached_property(event.window, event.atom);
         let Some(resolved) = call_wrapper.resolve_atom(event.atom) else {
             pgwm_utils::debug!(
                 "Got unsupported atom on property change {:?}",
@@ -1710,6 +3262,35 @@ impl<'a> Manager<'a> {
                     mw.properties.wm_state = wm_state;
                 }
             }
+            SupportedAtom::GtkFrameExtents => {
+                let gtk_frame_extents = call_wrapper
+                    .get_gtk_frame_extents(event.window)?
+                    .await_extents(call_wrapper)?;
+                let changed = if let Some(mw) = state.workspaces.get_managed_win_mut(event.window) {
+                    let changed = mw.properties.gtk_frame_extents != gtk_frame_extents;
+                    mw.properties.gtk_frame_extents = gtk_frame_extents;
+                    changed
+                } else {
+                    false
+                };
+                if changed {
+                    if let Some(mon_ind) = state.find_monitor_index_of_window(event.window) {
+                        self.drawer.draw_on(call_wrapper, mon_ind, false, state)?;
+                    }
+                }
+            }
+            SupportedAtom::WmColormapWindows => {
+                let colormap_windows = call_wrapper
+                    .get_colormap_windows(event.window)?
+                    .await_windows(call_wrapper)?;
+                call_wrapper.select_colormap_changes(event.window, &colormap_windows)?;
+                if let Some(mw) = state.workspaces.get_managed_win_mut(event.window) {
+                    mw.properties.colormap_windows = colormap_windows;
+                }
+                if state.input_focus == Some(event.window) {
+                    Self::install_focused_colormaps(call_wrapper, event.window, state)?;
+                }
+            }
             SupportedAtom::NetWmWindowType => {
                 let window_types = call_wrapper.get_window_types(event.window)?;
                 let (new_float, old_float) =
@@ -1794,10 +3375,22 @@ impl<'a> Manager<'a> {
         new_name: heapless::String<_WM_NAME_LIMIT>,
         state: &mut State,
     ) -> Result<()> {
-        state.monitors[mon_ind]
-            .bar_geometry
-            .window_title_section
-            .display = new_name;
+        let section = &mut state.monitors[mon_ind].bar_geometry.window_title_section;
+        if section.display == new_name {
+            // Already drawn, full redraws go through the bar manager directly
+            return Ok(());
+        }
+        section.display = new_name;
+        if state.window_picker.is_some()
+            || state.monitors[mon_ind]
+                .bar_geometry
+                .shortcuts
+                .hovered
+                .is_some()
+        {
+            // Drawn when the picker closes or the shortcut is no longer hovered
+            return Ok(());
+        }
         self.bar_manager
             .draw_focused_window_title(call_wrapper, mon_ind, state)
     }
@@ -1810,8 +3403,9 @@ impl<'a> Manager<'a> {
         state: &mut State,
     ) -> Result<()> {
         if let Some(mapped) = state.workspaces.find_ws_containing_window(win) {
+            let set = state.workspaces.set_of(mapped);
             for class in class_names {
-                if let Some(ind) = state.workspaces.find_ws_for_window_class_name(class) {
+                if let Some(ind) = state.workspaces.find_ws_for_window_class_name(class, set) {
                     if mapped != ind {
                         pgwm_utils::debug!("Remapping from {} to {} on prop change", mapped, ind);
                         // We know it's present because of the above check
@@ -1820,7 +3414,7 @@ impl<'a> Manager<'a> {
                             .into_option()
                             .unwrap();
                         let focus_style = removed.focus_style;
-                        call_wrapper.send_unmap(win, state)?;
+                        Drawer::hide(call_wrapper, win, state)?;
                         state.workspaces.add_child_to_ws(
                             win,
                             ind,
@@ -1859,6 +3453,13 @@ impl<'a> Manager<'a> {
         recv_mon_ind: usize,
         state: &mut State,
     ) -> Result<()> {
+        // With a set of workspaces per monitor a workspace is only shown on the monitor owning it
+        let Some(recv_mon_ind) = state.monitor_for_workspace(ws_ind, recv_mon_ind) else {
+            pgwm_utils::debug!("Monitor owning workspace {ws_ind} is gone, skipping.");
+            return Ok(());
+        };
+        #[cfg(feature = "animations")]
+        Self::finish_workspace_slide(call_wrapper, state)?;
         let recv_prev_ws = state.monitors[recv_mon_ind].hosted_workspace;
         pgwm_utils::debug!(
             "Mapping workspace {} to main window {}",
@@ -1876,8 +3477,17 @@ impl<'a> Manager<'a> {
                 recv_prev_ws,
                 state,
             )?;
-            state.monitors[recv_mon_ind].hosted_workspace = ws_ind;
-            state.monitors[send_mon_ind].hosted_workspace = recv_prev_ws;
+            // Pinned windows stay on their monitors, joining the workspace swapped in
+            state
+                .workspaces
+                .move_pinned(recv_prev_ws, ws_ind, recv_mon_ind)?;
+            state
+                .workspaces
+                .move_pinned(ws_ind, recv_prev_ws, send_mon_ind)?;
+            state.monitors[recv_mon_ind].host_workspace(ws_ind);
+            state.monitors[send_mon_ind].host_workspace(recv_prev_ws);
+            Self::paint_workspace_background(call_wrapper, recv_mon_ind, state)?;
+            Self::paint_workspace_background(call_wrapper, send_mon_ind, state)?;
             self.bar_manager
                 .set_workspace_focused(call_wrapper, recv_mon_ind, ws_ind, state)?;
             self.bar_manager.set_workspace_selected_not_focused(
@@ -1895,8 +3505,16 @@ impl<'a> Manager<'a> {
             state.monitors[recv_mon_ind].last_focus = focus_to_transfer;
             self.focus_mon(call_wrapper, recv_mon_ind, state)?;
         } else {
+            // Moved before undrawing so that they're never unmapped
+            #[cfg_attr(not(feature = "animations"), allow(unused_variables))]
+            let pinned = state
+                .workspaces
+                .move_pinned(recv_prev_ws, ws_ind, recv_mon_ind)?;
             Drawer::undraw(call_wrapper, recv_mon_ind, state)?;
-            state.monitors[recv_mon_ind].hosted_workspace = ws_ind;
+            state.monitors[recv_mon_ind].host_workspace(ws_ind);
+            #[cfg(feature = "animations")]
+            Self::start_workspace_slide(recv_mon_ind, ws_ind > recv_prev_ws, pinned, state);
+            Self::paint_workspace_background(call_wrapper, recv_mon_ind, state)?;
             let mon = &mut state.monitors[recv_mon_ind];
             mon.last_focus.take();
             self.drawer
@@ -1912,38 +3530,60 @@ impl<'a> Manager<'a> {
             )?;
             self.focus_mon(call_wrapper, recv_mon_ind, state)?;
         }
+        self.reveal_autohide_bar(call_wrapper, recv_mon_ind, state)?;
+        call_wrapper.set_desktop_properties(state)?;
         Ok(())
     }
 
+    /// The workspace that a window's class was last placed on, with its floating geometry if it was
+    /// left floating, or else the workspace its class is mapped to
     fn map_window_class_to_workspace(
         call_wrapper: &mut CallWrapper,
         win: Window,
+        set: usize,
         workspaces: &Workspaces,
-    ) -> Result<Option<usize>> {
+    ) -> Result<Option<(usize, Option<FloatingGeometry>)>> {
         let wm_classes = call_wrapper
             .get_class_names(win)?
             .await_class_names(call_wrapper)?;
         pgwm_utils::debug!("WM_CLASS {:?}", wm_classes);
         if let Some(wm_classes) = wm_classes {
+            if let Some(placement) = wm_classes
+                .last()
+                .and_then(|class| workspaces.find_placement_for_window_class_name(class, set))
+            {
+                return Ok(Some(placement));
+            }
             for class in wm_classes {
-                if let Some(ind) = workspaces.find_ws_for_window_class_name(&class) {
-                    return Ok(Some(ind));
+                if let Some(ind) = workspaces.find_ws_for_window_class_name(&class, set) {
+                    return Ok(Some((ind, None)));
                 }
             }
         }
         Ok(None)
     }
 
+    /// Remembers where the user put a window for the next window of its class,
+    /// see [`pgwm_core::config::PLACEMENT_HISTORY_SIZE`]
+    fn remember_placement(window: Window, floating: Option<FloatingGeometry>, state: &mut State) {
+        if state.workspaces.remember_placement(window, floating) {
+            if let Err(_e) = state.workspaces.placement_history().save() {
+                pgwm_utils::debug!("Failed to save placement history {_e}");
+            }
+        }
+    }
+
     #[cfg(feature = "status-bar")]
     pub(crate) fn draw_status(
         &self,
         call_wrapper: &mut CallWrapper,
         content: heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>,
         content_ind: usize,
+        alert: bool,
         state: &mut State,
     ) -> Result<()> {
         self.bar_manager
-            .update_status(call_wrapper, content, content_ind, state)
+            .update_status(call_wrapper, content, content_ind, alert, state)
     }
 
     fn unmanage(
@@ -1952,17 +3592,47 @@ impl<'a> Manager<'a> {
         window: Window,
         state: &mut State,
     ) -> Result<()> {
+        Self::forget_window(call_wrapper, window, state);
+        #[cfg(feature = "frames")]
+        call_wrapper.unframe(window, state)?;
+        let mon_ind = state.find_monitor_index_of_window(window);
         if self
             .remove_win_from_state_then_redraw_if_tiled(call_wrapper, window, state)?
             .into_option()
             .is_some()
         {
-            let windows = state.workspaces.get_all_managed_windows();
-            call_wrapper.update_client_list(windows.as_slice(), state)?;
+            Self::publish_client_list(call_wrapper, state)?;
+            if let Some(mon_ind) = mon_ind {
+                if let Some(next_ws) =
+                    state.workspace_after_last_closed(mon_ind, ON_LAST_WINDOW_CLOSED)
+                {
+                    pgwm_utils::debug!("Last window closed on mon {mon_ind}, showing {next_ws}");
+                    self.toggle_workspace(call_wrapper, next_ws, mon_ind, state)?;
+                }
+            }
+        }
+        if state.remove_urgent(window) {
+            self.draw_urgent_on_all(call_wrapper, state)?;
         }
         Ok(())
     }
 
+    /// Drops what's kept about a window that's being unmanaged, apart from its place in a workspace
+    fn forget_window<X: ManagerCalls>(call_wrapper: &mut X, window: Window, state: &mut State) {
+        state.workspaces.unmark(window);
+        state.border_transitions.forget(window);
+        if let Some(slide) = state.workspace_slide.as_mut() {
+            slide.forget(window);
+        }
+        call_wrapper.forget_cached_properties(window);
+    }
+
+    /// Sets `_NET_CLIENT_LIST` to the windows that are managed
+    fn publish_client_list<X: ManagerCalls>(call_wrapper: &mut X, state: &State) -> Result<()> {
+        let windows = state.workspaces.get_all_managed_windows();
+        call_wrapper.update_client_list(windows.as_slice(), state)
+    }
+
     fn remove_win_from_state_then_redraw_if_tiled(
         &self,
         call_wrapper: &mut CallWrapper,
@@ -2096,6 +3766,16 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
+    /// Grabs the pointer to focus whatever it moves to after a focus change, unless a popup is open
+    /// on the focused monitor. Grabbing would take the pointer from a menu and close it.
+    fn grab_pointer_for_focus(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+        if state.popup_open_on(state.focused_mon) {
+            pgwm_utils::debug!("Popup open, not grabbing the pointer");
+            return Ok(());
+        }
+        Self::conditional_grab_pointer(call_wrapper, state)
+    }
+
     fn conditional_ungrab_pointer(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
         if state.pointer_grabbed {
             call_wrapper.ungrab_pointer()?;
@@ -2104,6 +3784,19 @@ impl<'a> Manager<'a> {
         Ok(())
     }
 
+    /// Runs the same cleanup as `Action::Quit` if the WM was asked to terminate by a signal
+    pub(crate) fn exec_requested_shutdown(
+        call_wrapper: &mut CallWrapper,
+        state: &mut State,
+    ) -> Result<()> {
+        if crate::signal::shutdown_requested() {
+            pgwm_utils::debug!("Shutting down on signal");
+            Self::cleanup(call_wrapper, state)?;
+            return Err(Error::GracefulShutdown);
+        }
+        Ok(())
+    }
+
     fn cleanup(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
         let managed = state.workspaces.get_all_managed_windows();
         for win in managed {
@@ -2183,22 +3876,10 @@ fn float_status(properties: &WindowProperties, root: Window) -> WindowFloatDeduc
     WindowFloatDeduction::Docked { parent }
 }
 
-fn toggle_tabbed(mon_ind: usize, ws_ind: usize, state: &mut State) -> Result<bool> {
-    state.workspaces.set_draw_mode(ws_ind, Mode::Tabbed(0));
-    if let Some(should_focus) = state.find_appropriate_ws_focus(mon_ind, ws_ind) {
-        return Ok(state
-            .workspaces
-            .switch_tab_focus_window(ws_ind, should_focus)?
-            .filter(|b| *b)
-            .is_some());
-    }
-    Ok(false)
-}
-
 struct ScanProperties {
     window: Window,
     attributes: FixedCookie<GetWindowAttributesReply, 44>,
-    transient_cookie: SingleCardCookie,
+    transient_cookie: TransientForCookie,
     wm_state: WmStateCookie,
     prop_cookie: WindowPropertiesCookie,
 }
@@ -2232,3 +3913,346 @@ enum InputSource {
     Mouse(i16, i16),
     Keyboard,
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::VecDeque;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use xcb_rust_protocol::proto::xproto::{Colormap, Window};
+
+    use pgwm_core::colors::Color;
+    use pgwm_core::geometry::Dimensions;
+    use pgwm_core::state::properties::{NetWmState, WmState};
+    use pgwm_core::state::test_support::{create_base_state, test_properties};
+    use pgwm_core::state::workspace::{ArrangeKind, FocusStyle};
+    use pgwm_core::state::State;
+
+    use crate::error::Result;
+    use crate::manager::Manager;
+    use crate::x11::manager_calls::ManagerCalls;
+
+    #[derive(Debug, Eq, PartialEq)]
+    enum Call {
+        TakeFocus(Window),
+        Raise(Window),
+        InstallColormaps(Window),
+        Border(Window, u32),
+        GetAttributes(Window),
+        SetState(Window, WmState),
+        ForgetProperties(Window),
+        ClientList(Vec<Window>),
+        NetWmState(Window, NetWmState),
+        GetGeometry(Window),
+        Resize(Window, u32, u32),
+    }
+
+    /// What the server answers, in the order the calls that wait for a reply are made
+    #[derive(Debug)]
+    enum Reply {
+        OverrideRedirect(Option<bool>),
+        Dimensions(Dimensions),
+    }
+
+    /// Records the calls instead of making them, answering with scripted replies
+    #[derive(Default)]
+    struct MockCalls {
+        calls: Vec<Call>,
+        replies: VecDeque<Reply>,
+    }
+
+    impl MockCalls {
+        fn with_replies(replies: Vec<Reply>) -> Self {
+            Self {
+                calls: Vec::new(),
+                replies: replies.into(),
+            }
+        }
+
+        fn next_reply(&mut self) -> Reply {
+            self.replies
+                .pop_front()
+                .expect("A call waited for a reply that wasn't scripted")
+        }
+    }
+
+    impl ManagerCalls for MockCalls {
+        fn take_focus(
+            &mut self,
+            _root: Window,
+            target: Window,
+            _focus_style: FocusStyle,
+            _state: &State,
+        ) -> Result<()> {
+            self.calls.push(Call::TakeFocus(target));
+            Ok(())
+        }
+
+        fn push_window_to_top(&mut self, window: Window, _state: &mut State) -> Result<()> {
+            self.calls.push(Call::Raise(window));
+            Ok(())
+        }
+
+        fn install_colormaps(
+            &mut self,
+            toplevel: Window,
+            _colormap_windows: &[Window],
+        ) -> Result<()> {
+            self.calls.push(Call::InstallColormaps(toplevel));
+            Ok(())
+        }
+
+        fn change_border_color(&mut self, window: Window, pixel: u32) -> Result<()> {
+            self.calls.push(Call::Border(window, pixel));
+            Ok(())
+        }
+
+        fn alloc_gradient(
+            &mut self,
+            _color_map: Colormap,
+            _from: Color,
+            to: Color,
+            steps: usize,
+        ) -> Result<Vec<Color>> {
+            Ok(vec![to; steps])
+        }
+
+        fn is_override_redirect(&mut self, window: Window) -> Result<Option<bool>> {
+            self.calls.push(Call::GetAttributes(window));
+            match self.next_reply() {
+                Reply::OverrideRedirect(override_redirect) => Ok(override_redirect),
+                reply => panic!("Expected attributes, got {reply:?}"),
+            }
+        }
+
+        fn set_state(&mut self, window: Window, wm_state: WmState) -> Result<()> {
+            self.calls.push(Call::SetState(window, wm_state));
+            Ok(())
+        }
+
+        fn forget_cached_properties(&mut self, window: Window) {
+            self.calls.push(Call::ForgetProperties(window));
+        }
+
+        fn update_client_list(&mut self, managed: &[Window], _state: &State) -> Result<()> {
+            self.calls.push(Call::ClientList(managed.to_vec()));
+            Ok(())
+        }
+
+        fn set_net_wm_state(&mut self, window: Window, net_wm_state: NetWmState) -> Result<()> {
+            self.calls.push(Call::NetWmState(window, net_wm_state));
+            Ok(())
+        }
+
+        fn dimensions(&mut self, window: Window) -> Result<Dimensions> {
+            self.calls.push(Call::GetGeometry(window));
+            match self.next_reply() {
+                Reply::Dimensions(dimensions) => Ok(dimensions),
+                reply => panic!("Expected dimensions, got {reply:?}"),
+            }
+        }
+
+        fn resize_window(
+            &mut self,
+            window: Window,
+            height: u32,
+            width: u32,
+            _state: &mut State,
+        ) -> Result<()> {
+            self.calls.push(Call::Resize(window, height, width));
+            Ok(())
+        }
+    }
+
+    fn manage(state: &mut State, windows: &[Window]) {
+        let properties = test_properties();
+        for window in windows {
+            state
+                .workspaces
+                .add_child_to_ws(
+                    *window,
+                    0,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+    }
+
+    /// The shared fixture with every color's pixel set to its index, so that borders can be told apart
+    fn create_state() -> State {
+        let mut state = create_base_state();
+        for (ind, color) in state.colors.inner.iter_mut().enumerate() {
+            color.pixel = ind as u32;
+        }
+        state
+    }
+
+    #[test]
+    fn shifts_focus_and_highlight_unless_window_takes_no_input() {
+        let mut state = create_state();
+        let properties = test_properties();
+        for (window, focus_style) in [
+            (15, FocusStyle::Passive),
+            (16, FocusStyle::GloballyActive),
+            (17, FocusStyle::NoInput),
+        ] {
+            state
+                .workspaces
+                .add_child_to_ws(window, 0, ArrangeKind::NoFloat, focus_style, &properties)
+                .unwrap();
+        }
+        let normal = state.colors.window_border().pixel;
+        let highlighted = state.colors.window_border_highlighted().pixel;
+
+        let mut calls = MockCalls::default();
+        assert!(Manager::shift_focus(&mut calls, 15, 15, FocusStyle::Passive, &mut state).unwrap());
+        assert_eq!(
+            vec![
+                Call::Border(15, highlighted),
+                Call::TakeFocus(15),
+                Call::InstallColormaps(15),
+            ],
+            calls.calls
+        );
+        assert_eq!(Some(15), state.input_focus);
+        assert!(state.pending_focus.is_none());
+
+        // Globally active windows may decline, falling back to the previous focus is checked later
+        let mut calls = MockCalls::default();
+        assert!(
+            Manager::shift_focus(&mut calls, 16, 16, FocusStyle::GloballyActive, &mut state)
+                .unwrap()
+        );
+        assert_eq!(
+            vec![
+                Call::Border(15, normal),
+                Call::Border(16, highlighted),
+                Call::TakeFocus(16),
+                Call::InstallColormaps(16),
+            ],
+            calls.calls
+        );
+        assert_eq!(Some(16), state.input_focus);
+        let pending = state.pending_focus.as_ref().unwrap();
+        assert_eq!(16, pending.window);
+        assert_eq!(Some(15), pending.previous);
+
+        // Only raised, keyboard focus and the highlight stay where they were
+        let mut calls = MockCalls::default();
+        assert!(
+            !Manager::shift_focus(&mut calls, 17, 17, FocusStyle::NoInput, &mut state).unwrap()
+        );
+        assert_eq!(vec![Call::Raise(17)], calls.calls);
+        assert_eq!(Some(16), state.input_focus);
+    }
+
+    #[test]
+    fn clears_urgent_border_to_highlight_only_when_focused() {
+        let mut state = create_state();
+        state.input_focus = Some(15);
+        let normal = state.colors.window_border().pixel;
+        let highlighted = state.colors.window_border_highlighted().pixel;
+        let mut calls = MockCalls::default();
+        Manager::clear_urgent_border(&mut calls, 15, &mut state).unwrap();
+        Manager::clear_urgent_border(&mut calls, 16, &mut state).unwrap();
+        assert_eq!(
+            vec![Call::Border(15, highlighted), Call::Border(16, normal)],
+            calls.calls
+        );
+    }
+
+    #[test]
+    fn manages_mapped_windows_unless_override_redirect_or_gone() {
+        let mut calls = MockCalls::with_replies(vec![
+            Reply::OverrideRedirect(Some(false)),
+            Reply::OverrideRedirect(Some(true)),
+            Reply::OverrideRedirect(None),
+        ]);
+        assert!(Manager::accept_map_request(&mut calls, 15).unwrap());
+        assert!(!Manager::accept_map_request(&mut calls, 16).unwrap());
+        assert!(!Manager::accept_map_request(&mut calls, 17).unwrap());
+        assert_eq!(
+            vec![
+                Call::GetAttributes(15),
+                Call::SetState(15, WmState::Normal),
+                Call::GetAttributes(16),
+                Call::GetAttributes(17),
+            ],
+            calls.calls
+        );
+        assert!(calls.replies.is_empty());
+    }
+
+    #[test]
+    fn forgets_unmapped_windows_and_publishes_the_rest() {
+        let mut state = create_state();
+        manage(&mut state, &[15, 16]);
+        assert!(state.workspaces.toggle_mark(15, "a"));
+        let highlighted = state.colors.window_border_highlighted();
+        state.border_transitions.set(15, highlighted);
+
+        let mut calls = MockCalls::default();
+        Manager::forget_window(&mut calls, 15, &mut state);
+        assert!(state.workspaces.get_mark(15).is_none());
+        assert!(state.border_transitions.shown(15).is_none());
+        state.workspaces.delete_child_from_ws(15);
+        Manager::publish_client_list(&mut calls, &state).unwrap();
+        assert_eq!(
+            vec![Call::ForgetProperties(15), Call::ClientList(vec![16])],
+            calls.calls
+        );
+    }
+
+    #[test]
+    fn updates_net_wm_state_of_managed_windows_from_client_messages() {
+        let mut state = create_state();
+        manage(&mut state, &[15]);
+        let mut calls = MockCalls::default();
+        let skip_taskbar: fn(&mut NetWmState) -> &mut bool =
+            |net_wm_state| &mut net_wm_state.skip_taskbar;
+        // Add, toggle, then remove
+        for action in [1, 2, 0] {
+            assert!(
+                Manager::update_net_wm_state(&mut calls, 15, action, skip_taskbar, &mut state)
+                    .unwrap()
+            );
+        }
+        let added = NetWmState {
+            skip_taskbar: true,
+            ..NetWmState::default()
+        };
+        assert_eq!(
+            vec![
+                Call::NetWmState(15, added),
+                Call::NetWmState(15, NetWmState::default()),
+                Call::NetWmState(15, NetWmState::default()),
+            ],
+            calls.calls
+        );
+
+        let mut calls = MockCalls::default();
+        assert!(
+            !Manager::update_net_wm_state(&mut calls, 16, 1, skip_taskbar, &mut state).unwrap()
+        );
+        assert!(calls.calls.is_empty());
+    }
+
+    #[test]
+    fn resizes_untiled_windows_relative_to_their_geometry() {
+        let mut state = create_state();
+        let mut calls = MockCalls::with_replies(vec![Reply::Dimensions(Dimensions {
+            width: 200,
+            height: 100,
+            x: 0,
+            y: 0,
+        })]);
+        Manager::resize_untiled(&mut calls, 15, 0.1, &mut state).unwrap();
+        assert_eq!(
+            vec![Call::GetGeometry(15), Call::Resize(15, 110, 220)],
+            calls.calls
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use pgwm_core::util::env::{expand_env, path_candidates};
+use rusl::platform::WaitPidFlags;
+use rusl::string::unix_str::UnixStr;
+
+use crate::error::{Error, Result};
+
+/// Searched if `$PATH` isn't set
+const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+/// The running binary, restarted if the one the WM was started as can't be found
+const SELF_EXE: &UnixStr = UnixStr::from_str_checked("/proc/self/exe\0");
+
+/// Spawns `cmd` with `args` and no stdio, in `cwd` if given, otherwise in the WM's directory.
+/// A leading `~` and environment variables in the command and arguments are expanded first,
+/// and a command that isn't a path is looked up in `$PATH`.
+pub(crate) fn spawn(cmd: &UnixStr, args: &[&UnixStr], cwd: Option<&UnixStr>) -> Result<()> {
+    let expanded_cmd = expand(cmd);
+    let expanded_args = args.iter().map(|arg| expand(arg)).collect::<Vec<_>>();
+    let cmd = expanded_or_original(cmd, expanded_cmd.as_deref())?;
+    let name = cmd.as_str()?.trim_end_matches('\0');
+    let found;
+    let cmd = if name.contains('/') {
+        cmd
+    } else {
+        found = find_in_path(name);
+        let Some(found) = &found else {
+            return Err(Error::SpawnNotFound(name.into()));
+        };
+        UnixStr::try_from_str(found)?
+    };
+    let args = args
+        .iter()
+        .zip(&expanded_args)
+        .map(|(arg, expanded)| expanded_or_original(arg, expanded.as_deref()))
+        .collect::<Result<Vec<_>>>()?;
+    let mut command = tiny_std::process::Command::new(cmd)?;
+    command
+        .args(args.iter().copied())
+        .stdin(tiny_std::process::Stdio::Null)
+        .stdout(tiny_std::process::Stdio::Null)
+        .stderr(tiny_std::process::Stdio::Null);
+    if let Some(cwd) = cwd {
+        command.cwd(cwd);
+    }
+    command.spawn()?;
+    Ok(())
+}
+
+/// Replaces the WM with a fresh start of the binary it was started as, with the same arguments and environment.
+/// The binary is looked up again the same way the shell that started it did, so that a newly installed
+/// binary is picked up, falling back to the running one. Only returns if the exec fails.
+pub(crate) fn exec_self() -> Result<()> {
+    let mut args = tiny_std::env::args().filter_map(core::result::Result::ok);
+    let program = args.next().unwrap_or_default();
+    let found = if program.contains('/') {
+        Some(format!("{program}\0"))
+    } else {
+        find_in_path(program)
+    };
+    let cmd = match &found {
+        Some(found) => UnixStr::try_from_str(found)?,
+        None => SELF_EXE,
+    };
+    let args = args.map(|arg| format!("{arg}\0")).collect::<Vec<_>>();
+    let args = args
+        .iter()
+        .map(|arg| UnixStr::try_from_str(arg))
+        .collect::<core::result::Result<Vec<_>, _>>()?;
+    pgwm_utils::debug!("Restarting through {cmd:?} with args {args:?}");
+    let mut command = tiny_std::process::Command::new(cmd)?;
+    command.args(args.iter().copied());
+    Err(command.exec().into())
+}
+
+/// Collects every spawned program that has exited so that it doesn't linger as a zombie,
+/// run after each event since nothing is notified when a child exits
+pub(crate) fn reap_children() {
+    // Stops at 0 when every remaining child is still running, or with `ECHILD` when there are none
+    while rusl::process::wait_pid(-1, WaitPidFlags::WNOHANG).is_ok_and(|waited| waited.pid != 0) {}
+}
+
+/// The first file named `cmd` in a directory of `$PATH`, null terminated
+fn find_in_path(cmd: &str) -> Option<String> {
+    let path = tiny_std::env::var("PATH").unwrap_or(DEFAULT_PATH);
+    path_candidates(cmd, path).find(|candidate| {
+        UnixStr::try_from_str(candidate)
+            .ok()
+            .and_then(|candidate| tiny_std::fs::metadata(candidate).ok())
+            .is_some_and(|metadata| metadata.is_file())
+    })
+}
+
+/// Null terminated with `~` and environment variables expanded, `None` if there's nothing to expand
+fn expand(arg: &UnixStr) -> Option<String> {
+    let arg = arg.as_str().ok()?.trim_end_matches('\0');
+    let mut expanded = expand_env(arg, |name| tiny_std::env::var(name).ok())?;
+    expanded.push('\0');
+    pgwm_utils::debug!("Expanded {arg} to {expanded}");
+    Some(expanded)
+}
+
+fn expanded_or_original<'a>(
+    original: &'a UnixStr,
+    expanded: Option<&'a str>,
+) -> Result<&'a UnixStr> {
+    match expanded {
+        Some(expanded) => Ok(UnixStr::try_from_str(expanded)?),
+        None => Ok(original),
+    }
+}
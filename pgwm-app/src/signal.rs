@@ -0,0 +1,28 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use rusl::process::{add_signal_action, CatchSignal, SaSignalaction};
+
+use crate::error::Result;
+
+/// Set by the handler when the WM has been asked to terminate, checked after each event
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes `SIGTERM` and `SIGINT` request a shutdown instead of killing the WM,
+/// so that a display manager ending the session goes through the same cleanup as `Action::Quit`
+pub(crate) fn handle_termination_signals() -> Result<()> {
+    unsafe {
+        add_signal_action(CatchSignal::Term, SaSignalaction::Handler(request_shutdown))?;
+        add_signal_action(CatchSignal::Int, SaSignalaction::Handler(request_shutdown))?;
+    }
+    Ok(())
+}
+
+/// Whether a termination signal has been received
+pub(crate) fn shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::Relaxed)
+}
+
+// Only async-signal-safe work in here
+extern "C" fn request_shutdown(_signal: i32) {
+    SHUTDOWN_REQUESTED.store(true, Ordering::Relaxed);
+}
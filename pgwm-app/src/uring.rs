@@ -10,7 +10,6 @@ use rusl::platform::{
     Fd, IoSliceMut, IoUring, IoUringEnterFlags, IoUringParamFlags, IoUringSQEFlags,
     IoUringSubmissionQueueEntry, NonNegativeI32,
 };
-#[cfg(feature = "status-bar")]
 use tiny_std::time::Instant;
 use tiny_std::unix::fd::RawFd;
 use xcb_rust_protocol::con::SocketIo;
@@ -26,6 +25,8 @@ const NET_FD_INDEX: NonNegativeI32 = NonNegativeI32::comptime_checked_new(2);
 const MEM_FD_INDEX: NonNegativeI32 = NonNegativeI32::comptime_checked_new(3);
 #[cfg(feature = "status-bar")]
 const CPU_FD_INDEX: NonNegativeI32 = NonNegativeI32::comptime_checked_new(4);
+#[cfg(feature = "status-bar")]
+const PING_FD_INDEX: NonNegativeI32 = NonNegativeI32::comptime_checked_new(5);
 
 const SOCK_IN_BUF_INDEX: usize = 0;
 const SOCK_OUT_BUF_INDEX: usize = 1;
@@ -37,27 +38,29 @@ const NET_BUF_INDEX: usize = 3;
 const MEM_BUF_INDEX: usize = 4;
 #[cfg(feature = "status-bar")]
 const CPU_BUF_INDEX: usize = 5;
+#[cfg(feature = "status-bar")]
+const PING_BUF_INDEX: usize = 6;
 
 const SOCK_READ_USER_DATA: u64 = 0;
 const SOCK_WRITE_USER_DATA: u64 = 1;
+const WAKEUP_USER_DATA: u64 = 2;
 #[cfg(feature = "status-bar")]
-const BAT_READ_USER_DATA: u64 = 2;
-#[cfg(feature = "status-bar")]
-const BAT_TIMEOUT_USER_DATA: u64 = 3;
+const BAT_READ_USER_DATA: u64 = 3;
 #[cfg(feature = "status-bar")]
 const NET_READ_USER_DATA: u64 = 4;
 #[cfg(feature = "status-bar")]
-const NET_TIMEOUT_USER_DATA: u64 = 5;
-#[cfg(feature = "status-bar")]
-const MEM_READ_USER_DATA: u64 = 6;
+const MEM_READ_USER_DATA: u64 = 5;
 #[cfg(feature = "status-bar")]
-const MEM_TIMEOUT_USER_DATA: u64 = 7;
+const CPU_READ_USER_DATA: u64 = 6;
 #[cfg(feature = "status-bar")]
-const CPU_READ_USER_DATA: u64 = 8;
-#[cfg(feature = "status-bar")]
-const CPU_TIMEOUT_USER_DATA: u64 = 9;
-#[cfg(feature = "status-bar")]
-const DATE_TIMEOUT_USER_DATA: u64 = 10;
+const PING_READ_USER_DATA: u64 = 7;
+
+/// If less than this much space is left in the socket write buffer during a batch,
+/// pending writes are submitted early
+const BATCH_FLUSH_THRESHOLD: usize = 4096;
+
+/// `EPIPE` and `ECONNRESET`, socket operations complete with these once the X server has gone away
+const DISCONNECT_ERRNOS: [i32; 2] = [32, 104];
 
 #[cfg(feature = "status-bar")]
 const NUM_CHECKS: usize = 6;
@@ -198,6 +201,19 @@ pub(crate) struct UringWrapper {
     pub(crate) counter: UringCounter,
     sock_read_buffer: KernelSharedStreamReadBuffer,
     sock_write_buffer: KernelSharedStreamWriteBuffer,
+    /// Nesting depth of open write batches, socket writes are held back until the outermost one ends
+    write_batch_depth: usize,
+    /// Deadlines of submitted wakeup timeouts, earliest first
+    armed_wakeups: Vec<Instant>,
+    /// Set once the X server has closed the socket, errors that surface through the
+    /// connection afterwards are caused by it
+    disconnected: bool,
+    /// `None` if the capture file couldn't be created
+    #[cfg(any(feature = "trace-x11", feature = "record"))]
+    capture: Option<crate::x11::capture::X11Capture>,
+    /// Lets X11 errors be logged with the request that caused them
+    #[cfg(feature = "debug")]
+    pub(crate) recent_requests: pgwm_core::util::requests::RecentRequests,
     #[cfg(feature = "status-bar")]
     bat_buf: Vec<u8>,
     #[cfg(feature = "status-bar")]
@@ -206,6 +222,11 @@ pub(crate) struct UringWrapper {
     mem_buf: Vec<u8>,
     #[cfg(feature = "status-bar")]
     cpu_buf: Vec<u8>,
+    #[cfg(feature = "status-bar")]
+    ping_buf: Vec<u8>,
+    /// Echo requests are sent straight to the socket, only replies are read through the ring
+    #[cfg(feature = "status-bar")]
+    ping_fd: RawFd,
 }
 
 #[derive(Debug)]
@@ -221,12 +242,16 @@ pub(crate) struct UringCounter {
     #[cfg(feature = "status-bar")]
     pending_cpu_read: ReadStatus,
     #[cfg(feature = "status-bar")]
-    pending_date_read: ReadStatus,
+    pending_ping_read: ReadStatus,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub(crate) enum UringReadEvent {
     SockIn,
+    /// A deadline submitted through `submit_wakeup` has passed
+    Wakeup,
+    /// Waiting was interrupted by a signal asking the WM to shut down
+    Shutdown,
     #[cfg(feature = "status-bar")]
     Bat,
     #[cfg(feature = "status-bar")]
@@ -236,7 +261,7 @@ pub(crate) enum UringReadEvent {
     #[cfg(feature = "status-bar")]
     Cpu,
     #[cfg(feature = "status-bar")]
-    DateTimeout,
+    Ping,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -248,23 +273,21 @@ pub(crate) enum ReadStatus {
 }
 
 macro_rules! impl_submit_check {
-    ($fn_name: ident, $counter_name: ident, $buf: ident, $user_data: expr, $timeout_user_data: expr, $fd_index: expr, $buf_index: expr) => {
+    ($fn_name: ident, $counter_name: ident, $buf: ident, $user_data: expr, $fd_index: expr, $buf_index: expr) => {
         #[inline]
         #[cfg(feature = "status-bar")]
-        pub fn $fn_name(&mut self, execute_at: &Instant) -> Result<()> {
+        pub fn $fn_name(&mut self) -> Result<()> {
             if self.counter.$counter_name != ReadStatus::Inactive {
                 crate::debug!(
                     "Tried to submit multiple reads for {}, status: {:?}",
                     stringify!($fn_name),
                     self.counter.$counter_name
                 );
-            } else if *execute_at >= Instant::now() {
-                self.submit_indexed_timeout($timeout_user_data, execute_at)?;
-                self.counter.$counter_name = ReadStatus::Pending;
             } else {
                 let addr = self.$buf.as_ptr() as u64;
                 let space = self.$buf.len();
                 self.submit_indexed_read($fd_index, $buf_index, $user_data, addr, space)?;
+                self.counter.$counter_name = ReadStatus::Pending;
             }
             Ok(())
         }
@@ -326,6 +349,37 @@ impl UringWrapper {
         self.finish_submit(1)
     }
 
+    /// Hold back socket writes until the matching `end_write_batch`, letting everything written in between
+    /// go out as a single write. Batches can be nested, writes are submitted when the outermost ends.
+    #[inline]
+    pub fn begin_write_batch(&mut self) {
+        self.write_batch_depth += 1;
+    }
+
+    /// End a batch started by `begin_write_batch`, submitting held back writes if it was the outermost
+    pub fn end_write_batch(&mut self) -> Result<()> {
+        self.write_batch_depth = self.write_batch_depth.saturating_sub(1);
+        if self.write_batch_depth == 0 {
+            self.submit_pending_socket_writes()?;
+        }
+        Ok(())
+    }
+
+    /// Submit writes held back by a batch, writes need to be submitted before waiting on a reply
+    #[inline]
+    fn submit_pending_socket_writes(&mut self) -> Result<()> {
+        if self.sock_write_buffer.kernel_readable().is_empty() {
+            Ok(())
+        } else {
+            self.submit_socket_write()
+        }
+    }
+
+    /// Whether the X server has closed the connection
+    pub(crate) fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
     /// Same as `submit_socket_write` but a read operation
     pub fn submit_sock_read(&mut self) -> Result<()> {
         if self.counter.pending_sock_read != ReadStatus::Inactive {
@@ -357,7 +411,6 @@ impl UringWrapper {
         pending_bat_read,
         bat_buf,
         BAT_READ_USER_DATA,
-        BAT_TIMEOUT_USER_DATA,
         BAT_FD_INDEX,
         BAT_BUF_INDEX
     );
@@ -366,7 +419,6 @@ impl UringWrapper {
         pending_net_read,
         net_buf,
         NET_READ_USER_DATA,
-        NET_TIMEOUT_USER_DATA,
         NET_FD_INDEX,
         NET_BUF_INDEX
     );
@@ -375,7 +427,6 @@ impl UringWrapper {
         pending_cpu_read,
         cpu_buf,
         CPU_READ_USER_DATA,
-        CPU_TIMEOUT_USER_DATA,
         CPU_FD_INDEX,
         CPU_BUF_INDEX
     );
@@ -384,28 +435,50 @@ impl UringWrapper {
         pending_mem_read,
         mem_buf,
         MEM_READ_USER_DATA,
-        MEM_TIMEOUT_USER_DATA,
         MEM_FD_INDEX,
         MEM_BUF_INDEX
     );
+    impl_submit_check!(
+        submit_ping_read,
+        pending_ping_read,
+        ping_buf,
+        PING_READ_USER_DATA,
+        PING_FD_INDEX,
+        PING_BUF_INDEX
+    );
 
-    #[inline]
+    /// Send an echo request on the ping socket. Failing to send, like when there's no route to the target,
+    /// is left to show as a lost ping
     #[cfg(feature = "status-bar")]
-    fn submit_indexed_timeout(
-        &mut self,
-        timeout_user_data: u64,
-        execute_at: &Instant,
-    ) -> Result<()> {
+    pub fn send_ping(&mut self, request: &[u8]) {
+        if let Err(_e) = rusl::unistd::write(self.ping_fd, request) {
+            crate::debug!("Failed to send ping {_e}");
+        }
+    }
+
+    /// Make sure that the next completion is at the latest at `execute_at`.
+    /// Only submits a timeout if none is already armed at or before that deadline,
+    /// so repeatedly asking for the same wakeup is cheap.
+    pub fn submit_wakeup(&mut self, execute_at: &Instant) -> Result<()> {
+        if self
+            .armed_wakeups
+            .first()
+            .is_some_and(|armed| armed <= execute_at)
+        {
+            return Ok(());
+        }
         unsafe {
             let timeout = IoUringSubmissionQueueEntry::new_timeout(
                 execute_at.as_ref(),
                 false,
                 None,
-                timeout_user_data,
+                WAKEUP_USER_DATA,
                 IoUringSQEFlags::empty(),
             );
             self.inner.get_next_sqe_slot().unwrap().write(timeout);
         }
+        // Earlier than any armed wakeup, timeouts complete in deadline order
+        self.armed_wakeups.insert(0, *execute_at);
         self.finish_submit(1)?;
         Ok(())
     }
@@ -435,34 +508,6 @@ impl UringWrapper {
         Ok(())
     }
 
-    #[inline]
-    #[cfg(feature = "status-bar")]
-    pub fn submit_date_timeout(&mut self, execute_at: &Instant) -> Result<()> {
-        if self.counter.pending_date_read != ReadStatus::Inactive {
-            crate::debug!(
-                "Tried to submit multiple date timeouts, status: {:?}",
-                self.counter.pending_date_read
-            );
-            return Ok(());
-        } else if *execute_at >= Instant::now() {
-            unsafe {
-                let entry = IoUringSubmissionQueueEntry::new_timeout(
-                    execute_at.as_ref(),
-                    false,
-                    None,
-                    DATE_TIMEOUT_USER_DATA,
-                    IoUringSQEFlags::empty(),
-                );
-                self.inner.get_next_sqe_slot().unwrap().write(entry);
-            };
-            self.counter.pending_date_read = ReadStatus::Pending;
-            self.finish_submit(1)?;
-        } else {
-            self.counter.pending_date_read = ReadStatus::Ready(0);
-        }
-        Ok(())
-    }
-
     #[inline]
     fn finish_submit(&mut self, submit_count: u32) -> Result<()> {
         // Flush queue, could optimize this a bit on the tiny-std side
@@ -477,17 +522,7 @@ impl UringWrapper {
     impl_read_check!(read_net, pending_net_read, net_buf);
     impl_read_check!(read_mem, pending_mem_read, mem_buf);
     impl_read_check!(read_cpu, pending_cpu_read, cpu_buf);
-
-    #[inline]
-    #[cfg(feature = "status-bar")]
-    pub fn read_date(&mut self) {
-        match self.counter.pending_date_read {
-            ReadStatus::Ready(_ind) => {
-                self.counter.pending_date_read = ReadStatus::Inactive;
-            }
-            _ => panic!("Date not ready on read."),
-        }
-    }
+    impl_read_check!(read_ping, pending_ping_read, ping_buf);
 
     pub(crate) fn check_ready_cached(&mut self) -> heapless::Vec<UringReadEvent, NUM_CHECKS> {
         let mut ready = heapless::Vec::new();
@@ -505,8 +540,8 @@ impl UringWrapper {
             if matches!(self.counter.pending_cpu_read, ReadStatus::Ready(_)) {
                 let _ = ready.push(UringReadEvent::Cpu);
             }
-            if matches!(self.counter.pending_date_read, ReadStatus::Ready(_)) {
-                let _ = ready.push(UringReadEvent::DateTimeout);
+            if matches!(self.counter.pending_ping_read, ReadStatus::Ready(_)) {
+                let _ = ready.push(UringReadEvent::Ping);
             }
         }
         if self.sock_read_buffer.has_unchecked_data {
@@ -519,6 +554,11 @@ impl UringWrapper {
         while let Some(cqe) = self.inner.get_next_cqe() {
             match cqe.0.user_data {
                 SOCK_READ_USER_DATA => {
+                    // A read of 0 bytes is EOF, the server won't send anything else
+                    if cqe.0.res == 0 || DISCONNECT_ERRNOS.contains(&-cqe.0.res) {
+                        self.disconnected = true;
+                        return Err(Error::XDisconnected);
+                    }
                     if cqe.0.res < 0 {
                         return Err(Error::Uring(format!("Got error on cqe {cqe:?}")));
                     }
@@ -531,11 +571,22 @@ impl UringWrapper {
                     return Ok(Some(UringReadEvent::SockIn));
                 }
                 SOCK_WRITE_USER_DATA => {
+                    if DISCONNECT_ERRNOS.contains(&-cqe.0.res) {
+                        self.disconnected = true;
+                        return Err(Error::XDisconnected);
+                    }
                     if cqe.0.res < 0 {
                         return Err(Error::Uring(format!("Got error on cqe {cqe:?}")));
                     }
                     self.counter.pending_sock_writes -= 1;
                 }
+                WAKEUP_USER_DATA => {
+                    // Completes with `ETIME` when the deadline passes, which is the expected outcome
+                    if !self.armed_wakeups.is_empty() {
+                        self.armed_wakeups.remove(0);
+                    }
+                    return Ok(Some(UringReadEvent::Wakeup));
+                }
                 #[cfg(feature = "status-bar")]
                 BAT_READ_USER_DATA => {
                     if cqe.0.res < 0 {
@@ -545,18 +596,6 @@ impl UringWrapper {
                     return Ok(Some(UringReadEvent::Bat));
                 }
                 #[cfg(feature = "status-bar")]
-                BAT_TIMEOUT_USER_DATA => {
-                    let addr = self.bat_buf.as_ptr() as u64;
-                    let space = self.bat_buf.len();
-                    self.submit_indexed_read(
-                        BAT_FD_INDEX,
-                        BAT_BUF_INDEX,
-                        BAT_READ_USER_DATA,
-                        addr,
-                        space,
-                    )?;
-                }
-                #[cfg(feature = "status-bar")]
                 NET_READ_USER_DATA => {
                     if cqe.0.res < 0 {
                         return Err(Error::Uring(format!("Got error on cqe {cqe:?}")));
@@ -565,18 +604,6 @@ impl UringWrapper {
                     return Ok(Some(UringReadEvent::Net));
                 }
                 #[cfg(feature = "status-bar")]
-                NET_TIMEOUT_USER_DATA => {
-                    let addr = self.net_buf.as_ptr() as u64;
-                    let space = self.net_buf.len();
-                    self.submit_indexed_read(
-                        NET_FD_INDEX,
-                        NET_BUF_INDEX,
-                        NET_READ_USER_DATA,
-                        addr,
-                        space,
-                    )?;
-                }
-                #[cfg(feature = "status-bar")]
                 MEM_READ_USER_DATA => {
                     if cqe.0.res < 0 {
                         return Err(Error::Uring(format!("Got error on cqe {cqe:?}")));
@@ -585,18 +612,6 @@ impl UringWrapper {
                     return Ok(Some(UringReadEvent::Mem));
                 }
                 #[cfg(feature = "status-bar")]
-                MEM_TIMEOUT_USER_DATA => {
-                    let addr = self.mem_buf.as_ptr() as u64;
-                    let space = self.mem_buf.len();
-                    self.submit_indexed_read(
-                        MEM_FD_INDEX,
-                        MEM_BUF_INDEX,
-                        MEM_READ_USER_DATA,
-                        addr,
-                        space,
-                    )?;
-                }
-                #[cfg(feature = "status-bar")]
                 CPU_READ_USER_DATA => {
                     if cqe.0.res < 0 {
                         return Err(Error::Uring(format!("Got error on cqe {cqe:?}")));
@@ -605,21 +620,12 @@ impl UringWrapper {
                     return Ok(Some(UringReadEvent::Cpu));
                 }
                 #[cfg(feature = "status-bar")]
-                CPU_TIMEOUT_USER_DATA => {
-                    let addr = self.cpu_buf.as_ptr() as u64;
-                    let space = self.cpu_buf.len();
-                    self.submit_indexed_read(
-                        CPU_FD_INDEX,
-                        CPU_BUF_INDEX,
-                        CPU_READ_USER_DATA,
-                        addr,
-                        space,
-                    )?;
-                }
-                #[cfg(feature = "status-bar")]
-                DATE_TIMEOUT_USER_DATA => {
-                    self.counter.pending_date_read = ReadStatus::Ready(0);
-                    return Ok(Some(UringReadEvent::DateTimeout));
+                PING_READ_USER_DATA => {
+                    // ICMP errors, like the target's host being unreachable, fail the read,
+                    // that's a lost ping rather than a broken ring
+                    self.counter.pending_ping_read =
+                        ReadStatus::Ready(usize::try_from(cqe.0.res).unwrap_or_default());
+                    return Ok(Some(UringReadEvent::Ping));
                 }
                 _ => {
                     panic!("Io uring in inconsistent state");
@@ -644,6 +650,9 @@ impl UringWrapper {
                 Err(e) => {
                     if let Some(e) = e.code {
                         if e == Errno::EINTR {
+                            if crate::signal::shutdown_requested() {
+                                return Ok(UringReadEvent::Shutdown);
+                            }
                             continue;
                         }
                     }
@@ -654,6 +663,8 @@ impl UringWrapper {
     }
 
     pub fn await_write_completions(&mut self) -> Result<()> {
+        // Clearing the buffer would drop writes held back by a batch
+        self.submit_pending_socket_writes()?;
         if self.counter.pending_sock_writes == 0 {
             unsafe {
                 self.sock_write_buffer.clear();
@@ -685,10 +696,12 @@ impl UringWrapper {
         #[cfg(feature = "status-bar")] mut net_buf: Vec<u8>,
         #[cfg(feature = "status-bar")] mut mem_buf: Vec<u8>,
         #[cfg(feature = "status-bar")] mut cpu_buf: Vec<u8>,
+        #[cfg(feature = "status-bar")] mut ping_buf: Vec<u8>,
         #[cfg(feature = "status-bar")] bat_fd: RawFd,
         #[cfg(feature = "status-bar")] net_fd: RawFd,
         #[cfg(feature = "status-bar")] mem_fd: RawFd,
         #[cfg(feature = "status-bar")] cpu_fd: RawFd,
+        #[cfg(feature = "status-bar")] ping_fd: RawFd,
     ) -> Result<Self> {
         let inner = setup_io_uring(512, IoUringParamFlags::IORING_SETUP_SINGLE_ISSUER, 0, 0)?;
         unsafe {
@@ -705,6 +718,8 @@ impl UringWrapper {
                     IoSliceMut::new(&mut mem_buf),
                     #[cfg(feature = "status-bar")]
                     IoSliceMut::new(&mut cpu_buf),
+                    #[cfg(feature = "status-bar")]
+                    IoSliceMut::new(&mut ping_buf),
                 ],
             )?;
         }
@@ -720,6 +735,8 @@ impl UringWrapper {
                 mem_fd,
                 #[cfg(feature = "status-bar")]
                 cpu_fd,
+                #[cfg(feature = "status-bar")]
+                ping_fd,
             ],
         )?;
         Ok(Self {
@@ -736,10 +753,17 @@ impl UringWrapper {
                 #[cfg(feature = "status-bar")]
                 pending_cpu_read: ReadStatus::Inactive,
                 #[cfg(feature = "status-bar")]
-                pending_date_read: ReadStatus::Inactive,
+                pending_ping_read: ReadStatus::Inactive,
             },
             sock_read_buffer: KernelSharedStreamReadBuffer::new(read_buf),
             sock_write_buffer: KernelSharedStreamWriteBuffer::new(write_buf),
+            write_batch_depth: 0,
+            armed_wakeups: Vec::new(),
+            disconnected: false,
+            #[cfg(any(feature = "trace-x11", feature = "record"))]
+            capture: crate::x11::capture::X11Capture::open(),
+            #[cfg(feature = "debug")]
+            recent_requests: pgwm_core::util::requests::RecentRequests::new(),
             #[cfg(feature = "status-bar")]
             bat_buf,
             #[cfg(feature = "status-bar")]
@@ -748,6 +772,10 @@ impl UringWrapper {
             mem_buf,
             #[cfg(feature = "status-bar")]
             cpu_buf,
+            #[cfg(feature = "status-bar")]
+            ping_buf,
+            #[cfg(feature = "status-bar")]
+            ping_fd,
         })
     }
 }
@@ -757,6 +785,11 @@ impl SocketIo for UringWrapper {
         if self.sock_read_buffer.has_unchecked_data {
             return Ok(());
         }
+        // The request we're waiting on a reply for may be held back by a batch
+        self.submit_pending_socket_writes().map_err(|_e| {
+            crate::debug!("Got error submitting pending writes {_e}");
+            "Got error submitting pending writes"
+        })?;
         loop {
             #[allow(unused_variables)]
             let evt = self.await_next_completion().map_err(|e| {
@@ -775,6 +808,10 @@ impl SocketIo for UringWrapper {
         read_op: F,
     ) -> core::result::Result<(), E> {
         let consumed_bytes = (read_op)(self.sock_read_buffer.user_readable())?;
+        #[cfg(any(feature = "trace-x11", feature = "record"))]
+        if let Some(capture) = &mut self.capture {
+            capture.read(&self.sock_read_buffer.user_readable()[..consumed_bytes]);
+        }
         self.sock_read_buffer.advance_read(consumed_bytes);
         Ok(())
     }
@@ -785,8 +822,19 @@ impl SocketIo for UringWrapper {
         write_op: F,
     ) -> core::result::Result<(), E> {
         let consumed_bytes = (write_op)(self.sock_write_buffer.user_writeable())?;
+        #[cfg(any(feature = "trace-x11", feature = "record"))]
+        if let Some(capture) = &mut self.capture {
+            capture.written(&self.sock_write_buffer.user_writeable()[..consumed_bytes]);
+        }
+        #[cfg(feature = "debug")]
+        self.recent_requests
+            .record_written(&self.sock_write_buffer.user_writeable()[..consumed_bytes]);
         self.sock_write_buffer.advance_written(consumed_bytes);
-        self.submit_socket_write().unwrap();
+        if self.write_batch_depth == 0
+            || self.sock_write_buffer.user_writeable().len() < BATCH_FLUSH_THRESHOLD
+        {
+            self.submit_socket_write().unwrap();
+        }
         Ok(())
     }
 }
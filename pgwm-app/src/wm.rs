@@ -1,18 +1,22 @@
 use alloc::vec;
 use alloc::vec::Vec;
+use core::time::Duration;
 use rusl::platform::{AddressFamily, SocketAddressUnix, SocketFlags, SocketOptions, SocketType};
 use rusl::process::{CatchSignal, SaSignalaction};
 use rusl::string::unix_str::UnixStr;
 use smallmap::Map;
+use tiny_std::time::Instant;
 use tiny_std::unix::fd::RawFd;
 use xcb_rust_protocol::con::XcbState;
 use xcb_rust_protocol::connection::render::query_pict_formats;
+use xcb_rust_protocol::helpers::resource_manager::{new_from_default, Database};
 use xcb_rust_protocol::proto::render::{PictTypeEnum, Pictformat, Pictforminfo};
 use xcb_rust_protocol::proto::xproto::{
-    ButtonPressEvent, ButtonReleaseEvent, ClientMessageEvent, ConfigureNotifyEvent,
-    ConfigureRequestEvent, DestroyNotifyEvent, EnterNotifyEvent, KeyPressEvent, MapRequestEvent,
-    MotionNotifyEvent, PropertyNotifyEvent, Screen, UnmapNotifyEvent, VisibilityNotifyEvent,
-    Visualid,
+    ButtonPressEvent, ButtonReleaseEvent, ClientMessageEvent, ColormapNotifyEvent,
+    ConfigureNotifyEvent, ConfigureRequestEvent, DestroyNotifyEvent, EnterNotifyEvent,
+    KeyPressEvent, KeyReleaseEvent, LeaveNotifyEvent, MapNotifyEvent, MapRequestEvent,
+    MappingNotifyEvent, MotionNotifyEvent, PropertyNotifyEvent, Screen, UnmapNotifyEvent,
+    VisibilityNotifyEvent, Visualid,
 };
 use xcb_rust_protocol::util::FixedLengthFromBytes;
 use xcb_rust_protocol::XcbEnv;
@@ -25,37 +29,41 @@ use crate::manager;
 use crate::manager::bar::BarManager;
 use crate::manager::draw::Drawer;
 use crate::manager::font::{load_alloc_fonts, FontDrawer, LoadedFonts};
+use crate::manager::spawn::reap_children;
 use crate::manager::Manager;
 use crate::uring::{UringReadEvent, UringWrapper};
 use crate::x11::call_wrapper::CallWrapper;
-use crate::x11::colors::alloc_colors;
+use crate::x11::colors::{alloc_colors, with_resource_colors};
 
 const HOME: &UnixStr = UnixStr::from_str_checked("HOME\0");
 const XENVIRONMENT: &UnixStr = UnixStr::from_str_checked("XENVIRONMENT\0");
 const XAUTHORITY: &UnixStr = UnixStr::from_str_checked("XAUTHORITY\0");
 const DISPLAY: &UnixStr = UnixStr::from_str_checked("DISPLAY\0");
 const XCURSOR_SIZE: &UnixStr = UnixStr::from_str_checked("XCURSOR_SIZE\0");
+/// How often to try connecting while waiting for the X server to come back
+const RECONNECT_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 #[allow(clippy::too_many_lines)]
 pub(crate) fn run_wm() -> Result<()> {
-    #[cfg(feature = "perf-test")]
-    let dpy = Some(":4");
-    #[cfg(not(feature = "perf-test"))]
-    let dpy = None;
-    // We just spawn user stuff, we don't care when they terminate, could signalfd -> poll if we did
-    // without the raw unsafety of setting up a signal handler
+    let dpy = display();
+    // Spawned programs are reaped by `reap_children` in the loop. Ignoring `SIGCHLD` would have the kernel
+    // do it, but an ignored `SIGCHLD` is inherited through exec and breaks spawned programs that wait
+    // on their own children, so make sure it's not ignored even if whatever started the WM ignored it.
     unsafe {
-        rusl::process::add_signal_action(CatchSignal::Chld, SaSignalaction::Ign)?;
+        rusl::process::add_signal_action(CatchSignal::Chld, SaSignalaction::Dfl)?;
     }
-    crate::debug!("Set sigignore for children");
+    crate::debug!("Set default sigaction for children");
+    crate::signal::handle_termination_signals()?;
     let xcb_env = env_to_xcb_env();
     let xcb_socket_in_buffer = vec![0u8; 65536];
     let xcb_socket_out_buffer = vec![0u8; 65536];
+    let cfg = pgwm_core::config::cfg::Cfg::load().map_err(Error::InvalidConfig)?;
     crate::debug!("Looking for socket path");
     let (path, dpy_info) = xcb_rust_connection::connection::find_socket_path(dpy)?;
+    // Not inherited by spawned programs, or by the new WM when restarting, which connects on its own
     let socket_fd = rusl::network::socket(
         AddressFamily::AF_UNIX,
-        SocketOptions::new(SocketType::SOCK_STREAM, SocketFlags::empty()),
+        SocketOptions::new(SocketType::SOCK_STREAM, SocketFlags::SOCK_CLOEXEC),
         0,
     )?;
 
@@ -68,7 +76,7 @@ pub(crate) fn run_wm() -> Result<()> {
         xcb_socket_out_buffer,
         socket_fd,
         #[cfg(feature = "status-bar")]
-        &pgwm_core::config::STATUS_CHECKS,
+        cfg.status_checks,
     )?;
     // On connect we'll start the listening loop
     uring_wrapper.submit_sock_read()?;
@@ -81,13 +89,10 @@ pub(crate) fn run_wm() -> Result<()> {
     pgwm_utils::debug!("Set up call wrapper");
     call_wrapper.try_become_wm(screen)?;
     pgwm_utils::debug!("Became wm");
+    #[cfg(feature = "xkb")]
+    call_wrapper.init_xkb()?;
     pgwm_utils::debug!("Got resource database properties");
-    let resource_db = xcb_rust_protocol::helpers::resource_manager::new_from_default(
-        &mut call_wrapper.uring,
-        &mut call_wrapper.xcb_state,
-        tiny_std::env::var_unix(HOME).ok(),
-        tiny_std::env::var_unix(XENVIRONMENT).ok(),
-    )?;
+    let resource_db = load_resource_db(&mut call_wrapper)?;
     let cursor_handle = xcb_rust_protocol::helpers::cursor::Handle::new(
         &mut call_wrapper.uring,
         &mut call_wrapper.xcb_state,
@@ -96,14 +101,22 @@ pub(crate) fn run_wm() -> Result<()> {
         xcb_env,
     )?;
     let visual = find_render_visual_info(&mut call_wrapper, screen)?;
-    let loaded = load_alloc_fonts(&mut call_wrapper, &visual)?;
-    call_wrapper.uring.await_write_completions()?;
-
-    crate::debug!("Loaded {} fonts", loaded.len());
-    let lf = LoadedFonts::new(loaded)?;
+    let scales = crate::x11::state_lifecycle::monitor_scales(&mut call_wrapper, screen)?;
+    pgwm_utils::debug!("Using scales {scales:?}");
+    let mut lf = Vec::with_capacity(scales.len());
+    for scale in &scales {
+        let loaded = load_alloc_fonts(&mut call_wrapper, &visual, *scale, cfg)?;
+        call_wrapper.uring.await_write_completions()?;
+        crate::debug!("Loaded {} fonts at scale {scale}", loaded.len());
+        lf.push(LoadedFonts::new(loaded, *scale)?);
+    }
     let font_drawer = FontDrawer::new(&lf);
     crate::debug!("Font drawer initialized");
-    let colors = alloc_colors(&mut call_wrapper, screen.default_colormap)?;
+    let colors = alloc_colors(
+        &mut call_wrapper,
+        screen.default_colormap,
+        &with_resource_colors(&cfg.colors, &resource_db),
+    )?;
     crate::debug!("Allocated colors");
 
     pgwm_utils::debug!("Creating state");
@@ -113,6 +126,8 @@ pub(crate) fn run_wm() -> Result<()> {
         visual,
         screen,
         colors,
+        &scales,
+        cfg,
     )?;
 
     crate::debug!("Initialized mappings");
@@ -124,12 +139,14 @@ pub(crate) fn run_wm() -> Result<()> {
     crate::debug!("Initialized manager");
     // Extremely ugly control flow here
     #[cfg(feature = "status-bar")]
-    let should_check = !pgwm_core::config::STATUS_CHECKS.is_empty();
+    let should_check = !cfg.status_checks.is_empty();
 
     #[cfg(feature = "status-bar")]
-    let mut mut_checks = pgwm_core::config::STATUS_CHECKS;
+    let mut mut_checks = cfg.status_checks.to_vec();
     #[cfg(feature = "status-bar")]
     let mut checker = pgwm_core::status::checker::Checker::new(&mut mut_checks);
+    #[cfg(feature = "status-bar")]
+    let mut fifos = CheckFifos::new(cfg.status_checks);
     crate::debug!("Initialized Checker");
     manager.init(&mut call_wrapper, &mut state)?;
     crate::debug!("Initialized manager state");
@@ -138,7 +155,13 @@ pub(crate) fn run_wm() -> Result<()> {
     loop {
         #[cfg(feature = "status-bar")]
         let loop_result = if should_check {
-            loop_with_status(&mut call_wrapper, &manager, &mut checker, &mut state)
+            loop_with_status(
+                &mut call_wrapper,
+                &manager,
+                &mut checker,
+                &mut fifos,
+                &mut state,
+            )
         } else {
             loop_without_status(&mut call_wrapper, &mut checker, &manager, &mut state)
         };
@@ -182,6 +205,10 @@ pub(crate) fn run_wm() -> Result<()> {
                     drop(call_wrapper);
                     return Err(Error::FullRestart);
                 }
+                // Errors from the X11 connection don't say why it failed
+                _ if call_wrapper.uring.is_disconnected() => {
+                    return Err(Error::XDisconnected);
+                }
                 _ => {
                     return Err(e);
                 }
@@ -190,6 +217,55 @@ pub(crate) fn run_wm() -> Result<()> {
     }
 }
 
+/// Waits for an X server to accept connections on the display again, gives up after `timeout`
+pub(crate) fn await_x_server(timeout: Duration) -> bool {
+    let start = Instant::now();
+    loop {
+        if probe_x_server().is_ok() {
+            return true;
+        }
+        if Instant::now()
+            .duration_since(start)
+            .is_some_and(|waited| waited >= timeout)
+        {
+            return false;
+        }
+        let _ = tiny_std::thread::sleep(RECONNECT_POLL_INTERVAL);
+    }
+}
+
+fn probe_x_server() -> Result<()> {
+    let (path, _dpy_info) = xcb_rust_connection::connection::find_socket_path(display())?;
+    let addr = SocketAddressUnix::try_from_unix(&path)?;
+    let socket_fd = rusl::network::socket(
+        AddressFamily::AF_UNIX,
+        SocketOptions::new(SocketType::SOCK_STREAM, SocketFlags::empty()),
+        0,
+    )?;
+    let res = rusl::network::connect_unix(socket_fd, &addr);
+    let _ = rusl::unistd::close(socket_fd);
+    res?;
+    Ok(())
+}
+
+/// The X resources, from the root window's `RESOURCE_MANAGER` property or the user's resource files
+pub(crate) fn load_resource_db(call_wrapper: &mut CallWrapper) -> Result<Database> {
+    Ok(new_from_default(
+        &mut call_wrapper.uring,
+        &mut call_wrapper.xcb_state,
+        tiny_std::env::var_unix(HOME).ok(),
+        tiny_std::env::var_unix(XENVIRONMENT).ok(),
+    )?)
+}
+
+fn display() -> Option<&'static str> {
+    #[cfg(feature = "perf-test")]
+    let dpy = Some(":4");
+    #[cfg(not(feature = "perf-test"))]
+    let dpy = None;
+    dpy
+}
+
 fn env_to_xcb_env() -> XcbEnv<'static> {
     XcbEnv {
         home_dir: tiny_std::env::var_unix(HOME).ok(),
@@ -210,11 +286,13 @@ fn instantiate_uring(
     #[cfg(feature = "status-bar")]
     let bat_buf = vec![0u8; 64];
     #[cfg(feature = "status-bar")]
-    let net_buf = vec![0u8; 4096];
+    // Around 130 bytes per interface, containers and VMs can add a lot of them
+    let net_buf = vec![0u8; 16384];
     #[cfg(feature = "status-bar")]
     let mem_buf = vec![0u8; 4096];
     #[cfg(feature = "status-bar")]
-    let cpu_buf = vec![0u8; 4096];
+    // Around 60 bytes per core, only the cpu lines at the start are needed
+    let cpu_buf = vec![0u8; 16384];
     #[cfg(feature = "status-bar")]
     let mut bat_fd = None;
     #[cfg(feature = "status-bar")]
@@ -224,21 +302,37 @@ fn instantiate_uring(
     #[cfg(feature = "status-bar")]
     let mut cpu_fd = None;
     #[cfg(feature = "status-bar")]
+    let ping_buf = vec![0u8; 128];
+    #[cfg(feature = "status-bar")]
+    let mut ping_fd = None;
+    #[cfg(feature = "status-bar")]
     for check in checks {
-        match check.check_type {
+        match &check.check_type {
             pgwm_core::status::checker::CheckType::Battery(_) => {
                 bat_fd = Some(try_open_fd(pgwm_core::status::sys::bat::BAT_FILE)?);
             }
-            pgwm_core::status::checker::CheckType::Cpu(_) => {
-                cpu_fd = Some(try_open_fd(pgwm_core::status::sys::cpu::CPU_LOAD_FILE)?);
+            pgwm_core::status::checker::CheckType::Cpu(fmt) => {
+                let file = if fmt.mode() == pgwm_core::status::checker::CpuMode::LoadAvg {
+                    pgwm_core::status::sys::cpu::LOAD_AVG_FILE
+                } else {
+                    pgwm_core::status::sys::cpu::CPU_LOAD_FILE
+                };
+                cpu_fd = Some(try_open_fd(file)?);
             }
             pgwm_core::status::checker::CheckType::Net(_) => {
-                net_fd = Some(try_open_fd(pgwm_core::status::sys::net::NET_STAT_FILE)?);
+                net_fd = Some(try_open_fd(pgwm_core::status::sys::net::NET_DEV_FILE)?);
             }
             pgwm_core::status::checker::CheckType::Mem(_) => {
                 mem_fd = Some(try_open_fd(pgwm_core::status::sys::mem::MEM_LOAD_FILE)?);
             }
-            pgwm_core::status::checker::CheckType::Date(_) => {}
+            pgwm_core::status::checker::CheckType::Date(_)
+            | pgwm_core::status::checker::CheckType::Keyboard(_)
+            | pgwm_core::status::checker::CheckType::Privacy(_)
+            | pgwm_core::status::checker::CheckType::Media(_)
+            | pgwm_core::status::checker::CheckType::External(_) => {}
+            pgwm_core::status::checker::CheckType::Ping(fmt) => {
+                ping_fd = Some(try_open_ping_socket(fmt.target())?);
+            }
         }
     }
 
@@ -255,6 +349,8 @@ fn instantiate_uring(
         #[cfg(feature = "status-bar")]
         cpu_buf,
         #[cfg(feature = "status-bar")]
+        ping_buf,
+        #[cfg(feature = "status-bar")]
         bat_fd.unwrap_or_default(),
         #[cfg(feature = "status-bar")]
         net_fd.unwrap_or_default(),
@@ -262,13 +358,19 @@ fn instantiate_uring(
         mem_fd.unwrap_or_default(),
         #[cfg(feature = "status-bar")]
         cpu_fd.unwrap_or_default(),
+        #[cfg(feature = "status-bar")]
+        ping_fd.unwrap_or_default(),
     )?;
     Ok(uring_wrapper)
 }
 
 #[cfg(feature = "status-bar")]
 fn try_open_fd(file: &UnixStr) -> Result<RawFd> {
-    match rusl::unistd::open(file, rusl::platform::OpenFlags::O_RDONLY) {
+    // Not inherited by spawned programs
+    match rusl::unistd::open(
+        file,
+        rusl::platform::OpenFlags::O_RDONLY | rusl::platform::OpenFlags::O_CLOEXEC,
+    ) {
         Ok(f) => Ok(f),
         Err(e) => {
             tiny_std::eprintln!("Failed to open check file {file:?} {e}");
@@ -277,41 +379,148 @@ fn try_open_fd(file: &UnixStr) -> Result<RawFd> {
     }
 }
 
+/// An unprivileged ICMP socket connected to `target`, so that echo requests can be written to it and only
+/// replies from the target are read
+#[cfg(feature = "status-bar")]
+fn try_open_ping_socket(target: [u8; 4]) -> Result<RawFd> {
+    let open = || -> Result<RawFd> {
+        let fd = rusl::network::socket(
+            AddressFamily::AF_INET,
+            SocketOptions::new(SocketType::SOCK_DGRAM, SocketFlags::SOCK_CLOEXEC),
+            // IPPROTO_ICMP
+            1,
+        )?;
+        rusl::network::connect_inet(fd, &rusl::platform::SocketAddressInet::new(target, 0))?;
+        Ok(fd)
+    };
+    open().map_err(|e| {
+        tiny_std::eprintln!("Failed to open ping socket to {target:?} {e}");
+        e
+    })
+}
+
+/// The fifos that the media check and an external check read from
+#[cfg(feature = "status-bar")]
+struct CheckFifos {
+    media: CheckFifo,
+    external: CheckFifo,
+}
+
+#[cfg(feature = "status-bar")]
+impl CheckFifos {
+    fn new(checks: &'static [pgwm_core::status::checker::Check]) -> Self {
+        let media = checks.iter().find_map(|check| match &check.check_type {
+            pgwm_core::status::checker::CheckType::Media(fmt) => Some(fmt.fifo()),
+            _ => None,
+        });
+        let external = checks.iter().find_map(|check| match &check.check_type {
+            pgwm_core::status::checker::CheckType::External(fmt) => match fmt.source() {
+                pgwm_core::status::checker::ExternalSource::Fifo(fifo) => Some(fifo),
+                pgwm_core::status::checker::ExternalSource::RootName => None,
+            },
+            _ => None,
+        });
+        Self {
+            media: CheckFifo::new(media),
+            external: CheckFifo::new(external),
+        }
+    }
+}
+
+/// The read end of a check's fifo, kept open between checks since the writer
+/// is cut off when the last reader closes it
+#[cfg(feature = "status-bar")]
+struct CheckFifo {
+    fifo: Option<&'static UnixStr>,
+    fd: Option<RawFd>,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "status-bar")]
+impl CheckFifo {
+    fn new(fifo: Option<&'static UnixStr>) -> Self {
+        Self {
+            fifo,
+            fd: None,
+            buf: vec![0u8; 4096],
+        }
+    }
+
+    /// What's been written since the last read, empty if nothing has or if the fifo can't be opened.
+    /// Opening is retried on each read, so the fifo can be created after the WM has started
+    fn read_new(&mut self) -> &[u8] {
+        let Some(fifo) = self.fifo else {
+            return &[];
+        };
+        if self.fd.is_none() {
+            self.fd = rusl::unistd::open(
+                fifo,
+                rusl::platform::OpenFlags::O_RDONLY
+                    | rusl::platform::OpenFlags::O_NONBLOCK
+                    | rusl::platform::OpenFlags::O_CLOEXEC,
+            )
+            .ok();
+        }
+        let Some(fd) = self.fd else {
+            return &[];
+        };
+        // Nothing written since the last read is an error, as the read would block
+        match rusl::unistd::read(fd, &mut self.buf) {
+            Ok(read) => &self.buf[..read],
+            Err(_) => &[],
+        }
+    }
+}
+
+#[cfg(feature = "status-bar")]
+impl Drop for CheckFifo {
+    fn drop(&mut self) {
+        if let Some(fd) = self.fd.take() {
+            let _ = rusl::unistd::close(fd);
+        }
+    }
+}
+
 #[cfg(feature = "status-bar")]
 fn loop_with_status(
     call_wrapper: &mut CallWrapper,
     manager: &Manager,
     checker: &mut pgwm_core::status::checker::Checker,
+    fifos: &mut CheckFifos,
     state: &mut State,
 ) -> Result<()> {
-    for (next, when) in checker.get_all_check_submits() {
-        match next {
-            pgwm_core::status::checker::NextCheck::BAT => {
-                call_wrapper.uring.submit_bat_read(&when)?;
-            }
-            pgwm_core::status::checker::NextCheck::CPU => {
-                call_wrapper.uring.submit_cpu_read(&when)?;
-            }
-            pgwm_core::status::checker::NextCheck::NET => {
-                call_wrapper.uring.submit_net_read(&when)?;
-            }
-            pgwm_core::status::checker::NextCheck::MEM => {
-                call_wrapper.uring.submit_mem_read(&when)?;
-            }
-            pgwm_core::status::checker::NextCheck::Date => {
-                call_wrapper.uring.submit_date_timeout(&when)?;
-            }
-        }
-    }
+    start_due_checks(call_wrapper, manager, checker, fifos, state)?;
     crate::debug!("Starting wm loop");
     // Extremely hot place in the code, should bench the checker
     loop {
+        schedule_wakeup(call_wrapper, Some(&*checker), state)?;
         for evt in call_wrapper.uring.check_ready_cached() {
             handle_read_event(evt, call_wrapper, checker, manager, state)?;
         }
         let next = call_wrapper.uring.await_next_completion()?;
         handle_read_event(next, call_wrapper, checker, manager, state)?;
+        Manager::exec_requested_shutdown(call_wrapper, state)?;
         Manager::destroy_marked(call_wrapper, state)?;
+        reap_children();
+        manager.exec_due_edge_action(call_wrapper, state)?;
+        manager.clear_expired_title_notice(call_wrapper, state)?;
+        manager.verify_due_focus(call_wrapper, state)?;
+        Manager::reset_screensaver_if_fullscreen(call_wrapper, state)?;
+        Manager::draw_due_border_frames(call_wrapper, state)?;
+        #[cfg(feature = "animations")]
+        Manager::draw_due_slide_frame(call_wrapper, state)?;
+        #[cfg(feature = "idle")]
+        manager.exec_due_idle_action(call_wrapper, state)?;
+        if let Some(check_ind) = state.status_format_cycle.take() {
+            checker.cycle_format(check_ind);
+        }
+        if let Some((check_ind, interval)) = state.status_interval_change.take() {
+            match checker.check_at(check_ind) {
+                Some(check) => checker.set_interval(check, interval),
+                None => tiny_std::eprintln!("No status check at {check_ind}"),
+            }
+        }
+        start_due_checks(call_wrapper, manager, checker, fifos, state)?;
         #[cfg(feature = "debug")]
         call_wrapper
             .xcb_state
@@ -322,6 +531,188 @@ fn loop_with_status(
     }
 }
 
+/// Start every status check that's due, checks reading a file complete as a read event,
+/// the rest complete immediately
+#[cfg(feature = "status-bar")]
+fn start_due_checks(
+    call_wrapper: &mut CallWrapper,
+    manager: &Manager,
+    checker: &mut pgwm_core::status::checker::Checker,
+    fifos: &mut CheckFifos,
+    state: &mut State,
+) -> Result<()> {
+    let now = tiny_std::time::Instant::now();
+    while let Some(due) = checker.pop_due(now) {
+        match due {
+            pgwm_core::status::checker::NextCheck::BAT => {
+                call_wrapper.uring.submit_bat_read()?;
+            }
+            pgwm_core::status::checker::NextCheck::CPU => {
+                call_wrapper.uring.submit_cpu_read()?;
+            }
+            pgwm_core::status::checker::NextCheck::NET => {
+                call_wrapper.uring.submit_net_read()?;
+            }
+            pgwm_core::status::checker::NextCheck::MEM => {
+                call_wrapper.uring.submit_mem_read()?;
+            }
+            // Checks that don't read a file into a buffer, they're completed on the spot
+            due @ (pgwm_core::status::checker::NextCheck::Date
+            | pgwm_core::status::checker::NextCheck::Privacy) => {
+                crate::debug!("Got date or privacy event");
+                if let Some(next) = checker.handle_completed(due, &[]) {
+                    if let Some(content) = next.content {
+                        manager.draw_status(
+                            call_wrapper,
+                            content,
+                            next.position,
+                            next.alert,
+                            state,
+                        )?;
+                    }
+                }
+            }
+            pgwm_core::status::checker::NextCheck::Ping => {
+                // Shows the previous ping as lost if it hasn't been answered
+                if let Some(next) =
+                    checker.handle_completed(pgwm_core::status::checker::NextCheck::Ping, &[])
+                {
+                    if let Some(content) = next.content {
+                        manager.draw_status(
+                            call_wrapper,
+                            content,
+                            next.position,
+                            next.alert,
+                            state,
+                        )?;
+                    }
+                }
+                let request = checker.ping_request();
+                call_wrapper.uring.send_ping(&request);
+                call_wrapper.uring.submit_ping_read()?;
+            }
+            pgwm_core::status::checker::NextCheck::Media => {
+                if let Some(next) = checker.handle_completed(
+                    pgwm_core::status::checker::NextCheck::Media,
+                    fifos.media.read_new(),
+                ) {
+                    if let Some(content) = next.content {
+                        manager.draw_status(
+                            call_wrapper,
+                            content,
+                            next.position,
+                            next.alert,
+                            state,
+                        )?;
+                    }
+                }
+            }
+            pgwm_core::status::checker::NextCheck::ExternalFifo => {
+                if let Some(next) = checker.handle_completed(
+                    pgwm_core::status::checker::NextCheck::ExternalFifo,
+                    fifos.external.read_new(),
+                ) {
+                    if let Some(content) = next.content {
+                        manager.draw_status(
+                            call_wrapper,
+                            content,
+                            next.position,
+                            next.alert,
+                            state,
+                        )?;
+                    }
+                }
+            }
+            pgwm_core::status::checker::NextCheck::RootName => {
+                let name = call_wrapper.get_root_name(state.screen.root)?;
+                if let Some(next) =
+                    checker.handle_completed(pgwm_core::status::checker::NextCheck::RootName, &name)
+                {
+                    if let Some(content) = next.content {
+                        manager.draw_status(
+                            call_wrapper,
+                            content,
+                            next.position,
+                            next.alert,
+                            state,
+                        )?;
+                    }
+                }
+            }
+            pgwm_core::status::checker::NextCheck::Keyboard => {
+                // Without xkb there's no layout to show, an empty layout draws nothing
+                #[cfg(feature = "xkb")]
+                let (group, layouts) = call_wrapper.get_keyboard_layouts(state.screen.root)?;
+                #[cfg(feature = "xkb")]
+                let layout = layouts
+                    .get(usize::from(group))
+                    .map_or("", alloc::string::String::as_str);
+                #[cfg(not(feature = "xkb"))]
+                let layout = "";
+                if let Some(next) = checker.handle_completed(
+                    pgwm_core::status::checker::NextCheck::Keyboard,
+                    layout.as_bytes(),
+                ) {
+                    if let Some(content) = next.content {
+                        manager.draw_status(
+                            call_wrapper,
+                            content,
+                            next.position,
+                            next.alert,
+                            state,
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Arm a single wakeup at the earliest deadline across status checks, idle checks, screensaver resets,
+/// border transition frames, focus checks and windows marked for death, instead of waking up for each of them separately
+fn schedule_wakeup(
+    call_wrapper: &mut CallWrapper,
+    #[cfg(feature = "status-bar")] checker: Option<&pgwm_core::status::checker::Checker>,
+    state: &State,
+) -> Result<()> {
+    let mut next = state.next_dying_window_deadline();
+    if let Some(edge) = state.next_edge_dwell_deadline() {
+        next = Some(next.map_or(edge, |dying| dying.min(edge)));
+    }
+    if let Some(idle) = state.next_idle_check {
+        next = Some(next.map_or(idle, |dying| dying.min(idle)));
+    }
+    if let Some(reset) = state.next_screensaver_reset {
+        next = Some(next.map_or(reset, |dying| dying.min(reset)));
+    }
+    if let Some(frame) = state.border_transitions.next_frame() {
+        next = Some(next.map_or(frame, |dying| dying.min(frame)));
+    }
+    #[cfg(feature = "animations")]
+    if let Some(frame) = state
+        .workspace_slide
+        .as_ref()
+        .and_then(pgwm_core::state::slide::WorkspaceSlide::next_frame)
+    {
+        next = Some(next.map_or(frame, |dying| dying.min(frame)));
+    }
+    if let Some(notice) = state.title_notice.as_ref().map(|notice| notice.until) {
+        next = Some(next.map_or(notice, |dying| dying.min(notice)));
+    }
+    if let Some(focus) = state.pending_focus.as_ref().map(|focus| focus.verify_at) {
+        next = Some(next.map_or(focus, |dying| dying.min(focus)));
+    }
+    #[cfg(feature = "status-bar")]
+    if let Some(check) = checker.and_then(pgwm_core::status::checker::Checker::next_deadline) {
+        next = Some(next.map_or(check, |dying| dying.min(check)));
+    }
+    if let Some(next) = next {
+        call_wrapper.uring.submit_wakeup(&next)?;
+    }
+    Ok(())
+}
+
 #[inline]
 fn handle_read_event(
     next: UringReadEvent,
@@ -332,13 +723,32 @@ fn handle_read_event(
 ) -> Result<()> {
     match next {
         UringReadEvent::SockIn => {
-            for event in xcb_rust_connection::connection::try_drain(
+            let mut events = xcb_rust_connection::connection::try_drain(
                 &mut call_wrapper.uring,
                 &mut call_wrapper.xcb_state,
-            )? {
+            )?
+            .into_iter()
+            .peekable();
+            while let Some(event) = events.next() {
+                // Fast drags queue up lots of motion, only the latest position needs to be drawn
+                if (state.drag_window.is_some() || state.dragged_tab.is_some())
+                    && events
+                        .peek()
+                        .map_or(false, |next| is_superseded_motion(&event, next))
+                {
+                    crate::debug!("Skipping superseded motion event");
+                    continue;
+                }
                 handle_event(event, call_wrapper, manager, state)?;
             }
         }
+        UringReadEvent::Wakeup => {
+            // Whatever is due is handled after each completion
+            crate::debug!("Got wakeup");
+        }
+        UringReadEvent::Shutdown => {
+            crate::debug!("Got shutdown signal");
+        }
         #[cfg(feature = "status-bar")]
         UringReadEvent::Bat => {
             crate::debug!("Got bat event");
@@ -347,9 +757,8 @@ fn handle_read_event(
                 call_wrapper.uring.read_bat().unwrap(),
             ) {
                 if let Some(content) = next.content {
-                    manager.draw_status(call_wrapper, content, next.position, state)?;
+                    manager.draw_status(call_wrapper, content, next.position, next.alert, state)?;
                 }
-                call_wrapper.uring.submit_bat_read(&next.next_check)?;
             }
         }
         #[cfg(feature = "status-bar")]
@@ -360,12 +769,22 @@ fn handle_read_event(
                 call_wrapper.uring.read_net().unwrap(),
             ) {
                 if let Some(content) = next.content {
-                    manager.draw_status(call_wrapper, content, next.position, state)?;
+                    manager.draw_status(call_wrapper, content, next.position, next.alert, state)?;
                 }
-                call_wrapper.uring.submit_net_read(&next.next_check)?;
             }
         }
         #[cfg(feature = "status-bar")]
+        UringReadEvent::Ping => {
+            crate::debug!("Got ping event");
+            if let Some(next) = checker.handle_ping_reply(call_wrapper.uring.read_ping().unwrap()) {
+                if let Some(content) = next.content {
+                    manager.draw_status(call_wrapper, content, next.position, next.alert, state)?;
+                }
+            }
+            // Keep listening, late replies to earlier pings are read and ignored
+            call_wrapper.uring.submit_ping_read()?;
+        }
+        #[cfg(feature = "status-bar")]
         UringReadEvent::Mem => {
             crate::debug!("Got mem event");
             if let Some(next) = checker.handle_completed(
@@ -373,9 +792,8 @@ fn handle_read_event(
                 call_wrapper.uring.read_mem().unwrap(),
             ) {
                 if let Some(content) = next.content {
-                    manager.draw_status(call_wrapper, content, next.position, state)?;
+                    manager.draw_status(call_wrapper, content, next.position, next.alert, state)?;
                 }
-                call_wrapper.uring.submit_mem_read(&next.next_check)?;
             }
         }
         #[cfg(feature = "status-bar")]
@@ -386,22 +804,8 @@ fn handle_read_event(
                 call_wrapper.uring.read_cpu().unwrap(),
             ) {
                 if let Some(content) = next.content {
-                    manager.draw_status(call_wrapper, content, next.position, state)?;
-                }
-                call_wrapper.uring.submit_cpu_read(&next.next_check)?;
-            }
-        }
-        #[cfg(feature = "status-bar")]
-        UringReadEvent::DateTimeout => {
-            crate::debug!("Got date event");
-            if let Some(next) =
-                checker.handle_completed(pgwm_core::status::checker::NextCheck::Date, &[])
-            {
-                call_wrapper.uring.read_date();
-                if let Some(content) = next.content {
-                    manager.draw_status(call_wrapper, content, next.position, state)?;
+                    manager.draw_status(call_wrapper, content, next.position, next.alert, state)?;
                 }
-                call_wrapper.uring.submit_date_timeout(&next.next_check)?;
             }
         }
     }
@@ -421,6 +825,10 @@ fn loop_without_status<'a>(
     crate::debug!("Starting wm loop");
     // Extremely hot place in the code, should bench the checker
     loop {
+        #[cfg(feature = "status-bar")]
+        schedule_wakeup(call_wrapper, None, state)?;
+        #[cfg(not(feature = "status-bar"))]
+        schedule_wakeup(call_wrapper, state)?;
         crate::debug!("Checking cached");
         for evt in call_wrapper.uring.check_ready_cached() {
             #[cfg(feature = "status-bar")]
@@ -436,7 +844,18 @@ fn loop_without_status<'a>(
         #[cfg(not(feature = "status-bar"))]
         handle_read_event(next, call_wrapper, manager, state)?;
         crate::debug!("Handled next completion");
+        Manager::exec_requested_shutdown(call_wrapper, state)?;
         Manager::destroy_marked(call_wrapper, state)?;
+        reap_children();
+        manager.exec_due_edge_action(call_wrapper, state)?;
+        manager.clear_expired_title_notice(call_wrapper, state)?;
+        manager.verify_due_focus(call_wrapper, state)?;
+        Manager::reset_screensaver_if_fullscreen(call_wrapper, state)?;
+        Manager::draw_due_border_frames(call_wrapper, state)?;
+        #[cfg(feature = "animations")]
+        Manager::draw_due_slide_frame(call_wrapper, state)?;
+        #[cfg(feature = "idle")]
+        manager.exec_due_idle_action(call_wrapper, state)?;
         #[cfg(feature = "debug")]
         call_wrapper
             .xcb_state
@@ -448,6 +867,25 @@ fn loop_without_status<'a>(
     }
 }
 
+/// A motion event that's directly followed by another one on the same window, with the same
+/// buttons held, is superseded by it
+fn is_superseded_motion(event: &[u8], next: &[u8]) -> bool {
+    let parse_motion = |raw: &[u8]| {
+        let response_type = raw.first().map(|x| x & 0x7f)?;
+        if response_type == xcb_rust_protocol::proto::xproto::MOTION_NOTIFY_EVENT {
+            MotionNotifyEvent::from_bytes(raw).ok()
+        } else {
+            None
+        }
+    };
+    match (parse_motion(event), parse_motion(next)) {
+        (Some(motion), Some(next_motion)) => {
+            motion.event == next_motion.event && motion.state.0 == next_motion.state.0
+        }
+        _ => false,
+    }
+}
+
 #[inline]
 fn handle_event<'a>(
     raw: Vec<u8>,
@@ -463,11 +901,15 @@ fn handle_event<'a>(
         .ok_or(Error::X11EventParse)?;
 
     #[cfg(feature = "debug")]
-    dbg_event(&raw, &call_wrapper.xcb_state.extensions);
-    // Unmap and enter are caused by upstream actions, causing unwanted focusing behaviour etc.
+    {
+        dbg_event(&raw, &call_wrapper.xcb_state.extensions);
+        if let Some(description) = call_wrapper.uring.recent_requests.describe_error(&raw) {
+            crate::debug!("Got X11 error: {description}");
+        }
+    }
+    // Enter is caused by upstream actions, causing unwanted focusing behaviour etc.
     if state.should_ignore_sequence(seq)
-        && (response_type == xcb_rust_protocol::proto::xproto::ENTER_NOTIFY_EVENT
-            || response_type == xcb_rust_protocol::proto::xproto::UNMAP_NOTIFY_EVENT)
+        && response_type == xcb_rust_protocol::proto::xproto::ENTER_NOTIFY_EVENT
     {
         pgwm_utils::debug!("[Ignored]");
         return Ok(());
@@ -481,6 +923,13 @@ fn handle_event<'a>(
                 state,
             )?;
         }
+        xcb_rust_protocol::proto::xproto::KEY_RELEASE_EVENT => {
+            Manager::handle_key_release(
+                call_wrapper,
+                KeyReleaseEvent::from_bytes(&raw).unwrap(),
+                state,
+            )?;
+        }
         xcb_rust_protocol::proto::xproto::MAP_REQUEST_EVENT => {
             manager.handle_map_request(
                 call_wrapper,
@@ -488,9 +937,21 @@ fn handle_event<'a>(
                 state,
             )?;
         }
+        xcb_rust_protocol::proto::xproto::MAP_NOTIFY_EVENT => {
+            Manager::handle_map_notify(
+                call_wrapper,
+                MapNotifyEvent::from_bytes(&raw).unwrap(),
+                state,
+            )?;
+        }
         xcb_rust_protocol::proto::xproto::UNMAP_NOTIFY_EVENT => {
             let evt = UnmapNotifyEvent::from_bytes(&raw).unwrap();
-            manager.handle_unmap_notify(call_wrapper, evt, state)?;
+            // Windows hidden by the WM stay managed, windows withdrawn by their clients are unmanaged
+            if state.is_wm_unmap(evt.window, seq, raw[0] & 0x80 != 0) {
+                pgwm_utils::debug!("[Ignored]");
+            } else {
+                manager.handle_unmap_notify(call_wrapper, evt, state)?;
+            }
         }
         xcb_rust_protocol::proto::xproto::DESTROY_NOTIFY_EVENT => {
             manager.handle_destroy_notify(
@@ -514,30 +975,32 @@ fn handle_event<'a>(
             )?;
         }
         xcb_rust_protocol::proto::xproto::BUTTON_PRESS_EVENT => {
-            manager.handle_button_press(
-                call_wrapper,
-                ButtonPressEvent::from_bytes(&raw).unwrap(),
-                state,
-            )?;
+            let mut evt = ButtonPressEvent::from_bytes(&raw).unwrap();
+            // With the pointer grabbed on the root the child is the window under it, or its frame
+            evt.child.0 = call_wrapper.client_of(evt.child.0);
+            manager.handle_button_press(call_wrapper, evt, state)?;
         }
         xcb_rust_protocol::proto::xproto::BUTTON_RELEASE_EVENT => {
-            manager.handle_button_release(
-                call_wrapper,
-                ButtonReleaseEvent::from_bytes(&raw).unwrap(),
-                state,
-            )?;
+            let mut evt = ButtonReleaseEvent::from_bytes(&raw).unwrap();
+            evt.child.0 = call_wrapper.client_of(evt.child.0);
+            manager.handle_button_release(call_wrapper, evt, state)?;
         }
         xcb_rust_protocol::proto::xproto::MOTION_NOTIFY_EVENT => {
-            manager.handle_motion_notify(
-                call_wrapper,
-                MotionNotifyEvent::from_bytes(&raw).unwrap(),
-                state,
-            )?;
+            let mut evt = MotionNotifyEvent::from_bytes(&raw).unwrap();
+            evt.child.0 = call_wrapper.client_of(evt.child.0);
+            manager.handle_motion_notify(call_wrapper, evt, state)?;
         }
         xcb_rust_protocol::proto::xproto::ENTER_NOTIFY_EVENT => {
             let evt = EnterNotifyEvent::from_bytes(&raw).unwrap();
             manager.handle_enter(call_wrapper, evt, state)?;
         }
+        xcb_rust_protocol::proto::xproto::LEAVE_NOTIFY_EVENT => {
+            manager.handle_leave(
+                call_wrapper,
+                LeaveNotifyEvent::from_bytes(&raw).unwrap(),
+                state,
+            )?;
+        }
         xcb_rust_protocol::proto::xproto::CLIENT_MESSAGE_EVENT => {
             manager.handle_client_message(
                 call_wrapper,
@@ -552,6 +1015,20 @@ fn handle_event<'a>(
                 state,
             )?;
         }
+        xcb_rust_protocol::proto::xproto::MAPPING_NOTIFY_EVENT => {
+            Manager::handle_mapping_notify(
+                call_wrapper,
+                MappingNotifyEvent::from_bytes(&raw).unwrap(),
+                state,
+            )?;
+        }
+        xcb_rust_protocol::proto::xproto::COLORMAP_NOTIFY_EVENT => {
+            Manager::handle_colormap_notify(
+                call_wrapper,
+                ColormapNotifyEvent::from_bytes(&raw).unwrap(),
+                state,
+            )?;
+        }
         xcb_rust_protocol::proto::xproto::VISIBILITY_NOTIFY_EVENT => {
             manager.handle_visibility_change(
                 call_wrapper,
@@ -561,6 +1038,13 @@ fn handle_event<'a>(
         }
         _ => {}
     }
+    #[cfg(feature = "invariants")]
+    if let Err(violation) = state.check_invariants() {
+        tiny_std::eprintln!(
+            "State invariant violated after handling event {response_type}: {violation}"
+        );
+        panic!("State invariant violated: {violation}");
+    }
     Ok(())
 }
 
@@ -7,10 +7,14 @@ use xcb_rust_protocol::con::XcbState;
 use xcb_rust_protocol::connection::render::{
     add_glyphs, composite_glyphs16, create_glyph_set, create_picture, fill_rectangles,
 };
+#[cfg(feature = "frames")]
+use xcb_rust_protocol::connection::xproto::{change_save_set, create_window, reparent_window};
 use xcb_rust_protocol::connection::xproto::{
-    change_window_attributes, configure_window, delete_property, destroy_window, get_geometry,
-    get_property, get_window_attributes, grab_pointer, intern_atom, kill_client, map_window,
-    query_pointer, query_tree, send_event, set_input_focus, ungrab_pointer, unmap_window,
+    change_window_attributes, clear_area, configure_window, create_g_c, delete_property,
+    destroy_window, force_screen_saver, free_g_c, get_geometry, get_input_focus,
+    get_keyboard_mapping, get_property, get_window_attributes, grab_keyboard, grab_pointer,
+    install_colormap, intern_atom, kill_client, map_window, put_image, query_pointer, query_tree,
+    send_event, set_input_focus, ungrab_keyboard, ungrab_pointer, unmap_window,
 };
 use xcb_rust_protocol::cookie::{Cookie, FixedCookie, VoidCookie};
 use xcb_rust_protocol::helpers::properties::{
@@ -22,20 +26,26 @@ use xcb_rust_protocol::proto::render::{
     RepeatEnum,
 };
 use xcb_rust_protocol::proto::xproto::{
-    Atom, AtomEnum, ChangeWindowAttributesValueList, ConfigWindow, ConfigureRequestEvent,
-    ConfigureWindowValueList, CursorEnum, EventMask, GetGeometryReply, GetPropertyReply,
-    GetPropertyTypeEnum, GetWindowAttributesReply, GrabModeEnum, InputFocusEnum, InternAtomReply,
-    PropModeEnum, QueryPointerReply, QueryTreeReply, Screen, StackModeEnum, Timestamp, Window,
-    WindowEnum,
+    Atom, AtomEnum, ChangeWindowAttributesValueList, Colormap, ConfigWindow, ConfigureRequestEvent,
+    ConfigureWindowValueList, CreateGCValueList, CursorEnum, EventMask, GetGeometryReply,
+    GetPropertyReply, GetPropertyTypeEnum, GetWindowAttributesReply, GrabModeEnum, ImageFormatEnum,
+    InputFocusEnum, InternAtomReply, Pixmap, PropModeEnum, QueryPointerReply, QueryTreeReply,
+    Screen, ScreenSaverEnum, StackModeEnum, Timestamp, Window, WindowEnum,
 };
+#[cfg(feature = "frames")]
+use xcb_rust_protocol::proto::xproto::{CreateWindowValueList, SetModeEnum, WindowClassEnum};
+#[cfg(feature = "frames")]
+use xcb_rust_protocol::COPY_DEPTH_FROM_PARENT;
 use xcb_rust_protocol::{CURRENT_TIME, NONE};
 
+use pgwm_core::config::key_map::KeysymTable;
 use pgwm_core::config::{
-    STATUS_BAR_HEIGHT, WINDOW_MANAGER_NAME, X11_CURSOR_NAME, _WINDOW_MANAGER_NAME_BUF_SIZE,
-    _WM_CLASS_NAME_LIMIT, _WM_NAME_LIMIT,
+    WINDOW_MANAGER_NAME, X11_CURSOR_NAME, _WINDOW_MANAGER_NAME_BUF_SIZE, _WM_CLASS_NAME_LIMIT,
+    _WM_NAME_LIMIT,
 };
 use pgwm_core::geometry::Dimensions;
 use pgwm_core::push_heapless;
+use pgwm_core::render::image::Image;
 use pgwm_core::render::{DoubleBufferedRenderPicture, RenderVisualInfo};
 use pgwm_core::state::properties::{
     NetWmState, Protocol, WindowProperties, WindowType, WmName, WmState,
@@ -47,7 +57,7 @@ use crate::error::Error::GlyphMismatch;
 use crate::error::{Error, Result};
 use crate::uring::UringWrapper;
 
-const MAX_STORED_ATOMS: usize = 64;
+const MAX_STORED_ATOMS: usize = 128;
 
 pub(crate) trait PropFirstU32 {
     fn first_u32(&self) -> Option<u32>;
@@ -75,7 +85,7 @@ macro_rules! impl_atoms {
             fn init_maps(uring_wrapper: &mut UringWrapper, evt_state: &mut XcbEventState, ) -> Result<(Map<&'static [u8], ResolvedAtom>, Map<Atom, ResolvedAtom>)> {
                     let mut name_to_atom = Map::new();
                     let mut atom_to_resolved = Map::new();
-                    let mut cookies = heapless::Deque::<FixedCookie<InternAtomReply, 12>, 64>::new();
+                    let mut cookies = heapless::Deque::<FixedCookie<InternAtomReply, 12>, MAX_STORED_ATOMS>::new();
         $(
                     cookies.push_back(intern_atom(uring_wrapper, evt_state, 0, $const_name, false)?)
                     .expect("Not enough space for intern atoms");
@@ -131,6 +141,9 @@ impl_atoms!(
     WM_CLIENT_LEADER,
     WmClientLeader,
     false,
+    WM_COLORMAP_WINDOWS,
+    WmColormapWindows,
+    false,
     _NET_WM_WINDOW_TYPE,
     NetWmWindowType,
     true,
@@ -230,6 +243,9 @@ impl_atoms!(
     _NET_WM_STATE_DEMANDS_ATTENTION,
     NetWmStateDemandsAttention,
     true,
+    _NET_WM_FULLSCREEN_MONITORS,
+    NetWmFullscreenMonitors,
+    true,
     _NET_WM_NAME,
     NetWmName,
     true,
@@ -289,7 +305,23 @@ impl_atoms!(
     true,
     _NET_WM_SYNC_REQUEST_COUNTER,
     NetWmSyncRequestCounter,
-    true
+    true,
+    // Listed as supported so that GTK draws shadows it can tell the size of
+    _GTK_FRAME_EXTENTS,
+    GtkFrameExtents,
+    true,
+    _XKB_RULES_NAMES,
+    XkbRulesNames,
+    false,
+    _PGWM_COMMAND,
+    PgwmCommand,
+    false,
+    _PGWM_REPLY,
+    PgwmReply,
+    false,
+    UTF8_STRING,
+    Utf8String,
+    false
 );
 
 #[derive(Clone, Copy, Debug)]
@@ -304,6 +336,21 @@ pub(crate) struct CallWrapper {
     pub(crate) xcb_state: XcbEventState,
     name_to_atom: Map<&'static [u8], ResolvedAtom>,
     atom_to_resolved: Map<Atom, ResolvedAtom>,
+    property_cache: Map<Window, CachedProperties>,
+    /// Managed clients and the frames they've been reparented into
+    #[cfg(feature = "frames")]
+    frames: Vec<(Window, Window)>,
+}
+
+type WmClass = heapless::Vec<heapless::String<_WM_CLASS_NAME_LIMIT>, 4>;
+
+/// Replies for properties that are fetched often but rarely change.
+/// Only kept for windows that we've selected `PropertyNotify` on, since that's what invalidates them.
+#[derive(Default)]
+struct CachedProperties {
+    transient_for: Option<Option<Window>>,
+    class: Option<Option<WmClass>>,
+    protocols: Option<heapless::Vec<Protocol, 4>>,
 }
 
 impl CallWrapper {
@@ -322,12 +369,42 @@ impl CallWrapper {
         #[cfg_attr(not(feature = "debug"), allow(unused))]
         if let Err(e) = res {
             pgwm_utils::debug!("Fatal error, Failed to start WM, is another WM running? {e}");
-            Err(Error::BecomeWm)
+            Err(Error::BecomeWm(self.running_wm_name(screen.root)))
         } else {
             Ok(())
         }
     }
 
+    /// The name of the running EWMH compliant WM, through the `_NET_WM_NAME` of the window
+    /// it points to with `_NET_SUPPORTING_WM_CHECK` on the root
+    fn running_wm_name(&mut self, root: Window) -> Option<String> {
+        let check_win = get_property(
+            &mut self.uring,
+            &mut self.xcb_state,
+            0,
+            root,
+            self.name_to_atom
+                .get(&_NET_SUPPORTING_WM_CHECK)
+                .unwrap()
+                .value,
+            GetPropertyTypeEnum(AtomEnum::WINDOW.0),
+            0,
+            1,
+            false,
+        )
+        .ok()?
+        .reply(&mut self.uring, &mut self.xcb_state)
+        .ok()?
+        .first_u32()?;
+        // A WM that crashed may have left a stale check window id behind, which makes this fail
+        let name = self
+            .get_net_wm_name(check_win)
+            .ok()?
+            .await_name(self)
+            .ok()??;
+        Some(String::from(name.as_str()))
+    }
+
     #[allow(clippy::too_many_lines)]
     pub(crate) fn set_default_manager_props(&mut self, state: &State) -> Result<()> {
         change_property32(
@@ -355,19 +432,6 @@ impl CallWrapper {
             &[],
             true,
         )?;
-        change_property32(
-            &mut self.uring,
-            &mut self.xcb_state,
-            PropModeEnum::REPLACE,
-            state.screen.root,
-            self.name_to_atom
-                .get(&_NET_NUMBER_OF_DESKTOPS)
-                .unwrap()
-                .value,
-            AtomEnum::CARDINAL.0,
-            &[0],
-            true,
-        )?;
         let utf8 = WINDOW_MANAGER_NAME
             .chars()
             .chain(core::iter::once('\u{0}'))
@@ -389,20 +453,21 @@ impl CallWrapper {
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
             state.screen.root,
-            self.name_to_atom.get(&_NET_CURRENT_DESKTOP).unwrap().value,
+            self.name_to_atom.get(&_NET_DESKTOP_VIEWPORT).unwrap().value,
             AtomEnum::CARDINAL.0,
-            &[0],
+            &[0; 2],
             true,
         )?;
-
+        self.set_desktop_properties(state)?;
+        self.set_showing_desktop(state)?;
         change_property32(
             &mut self.uring,
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
             state.screen.root,
-            self.name_to_atom.get(&_NET_DESKTOP_VIEWPORT).unwrap().value,
+            self.name_to_atom.get(&_NET_ACTIVE_WINDOW).unwrap().value,
             AtomEnum::CARDINAL.0,
-            &[0; 2],
+            &[],
             true,
         )?;
         change_property32(
@@ -410,27 +475,56 @@ impl CallWrapper {
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
             state.screen.root,
-            self.name_to_atom.get(&_NET_DESKTOP_GEOMETRY).unwrap().value,
-            AtomEnum::CARDINAL.0,
-            &[
-                state.screen.width_in_pixels as u32,
-                state.screen.height_in_pixels as u32,
-            ],
+            self.name_to_atom
+                .get(&_NET_SUPPORTING_WM_CHECK)
+                .unwrap()
+                .value,
+            AtomEnum::WINDOW.0,
+            &[state.wm_check_win],
+            true,
+        )?;
+        change_property32(
+            &mut self.uring,
+            &mut self.xcb_state,
+            PropModeEnum::REPLACE,
+            state.wm_check_win,
+            self.name_to_atom
+                .get(&_NET_SUPPORTING_WM_CHECK)
+                .unwrap()
+                .value,
+            AtomEnum::WINDOW.0,
+            &[state.wm_check_win],
+            true,
+        )?;
+        change_property8(
+            &mut self.uring,
+            &mut self.xcb_state,
+            PropModeEnum::REPLACE,
+            state.wm_check_win,
+            self.name_to_atom.get(&_NET_WM_NAME).unwrap().value,
+            AtomEnum::STRING.0,
+            WINDOW_MANAGER_NAME.as_bytes(),
             true,
         )?;
+        Ok(())
+    }
+
+    /// Publish the workspaces as desktops, with the currently focused one, the overall desktop
+    /// geometry, and each workspace's work area so that maximizing clients size themselves correctly.
+    /// Needs to be called again whenever any of those change.
+    pub(crate) fn set_desktop_properties(&mut self, state: &State) -> Result<()> {
+        let num_workspaces = state.workspaces.num_workspaces();
         change_property32(
             &mut self.uring,
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
             state.screen.root,
-            self.name_to_atom.get(&_NET_WORKAREA).unwrap().value,
+            self.name_to_atom
+                .get(&_NET_NUMBER_OF_DESKTOPS)
+                .unwrap()
+                .value,
             AtomEnum::CARDINAL.0,
-            &[
-                0,
-                STATUS_BAR_HEIGHT as u32,
-                state.screen.width_in_pixels as u32,
-                state.screen.height_in_pixels as u32 - STATUS_BAR_HEIGHT as u32,
-            ],
+            &[num_workspaces as u32],
             true,
         )?;
         change_property32(
@@ -438,9 +532,9 @@ impl CallWrapper {
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
             state.screen.root,
-            self.name_to_atom.get(&_NET_ACTIVE_WINDOW).unwrap().value,
+            self.name_to_atom.get(&_NET_CURRENT_DESKTOP).unwrap().value,
             AtomEnum::CARDINAL.0,
-            &[],
+            &[state.monitors[state.focused_mon].hosted_workspace as u32],
             true,
         )?;
         change_property32(
@@ -448,35 +542,49 @@ impl CallWrapper {
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
             state.screen.root,
-            self.name_to_atom
-                .get(&_NET_SUPPORTING_WM_CHECK)
-                .unwrap()
-                .value,
-            AtomEnum::WINDOW.0,
-            &[state.wm_check_win],
+            self.name_to_atom.get(&_NET_DESKTOP_GEOMETRY).unwrap().value,
+            AtomEnum::CARDINAL.0,
+            &[
+                state.screen.width_in_pixels as u32,
+                state.screen.height_in_pixels as u32,
+            ],
             true,
         )?;
+        let mut workareas = Vec::with_capacity(num_workspaces * 4);
+        for ws_ind in 0..num_workspaces {
+            let area = state.workarea(ws_ind);
+            workareas.extend_from_slice(&[
+                area.x as u32,
+                area.y as u32,
+                area.width as u32,
+                area.height as u32,
+            ]);
+        }
         change_property32(
             &mut self.uring,
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
-            state.wm_check_win,
-            self.name_to_atom
-                .get(&_NET_SUPPORTING_WM_CHECK)
-                .unwrap()
-                .value,
-            AtomEnum::WINDOW.0,
-            &[state.wm_check_win],
+            state.screen.root,
+            self.name_to_atom.get(&_NET_WORKAREA).unwrap().value,
+            AtomEnum::CARDINAL.0,
+            &workareas,
             true,
         )?;
-        change_property8(
+        Ok(())
+    }
+
+    /// Publish whether any monitor is showing the desktop, see
+    /// [`pgwm_core::config::Action::ToggleShowDesktop`]
+    pub(crate) fn set_showing_desktop(&mut self, state: &State) -> Result<()> {
+        let showing = state.monitors.iter().any(|mon| mon.showing_desktop);
+        change_property32(
             &mut self.uring,
             &mut self.xcb_state,
             PropModeEnum::REPLACE,
-            state.wm_check_win,
-            self.name_to_atom.get(&_NET_WM_NAME).unwrap().value,
-            AtomEnum::STRING.0,
-            WINDOW_MANAGER_NAME.as_bytes(),
+            state.screen.root,
+            self.name_to_atom.get(&_NET_SHOWING_DESKTOP).unwrap().value,
+            AtomEnum::CARDINAL.0,
+            &[u32::from(showing)],
             true,
         )?;
         Ok(())
@@ -499,6 +607,8 @@ impl CallWrapper {
         let pid = self.get_pid(window)?;
         let protocols = self.get_protocols(window)?;
         let transient_for = self.get_is_transient_for(window)?;
+        let colormap_windows = self.get_colormap_windows(window)?;
+        let gtk_frame_extents = self.get_gtk_frame_extents(window)?;
 
         Ok(WindowPropertiesCookie {
             wm_state,
@@ -513,6 +623,8 @@ impl CallWrapper {
             pid,
             leader,
             transient_for,
+            colormap_windows,
+            gtk_frame_extents,
         })
     }
 
@@ -523,12 +635,98 @@ impl CallWrapper {
                 | EventMask::PROPERTY_CHANGE
                 | EventMask::VISIBILITY_CHANGE
                 | EventMask::EXPOSURE
-                | EventMask::STRUCTURE_NOTIFY,
+                | EventMask::STRUCTURE_NOTIFY
+                | EventMask::COLORMAP_CHANGE,
         );
         change_window_attributes(&mut self.uring, &mut self.xcb_state, window, cw, true)?;
+        // Changes from here on are announced through `PropertyNotify`, replies can be cached
+        if !self.property_cache.contains_key(&window) {
+            self.property_cache
+                .insert(window, CachedProperties::default());
+        }
+        Ok(())
+    }
+
+    /// Listen for colormap changes on the subwindows of `WM_COLORMAP_WINDOWS`, the top-level window
+    /// already gets them through its base event mask
+    pub(crate) fn select_colormap_changes(
+        &mut self,
+        toplevel: Window,
+        colormap_windows: &[Window],
+    ) -> Result<()> {
+        for win in colormap_windows.iter().filter(|win| **win != toplevel) {
+            let cw =
+                ChangeWindowAttributesValueList::default().event_mask(EventMask::COLORMAP_CHANGE);
+            change_window_attributes(&mut self.uring, &mut self.xcb_state, *win, cw, true)?;
+        }
+        Ok(())
+    }
+
+    /// Install the colormaps that a focused window needs, [ICCCM 4.1.8](https://tronche.com/gui/x/icccm/sec-4.html#s-4.1.8).
+    /// `WM_COLORMAP_WINDOWS` is in priority order, the top-level window goes first if it isn't listed.
+    /// Installed lowest priority first so that the highest priority colormap ends up on top.
+    pub(crate) fn install_colormaps(
+        &mut self,
+        toplevel: Window,
+        colormap_windows: &[Window],
+    ) -> Result<()> {
+        let mut cookies = heapless::Vec::<FixedCookie<GetWindowAttributesReply, 44>, 9>::new();
+        if !colormap_windows.contains(&toplevel) {
+            let _ = push_heapless!(cookies, self.get_window_attributes(toplevel)?);
+        }
+        for win in colormap_windows {
+            let _ = push_heapless!(cookies, self.get_window_attributes(*win)?);
+        }
+        let mut colormaps = heapless::Vec::<Colormap, 9>::new();
+        for cookie in cookies {
+            // Listed subwindows may already be destroyed
+            if let Ok(attrs) = cookie.reply(&mut self.uring, &mut self.xcb_state) {
+                if attrs.colormap != NONE && !colormaps.contains(&attrs.colormap) {
+                    let _ = push_heapless!(colormaps, attrs.colormap);
+                }
+            }
+        }
+        for colormap in colormaps.iter().rev() {
+            install_colormap(&mut self.uring, &mut self.xcb_state, *colormap, true)?;
+        }
+        Ok(())
+    }
+
+    /// Install the screen's default colormap when no client has focus
+    pub(crate) fn install_default_colormap(&mut self, state: &State) -> Result<()> {
+        install_colormap(
+            &mut self.uring,
+            &mut self.xcb_state,
+            state.screen.default_colormap,
+            true,
+        )?;
         Ok(())
     }
 
+    /// Drop a cached property after a `PropertyNotify` for it
+    pub(crate) fn invalidate_cached_property(&mut self, window: Window, atom: Atom) {
+        let protocols_atom = self.name_to_atom.get(&WM_PROTOCOLS).unwrap().value;
+        if let Some(cached) = self.property_cache.get_mut(&window) {
+            if atom == AtomEnum::WM_TRANSIENT_FOR.0 {
+                cached.transient_for = None;
+            } else if atom == AtomEnum::WM_CLASS.0 {
+                cached.class = None;
+            } else if atom == protocols_atom {
+                cached.protocols = None;
+            }
+        }
+    }
+
+    /// Stop caching properties of a window that's no longer managed, its id may be reused
+    pub(crate) fn forget_cached_properties(&mut self, window: Window) {
+        self.property_cache.remove(&window);
+    }
+
+    /// The window to cache a reply for, if its property changes are watched
+    fn cache_target(&self, window: Window) -> Option<Window> {
+        self.property_cache.contains_key(&window).then_some(window)
+    }
+
     pub(crate) fn set_base_client_properties(&mut self, window: Window) -> Result<()> {
         change_property32(
             &mut self.uring,
@@ -600,7 +798,14 @@ impl CallWrapper {
         )?)
     }
 
+    /// The window that currently has the input focus, waits for the reply
+    pub(crate) fn input_focus(&mut self) -> Result<Window> {
+        let cookie = get_input_focus(&mut self.uring, &mut self.xcb_state, false)?;
+        Ok(cookie.reply(&mut self.uring, &mut self.xcb_state)?.focus)
+    }
+
     pub(crate) fn get_dimensions(&mut self, window: Window) -> Result<DimensionsCookie> {
+        let window = self.outer(window);
         Ok(DimensionsCookie {
             inner: get_geometry(&mut self.uring, &mut self.xcb_state, window, false)?,
         })
@@ -619,7 +824,12 @@ impl CallWrapper {
     }
 
     pub(crate) fn get_class_names(&mut self, win: Window) -> Result<WmClassCookie> {
-        let inner = get_property(
+        if let Some(class) = self.property_cache.get(&win).and_then(|c| c.class.clone()) {
+            return Ok(WmClassCookie {
+                inner: PropertyCookie::Cached(class),
+            });
+        }
+        let cookie = get_property(
             &mut self.uring,
             &mut self.xcb_state,
             0,
@@ -630,7 +840,12 @@ impl CallWrapper {
             64,
             false,
         )?;
-        Ok(WmClassCookie { inner })
+        Ok(WmClassCookie {
+            inner: PropertyCookie::Pending {
+                cache_for: self.cache_target(win),
+                cookie,
+            },
+        })
     }
 
     pub(crate) fn get_wm_name(&mut self, win: Window) -> Result<NameCookie> {
@@ -649,6 +864,24 @@ impl CallWrapper {
         })
     }
 
+    /// The root window's name as set by `xsetroot -name`, empty if it isn't set
+    #[cfg(feature = "status-bar")]
+    pub(crate) fn get_root_name(&mut self, root: Window) -> Result<Vec<u8>> {
+        Ok(get_property(
+            &mut self.uring,
+            &mut self.xcb_state,
+            0,
+            root,
+            AtomEnum::WM_NAME.0,
+            GetPropertyTypeEnum(AtomEnum::ANY.0),
+            0,
+            256,
+            false,
+        )?
+        .reply(&mut self.uring, &mut self.xcb_state)?
+        .value)
+    }
+
     pub(crate) fn get_net_wm_name(&mut self, win: Window) -> Result<NameCookie> {
         Ok(NameCookie {
             inner: get_property(
@@ -665,8 +898,13 @@ impl CallWrapper {
         })
     }
 
-    pub(crate) fn get_is_transient_for(&mut self, win: Window) -> Result<SingleCardCookie> {
-        let inner = get_property(
+    pub(crate) fn get_is_transient_for(&mut self, win: Window) -> Result<TransientForCookie> {
+        if let Some(transient_for) = self.property_cache.get(&win).and_then(|c| c.transient_for) {
+            return Ok(TransientForCookie {
+                inner: PropertyCookie::Cached(transient_for),
+            });
+        }
+        let cookie = get_property(
             &mut self.uring,
             &mut self.xcb_state,
             0,
@@ -677,17 +915,24 @@ impl CallWrapper {
             32,
             false,
         )?;
-        Ok(SingleCardCookie { inner })
+        Ok(TransientForCookie {
+            inner: PropertyCookie::Pending {
+                cache_for: self.cache_target(win),
+                cookie,
+            },
+        })
     }
 
-    pub(crate) fn bulk_set_extents(&mut self, windows: &[Window], border_width: u32) -> Result<()> {
-        for win in windows {
-            self.set_extents(*win, border_width)?;
+    /// Set the frame extents of every managed window from the current state
+    pub(crate) fn bulk_set_extents(&mut self, state: &State) -> Result<()> {
+        for win in state.workspaces.get_all_managed_windows() {
+            self.set_extents(win, state.frame_extents(win))?;
         }
         Ok(())
     }
 
-    pub(crate) fn set_extents(&mut self, win: Window, border_width: u32) -> Result<()> {
+    /// Set `_NET_FRAME_EXTENTS` as left, right, top, bottom
+    pub(crate) fn set_extents(&mut self, win: Window, extents: [u32; 4]) -> Result<()> {
         change_property32(
             &mut self.uring,
             &mut self.xcb_state,
@@ -695,7 +940,29 @@ impl CallWrapper {
             win,
             self.name_to_atom.get(&_NET_FRAME_EXTENTS).unwrap().value,
             AtomEnum::CARDINAL.0,
-            &[border_width; 4],
+            &extents,
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// Set `_NET_WM_FULLSCREEN_MONITORS` as top, bottom, left, right monitor indices
+    pub(crate) fn set_fullscreen_monitors(
+        &mut self,
+        win: Window,
+        monitors: [u32; 4],
+    ) -> Result<()> {
+        change_property32(
+            &mut self.uring,
+            &mut self.xcb_state,
+            PropModeEnum::REPLACE,
+            win,
+            self.name_to_atom
+                .get(&_NET_WM_FULLSCREEN_MONITORS)
+                .unwrap()
+                .value,
+            AtomEnum::CARDINAL.0,
+            &monitors,
             true,
         )?;
         Ok(())
@@ -775,6 +1042,201 @@ impl CallWrapper {
         Ok(())
     }
 
+    /// Grab the keyboard so that all key presses are delivered to us, used while the window picker is open
+    pub(crate) fn grab_keyboard(&mut self, state: &State) -> Result<()> {
+        grab_keyboard(
+            &mut self.uring,
+            &mut self.xcb_state,
+            0,
+            state.screen.root,
+            CURRENT_TIME.into(),
+            GrabModeEnum::ASYNC,
+            GrabModeEnum::ASYNC,
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// Requests made until the matching `end_batch` are written to the socket together,
+    /// see [`UringWrapper::begin_write_batch`]
+    pub(crate) fn begin_batch(&mut self) {
+        self.uring.begin_write_batch();
+    }
+
+    pub(crate) fn end_batch(&mut self) -> Result<()> {
+        self.uring.end_write_batch()
+    }
+
+    pub(crate) fn ungrab_keyboard(&mut self) -> Result<()> {
+        ungrab_keyboard(
+            &mut self.uring,
+            &mut self.xcb_state,
+            CURRENT_TIME.into(),
+            true,
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn get_keysym_table(&mut self) -> Result<KeysymTable> {
+        let setup = self.xcb_state.setup();
+        let lo = setup.min_keycode;
+        let hi = setup.max_keycode;
+        let mapping =
+            get_keyboard_mapping(&mut self.uring, &mut self.xcb_state, lo, hi - lo + 1, false)?
+                .reply(&mut self.uring, &mut self.xcb_state)?;
+        Ok(KeysymTable::new(
+            mapping.keysyms,
+            mapping.keysyms_per_keycode,
+            lo,
+        ))
+    }
+
+    /// Takes the command last written to the root window's `_PGWM_COMMAND`, deleting it so that the
+    /// same command can be sent again
+    pub(crate) fn take_command(&mut self, root: Window) -> Result<Option<String>> {
+        let reply = get_property(
+            &mut self.uring,
+            &mut self.xcb_state,
+            1,
+            root,
+            self.name_to_atom.get(&_PGWM_COMMAND).unwrap().value,
+            GetPropertyTypeEnum(AtomEnum::ANY.0),
+            0,
+            1024,
+            false,
+        )?
+        .reply(&mut self.uring, &mut self.xcb_state)?;
+        if reply.value.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&reply.value).into_owned()))
+    }
+
+    /// Answers a query by replacing the root window's `_PGWM_REPLY`
+    pub(crate) fn set_reply(&mut self, root: Window, reply: &str) -> Result<()> {
+        change_property8(
+            &mut self.uring,
+            &mut self.xcb_state,
+            PropModeEnum::REPLACE,
+            root,
+            self.name_to_atom.get(&_PGWM_REPLY).unwrap().value,
+            self.name_to_atom.get(&UTF8_STRING).unwrap().value,
+            reply.as_bytes(),
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// Resets the screensaver as if there was input, which also puts off DPMS blanking
+    pub(crate) fn reset_screensaver(&mut self) -> Result<()> {
+        force_screen_saver(
+            &mut self.uring,
+            &mut self.xcb_state,
+            ScreenSaverEnum::RESET,
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// How long there's been no keyboard or pointer input, according to the screensaver extension
+    #[cfg(feature = "idle")]
+    pub(crate) fn query_idle_time(&mut self, root: Window) -> Result<core::time::Duration> {
+        let info = xcb_rust_protocol::connection::screensaver::query_info(
+            &mut self.uring,
+            &mut self.xcb_state,
+            root,
+            false,
+        )?
+        .reply(&mut self.uring, &mut self.xcb_state)?;
+        Ok(core::time::Duration::from_millis(u64::from(
+            info.ms_since_user_input,
+        )))
+    }
+
+    /// Whether the server has the screensaver extension that [`Self::query_idle_time`] relies on
+    #[cfg(feature = "idle")]
+    pub(crate) fn has_screensaver_extension(&mut self) -> Result<bool> {
+        let reply = xcb_rust_protocol::connection::xproto::query_extension(
+            &mut self.uring,
+            &mut self.xcb_state,
+            b"MIT-SCREEN-SAVER",
+            false,
+        )?
+        .reply(&mut self.uring, &mut self.xcb_state)?;
+        Ok(reply.present == 1)
+    }
+
+    /// XKB refuses requests from clients that haven't negotiated a version first
+    #[cfg(feature = "xkb")]
+    pub(crate) fn init_xkb(&mut self) -> Result<()> {
+        let reply = xcb_rust_protocol::connection::xkb::use_extension(
+            &mut self.uring,
+            &mut self.xcb_state,
+            1,
+            0,
+            false,
+        )?
+        .reply(&mut self.uring, &mut self.xcb_state)?;
+        pgwm_utils::debug!(
+            "Xkb supported {}, server version {}.{}",
+            reply.supported,
+            reply.server_major,
+            reply.server_minor
+        );
+        Ok(())
+    }
+
+    /// The active keyboard group and the layouts `setxkbmap` has assigned to the groups
+    #[cfg(feature = "xkb")]
+    pub(crate) fn get_keyboard_layouts(&mut self, root: Window) -> Result<(u8, Vec<String>)> {
+        let group_cookie = xcb_rust_protocol::connection::xkb::get_state(
+            &mut self.uring,
+            &mut self.xcb_state,
+            xcb_rust_protocol::proto::xkb::IDEnum::USE_CORE_KBD.0,
+            false,
+        )?;
+        let names_cookie = get_property(
+            &mut self.uring,
+            &mut self.xcb_state,
+            0,
+            root,
+            self.name_to_atom.get(&_XKB_RULES_NAMES).unwrap().value,
+            GetPropertyTypeEnum(AtomEnum::STRING.0),
+            0,
+            256,
+            false,
+        )?;
+        let group = group_cookie
+            .reply(&mut self.uring, &mut self.xcb_state)?
+            .group
+            .0;
+        let names = names_cookie.reply(&mut self.uring, &mut self.xcb_state)?;
+        let layouts = pgwm_core::config::key_map::xkb_layouts(&names.value)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        Ok((group, layouts))
+    }
+
+    #[cfg(feature = "xkb")]
+    pub(crate) fn lock_keyboard_group(&mut self, group: u8) -> Result<()> {
+        let no_mods = xcb_rust_protocol::proto::xproto::ModMask(0);
+        xcb_rust_protocol::connection::xkb::latch_lock_state(
+            &mut self.uring,
+            &mut self.xcb_state,
+            xcb_rust_protocol::proto::xkb::IDEnum::USE_CORE_KBD.0,
+            no_mods,
+            no_mods,
+            1,
+            xcb_rust_protocol::proto::xkb::GroupEnum(group),
+            no_mods,
+            0,
+            0,
+            true,
+        )?;
+        Ok(())
+    }
+
     /// Handling x10 style windows becomes strange: <https://tronche.com/gui/x/xlib/ICC/client-to-window-manager/wm-hints.html>
     pub(crate) fn take_focus(
         &mut self,
@@ -797,8 +1259,9 @@ impl CallWrapper {
         } else {
             match focus_style {
                 FocusStyle::NoInput => {
+                    // The manager raises these without taking focus, nothing to do here
                     pgwm_utils::debug!("NoInput win {target} take focus");
-                    target
+                    return Ok(());
                 }
                 FocusStyle::Passive => {
                     pgwm_utils::debug!("Passive win {target} take focus");
@@ -925,6 +1388,7 @@ impl CallWrapper {
     }
 
     pub(crate) fn send_map(&mut self, window: Window, state: &mut State) -> Result<()> {
+        let window = self.outer(window);
         let cookie = map_window(&mut self.uring, &mut self.xcb_state, window, true)?;
         // Triggers an enter-notify that needs to be ignored
         state.push_sequence(cookie.seq);
@@ -932,9 +1396,11 @@ impl CallWrapper {
     }
 
     pub(crate) fn send_unmap(&mut self, window: Window, state: &mut State) -> Result<()> {
+        let window = self.outer(window);
         let cookie = unmap_window(&mut self.uring, &mut self.xcb_state, window, true)?;
         // Triggers an enter-notify that needs to be ignored, we also don't want to react to an UnmapNotify that we created
         state.push_sequence(cookie.seq);
+        state.push_unmap(window, cookie.seq);
         Ok(())
     }
 
@@ -953,7 +1419,7 @@ impl CallWrapper {
     #[inline]
     pub(crate) fn push_window_to_top(&mut self, window: Window, state: &mut State) -> Result<()> {
         let cfg = ConfigureWindowValueList::default().stack_mode(StackModeEnum::ABOVE);
-        self.do_configure(window, cfg, state)
+        self.do_configure(self.outer(window), cfg, state)
     }
 
     pub(crate) fn configure_window(
@@ -970,8 +1436,19 @@ impl CallWrapper {
             .height(dimension.height as u32)
             .border_width(border_width)
             .stack_mode(StackModeEnum::ABOVE);
-        self.set_extents(window, border_width)?;
-        self.do_configure(window, cfg, state)
+        self.set_extents(window, [border_width; 4])?;
+        let outer = self.outer(window);
+        if outer != window {
+            // The frame has the border, the client fills it
+            let inner = ConfigureWindowValueList::default()
+                .x(0)
+                .y(0)
+                .width(dimension.width as u32)
+                .height(dimension.height as u32)
+                .border_width(0);
+            self.do_configure(window, inner, state)?;
+        }
+        self.do_configure(outer, cfg, state)
     }
 
     // When windows themselves ask to configure resultant mapping should not be ignored, so no need to ignore child-sequences here
@@ -987,7 +1464,7 @@ impl CallWrapper {
             cfg.border_width = Some(event.border_width as u32);
         }
         if event.value_mask.0 & ConfigWindow::SIBLING.0 != 0 {
-            cfg.sibling = Some(event.sibling);
+            cfg.sibling = Some(self.outer(event.sibling));
         }
         if event.value_mask.0 & ConfigWindow::STACK_MODE.0 != 0 {
             cfg.stack_mode = Some(event.stack_mode);
@@ -1001,15 +1478,23 @@ impl CallWrapper {
 
         crate::debug!("Configuring {event:?}\n on {}", event.window);
         if let Some(border_width) = cfg.border_width {
-            self.set_extents(event.window, border_width)?;
+            self.set_extents(event.window, [border_width; 4])?;
         }
-        configure_window(
-            &mut self.uring,
-            &mut self.xcb_state,
-            event.window,
-            cfg,
-            true,
-        )?;
+        let outer = self.outer(event.window);
+        if outer != event.window && (cfg.width.is_some() || cfg.height.is_some()) {
+            // Only the size applies to a framed client, the rest goes to its frame
+            let mut inner = ConfigureWindowValueList::default();
+            inner.width = cfg.width;
+            inner.height = cfg.height;
+            configure_window(
+                &mut self.uring,
+                &mut self.xcb_state,
+                event.window,
+                inner,
+                true,
+            )?;
+        }
+        configure_window(&mut self.uring, &mut self.xcb_state, outer, cfg, true)?;
         Ok(())
     }
 
@@ -1025,7 +1510,19 @@ impl CallWrapper {
             .x(x)
             .y(y)
             .stack_mode(StackModeEnum::ABOVE);
-        self.do_configure(window, cfg, state)
+        self.do_configure(self.outer(window), cfg, state)
+    }
+
+    /// Moves a window without restacking it
+    pub(crate) fn reposition_window(
+        &mut self,
+        window: Window,
+        x: i32,
+        y: i32,
+        state: &mut State,
+    ) -> Result<()> {
+        let cfg = ConfigureWindowValueList::default().x(x).y(y);
+        self.do_configure(self.outer(window), cfg, state)
     }
 
     pub(crate) fn resize_window(
@@ -1038,7 +1535,14 @@ impl CallWrapper {
         let cfg = ConfigureWindowValueList::default()
             .height(height)
             .width(width);
-        self.do_configure(window, cfg, state)
+        let outer = self.outer(window);
+        if outer != window {
+            let inner = ConfigureWindowValueList::default()
+                .height(height)
+                .width(width);
+            self.do_configure(window, inner, state)?;
+        }
+        self.do_configure(outer, cfg, state)
     }
 
     fn do_configure(
@@ -1055,10 +1559,200 @@ impl CallWrapper {
 
     pub(crate) fn change_border_color(&mut self, window: Window, pixel: u32) -> Result<()> {
         let cw = ChangeWindowAttributesValueList::default().border_pixel(pixel);
+        let window = self.outer(window);
         change_window_attributes(&mut self.uring, &mut self.xcb_state, window, cw, true)?;
         Ok(())
     }
 
+    /// The window that's moved, stacked, mapped, and has the border for `window`,
+    /// its frame if it's been reparented into one
+    #[inline]
+    #[cfg_attr(not(feature = "frames"), allow(clippy::unused_self))]
+    fn outer(&self, window: Window) -> Window {
+        #[cfg(feature = "frames")]
+        if let Some((_, frame)) = self.frames.iter().find(|(client, _)| *client == window) {
+            return *frame;
+        }
+        window
+    }
+
+    /// The client in `window` if it's a frame, pointer events report the frame as the child of the root
+    #[inline]
+    #[cfg_attr(not(feature = "frames"), allow(clippy::unused_self))]
+    pub(crate) fn client_of(&self, window: Window) -> Window {
+        #[cfg(feature = "frames")]
+        if let Some((client, _)) = self.frames.iter().find(|(_, frame)| *frame == window) {
+            return *client;
+        }
+        window
+    }
+
+    /// Reparent a newly managed client into a frame where it currently is,
+    /// from then on the frame is what's moved, stacked, mapped, and bordered
+    #[cfg(feature = "frames")]
+    pub(crate) fn frame(&mut self, client: Window, state: &mut State) -> Result<()> {
+        let geometry = get_geometry(&mut self.uring, &mut self.xcb_state, client, false)?
+            .reply(&mut self.uring, &mut self.xcb_state)?;
+        let frame = self.generate_id()?;
+        let cw = CreateWindowValueList::default()
+            .event_mask(EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY);
+        create_window(
+            &mut self.uring,
+            &mut self.xcb_state,
+            COPY_DEPTH_FROM_PARENT,
+            frame,
+            state.screen.root,
+            geometry.x,
+            geometry.y,
+            geometry.width.max(1),
+            geometry.height.max(1),
+            0,
+            WindowClassEnum::INPUT_OUTPUT,
+            0,
+            cw,
+            true,
+        )?;
+        // If the WM goes away the server puts the client back on the root
+        change_save_set(
+            &mut self.uring,
+            &mut self.xcb_state,
+            SetModeEnum::INSERT,
+            client,
+            true,
+        )?;
+        let cookie = reparent_window(
+            &mut self.uring,
+            &mut self.xcb_state,
+            client,
+            frame,
+            0,
+            0,
+            true,
+        )?;
+        // Reparenting a mapped client, like one found on startup, unmaps it first
+        state.push_sequence(cookie.seq);
+        state.push_unmap(client, cookie.seq);
+        let cookie = map_window(&mut self.uring, &mut self.xcb_state, client, true)?;
+        state.push_sequence(cookie.seq);
+        self.frames.push((client, frame));
+        Ok(())
+    }
+
+    /// Put a client that's no longer managed back on the root where its frame was, and destroy the frame
+    #[cfg(feature = "frames")]
+    pub(crate) fn unframe(&mut self, client: Window, state: &mut State) -> Result<()> {
+        let Some(ind) = self.frames.iter().position(|(framed, _)| *framed == client) else {
+            return Ok(());
+        };
+        let (_, frame) = self.frames.swap_remove(ind);
+        // The client may already be gone, then these fail harmlessly
+        let cookie = unmap_window(&mut self.uring, &mut self.xcb_state, client, true)?;
+        state.push_sequence(cookie.seq);
+        state.push_unmap(client, cookie.seq);
+        self.release(client, frame)
+    }
+
+    /// Put every framed client back on the root, on exit or restart
+    #[cfg(feature = "frames")]
+    pub(crate) fn release_frames(&mut self) -> Result<()> {
+        for (client, frame) in core::mem::take(&mut self.frames) {
+            self.release(client, frame)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "frames")]
+    fn release(&mut self, client: Window, frame: Window) -> Result<()> {
+        let geometry = get_geometry(&mut self.uring, &mut self.xcb_state, frame, false)?
+            .reply(&mut self.uring, &mut self.xcb_state)?;
+        let root = geometry.root;
+        reparent_window(
+            &mut self.uring,
+            &mut self.xcb_state,
+            client,
+            root,
+            geometry.x,
+            geometry.y,
+            true,
+        )?;
+        change_save_set(
+            &mut self.uring,
+            &mut self.xcb_state,
+            SetModeEnum::DELETE,
+            client,
+            true,
+        )?;
+        self.send_destroy(frame)
+    }
+
+    /// Make `pixmap` the root window's background and repaint `area` of the root with it.
+    /// Needs to be called after drawing to the pixmap, the server may have copied it when it was set
+    pub(crate) fn set_root_background(
+        &mut self,
+        root: Window,
+        pixmap: Pixmap,
+        area: Dimensions,
+    ) -> Result<()> {
+        let cw = ChangeWindowAttributesValueList::default().background_pixmap(pixmap);
+        change_window_attributes(&mut self.uring, &mut self.xcb_state, root, cw, true)?;
+        clear_area(
+            &mut self.uring,
+            &mut self.xcb_state,
+            0,
+            root,
+            area.x,
+            area.y,
+            area.width as u16,
+            area.height as u16,
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// Upload `image` to `pixmap` at `x`, `y`, split by rows into requests that fit
+    /// the core protocol's maximum request length
+    pub(crate) fn put_image(
+        &mut self,
+        pixmap: Pixmap,
+        depth: u8,
+        image: &Image,
+        x: i16,
+        y: i16,
+    ) -> Result<()> {
+        // 65535 four byte units, less the 24 byte request header
+        const MAX_PUT_IMAGE_DATA: usize = 65535 * 4 - 24;
+        let gc = self.generate_id()?;
+        create_g_c(
+            &mut self.uring,
+            &mut self.xcb_state,
+            gc,
+            pixmap,
+            CreateGCValueList::default().graphics_exposures(0),
+            true,
+        )?;
+        let row_len = image.width as usize * 4;
+        let rows_per_request = (MAX_PUT_IMAGE_DATA / row_len).max(1);
+        for (ind, rows) in image.pixels.chunks(rows_per_request * row_len).enumerate() {
+            put_image(
+                &mut self.uring,
+                &mut self.xcb_state,
+                ImageFormatEnum::Z_PIXMAP,
+                pixmap,
+                gc,
+                image.width,
+                (rows.len() / row_len) as u16,
+                x,
+                y + (ind * rows_per_request) as i16,
+                0,
+                depth,
+                rows,
+                true,
+            )?;
+        }
+        free_g_c(&mut self.uring, &mut self.xcb_state, gc, true)?;
+        Ok(())
+    }
+
     pub(crate) fn window_mapped_picture(
         &mut self,
         win: Window,
@@ -1301,6 +1995,9 @@ impl CallWrapper {
             xcb_state,
             name_to_atom,
             atom_to_resolved,
+            property_cache: Map::new(),
+            #[cfg(feature = "frames")]
+            frames: Vec::new(),
         })
     }
 
@@ -1473,14 +2170,30 @@ impl CallWrapper {
         })
     }
 
-    pub(crate) fn get_pid(&mut self, window: Window) -> Result<SingleCardCookie> {
-        Ok(SingleCardCookie {
+    pub(crate) fn get_colormap_windows(&mut self, window: Window) -> Result<ColormapWindowsCookie> {
+        Ok(ColormapWindowsCookie {
             inner: get_property(
                 &mut self.uring,
                 &mut self.xcb_state,
                 0,
                 window,
-                self.name_to_atom.get(&_NET_WM_PID).unwrap().value,
+                self.name_to_atom.get(&WM_COLORMAP_WINDOWS).unwrap().value,
+                AtomEnum::WINDOW.0.into(),
+                0,
+                8,
+                false,
+            )?,
+        })
+    }
+
+    pub(crate) fn get_gtk_frame_extents(&mut self, window: Window) -> Result<FrameExtentsCookie> {
+        Ok(FrameExtentsCookie {
+            inner: get_property(
+                &mut self.uring,
+                &mut self.xcb_state,
+                0,
+                window,
+                self.name_to_atom.get(&_GTK_FRAME_EXTENTS).unwrap().value,
                 AtomEnum::CARDINAL.0.into(),
                 0,
                 4,
@@ -1489,22 +2202,51 @@ impl CallWrapper {
         })
     }
 
-    pub(crate) fn get_protocols(&mut self, window: Window) -> Result<ProtocolsCookie> {
-        Ok(ProtocolsCookie {
+    pub(crate) fn get_pid(&mut self, window: Window) -> Result<SingleCardCookie> {
+        Ok(SingleCardCookie {
             inner: get_property(
                 &mut self.uring,
                 &mut self.xcb_state,
                 0,
                 window,
-                self.name_to_atom.get(&WM_PROTOCOLS).unwrap().value,
-                AtomEnum::ATOM.0.into(),
+                self.name_to_atom.get(&_NET_WM_PID).unwrap().value,
+                AtomEnum::CARDINAL.0.into(),
                 0,
-                4 * 32,
+                4,
                 false,
             )?,
         })
     }
 
+    pub(crate) fn get_protocols(&mut self, window: Window) -> Result<ProtocolsCookie> {
+        if let Some(protocols) = self
+            .property_cache
+            .get(&window)
+            .and_then(|c| c.protocols.clone())
+        {
+            return Ok(ProtocolsCookie {
+                inner: PropertyCookie::Cached(protocols),
+            });
+        }
+        let cookie = get_property(
+            &mut self.uring,
+            &mut self.xcb_state,
+            0,
+            window,
+            self.name_to_atom.get(&WM_PROTOCOLS).unwrap().value,
+            AtomEnum::ATOM.0.into(),
+            0,
+            4 * 32,
+            false,
+        )?;
+        Ok(ProtocolsCookie {
+            inner: PropertyCookie::Pending {
+                cache_for: self.cache_target(window),
+                cookie,
+            },
+        })
+    }
+
     #[inline]
     pub(crate) fn generate_id(&mut self) -> Result<u32> {
         Ok(self.xcb_state.generate_id(&mut self.uring)?)
@@ -1523,7 +2265,9 @@ pub(crate) struct WindowPropertiesCookie {
     protocols: ProtocolsCookie,
     pid: SingleCardCookie,
     leader: SingleCardCookie,
-    transient_for: SingleCardCookie,
+    transient_for: TransientForCookie,
+    colormap_windows: ColormapWindowsCookie,
+    gtk_frame_extents: FrameExtentsCookie,
 }
 
 impl WindowPropertiesCookie {
@@ -1545,7 +2289,9 @@ impl WindowPropertiesCookie {
         let pid = self.pid.await_card(call_wrapper);
         let leader = self.leader.await_card(call_wrapper);
         let protocols = self.protocols.await_protocols(call_wrapper);
-        let transient_for = self.transient_for.await_card(call_wrapper);
+        let transient_for = self.transient_for.await_transient_for(call_wrapper);
+        let colormap_windows = self.colormap_windows.await_windows(call_wrapper);
+        let gtk_frame_extents = self.gtk_frame_extents.await_extents(call_wrapper);
         let class = self.wm_class.await_class_names(call_wrapper);
         let name = if let Ok(Some(net_wm_name)) = self.net_wm_name.await_name(call_wrapper) {
             self.wm_name.inner.forget(&mut call_wrapper.xcb_state);
@@ -1567,6 +2313,8 @@ impl WindowPropertiesCookie {
             protocols: protocols?,
             name,
             transient_for: transient_for?,
+            colormap_windows: colormap_windows?,
+            gtk_frame_extents: gtk_frame_extents?,
         })
     }
 
@@ -1583,6 +2331,12 @@ impl WindowPropertiesCookie {
         self.protocols.inner.forget(&mut call_wrapper.xcb_state);
         self.window_types.inner.forget(&mut call_wrapper.xcb_state);
         self.transient_for.inner.forget(&mut call_wrapper.xcb_state);
+        self.colormap_windows
+            .inner
+            .forget(&mut call_wrapper.xcb_state);
+        self.gtk_frame_extents
+            .inner
+            .forget(&mut call_wrapper.xcb_state);
     }
 }
 
@@ -1608,24 +2362,44 @@ fn utf8_heapless<const N: usize>(bytes: Vec<u8>) -> Result<Option<heapless::Stri
     Ok(core::str::from_utf8(slice).map(|s| heapless::String::try_from(s).ok())?)
 }
 
+/// A property request that may have been answered from the property cache
+pub(crate) enum PropertyCookie<T> {
+    Pending {
+        /// Set if the reply should be cached for this window
+        cache_for: Option<Window>,
+        cookie: Cookie<GetPropertyReply>,
+    },
+    Cached(T),
+}
+
+impl<T> PropertyCookie<T> {
+    pub(crate) fn forget(self, xcb_state: &mut XcbEventState) {
+        if let PropertyCookie::Pending { cookie, .. } = self {
+            cookie.forget(xcb_state);
+        }
+    }
+}
+
 pub(crate) struct WmClassCookie {
-    pub(crate) inner: Cookie<GetPropertyReply>,
+    pub(crate) inner: PropertyCookie<Option<WmClass>>,
 }
 
 impl WmClassCookie {
-    pub(crate) fn await_class_names(
-        self,
-        con: &mut CallWrapper,
-    ) -> Result<Option<heapless::Vec<heapless::String<_WM_CLASS_NAME_LIMIT>, 4>>> {
-        Ok(extract_wm_class(
-            self.inner.reply(&mut con.uring, &mut con.xcb_state)?,
-        ))
+    pub(crate) fn await_class_names(self, con: &mut CallWrapper) -> Result<Option<WmClass>> {
+        match self.inner {
+            PropertyCookie::Pending { cache_for, cookie } => {
+                let class = extract_wm_class(cookie.reply(&mut con.uring, &mut con.xcb_state)?);
+                if let Some(cached) = cache_for.and_then(|win| con.property_cache.get_mut(&win)) {
+                    cached.class = Some(class.clone());
+                }
+                Ok(class)
+            }
+            PropertyCookie::Cached(class) => Ok(class),
+        }
     }
 }
 
-fn extract_wm_class(
-    class_response: GetPropertyReply,
-) -> Option<heapless::Vec<heapless::String<_WM_CLASS_NAME_LIMIT>, 4>> {
+fn extract_wm_class(class_response: GetPropertyReply) -> Option<WmClass> {
     // Already allocated vec
     let raw_utf8 = String::from_utf8(class_response.value);
     if let Ok(raw_utf8) = &raw_utf8 {
@@ -1637,7 +2411,7 @@ fn extract_wm_class(
                     .flatten()
             })
             // Avoiding another alloc here
-            .collect::<heapless::Vec<heapless::String<_WM_CLASS_NAME_LIMIT>, 4>>();
+            .collect::<WmClass>();
         Some(complete_names)
     } else {
         pgwm_utils::debug!("Failed to parse class response value as utf-8");
@@ -1869,7 +2643,7 @@ impl WindowTypesCookie {
 }
 
 pub(crate) struct ProtocolsCookie {
-    pub(crate) inner: Cookie<GetPropertyReply>,
+    pub(crate) inner: PropertyCookie<heapless::Vec<Protocol, 4>>,
 }
 
 impl ProtocolsCookie {
@@ -1877,8 +2651,11 @@ impl ProtocolsCookie {
         self,
         call_wrapper: &mut CallWrapper,
     ) -> Result<heapless::Vec<Protocol, 4>> {
-        let protocols = self
-            .inner
+        let (cache_for, cookie) = match self.inner {
+            PropertyCookie::Pending { cache_for, cookie } => (cache_for, cookie),
+            PropertyCookie::Cached(protocols) => return Ok(protocols),
+        };
+        let protocols = cookie
             .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?
             .value32()
             .map(|it| {
@@ -1909,6 +2686,9 @@ impl ProtocolsCookie {
                 protocols
             })
             .unwrap_or_default();
+        if let Some(cached) = cache_for.and_then(|win| call_wrapper.property_cache.get_mut(&win)) {
+            cached.protocols = Some(protocols.clone());
+        }
         Ok(protocols)
     }
 }
@@ -1926,6 +2706,70 @@ impl SingleCardCookie {
     }
 }
 
+pub(crate) struct ColormapWindowsCookie {
+    pub(crate) inner: Cookie<GetPropertyReply>,
+}
+
+impl ColormapWindowsCookie {
+    pub(crate) fn await_windows(
+        self,
+        call_wrapper: &mut CallWrapper,
+    ) -> Result<heapless::Vec<Window, 8>> {
+        Ok(self
+            .inner
+            .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?
+            .value32()
+            .map(|it| it.take(8).collect())
+            .unwrap_or_default())
+    }
+}
+
+pub(crate) struct FrameExtentsCookie {
+    pub(crate) inner: Cookie<GetPropertyReply>,
+}
+
+impl FrameExtentsCookie {
+    /// Left, right, top, bottom, `None` unless all four are there
+    pub(crate) fn await_extents(self, call_wrapper: &mut CallWrapper) -> Result<Option<[u32; 4]>> {
+        let reply = self
+            .inner
+            .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
+        let mut extents = [0; 4];
+        let mut found = 0;
+        for (extent, value) in extents.iter_mut().zip(Iter32::new(&reply.value)) {
+            *extent = value;
+            found += 1;
+        }
+        Ok((found == 4).then_some(extents))
+    }
+}
+
+pub(crate) struct TransientForCookie {
+    pub(crate) inner: PropertyCookie<Option<Window>>,
+}
+
+impl TransientForCookie {
+    pub(crate) fn await_transient_for(
+        self,
+        call_wrapper: &mut CallWrapper,
+    ) -> Result<Option<Window>> {
+        match self.inner {
+            PropertyCookie::Pending { cache_for, cookie } => {
+                let transient_for = cookie
+                    .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?
+                    .first_u32();
+                if let Some(cached) =
+                    cache_for.and_then(|win| call_wrapper.property_cache.get_mut(&win))
+                {
+                    cached.transient_for = Some(transient_for);
+                }
+                Ok(transient_for)
+            }
+            PropertyCookie::Cached(transient_for) => Ok(transient_for),
+        }
+    }
+}
+
 pub(crate) struct QueryTreeCookie {
     pub(crate) inner: Cookie<QueryTreeReply>,
 }
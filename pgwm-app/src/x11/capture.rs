@@ -0,0 +1,145 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use tiny_std::fs::File;
+use tiny_std::io::Write;
+use tiny_std::time::{Instant, SystemTime};
+use tiny_std::UnixStr;
+
+use pgwm_core::config::X11_CAPTURE_FALLBACK_DIR;
+use pgwm_core::util::capture::{capture_requests, capture_responses, CaptureKind, CaptureToken};
+
+use crate::error::Result;
+
+/// Tokens are buffered and written once there's at least this much
+const FLUSH_AT: usize = 65536;
+
+/// Writes everything sent and received over the X11 connection as [`CaptureToken`]s
+/// to a new file in `$XDG_RUNTIME_DIR`, or [`X11_CAPTURE_FALLBACK_DIR`] if it isn't set.
+/// Payloads are only kept with the `record` feature, `trace-x11` alone writes the headers.
+pub(crate) struct X11Capture {
+    file: File,
+    buf: Vec<u8>,
+    start: Instant,
+    payloads: bool,
+    /// Requests are numbered by the server in the order they're received, starting at 1
+    next_sequence: u16,
+    /// The connection setup and its reply are the first bytes each way
+    setup_written: bool,
+    setup_read: bool,
+}
+
+impl X11Capture {
+    /// Create the capture file, or `None` if that fails, the WM then runs as usual without a capture
+    pub(crate) fn open() -> Option<Self> {
+        let path = Self::path();
+        match Self::create(&path) {
+            Ok(file) => Some(Self {
+                file,
+                buf: Vec::with_capacity(FLUSH_AT * 2),
+                start: Instant::now(),
+                payloads: cfg!(feature = "record"),
+                next_sequence: 1,
+                setup_written: false,
+                setup_read: false,
+            }),
+            Err(e) => {
+                tiny_std::eprintln!(
+                    "Failed to create x11 capture {}, running without one: {e}",
+                    path.trim_end_matches('\0')
+                );
+                None
+            }
+        }
+    }
+
+    /// Never follows or reuses what's already at `path`
+    fn create(path: &str) -> Result<File> {
+        Ok(tiny_std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(UnixStr::try_from_str(path)?)?)
+    }
+
+    /// Null terminated
+    fn path() -> String {
+        let dir = tiny_std::env::var("XDG_RUNTIME_DIR").unwrap_or(X11_CAPTURE_FALLBACK_DIR);
+        let nanos = SystemTime::now().duration_since_unix_time().as_nanos();
+        format!("{dir}/pgwm-x11-capture-{nanos}\0")
+    }
+
+    /// Capture bytes that were just put in the socket write buffer
+    pub(crate) fn written(&mut self, bytes: &[u8]) {
+        let nanos = self.nanos();
+        if self.setup_written {
+            let mut next_sequence = self.next_sequence;
+            capture_requests(bytes, &mut next_sequence, nanos, |token| self.push(token));
+            self.next_sequence = next_sequence;
+        } else {
+            self.setup_written = true;
+            self.push(CaptureToken {
+                kind: CaptureKind::Setup,
+                code: 0,
+                detail: 0,
+                sequence: 0,
+                nanos,
+                payload: bytes,
+            });
+        }
+        self.flush_if_full();
+    }
+
+    /// Capture bytes that were just consumed from the socket read buffer
+    pub(crate) fn read(&mut self, bytes: &[u8]) {
+        let nanos = self.nanos();
+        if self.setup_read {
+            capture_responses(bytes, nanos, |token| self.push(token));
+        } else {
+            self.setup_read = true;
+            self.push(CaptureToken {
+                kind: CaptureKind::SetupReply,
+                code: 0,
+                detail: 0,
+                sequence: 0,
+                nanos,
+                payload: bytes,
+            });
+        }
+        self.flush_if_full();
+    }
+
+    fn push(&mut self, mut token: CaptureToken) {
+        if !self.payloads {
+            token.payload = &[];
+        }
+        self.buf.extend_from_slice(&token.encode_header());
+        self.buf.extend_from_slice(token.payload);
+    }
+
+    fn nanos(&self) -> u64 {
+        Instant::now()
+            .duration_since(self.start)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+    }
+
+    fn flush_if_full(&mut self) {
+        if self.buf.len() >= FLUSH_AT {
+            self.flush();
+        }
+    }
+
+    /// A capture that can't be written shouldn't take the WM down with it, what's lost is lost
+    fn flush(&mut self) {
+        if let Err(_e) = self.file.write_all(&self.buf) {
+            pgwm_utils::debug!("Failed to write x11 capture {_e}");
+        }
+        self.buf.clear();
+    }
+}
+
+impl Drop for X11Capture {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
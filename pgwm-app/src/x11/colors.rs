@@ -1,51 +1,132 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use xcb_rust_protocol::connection::xproto::alloc_color;
 use xcb_rust_protocol::cookie::FixedCookie;
+use xcb_rust_protocol::helpers::resource_manager::Database;
 use xcb_rust_protocol::proto::xproto::{AllocColorReply, Colormap};
 
-use pgwm_core::colors::{Color, Colors, Rgba8};
-use pgwm_core::config::COLORS;
+use pgwm_core::colors::gradient::gradient;
+use pgwm_core::colors::{apply_resource_colors, Color, Colors, Rgba8, RGBA};
+use pgwm_core::config::{BORDER_COLOR_RULES, COLORS, XRESOURCE_COLORS};
 use pgwm_core::push_heapless;
 
 use crate::error::Result;
 use crate::x11::call_wrapper::CallWrapper;
 
+/// The configured colors with those in [`XRESOURCE_COLORS`] replaced by their X resources
+pub(crate) fn with_resource_colors(
+    colors: &[RGBA; COLORS.len()],
+    resource_db: &Database,
+) -> [RGBA; COLORS.len()] {
+    let mut colors = *colors;
+    apply_resource_colors(&mut colors, XRESOURCE_COLORS, |resource| {
+        resource_db
+            .get_string(&format!("pgwm.{resource}"), &format!("Pgwm.{resource}"))
+            .map(String::from)
+    });
+    colors
+}
+
 #[allow(clippy::type_complexity)]
-pub(crate) fn alloc_colors(call_wrapper: &mut CallWrapper, color_map: Colormap) -> Result<Colors> {
+pub(crate) fn alloc_colors(
+    call_wrapper: &mut CallWrapper,
+    color_map: Colormap,
+    colors: &[RGBA; COLORS.len()],
+) -> Result<Colors> {
     let mut alloc_rgba_cookies: heapless::Vec<
         ((u8, u8, u8, u8), FixedCookie<AllocColorReply, 20>),
         { COLORS.len() },
     > = heapless::Vec::new();
-    for color in COLORS {
-        let (r, g, b, _) = color.to_rgba16();
+    for &color in colors {
         push_heapless!(
             alloc_rgba_cookies,
-            (
-                color,
-                alloc_color(
-                    &mut call_wrapper.uring,
-                    &mut call_wrapper.xcb_state,
-                    color_map,
-                    r,
-                    g,
-                    b,
-                    false
-                )?
-            )
+            (color, alloc_rgba(call_wrapper, color_map, color)?)
         )?;
     }
+    let mut class_border_cookies = Vec::with_capacity(BORDER_COLOR_RULES.len());
+    for rule in BORDER_COLOR_RULES {
+        class_border_cookies.push((
+            (
+                rule.normal,
+                alloc_rgba(call_wrapper, color_map, rule.normal)?,
+            ),
+            (
+                rule.highlighted,
+                alloc_rgba(call_wrapper, color_map, rule.highlighted)?,
+            ),
+        ));
+    }
     let mut allocated_colors: [Color; 17] = [Color {
         pixel: 0,
         bgra8: [0, 0, 0, 0],
     }; 17];
-    for (ind, ((r, g, b, a), cookie)) in alloc_rgba_cookies.into_iter().enumerate() {
-        allocated_colors[ind] = Color {
-            pixel: cookie
-                .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?
-                .pixel,
-            bgra8: [b, g, r, a],
-        };
+    for (ind, (color, cookie)) in alloc_rgba_cookies.into_iter().enumerate() {
+        allocated_colors[ind] = await_color(call_wrapper, color, cookie)?;
+    }
+    let mut class_borders = Vec::with_capacity(class_border_cookies.len());
+    for ((normal, normal_cookie), (highlighted, highlighted_cookie)) in class_border_cookies {
+        class_borders.push((
+            await_color(call_wrapper, normal, normal_cookie)?,
+            await_color(call_wrapper, highlighted, highlighted_cookie)?,
+        ));
     }
     Ok(Colors {
         inner: allocated_colors,
+        class_borders,
+    })
+}
+
+/// The colors stepped through going from `from` to `to` in `steps` frames, ending with `to`
+pub(crate) fn alloc_gradient(
+    call_wrapper: &mut CallWrapper,
+    color_map: Colormap,
+    from: Color,
+    to: Color,
+    steps: usize,
+) -> Result<Vec<Color>> {
+    let mut cookies = Vec::with_capacity(steps);
+    for [b, g, r, a] in gradient(from.bgra8, to.bgra8, steps).take(steps.saturating_sub(1)) {
+        let color = (r, g, b, a);
+        cookies.push((color, alloc_rgba(call_wrapper, color_map, color)?));
+    }
+    let mut frames = Vec::with_capacity(steps);
+    for (color, cookie) in cookies {
+        frames.push(await_color(call_wrapper, color, cookie)?);
+    }
+    if steps > 0 {
+        frames.push(to);
+    }
+    Ok(frames)
+}
+
+fn alloc_rgba(
+    call_wrapper: &mut CallWrapper,
+    color_map: Colormap,
+    color: RGBA,
+) -> Result<FixedCookie<AllocColorReply, 20>> {
+    let (r, g, b, _) = color.to_rgba16();
+    Ok(alloc_color(
+        &mut call_wrapper.uring,
+        &mut call_wrapper.xcb_state,
+        color_map,
+        r,
+        g,
+        b,
+        false,
+    )?)
+}
+
+fn await_color(
+    call_wrapper: &mut CallWrapper,
+    (r, g, b, a): RGBA,
+    cookie: FixedCookie<AllocColorReply, 20>,
+) -> Result<Color> {
+    Ok(Color {
+        pixel: cookie
+            .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?
+            .pixel,
+        bgra8: [b, g, r, a],
     })
 }
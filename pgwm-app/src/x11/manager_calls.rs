@@ -0,0 +1,147 @@
+use alloc::vec::Vec;
+
+use xcb_rust_protocol::proto::xproto::{Colormap, Window};
+
+use pgwm_core::colors::Color;
+use pgwm_core::geometry::Dimensions;
+use pgwm_core::state::properties::{NetWmState, WmState};
+use pgwm_core::state::workspace::FocusStyle;
+use pgwm_core::state::State;
+
+use crate::error::Result;
+use crate::x11::call_wrapper::CallWrapper;
+use crate::x11::colors::alloc_gradient;
+
+/// The X calls the manager's event handlers make, behind a trait so that they can be tested
+/// without an X server. Calls that wait for a reply return it directly instead of a cookie.
+pub(crate) trait ManagerCalls {
+    fn take_focus(
+        &mut self,
+        root: Window,
+        target: Window,
+        focus_style: FocusStyle,
+        state: &State,
+    ) -> Result<()>;
+
+    fn push_window_to_top(&mut self, window: Window, state: &mut State) -> Result<()>;
+
+    fn install_colormaps(&mut self, toplevel: Window, colormap_windows: &[Window]) -> Result<()>;
+
+    fn change_border_color(&mut self, window: Window, pixel: u32) -> Result<()>;
+
+    /// Colors stepping from `from` to `to`, see [`alloc_gradient`]
+    fn alloc_gradient(
+        &mut self,
+        color_map: Colormap,
+        from: Color,
+        to: Color,
+        steps: usize,
+    ) -> Result<Vec<Color>>;
+
+    /// Whether the window has override-redirect set, `None` if its attributes can't be read,
+    /// like when it's already gone
+    fn is_override_redirect(&mut self, window: Window) -> Result<Option<bool>>;
+
+    fn set_state(&mut self, window: Window, wm_state: WmState) -> Result<()>;
+
+    fn forget_cached_properties(&mut self, window: Window);
+
+    fn update_client_list(&mut self, managed: &[Window], state: &State) -> Result<()>;
+
+    fn set_net_wm_state(&mut self, window: Window, net_wm_state: NetWmState) -> Result<()>;
+
+    /// The window's geometry as the server has it
+    fn dimensions(&mut self, window: Window) -> Result<Dimensions>;
+
+    fn resize_window(
+        &mut self,
+        window: Window,
+        height: u32,
+        width: u32,
+        state: &mut State,
+    ) -> Result<()>;
+}
+
+impl ManagerCalls for CallWrapper {
+    #[inline]
+    fn take_focus(
+        &mut self,
+        root: Window,
+        target: Window,
+        focus_style: FocusStyle,
+        state: &State,
+    ) -> Result<()> {
+        CallWrapper::take_focus(self, root, target, focus_style, state)
+    }
+
+    #[inline]
+    fn push_window_to_top(&mut self, window: Window, state: &mut State) -> Result<()> {
+        CallWrapper::push_window_to_top(self, window, state)
+    }
+
+    #[inline]
+    fn install_colormaps(&mut self, toplevel: Window, colormap_windows: &[Window]) -> Result<()> {
+        CallWrapper::install_colormaps(self, toplevel, colormap_windows)
+    }
+
+    #[inline]
+    fn change_border_color(&mut self, window: Window, pixel: u32) -> Result<()> {
+        CallWrapper::change_border_color(self, window, pixel)
+    }
+
+    #[inline]
+    fn alloc_gradient(
+        &mut self,
+        color_map: Colormap,
+        from: Color,
+        to: Color,
+        steps: usize,
+    ) -> Result<Vec<Color>> {
+        alloc_gradient(self, color_map, from, to, steps)
+    }
+
+    #[inline]
+    fn is_override_redirect(&mut self, window: Window) -> Result<Option<bool>> {
+        let attributes = self.get_window_attributes(window)?;
+        Ok(attributes
+            .reply(&mut self.uring, &mut self.xcb_state)
+            .ok()
+            .map(|attributes| attributes.override_redirect == 1))
+    }
+
+    #[inline]
+    fn set_state(&mut self, window: Window, wm_state: WmState) -> Result<()> {
+        CallWrapper::set_state(self, window, wm_state)
+    }
+
+    #[inline]
+    fn forget_cached_properties(&mut self, window: Window) {
+        CallWrapper::forget_cached_properties(self, window);
+    }
+
+    #[inline]
+    fn update_client_list(&mut self, managed: &[Window], state: &State) -> Result<()> {
+        CallWrapper::update_client_list(self, managed, state)
+    }
+
+    #[inline]
+    fn set_net_wm_state(&mut self, window: Window, net_wm_state: NetWmState) -> Result<()> {
+        CallWrapper::set_net_wm_state(self, window, net_wm_state)
+    }
+
+    #[inline]
+    fn dimensions(&mut self, window: Window) -> Result<Dimensions> {
+        self.get_dimensions(window)?.await_dimensions(self)
+    }
+
+    #[inline]
+    fn resize_window(
+        &mut self,
+        window: Window,
+        height: u32,
+        width: u32,
+        state: &mut State,
+    ) -> Result<()> {
+        CallWrapper::resize_window(self, window, height, width, state)
+    }
+}
@@ -1,3 +1,6 @@
 pub(crate) mod call_wrapper;
+#[cfg(any(feature = "trace-x11", feature = "record"))]
+pub(crate) mod capture;
 pub(crate) mod colors;
+pub(crate) mod manager_calls;
 pub(crate) mod state_lifecycle;
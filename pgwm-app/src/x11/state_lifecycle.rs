@@ -2,11 +2,15 @@ use alloc::vec::Vec;
 
 use heapless::binary_heap::Min;
 use smallmap::Map;
+use tiny_std::eprintln;
+#[cfg(feature = "idle")]
+use tiny_std::time::Instant;
+use tiny_std::UnixStr;
 use xcb_rust_protocol::con::XcbState;
 use xcb_rust_protocol::connection::render::{free_glyph_set, free_picture};
 use xcb_rust_protocol::connection::xproto::{
-    create_g_c, create_pixmap, create_window, free_pixmap, get_keyboard_mapping, grab_button,
-    grab_key, map_window, ungrab_button, ungrab_key,
+    create_g_c, create_pixmap, create_window, free_pixmap, grab_button, grab_key, map_window,
+    ungrab_button, ungrab_key,
 };
 use xcb_rust_protocol::cookie::VoidCookie;
 use xcb_rust_protocol::proto::xproto::{
@@ -16,32 +20,41 @@ use xcb_rust_protocol::proto::xproto::{
 };
 use xcb_rust_protocol::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME};
 
+use pgwm_core::colors::gradient::GradientCache;
 use pgwm_core::colors::Colors;
-use pgwm_core::config::key_map::{KeyBoardMappingKey, KeyboardMapping};
+use pgwm_core::config::cfg::Cfg;
+use pgwm_core::config::key_map::{
+    keysym_label, mods_label, KeyBoardMappingKey, KeyboardMapping, LOCK_MOD_COMBINATIONS,
+};
 use pgwm_core::config::mouse_map::MouseActionKey;
 use pgwm_core::config::workspaces::UserWorkspace;
 use pgwm_core::config::{
-    Action, FontCfg, BAR_SHORTCUTS, BINARY_HEAP_LIMIT, DYING_WINDOW_CACHE, KEYBOARD_MAPPINGS,
-    MOUSE_MAPPINGS, STATUS_BAR_HEIGHT, TAB_BAR_HEIGHT, USER_WORKSPACES, WINDOW_BORDER_WIDTH,
-    WINDOW_PADDING, WM_SHOW_BAR_INITIALLY, WORKSPACE_BAR_WINDOW_NAME_PADDING,
-    WORKSPACE_SECTION_FONTS,
+    Action, BarShortcut, FontCfg, AUTOHIDE_BAR_MONITORS, BAR_SECTIONS, BINARY_HEAP_LIMIT,
+    DYING_WINDOW_CACHE, PER_MONITOR_WORKSPACES, PLACEMENT_HISTORY_SIZE, SCALE_FACTOR,
+    STATUS_BAR_HEIGHT, TAB_BAR_HEIGHT, WM_SHOW_BAR_INITIALLY, WORKSPACE_BAR_WINDOW_NAME_PADDING,
+    WS_WINDOW_LIMIT, _BORDER_GRADIENT_CACHE_LIMIT,
 };
 #[cfg(feature = "status-bar")]
-use pgwm_core::config::{_STATUS_BAR_CHECK_SEP, _STATUS_BAR_FIRST_SEP};
-use pgwm_core::geometry::{Dimensions, Line};
+use pgwm_core::config::{_STATUS_BAR_CHECK_SEP, _STATUS_BAR_FIRST_SEP, _STATUS_CHECK_LIMIT};
+use pgwm_core::geometry::{dpi_scale, scale_px, Dimensions, Line};
 use pgwm_core::push_heapless;
+use pgwm_core::render::image::Image;
 use pgwm_core::render::{DoubleBufferedRenderPicture, RenderPicture, RenderVisualInfo};
 #[cfg(feature = "status-bar")]
 use pgwm_core::state::bar_geometry::StatusSection;
 use pgwm_core::state::bar_geometry::{
-    BarGeometry, FixedDisplayComponent, ShortcutComponent, ShortcutSection, WorkspaceSection,
+    format_urgent, BarGeometry, FixedDisplayComponent, ShortcutComponent, ShortcutSection,
+    UrgentSection, WorkspaceSection,
 };
+use pgwm_core::state::hints::MAX_WINDOW_HINTS;
+use pgwm_core::state::placement::PlacementHistory;
+use pgwm_core::state::transition::BorderTransitions;
 use pgwm_core::state::workspace::Workspaces;
 use pgwm_core::state::{Monitor, State, WinMarkedForDeath};
 #[cfg(feature = "status-bar")]
 use pgwm_core::status::checker::{Check, CheckType};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::manager::font::{FontDrawer, LoadedFonts};
 use crate::x11::call_wrapper::CallWrapper;
 
@@ -53,9 +66,20 @@ pub(crate) fn create_state<'a>(
     visual: RenderVisualInfo,
     screen: &'a Screen,
     colors: Colors,
+    scales: &[f32],
+    cfg: &'static Cfg,
 ) -> Result<State> {
     let mut cookie_container = heapless::Vec::new();
-    let static_state = create_static_state(call_wrapper, screen, &colors, &mut cookie_container)?;
+    let max_scale = scales.iter().fold(1.0, |max, scale| scale.max(max));
+    let static_state = create_static_state(
+        call_wrapper,
+        screen,
+        &colors,
+        scale_px(TAB_BAR_HEIGHT, max_scale),
+        &mut cookie_container,
+    )?;
+    let mut workspaces = Workspaces::create_empty(cfg.workspaces)?;
+    workspaces.set_placement_history(PlacementHistory::load(PLACEMENT_HISTORY_SIZE));
     do_create_state(
         call_wrapper,
         font_manager,
@@ -63,14 +87,17 @@ pub(crate) fn create_state<'a>(
         screen.clone(),
         static_state.intern_created_windows,
         heapless::Vec::new(),
-        Workspaces::create_empty(&USER_WORKSPACES)?,
+        workspaces,
         colors,
         static_state.wm_check_win,
         static_state.sequences_to_ignore,
+        Vec::new(),
         false,
-        WINDOW_BORDER_WIDTH,
-        WINDOW_PADDING,
+        false,
+        cfg.window_border_width,
+        cfg.window_padding,
         cookie_container,
+        cfg,
     )
 }
 
@@ -92,10 +119,13 @@ pub(crate) fn reinit_state<'a>(
         state.colors,
         state.wm_check_win,
         state.sequences_to_ignore,
+        state.pending_unmaps,
         state.pointer_grabbed,
+        state.idle_inhibited,
         state.window_border_width,
         state.window_padding,
         cookie_container,
+        state.cfg,
     )
 }
 
@@ -116,17 +146,43 @@ pub(crate) fn teardown_dynamic_state(call_wrapper: &mut CallWrapper, state: &Sta
             true,
         )?;
     }
+    for win in core::iter::once(&state.keybind_help_win).chain(&state.window_hint_wins) {
+        call_wrapper.send_destroy(win.window.drawable)?;
+        free_picture(
+            &mut call_wrapper.uring,
+            &mut call_wrapper.xcb_state,
+            win.window.picture,
+            true,
+        )?;
+    }
+    if let Some(root_background) = &state.root_background {
+        free_picture(
+            &mut call_wrapper.uring,
+            &mut call_wrapper.xcb_state,
+            root_background.picture,
+            true,
+        )?;
+        // The root window keeps its own reference while the pixmap is its background
+        free_pixmap(
+            &mut call_wrapper.uring,
+            &mut call_wrapper.xcb_state,
+            root_background.drawable,
+            true,
+        )?;
+    }
     Ok(())
 }
 
 pub(crate) fn teardown_full_state(
     call_wrapper: &mut CallWrapper,
     state: &State,
-    loaded_fonts: &LoadedFonts,
+    loaded_fonts: &[LoadedFonts],
 ) -> Result<()> {
     let _ = teardown_dynamic_state(call_wrapper, state);
+    #[cfg(feature = "frames")]
+    call_wrapper.release_frames()?;
     call_wrapper.send_destroy(state.wm_check_win)?;
-    for font in loaded_fonts.fonts.values() {
+    for font in loaded_fonts.iter().flat_map(|loaded| loaded.fonts.values()) {
         free_glyph_set(
             &mut call_wrapper.uring,
             &mut call_wrapper.xcb_state,
@@ -159,25 +215,37 @@ fn do_create_state<'a>(
     screen: Screen,
     mut intern_created_windows: Map<Window, ()>,
     dying_windows: heapless::Vec<WinMarkedForDeath, DYING_WINDOW_CACHE>,
-    workspaces: Workspaces,
+    mut workspaces: Workspaces,
     colors: Colors,
     wm_check_win: Window,
     sequences_to_ignore: heapless::BinaryHeap<u16, Min, BINARY_HEAP_LIMIT>,
+    pending_unmaps: Vec<(Window, u16)>,
     pointer_grabbed: bool,
+    idle_inhibited: bool,
     window_border_width: u32,
     window_padding: i16,
     mut cookie_container: heapless::Vec<VoidCookie, COOKIE_CONTAINER_CAPACITY>,
+    cfg: &'static Cfg,
 ) -> Result<State> {
-    let screen_dimensions = get_screen_dimensions(call_wrapper, &screen)?;
+    let screen_dimensions = get_monitors(call_wrapper, &screen)?;
 
+    if PER_MONITOR_WORKSPACES {
+        workspaces.ensure_sets(cfg.workspaces, screen_dimensions.len());
+    }
     let mut monitors = Vec::with_capacity(8);
     let mut max_bar_width = 0;
-    for (i, dimensions) in screen_dimensions.into_iter().enumerate() {
+    let mut max_bar_height = 0;
+    for (i, (dimensions, scale)) in screen_dimensions.into_iter().enumerate() {
         if dimensions.width > max_bar_width {
             max_bar_width = dimensions.width;
         }
-        pgwm_utils::debug!("Monitor {} size = {:?}", i, dimensions);
-        if i > USER_WORKSPACES.len() {
+        let status_bar_height = scale_px(STATUS_BAR_HEIGHT, scale);
+        let tab_bar_height = scale_px(TAB_BAR_HEIGHT, scale);
+        if status_bar_height > max_bar_height {
+            max_bar_height = status_bar_height;
+        }
+        pgwm_utils::debug!("Monitor {} size = {:?}, scale = {}", i, dimensions, scale);
+        if !PER_MONITOR_WORKSPACES && i > cfg.workspaces.len() {
             pgwm_utils::debug!(
                 "More monitors than workspaces, not using more than {}",
                 i - 1
@@ -194,7 +262,7 @@ fn do_create_state<'a>(
                 &screen,
                 tab_bar_win,
                 dimensions,
-                TAB_BAR_HEIGHT,
+                tab_bar_height,
             )?
         )?;
         let bar_win = call_wrapper.generate_id()?;
@@ -206,7 +274,7 @@ fn do_create_state<'a>(
                 &screen,
                 bar_win,
                 dimensions,
-                STATUS_BAR_HEIGHT as u16
+                status_bar_height as u16
             )?
         )?;
         let bar_pixmap = call_wrapper.generate_id()?;
@@ -217,7 +285,7 @@ fn do_create_state<'a>(
                 &screen,
                 bar_pixmap,
                 dimensions,
-                STATUS_BAR_HEIGHT as u16
+                status_bar_height as u16
             )?
         )?;
         if WM_SHOW_BAR_INITIALLY {
@@ -233,31 +301,40 @@ fn do_create_state<'a>(
         let tab_bar_win =
             init_xrender_double_buffered(call_wrapper, screen.root, tab_bar_win, &vis_info)?;
         let bar_geometry = create_bar_geometry(
-            font_manager,
+            &font_manager.at(scale),
             dimensions.width,
-            &USER_WORKSPACES,
+            cfg,
             WORKSPACE_BAR_WINDOW_NAME_PADDING,
             WORKSPACE_BAR_WINDOW_NAME_PADDING,
-            #[cfg(feature = "status-bar")]
-            &pgwm_core::config::STATUS_CHECKS,
         );
         let new_mon = Monitor {
             bar_geometry,
             bar_win,
             tab_bar_win,
             dimensions,
-            hosted_workspace: i,
+            scale,
+            bar_height: status_bar_height,
+            tab_bar_height,
+            // The first workspace of its own set, or the next one of the shared set
+            hosted_workspace: if PER_MONITOR_WORKSPACES {
+                i * cfg.workspaces.len()
+            } else {
+                i
+            },
+            recent_workspaces: Vec::new(),
             last_focus: None,
             show_bar: WM_SHOW_BAR_INITIALLY,
+            autohide_bar: AUTOHIDE_BAR_MONITORS.contains(&i),
+            showing_desktop: false,
             window_title_display: heapless::String::try_from("pgwm").unwrap(),
         };
         monitors.push(new_mon);
     }
 
     pgwm_utils::debug!("Initializing mouse");
-    let mouse_mapping = init_mouse();
+    let mouse_mapping = init_mouse(cfg);
     pgwm_utils::debug!("Initializing keys");
-    let key_mapping = init_keys(call_wrapper)?;
+    let key_mapping = init_keys(call_wrapper, cfg)?;
     grab_keys(call_wrapper, &key_mapping, screen.root)?;
     for bar_win in monitors.iter().map(|mon| &mon.bar_win) {
         pgwm_utils::debug!("Grabbing mouse keys on bar_win");
@@ -281,10 +358,78 @@ fn do_create_state<'a>(
             &screen,
             status_pixmap,
             max_bar_width as u16,
-            STATUS_BAR_HEIGHT as u16
+            max_bar_height as u16
         )?
     )?;
 
+    pgwm_utils::debug!("Creating keybind help window");
+    let keybind_help_win = call_wrapper.generate_id()?;
+    intern_created_windows.insert(keybind_help_win, ());
+    push_heapless!(
+        cookie_container,
+        create_keybind_help_win(call_wrapper, &screen, keybind_help_win)?
+    )?;
+    let keybind_help_win =
+        init_xrender_double_buffered(call_wrapper, screen.root, keybind_help_win, &vis_info)?;
+
+    pgwm_utils::debug!("Creating window hint windows");
+    let mut window_hint_wins = Vec::with_capacity(MAX_WINDOW_HINTS);
+    for _ in 0..MAX_WINDOW_HINTS {
+        let hint_win = call_wrapper.generate_id()?;
+        intern_created_windows.insert(hint_win, ());
+        // Checked right away so that they don't take up room in the cookie container
+        create_keybind_help_win(call_wrapper, &screen, hint_win)?
+            .check(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
+        window_hint_wins.push(init_xrender_double_buffered(
+            call_wrapper,
+            screen.root,
+            hint_win,
+            &vis_info,
+        )?);
+    }
+
+    let background_images = load_background_images(cfg);
+    let root_background = if cfg
+        .workspaces
+        .iter()
+        .any(|ws| ws.background.is_some() || ws.background_image.is_some())
+    {
+        pgwm_utils::debug!("Creating root background pixmap");
+        let pixmap = call_wrapper.generate_id()?;
+        push_heapless!(
+            cookie_container,
+            create_pixmap(
+                &mut call_wrapper.uring,
+                &mut call_wrapper.xcb_state,
+                screen.root_depth,
+                pixmap,
+                screen.root,
+                screen.width_in_pixels,
+                screen.height_in_pixels,
+                false,
+            )?
+        )?;
+        let picture = call_wrapper.window_mapped_picture(pixmap, &vis_info)?;
+        Some(RenderPicture {
+            drawable: pixmap,
+            picture,
+            format: vis_info.root.pict_format,
+        })
+    } else {
+        None
+    };
+
+    // Checked right away to schedule the next check
+    #[cfg(feature = "idle")]
+    let next_idle_check = if call_wrapper.has_screensaver_extension()? {
+        Some(Instant::now())
+    } else {
+        eprintln!("The X server lacks the MIT-SCREEN-SAVER extension, idle actions are disabled");
+        None
+    };
+    #[cfg(not(feature = "idle"))]
+    let next_idle_check = None;
+
     for cookie in cookie_container {
         cookie.check(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
     }
@@ -292,12 +437,20 @@ fn do_create_state<'a>(
     Ok(State {
         intern_created_windows,
         drag_window: None,
+        window_picker: None,
+        keybind_help_win,
+        keybind_help_shown: false,
+        window_hint_wins,
+        window_hints: None,
+        urgent_windows: Vec::new(),
         focused_mon: 0,
         input_focus: None,
+        pending_focus: None,
         screen: screen.clone(),
         dying_windows,
         wm_check_win,
         sequences_to_ignore,
+        pending_unmaps,
         monitors,
         workspaces,
         colors,
@@ -307,13 +460,53 @@ fn do_create_state<'a>(
         mouse_mapping,
         key_mapping,
         last_timestamp: CURRENT_TIME,
+        last_key_release: None,
+        root_background,
+        background_images,
+        edge_dwell: None,
+        dragged_tab: None,
+        idle_inhibited,
+        idle_handled: false,
+        next_idle_check,
+        next_screensaver_reset: None,
+        title_notice: None,
+        border_transitions: BorderTransitions::default(),
+        border_gradients: GradientCache::new(_BORDER_GRADIENT_CACHE_LIMIT),
+        workspace_slide: None,
+        status_format_cycle: None,
+        status_interval_change: None,
+        popups: Vec::new(),
+        cfg,
     })
 }
 
+/// Decode the workspaces' background images, an image that can't be loaded is left out
+/// and the workspace falls back to its background color
+fn load_background_images(cfg: &Cfg) -> Vec<(&'static UnixStr, Image)> {
+    let mut images: Vec<(&'static UnixStr, Image)> = Vec::new();
+    for path in cfg.workspaces.iter().filter_map(|ws| ws.background_image) {
+        if images.iter().any(|(loaded, _)| *loaded == path) {
+            continue;
+        }
+        match tiny_std::fs::read(path)
+            .map_err(Error::from)
+            .and_then(|bytes| Image::decode_ppm(&bytes).map_err(Error::from))
+        {
+            Ok(image) => images.push((path, image)),
+            Err(e) => eprintln!(
+                "Failed to load background image {}: {e}",
+                path.as_str().unwrap_or_default().trim_end_matches('\0')
+            ),
+        }
+    }
+    images
+}
+
 fn create_static_state<'a>(
     call_wrapper: &'a mut CallWrapper,
     screen: &'a Screen,
     colors: &Colors,
+    tab_bar_height: i16,
     cookie_container: &mut heapless::Vec<VoidCookie, COOKIE_CONTAINER_CAPACITY>,
 ) -> Result<StaticState> {
     let mut intern_created_windows = Map::new();
@@ -321,7 +514,7 @@ fn create_static_state<'a>(
     let tab_pixmap = call_wrapper.generate_id()?;
     push_heapless!(
         cookie_container,
-        create_tab_pixmap(call_wrapper, screen, tab_pixmap, TAB_BAR_HEIGHT as u16)?
+        create_tab_pixmap(call_wrapper, screen, tab_pixmap, tab_bar_height as u16)?
     )?;
 
     let sequences_to_ignore = heapless::BinaryHeap::new();
@@ -356,7 +549,7 @@ fn create_tab_bar_win(
     tab_bar_height: i16,
 ) -> Result<VoidCookie> {
     let create_win = CreateWindowValueList::default()
-        .event_mask(EventMask::BUTTON_PRESS)
+        .event_mask(EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE | EventMask::BUTTON_MOTION)
         .background_pixel(0);
     Ok(create_window(
         &mut call_wrapper.uring,
@@ -376,6 +569,31 @@ fn create_tab_bar_win(
     )?)
 }
 
+/// Created at 1x1, it's sized to fit its content when shown. Also used for the window hint badges.
+fn create_keybind_help_win(
+    call_wrapper: &mut CallWrapper,
+    screen: &Screen,
+    keybind_help_win: Window,
+) -> Result<VoidCookie> {
+    let create_win = CreateWindowValueList::default().background_pixel(0);
+    Ok(create_window(
+        &mut call_wrapper.uring,
+        &mut call_wrapper.xcb_state,
+        COPY_DEPTH_FROM_PARENT,
+        keybind_help_win,
+        screen.root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClassEnum::INPUT_OUTPUT,
+        0,
+        create_win,
+        false,
+    )?)
+}
+
 fn create_workspace_bar_win(
     call_wrapper: &mut CallWrapper,
     screen: &Screen,
@@ -390,7 +608,8 @@ fn create_workspace_bar_win(
                 | EventMask::FOCUS_CHANGE
                 | EventMask::STRUCTURE_NOTIFY
                 | EventMask::VISIBILITY_CHANGE
-                | EventMask::LEAVE_WINDOW,
+                | EventMask::LEAVE_WINDOW
+                | EventMask::POINTER_MOTION,
         );
     Ok(create_window(
         &mut call_wrapper.uring,
@@ -506,25 +725,48 @@ fn create_background_gc(
     Ok((gc, cookie))
 }
 
-#[cfg(not(feature = "xinerama"))]
+/// The distinct scales of the connected monitors, fonts are loaded once for each
+pub(crate) fn monitor_scales(call_wrapper: &mut CallWrapper, screen: &Screen) -> Result<Vec<f32>> {
+    let mut scales: Vec<f32> = Vec::new();
+    for (_, scale) in get_monitors(call_wrapper, screen)? {
+        if !scales.contains(&scale) {
+            scales.push(scale);
+        }
+    }
+    Ok(scales)
+}
+
+/// [`SCALE_FACTOR`] if configured, otherwise derived from the monitor's DPI
+fn monitor_scale(width_px: u16, width_mm: u32) -> f32 {
+    SCALE_FACTOR.unwrap_or_else(|| dpi_scale(width_px, u16::try_from(width_mm).unwrap_or_default()))
+}
+
+/// The whole screen as a single monitor
+fn screen_monitor(screen: &Screen) -> (Dimensions, f32) {
+    (
+        Dimensions::new(
+            screen.width_in_pixels as i16,
+            screen.height_in_pixels as i16,
+            0,
+            0,
+        ),
+        monitor_scale(
+            screen.width_in_pixels,
+            u32::from(screen.width_in_millimeters),
+        ),
+    )
+}
+
+#[cfg(not(any(feature = "xinerama", feature = "randr")))]
 #[allow(clippy::unnecessary_wraps)]
-fn get_screen_dimensions(
-    _connection: &mut CallWrapper,
-    screen: &Screen,
-) -> Result<Vec<Dimensions>> {
-    Ok(alloc::vec![Dimensions::new(
-        screen.width_in_pixels as i16,
-        screen.height_in_pixels as i16,
-        0,
-        0,
-    )])
+fn get_monitors(_connection: &mut CallWrapper, screen: &Screen) -> Result<Vec<(Dimensions, f32)>> {
+    Ok(alloc::vec![screen_monitor(screen)])
 }
 
-#[cfg(feature = "xinerama")]
-fn get_screen_dimensions(
-    call_wrapper: &mut CallWrapper,
-    _screen: &Screen,
-) -> Result<Vec<Dimensions>> {
+/// Xinerama doesn't report physical sizes, every monitor gets the screen's DPI
+#[cfg(all(feature = "xinerama", not(feature = "randr")))]
+fn get_monitors(call_wrapper: &mut CallWrapper, screen: &Screen) -> Result<Vec<(Dimensions, f32)>> {
+    let (_, scale) = screen_monitor(screen);
     Ok(xcb_rust_protocol::connection::xinerama::query_screens(
         &mut call_wrapper.uring,
         &mut call_wrapper.xcb_state,
@@ -534,16 +776,52 @@ fn get_screen_dimensions(
     .screen_info
     .iter()
     .map(|screen_info| {
-        Dimensions::new(
-            screen_info.width as i16,
-            screen_info.height as i16,
-            screen_info.x_org,
-            screen_info.y_org,
+        (
+            Dimensions::new(
+                screen_info.width as i16,
+                screen_info.height as i16,
+                screen_info.x_org,
+                screen_info.y_org,
+            ),
+            scale,
         )
     })
     .collect())
 }
 
+/// Active `RandR` monitors, each scaled by the physical size of its outputs
+#[cfg(feature = "randr")]
+fn get_monitors(call_wrapper: &mut CallWrapper, screen: &Screen) -> Result<Vec<(Dimensions, f32)>> {
+    let monitors = xcb_rust_protocol::connection::randr::get_monitors(
+        &mut call_wrapper.uring,
+        &mut call_wrapper.xcb_state,
+        screen.root,
+        true,
+        false,
+    )?
+    .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?
+    .monitors
+    .iter()
+    .map(|monitor| {
+        (
+            Dimensions::new(
+                monitor.width as i16,
+                monitor.height as i16,
+                monitor.x,
+                monitor.y,
+            ),
+            monitor_scale(monitor.width, monitor.width_in_millimeters),
+        )
+    })
+    .collect::<Vec<_>>();
+    // Servers without `RandR` 1.5 don't list monitors
+    if monitors.is_empty() {
+        Ok(alloc::vec![screen_monitor(screen)])
+    } else {
+        Ok(monitors)
+    }
+}
+
 fn create_tab_pixmap<'a>(
     call_wrapper: &'a mut CallWrapper,
     screen: &'a Screen,
@@ -585,28 +863,34 @@ fn create_status_bar_pixmap(
 fn create_bar_geometry<'a>(
     font_manager: &'a FontDrawer<'a>,
     mon_width: i16,
-    workspaces: &[UserWorkspace],
+    cfg: &Cfg,
     workspace_bar_window_name_padding: u16,
     shortcut_padding: u16,
-    #[cfg(feature = "status-bar")] checks: &[Check],
 ) -> BarGeometry {
+    let fonts = cfg.fonts.workspace_section;
     let workspace_section = create_workspace_section_geometry(
         font_manager,
-        workspaces,
+        cfg.workspaces,
         workspace_bar_window_name_padding,
+        fonts,
     );
-    let shortcut_section = create_shortcut_geometry(font_manager, mon_width, shortcut_padding);
-    #[cfg(feature = "status-bar")]
-    let status_section = create_status_section_geometry(
+    let urgent_section = create_urgent_geometry(
         font_manager,
-        mon_width,
-        shortcut_section.position.length,
-        checks,
+        cfg.workspaces,
+        workspace_bar_window_name_padding,
+        fonts,
     );
+    let shortcut_section =
+        create_shortcut_geometry(font_manager, cfg.bar_shortcuts, shortcut_padding, fonts);
+    #[cfg(feature = "status-bar")]
+    let status_section =
+        create_status_section_geometry(font_manager, cfg.status_checks, cfg.fonts.status_section);
 
     BarGeometry::new(
         mon_width,
+        &BAR_SECTIONS,
         workspace_section,
+        urgent_section,
         shortcut_section,
         #[cfg(feature = "status-bar")]
         status_section,
@@ -616,13 +900,10 @@ fn create_bar_geometry<'a>(
 #[cfg(feature = "status-bar")]
 fn create_status_section_geometry<'a>(
     font_manager: &'a FontDrawer<'a>,
-    mon_width: i16,
-    shortcut_width: i16,
     checks: &[Check],
+    fonts: &[FontCfg],
 ) -> StatusSection {
-    use pgwm_core::config::STATUS_SECTION;
-    let mut check_lengths: heapless::Vec<i16, { pgwm_core::config::STATUS_CHECKS.len() }> =
-        heapless::Vec::new();
+    let mut check_lengths: heapless::Vec<i16, _STATUS_CHECK_LIMIT> = heapless::Vec::new();
     for check in checks {
         let length = match &check.check_type {
             CheckType::Battery(bc) => bc
@@ -630,92 +911,133 @@ fn create_status_section_geometry<'a>(
                 .iter()
                 .map(|bc| {
                     font_manager
-                        .text_geometry(&bc.max_length_content(), STATUS_SECTION)
+                        .text_geometry(&bc.max_length_content(), fonts)
                         .0
                 })
                 .max()
                 .unwrap_or(0),
             CheckType::Cpu(fmt) => {
                 font_manager
-                    .text_geometry(&fmt.max_length_content(), STATUS_SECTION)
+                    .text_geometry(&fmt.max_length_content(), fonts)
                     .0
             }
             CheckType::Net(fmt) => {
                 font_manager
-                    .text_geometry(&fmt.max_length_content(), STATUS_SECTION)
+                    .text_geometry(&fmt.max_length_content(), fonts)
                     .0
             }
             CheckType::Mem(fmt) => {
                 font_manager
-                    .text_geometry(&fmt.max_length_content(), STATUS_SECTION)
+                    .text_geometry(&fmt.max_length_content(), fonts)
+                    .0
+            }
+            // Wide enough for the widest format
+            CheckType::Date(fmt) => (0..fmt.num_formats())
+                .map(|format_ind| {
+                    font_manager
+                        .text_geometry(&fmt.format_date(format_ind), fonts)
+                        .0
+                })
+                .max()
+                .unwrap_or_default(),
+            CheckType::Keyboard(fmt) => {
+                font_manager
+                    .text_geometry(&fmt.max_length_content(), fonts)
+                    .0
+            }
+            CheckType::Privacy(fmt) => {
+                font_manager
+                    .text_geometry(&fmt.max_length_content(), fonts)
+                    .0
+            }
+            CheckType::Media(fmt) => {
+                font_manager
+                    .text_geometry(&fmt.max_length_content(), fonts)
+                    .0
+            }
+            CheckType::Ping(fmt) => {
+                font_manager
+                    .text_geometry(&fmt.max_length_content(), fonts)
                     .0
             }
-            CheckType::Date(fmt) => {
+            CheckType::External(fmt) => {
                 font_manager
-                    .text_geometry(&fmt.format_date(), STATUS_SECTION)
+                    .text_geometry(&fmt.max_length_content(), fonts)
                     .0
             }
         };
         let _ = check_lengths.push(length);
     }
-    let sep_len = font_manager
-        .text_geometry(_STATUS_BAR_CHECK_SEP, STATUS_SECTION)
-        .0;
-    let first_sep = font_manager
-        .text_geometry(_STATUS_BAR_FIRST_SEP, STATUS_SECTION)
-        .0;
-    StatusSection::new(
-        mon_width,
-        shortcut_width,
-        &check_lengths,
-        sep_len,
-        first_sep,
-    )
+    let sep_len = font_manager.text_geometry(_STATUS_BAR_CHECK_SEP, fonts).0;
+    let first_sep = font_manager.text_geometry(_STATUS_BAR_FIRST_SEP, fonts).0;
+    StatusSection::new(&check_lengths, sep_len, first_sep)
 }
 
 fn create_workspace_section_geometry<'a>(
     font_manager: &'a FontDrawer<'a>,
     workspaces: &[UserWorkspace],
     workspace_bar_window_name_padding: u16,
+    fonts: &[FontCfg],
 ) -> WorkspaceSection {
     let (components, position) = create_fixed_components(
         workspaces.iter().map(|s| s.name),
         0,
         workspace_bar_window_name_padding,
         font_manager,
-        WORKSPACE_SECTION_FONTS,
+        fonts,
     );
     WorkspaceSection {
         position,
+        drawn_backgrounds: alloc::vec![None; components.len()],
         components,
     }
 }
 
+/// Wide enough to list every workspace with every window urgent
+fn create_urgent_geometry<'a>(
+    font_manager: &'a FontDrawer<'a>,
+    workspaces: &[UserWorkspace],
+    padding: u16,
+    fonts: &[FontCfg],
+) -> UrgentSection {
+    let widest = format_urgent(
+        WS_WINDOW_LIMIT * workspaces.len(),
+        workspaces.iter().map(|ws| ws.name),
+    );
+    let width = font_manager.text_geometry(&widest, fonts).0;
+    UrgentSection {
+        position: Line::new(0, width + padding as i16),
+    }
+}
+
 fn create_shortcut_geometry<'a>(
     font_manager: &'a FontDrawer<'a>,
-    mon_width: i16,
+    shortcuts: &[BarShortcut],
     shortcut_padding: u16,
+    fonts: &[FontCfg],
 ) -> ShortcutSection {
     let (components, position) = create_fixed_components(
-        BAR_SHORTCUTS.into_iter(),
+        shortcuts.iter().map(|shortcut| shortcut.glyph),
         0,
         shortcut_padding,
         font_manager,
-        WORKSPACE_SECTION_FONTS,
+        fonts,
     );
-    let position = Line::new(mon_width - position.length, position.length);
-    let mut shifted_components = Vec::new();
-    let component_offset = 0;
-    for component in components {
-        shifted_components.push(ShortcutComponent {
-            position: Line::new(position.start + component_offset, component.position.length),
+    let components = components
+        .into_iter()
+        .zip(shortcuts)
+        .map(|(component, shortcut)| ShortcutComponent {
+            position: component.position,
             write_offset: component.write_offset,
             text: component.text,
-        });
-    }
+            action: shortcut.action,
+            hover_text: shortcut.hover_text,
+        })
+        .collect();
     ShortcutSection {
         position,
-        components: shifted_components,
+        components,
+        hovered: None,
     }
 }
 
@@ -752,34 +1074,35 @@ fn create_fixed_components<It: Iterator<Item = &'static str>>(
     (components, Line::new(x, total_width as i16))
 }
 
-fn init_keys(call_wrapper: &mut CallWrapper) -> Result<Map<KeyBoardMappingKey, Action>> {
-    let setup = call_wrapper.xcb_state.setup();
-    let lo = setup.min_keycode;
-    let hi = setup.max_keycode;
-    let capacity = hi - lo + 1;
+/// The keyboard mapping changed (ie. through `setxkbmap`), keycodes the configured keysyms
+/// resolved to may have moved, ungrab the old ones and grab them again.
+pub(crate) fn reinit_keys(call_wrapper: &mut CallWrapper, state: &mut State) -> Result<()> {
+    ungrab_keys(call_wrapper, &state.key_mapping, state.screen.root)?;
+    state.key_mapping = init_keys(call_wrapper, state.cfg)?;
+    grab_keys(call_wrapper, &state.key_mapping, state.screen.root)
+}
 
-    let mapping = get_keyboard_mapping(
-        &mut call_wrapper.uring,
-        &mut call_wrapper.xcb_state,
-        lo,
-        capacity,
-        false,
-    )?
-    .reply(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
+fn init_keys(
+    call_wrapper: &mut CallWrapper,
+    cfg: &Cfg,
+) -> Result<Map<KeyBoardMappingKey, KeyboardMapping>> {
+    let table = call_wrapper.get_keysym_table()?;
     pgwm_utils::debug!("Got key mapping");
-    let syms = mapping.keysyms;
     let mut map = Map::new();
-
-    let mut converted: Vec<KeyboardMapping> = KEYBOARD_MAPPINGS.to_vec();
-    for (keysym_ind, sym) in syms.iter().enumerate() {
-        while let Some(keymap_ind) = converted.iter().position(|k| &k.keysym == sym) {
-            let key_def = converted.swap_remove(keymap_ind);
-            let mods = key_def.modmask.0;
-            let modded_ind = keysym_ind + mods as usize;
-            let code =
-                (modded_ind - mods as usize) / mapping.keysyms_per_keycode as usize + lo as usize;
-            let key = KeyBoardMappingKey::new(code as u8, mods);
-            map.insert(key, key_def.action);
+    for key_def in cfg.key_mappings {
+        if let Some(key) = table.resolve(key_def) {
+            // Distinct keysyms can end up on the same key, like `1` with shift and `!` on a us layout
+            if map.get(&key).is_some() {
+                eprintln!(
+                    "Keysym {} with {} is on the same key as an earlier binding, keeping the earlier one",
+                    keysym_label(key_def.keysym),
+                    mods_label(key_def.modmask.0)
+                );
+            } else {
+                map.insert(key, *key_def);
+            }
+        } else {
+            pgwm_utils::debug!("No keycode produces keysym {:#x}", key_def.keysym);
         }
     }
     Ok(map)
@@ -787,84 +1110,118 @@ fn init_keys(call_wrapper: &mut CallWrapper) -> Result<Map<KeyBoardMappingKey, A
 
 fn grab_keys(
     call_wrapper: &mut CallWrapper,
-    key_map: &Map<KeyBoardMappingKey, Action>,
+    key_map: &Map<KeyBoardMappingKey, KeyboardMapping>,
     root_win: Window,
 ) -> Result<()> {
     for key in key_map.keys() {
-        grab_key(
-            &mut call_wrapper.uring,
-            &mut call_wrapper.xcb_state,
-            0,
-            root_win,
-            key.mods.into(),
-            key.code.into(),
-            GrabModeEnum::ASYNC,
-            GrabModeEnum::ASYNC,
-            false,
-        )?
-        .check(&mut call_wrapper.uring, &mut call_wrapper.xcb_state)?;
+        for lock in LOCK_MOD_COMBINATIONS {
+            let res = grab_key(
+                &mut call_wrapper.uring,
+                &mut call_wrapper.xcb_state,
+                0,
+                root_win,
+                (key.mods | lock).into(),
+                key.code.into(),
+                GrabModeEnum::ASYNC,
+                GrabModeEnum::ASYNC,
+                false,
+            )?
+            .check(&mut call_wrapper.uring, &mut call_wrapper.xcb_state);
+            // Another client holding the grab shouldn't keep the rest of the bindings from working
+            if let Err(e) = res {
+                eprintln!(
+                    "Failed to grab keycode {} with {}, is another program bound to it? {e}",
+                    key.code,
+                    mods_label(key.mods)
+                );
+                break;
+            }
+        }
     }
     Ok(())
 }
 
 fn ungrab_keys(
     call_wrapper: &mut CallWrapper,
-    key_map: &Map<KeyBoardMappingKey, Action>,
+    key_map: &Map<KeyBoardMappingKey, KeyboardMapping>,
     root_win: Window,
 ) -> Result<()> {
     for key in key_map.keys() {
-        ungrab_key(
-            &mut call_wrapper.uring,
-            &mut call_wrapper.xcb_state,
-            GrabEnum(key.code),
-            root_win,
-            key.mods.into(),
-            true,
-        )?;
+        for lock in LOCK_MOD_COMBINATIONS {
+            ungrab_key(
+                &mut call_wrapper.uring,
+                &mut call_wrapper.xcb_state,
+                GrabEnum(key.code),
+                root_win,
+                (key.mods | lock).into(),
+                true,
+            )?;
+        }
     }
     Ok(())
 }
 
-fn init_mouse() -> Map<MouseActionKey, Action> {
+fn init_mouse(cfg: &Cfg) -> Map<MouseActionKey, Action> {
     let mut action_map = Map::new();
-    for mapping in MOUSE_MAPPINGS {
-        action_map.insert(
-            MouseActionKey {
-                detail: mapping.button.0,
-                state: mapping.mods.0,
-                target: mapping.target,
-            },
-            mapping.action,
-        );
+    for mapping in cfg.mouse_mappings {
+        let key = MouseActionKey {
+            detail: mapping.button.0,
+            state: mapping.mods.0,
+            target: mapping.target.clone(),
+        };
+        // Same as for keys the first binding wins, `Cfg::validate` rejects configurations with duplicates
+        if action_map.get(&key).is_none() {
+            action_map.insert(key, mapping.action);
+        }
     }
     action_map
 }
 
+/// The distinct `(button, mods, window)` grabs needed for the mouse mappings,
+/// every target on the bar is covered by a single grab on the bar window
+fn button_grabs(
+    bar_win: Window,
+    root_win: Window,
+    mouse_map: &Map<MouseActionKey, Action>,
+) -> Vec<(u8, u16, Window)> {
+    let mut grabs = Vec::new();
+    for key in mouse_map.keys() {
+        let win = if key.target.on_bar() {
+            bar_win
+        } else {
+            root_win
+        };
+        let grab = (key.detail, key.state, win);
+        if !grabs.contains(&grab) {
+            grabs.push(grab);
+        }
+    }
+    grabs
+}
+
 fn grab_mouse(
     call_wrapper: &mut CallWrapper,
     bar_win: Window,
     root_win: Window,
     mouse_map: &Map<MouseActionKey, Action>,
 ) -> Result<()> {
-    for key in mouse_map.keys() {
-        grab_button(
-            &mut call_wrapper.uring,
-            &mut call_wrapper.xcb_state,
-            0,
-            if key.target.on_bar() {
-                bar_win
-            } else {
-                root_win
-            },
-            EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE,
-            GrabModeEnum::ASYNC,
-            GrabModeEnum::ASYNC,
-            WindowEnum::NONE,
-            CursorEnum::NONE,
-            key.detail.into(),
-            key.state.into(),
-            true,
-        )?;
+    for (detail, mods, win) in button_grabs(bar_win, root_win, mouse_map) {
+        for lock in LOCK_MOD_COMBINATIONS {
+            grab_button(
+                &mut call_wrapper.uring,
+                &mut call_wrapper.xcb_state,
+                0,
+                win,
+                EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE,
+                GrabModeEnum::ASYNC,
+                GrabModeEnum::ASYNC,
+                WindowEnum::NONE,
+                CursorEnum::NONE,
+                detail.into(),
+                (mods | lock).into(),
+                true,
+            )?;
+        }
     }
     Ok(())
 }
@@ -875,19 +1232,17 @@ fn ungrab_mouse(
     root_win: Window,
     mouse_map: &Map<MouseActionKey, Action>,
 ) -> Result<()> {
-    for key in mouse_map.keys() {
-        ungrab_button(
-            &mut call_wrapper.uring,
-            &mut call_wrapper.xcb_state,
-            key.detail.into(),
-            if key.target.on_bar() {
-                bar_win
-            } else {
-                root_win
-            },
-            key.state.into(),
-            true,
-        )?;
+    for (detail, mods, win) in button_grabs(bar_win, root_win, mouse_map) {
+        for lock in LOCK_MOD_COMBINATIONS {
+            ungrab_button(
+                &mut call_wrapper.uring,
+                &mut call_wrapper.xcb_state,
+                detail.into(),
+                win,
+                (mods | lock).into(),
+                true,
+            )?;
+        }
     }
     Ok(())
 }
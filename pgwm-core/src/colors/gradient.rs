@@ -0,0 +1,97 @@
+use alloc::vec::Vec;
+
+use crate::colors::Color;
+
+/// The `steps` colors going from `from` to `to` as `bgra8`, without `from` and ending with `to`
+#[must_use]
+pub fn gradient(from: [u8; 4], to: [u8; 4], steps: usize) -> impl Iterator<Item = [u8; 4]> {
+    (1..=steps).map(move |step| {
+        let mut color = [0; 4];
+        for (channel, (from, to)) in color.iter_mut().zip(from.into_iter().zip(to)) {
+            let diff = i64::from(to) - i64::from(from);
+            *channel = (i64::from(from) + diff * step as i64 / steps as i64) as u8;
+        }
+        color
+    })
+}
+
+/// Allocated gradients between pairs of colors, so that a transition that's been shown before
+/// doesn't need its pixels allocated again. The least recently used gradient is dropped first.
+pub struct GradientCache {
+    // Least recently used first
+    entries: Vec<(([u8; 4], [u8; 4]), Vec<Color>)>,
+    capacity: usize,
+}
+
+impl GradientCache {
+    #[must_use]
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// The gradient from `from` to `to` if it's been allocated
+    pub fn get(&mut self, from: [u8; 4], to: [u8; 4]) -> Option<&[Color]> {
+        let ind = self
+            .entries
+            .iter()
+            .position(|(key, _)| *key == (from, to))?;
+        let entry = self.entries.remove(ind);
+        self.entries.push(entry);
+        self.entries.last().map(|(_, gradient)| gradient.as_slice())
+    }
+
+    pub fn insert(&mut self, from: [u8; 4], to: [u8; 4], gradient: Vec<Color>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(key, _)| *key != (from, to));
+        while self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push(((from, to), gradient));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::colors::gradient::{gradient, GradientCache};
+    use crate::colors::Color;
+
+    #[test]
+    fn steps_towards_target() {
+        let steps = gradient([0, 100, 255, 0], [100, 0, 255, 0], 4).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                [25, 75, 255, 0],
+                [50, 50, 255, 0],
+                [75, 25, 255, 0],
+                [100, 0, 255, 0]
+            ],
+            steps
+        );
+        assert_eq!(0, gradient([0; 4], [1; 4], 0).count());
+    }
+
+    #[test]
+    fn drops_least_recently_used() {
+        let color = |pixel| Color {
+            pixel,
+            bgra8: [0; 4],
+        };
+        let mut cache = GradientCache::new(2);
+        cache.insert([0; 4], [1; 4], vec![color(1)]);
+        cache.insert([1; 4], [0; 4], vec![color(0)]);
+        // Uses the first, the second is now the least recently used
+        assert_eq!(1, cache.get([0; 4], [1; 4]).unwrap()[0].pixel);
+        cache.insert([2; 4], [0; 4], vec![color(0)]);
+        assert!(cache.get([1; 4], [0; 4]).is_none());
+        assert!(cache.get([0; 4], [1; 4]).is_some());
+        assert!(cache.get([2; 4], [0; 4]).is_some());
+    }
+}
@@ -1,6 +1,12 @@
-use crate::config::COLORS;
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::fmt::Debug;
 
+use crate::config::_WM_CLASS_NAME_LIMIT;
+use crate::config::{BORDER_COLOR_RULES, COLORS};
+
+pub mod gradient;
+
 #[derive(Debug, Copy, Clone)]
 pub struct Color {
     pub pixel: u32,
@@ -48,6 +54,97 @@ impl Rgba8 for (u8, u8, u8, u8) {
 
 pub type RGBA = (u8, u8, u8, u8);
 
+/// Names of the colors in the order of [`COLORS`], used by the config file and [`crate::config::XRESOURCE_COLORS`]
+pub const COLOR_NAMES: [&str; COLORS.len()] = [
+    "window-border",
+    "window-border-highlighted",
+    "window-border-urgent",
+    "workspace-bar-selected-unfocused-workspace-background",
+    "workspace-bar-unfocused-workspace-background",
+    "workspace-bar-focused-workspace-background",
+    "workspace-bar-urgent-workspace-background",
+    "workspace-bar-workspace-section-text",
+    "workspace-bar-current-window-title-text",
+    "workspace-bar-current-window-title-background",
+    "status-bar-text",
+    "status-bar-background",
+    "tab-bar-text",
+    "tab-bar-focused-tab-background",
+    "tab-bar-unfocused-tab-background",
+    "shortcut-text",
+    "shortcut-background",
+    "status-bar-alert-text",
+];
+
+/// Parses `#rrggbb` and `#rrggbbaa` (the `#` is optional), the alpha is 0 if left out
+#[must_use]
+pub fn parse_hex(hex: &str) -> Option<RGBA> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let channel = |i: usize| {
+        digits
+            .get(i * 2..i * 2 + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    match digits.len() {
+        6 => channel(0)
+            .zip(channel(1))
+            .zip(channel(2))
+            .map(|((r, g), b)| (r, g, b, 0)),
+        8 => channel(0)
+            .zip(channel(1))
+            .zip(channel(2))
+            .zip(channel(3))
+            .map(|(((r, g), b), a)| (r, g, b, a)),
+        _ => None,
+    }
+}
+
+/// Parses a color the way it's usually written in X resources, `#rrggbb` or `rgb:r/g/b` with 1 to 4 hex digits
+/// per channel, the alpha is 0
+#[must_use]
+pub fn parse_x_color(color: &str) -> Option<RGBA> {
+    let color = color.trim();
+    let Some(channels) = color.strip_prefix("rgb:") else {
+        return color
+            .strip_prefix('#')
+            .filter(|digits| digits.len() == 6)
+            .and_then(parse_hex);
+    };
+    let mut parsed = [0u8; 3];
+    let mut split = channels.split('/');
+    for channel in &mut parsed {
+        let digits = split.next()?;
+        if digits.is_empty() || digits.len() > 4 {
+            return None;
+        }
+        let value = u16::from_str_radix(digits, 16).ok()?;
+        // Scale to 8 bits, `f` is as bright as `ffff`
+        let max = (1u32 << (4 * digits.len())) - 1;
+        *channel = u8::try_from(u32::from(value) * 255 / max).ok()?;
+    }
+    if split.next().is_some() {
+        return None;
+    }
+    Some((parsed[0], parsed[1], parsed[2], 0))
+}
+
+/// Replaces the colors named in `mappings`, pairs of a name from [`COLOR_NAMES`] and the resource to read it from,
+/// with the values `lookup` finds for those resources. Colors keep their alpha.
+pub fn apply_resource_colors(
+    colors: &mut [RGBA; COLORS.len()],
+    mappings: &[(&str, &str)],
+    lookup: impl Fn(&str) -> Option<String>,
+) {
+    for (name, resource) in mappings {
+        let Some(ind) = COLOR_NAMES.iter().position(|color| color == name) else {
+            continue;
+        };
+        if let Some((r, g, b, _)) = lookup(resource).as_deref().and_then(parse_x_color) {
+            colors[ind] = (r, g, b, colors[ind].3);
+        }
+    }
+}
+
 /**
 Color configuration, Here colors are set for different segments that the WM draws.
 Naming is hopefully fairly self-explanatory for what each color does.
@@ -73,13 +170,55 @@ pub struct ColorBuilder {
     pub tab_bar_unfocused_tab_background: RGBA,
     pub shortcut_text: RGBA,
     pub shortcut_background: RGBA,
+    pub status_bar_alert_text: RGBA,
+}
+
+/// Border colors for windows of a specific class, see [`BORDER_COLOR_RULES`]
+#[derive(Debug, Copy, Clone)]
+pub struct BorderColorRule {
+    pub class: &'static str,
+    pub normal: RGBA,
+    pub highlighted: RGBA,
+}
+
+impl BorderColorRule {
+    #[must_use]
+    pub const fn new(class: &'static str, normal: RGBA, highlighted: RGBA) -> Self {
+        Self {
+            class,
+            normal,
+            highlighted,
+        }
+    }
+
+    /// Whether either of a window's `WM_CLASS` names is this rule's class
+    #[must_use]
+    pub fn matches(&self, class: &[heapless::String<_WM_CLASS_NAME_LIMIT>]) -> bool {
+        class.iter().any(|name| name.as_str() == self.class)
+    }
 }
 
 pub struct Colors {
     pub inner: [Color; COLORS.len()],
+    /// The normal and highlighted border of each of [`BORDER_COLOR_RULES`], in the same order
+    pub class_borders: Vec<(Color, Color)>,
 }
 
 impl Colors {
+    /// The normal and highlighted border of a window with these `WM_CLASS` names,
+    /// from the first matching [`BORDER_COLOR_RULES`] entry if there is one
+    #[must_use]
+    pub fn window_borders(
+        &self,
+        class: &[heapless::String<_WM_CLASS_NAME_LIMIT>],
+    ) -> (Color, Color) {
+        BORDER_COLOR_RULES
+            .iter()
+            .zip(&self.class_borders)
+            .find_map(|(rule, borders)| rule.matches(class).then_some(*borders))
+            .unwrap_or((self.window_border(), self.window_border_highlighted()))
+    }
+
     #[inline]
     #[must_use]
     pub const fn window_border(&self) -> Color {
@@ -165,8 +304,77 @@ impl Colors {
     pub const fn shortcut_background(&self) -> Color {
         self.inner[16]
     }
+    #[inline]
+    #[must_use]
+    pub const fn status_bar_alert_text(&self) -> Color {
+        self.inner[17]
+    }
 }
 
 const fn convert_up(v: u8) -> u16 {
     v as u16 * 256
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::colors::{apply_resource_colors, parse_hex, parse_x_color, BorderColorRule};
+    use crate::config::COLORS;
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(Some((0xee, 0xd8, 0xae, 0)), parse_hex("#eed8ae"));
+        assert_eq!(Some((0xee, 0xd8, 0xae, 0x80)), parse_hex("eed8ae80"));
+        assert_eq!(None, parse_hex("#eed8a"));
+        assert_eq!(None, parse_hex("#gggggg"));
+    }
+
+    #[test]
+    fn parses_x_colors() {
+        assert_eq!(Some((0x28, 0x2a, 0x36, 0)), parse_x_color(" #282a36"));
+        assert_eq!(Some((0xff, 0x80, 0x00, 0)), parse_x_color("rgb:ff/80/00"));
+        assert_eq!(Some((0xff, 0x11, 0x00, 0)), parse_x_color("rgb:f/1111/0"));
+        assert_eq!(None, parse_x_color("#282a36ff"));
+        assert_eq!(None, parse_x_color("rgb:ff/80"));
+        assert_eq!(None, parse_x_color("rgb:ff/80/00/00"));
+        assert_eq!(None, parse_x_color("darkblue"));
+    }
+
+    #[test]
+    fn applies_resource_colors_keeping_alpha() {
+        let mut colors = COLORS;
+        let mappings = [
+            ("window-border", "color8"),
+            ("shortcut-text", "color15"),
+            ("tab-bar-text", "unset"),
+            ("no-such-color", "color1"),
+        ];
+        apply_resource_colors(&mut colors, &mappings, |resource| match resource {
+            "color8" => Some(String::from("#44475a")),
+            "color15" => Some(String::from("white")),
+            "color1" => Some(String::from("#ff5555")),
+            _ => None,
+        });
+        assert_eq!((0x44, 0x47, 0x5a, COLORS[0].3), colors[0]);
+        // Not a color
+        assert_eq!(COLORS[15], colors[15]);
+        assert_eq!(COLORS[12], colors[12]);
+        assert_eq!(COLORS[1..], colors[1..]);
+    }
+
+    #[test]
+    fn border_rule_matches_instance_or_class() {
+        let rule = BorderColorRule::new("scratch", (255, 0, 0, 0), (255, 0, 0, 0));
+        let class = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| heapless::String::try_from(*name).unwrap())
+                .collect::<heapless::Vec<_, 4>>()
+        };
+        assert!(rule.matches(&class(&["scratch", "Alacritty"])));
+        assert!(rule.matches(&class(&["st", "scratch"])));
+        assert!(!rule.matches(&class(&["scratchpad", "Alacritty"])));
+        assert!(!rule.matches(&class(&[])));
+    }
+}
@@ -0,0 +1,345 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Formatter;
+
+use tiny_std::UnixStr;
+
+use crate::colors::{COLOR_NAMES, RGBA};
+use crate::config::key_map::KeyboardMapping;
+use crate::config::mouse_map::{MouseMapping, MouseTarget};
+use crate::config::workspaces::UserWorkspace;
+use crate::config::{
+    Action, BarShortcut, FontCfg, BAR_SHORTCUTS, CHAR_REMAP_FONTS, COLORS, KEYBOARD_MAPPINGS, MOUSE_MAPPINGS,
+    PAD_WHILE_TABBED, SHORTCUT_SECTION, SMART_GAPS, TAB_BAR_SECTION, USER_WORKSPACES, WINDOW_BORDER_WIDTH,
+    WINDOW_NAME_DISPLAY_SECTION, WINDOW_PADDING, WORKSPACE_SECTION_FONTS, XRESOURCE_COLORS,
+};
+
+/// Fonts to draw each section of the bar and the tab bar with
+#[derive(Debug, Copy, Clone)]
+pub struct SectionFonts {
+    pub workspace_section: &'static [FontCfg<'static>],
+    pub window_name_display_section: &'static [FontCfg<'static>],
+    #[cfg(feature = "status-bar")]
+    pub status_section: &'static [FontCfg<'static>],
+    pub tab_bar_section: &'static [FontCfg<'static>],
+    pub shortcut_section: &'static [FontCfg<'static>],
+}
+
+/// The parts of the configuration that can be read from a config file when built with the `config-file` feature.
+/// Anything the file leaves out, or everything if there is no file, comes from the constants in [`crate::config`].
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub window_padding: i16,
+    pub window_border_width: u32,
+    pub pad_while_tabbed: bool,
+    pub smart_gaps: bool,
+    pub colors: [RGBA; COLORS.len()],
+    pub fonts: SectionFonts,
+    pub workspaces: &'static [UserWorkspace],
+    pub key_mappings: &'static [KeyboardMapping],
+    pub mouse_mappings: &'static [MouseMapping],
+    pub bar_shortcuts: &'static [BarShortcut],
+    #[cfg(feature = "status-bar")]
+    pub status_checks: &'static [crate::status::checker::Check],
+}
+
+impl Cfg {
+    /// The compiled in configuration
+    pub const DEFAULT: Cfg = Cfg {
+        window_padding: WINDOW_PADDING,
+        window_border_width: WINDOW_BORDER_WIDTH,
+        pad_while_tabbed: PAD_WHILE_TABBED,
+        smart_gaps: SMART_GAPS,
+        colors: COLORS,
+        fonts: SectionFonts {
+            workspace_section: WORKSPACE_SECTION_FONTS,
+            window_name_display_section: WINDOW_NAME_DISPLAY_SECTION,
+            #[cfg(feature = "status-bar")]
+            status_section: crate::config::STATUS_SECTION,
+            tab_bar_section: TAB_BAR_SECTION,
+            shortcut_section: SHORTCUT_SECTION,
+        },
+        workspaces: &USER_WORKSPACES,
+        key_mappings: &KEYBOARD_MAPPINGS,
+        mouse_mappings: &MOUSE_MAPPINGS,
+        bar_shortcuts: &BAR_SHORTCUTS,
+        #[cfg(feature = "status-bar")]
+        status_checks: &crate::config::STATUS_CHECKS,
+    };
+
+    /// The configuration to run with, read from the config file if there is one.
+    /// A file that can't be read or parsed, or that has problems, is reported and the compiled in
+    /// configuration is used instead.
+    /// Values from the file live until the process exits, each restart of the WM reads the file again.
+    /// # Errors
+    /// Every [`Problem`] in the compiled in configuration, if that's the one that would be used.
+    pub fn load() -> core::result::Result<&'static Cfg, Vec<Problem>> {
+        #[cfg(feature = "config-file")]
+        match Self::read_file() {
+            Ok(Some(cfg)) => {
+                let problems = cfg.validate();
+                if problems.is_empty() {
+                    return Ok(alloc::boxed::Box::leak(alloc::boxed::Box::new(cfg)));
+                }
+                tiny_std::eprintln!("Config file has problems, using the default configuration:");
+                for problem in problems {
+                    tiny_std::eprintln!("  {problem}");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tiny_std::eprintln!(
+                    "Failed to load config file, using the default configuration: {e}"
+                );
+            }
+        }
+        let problems = Self::DEFAULT.validate();
+        if problems.is_empty() {
+            Ok(&Self::DEFAULT)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Every problem in the configuration that would crash the WM, or silently make a binding useless.
+    /// Color values need no checking here, their components are bytes and the config file rejects anything
+    /// out of range when it's parsed.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        self.check_fonts(&mut problems);
+        for &(name, _) in XRESOURCE_COLORS {
+            if !COLOR_NAMES.contains(&name) {
+                problems.push(Problem::UnknownColor { name });
+            }
+        }
+        let workspaces = self.workspaces.len();
+        let actions = self
+            .key_mappings
+            .iter()
+            .enumerate()
+            .map(|(i, mapping)| (format!("key-mapping {}", i + 1), mapping.action))
+            .chain(
+                self.mouse_mappings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, mapping)| (format!("mouse-mapping {}", i + 1), mapping.action)),
+            )
+            .chain(
+                self.bar_shortcuts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, shortcut)| (format!("bar shortcut {}", i + 1), shortcut.action)),
+            );
+        for (binding, action) in actions {
+            if let Action::ToggleWorkspace(index)
+            | Action::SendToWorkspace(index)
+            | Action::SendMarkedToWorkspace(index) = action
+            {
+                if index >= workspaces {
+                    problems.push(Problem::WorkspaceOutOfBounds {
+                        binding,
+                        index,
+                        workspaces,
+                    });
+                }
+            }
+        }
+        for (i, mapping) in self.mouse_mappings.iter().enumerate() {
+            if let MouseTarget::WorkspaceBarComponent(index) = mapping.target {
+                if index >= workspaces {
+                    problems.push(Problem::WorkspaceOutOfBounds {
+                        binding: format!("mouse-mapping {}", i + 1),
+                        index,
+                        workspaces,
+                    });
+                }
+            }
+        }
+        for (second, mapping) in self.key_mappings.iter().enumerate() {
+            if let Some(first) = self.key_mappings[..second].iter().position(|earlier| {
+                earlier.modmask.0 == mapping.modmask.0 && earlier.keysym == mapping.keysym
+            }) {
+                problems.push(Problem::DuplicateBinding {
+                    first: format!("key-mapping {}", first + 1),
+                    second: format!("key-mapping {}", second + 1),
+                });
+            }
+        }
+        for (second, mapping) in self.mouse_mappings.iter().enumerate() {
+            if let Some(first) = self.mouse_mappings[..second].iter().position(|earlier| {
+                earlier.mods.0 == mapping.mods.0
+                    && earlier.button.0 == mapping.button.0
+                    && earlier.target == mapping.target
+            }) {
+                problems.push(Problem::DuplicateBinding {
+                    first: format!("mouse-mapping {}", first + 1),
+                    second: format!("mouse-mapping {}", second + 1),
+                });
+            }
+        }
+        problems
+    }
+
+    fn check_fonts(&self, problems: &mut Vec<Problem>) {
+        let sections = [
+            ("workspace-section", self.fonts.workspace_section),
+            (
+                "window-name-display-section",
+                self.fonts.window_name_display_section,
+            ),
+            #[cfg(feature = "status-bar")]
+            ("status-section", self.fonts.status_section),
+            ("tab-bar-section", self.fonts.tab_bar_section),
+            ("shortcut-section", self.fonts.shortcut_section),
+        ];
+        let fonts = sections
+            .into_iter()
+            .flat_map(|(section, fonts)| fonts.iter().map(move |font| (section, font)))
+            .chain(
+                CHAR_REMAP_FONTS
+                    .into_iter()
+                    .map(|font| ("char-remap", font)),
+            );
+        for (section, font) in fonts {
+            if tiny_std::fs::metadata(font.path).is_err() {
+                problems.push(Problem::MissingFont {
+                    section,
+                    path: font.path,
+                });
+            }
+            if !font.size.parse::<f32>().is_ok_and(|size| size > 0.0) {
+                problems.push(Problem::BadFontSize {
+                    section,
+                    size: font.size,
+                });
+            }
+        }
+    }
+}
+
+/// Something in the configuration that would crash the WM, or that makes a binding useless
+#[derive(Debug, Clone)]
+pub enum Problem {
+    MissingFont {
+        section: &'static str,
+        path: &'static UnixStr,
+    },
+    BadFontSize {
+        section: &'static str,
+        size: &'static str,
+    },
+    /// An action or mouse target pointing at a workspace index that doesn't exist
+    WorkspaceOutOfBounds {
+        binding: String,
+        index: usize,
+        workspaces: usize,
+    },
+    /// Two bindings on the same keys or buttons, only the first one is ever run
+    DuplicateBinding { first: String, second: String },
+    /// A color in [`XRESOURCE_COLORS`] that isn't one of the configurable colors
+    UnknownColor { name: &'static str },
+}
+
+impl core::fmt::Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Problem::MissingFont { section, path } => f.write_fmt(format_args!(
+                "{section} font {} can't be found, install it or configure another font",
+                path.as_str().unwrap_or("<not utf8>").trim_end_matches('\0')
+            )),
+            Problem::BadFontSize { section, size } => f.write_fmt(format_args!(
+                "{section} font size {size:?} should be a positive number like \"14.0\""
+            )),
+            Problem::WorkspaceOutOfBounds {
+                binding,
+                index,
+                workspaces,
+            } => f.write_fmt(format_args!(
+                "{binding} refers to workspace {index} but there are only {workspaces} workspaces, indices start at 0"
+            )),
+            Problem::DuplicateBinding { first, second } => f.write_fmt(format_args!(
+                "{second} is bound to the same keys as {first} and would never run, remove or rebind one of them"
+            )),
+            Problem::UnknownColor { name } => f.write_fmt(format_args!(
+                "XRESOURCE_COLORS names {name:?} which isn't a color, expected one of {}",
+                COLOR_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl Default for Cfg {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    use xcb_rust_protocol::proto::xproto::{ButtonIndexEnum, ModMask};
+
+    use crate::config::cfg::{Cfg, Problem};
+    use crate::config::key_map::KeyboardMapping;
+    use crate::config::mouse_map::{MouseMapping, MouseTarget};
+    use crate::config::{Action, USER_WORKSPACES};
+
+    // Whether fonts exist depends on the machine running the tests
+    fn without_missing_fonts(problems: Vec<Problem>) -> Vec<Problem> {
+        problems
+            .into_iter()
+            .filter(|problem| !matches!(problem, Problem::MissingFont { .. }))
+            .collect()
+    }
+
+    #[test]
+    fn default_has_no_problems() {
+        let problems = without_missing_fonts(Cfg::DEFAULT.validate());
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let key_mappings = [
+            KeyboardMapping::new(ModMask::FOUR, 1, Action::ToggleWorkspace(0)),
+            KeyboardMapping::new(
+                ModMask::FOUR,
+                2,
+                Action::SendToWorkspace(USER_WORKSPACES.len()),
+            ),
+            KeyboardMapping::new(ModMask::FOUR, 1, Action::Close),
+        ];
+        let mouse_mappings = [MouseMapping {
+            target: MouseTarget::WorkspaceBarComponent(USER_WORKSPACES.len() + 1),
+            action: Action::ToggleWorkspace(0),
+            mods: ModMask::from(0u16),
+            button: ButtonIndexEnum::ONE,
+        }];
+        let cfg = Cfg {
+            key_mappings: Vec::leak(key_mappings.to_vec()),
+            mouse_mappings: Vec::leak(mouse_mappings.into_iter().collect()),
+            ..Cfg::DEFAULT
+        };
+        let problems = without_missing_fonts(cfg.validate());
+        assert_eq!(3, problems.len(), "{problems:?}");
+        let workspaces = USER_WORKSPACES.len();
+        assert_eq!(
+            format!("key-mapping 2 refers to workspace {workspaces} but there are only {workspaces} workspaces, indices start at 0"),
+            problems[0].to_string()
+        );
+        assert!(matches!(
+            &problems[1],
+            Problem::WorkspaceOutOfBounds { binding, index, .. }
+                if binding == "mouse-mapping 1" && *index == workspaces + 1
+        ));
+        assert!(matches!(
+            &problems[2],
+            Problem::DuplicateBinding { first, second }
+                if first == "key-mapping 1" && second == "key-mapping 3"
+        ));
+    }
+}
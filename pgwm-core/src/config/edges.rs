@@ -0,0 +1,66 @@
+use crate::config::Action;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Edge {
+    /// The screen edge that a pointer position is against, if any, top and bottom take precedence
+    /// in the corners
+    #[must_use]
+    pub fn at(x: i16, y: i16, screen_width: u16, screen_height: u16) -> Option<Self> {
+        if y <= 0 {
+            Some(Edge::Top)
+        } else if i32::from(y) >= i32::from(screen_height) - 1 {
+            Some(Edge::Bottom)
+        } else if x <= 0 {
+            Some(Edge::Left)
+        } else if i32::from(x) >= i32::from(screen_width) - 1 {
+            Some(Edge::Right)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[derive(Debug, Copy, Clone)]
+pub struct EdgeAction {
+    pub edge: Edge,
+
+    /// How long the pointer has to stay against the edge before the action runs, 0 runs it on contact
+    pub dwell_ms: u64,
+
+    pub action: Action,
+}
+
+impl EdgeAction {
+    pub(crate) const fn new(edge: Edge, dwell_ms: u64, action: Action) -> Self {
+        Self {
+            edge,
+            dwell_ms,
+            action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::edges::Edge;
+
+    #[test]
+    fn finds_edges() {
+        assert_eq!(None, Edge::at(500, 500, 1920, 1080));
+        assert_eq!(Some(Edge::Top), Edge::at(500, 0, 1920, 1080));
+        assert_eq!(Some(Edge::Bottom), Edge::at(500, 1079, 1920, 1080));
+        assert_eq!(Some(Edge::Left), Edge::at(0, 500, 1920, 1080));
+        assert_eq!(Some(Edge::Right), Edge::at(1919, 500, 1920, 1080));
+        // Corners go to top and bottom
+        assert_eq!(Some(Edge::Top), Edge::at(0, 0, 1920, 1080));
+        assert_eq!(Some(Edge::Bottom), Edge::at(1919, 1079, 1920, 1080));
+    }
+}
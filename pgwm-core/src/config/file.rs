@@ -0,0 +1,843 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use tiny_std::UnixStr;
+use xcb_rust_protocol::proto::xproto::{ButtonIndexEnum, ModMask};
+
+use crate::colors::{parse_hex, COLOR_NAMES, RGBA};
+use crate::config::cfg::Cfg;
+use crate::config::key_map::KeyboardMapping;
+use crate::config::mouse_map::{MouseMapping, MouseTarget};
+use crate::config::toml::{parse, Value};
+use crate::config::workspaces::UserWorkspace;
+use crate::config::{Action, BarShortcut, DefaultDraw, FontCfg, MonitorDirection};
+use crate::error::{Error, Result};
+
+impl Cfg {
+    /// `$XDG_CONFIG_HOME/pgwm/pgwm.toml`, or `$HOME/.config/pgwm/pgwm.toml` if `XDG_CONFIG_HOME` isn't set
+    #[must_use]
+    pub fn file_path() -> Option<String> {
+        if let Ok(config_home) = tiny_std::env::var("XDG_CONFIG_HOME") {
+            return Some(format!("{config_home}/pgwm/pgwm.toml"));
+        }
+        tiny_std::env::var("HOME")
+            .ok()
+            .map(|home| format!("{home}/.config/pgwm/pgwm.toml"))
+    }
+
+    /// The configuration in the config file, `None` if there is no file
+    pub fn read_file() -> Result<Option<Cfg>> {
+        let Some(path) = Self::file_path() else {
+            return Ok(None);
+        };
+        let terminated = format!("{path}\0");
+        let unix_path = UnixStr::try_from_str(&terminated)
+            .map_err(|_| invalid(format!("bad config path {path}")))?;
+        if tiny_std::fs::metadata(unix_path).is_err() {
+            return Ok(None);
+        }
+        let content = tiny_std::fs::read(unix_path)?;
+        let content = core::str::from_utf8(&content)
+            .map_err(|_| invalid(format!("{path} is not valid utf8")))?;
+        Self::from_toml(content).map(Some)
+    }
+
+    /// Parse a config file, anything it doesn't set is taken from [`Cfg::DEFAULT`].
+    /// Listing any `[[workspace]]`, `[[key-mapping]]`, `[[mouse-mapping]]`, `[[bar-shortcut]]`,
+    /// or `[[status-check]]` replaces all of the compiled in ones of that kind.
+    pub fn from_toml(src: &str) -> Result<Cfg> {
+        let root = parse(src).map_err(|e| invalid(format!("{e}")))?;
+        let mut cfg = Cfg::DEFAULT;
+        if let Value::Table(entries) = &root {
+            for (key, value) in entries {
+                match key.as_str() {
+                    "window-padding" => cfg.window_padding = int(value, key)?,
+                    "window-border-width" => cfg.window_border_width = int(value, key)?,
+                    "pad-while-tabbed" => cfg.pad_while_tabbed = boolean(value, key)?,
+                    "smart-gaps" => cfg.smart_gaps = boolean(value, key)?,
+                    "colors" => parse_colors(value, &mut cfg.colors)?,
+                    "fonts" => parse_fonts(value, &mut cfg)?,
+                    "workspace" => {
+                        cfg.workspaces = leak_slice(each(value, key, parse_workspace)?);
+                    }
+                    "key-mapping" => {
+                        cfg.key_mappings = leak_slice(each(value, key, parse_key_mapping)?);
+                    }
+                    "mouse-mapping" => {
+                        cfg.mouse_mappings = leak_slice(each(value, key, parse_mouse_mapping)?);
+                    }
+                    "bar-shortcut" => {
+                        cfg.bar_shortcuts = leak_slice(each(value, key, parse_bar_shortcut)?);
+                    }
+                    #[cfg(feature = "status-bar")]
+                    "status-check" => {
+                        let checks = each(value, key, parse_status_check)?;
+                        if checks.len() > crate::config::_STATUS_CHECK_LIMIT {
+                            return Err(invalid(format!(
+                                "at most {} status checks can be configured",
+                                crate::config::_STATUS_CHECK_LIMIT
+                            )));
+                        }
+                        check_external_sources(&checks)?;
+                        cfg.status_checks = leak_slice(checks);
+                    }
+                    _ => return Err(invalid(format!("unknown key {key}"))),
+                }
+            }
+        }
+        Ok(cfg)
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::ConfigFile(message)
+}
+
+fn leak_str(s: &str) -> &'static str {
+    Box::leak(String::from(s).into_boxed_str())
+}
+
+fn leak_unix_str(s: &str) -> Result<&'static UnixStr> {
+    let terminated = leak_str(&format!("{s}\0"));
+    UnixStr::try_from_str(terminated).map_err(|_| invalid(format!("{s} contains a null byte")))
+}
+
+fn leak_slice<T>(values: Vec<T>) -> &'static [T] {
+    Box::leak(values.into_boxed_slice())
+}
+
+/// Run `parse_one` on every table in an array of tables, errors name the table's position
+fn each<T>(value: &Value, key: &str, parse_one: fn(&Value) -> Result<T>) -> Result<Vec<T>> {
+    array(value, key)?
+        .iter()
+        .enumerate()
+        .map(|(i, table)| {
+            parse_one(table).map_err(|e| match e {
+                Error::ConfigFile(message) => invalid(format!("{key} {}: {message}", i + 1)),
+                e => e,
+            })
+        })
+        .collect()
+}
+
+fn required<'a>(table: &'a Value, key: &str) -> Result<&'a Value> {
+    table
+        .get(key)
+        .ok_or_else(|| invalid(format!("missing {key}")))
+}
+
+fn wrong_type(value: &Value, key: &str, expected: &str) -> Error {
+    invalid(format!(
+        "{key} should be {expected}, found {}",
+        value.type_name()
+    ))
+}
+
+fn int<T: TryFrom<i64>>(value: &Value, key: &str) -> Result<T> {
+    match value {
+        Value::Integer(v) => {
+            T::try_from(*v).map_err(|_| invalid(format!("{key} is out of range, found {v}")))
+        }
+        _ => Err(wrong_type(value, key, "an integer")),
+    }
+}
+
+fn boolean(value: &Value, key: &str) -> Result<bool> {
+    match value {
+        Value::Boolean(v) => Ok(*v),
+        _ => Err(wrong_type(value, key, "a boolean")),
+    }
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> Result<&'a str> {
+    match value {
+        Value::String(v) => Ok(v.as_str()),
+        _ => Err(wrong_type(value, key, "a string")),
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a [Value]> {
+    match value {
+        Value::Array(v) => Ok(v.as_slice()),
+        _ => Err(wrong_type(value, key, "an array")),
+    }
+}
+
+fn table_entries<'a>(value: &'a Value, key: &str) -> Result<&'a [(String, Value)]> {
+    match value {
+        Value::Table(v) => Ok(v.as_slice()),
+        _ => Err(wrong_type(value, key, "a table")),
+    }
+}
+
+/// `"#rrggbb"`, `"#rrggbbaa"`, or `[r, g, b, a]`
+fn color(value: &Value, key: &str) -> Result<RGBA> {
+    match value {
+        Value::String(hex) => {
+            parse_hex(hex).ok_or_else(|| invalid(format!("{key} is not a color, found {hex}")))
+        }
+        Value::Array(channels) if channels.len() == 4 => Ok((
+            int(&channels[0], key)?,
+            int(&channels[1], key)?,
+            int(&channels[2], key)?,
+            int(&channels[3], key)?,
+        )),
+        _ => Err(wrong_type(value, key, "a color")),
+    }
+}
+
+fn parse_colors(value: &Value, colors: &mut [RGBA]) -> Result<()> {
+    for (key, value) in table_entries(value, "colors")? {
+        let Some(ind) = COLOR_NAMES.iter().position(|name| name == key) else {
+            return Err(invalid(format!("unknown color {key}")));
+        };
+        colors[ind] = color(value, key)?;
+    }
+    Ok(())
+}
+
+fn parse_fonts(value: &Value, cfg: &mut Cfg) -> Result<()> {
+    for (key, value) in table_entries(value, "fonts")? {
+        let fonts = leak_slice(
+            array(value, key)?
+                .iter()
+                .map(|font| parse_font(font, key))
+                .collect::<Result<Vec<_>>>()?,
+        );
+        match key.as_str() {
+            "workspace-section" => cfg.fonts.workspace_section = fonts,
+            "window-name-display-section" => cfg.fonts.window_name_display_section = fonts,
+            #[cfg(feature = "status-bar")]
+            "status-section" => cfg.fonts.status_section = fonts,
+            "tab-bar-section" => cfg.fonts.tab_bar_section = fonts,
+            "shortcut-section" => cfg.fonts.shortcut_section = fonts,
+            _ => return Err(invalid(format!("unknown font section {key}"))),
+        }
+    }
+    Ok(())
+}
+
+/// `{ path = "/usr/share/fonts/...", size = 14.0 }`
+fn parse_font(value: &Value, key: &str) -> Result<FontCfg<'static>> {
+    let path = leak_unix_str(string(required(value, "path")?, "path")?)?;
+    let size = match required(value, "size")? {
+        Value::Float(size) => leak_str(&format!("{size}")),
+        Value::Integer(size) => leak_str(&format!("{size}")),
+        Value::String(size) if size.parse::<f32>().is_ok() => leak_str(size),
+        other => return Err(wrong_type(other, key, "a font size")),
+    };
+    Ok(FontCfg::new(path, size))
+}
+
+fn parse_workspace(value: &Value) -> Result<UserWorkspace> {
+    let name = leak_str(string(required(value, "name")?, "name")?);
+    let mapped_class_names = match value.get("mapped-class-names") {
+        Some(names) => leak_slice(
+            array(names, "mapped-class-names")?
+                .iter()
+                .map(|name| string(name, "mapped-class-names").map(leak_str))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        None => &[],
+    };
+    let default_draw = match value.get("default-draw") {
+        Some(draw) => match string(draw, "default-draw")? {
+            "LeftLeader" => DefaultDraw::LeftLeader,
+            "CenterLeader" => DefaultDraw::CenterLeader,
+            "Tabbed" => DefaultDraw::Tabbed,
+            other => return Err(invalid(format!("unknown default-draw {other}"))),
+        },
+        None => DefaultDraw::default(),
+    };
+    let mut workspace = UserWorkspace::new(name, mapped_class_names, default_draw);
+    if let Some(background) = value.get("background") {
+        workspace = workspace.with_background(color(background, "background")?);
+    }
+    if let Some(path) = value.get("background-image") {
+        workspace =
+            workspace.with_background_image(leak_unix_str(string(path, "background-image")?)?);
+    }
+    if let Some(padding) = value.get("window-padding") {
+        workspace = workspace.with_padding(int(padding, "window-padding")?);
+    }
+    if let Some(border_width) = value.get("window-border-width") {
+        workspace = workspace.with_border_width(int(border_width, "window-border-width")?);
+    }
+    Ok(workspace)
+}
+
+/// `["M4", "Shift"]`, an empty array for no modifiers
+fn parse_mods(value: Option<&Value>) -> Result<ModMask> {
+    let mut mask = 0u16;
+    let Some(value) = value else {
+        return Ok(ModMask(mask));
+    };
+    for name in array(value, "mods")? {
+        mask |= match string(name, "mods")? {
+            "Shift" => ModMask::SHIFT.0,
+            "Lock" => ModMask::LOCK.0,
+            "Control" => ModMask::CONTROL.0,
+            "M1" => ModMask::ONE.0,
+            "M2" => ModMask::TWO.0,
+            "M3" => ModMask::THREE.0,
+            "M4" => ModMask::FOUR.0,
+            "M5" => ModMask::FIVE.0,
+            other => return Err(invalid(format!("unknown modifier {other}"))),
+        };
+    }
+    Ok(ModMask(mask))
+}
+
+fn parse_key_mapping(value: &Value) -> Result<KeyboardMapping> {
+    let mapping = KeyboardMapping::new(
+        parse_mods(value.get("mods"))?,
+        int(required(value, "key")?, "key")?,
+        parse_action(required(value, "on_click")?)?,
+    );
+    Ok(match value.get("repeat") {
+        Some(repeat) if !boolean(repeat, "repeat")? => mapping.without_repeat(),
+        _ => mapping,
+    })
+}
+
+fn parse_mouse_mapping(value: &Value) -> Result<MouseMapping> {
+    let button = match string(required(value, "button")?, "button")? {
+        "M1" => ButtonIndexEnum::ONE,
+        "M2" => ButtonIndexEnum::TWO,
+        "M3" => ButtonIndexEnum::THREE,
+        "M4" => ButtonIndexEnum::FOUR,
+        "M5" => ButtonIndexEnum::FIVE,
+        other => return Err(invalid(format!("unknown button {other}"))),
+    };
+    Ok(MouseMapping {
+        target: parse_mouse_target(required(value, "target")?)?,
+        mods: parse_mods(value.get("mods"))?,
+        button,
+        action: parse_action(required(value, "on_click")?)?,
+    })
+}
+
+/// `{ glyph = "\u{f304}", on_click = "Quit", hover-text = "Quit" }`, the hover text can be left out
+fn parse_bar_shortcut(value: &Value) -> Result<BarShortcut> {
+    let hover_text = match value.get("hover-text") {
+        Some(text) => Some(leak_str(string(text, "hover-text")?)),
+        None => None,
+    };
+    Ok(BarShortcut::new(
+        leak_str(string(required(value, "glyph")?, "glyph")?),
+        parse_action(required(value, "on_click")?)?,
+        hover_text,
+    ))
+}
+
+/// A kind without arguments like `"ClientWindow"`, or `{ kind = "StatusComponent", args = 0 }`
+fn parse_mouse_target(value: &Value) -> Result<MouseTarget> {
+    let (kind, args) = kind_and_args(value, "kind", "target")?;
+    let index = || {
+        int::<usize>(
+            args.ok_or_else(|| invalid(format!("{kind} needs an index")))?,
+            kind,
+        )
+    };
+    Ok(match kind {
+        "ClientWindow" => MouseTarget::ClientWindow,
+        "WorkspaceBar" => MouseTarget::WorkspaceBar,
+        "WindowTitle" => MouseTarget::WindowTitle,
+        "UrgentSection" => MouseTarget::UrgentSection,
+        "Tab" => MouseTarget::Tab,
+        "WorkspaceBarComponent" => MouseTarget::WorkspaceBarComponent(index()?),
+        "ShortcutComponent" => MouseTarget::ShortcutComponent(index()?),
+        "StatusComponent" => MouseTarget::StatusComponent(index()?),
+        other => return Err(invalid(format!("unknown mouse target {other}"))),
+    })
+}
+
+/// Either a bare name or a table with the name under `name_key` and optional `args`
+fn kind_and_args<'a>(
+    value: &'a Value,
+    name_key: &str,
+    key: &str,
+) -> Result<(&'a str, Option<&'a Value>)> {
+    match value {
+        Value::String(name) => Ok((name.as_str(), None)),
+        Value::Table(_) => Ok((
+            string(required(value, name_key)?, name_key)?,
+            value.get("args"),
+        )),
+        _ => Err(wrong_type(value, key, "a string or a table")),
+    }
+}
+
+/// A bare action name like `"Quit"`, or `{ action = "Spawn", args = ["dmenu_run", ["-i"]] }`
+fn parse_action(value: &Value) -> Result<Action> {
+    let (name, args) = kind_and_args(value, "action", "on_click")?;
+    let arg = || args.ok_or_else(|| invalid(format!("{name} needs args")));
+    Ok(match name {
+        "Quit" => Action::Quit,
+        "Restart" => Action::Restart,
+        "Spawn" => {
+            let (cmd, args) = parse_spawn(arg()?)?;
+            Action::Spawn(cmd, args)
+        }
+        "SpawnInFocusedCwd" => {
+            let (cmd, args) = parse_spawn(arg()?)?;
+            Action::SpawnInFocusedCwd(cmd, args)
+        }
+        "Close" => Action::Close,
+        "CloseGroup" => Action::CloseGroup,
+        "ToggleWorkspace" => Action::ToggleWorkspace(int(arg()?, name)?),
+        "ToggleLastWorkspace" => Action::ToggleLastWorkspace,
+        "NextWorkspace" => Action::NextWorkspace,
+        "PrevWorkspace" => Action::PrevWorkspace,
+        "SendToWorkspace" => Action::SendToWorkspace(int(arg()?, name)?),
+        "MarkWindow" => Action::MarkWindow(leak_str(string(arg()?, name)?)),
+        "FocusMark" => Action::FocusMark(leak_str(string(arg()?, name)?)),
+        "SendMarkedToWorkspace" => Action::SendMarkedToWorkspace(int(arg()?, name)?),
+        "WindowPicker" => Action::WindowPicker,
+        "WindowHints" => Action::WindowHints,
+        "ShowKeybindHelp" => Action::ShowKeybindHelp,
+        "FocusUrgent" => Action::FocusUrgent,
+        "SendToFront" => Action::SendToFront,
+        "UnFloat" => Action::UnFloat,
+        "ToggleFullscreen" => Action::ToggleFullscreen,
+        "ToggleFakeFullscreen" => Action::ToggleFakeFullscreen,
+        "CycleStatusFormat" => Action::CycleStatusFormat(int(arg()?, name)?),
+        "TogglePinToMonitor" => Action::TogglePinToMonitor,
+        "ToggleMaximize" => Action::ToggleMaximize,
+        "CycleDrawMode" => Action::CycleDrawMode,
+        "MoveWindow" => Action::MoveWindow,
+        "NextTilingMode" => Action::NextTilingMode,
+        "ResizeWindow" => Action::ResizeWindow(int(arg()?, name)?),
+        "ResizePadding" => Action::ResizePadding(int(arg()?, name)?),
+        "ResizeBorders" => Action::ResizeBorders(int(arg()?, name)?),
+        "ResetToDefaultSizeModifiers" => Action::ResetToDefaultSizeModifiers,
+        "FocusNextWindow" => Action::FocusNextWindow,
+        "FocusPreviousWindow" => Action::FocusPreviousWindow,
+        "FocusNextInGroup" => Action::FocusNextInGroup,
+        "FocusNextMonitor" => Action::FocusNextMonitor,
+        "FocusPreviousMonitor" => Action::FocusPreviousMonitor,
+        "MoveWorkspaceToMonitor" => match string(arg()?, name)? {
+            "Next" => Action::MoveWorkspaceToMonitor(MonitorDirection::Next),
+            "Previous" => Action::MoveWorkspaceToMonitor(MonitorDirection::Previous),
+            other => return Err(invalid(format!("unknown monitor direction {other}"))),
+        },
+        "ToggleBar" => Action::ToggleBar,
+        "RevealBar" => Action::RevealBar,
+        "ToggleAutohideBar" => Action::ToggleAutohideBar,
+        #[cfg(feature = "idle")]
+        "InhibitIdle" => Action::InhibitIdle,
+        "ToggleShowDesktop" => Action::ToggleShowDesktop,
+        "NextKeyboardLayout" => Action::NextKeyboardLayout,
+        other => return Err(invalid(format!("unknown action {other}"))),
+    })
+}
+
+/// `["program", ["arg", ...]]`, the arguments can be left out
+fn parse_spawn(value: &Value) -> Result<(&'static UnixStr, &'static [&'static UnixStr])> {
+    let parts = array(value, "args")?;
+    let Some(cmd) = parts.first() else {
+        return Err(invalid(String::from("Spawn needs a program")));
+    };
+    let cmd = leak_unix_str(string(cmd, "args")?)?;
+    let args = match parts.get(1) {
+        Some(args) => leak_slice(
+            array(args, "args")?
+                .iter()
+                .map(|arg| leak_unix_str(string(arg, "args")?))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        None => &[],
+    };
+    Ok((cmd, args))
+}
+
+/// Checks are told apart by their kind, and external checks by their source,
+/// so each source can only be read by one check
+#[cfg(feature = "status-bar")]
+fn check_external_sources(checks: &[crate::status::checker::Check]) -> Result<()> {
+    use crate::status::checker::{CheckType, ExternalSource};
+    let (mut fifos, mut root_names) = (0, 0);
+    for check in checks {
+        if let CheckType::External(fmt) = &check.check_type {
+            match fmt.source() {
+                ExternalSource::Fifo(_) => fifos += 1,
+                ExternalSource::RootName => root_names += 1,
+            }
+        }
+    }
+    if fifos > 1 || root_names > 1 {
+        return Err(invalid(String::from(
+            "at most one External status check can read from a fifo, and one from the root window name",
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "status-bar")]
+fn parse_status_check(value: &Value) -> Result<crate::status::checker::Check> {
+    use crate::status::checker::{
+        BatChecks, BatFormat, Check, CheckType, CpuFormat, CpuMode, DateFormat, ExternalFormat,
+        ExternalSource, KeyboardFormat, MediaFormat, MemFormat, MemMode, NetFormat, NetScale,
+        NetUnit, PingFormat, PrivacyFormat,
+    };
+    let icon = |key: &str| -> Result<&'static str> {
+        value
+            .get(key)
+            .map_or(Ok(""), |icon| string(icon, key))
+            .map(leak_str)
+    };
+    let decimals = value
+        .get("decimals")
+        .map_or(Ok(1), |decimals| int(decimals, "decimals"))?;
+    let check_type = match string(required(value, "kind")?, "kind")? {
+        "Cpu" => {
+            let mode = match value
+                .get("mode")
+                .map_or(Ok("total"), |m| string(m, "mode"))?
+            {
+                "total" => CpuMode::Total,
+                "per-core" => CpuMode::PerCore,
+                "load-avg" => CpuMode::LoadAvg,
+                other => return Err(invalid(format!("unknown cpu mode {other}"))),
+            };
+            CheckType::Cpu(CpuFormat::new(icon("icon")?, decimals).with_mode(mode))
+        }
+        "Mem" => {
+            let mode = match value
+                .get("mode")
+                .map_or(Ok("available"), |m| string(m, "mode"))?
+            {
+                "available" => MemMode::Available,
+                "used" => MemMode::Used,
+                other => return Err(invalid(format!("unknown mem mode {other}"))),
+            };
+            let fmt = MemFormat::new(icon("icon")?, decimals).with_mode(mode);
+            CheckType::Mem(if value.get("swap-icon").is_some() {
+                fmt.with_swap(icon("swap-icon")?)
+            } else {
+                fmt
+            })
+        }
+        "Net" => {
+            let interfaces = value
+                .get("interfaces")
+                .map_or(Ok(&[][..]), |interfaces| array(interfaces, "interfaces"))?
+                .iter()
+                .map(|interface| string(interface, "interfaces").map(leak_str))
+                .collect::<Result<Vec<_>>>()?;
+            let unit = match value
+                .get("unit")
+                .map_or(Ok("bytes"), |u| string(u, "unit"))?
+            {
+                "bytes" => NetUnit::Bytes,
+                "bits" => NetUnit::Bits,
+                other => return Err(invalid(format!("unknown net unit {other}"))),
+            };
+            let scale = match value
+                .get("scale")
+                .map_or(Ok("auto"), |s| string(s, "scale"))?
+            {
+                "auto" => NetScale::Auto,
+                "K" => NetScale::Kilo,
+                "M" => NetScale::Mega,
+                "G" => NetScale::Giga,
+                other => return Err(invalid(format!("unknown net scale {other}"))),
+            };
+            CheckType::Net(
+                NetFormat::new(icon("icon-up")?, icon("icon-down")?, decimals)
+                    .with_interfaces(leak_slice(interfaces))
+                    .with_unit(unit)
+                    .with_scale(scale),
+            )
+        }
+        "Date" => {
+            let date = DateFormat::new(icon("icon")?, parse_clock(value)?);
+            // Tables with their own format and utc-offset, cycled through by clicking the date
+            let alternatives = value
+                .get("alternatives")
+                .map_or(Ok(&[][..]), |alternatives| {
+                    array(alternatives, "alternatives")
+                })?
+                .iter()
+                .map(parse_clock)
+                .collect::<Result<Vec<_>>>()?;
+            CheckType::Date(date.with_alternatives(leak_slice(alternatives)))
+        }
+        "Keyboard" => CheckType::Keyboard(KeyboardFormat::new(
+            icon("icon")?,
+            value
+                .get("max-chars")
+                .map_or(Ok(8), |max| int(max, "max-chars"))?,
+        )),
+        "Privacy" => {
+            CheckType::Privacy(PrivacyFormat::new(icon("mic-icon")?, icon("camera-icon")?))
+        }
+        "Media" => {
+            let fifo = leak_unix_str(string(required(value, "fifo")?, "fifo")?)?;
+            let max_chars = value
+                .get("max-chars")
+                .map_or(Ok(32), |max| int(max, "max-chars"))?;
+            let media = MediaFormat::new(icon("icon")?, fifo, max_chars);
+            CheckType::Media(match value.get("controller") {
+                Some(controller) => {
+                    media.with_controller(leak_unix_str(string(controller, "controller")?)?)
+                }
+                None => media,
+            })
+        }
+        "Ping" => {
+            let target = string(required(value, "target")?, "target")?;
+            let target = target
+                .parse::<core::net::Ipv4Addr>()
+                .map_err(|_| invalid(format!("ping target {target} isn't an IPv4 address")))?;
+            CheckType::Ping(PingFormat::new(icon("icon")?, target.octets()))
+        }
+        // Read from `fifo` if there is one, otherwise from the root window name
+        "External" => {
+            let source = match value.get("fifo") {
+                Some(fifo) => ExternalSource::Fifo(leak_unix_str(string(fifo, "fifo")?)?),
+                None => ExternalSource::RootName,
+            };
+            let max_chars = value
+                .get("max-chars")
+                .map_or(Ok(48), |max| int(max, "max-chars"))?;
+            CheckType::External(ExternalFormat::new(source, max_chars))
+        }
+        "Battery" => {
+            let mut levels = array(required(value, "levels")?, "levels")?
+                .iter()
+                .map(|level| {
+                    Ok(BatFormat::new(
+                        int(required(level, "above")?, "above")?,
+                        leak_str(string(required(level, "icon")?, "icon")?),
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // Checked in descending order, the first level the capacity is above is shown
+            levels.sort_by(|a, b| b.above.cmp(&a.above));
+            if levels.windows(2).any(|pair| pair[0].above == pair[1].above) {
+                return Err(invalid(String::from(
+                    "two battery levels have the same above",
+                )));
+            }
+            CheckType::Battery(BatChecks::new(leak_slice(levels)))
+        }
+        other => return Err(invalid(format!("unknown status check kind {other}"))),
+    };
+    Ok(Check {
+        interval: value
+            .get("interval")
+            .map_or(Ok(1000), |interval| int(interval, "interval"))?,
+        check_type,
+    })
+}
+
+/// `format = "{year}-{month}-{day} {hour}:{minute}"` and the utc offset in hours as `utc-offset`
+#[cfg(feature = "status-bar")]
+fn parse_clock(value: &Value) -> Result<crate::status::time::ClockFormatter> {
+    use crate::status::time::{ClockFormatter, Format, FormatChunk, Token};
+    let format = value
+        .get("format")
+        .map_or(Ok("{year}-{month}-{day} {hour}:{minute}:{second}"), |f| {
+            string(f, "format")
+        })?;
+    let mut chunks = Vec::new();
+    let mut rest = format;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('{') else {
+            chunks.push(FormatChunk::Value(leak_str(rest)));
+            break;
+        };
+        if start > 0 {
+            chunks.push(FormatChunk::Value(leak_str(&rest[..start])));
+        }
+        let Some(end) = rest[start..].find('}') else {
+            return Err(invalid(format!("unclosed token in format {format}")));
+        };
+        let token = match &rest[start + 1..start + end] {
+            "year" => Token::Year,
+            "month" => Token::Month,
+            "week" => Token::Week,
+            "weekday" => Token::WeekDay,
+            "day" => Token::Day,
+            "hour" => Token::Hour,
+            "minute" => Token::Minute,
+            "second" => Token::Second,
+            other => return Err(invalid(format!("unknown format token {other}"))),
+        };
+        chunks.push(FormatChunk::Token(token));
+        rest = &rest[start + end + 1..];
+    }
+    let hours = value
+        .get("utc-offset")
+        .map_or(Ok(0), |offset| int(offset, "utc-offset"))?;
+    let offset = time::UtcOffset::from_hms(hours, 0, 0)
+        .map_err(|_| invalid(format!("utc-offset {hours} is out of range")))?;
+    Ok(ClockFormatter::new(Format::new(leak_slice(chunks)), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use xcb_rust_protocol::proto::xproto::{ButtonIndexEnum, ModMask};
+
+    use crate::config::cfg::Cfg;
+    use crate::config::mouse_map::MouseTarget;
+    use crate::config::{
+        Action, DefaultDraw, BAR_SHORTCUTS, COLORS, KEYBOARD_MAPPINGS, WINDOW_PADDING,
+    };
+
+    #[test]
+    fn overrides_what_the_file_sets() {
+        let cfg = Cfg::from_toml(
+            r##"
+window-border-width = 5
+smart-gaps = true
+
+[colors]
+window-border = "#ff000080"
+shortcut-text = [1, 2, 3, 4]
+
+[fonts]
+tab-bar-section = [{ path = "/fonts/mono.ttf", size = 12.5 }]
+
+[[workspace]]
+name = "web"
+mapped-class-names = ["firefox"]
+default-draw = "Tabbed"
+
+[[workspace]]
+name = "term"
+background = "#102030"
+background-image = "/walls/term.ppm"
+window-padding = 0
+window-border-width = 1
+
+[[key-mapping]]
+mods = ["M4", "Shift"]
+key = 0x0064
+on_click = { action = "Spawn", args = ["dmenu_run", ["-i", "-p", "Run: "]] }
+
+[[key-mapping]]
+key = 0x0071
+on_click = "Quit"
+repeat = false
+
+[[mouse-mapping]]
+button = "M1"
+target = { kind = "ShortcutComponent", args = 1 }
+on_click = { action = "ToggleWorkspace", args = 1 }
+
+[[bar-shortcut]]
+glyph = "T"
+on_click = { action = "Spawn", args = ["xterm"] }
+hover-text = "Terminal"
+
+[[bar-shortcut]]
+glyph = "Q"
+on_click = "Quit"
+"##,
+        )
+        .unwrap();
+        assert_eq!(5, cfg.window_border_width);
+        assert_eq!(WINDOW_PADDING, cfg.window_padding);
+        assert!(cfg.smart_gaps);
+        assert_eq!((255, 0, 0, 128), cfg.colors[0]);
+        assert_eq!((1, 2, 3, 4), cfg.colors[15]);
+        assert_eq!(COLORS[1], cfg.colors[1]);
+        assert_eq!("12.5", cfg.fonts.tab_bar_section[0].size);
+        assert_eq!(
+            "/fonts/mono.ttf",
+            cfg.fonts.tab_bar_section[0]
+                .path
+                .as_str()
+                .unwrap()
+                .trim_end_matches('\0')
+        );
+        assert_eq!(2, cfg.workspaces.len());
+        assert_eq!(&["firefox"], cfg.workspaces[0].mapped_class_names);
+        assert_eq!(DefaultDraw::Tabbed, cfg.workspaces[0].default_draw);
+        assert_eq!(Some((16, 32, 48, 0)), cfg.workspaces[1].background);
+        assert_eq!(
+            "/walls/term.ppm\0",
+            cfg.workspaces[1]
+                .background_image
+                .unwrap()
+                .as_str()
+                .unwrap()
+        );
+        assert!(cfg.workspaces[0].background_image.is_none());
+        assert_eq!(None, cfg.workspaces[0].padding);
+        assert_eq!(Some(0), cfg.workspaces[1].padding);
+        assert_eq!(Some(1), cfg.workspaces[1].border_width);
+        assert_eq!(2, cfg.key_mappings.len());
+        let spawn = cfg.key_mappings[0];
+        assert_eq!(ModMask::FOUR.0 | ModMask::SHIFT.0, spawn.modmask.0);
+        assert_eq!(0x64, spawn.keysym);
+        let Action::Spawn(cmd, args) = spawn.action else {
+            panic!("Expected a spawn, got {:?}", spawn.action);
+        };
+        assert_eq!("dmenu_run\0", cmd.as_str().unwrap());
+        assert_eq!(3, args.len());
+        assert!(spawn.repeat);
+        assert_eq!(Action::Quit, cfg.key_mappings[1].action);
+        assert_eq!(0, cfg.key_mappings[1].modmask.0);
+        assert!(!cfg.key_mappings[1].repeat);
+        let mouse = &cfg.mouse_mappings[0];
+        assert_eq!(MouseTarget::ShortcutComponent(1), mouse.target);
+        assert_eq!(ButtonIndexEnum::ONE, mouse.button);
+        assert_eq!(Action::ToggleWorkspace(1), mouse.action);
+        assert_eq!(2, cfg.bar_shortcuts.len());
+        assert_eq!("T", cfg.bar_shortcuts[0].glyph);
+        let Action::Spawn(cmd, args) = cfg.bar_shortcuts[0].action else {
+            panic!("Expected a spawn, got {:?}", cfg.bar_shortcuts[0].action);
+        };
+        assert_eq!("xterm\0", cmd.as_str().unwrap());
+        assert!(args.is_empty());
+        assert_eq!(Some("Terminal"), cfg.bar_shortcuts[0].hover_text);
+        assert_eq!(Action::Quit, cfg.bar_shortcuts[1].action);
+        assert_eq!(None, cfg.bar_shortcuts[1].hover_text);
+    }
+
+    #[test]
+    fn empty_file_is_the_default() {
+        let cfg = Cfg::from_toml("# Nothing here\n").unwrap();
+        assert_eq!(KEYBOARD_MAPPINGS.len(), cfg.key_mappings.len());
+        assert_eq!(BAR_SHORTCUTS.len(), cfg.bar_shortcuts.len());
+        assert_eq!(COLORS, cfg.colors);
+    }
+
+    #[test]
+    #[cfg(feature = "status-bar")]
+    fn rejects_a_second_external_fifo() {
+        let external = |fifo: &str| {
+            alloc::format!("[[status-check]]\nkind = \"External\"\nfifo = \"{fifo}\"\n")
+        };
+        let one = external("/tmp/first");
+        assert!(Cfg::from_toml(&one).is_ok());
+        // Reading the root window name next to a fifo is fine
+        let with_root_name = alloc::format!("{one}[[status-check]]\nkind = \"External\"\n");
+        assert!(Cfg::from_toml(&with_root_name).is_ok());
+        let two = alloc::format!("{one}{}", external("/tmp/second"));
+        let err = Cfg::from_toml(&two).unwrap_err().to_string();
+        assert!(err.contains("at most one External status check"), "{err}");
+    }
+
+    #[test]
+    fn errors_name_the_problem() {
+        let err = Cfg::from_toml("[[key-mapping]]\nkey = 1\non_click = \"Explode\"")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("key-mapping 1: unknown action Explode"),
+            "{err}"
+        );
+        let err = Cfg::from_toml("window-padding = \"wide\"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("window-padding should be an integer"), "{err}");
+        assert!(Cfg::from_toml("unknown = 1").is_err());
+        assert!(Cfg::from_toml("[colors]\nwindow-border = \"#12\"").is_err());
+    }
+}
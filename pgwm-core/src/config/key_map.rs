@@ -1,3 +1,6 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 use xcb_rust_protocol::proto::xproto::ModMask;
 
 use crate::config::Action;
@@ -7,6 +10,8 @@ pub struct KeyboardMapping {
     pub modmask: ModMask,
     pub keysym: u32,
     pub action: Action,
+    /// Whether the action runs again for each auto-repeated press while the key is held
+    pub repeat: bool,
 }
 
 impl KeyboardMapping {
@@ -16,8 +21,16 @@ impl KeyboardMapping {
             modmask,
             keysym,
             action,
+            repeat: true,
         }
     }
+
+    /// Only run the action once while the key is held, ignoring auto-repeated presses
+    #[must_use]
+    pub const fn without_repeat(mut self) -> Self {
+        self.repeat = false;
+        self
+    }
 }
 
 #[derive(Debug, Hash, Eq, PartialEq)]
@@ -26,9 +39,331 @@ pub struct KeyBoardMappingKey {
     pub mods: u16,
 }
 
+/// XKB reports the active keyboard group in these bits of a key event's state
+const XKB_GROUP_MASK: u16 = 0x6000;
+
+/// Lock modifiers, `CapsLock` and `NumLock` (usually `Mod2`), bindings should trigger regardless of them
+pub const LOCK_MODS: u16 = ModMask::LOCK.0 | ModMask::TWO.0;
+
+/// Every combination of lock modifiers, bindings are grabbed once with each so that they
+/// are delivered regardless of what locks are active
+pub const LOCK_MOD_COMBINATIONS: [u16; 4] = [
+    0,
+    ModMask::LOCK.0,
+    ModMask::TWO.0,
+    ModMask::LOCK.0 | ModMask::TWO.0,
+];
+
 impl KeyBoardMappingKey {
     #[must_use]
     pub fn new(code: u8, mods: u16) -> Self {
         KeyBoardMappingKey { code, mods }
     }
+
+    /// Create a key from a key event, ignoring which keyboard group and lock modifiers are active
+    #[must_use]
+    pub fn from_event(code: u8, state: u16) -> Self {
+        KeyBoardMappingKey {
+            code,
+            mods: state & !(XKB_GROUP_MASK | LOCK_MODS),
+        }
+    }
+}
+
+/// The server's keycode to keysym mapping, as returned by `GetKeyboardMapping`
+#[derive(Debug, Clone)]
+pub struct KeysymTable {
+    keysyms: Vec<u32>,
+    keysyms_per_keycode: u8,
+    min_keycode: u8,
+}
+
+impl KeysymTable {
+    #[must_use]
+    pub fn new(keysyms: Vec<u32>, keysyms_per_keycode: u8, min_keycode: u8) -> Self {
+        Self {
+            keysyms,
+            keysyms_per_keycode,
+            min_keycode,
+        }
+    }
+
+    /// Get the keysym for a keycode, the shifted column falls back to the unshifted one if empty
+    #[must_use]
+    pub fn keysym(&self, code: u8, shifted: bool) -> Option<u32> {
+        let base = code.checked_sub(self.min_keycode)? as usize * self.keysyms_per_keycode as usize;
+        let unshifted = self.keysyms.get(base).copied().filter(|sym| *sym != 0);
+        if shifted && self.keysyms_per_keycode > 1 {
+            self.keysyms
+                .get(base + 1)
+                .copied()
+                .filter(|sym| *sym != 0)
+                .or(unshifted)
+        } else {
+            unshifted
+        }
+    }
+
+    /// Find the keycode producing a keysym and whether it's on a shifted level.
+    /// Keysyms are laid out per keycode as group 1 unshifted, group 1 shifted, group 2 unshifted,
+    /// group 2 shifted, and so on. Columns are searched in that order across all keycodes, so that
+    /// a keysym on a primary group is preferred, but one only on a secondary group or level is still found.
+    #[must_use]
+    pub fn find_keycode(&self, keysym: u32) -> Option<(u8, bool)> {
+        let per = self.keysyms_per_keycode as usize;
+        if per == 0 || keysym == 0 {
+            return None;
+        }
+        let num_codes = self.keysyms.len() / per;
+        (0..per).find_map(|col| {
+            (0..num_codes)
+                .find(|code_ind| self.keysyms[code_ind * per + col] == keysym)
+                .map(|code_ind| ((code_ind + self.min_keycode as usize) as u8, col % 2 == 1))
+        })
+    }
+
+    /// Resolve a configured mapping to the key to grab, shift is added to the mapping's modifiers
+    /// if its keysym is only found on a shifted level
+    #[must_use]
+    pub fn resolve(&self, mapping: &KeyboardMapping) -> Option<KeyBoardMappingKey> {
+        self.find_keycode(mapping.keysym).map(|(code, shifted)| {
+            let mods = if shifted {
+                mapping.modmask.0 | ModMask::SHIFT.0
+            } else {
+                mapping.modmask.0
+            };
+            KeyBoardMappingKey::new(code, mods)
+        })
+    }
+}
+
+/// Modifier names in the order they're shown in the keybind help
+const MOD_NAMES: [(u16, &str); 6] = [
+    (ModMask::FOUR.0, "Mod4"),
+    (ModMask::ONE.0, "Mod1"),
+    (ModMask::THREE.0, "Mod3"),
+    (ModMask::FIVE.0, "Mod5"),
+    (ModMask::CONTROL.0, "Control"),
+    (ModMask::SHIFT.0, "Shift"),
+];
+
+/// Named keysyms that aren't printable characters
+const KEYSYM_NAMES: [(u32, &str); 16] = [
+    (0x20, "Space"),
+    (0xff08, "BackSpace"),
+    (0xff09, "Tab"),
+    (0xff0d, "Return"),
+    (0xff1b, "Escape"),
+    (0xff50, "Home"),
+    (0xff51, "Left"),
+    (0xff52, "Up"),
+    (0xff53, "Right"),
+    (0xff54, "Down"),
+    (0xff55, "PageUp"),
+    (0xff56, "PageDown"),
+    (0xff57, "End"),
+    (0xff61, "Print"),
+    (0xff63, "Insert"),
+    (0xffff, "Delete"),
+];
+
+/// `F1` through `F12` are contiguous from here
+const XK_F1: u32 = 0xffbe;
+
+/// A readable name for a set of modifiers, like `Mod4+Shift`
+#[must_use]
+pub fn mods_label(mods: u16) -> String {
+    let mut label = String::new();
+    for (mask, name) in MOD_NAMES {
+        if mods & mask != 0 {
+            if !label.is_empty() {
+                label.push('+');
+            }
+            label.push_str(name);
+        }
+    }
+    if label.is_empty() {
+        label.push_str("No modifier");
+    }
+    label
+}
+
+/// A readable name for a keysym, printable ascii is shown as is, common function keys by name,
+/// and anything else as its hex value
+#[must_use]
+pub fn keysym_label(keysym: u32) -> String {
+    if let Some((_, name)) = KEYSYM_NAMES.iter().find(|(sym, _)| *sym == keysym) {
+        return String::from(*name);
+    }
+    match keysym {
+        0x21..=0x7e => char::from_u32(keysym).map(String::from).unwrap_or_default(),
+        _ if (XK_F1..XK_F1 + 12).contains(&keysym) => format!("F{}", keysym - XK_F1 + 1),
+        _ => format!("{keysym:#x}"),
+    }
+}
+
+/// Lines listing bindings as `(mods, keysym, action)`, grouped under a header for each combination of
+/// modifiers and sorted by key within a group
+#[must_use]
+pub fn keybind_help_lines(bindings: &mut [(u16, u32, Action)]) -> Vec<String> {
+    bindings.sort_by_key(|(mods, keysym, _)| (*mods, *keysym));
+    let mut lines = Vec::new();
+    let mut current_mods = None;
+    for (mods, keysym, action) in bindings.iter() {
+        if current_mods != Some(*mods) {
+            if current_mods.is_some() {
+                lines.push(String::new());
+            }
+            lines.push(mods_label(*mods));
+            current_mods = Some(*mods);
+        }
+        lines.push(format!("    {:<10} {action:?}", keysym_label(*keysym)));
+    }
+    lines
+}
+
+/// The layouts in the root window's `_XKB_RULES_NAMES` as set by `setxkbmap`, one for each keyboard group.
+/// The property is null separated rules, model, layouts, variants, and options, the layouts are comma separated.
+#[must_use]
+pub fn xkb_layouts(rules_names: &[u8]) -> Vec<&str> {
+    rules_names
+        .split(|byte| *byte == 0)
+        .nth(2)
+        .and_then(|layouts| core::str::from_utf8(layouts).ok())
+        .map(|layouts| {
+            layouts
+                .split(',')
+                .filter(|layout| !layout.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use xcb_rust_protocol::proto::xproto::ModMask;
+
+    use crate::config::key_map::{
+        keybind_help_lines, keysym_label, mods_label, xkb_layouts, KeyBoardMappingKey,
+        KeyboardMapping, KeysymTable, LOCK_MOD_COMBINATIONS,
+    };
+    use crate::config::Action;
+
+    #[test]
+    fn can_look_up_keysyms() {
+        // Keycodes 8 and 9, two keysyms each
+        let table = KeysymTable::new(vec![0x61, 0x41, 0xff0d, 0], 2, 8);
+        assert_eq!(Some(0x61), table.keysym(8, false));
+        assert_eq!(Some(0x41), table.keysym(8, true));
+        assert_eq!(Some(0xff0d), table.keysym(9, false));
+        // Empty shifted column falls back to unshifted
+        assert_eq!(Some(0xff0d), table.keysym(9, true));
+        // Out of range
+        assert!(table.keysym(7, false).is_none());
+        assert!(table.keysym(10, false).is_none());
+    }
+
+    #[test]
+    fn finds_keycodes_on_secondary_groups_and_levels() {
+        // Keycodes 8, 9, and 10, four keysyms each, two per group
+        let table = KeysymTable::new(
+            vec![
+                0x61, 0x41, 0x6c6, 0x6e6, // a A Cyrillic_ef Cyrillic_EF
+                0x26, 0x31, 0x22, 0x32, // ampersand 1 quotedbl 2
+                0x62, 0x42, 0, 0, // b B
+            ],
+            4,
+            8,
+        );
+        assert_eq!(Some((8, false)), table.find_keycode(0x61));
+        assert_eq!(Some((10, false)), table.find_keycode(0x62));
+        // Only on a shifted level
+        assert_eq!(Some((9, true)), table.find_keycode(0x31));
+        // Only on the second group
+        assert_eq!(Some((8, false)), table.find_keycode(0x6c6));
+        assert_eq!(Some((9, false)), table.find_keycode(0x22));
+        assert!(table.find_keycode(0x63).is_none());
+        // Empty slots never match
+        assert!(table.find_keycode(0).is_none());
+    }
+
+    #[test]
+    fn resolve_adds_shift_for_shifted_levels() {
+        let table = KeysymTable::new(vec![0x26, 0x31, 0x61, 0x41], 2, 8);
+        let unshifted = KeyboardMapping::new(ModMask::FOUR, 0x61, Action::Quit);
+        assert_eq!(
+            Some(KeyBoardMappingKey::new(9, ModMask::FOUR.0)),
+            table.resolve(&unshifted)
+        );
+        let shifted = KeyboardMapping::new(ModMask::FOUR, 0x31, Action::Quit);
+        assert_eq!(
+            Some(KeyBoardMappingKey::new(
+                8,
+                ModMask::FOUR.0 | ModMask::SHIFT.0
+            )),
+            table.resolve(&shifted)
+        );
+    }
+
+    #[test]
+    fn key_from_event_ignores_group() {
+        assert_eq!(
+            KeyBoardMappingKey::new(8, ModMask::FOUR.0),
+            KeyBoardMappingKey::from_event(8, ModMask::FOUR.0 | 0x2000)
+        );
+    }
+
+    #[test]
+    fn key_from_event_ignores_locks() {
+        for lock in LOCK_MOD_COMBINATIONS {
+            assert_eq!(
+                KeyBoardMappingKey::new(8, ModMask::FOUR.0 | ModMask::SHIFT.0),
+                KeyBoardMappingKey::from_event(8, ModMask::FOUR.0 | ModMask::SHIFT.0 | lock)
+            );
+        }
+    }
+
+    #[test]
+    fn labels_mods_and_keysyms() {
+        assert_eq!("Mod4+Shift", mods_label(ModMask::FOUR.0 | ModMask::SHIFT.0));
+        assert_eq!("No modifier", mods_label(0));
+        assert_eq!("b", keysym_label(0x62));
+        assert_eq!("Return", keysym_label(0xff0d));
+        assert_eq!("F12", keysym_label(0xffc9));
+        assert_eq!("0x1008ff13", keysym_label(0x1008_ff13));
+    }
+
+    #[test]
+    fn groups_help_lines_by_mods() {
+        let mut bindings = [
+            (ModMask::FOUR.0 | ModMask::SHIFT.0, 0x71, Action::Quit),
+            (ModMask::FOUR.0, 0x6b, Action::FocusPreviousWindow),
+            (ModMask::FOUR.0, 0x62, Action::ToggleBar),
+        ];
+        let lines = keybind_help_lines(&mut bindings);
+        assert_eq!(
+            vec![
+                "Mod4",
+                "    b          ToggleBar",
+                "    k          FocusPreviousWindow",
+                "",
+                "Mod4+Shift",
+                "    q          Quit",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn finds_xkb_layouts() {
+        assert_eq!(
+            vec!["us", "se"],
+            xkb_layouts(b"evdev\0pc105\0us,se\0,nodeadkeys\0grp:alt_shift_toggle\0")
+        );
+        assert_eq!(vec!["us"], xkb_layouts(b"evdev\0pc105\0us\0\0\0"));
+        assert!(xkb_layouts(b"evdev\0pc105\0\0\0\0").is_empty());
+        assert!(xkb_layouts(b"").is_empty());
+    }
 }
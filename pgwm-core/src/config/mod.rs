@@ -1,22 +1,30 @@
-use crate::colors::RGBA;
+use crate::colors::{BorderColorRule, RGBA};
 use tiny_std::UnixStr;
 use x11_keysyms::{
-    XK_Print, XK_Return, XK_b, XK_c, XK_comma, XK_d, XK_f, XK_h, XK_j, XK_k, XK_l, XK_n, XK_period,
-    XK_q, XK_r, XK_space, XK_t, XK_1, XK_2, XK_3, XK_4, XK_5, XK_6, XK_7, XK_8, XK_9,
+    XK_Print, XK_Return, XK_Tab, XK_b, XK_c, XK_comma, XK_d, XK_f, XK_g, XK_h, XK_j, XK_k, XK_l,
+    XK_m, XK_n, XK_period, XK_q, XK_question, XK_r, XK_space, XK_t, XK_w, XK_1, XK_2, XK_3, XK_4,
+    XK_5, XK_6, XK_7, XK_8, XK_9,
 };
 use xcb_rust_protocol::proto::xproto::{ButtonIndexEnum, ModMask};
 
+use crate::config::edges::{Edge, EdgeAction};
 use crate::config::key_map::KeyboardMapping;
 use crate::config::mouse_map::{MouseMapping, MouseTarget};
 use crate::config::workspaces::UserWorkspace;
 
+pub mod cfg;
+pub mod edges;
+#[cfg(feature = "config-file")]
+pub mod file;
 pub mod key_map;
 pub mod mouse_map;
+#[cfg(feature = "config-file")]
+pub mod toml;
 pub mod workspaces;
 
 /// Internal
 #[cfg(feature = "status-bar")]
-pub const _STATUS_BAR_CHECK_CONTENT_LIMIT: usize = 32;
+pub const _STATUS_BAR_CHECK_CONTENT_LIMIT: usize = 64;
 
 /// Internal
 #[cfg(feature = "status-bar")]
@@ -40,6 +48,19 @@ pub const _WM_NAME_LIMIT: usize = 256;
 /// Internal
 pub const _WM_CLASS_NAME_LIMIT: usize = 128;
 
+/// Internal
+pub const _WINDOW_PICKER_FILTER_LIMIT: usize = 32;
+
+/// Internal
+pub const _URGENT_DISPLAY_LIMIT: usize = 128;
+
+/// Internal
+pub const _SPAWN_CWD_PROCESS_DEPTH: usize = 8;
+
+/// Internal, most status checks that can be configured
+#[cfg(feature = "status-bar")]
+pub const _STATUS_CHECK_LIMIT: usize = 16;
+
 /// The name that the window manager will broadcast itself as.
 pub const WINDOW_MANAGER_NAME: &str = "pgwm";
 
@@ -72,6 +93,16 @@ pub const _NUM_TILING_MODIFIERS: usize = WS_WINDOW_LIMIT - 1;
 /// or set it to hidden by default with [`WM_SHOW_BAR_INITIALLY`].
 pub const STATUS_BAR_HEIGHT: i16 = 20;
 
+/// Monitors, by index starting at 0, whose bar starts out autohiding. An autohiding bar is hidden when a window
+/// is focused and shown again when the pointer touches the top of the monitor, or when the workspace changes
+/// or a window becomes urgent. The bar is drawn over windows instead of making room for them.
+/// Toggled per monitor with [`Action::ToggleAutohideBar`].
+pub const AUTOHIDE_BAR_MONITORS: &[usize] = &[];
+
+/// Internal, height in pixels at the top of a monitor with an autohiding bar that's kept clear of windows,
+/// so that the pointer can reach the root window there to reveal the bar
+pub const _AUTOHIDE_BAR_TRIGGER_HEIGHT: i16 = 1;
+
 /// Height in pixels of the tab bar showing which tabs are open (if in tabbed mode)
 pub const TAB_BAR_HEIGHT: i16 = 20;
 
@@ -83,12 +114,88 @@ pub const WINDOW_PADDING: i16 = 8;
 /// `[a, b] `will have a total length of 4 * `window_border_width`, , one left of a, one right of a, one left of b, and one right of b
 pub const WINDOW_BORDER_WIDTH: u32 = 3;
 
+/// Scale applied to [`STATUS_BAR_HEIGHT`], [`TAB_BAR_HEIGHT`], font sizes, [`WINDOW_PADDING`] and [`WINDOW_BORDER_WIDTH`].
+/// `None` derives a scale for each monitor from its reported physical size, where 96 DPI is a scale of 1.0.
+/// Monitors' physical sizes come from `RandR` with the `randr` feature, otherwise they're assumed to share
+/// the screen's DPI.
+pub const SCALE_FACTOR: Option<f32> = None;
+
 /// Padding to the left of where in the workspace bar the window's `WM_NAME` or `_NET_WM_NAME` property is displayed
 pub const WORKSPACE_BAR_WINDOW_NAME_PADDING: u16 = 8;
 
 /// Whether or not to have window padding in the tabbed layout
 pub const PAD_WHILE_TABBED: bool = true;
 
+/// Draw the only tiled window of a workspace without padding and border, like i3-gaps' `smart_gaps`.
+/// They're back as soon as there's a second tiled window, floating windows keep their borders
+pub const SMART_GAPS: bool = false;
+
+/// Whether to show a window's mark (if any) in front of its name in the tab bar
+pub const SHOW_MARKS_IN_TAB_BAR: bool = true;
+
+/// Tab label for windows that share their title with another tab on the same workspace.
+/// `{title}` is replaced by the window's title, `{instance}` and `{class}` by the parts of its `WM_CLASS`.
+pub const DUPLICATE_TITLE_TAB_LABEL: &str = "{class}: {title}";
+
+/// How windows are listed in the window picker after their workspace's name,
+/// with the same replacements as [`DUPLICATE_TITLE_TAB_LABEL`]
+pub const WINDOW_PICKER_LABEL: &str = "{class} {title}";
+
+/// `WM_CLASS` instance or class names of windows that are only told they're fullscreen when they ask to be,
+/// while the WM keeps them in the layout. Useful for browsers and video players that change their UI in fullscreen.
+/// Can be toggled per window with [`Action::ToggleFakeFullscreen`], fe. `&["firefox", "mpv"]`
+pub const FAKE_FULLSCREEN_CLASSES: &[&str] = &[];
+
+/// Distance in pixels within which the edges of a dragged floating window snap to monitor edges,
+/// the bottom of the status bar, and the edges of other floating windows.
+/// Set to 0 to disable snapping
+pub const FLOATING_SNAP_THRESHOLD: i16 = 12;
+
+/// A window dragged to another monitor is tiled there, at the position under the pointer, if it's dropped
+/// on a tiled workspace at least this many pixels inside the tiling area. Dropping it closer to the edges
+/// leaves it floating.
+pub const DROP_TO_TILE_EDGE_MARGIN: i16 = 64;
+
+/// Whether windows slide into place when a monitor switches to another workspace, from the right when going
+/// to a later workspace. Only used when built with the `animations` feature, turn it off to have workspaces
+/// show up at once.
+pub const ANIMATE_WORKSPACE_SWITCH: bool = true;
+
+/// Frames of the workspace switch slide, one every [`WORKSPACE_SWITCH_FRAME_MS`]
+pub const WORKSPACE_SWITCH_FRAMES: usize = 4;
+
+/// Millis each frame of the workspace switch slide is shown
+pub const WORKSPACE_SWITCH_FRAME_MS: u64 = 12;
+
+/// Internal, windows start sliding from this part of the monitor's width to the side
+pub const _WORKSPACE_SWITCH_SLIDE_DIVISOR: i16 = 8;
+
+/// How long messages like failed spawns are shown in place of the focused monitor's window title
+pub const TITLE_NOTICE_MS: u64 = 4000;
+
+/// How long a globally active window (one that takes focus itself on `WM_TAKE_FOCUS`) gets to accept
+/// focus before it's given back to the previously focused window
+pub const FOCUS_VERIFY_MS: u64 = 200;
+
+/// Internal, the longest answer to a `_PGWM_COMMAND` query, a longer one is replaced by an error
+/// so that it fits in the socket write buffer
+pub const _IPC_REPLY_LIMIT: usize = 32768;
+
+/// What a monitor shows when the last window on its workspace closes, see [`LastWindowClosed`]
+pub const ON_LAST_WINDOW_CLOSED: LastWindowClosed = LastWindowClosed::Stay;
+
+/// Actions run when the pointer is pushed against a screen edge and stays there for the dwell time.
+/// Edges are found through pointer motion over the root window and the bar, an edge covered by a client
+/// window (no padding, hidden bar) won't trigger. Runs once per contact, leave the edge to run it again.
+pub const EDGE_ACTIONS: &[EdgeAction] = &[
+    EdgeAction::new(Edge::Top, 0, Action::RevealBar),
+    EdgeAction::new(Edge::Left, 400, Action::PrevWorkspace),
+    EdgeAction::new(Edge::Right, 400, Action::NextWorkspace),
+];
+
+/// Internal, max edges per axis that a dragged window can snap to
+pub const _DRAG_SNAP_EDGE_LIMIT: usize = 64;
+
 /// When a window is signalled to be killed a delete request is sent to the client this is a timeout in milliseconds
 /// starting from when that request is sent to when a destroy-window for that client is sent to x11
 pub const CLIENT_WINDOW_DESTROY_AFTER: u64 = 2000;
@@ -97,6 +204,35 @@ pub const CLIENT_WINDOW_DESTROY_AFTER: u64 = 2000;
 /// If a window is not destroyed after sending a destroy-window, a kill request will be sent after this timeout in milliseconds
 pub const CLIENT_WINDOW_KILL_AFTER: u64 = 5000;
 
+/// Millis to keep trying to connect to the display again after losing the connection to the X server,
+/// in case a display manager is restarting it. If no server comes back the WM exits with status 2,
+/// 0 exits right away.
+pub const X_RECONNECT_TIMEOUT_MS: u64 = 10_000;
+
+/// Minutes without keyboard or pointer input before [`IDLE_ACTION`] runs, as measured by the X screensaver extension.
+/// 0 disables it. Only used when built with the `idle` feature, can be inhibited with `Action::InhibitIdle`.
+/// Disabled if the X server doesn't have the screensaver extension.
+pub const IDLE_AFTER_MINUTES: u64 = 10;
+
+/// Action run once each time input has been idle for [`IDLE_AFTER_MINUTES`], usually spawning a locker
+pub const IDLE_ACTION: Action = Action::Spawn(UnixStr::from_str_checked("/usr/bin/slock\0"), &[]);
+
+/// Shown in front of the window title while idling is inhibited
+pub const IDLE_INHIBITED_ICON: &str = "\u{f0f4}";
+
+/// Internal, millis between checks whether input has resumed while idle
+pub const _IDLE_RECHECK_MS: u64 = 5000;
+
+/// Seconds between resets of the screensaver while a fullscreen window is shown on any monitor, so that a video
+/// isn't interrupted by the screensaver or DPMS blanking the screen. Should be shorter than the screensaver timeout
+/// set with `xset s`, 0 disables it. Resetting counts as input, so [`IDLE_ACTION`] doesn't run either.
+pub const FULLSCREEN_SCREENSAVER_RESET_SECS: u64 = 30;
+
+/// Where captures of the X11 connection go when built with the `trace-x11` or `record` feature
+/// and `$XDG_RUNTIME_DIR` isn't set. Each start of the WM creates a new `pgwm-x11-capture-<unix nanos>` file
+/// and never writes to one that already exists, see [`crate::util::capture::CaptureToken`] for the format
+pub const X11_CAPTURE_FALLBACK_DIR: &str = "/tmp";
+
 /// X11 cursor name, can be found online somewhere, currently unknown where.
 /// Millis before we kill the client
 pub const X11_CURSOR_NAME: &str = "left_ptr";
@@ -136,7 +272,7 @@ pub struct TilingModifiers {
 }
 
 /// Colors, RGBA color values
-pub const COLORS: [RGBA; 17] = [
+pub const COLORS: [RGBA; 18] = [
     WINDOW_BORDER,
     WINDOW_BORDER_HIGHLIGHTED,
     WINDOW_BORDER_URGENT,
@@ -154,8 +290,34 @@ pub const COLORS: [RGBA; 17] = [
     TAB_BAR_UNFOCUSED_TAB_BACKGROUND,
     SHORTCUT_TEXT,
     SHORTCUT_BACKGROUND,
+    STATUS_BAR_ALERT_TEXT,
 ];
 
+/// Colors to read from the X resources (`xrdb`) at startup and whenever they're reloaded, so that the WM follows
+/// the same palette as terminals. Pairs of a color name, `window-border`, `status-bar-text`, and so on, as they're
+/// named in the config file's `[colors]` table, and the resource to read it from, looked up as `pgwm.<resource>`.
+/// A resource that's missing or isn't a `#rrggbb` or `rgb:rr/gg/bb` color leaves the configured color, the alpha
+/// always comes from the configured color. Empty disables it, example:
+/// `&[("window-border-highlighted", "color4"), ("status-bar-background", "background")]`
+pub const XRESOURCE_COLORS: &[(&str, &str)] = &[];
+
+/// Border colors for windows whose `WM_CLASS` instance or class name is the rule's class, used instead of
+/// [`WINDOW_BORDER`] and [`WINDOW_BORDER_HIGHLIGHTED`] for them, the first matching rule wins.
+/// Urgent windows still get [`WINDOW_BORDER_URGENT`]. Example, a red border for a scratchpad terminal:
+/// `&[BorderColorRule::new("scratch", (153, 0, 0, 0), (255, 0, 0, 0))]`
+pub const BORDER_COLOR_RULES: &[BorderColorRule] = &[];
+
+/// Frames of the transition between border colors when a window is focused, unfocused or becomes urgent,
+/// the colors in between are stepped through one frame every [`BORDER_TRANSITION_FRAME_MS`].
+/// 0 disables it, borders change color at once.
+pub const BORDER_TRANSITION_FRAMES: usize = 0;
+
+/// Millis each frame of a border color transition is shown, see [`BORDER_TRANSITION_FRAMES`]
+pub const BORDER_TRANSITION_FRAME_MS: u64 = 16;
+
+/// Internal, max gradients between two border colors kept allocated for transitions
+pub const _BORDER_GRADIENT_CACHE_LIMIT: usize = 32;
+
 /// Window border color when not focused
 pub const WINDOW_BORDER: RGBA = default_black();
 /// Window border color when focused
@@ -190,6 +352,8 @@ pub const TAB_BAR_UNFOCUSED_TAB_BACKGROUND: RGBA = default_black();
 pub const SHORTCUT_TEXT: RGBA = default_white();
 /// Shortcut text color
 pub const SHORTCUT_BACKGROUND: RGBA = default_black();
+/// Text for status checks that need attention, like the privacy check while the microphone or camera is in use
+pub const STATUS_BAR_ALERT_TEXT: RGBA = default_orange();
 
 /// Just some default colors
 const fn default_white() -> RGBA {
@@ -257,10 +421,99 @@ impl<'a> FontCfg<'a> {
     }
 }
 
-/// Shortcuts, placed to the right-most part of the tab bar.
-pub const BAR_SHORTCUTS: [&str; 2] = ["\u{f304}", "\u{f502}"];
+/// Sections of the bar, the window title isn't included since it fills the space left between them
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BarSection {
+    Workspaces,
+    /// Lists workspaces containing urgent windows, see [`URGENT_SECTION_ICON`]
+    Urgent,
+    Shortcuts,
+    /// Ignored if the `status-bar` feature isn't enabled
+    Status,
+}
+
+/// Where on the bar a section is anchored
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BarAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Order and alignment of the bar's sections. Sections with the same alignment are placed left to right in the
+/// order they're listed here, the window title takes up the largest space left between them.
+/// Every section should be listed exactly once.
+pub const BAR_SECTIONS: [(BarSection, BarAlignment); 4] = [
+    (BarSection::Workspaces, BarAlignment::Left),
+    (BarSection::Urgent, BarAlignment::Left),
+    (BarSection::Status, BarAlignment::Right),
+    (BarSection::Shortcuts, BarAlignment::Right),
+];
+
+/// Shown in front of the number of urgent windows and the names of the workspaces containing them,
+/// the section is empty while no window is urgent.
+pub const URGENT_SECTION_ICON: &str = "\u{f0f3}";
+
+/// A clickable glyph on the bar, left clicking it executes its action.
+/// Other buttons can still be mapped in [`MOUSE_MAPPINGS`] through `MouseTarget::ShortcutComponent`,
+/// which is indexed by the shortcut's position in [`BAR_SHORTCUTS`], or in the config file's `[[bar-shortcut]]` list.
+#[derive(Debug, Copy, Clone)]
+pub struct BarShortcut {
+    pub glyph: &'static str,
+    pub action: Action,
+    /// Drawn in place of the window title while the pointer hovers the shortcut
+    pub hover_text: Option<&'static str>,
+}
+
+impl BarShortcut {
+    #[must_use]
+    pub const fn new(
+        glyph: &'static str,
+        action: Action,
+        hover_text: Option<&'static str>,
+    ) -> Self {
+        Self {
+            glyph,
+            action,
+            hover_text,
+        }
+    }
+}
 
-/// Status checks, put at the top-right of the tab bar.
+/// Shortcuts, placed according to [`BAR_SECTIONS`], any number of them can be added.
+/// A config file's `[[bar-shortcut]]` list replaces them.
+pub const BAR_SHORTCUTS: [BarShortcut; 2] = [
+    BarShortcut::new(
+        "\u{f304}",
+        Action::Spawn(
+            UnixStr::from_str_checked("/usr/bin/xterm\0"),
+            &[
+                UnixStr::from_str_checked("-e\0"),
+                // Using bash to access '~' as home
+                UnixStr::from_str_checked("/bin/bash\0"),
+                UnixStr::from_str_checked("-c\0"),
+                // Pop some configuration files in a new terminal
+                UnixStr::from_str_checked("nvim ~/.bashrc ~/.xinitrc ~/.config/pgwm/pgwm.toml\0"),
+            ],
+        ),
+        Some("Edit configuration"),
+    ),
+    BarShortcut::new(
+        "\u{f502}",
+        Action::Spawn(
+            UnixStr::from_str_checked("/usr/bin/xscreensaver-command\0"),
+            &[UnixStr::from_str_checked("-lock\0")],
+        ),
+        Some("Lock screen"),
+    ),
+];
+
+/// Status checks, placed according to [`BAR_SECTIONS`].
+/// A [`crate::status::checker::CheckType::Keyboard`] check shows the active keyboard layout
+/// when built with the `xkb` feature.
+/// A [`crate::status::checker::CheckType::Privacy`] check shows when the microphone or camera is in use.
+/// A [`crate::status::checker::CheckType::External`] check shows what an external generator writes to a fifo
+/// or sets as the root window name, one of each source can be used.
 #[cfg(feature = "status-bar")]
 pub const STATUS_CHECKS: [crate::status::checker::Check; 4] = [
     crate::status::checker::Check {
@@ -322,6 +575,19 @@ pub const fn offset() -> time::UtcOffset {
     }
 }
 
+/// Policy for a monitor whose hosted workspace has its last window closed
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[derive(Debug, Copy, Clone)]
+pub enum LastWindowClosed {
+    /// Keep showing the empty workspace
+    Stay,
+    /// Show the workspace most recently hosted on the monitor that has windows and isn't hosted elsewhere,
+    /// stays if there is none
+    MostRecentNonEmpty,
+    /// Show the workspace at this index, in the monitor's set if [`PER_MONITOR_WORKSPACES`] is set
+    Workspace(usize),
+}
+
 #[cfg_attr(test, derive(Eq, PartialEq))]
 #[derive(Default, Debug, Copy, Clone)]
 pub enum DefaultDraw {
@@ -331,10 +597,46 @@ pub enum DefaultDraw {
     Tabbed,
 }
 
+/// Give each monitor its own set of `USER_WORKSPACES` instead of sharing one set between all monitors.
+/// Workspace actions, the bar, class name mappings, and [`LastWindowClosed::Workspace`] then refer to
+/// the workspace at that index in the focused monitor's set, and a workspace is only ever shown on the
+/// monitor owning its set. Windows on the set of a monitor that's disconnected are shown again when it's back.
+pub const PER_MONITOR_WORKSPACES: bool = false;
+
+/// Keep transient windows, like dialogs, on the workspace of the window they're transient for even if
+/// their class name is mapped to another workspace. If false the class mapping wins and the transient
+/// is managed on its own there.
+pub const TRANSIENTS_FOLLOW_PARENT: bool = false;
+
+/// How windows on workspaces that aren't shown are hidden, see [`HideStrategy`]
+pub const HIDE_STRATEGY: HideStrategy = HideStrategy::Unmap;
+
+/// How windows are hidden when their workspace isn't shown
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[derive(Debug, Copy, Clone)]
+pub enum HideStrategy {
+    /// Unmap them, some clients pause rendering while unmapped
+    Unmap,
+    /// Keep them mapped but move them to the left of the screen. Clients keep rendering, and an `UnmapNotify`
+    /// can only mean that the client unmapped itself
+    MoveOffscreen,
+}
+
+/// Remember the workspace that the user last sent or dragged a window of a class to, and where it was
+/// left floating, for up to this many classes. The next window of that class is put there instead of
+/// where `USER_WORKSPACES` maps it. Kept in `$XDG_STATE_HOME/pgwm/placements` so that it survives
+/// restarts, 0 turns it off.
+pub const PLACEMENT_HISTORY_SIZE: usize = 0;
+
 /// Available workspaces and their names and respective `class_name` mappings
 /// The `mapped_class_names` is an array of wm class names
 /// If a window is spawned with a mapped class name it will be remapped to the specified workspace
 /// Finding a windows `WM_CLASS_NAME` property can be done with fe. [xprop](https://www.x.org/releases/X11R7.5/doc/man/man1/xprop.1.html)
+/// A workspace can be given a root window background color through `.with_background((r, g, b, a))`,
+/// painted behind it on whichever monitor it's hosted on. If any workspace has a background the WM takes
+/// over the root window's background, and workspaces without one are painted black.
+/// `.with_padding(0)` and `.with_border_width(0)` override `WINDOW_PADDING` and `WINDOW_BORDER_WIDTH` on a workspace,
+/// like one for watching videos without gaps. Resizing padding or borders through actions leaves overrides as they are.
 pub const USER_WORKSPACES: [UserWorkspace; 9] = [
     UserWorkspace::new(
         "\u{f121}",
@@ -367,7 +669,7 @@ The unit of 2 is undefined, it's some implementation specific modifier
 Available modifiers can be found in `ButtonIndex` imported at the top of this file (although it's M1 through M5).
 `MouseTarget` should likely always be `MouseTarget::ClientWindow`
  **/
-pub const MOUSE_MAPPINGS: [MouseMapping; 16] = [
+pub const MOUSE_MAPPINGS: [MouseMapping; 17] = [
     MouseMapping {
         target: MouseTarget::ClientWindow,
         mods: MOD_KEY,
@@ -440,6 +742,26 @@ pub const MOUSE_MAPPINGS: [MouseMapping; 16] = [
         button: ButtonIndexEnum::ONE,
         action: Action::ToggleWorkspace(8),
     },
+    // Scroll on the workspace section to cycle workspaces on that monitor
+    MouseMapping {
+        target: MouseTarget::WorkspaceBar,
+        mods: ModMask(0u16),
+        button: ButtonIndexEnum::FOUR,
+        action: Action::PrevWorkspace,
+    },
+    MouseMapping {
+        target: MouseTarget::WorkspaceBar,
+        mods: ModMask(0u16),
+        button: ButtonIndexEnum::FIVE,
+        action: Action::NextWorkspace,
+    },
+    // Click the urgent section to jump to the urgent window
+    MouseMapping {
+        target: MouseTarget::UrgentSection,
+        mods: ModMask(0u16),
+        button: ButtonIndexEnum::ONE,
+        action: Action::FocusUrgent,
+    },
     MouseMapping {
         target: MouseTarget::StatusComponent(0),
         mods: ModMask(0u16),
@@ -464,31 +786,6 @@ pub const MOUSE_MAPPINGS: [MouseMapping; 16] = [
             ],
         ),
     },
-    MouseMapping {
-        target: MouseTarget::ShortcutComponent(0),
-        mods: ModMask(0u16),
-        button: ButtonIndexEnum::ONE,
-        action: Action::Spawn(
-            UnixStr::from_str_checked("/usr/bin/xterm\0"),
-            &[
-                UnixStr::from_str_checked("-e\0"),
-                // Using bash to access '~' as home
-                UnixStr::from_str_checked("/bin/bash\0"),
-                UnixStr::from_str_checked("-c\0"),
-                // Pop some configuration files in a new terminal
-                UnixStr::from_str_checked("nvim ~/.bashrc ~/.xinitrc ~/.config/pgwm/pgwm.toml\0"),
-            ],
-        ),
-    },
-    MouseMapping {
-        target: MouseTarget::ShortcutComponent(1),
-        mods: ModMask(0u16),
-        button: ButtonIndexEnum::ONE,
-        action: Action::Spawn(
-            UnixStr::from_str_checked("/usr/bin/xscreensaver-command\0"),
-            &[UnixStr::from_str_checked("-lock\0")],
-        ),
-    },
 ];
 
 /// The mod key, maps to super on my machine's keyboard, can be changed to any of the available
@@ -502,9 +799,11 @@ const MOD_KEY: ModMask = ModMask::FOUR;
 /// others and used more concisely as `XK_b`.
 /// The third parameter is the action that should be taken when the mods and key gets pressed.
 /// It's an enum of which all values are exemplified in the below default configuration.
-pub const KEYBOARD_MAPPINGS: [KeyboardMapping; 41] = [
+/// Holding a key runs its action again for every auto-repeated press, unless the mapping is
+/// made with `.without_repeat()`, which is useful for toggles.
+pub const KEYBOARD_MAPPINGS: [KeyboardMapping; 50] = [
     // Shows or hides the top bar
-    KeyboardMapping::new(MOD_KEY, XK_b, Action::ToggleBar),
+    KeyboardMapping::new(MOD_KEY, XK_b, Action::ToggleBar).without_repeat(),
     // Focuses the (logically) previous window of the focused workspace (if any)
     KeyboardMapping::new(MOD_KEY, XK_k, Action::FocusPreviousWindow),
     // Focuses the (logically) next window of the focused workspace (if any)
@@ -513,8 +812,26 @@ pub const KEYBOARD_MAPPINGS: [KeyboardMapping; 41] = [
     KeyboardMapping::new(MOD_KEY, XK_comma, Action::FocusPreviousMonitor),
     // Focuses the (logically) next monitor of the focused monitor (if any)
     KeyboardMapping::new(MOD_KEY, XK_period, Action::FocusNextMonitor),
+    // Moves the focused workspace to the previous monitor, swapping with the workspace hosted there
+    KeyboardMapping::new(
+        ModMask(MOD_KEY.0 | ModMask::SHIFT.0),
+        XK_comma,
+        Action::MoveWorkspaceToMonitor(MonitorDirection::Previous),
+    ),
+    // Moves the focused workspace to the next monitor, swapping with the workspace hosted there
+    KeyboardMapping::new(
+        ModMask(MOD_KEY.0 | ModMask::SHIFT.0),
+        XK_period,
+        Action::MoveWorkspaceToMonitor(MonitorDirection::Next),
+    ),
     // Cycles the DrawMode from tiled to tabbed
-    KeyboardMapping::new(MOD_KEY, XK_space, Action::CycleDrawMode),
+    KeyboardMapping::new(MOD_KEY, XK_space, Action::CycleDrawMode).without_repeat(),
+    // Switches to the next keyboard layout, see `Action::NextKeyboardLayout`
+    KeyboardMapping::new(
+        ModMask(MOD_KEY.0 | ModMask::SHIFT.0),
+        XK_space,
+        Action::NextKeyboardLayout,
+    ),
     // Cycles the Tiling layout from left-leader to center-leader to left-leader to ... etc.
     KeyboardMapping::new(MOD_KEY, XK_n, Action::NextTilingMode),
     // Updates the window size, if positive increases size, negative decreases.
@@ -557,7 +874,17 @@ pub const KEYBOARD_MAPPINGS: [KeyboardMapping; 41] = [
     // Unfloat a tiling window, placing it at the 0th position of the tile-set
     KeyboardMapping::new(MOD_KEY, XK_t, Action::UnFloat),
     // Toggle fullscreen on the currently focused workspace
-    KeyboardMapping::new(MOD_KEY, XK_f, Action::ToggleFullscreen),
+    KeyboardMapping::new(MOD_KEY, XK_f, Action::ToggleFullscreen).without_repeat(),
+    // Maximize the focused window (keeping the bar and borders), or restore it if already maximized
+    KeyboardMapping::new(MOD_KEY, XK_m, Action::ToggleMaximize).without_repeat(),
+    // Toggle the mark "m" on the focused window, moving it from any other window that has it.
+    // A marked window can be focused with `Action::FocusMark("m")`, and all marked windows
+    // can be sent to a workspace at once with `Action::SendMarkedToWorkspace(n)`
+    KeyboardMapping::new(
+        ModMask(MOD_KEY.0 | ModMask::SHIFT.0),
+        XK_m,
+        Action::MarkWindow("m"),
+    ),
     // Toggle a workspace on the currently focused monitor.
     // The number is an index, and if that index does not match an existing workspace
     // the WM will immediately crash.
@@ -570,6 +897,14 @@ pub const KEYBOARD_MAPPINGS: [KeyboardMapping; 41] = [
     KeyboardMapping::new(MOD_KEY, XK_7, Action::ToggleWorkspace(6)),
     KeyboardMapping::new(MOD_KEY, XK_8, Action::ToggleWorkspace(7)),
     KeyboardMapping::new(MOD_KEY, XK_9, Action::ToggleWorkspace(8)),
+    // Pick a window to focus from the bar, type to filter, up/down to select, enter to focus
+    KeyboardMapping::new(MOD_KEY, XK_w, Action::WindowPicker),
+    // Number the visible windows, keep the mod key held and press a number to focus that window
+    KeyboardMapping::new(MOD_KEY, XK_g, Action::WindowHints),
+    // Show every key binding in an overlay on the focused monitor, any key closes it
+    KeyboardMapping::new(MOD_KEY, XK_question, Action::ShowKeybindHelp),
+    // Switch the focused monitor back to the workspace it hosted before the current one
+    KeyboardMapping::new(MOD_KEY, XK_Tab, Action::ToggleLastWorkspace).without_repeat(),
     // Send the currently focused window to another workspace.
     // The number is an index, and if that index does not match an existing workspace
     // the WM will immediately crash.
@@ -623,6 +958,12 @@ pub const KEYBOARD_MAPPINGS: [KeyboardMapping; 41] = [
         XK_Return,
         Action::Spawn(UnixStr::from_str_checked("/usr/bin/xterm\0"), &[]),
     ),
+    // Opens a terminal in the directory of the focused terminal
+    KeyboardMapping::new(
+        ModMask(MOD_KEY.0 | ModMask::CONTROL.0),
+        XK_Return,
+        Action::SpawnInFocusedCwd(UnixStr::from_str_checked("/usr/bin/xterm\0"), &[]),
+    ),
     KeyboardMapping::new(
         MOD_KEY,
         XK_d,
@@ -683,20 +1024,61 @@ pub const CHAR_REMAP: &[(char, &FontCfg<'static>)] = &[
     ('\u{f502}', ICON_FONT),
     ('\u{f304}', ICON_FONT),
     ('\u{f073}', ICON_FONT),
+    ('\u{f0f4}', ICON_FONT),
 ];
 
+/// Which monitor relative to the focused one, monitors wrap around
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+pub enum MonitorDirection {
+    Next,
+    Previous,
+}
+
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub enum Action {
     Quit,
     Restart,
+    /// Runs a program with arguments, a leading `~` and environment variables like `$HOME` or `${TERMINAL}`
+    /// in either are expanded when it's spawned. A program given by name rather than path is looked up in `$PATH`.
     Spawn(&'static UnixStr, &'static [&'static UnixStr]),
+    /// Like [`Action::Spawn`] but started in the working directory of the focused window's process,
+    /// or of the youngest process started under it such as the shell of a terminal, found through
+    /// `_NET_WM_PID`. Falls back to the WM's directory if the focused window has no pid.
+    SpawnInFocusedCwd(&'static UnixStr, &'static [&'static UnixStr]),
     Close,
+    /// Closes every window in the focused window's group, the windows of one application as told by
+    /// their `WM_CLIENT_LEADER`, or their `WM_HINTS` window group if they have no leader
+    CloseGroup,
     ToggleWorkspace(usize),
+    ToggleLastWorkspace,
+    NextWorkspace,
+    PrevWorkspace,
     SendToWorkspace(usize),
+    MarkWindow(&'static str),
+    FocusMark(&'static str),
+    SendMarkedToWorkspace(usize),
+    WindowPicker,
+    /// Draws a number over each visible window, up to 9, and grabs the keyboard so that pressing a number
+    /// focuses its window. Releasing a modifier, like the one held to trigger this, or any other key closes it.
+    WindowHints,
+    /// Shows an overlay listing every key binding, generated from the grabbed keys
+    ShowKeybindHelp,
+    /// Focuses the window that has been urgent the longest, showing its workspace if hidden
+    FocusUrgent,
     SendToFront,
     UnFloat,
     ToggleFullscreen,
+    /// Switches the status check at this position in the status bar to its next format, if it has more than one.
+    /// Left clicking a status check does this, fe. to cycle through the alternatives of a date check
+    CycleStatusFormat(usize),
+    /// Toggles fake fullscreen for a window, see [`FAKE_FULLSCREEN_CLASSES`]
+    ToggleFakeFullscreen,
+    /// Pins a window to its monitor, or unpins it. A pinned window stays on its monitor when its workspace
+    /// is moved to another one, joining whichever workspace is shown there instead
+    TogglePinToMonitor,
+    ToggleMaximize,
     CycleDrawMode,
     MoveWindow,
     NextTilingMode,
@@ -706,7 +1088,24 @@ pub enum Action {
     ResetToDefaultSizeModifiers,
     FocusNextWindow,
     FocusPreviousWindow,
+    /// Focuses the next window in the focused window's group, see [`Action::CloseGroup`],
+    /// showing its workspace if it's hidden
+    FocusNextInGroup,
     FocusNextMonitor,
     FocusPreviousMonitor,
+    MoveWorkspaceToMonitor(MonitorDirection),
     ToggleBar,
+    /// Shows the bar on the focused monitor if it's hidden
+    RevealBar,
+    /// Toggles whether the bar on the focused monitor autohides, see [`AUTOHIDE_BAR_MONITORS`]
+    ToggleAutohideBar,
+    /// Toggles whether [`IDLE_ACTION`] is kept from running, shown by [`IDLE_INHIBITED_ICON`] in the bar
+    #[cfg(feature = "idle")]
+    InhibitIdle,
+    /// Unmaps the windows on the focused monitor to show the desktop, or maps them again.
+    /// Drawing anything on the monitor, like a new window or another workspace, also maps them again.
+    ToggleShowDesktop,
+    /// Locks the next XKB group, wrapping around to the first, groups are the layouts set with `setxkbmap`.
+    /// Only does something when built with the `xkb` feature.
+    NextKeyboardLayout,
 }
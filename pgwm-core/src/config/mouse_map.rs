@@ -24,7 +24,11 @@ impl MouseActionKey {
 pub enum MouseTarget {
     ClientWindow,
     WorkspaceBarComponent(usize),
+    /// Anywhere on the workspace section of the bar, used if no binding matches the specific component
+    WorkspaceBar,
     WindowTitle,
+    /// The section listing workspaces containing urgent windows
+    UrgentSection,
     ShortcutComponent(usize),
     StatusComponent(usize),
     Tab,
@@ -36,11 +40,22 @@ impl MouseTarget {
         matches!(
             self,
             MouseTarget::WorkspaceBarComponent(_)
+                | MouseTarget::WorkspaceBar
                 | MouseTarget::WindowTitle
+                | MouseTarget::UrgentSection
                 | MouseTarget::ShortcutComponent(_)
                 | MouseTarget::StatusComponent(_)
         )
     }
+
+    /// The section a component belongs to, if bindings can target the whole section
+    #[must_use]
+    pub fn section(&self) -> Option<MouseTarget> {
+        match self {
+            MouseTarget::WorkspaceBarComponent(_) => Some(MouseTarget::WorkspaceBar),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
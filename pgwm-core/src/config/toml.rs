@@ -0,0 +1,596 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A parsed TOML value, only what the config file needs, dates are not supported
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    /// Keys in the order they were defined
+    Table(Vec<(String, Value)>),
+}
+
+impl Value {
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Table(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+/// Where and why a document couldn't be parsed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// Starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_fmt(format_args!("line {}: {}", self.line, self.message))
+    }
+}
+
+type ParseResult<T> = core::result::Result<T, ParseError>;
+
+/// Parse a TOML document into its root table.
+/// Supports comments, tables, arrays of tables, dotted keys, basic and literal strings,
+/// integers (also hex, octal and binary), floats, booleans, arrays, and inline tables.
+/// Multiline strings are not supported and rejected.
+pub fn parse(src: &str) -> ParseResult<Value> {
+    let mut parser = Parser {
+        src: src.as_bytes(),
+        pos: 0,
+        line: 1,
+    };
+    let mut root = Value::Table(Vec::new());
+    // Path to the table that key/value pairs go into, the last element of an array of tables for `[[name]]`
+    let mut current: Vec<String> = Vec::new();
+    // Tables defined by a `[name]` header, each may only be defined once
+    let mut defined: Vec<Vec<String>> = Vec::new();
+    loop {
+        parser.skip_whitespace_comments_and_newlines();
+        let Some(c) = parser.peek() else {
+            return Ok(root);
+        };
+        let line = parser.line;
+        if c == b'[' {
+            parser.pos += 1;
+            let array = parser.peek() == Some(b'[');
+            if array {
+                parser.pos += 1;
+            }
+            parser.skip_whitespace();
+            let path = parser.key()?;
+            parser.skip_whitespace();
+            parser.expect(b']')?;
+            if array {
+                parser.expect(b']')?;
+            }
+            parser.end_of_line()?;
+            let parent = table_at(&mut root, &path[..path.len() - 1], line)?;
+            let last = &path[path.len() - 1];
+            if array {
+                // Tables under the previous element may be defined again under the new one
+                defined.retain(|table| !table.starts_with(&path));
+                match entry(parent, last) {
+                    Some(Value::Array(tables)) => tables.push(Value::Table(Vec::new())),
+                    Some(_) => {
+                        return Err(error(line, format!("{last} is not an array of tables")))
+                    }
+                    None => insert(
+                        parent,
+                        last.clone(),
+                        Value::Array(alloc::vec![Value::Table(Vec::new())]),
+                    ),
+                }
+            } else {
+                if defined.contains(&path) {
+                    return Err(error(
+                        line,
+                        format!("table {} is defined twice", path.join(".")),
+                    ));
+                }
+                defined.push(path.clone());
+                match entry(parent, last) {
+                    // Created implicitly by a header of a table nested in it
+                    Some(Value::Table(_)) => {}
+                    Some(_) => return Err(error(line, format!("{last} is already defined"))),
+                    None => insert(parent, last.clone(), Value::Table(Vec::new())),
+                }
+            }
+            current = path;
+        } else {
+            let key = parser.key()?;
+            parser.skip_whitespace();
+            parser.expect(b'=')?;
+            parser.skip_whitespace();
+            let value = parser.value()?;
+            parser.end_of_line()?;
+            let mut table = table_at(&mut root, &current, line)?;
+            for part in &key[..key.len() - 1] {
+                table = child_table(table, part, line)?;
+            }
+            let last = &key[key.len() - 1];
+            if entry(table, last).is_some() {
+                return Err(error(line, format!("{last} is already defined")));
+            }
+            insert(table, last.clone(), value);
+        }
+    }
+}
+
+fn error(line: usize, message: String) -> ParseError {
+    ParseError { line, message }
+}
+
+fn entry<'a>(table: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match table {
+        Value::Table(entries) => entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+        _ => None,
+    }
+}
+
+fn insert(table: &mut Value, key: String, value: Value) {
+    if let Value::Table(entries) = table {
+        entries.push((key, value));
+    }
+}
+
+/// The table under `key`, created if missing, or the last table of an array of tables
+fn child_table<'a>(table: &'a mut Value, key: &str, line: usize) -> ParseResult<&'a mut Value> {
+    if entry(table, key).is_none() {
+        insert(table, String::from(key), Value::Table(Vec::new()));
+    }
+    match entry(table, key) {
+        Some(Value::Array(values)) => match values.last_mut() {
+            Some(last @ Value::Table(_)) => Ok(last),
+            _ => Err(error(line, format!("{key} is not a table"))),
+        },
+        Some(table @ Value::Table(_)) => Ok(table),
+        _ => Err(error(line, format!("{key} is not a table"))),
+    }
+}
+
+fn table_at<'a>(root: &'a mut Value, path: &[String], line: usize) -> ParseResult<&'a mut Value> {
+    let mut table = root;
+    for part in path {
+        table = child_table(table, part, line)?;
+    }
+    Ok(table)
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn err<T>(&self, message: String) -> ParseResult<T> {
+        Err(error(self.line, message))
+    }
+
+    fn expect(&mut self, c: u8) -> ParseResult<()> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.err(format!("expected '{}'", c as char))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while !matches!(self.peek(), Some(b'\n') | None) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn newline(&mut self) -> bool {
+        match self.peek() {
+            Some(b'\n') => {
+                self.pos += 1;
+                self.line += 1;
+                true
+            }
+            Some(b'\r') if self.src.get(self.pos + 1) == Some(&b'\n') => {
+                self.pos += 2;
+                self.line += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn skip_whitespace_comments_and_newlines(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            if !self.newline() {
+                return;
+            }
+        }
+    }
+
+    /// Only whitespace and a comment may follow a key/value pair or table header on its line
+    fn end_of_line(&mut self) -> ParseResult<()> {
+        self.skip_whitespace();
+        self.skip_comment();
+        if self.newline() || self.peek().is_none() {
+            Ok(())
+        } else {
+            self.err(String::from("expected a newline"))
+        }
+    }
+
+    /// A possibly dotted key
+    fn key(&mut self) -> ParseResult<Vec<String>> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                Some(b'"') => self.basic_string()?,
+                Some(b'\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return self.err(String::from("expected a key"));
+                    }
+                    String::from_utf8_lossy(&self.src[start..self.pos]).into_owned()
+                }
+            };
+            parts.push(part);
+            self.skip_whitespace();
+            if self.peek() == Some(b'.') {
+                self.pos += 1;
+            } else {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> ParseResult<Value> {
+        match self.peek() {
+            Some(b'"') => Ok(Value::String(self.basic_string()?)),
+            Some(b'\'') => Ok(Value::String(self.literal_string()?)),
+            Some(b'[') => self.array(),
+            Some(b'{') => self.inline_table(),
+            Some(b't' | b'f') => self.boolean(),
+            Some(c) if c.is_ascii_digit() || matches!(c, b'+' | b'-' | b'i' | b'n') => {
+                self.number()
+            }
+            _ => self.err(String::from("expected a value")),
+        }
+    }
+
+    fn basic_string(&mut self) -> ParseResult<String> {
+        self.expect(b'"')?;
+        self.reject_multiline(b"\"\"")?;
+        let mut out = String::new();
+        let mut start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return self.err(String::from("unterminated string")),
+                Some(b'"') => {
+                    out.push_str(self.str_from(start)?);
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    out.push_str(self.str_from(start)?);
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'r') => '\r',
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'u') => self.unicode_escape(4)?,
+                        Some(b'U') => self.unicode_escape(8)?,
+                        _ => return self.err(String::from("invalid escape")),
+                    };
+                    out.push(escaped);
+                    self.pos += 1;
+                    start = self.pos;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Reads the hex digits after `\u` or `\U`, leaves the position at the last digit
+    fn unicode_escape(&mut self, digits: usize) -> ParseResult<char> {
+        let start = self.pos + 1;
+        let end = start + digits;
+        let code = self
+            .src
+            .get(start..end)
+            .and_then(|hex| core::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32);
+        let Some(code) = code else {
+            return self.err(String::from("invalid unicode escape"));
+        };
+        self.pos = end - 1;
+        Ok(code)
+    }
+
+    fn literal_string(&mut self) -> ParseResult<String> {
+        self.expect(b'\'')?;
+        self.reject_multiline(b"''")?;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return self.err(String::from("unterminated string")),
+                Some(b'\'') => {
+                    let out = String::from(self.str_from(start)?);
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Called after an opening quote, two more of them start a multiline string
+    fn reject_multiline(&self, quotes: &[u8]) -> ParseResult<()> {
+        if self.src[self.pos..].starts_with(quotes) {
+            return self.err(String::from("multiline strings are not supported"));
+        }
+        Ok(())
+    }
+
+    fn str_from(&self, start: usize) -> ParseResult<&'a str> {
+        core::str::from_utf8(&self.src[start..self.pos])
+            .or_else(|_| self.err(String::from("invalid utf8")))
+    }
+
+    fn array(&mut self) -> ParseResult<Value> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace_comments_and_newlines();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_whitespace_comments_and_newlines();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return self.err(String::from("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> ParseResult<Value> {
+        self.expect(b'{')?;
+        let mut table = Value::Table(Vec::new());
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(table);
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_whitespace();
+            self.expect(b'=')?;
+            self.skip_whitespace();
+            let value = self.value()?;
+            let mut inner = &mut table;
+            for part in &key[..key.len() - 1] {
+                inner = child_table(inner, part, self.line)?;
+            }
+            let last = &key[key.len() - 1];
+            if entry(inner, last).is_some() {
+                return self.err(format!("{last} is already defined"));
+            }
+            insert(inner, last.clone(), value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                }
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(table);
+                }
+                _ => return self.err(String::from("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn boolean(&mut self) -> ParseResult<Value> {
+        for (word, value) in [("true", true), ("false", false)] {
+            if self.src[self.pos..].starts_with(word.as_bytes()) {
+                self.pos += word.len();
+                return Ok(Value::Boolean(value));
+            }
+        }
+        self.err(String::from("expected a value"))
+    }
+
+    fn number(&mut self) -> ParseResult<Value> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || matches!(c, b'+' | b'-' | b'.' | b'_'))
+        {
+            self.pos += 1;
+        }
+        let raw = self.str_from(start)?;
+        let cleaned = raw.replace('_', "");
+        let (negative, unsigned) = match cleaned.as_bytes().first() {
+            Some(b'-') => (true, &cleaned[1..]),
+            Some(b'+') => (false, &cleaned[1..]),
+            _ => (false, cleaned.as_str()),
+        };
+        let radix = match unsigned.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            return i64::from_str_radix(&unsigned[2..], radix)
+                .map(|v| Value::Integer(if negative { -v } else { v }))
+                .or_else(|_| self.err(format!("invalid number {raw}")));
+        }
+        if let Ok(v) = cleaned.parse::<i64>() {
+            return Ok(Value::Integer(v));
+        }
+        match unsigned {
+            "inf" if negative => Ok(Value::Float(f64::NEG_INFINITY)),
+            "inf" => Ok(Value::Float(f64::INFINITY)),
+            "nan" => Ok(Value::Float(f64::NAN)),
+            _ => cleaned
+                .parse::<f64>()
+                .map(Value::Float)
+                .or_else(|_| self.err(format!("invalid number {raw}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::config::toml::{parse, Value};
+
+    fn s(v: &str) -> Value {
+        Value::String(String::from(v))
+    }
+
+    #[test]
+    fn parses_values_and_tables() {
+        let doc = parse(
+            r#"
+# Comment
+window-padding = 8 # trailing
+scale = 1.5
+enabled = true
+name = "a \"quoted\" \u00e9"
+path = 'C:\raw'
+hex = 0xff61
+neg = -1_000
+
+[colors]
+border = [28, 31, 36,
+    0, ] # multiline
+nested.inner = "x"
+
+[[key-mapping]]
+mods = ["M4", "Shift"]
+on_click = { action = "Spawn", args = ["dmenu_run", ["-i"]] }
+
+[[key-mapping]]
+key = 0x0064
+"#,
+        )
+        .unwrap();
+        assert_eq!(Some(&Value::Integer(8)), doc.get("window-padding"));
+        assert_eq!(Some(&Value::Float(1.5)), doc.get("scale"));
+        assert_eq!(Some(&Value::Boolean(true)), doc.get("enabled"));
+        assert_eq!(Some(&s("a \"quoted\" é")), doc.get("name"));
+        assert_eq!(Some(&s("C:\\raw")), doc.get("path"));
+        assert_eq!(Some(&Value::Integer(0xff61)), doc.get("hex"));
+        assert_eq!(Some(&Value::Integer(-1000)), doc.get("neg"));
+        let colors = doc.get("colors").unwrap();
+        assert_eq!(
+            Some(&Value::Array(vec![
+                Value::Integer(28),
+                Value::Integer(31),
+                Value::Integer(36),
+                Value::Integer(0)
+            ])),
+            colors.get("border")
+        );
+        assert_eq!(
+            Some(&s("x")),
+            colors.get("nested").and_then(|n| n.get("inner"))
+        );
+        let Some(Value::Array(mappings)) = doc.get("key-mapping") else {
+            panic!("Expected an array of tables");
+        };
+        assert_eq!(2, mappings.len());
+        assert_eq!(
+            Some(&Value::Array(vec![s("M4"), s("Shift")])),
+            mappings[0].get("mods")
+        );
+        let on_click = mappings[0].get("on_click").unwrap();
+        assert_eq!(Some(&s("Spawn")), on_click.get("action"));
+        assert_eq!(
+            Some(&Value::Array(vec![
+                s("dmenu_run"),
+                Value::Array(vec![s("-i")])
+            ])),
+            on_click.get("args")
+        );
+        assert_eq!(Some(&Value::Integer(0x64)), mappings[1].get("key"));
+    }
+
+    #[test]
+    fn reports_errors_with_lines() {
+        let err = parse("a = 1\nb = \"open\n").unwrap_err();
+        assert_eq!(2, err.line);
+        let err = parse("a = 1\na = 2").unwrap_err();
+        assert_eq!(2, err.line);
+        assert!(parse("a = 1 b = 2").is_err());
+        assert!(parse("[t]\nx = 1\n[t.x]").is_err());
+        let err = parse("a = \"\"\"\nmulti\n\"\"\"").unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!("multiline strings are not supported", err.message);
+        assert!(parse("a = '''raw'''").is_err());
+        // Empty strings are fine
+        assert_eq!(Some(&s("")), parse("a = \"\"").unwrap().get("a"));
+    }
+
+    #[test]
+    fn rejects_tables_defined_twice() {
+        let err = parse("[colors]\na = 1\n\n[fonts]\n[colors]\nb = 2").unwrap_err();
+        assert_eq!(5, err.line);
+        assert_eq!("table colors is defined twice", err.message);
+        let err = parse("[a.b]\n[a.b]").unwrap_err();
+        assert_eq!("table a.b is defined twice", err.message);
+        // A table created by a nested header can still be defined once
+        assert!(parse("[a.b]\nx = 1\n[a]\ny = 2").is_ok());
+        assert!(parse("[a.b]\n[a]\n[a]").is_err());
+        // Every element of an array of tables has its own sub-tables
+        assert!(parse("[[w]]\n[w.sub]\nx = 1\n[[w]]\n[w.sub]\nx = 2").is_ok());
+    }
+}
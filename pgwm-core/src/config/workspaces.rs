@@ -1,3 +1,6 @@
+use tiny_std::UnixStr;
+
+use crate::colors::RGBA;
 use crate::config::DefaultDraw;
 
 #[cfg_attr(test, derive(Eq, PartialEq))]
@@ -8,6 +11,18 @@ pub struct UserWorkspace {
     pub mapped_class_names: &'static [&'static str],
 
     pub default_draw: DefaultDraw,
+
+    /// Color painted on the root window behind this workspace while it's hosted on a monitor
+    pub background: Option<RGBA>,
+
+    /// Binary PPM image painted instead of `background`, scaled to cover the monitor
+    pub background_image: Option<&'static UnixStr>,
+
+    /// Padding around tiled windows on this workspace instead of `WINDOW_PADDING`
+    pub padding: Option<i16>,
+
+    /// Border width of windows on this workspace instead of `WINDOW_BORDER_WIDTH`
+    pub border_width: Option<u32>,
 }
 
 impl UserWorkspace {
@@ -20,6 +35,62 @@ impl UserWorkspace {
             name,
             mapped_class_names,
             default_draw,
+            background: None,
+            background_image: None,
+            padding: None,
+            border_width: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_background(self, background: RGBA) -> Self {
+        Self {
+            name: self.name,
+            mapped_class_names: self.mapped_class_names,
+            default_draw: self.default_draw,
+            background: Some(background),
+            background_image: self.background_image,
+            padding: self.padding,
+            border_width: self.border_width,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_background_image(self, path: &'static UnixStr) -> Self {
+        Self {
+            name: self.name,
+            mapped_class_names: self.mapped_class_names,
+            default_draw: self.default_draw,
+            background: self.background,
+            background_image: Some(path),
+            padding: self.padding,
+            border_width: self.border_width,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_padding(self, padding: i16) -> Self {
+        Self {
+            name: self.name,
+            mapped_class_names: self.mapped_class_names,
+            default_draw: self.default_draw,
+            background: self.background,
+            background_image: self.background_image,
+            padding: Some(padding),
+            border_width: self.border_width,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_border_width(self, border_width: u32) -> Self {
+        Self {
+            name: self.name,
+            mapped_class_names: self.mapped_class_names,
+            default_draw: self.default_draw,
+            background: self.background,
+            background_image: self.background_image,
+            padding: self.padding,
+            border_width: Some(border_width),
         }
     }
 }
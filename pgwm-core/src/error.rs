@@ -30,9 +30,16 @@ pub enum Error {
     #[cfg(feature = "status-bar")]
     ProcStatParseError,
     #[cfg(feature = "status-bar")]
+    LoadAvgParseError,
+    #[cfg(feature = "status-bar")]
     FontLoad(alloc::string::String),
     #[cfg(feature = "status-bar")]
     Time(alloc::string::String),
+    #[cfg(feature = "config-file")]
+    ConfigFile(alloc::string::String),
+    Ipc(&'static str),
+    Snapshot(&'static str),
+    Image(&'static str),
 }
 from_error!(StdError, Error, Syscall);
 #[cfg(feature = "status-bar")]
@@ -66,9 +73,16 @@ impl core::fmt::Display for Error {
             #[cfg(feature = "status-bar")]
             Error::ProcStatParseError => f.write_str("Failed to parse proc stat, could not find aggregate CPU"),
             #[cfg(feature = "status-bar")]
+            Error::LoadAvgParseError => f.write_str("Failed to parse load average, could not find all three averages"),
+            #[cfg(feature = "status-bar")]
             Error::Time(t) => f.write_fmt(format_args!("Failed to format time {t}")),
             #[cfg(feature = "status-bar")]
             Error::FontLoad(s) => f.write_fmt(format_args!("Error loading render {s}")),
+            #[cfg(feature = "config-file")]
+            Error::ConfigFile(s) => f.write_fmt(format_args!("Invalid config file, {s}")),
+            Error::Ipc(r) => f.write_fmt(format_args!("Invalid command, {r}")),
+            Error::Snapshot(r) => f.write_fmt(format_args!("Invalid state snapshot, {r}")),
+            Error::Image(r) => f.write_fmt(format_args!("Invalid image, {r}")),
             Error::Syscall(e) => f.write_fmt(format_args!("Syscall error {e}")),
         }
     }
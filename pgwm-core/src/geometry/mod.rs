@@ -82,3 +82,95 @@ impl Dimensions {
         x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.width
     }
 }
+
+/// Snaps a line starting at `start` with length `length` so that either its start or its end
+/// lines up with the closest of `edges`, if that edge is at most `threshold` pixels away.
+/// Returns the (possibly) adjusted start.
+#[must_use]
+pub fn snap_line(start: i16, length: i16, edges: &[i16], threshold: i16) -> i16 {
+    if threshold <= 0 {
+        return start;
+    }
+    let mut best: Option<(u16, i16)> = None;
+    for edge in edges {
+        for candidate in [*edge, edge.saturating_sub(length)] {
+            let distance = candidate.abs_diff(start);
+            if distance <= threshold as u16 && best.map_or(true, |(d, _)| distance < d) {
+                best = Some((distance, candidate));
+            }
+        }
+    }
+    best.map_or(start, |(_, snapped)| snapped)
+}
+
+/// Derives a scale factor from a monitor's width in pixels and millimeters, 96 DPI being a scale of 1.0.
+/// Rounded to the closest quarter, never below 1.0, and 1.0 if the server doesn't report a physical size.
+#[must_use]
+pub fn dpi_scale(width_px: u16, width_mm: u16) -> f32 {
+    if width_mm == 0 {
+        return 1.0;
+    }
+    let dpi = f32::from(width_px) * 25.4 / f32::from(width_mm);
+    let quarters = (dpi / 96.0 * 4.0 + 0.5) as u32;
+    (quarters as f32 / 4.0).max(1.0)
+}
+
+/// Scales a pixel value, rounding to the closest pixel
+#[must_use]
+pub fn scale_px(value: i16, scale: f32) -> i16 {
+    (f32::from(value) * scale + 0.5) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{dpi_scale, scale_px, snap_line};
+
+    #[test]
+    fn snap_line_leaves_far_away_lines() {
+        assert_eq!(100, snap_line(100, 50, &[0, 500], 10));
+        assert_eq!(100, snap_line(100, 50, &[], 10));
+    }
+
+    #[test]
+    fn snap_line_snaps_start_and_end() {
+        // Start close to 0
+        assert_eq!(0, snap_line(7, 50, &[0, 500], 10));
+        // End (455 + 50) close to 500
+        assert_eq!(450, snap_line(455, 50, &[0, 500], 10));
+    }
+
+    #[test]
+    fn snap_line_picks_closest_edge() {
+        // Start is 4 away from 4, end (58) is 2 away from 60
+        assert_eq!(10, snap_line(8, 50, &[4, 60], 10));
+    }
+
+    #[test]
+    fn snap_line_disabled_on_zero_threshold() {
+        assert_eq!(3, snap_line(3, 50, &[0], 0));
+    }
+
+    #[test]
+    fn dpi_scale_rounds_to_quarters() {
+        // 1920 px over 508 mm is 96 DPI
+        assert!((dpi_scale(1920, 508) - 1.0).abs() < f32::EPSILON);
+        // 3840 px over 508 mm is 192 DPI
+        assert!((dpi_scale(3840, 508) - 2.0).abs() < f32::EPSILON);
+        // 2560 px over 508 mm is 128 DPI, 1.33 rounds to 1.25
+        assert!((dpi_scale(2560, 508) - 1.25).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn dpi_scale_falls_back_to_one() {
+        assert!((dpi_scale(1920, 0) - 1.0).abs() < f32::EPSILON);
+        // Low DPI screens aren't scaled down
+        assert!((dpi_scale(1024, 508) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn scale_px_rounds() {
+        assert_eq!(20, scale_px(20, 1.0));
+        assert_eq!(25, scale_px(20, 1.25));
+        assert_eq!(4, scale_px(3, 1.25));
+    }
+}
@@ -0,0 +1,492 @@
+use alloc::string::String;
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::geometry::draw::Mode;
+use crate::geometry::layout::Layout;
+use crate::geometry::Dimensions;
+use crate::state::snapshot::{StateSnapshot, WorkspaceSnapshot};
+use crate::state::workspace::ArrangeKind;
+
+/// A command sent to the WM by setting the `_PGWM_COMMAND` property on the root window, like
+/// `xprop -root -f _PGWM_COMMAND 8u -set _PGWM_COMMAND 'notify "Build done" 5'`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IpcCommand<'a> {
+    /// `notify <text> [duration]`, shows the text in place of the focused monitor's window title.
+    /// The text can be quoted, the duration is in seconds, or in millis with an `ms` suffix.
+    Notify {
+        text: &'a str,
+        duration: Option<Duration>,
+    },
+    /// `get_tree`, `get_workspaces`, or `get_outputs`, answered on the root window's `_PGWM_REPLY`
+    Query(IpcQuery),
+    /// `move_to`, `move_by`, `resize_to`, or `resize_by` followed by a target and two numbers,
+    /// like `move_to class:Firefox 0 0`, floats the target windows and places them
+    Place {
+        target: IpcTarget<'a>,
+        placement: IpcPlacement,
+    },
+    /// `set_interval <position> <duration>`, changes how often the status check at the position runs.
+    /// The duration is in seconds, or in millis with an `ms` suffix
+    SetStatusInterval { position: usize, interval: Duration },
+}
+
+/// Windows that a command applies to, a window id in decimal or hex, or `class:<name>` for every window
+/// with that instance or class name
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IpcTarget<'a> {
+    Window(u32),
+    Class(&'a str),
+}
+
+/// Where to put a window, positions are on the root window and sizes exclude the border
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IpcPlacement {
+    MoveTo(i16, i16),
+    MoveBy(i16, i16),
+    ResizeTo(i16, i16),
+    ResizeBy(i16, i16),
+}
+
+impl IpcPlacement {
+    /// The new geometry of a window that's currently at `current`, never smaller than a pixel
+    #[must_use]
+    pub fn apply(self, current: Dimensions) -> Dimensions {
+        let mut placed = current;
+        match self {
+            IpcPlacement::MoveTo(x, y) => {
+                placed.x = x;
+                placed.y = y;
+            }
+            IpcPlacement::MoveBy(dx, dy) => {
+                placed.x = current.x.saturating_add(dx);
+                placed.y = current.y.saturating_add(dy);
+            }
+            IpcPlacement::ResizeTo(width, height) => {
+                placed.width = width;
+                placed.height = height;
+            }
+            IpcPlacement::ResizeBy(dw, dh) => {
+                placed.width = current.width.saturating_add(dw);
+                placed.height = current.height.saturating_add(dh);
+            }
+        }
+        placed.width = placed.width.max(1);
+        placed.height = placed.height.max(1);
+        placed
+    }
+}
+
+/// A read-only query about what's shown where, answered with JSON
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IpcQuery {
+    /// Every output and workspace, with the windows on each workspace
+    Tree,
+    Workspaces,
+    /// The monitors
+    Outputs,
+}
+
+impl<'a> IpcCommand<'a> {
+    pub fn parse(command: &'a str) -> Result<Self> {
+        let command = command.trim();
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, args)| (name, args.trim_start()));
+        match name {
+            "notify" => parse_notify(args),
+            "get_tree" => parse_query(IpcQuery::Tree, args),
+            "get_workspaces" => parse_query(IpcQuery::Workspaces, args),
+            "get_outputs" => parse_query(IpcQuery::Outputs, args),
+            "move_to" => parse_place(IpcPlacement::MoveTo, args),
+            "move_by" => parse_place(IpcPlacement::MoveBy, args),
+            "resize_to" => parse_place(IpcPlacement::ResizeTo, args),
+            "resize_by" => parse_place(IpcPlacement::ResizeBy, args),
+            "set_interval" => parse_status_interval(args),
+            "" => Err(Error::Ipc("empty command")),
+            _ => Err(Error::Ipc("unknown command")),
+        }
+    }
+}
+
+fn parse_notify(args: &str) -> Result<IpcCommand> {
+    let (text, duration) = if let Some(quoted) = args.strip_prefix('"') {
+        let (text, rest) = quoted
+            .split_once('"')
+            .ok_or(Error::Ipc("unterminated quote"))?;
+        let rest = rest.trim();
+        let duration = if rest.is_empty() {
+            None
+        } else {
+            Some(parse_duration(rest).ok_or(Error::Ipc("invalid duration"))?)
+        };
+        (text, duration)
+    } else {
+        // Unquoted, the last word is the duration if it reads as one
+        let last_word = args
+            .rsplit_once(char::is_whitespace)
+            .and_then(|(text, last)| Some((text.trim_end(), parse_duration(last)?)));
+        match last_word {
+            Some((text, duration)) => (text, Some(duration)),
+            None => (args, None),
+        }
+    };
+    if text.is_empty() {
+        return Err(Error::Ipc("notify needs a text"));
+    }
+    Ok(IpcCommand::Notify { text, duration })
+}
+
+fn parse_query(query: IpcQuery, args: &str) -> Result<IpcCommand<'static>> {
+    if args.is_empty() {
+        Ok(IpcCommand::Query(query))
+    } else {
+        Err(Error::Ipc("queries take no arguments"))
+    }
+}
+
+fn parse_place(to_placement: fn(i16, i16) -> IpcPlacement, args: &str) -> Result<IpcCommand> {
+    let mut words = args.split_whitespace();
+    let target = words.next().ok_or(Error::Ipc("placing needs a target"))?;
+    let target = match target.strip_prefix("class:") {
+        Some(class) if !class.is_empty() => IpcTarget::Class(class),
+        _ => {
+            let id = match target.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => target.parse(),
+            };
+            IpcTarget::Window(id.map_err(|_| Error::Ipc("invalid window id"))?)
+        }
+    };
+    let mut number = || -> Result<i16> {
+        words
+            .next()
+            .and_then(|word| word.parse().ok())
+            .ok_or(Error::Ipc("placing needs two numbers"))
+    };
+    let placement = to_placement(number()?, number()?);
+    if words.next().is_some() {
+        return Err(Error::Ipc("too many arguments"));
+    }
+    Ok(IpcCommand::Place { target, placement })
+}
+
+fn parse_status_interval(args: &str) -> Result<IpcCommand<'static>> {
+    let mut words = args.split_whitespace();
+    let position = words
+        .next()
+        .and_then(|word| word.parse().ok())
+        .ok_or(Error::Ipc("set_interval needs a check position"))?;
+    let interval = words
+        .next()
+        .and_then(parse_duration)
+        .filter(|interval| !interval.is_zero())
+        .ok_or(Error::Ipc("set_interval needs a non-zero duration"))?;
+    if words.next().is_some() {
+        return Err(Error::Ipc("too many arguments"));
+    }
+    Ok(IpcCommand::SetStatusInterval { position, interval })
+}
+
+impl IpcQuery {
+    #[must_use]
+    pub fn answer(self, snapshot: &StateSnapshot) -> String {
+        let mut out = String::new();
+        match self {
+            IpcQuery::Tree => {
+                let _ = write!(
+                    out,
+                    "{{\"focused_output\":{},\"focused_window\":",
+                    snapshot.focused_mon
+                );
+                write_opt(&mut out, snapshot.input_focus);
+                out.push_str(",\"outputs\":");
+                write_outputs(&mut out, snapshot);
+                out.push_str(",\"workspaces\":[");
+                for (num, ws) in snapshot.workspaces.iter().enumerate() {
+                    if num > 0 {
+                        out.push(',');
+                    }
+                    write_workspace(&mut out, snapshot, num, ws);
+                    out.push_str(",\"windows\":[");
+                    for (ind, win) in ws.windows.iter().enumerate() {
+                        if ind > 0 {
+                            out.push(',');
+                        }
+                        let _ = write!(out, "{{\"id\":{},\"class\":", win.window);
+                        write_str(&mut out, &win.class);
+                        out.push_str(",\"name\":");
+                        write_str(&mut out, &win.name);
+                        let floating = !matches!(win.arrange, ArrangeKind::NoFloat);
+                        let _ = write!(out, ",\"floating\":{floating},\"attached_to\":");
+                        write_opt(&mut out, win.attached_to);
+                        out.push_str(",\"pinned_output\":");
+                        write_opt(&mut out, win.pinned_monitor);
+                        out.push('}');
+                    }
+                    out.push_str("]}");
+                }
+                out.push_str("]}");
+            }
+            IpcQuery::Workspaces => {
+                out.push('[');
+                for (num, ws) in snapshot.workspaces.iter().enumerate() {
+                    if num > 0 {
+                        out.push(',');
+                    }
+                    write_workspace(&mut out, snapshot, num, ws);
+                    let _ = write!(out, ",\"windows\":{}}}", ws.windows.len());
+                }
+                out.push(']');
+            }
+            IpcQuery::Outputs => write_outputs(&mut out, snapshot),
+        }
+        out
+    }
+}
+
+fn write_outputs(out: &mut String, snapshot: &StateSnapshot) {
+    out.push('[');
+    for (num, mon) in snapshot.monitors.iter().enumerate() {
+        if num > 0 {
+            out.push(',');
+        }
+        let dims = mon.dimensions;
+        let _ = write!(
+            out,
+            "{{\"num\":{num},\"x\":{},\"y\":{},\"width\":{},\"height\":{}",
+            dims.x, dims.y, dims.width, dims.height
+        );
+        let focused = num == snapshot.focused_mon;
+        let _ = write!(out, ",\"focused\":{focused},\"workspace\":");
+        match snapshot.workspaces.get(mon.hosted_workspace) {
+            Some(ws) => write_str(out, &ws.name),
+            None => out.push_str("null"),
+        }
+        out.push('}');
+    }
+    out.push(']');
+}
+
+/// An unclosed object with what's shared between workspaces in the tree and the workspace list
+fn write_workspace(out: &mut String, snapshot: &StateSnapshot, num: usize, ws: &WorkspaceSnapshot) {
+    let output = snapshot
+        .monitors
+        .iter()
+        .position(|mon| mon.hosted_workspace == num);
+    let mode = match ws.draw_mode {
+        Mode::Tiled(Layout::LeftLeader) => "left_leader",
+        Mode::Tiled(Layout::CenterLeader) => "center_leader",
+        Mode::Tabbed(_) => "tabbed",
+        Mode::Fullscreen { .. } => "fullscreen",
+    };
+    let _ = write!(out, "{{\"num\":{num},\"name\":");
+    write_str(out, &ws.name);
+    out.push_str(",\"output\":");
+    write_opt(out, output);
+    let focused = output == Some(snapshot.focused_mon);
+    let _ = write!(out, ",\"focused\":{focused},\"mode\":\"{mode}\"");
+}
+
+fn write_opt<T: core::fmt::Display>(out: &mut String, val: Option<T>) {
+    match val {
+        Some(val) => {
+            let _ = write!(out, "{val}");
+        }
+        None => out.push_str("null"),
+    }
+}
+
+fn write_str(out: &mut String, val: &str) {
+    out.push('"');
+    for ch in val.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+fn parse_duration(duration: &str) -> Option<Duration> {
+    if let Some(millis) = duration.strip_suffix("ms") {
+        return millis.parse().ok().map(Duration::from_millis);
+    }
+    duration
+        .strip_suffix('s')
+        .unwrap_or(duration)
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+    use core::time::Duration;
+
+    use crate::geometry::draw::Mode;
+    use crate::geometry::layout::Layout;
+    use crate::geometry::Dimensions;
+    use crate::ipc::{IpcCommand, IpcPlacement, IpcQuery, IpcTarget};
+    use crate::state::snapshot::{
+        MonitorSnapshot, StateSnapshot, WindowSnapshot, WorkspaceSnapshot,
+    };
+    use crate::state::workspace::ArrangeKind;
+
+    fn notify(text: &str, duration: Option<Duration>) -> IpcCommand {
+        IpcCommand::Notify { text, duration }
+    }
+
+    #[test]
+    fn parses_notify() {
+        assert_eq!(
+            notify("Build done", None),
+            IpcCommand::parse("notify Build done").unwrap()
+        );
+        assert_eq!(
+            notify("Build done", Some(Duration::from_secs(5))),
+            IpcCommand::parse("  notify Build done 5\n").unwrap()
+        );
+        assert_eq!(
+            notify("Took 5", Some(Duration::from_millis(1500))),
+            IpcCommand::parse("notify \"Took 5\" 1500ms").unwrap()
+        );
+        assert_eq!(
+            notify("Took 5", None),
+            IpcCommand::parse("notify \"Took 5\"").unwrap()
+        );
+        assert_eq!(notify("5", None), IpcCommand::parse("notify 5").unwrap());
+    }
+
+    #[test]
+    fn answers_queries() {
+        assert_eq!(
+            IpcCommand::Query(IpcQuery::Tree),
+            IpcCommand::parse("get_tree").unwrap()
+        );
+        assert_eq!(
+            IpcCommand::Query(IpcQuery::Outputs),
+            IpcCommand::parse(" get_outputs\n").unwrap()
+        );
+        let snapshot = StateSnapshot {
+            focused_mon: 0,
+            input_focus: Some(7),
+            monitors: vec![MonitorSnapshot {
+                dimensions: Dimensions::new(1920, 1080, 0, 0),
+                hosted_workspace: 1,
+                last_focus: Some(7),
+                show_bar: true,
+            }],
+            workspaces: vec![
+                WorkspaceSnapshot {
+                    name: String::from("web"),
+                    draw_mode: Mode::Tabbed(0),
+                    windows: vec![],
+                },
+                WorkspaceSnapshot {
+                    name: String::from("dev"),
+                    draw_mode: Mode::Tiled(Layout::LeftLeader),
+                    windows: vec![WindowSnapshot {
+                        window: 7,
+                        attached_to: None,
+                        arrange: ArrangeKind::NoFloat,
+                        pinned_monitor: None,
+                        class: String::from("xterm"),
+                        name: String::from("vim \"main.rs\""),
+                    }],
+                },
+            ],
+        };
+        assert_eq!(
+            r#"[{"num":0,"x":0,"y":0,"width":1920,"height":1080,"focused":true,"workspace":"dev"}]"#,
+            IpcQuery::Outputs.answer(&snapshot)
+        );
+        assert_eq!(
+            concat!(
+                r#"[{"num":0,"name":"web","output":null,"focused":false,"mode":"tabbed","windows":0},"#,
+                r#"{"num":1,"name":"dev","output":0,"focused":true,"mode":"left_leader","windows":1}]"#
+            ),
+            IpcQuery::Workspaces.answer(&snapshot)
+        );
+        let tree = IpcQuery::Tree.answer(&snapshot);
+        assert!(tree.starts_with(r#"{"focused_output":0,"focused_window":7,"outputs":[{"#));
+        assert!(tree.ends_with(concat!(
+            r#""mode":"left_leader","windows":[{"id":7,"class":"xterm","name":"vim \"main.rs\"","#,
+            r#""floating":false,"attached_to":null,"pinned_output":null}]}]}"#
+        )));
+    }
+
+    #[test]
+    fn parses_placements() {
+        assert_eq!(
+            IpcCommand::Place {
+                target: IpcTarget::Window(0x1a0_0003),
+                placement: IpcPlacement::MoveTo(0, -20),
+            },
+            IpcCommand::parse("move_to 0x1a00003 0 -20").unwrap()
+        );
+        assert_eq!(
+            IpcCommand::Place {
+                target: IpcTarget::Class("Firefox"),
+                placement: IpcPlacement::ResizeBy(-100, 50),
+            },
+            IpcCommand::parse("resize_by class:Firefox -100 50").unwrap()
+        );
+        let current = Dimensions::new(400, 300, 10, 20);
+        assert_eq!(
+            Dimensions::new(400, 300, 0, 40),
+            IpcPlacement::MoveBy(-10, 20).apply(current)
+        );
+        assert_eq!(
+            Dimensions::new(800, 600, 10, 20),
+            IpcPlacement::ResizeTo(800, 600).apply(current)
+        );
+        assert_eq!(
+            Dimensions::new(1, 300, 10, 20),
+            IpcPlacement::ResizeBy(-500, 0).apply(current)
+        );
+    }
+
+    #[test]
+    fn parses_status_intervals() {
+        assert_eq!(
+            IpcCommand::SetStatusInterval {
+                position: 2,
+                interval: Duration::from_millis(500),
+            },
+            IpcCommand::parse("set_interval 2 500ms").unwrap()
+        );
+        assert_eq!(
+            IpcCommand::SetStatusInterval {
+                position: 0,
+                interval: Duration::from_secs(30),
+            },
+            IpcCommand::parse("set_interval 0 30").unwrap()
+        );
+        assert!(IpcCommand::parse("set_interval 0").is_err());
+        assert!(IpcCommand::parse("set_interval 0 0ms").is_err());
+        assert!(IpcCommand::parse("set_interval cpu 5").is_err());
+        assert!(IpcCommand::parse("set_interval 0 5 5").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_commands() {
+        assert!(IpcCommand::parse("").is_err());
+        assert!(IpcCommand::parse("focus 1").is_err());
+        assert!(IpcCommand::parse("notify").is_err());
+        assert!(IpcCommand::parse("notify \"unterminated").is_err());
+        assert!(IpcCommand::parse("notify \"text\" soon").is_err());
+        assert!(IpcCommand::parse("get_tree now").is_err());
+        assert!(IpcCommand::parse("move_to class:xterm 10").is_err());
+        assert!(IpcCommand::parse("move_to xterm 10 10").is_err());
+        assert!(IpcCommand::parse("resize_to 12 10 10 10").is_err());
+    }
+}
@@ -23,6 +23,7 @@ pub mod colors;
 pub mod config;
 pub mod error;
 pub mod geometry;
+pub mod ipc;
 pub mod render;
 pub mod state;
 #[cfg(feature = "status-bar")]
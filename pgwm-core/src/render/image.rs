@@ -0,0 +1,180 @@
+use alloc::vec::Vec;
+
+use crate::error::{Error, Result};
+
+/// An image decoded to 32 bit `BGRX` pixels, the layout of a `ZPixmap` on a 24 or 32 bit deep visual
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Image {
+    pub width: u16,
+    pub height: u16,
+    /// Rows of pixels, 4 bytes each
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Decode a binary PPM (`P6`) image with 8 bit channels,
+    /// anything can be converted to one with `magick wallpaper.jpg wallpaper.ppm`
+    pub fn decode_ppm(bytes: &[u8]) -> Result<Self> {
+        let mut header = PpmHeader { bytes, pos: 0 };
+        if header.next_token() != Some(&b"P6"[..]) {
+            return Err(Error::Image("not a binary PPM image"));
+        }
+        let width = header.next_number()?;
+        let height = header.next_number()?;
+        if header.next_number()? != 255 {
+            return Err(Error::Image("only 8 bit channels are supported"));
+        }
+        // A single whitespace separates the header from the pixels
+        let data = bytes
+            .get(header.pos + 1..)
+            .ok_or(Error::Image("missing pixels"))?;
+        let num_pixels = width as usize * height as usize;
+        if width == 0 || height == 0 || data.len() < num_pixels * 3 {
+            return Err(Error::Image("missing pixels"));
+        }
+        let mut pixels = Vec::with_capacity(num_pixels * 4);
+        for rgb in data.chunks_exact(3).take(num_pixels) {
+            pixels.extend_from_slice(&[rgb[2], rgb[1], rgb[0], 0]);
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Scaled to cover `width` x `height`, keeping the aspect ratio and cropping
+    /// what falls outside evenly on both sides
+    #[must_use]
+    pub fn cover(&self, width: u16, height: u16) -> Self {
+        let scale = f32::max(
+            width as f32 / self.width as f32,
+            height as f32 / self.height as f32,
+        );
+        let source_offsets = |len: u16, source_len: u16| -> Vec<usize> {
+            let crop = (source_len as f32 * scale - len as f32) / 2.0;
+            (0..len)
+                .map(|ind| (((ind as f32 + crop) / scale) as usize).min(source_len as usize - 1))
+                .collect()
+        };
+        let source_columns = source_offsets(width, self.width);
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for source_row in source_offsets(height, self.height) {
+            let row_start = source_row * self.width as usize * 4;
+            for source_column in &source_columns {
+                let start = row_start + source_column * 4;
+                pixels.extend_from_slice(&self.pixels[start..start + 4]);
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+struct PpmHeader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PpmHeader<'a> {
+    /// The next whitespace separated token, skipping `#` comments
+    fn next_token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.bytes.get(self.pos)? {
+                b'#' => {
+                    while self.bytes.get(self.pos).is_some_and(|byte| *byte != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Some(&self.bytes[start..self.pos])
+    }
+
+    fn next_number(&mut self) -> Result<u16> {
+        self.next_token()
+            .and_then(|token| core::str::from_utf8(token).ok())
+            .and_then(|token| token.parse().ok())
+            .ok_or(Error::Image("invalid header"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::render::image::Image;
+
+    fn ppm(header: &str, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(pixels);
+        bytes
+    }
+
+    #[test]
+    fn decodes_ppm() {
+        let image = Image::decode_ppm(&ppm(
+            "P6\n# Made by hand\n2 1\n255\n",
+            &[255, 0, 0, 1, 2, 3],
+        ))
+        .unwrap();
+        assert_eq!(2, image.width);
+        assert_eq!(1, image.height);
+        assert_eq!(vec![0, 0, 255, 0, 3, 2, 1, 0], image.pixels);
+        // Pixels may look like whitespace
+        let image = Image::decode_ppm(&ppm("P6 1 1 255\n", b"\n\n\n")).unwrap();
+        assert_eq!(vec![b'\n', b'\n', b'\n', 0], image.pixels);
+    }
+
+    #[test]
+    fn rejects_invalid_ppm() {
+        assert!(Image::decode_ppm(&ppm("P3\n1 1\n255\n", b"1 2 3")).is_err());
+        assert!(Image::decode_ppm(&ppm("P6\n1 1\n65535\n", &[0; 6])).is_err());
+        assert!(Image::decode_ppm(&ppm("P6\n2 2\n255\n", &[0; 9])).is_err());
+        assert!(Image::decode_ppm(&ppm("P6\n0 0\n255\n", &[])).is_err());
+        assert!(Image::decode_ppm(b"P6\n1 1\n255").is_err());
+        assert!(Image::decode_ppm(b"").is_err());
+    }
+
+    #[test]
+    fn covers_keeping_aspect_ratio() {
+        // Four pixels in a row, colored by their position
+        let image = Image {
+            width: 4,
+            height: 1,
+            pixels: (0..4).flat_map(|ind| [ind, 0, 0, 0]).collect(),
+        };
+        // Doubled in height, the outermost columns are cropped
+        let covered = image.cover(4, 2);
+        assert_eq!(4, covered.width);
+        assert_eq!(2, covered.height);
+        let first_row: Vec<u8> = covered.pixels[..16].iter().step_by(4).copied().collect();
+        assert_eq!(vec![1, 1, 2, 2], first_row);
+        assert_eq!(covered.pixels[..16], covered.pixels[16..]);
+        // Same height, cropped to the middle
+        let covered = image.cover(2, 1);
+        assert_eq!(vec![1, 0, 0, 0, 2, 0, 0, 0], covered.pixels);
+        // Halved, every other column is left out
+        let two_rows = Image {
+            width: 4,
+            height: 2,
+            pixels: [image.pixels.as_slice(), image.pixels.as_slice()].concat(),
+        };
+        let covered = two_rows.cover(2, 1);
+        assert_eq!(vec![0, 0, 0, 0, 2, 0, 0, 0], covered.pixels);
+    }
+}
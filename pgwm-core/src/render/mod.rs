@@ -1,6 +1,8 @@
 use xcb_rust_protocol::proto::render::{Directformat, Pictformat, Picture};
 use xcb_rust_protocol::proto::xproto::{Drawable, Visualid};
 
+pub mod image;
+
 pub struct RenderPicture {
     pub drawable: Drawable,
     pub picture: Picture,
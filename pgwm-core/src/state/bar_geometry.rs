@@ -1,16 +1,19 @@
 use alloc::vec::Vec;
 
 use crate::config::mouse_map::MouseTarget;
-use crate::config::_WM_NAME_LIMIT;
+use crate::config::{
+    Action, BarAlignment, BarSection, URGENT_SECTION_ICON, _URGENT_DISPLAY_LIMIT, _WM_NAME_LIMIT,
+};
 #[cfg(feature = "status-bar")]
 use crate::config::{
-    STATUS_CHECKS, _STATUS_BAR_CHECK_CONTENT_LIMIT, _STATUS_BAR_CHECK_SEP, _STATUS_BAR_FIRST_SEP,
-    _STATUS_BAR_TOTAL_LENGTH_LIMIT,
+    _STATUS_BAR_CHECK_CONTENT_LIMIT, _STATUS_BAR_CHECK_SEP, _STATUS_BAR_FIRST_SEP,
+    _STATUS_BAR_TOTAL_LENGTH_LIMIT, _STATUS_CHECK_LIMIT,
 };
 use crate::geometry::Line;
 
 pub struct BarGeometry {
     pub workspace: WorkspaceSection,
+    pub urgent: UrgentSection,
     pub shortcuts: ShortcutSection,
     #[cfg(feature = "status-bar")]
     pub status: StatusSection,
@@ -23,6 +26,7 @@ impl BarGeometry {
         let hit = self
             .workspace
             .hit_component(x)
+            .or_else(|| self.urgent.hit_component(x))
             .or_else(|| self.shortcuts.hit_component(x));
         #[cfg(feature = "status-bar")]
         {
@@ -44,31 +48,51 @@ impl BarGeometry {
         }
     }
 
+    /// Lays out the sections according to `layout`, sections are expected to start at 0.
+    /// The window title takes up the largest space left between the laid out sections.
     #[must_use]
     pub fn new(
         mon_width: i16,
-        workspace: WorkspaceSection,
-        shortcuts: ShortcutSection,
-        #[cfg(feature = "status-bar")] status: StatusSection,
+        layout: &[(BarSection, BarAlignment)],
+        mut workspace: WorkspaceSection,
+        mut urgent: UrgentSection,
+        mut shortcuts: ShortcutSection,
+        #[cfg(feature = "status-bar")] mut status: StatusSection,
     ) -> Self {
-        #[cfg(feature = "status-bar")]
-        let title_width = mon_width
-            - workspace.position.length
-            - shortcuts.position.length
-            - status.position.length;
-        #[cfg(not(feature = "status-bar"))]
-        let title_width = mon_width - workspace.position.length - shortcuts.position.length;
-
+        let mut sections = Vec::with_capacity(layout.len());
+        for (section, alignment) in layout {
+            let length = match section {
+                BarSection::Workspaces => workspace.position.length,
+                BarSection::Urgent => urgent.position.length,
+                BarSection::Shortcuts => shortcuts.position.length,
+                #[cfg(feature = "status-bar")]
+                BarSection::Status => status.position.length,
+                #[cfg(not(feature = "status-bar"))]
+                BarSection::Status => 0,
+            };
+            sections.push((*alignment, length));
+        }
+        let positions = layout_sections(mon_width, &sections);
+        for ((section, _), position) in layout.iter().zip(positions.iter()) {
+            match section {
+                BarSection::Workspaces => workspace.shift_to(position.start),
+                BarSection::Urgent => urgent.position.start = position.start,
+                BarSection::Shortcuts => shortcuts.shift_to(position.start),
+                #[cfg(feature = "status-bar")]
+                BarSection::Status => status.shift_to(position.start),
+                #[cfg(not(feature = "status-bar"))]
+                BarSection::Status => {}
+            }
+        }
+        let title_position = largest_gap(mon_width, &positions);
         Self {
             window_title_section: WindowTitleSection {
-                position: Line::new(
-                    workspace.position.start + workspace.position.length,
-                    title_width,
-                ),
+                position: title_position,
                 display: heapless::String::try_from("pgwm").unwrap(),
-                last_draw_width: title_width, // Set last draw to full with so initial draw, paints the entire section
+                last_draw_width: title_position.length, // Set last draw to full with so initial draw, paints the entire section
             },
             workspace,
+            urgent,
             shortcuts,
             #[cfg(feature = "status-bar")]
             status,
@@ -76,6 +100,52 @@ impl BarGeometry {
     }
 }
 
+/// Positions fixed width sections on a bar, returned in the same order as they were passed.
+/// Sections with the same alignment are placed next to each other in the order they're passed,
+/// left aligned from the left edge, right aligned ending at the right edge, and centered around the middle.
+#[must_use]
+pub fn layout_sections(bar_width: i16, sections: &[(BarAlignment, i16)]) -> Vec<Line> {
+    let total_length = |alignment: BarAlignment| -> i16 {
+        sections
+            .iter()
+            .filter(|(a, _)| *a == alignment)
+            .map(|(_, length)| *length)
+            .sum()
+    };
+    let mut left_offset = 0;
+    let mut center_offset = (bar_width - total_length(BarAlignment::Center)) / 2;
+    let mut right_offset = bar_width - total_length(BarAlignment::Right);
+    let mut positions = Vec::with_capacity(sections.len());
+    for (alignment, length) in sections {
+        let offset = match alignment {
+            BarAlignment::Left => &mut left_offset,
+            BarAlignment::Center => &mut center_offset,
+            BarAlignment::Right => &mut right_offset,
+        };
+        positions.push(Line::new(*offset, *length));
+        *offset += length;
+    }
+    positions
+}
+
+/// The largest stretch of the bar not covered by any of the sections
+#[must_use]
+pub fn largest_gap(bar_width: i16, sections: &[Line]) -> Line {
+    let mut occupied: Vec<Line> = sections.iter().filter(|l| l.length > 0).copied().collect();
+    occupied.sort_unstable_by_key(|l| l.start);
+    // The end of the bar closes the last gap
+    occupied.push(Line::new(bar_width, 0));
+    let mut best = Line::new(0, 0);
+    let mut gap_start = 0;
+    for line in &occupied {
+        if line.start - gap_start > best.length {
+            best = Line::new(gap_start, line.start - gap_start);
+        }
+        gap_start = gap_start.max(line.start + line.length);
+    }
+    best
+}
+
 #[derive(Clone)]
 pub struct WindowTitleSection {
     pub position: Line,
@@ -83,9 +153,44 @@ pub struct WindowTitleSection {
     pub last_draw_width: i16,
 }
 
+pub struct UrgentSection {
+    pub position: Line,
+}
+
+impl UrgentSection {
+    fn hit_component(&self, x: i16) -> Option<MouseTarget> {
+        (self.position.length > 0 && self.position.contains(x))
+            .then_some(MouseTarget::UrgentSection)
+    }
+}
+
+/// What the urgent section shows, empty if there are no urgent windows
+#[must_use]
+pub fn format_urgent<'a>(
+    num_urgent: usize,
+    workspace_names: impl Iterator<Item = &'a str>,
+) -> heapless::String<_URGENT_DISPLAY_LIMIT> {
+    let mut display = heapless::String::new();
+    if num_urgent == 0 {
+        return display;
+    }
+    let _ = core::fmt::write(
+        &mut display,
+        format_args!("{URGENT_SECTION_ICON} {num_urgent}"),
+    );
+    for name in workspace_names {
+        if core::fmt::write(&mut display, format_args!(" {name}")).is_err() {
+            break;
+        }
+    }
+    display
+}
+
 pub struct ShortcutSection {
     pub position: Line,
     pub components: Vec<ShortcutComponent>,
+    /// The shortcut whose hover text is currently drawn over the window title
+    pub hovered: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -93,9 +198,19 @@ pub struct ShortcutComponent {
     pub position: Line,
     pub write_offset: i16,
     pub text: &'static str,
+    pub action: Action,
+    pub hover_text: Option<&'static str>,
 }
 
 impl ShortcutSection {
+    fn shift_to(&mut self, start: i16) {
+        let diff = start - self.position.start;
+        self.position.start = start;
+        for component in &mut self.components {
+            component.position.start += diff;
+        }
+    }
+
     fn hit_component(&self, x: i16) -> Option<MouseTarget> {
         (x >= self.position.start && x <= self.position.start + self.position.length)
             .then(|| {
@@ -110,6 +225,19 @@ impl ShortcutSection {
             })
             .flatten()
     }
+
+    /// The index of the shortcut at `x` if it has a hover text to show
+    #[must_use]
+    pub fn hovered_at(&self, x: i16) -> Option<usize> {
+        match self.hit_component(x) {
+            Some(MouseTarget::ShortcutComponent(ind))
+                if self.components[ind].hover_text.is_some() =>
+            {
+                Some(ind)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[cfg(feature = "status-bar")]
@@ -117,22 +245,16 @@ pub struct StatusSection {
     pub position: Line,
     pub first_sep_len: i16,
     pub sep_len: i16,
-    pub components: heapless::Vec<StatusComponent, { STATUS_CHECKS.len() }>,
+    pub components: heapless::Vec<StatusComponent, _STATUS_CHECK_LIMIT>,
 }
 
 #[cfg(feature = "status-bar")]
 impl StatusSection {
+    /// Creates a section starting at 0, it's moved into place when the [`BarGeometry`] is created
     #[must_use]
-    pub fn new(
-        mon_width: i16,
-        right_offset: i16,
-        check_lengths: &[i16],
-        sep_len: i16,
-        first_sep_len: i16,
-    ) -> Self {
+    pub fn new(check_lengths: &[i16], sep_len: i16, first_sep_len: i16) -> Self {
         let mut total_length = 0;
-        let mut corrected_lengths: heapless::Vec<i16, { STATUS_CHECKS.len() }> =
-            heapless::Vec::new();
+        let mut corrected_lengths: heapless::Vec<i16, _STATUS_CHECK_LIMIT> = heapless::Vec::new();
         for (ind, check) in check_lengths.iter().enumerate() {
             let mut cur_length = 0;
             if ind == 0 {
@@ -146,7 +268,7 @@ impl StatusSection {
             total_length += cur_length;
         }
         let mut components = heapless::Vec::new();
-        let start = mon_width - right_offset - total_length;
+        let start = 0;
         let mut offset = 0;
         for length in corrected_lengths {
             let _ = components.push(StatusComponent {
@@ -155,6 +277,7 @@ impl StatusSection {
                     length,
                 },
                 display: heapless::String::default(),
+                alert: false,
             });
             offset += length;
         }
@@ -170,11 +293,22 @@ impl StatusSection {
         }
     }
 
+    fn shift_to(&mut self, start: i16) {
+        let diff = start - self.position.start;
+        self.position.start = start;
+        for component in &mut self.components {
+            component.position.start += diff;
+        }
+    }
+
+    /// Updates a component's content and whether it's drawn as an alert, returning what to draw and where,
+    /// or `None` if it's already displaying that content that way
     pub fn update_and_get_section_line(
         &mut self,
         new_content: heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>,
         new_component_ind: usize,
-    ) -> (heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>, Line) {
+        alert: bool,
+    ) -> Option<(heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>, Line)> {
         let content = if new_component_ind == 0 {
             crate::format_heapless!("{_STATUS_BAR_FIRST_SEP}{new_content}")
         } else if new_component_ind == self.components.len() - 1 {
@@ -183,8 +317,12 @@ impl StatusSection {
             crate::format_heapless!("{_STATUS_BAR_CHECK_SEP}{new_content}")
         };
         let component = &mut self.components[new_component_ind];
+        if component.display == content && component.alert == alert {
+            return None;
+        }
         component.display = content.clone();
-        (content, component.position)
+        component.alert = alert;
+        Some((content, component.position))
     }
 
     #[must_use]
@@ -219,14 +357,46 @@ impl StatusSection {
 pub struct StatusComponent {
     pub position: Line,
     pub display: heapless::String<_STATUS_BAR_CHECK_CONTENT_LIMIT>,
+    /// Drawn in the status bar's alert color instead of its text color
+    pub alert: bool,
 }
 
 pub struct WorkspaceSection {
     pub position: Line,
     pub components: Vec<FixedDisplayComponent>,
+    /// Background pixel each cell was last drawn with, `None` if it needs to be drawn
+    pub drawn_backgrounds: Vec<Option<u32>>,
 }
 
 impl WorkspaceSection {
+    /// Records that a cell is drawn with a background, returns false if it already was
+    /// and drawing it again can be skipped
+    pub fn set_drawn_background(&mut self, ws_ind: usize, bg_pixel: u32) -> bool {
+        match self.drawn_backgrounds.get_mut(ws_ind) {
+            Some(drawn) if *drawn == Some(bg_pixel) => false,
+            Some(drawn) => {
+                *drawn = Some(bg_pixel);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Makes every cell get drawn on the next draw, needed when the whole bar is redrawn
+    pub fn invalidate(&mut self) {
+        for drawn in &mut self.drawn_backgrounds {
+            *drawn = None;
+        }
+    }
+
+    fn shift_to(&mut self, start: i16) {
+        let diff = start - self.position.start;
+        self.position.start = start;
+        for component in &mut self.components {
+            component.position.start += diff;
+        }
+    }
+
     fn hit_component(&self, x: i16) -> Option<MouseTarget> {
         (x >= self.position.start && x <= self.position.start + self.position.length)
             .then(|| {
@@ -248,3 +418,124 @@ pub struct FixedDisplayComponent {
     pub write_offset: i16,
     pub text: &'static str,
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::config::{Action, BarAlignment, URGENT_SECTION_ICON};
+    use crate::geometry::Line;
+    #[cfg(feature = "status-bar")]
+    use crate::state::bar_geometry::StatusSection;
+    use crate::state::bar_geometry::{
+        format_urgent, largest_gap, layout_sections, ShortcutComponent, ShortcutSection,
+        WorkspaceSection,
+    };
+
+    #[test]
+    fn lays_out_left_center_and_right() {
+        let positions = layout_sections(
+            100,
+            &[
+                (BarAlignment::Right, 10),
+                (BarAlignment::Left, 20),
+                (BarAlignment::Center, 10),
+                (BarAlignment::Right, 5),
+                (BarAlignment::Left, 5),
+            ],
+        );
+        assert_eq!(
+            vec![
+                Line::new(85, 10),
+                Line::new(0, 20),
+                Line::new(45, 10),
+                Line::new(95, 5),
+                Line::new(20, 5),
+            ],
+            positions
+        );
+    }
+
+    #[test]
+    fn finds_largest_gap() {
+        // Left 0..20, center 45..55, right 85..100
+        let sections = [Line::new(85, 15), Line::new(0, 20), Line::new(45, 10)];
+        assert_eq!(Line::new(55, 30), largest_gap(100, &sections));
+        // Only a right section, everything to the left of it is free
+        assert_eq!(Line::new(0, 85), largest_gap(100, &[Line::new(85, 15)]));
+        // Empty sections don't take up space
+        assert_eq!(
+            Line::new(20, 80),
+            largest_gap(100, &[Line::new(0, 20), Line::new(60, 0)])
+        );
+    }
+
+    #[test]
+    fn hovers_shortcuts_with_text() {
+        let shortcut = |start, hover_text| ShortcutComponent {
+            position: Line::new(start, 10),
+            write_offset: 0,
+            text: "",
+            action: Action::Quit,
+            hover_text,
+        };
+        let mut section = ShortcutSection {
+            position: Line::new(0, 20),
+            components: vec![shortcut(0, Some("Quit")), shortcut(11, None)],
+            hovered: None,
+        };
+        section.shift_to(50);
+        assert_eq!(Some(0), section.hovered_at(55));
+        // Shortcuts without text don't hover
+        assert_eq!(None, section.hovered_at(65));
+        assert_eq!(None, section.hovered_at(5));
+    }
+
+    #[test]
+    fn skips_drawn_workspace_backgrounds() {
+        let mut section = WorkspaceSection {
+            position: Line::new(0, 0),
+            components: vec![],
+            drawn_backgrounds: vec![None; 2],
+        };
+        assert!(section.set_drawn_background(0, 5));
+        assert!(!section.set_drawn_background(0, 5));
+        assert!(section.set_drawn_background(0, 6));
+        assert!(section.set_drawn_background(1, 6));
+        section.invalidate();
+        assert!(section.set_drawn_background(0, 6));
+        // Unknown cells are always drawn
+        assert!(section.set_drawn_background(2, 6));
+        assert!(section.set_drawn_background(2, 6));
+    }
+
+    #[cfg(feature = "status-bar")]
+    #[test]
+    fn skips_unchanged_status() {
+        let mut section = StatusSection::new(&[10, 10], 2, 1);
+        let content = heapless::String::try_from("50%").unwrap();
+        assert!(section
+            .update_and_get_section_line(content.clone(), 1, false)
+            .is_some());
+        assert!(section
+            .update_and_get_section_line(content.clone(), 1, false)
+            .is_none());
+        // Same content turning into an alert is drawn
+        assert!(section
+            .update_and_get_section_line(content.clone(), 1, true)
+            .is_some());
+        // Same content in another component is drawn
+        assert!(section
+            .update_and_get_section_line(content, 0, false)
+            .is_some());
+    }
+
+    #[test]
+    fn formats_urgent() {
+        assert_eq!("", format_urgent(0, ["a"].into_iter()));
+        assert_eq!(
+            alloc::format!("{URGENT_SECTION_ICON} 3 a c"),
+            format_urgent(3, ["a", "c"].into_iter()).as_str()
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::vec::Vec;
+
+use x11_keysyms::{XK_Hyper_R, XK_Mode_switch, XK_Shift_L, XK_1, XK_9};
+use xcb_rust_protocol::proto::xproto::Window;
+
+use crate::config::key_map::KeysymTable;
+use crate::geometry::Dimensions;
+
+/// The most windows hinted at once, one for each of the digit keys 1 to 9
+pub const MAX_WINDOW_HINTS: usize = 9;
+
+const HINT_LABELS: [&str; MAX_WINDOW_HINTS] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+/// Shown window hints, a numbered badge over each visible window that can be focused with its digit
+/// for as long as the modifier that showed them is held
+pub struct WindowHints {
+    targets: Vec<HintTarget>,
+    keysyms: KeysymTable,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct HintTarget {
+    pub window: Window,
+    pub dimensions: Dimensions,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HintEvent {
+    Ignore,
+    Close,
+    Select(Window),
+}
+
+impl WindowHints {
+    /// Hints for the first [`MAX_WINDOW_HINTS`] targets, the rest don't get a digit
+    #[must_use]
+    pub fn new(mut targets: Vec<HintTarget>, keysyms: KeysymTable) -> Self {
+        targets.truncate(MAX_WINDOW_HINTS);
+        Self { targets, keysyms }
+    }
+
+    /// Each target with the label drawn over it
+    pub fn labelled(&self) -> impl Iterator<Item = (&'static str, &HintTarget)> {
+        HINT_LABELS.iter().copied().zip(&self.targets)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Handle a pressed key, digits are resolved unshifted so that they work with shift held too
+    #[must_use]
+    pub fn handle_key_press(&self, code: u8) -> HintEvent {
+        self.keysyms
+            .keysym(code, false)
+            .map_or(HintEvent::Close, |sym| self.handle_pressed_keysym(sym))
+    }
+
+    /// A digit selects its window, modifiers are ignored since they're part of holding the mod key,
+    /// any other key closes the hints
+    #[must_use]
+    pub fn handle_pressed_keysym(&self, keysym: u32) -> HintEvent {
+        match keysym {
+            XK_1..=XK_9 => self
+                .targets
+                .get((keysym - XK_1) as usize)
+                .map_or(HintEvent::Ignore, |target| HintEvent::Select(target.window)),
+            sym if is_modifier_keysym(sym) => HintEvent::Ignore,
+            _ => HintEvent::Close,
+        }
+    }
+
+    /// Releasing a modifier closes the hints
+    #[must_use]
+    pub fn handle_key_release(&self, code: u8) -> HintEvent {
+        if self
+            .keysyms
+            .keysym(code, false)
+            .is_some_and(is_modifier_keysym)
+        {
+            HintEvent::Close
+        } else {
+            HintEvent::Ignore
+        }
+    }
+}
+
+fn is_modifier_keysym(keysym: u32) -> bool {
+    (XK_Shift_L..=XK_Hyper_R).contains(&keysym) || keysym == XK_Mode_switch
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use x11_keysyms::{XK_Escape, XK_Shift_L, XK_Super_L, XK_a, XK_1, XK_2, XK_3};
+
+    use crate::config::key_map::KeysymTable;
+    use crate::geometry::Dimensions;
+    use crate::state::hints::{HintEvent, HintTarget, WindowHints, MAX_WINDOW_HINTS};
+
+    fn hints(num_targets: u32) -> WindowHints {
+        let targets = (0..num_targets)
+            .map(|window| HintTarget {
+                window,
+                dimensions: Dimensions::new(100, 100, 0, 0),
+            })
+            .collect();
+        // Keycode 8 is `a`, 9 is `1`, 10 is left super
+        WindowHints::new(
+            targets,
+            KeysymTable::new(vec![XK_a, XK_1, XK_Super_L], 1, 8),
+        )
+    }
+
+    #[test]
+    fn digits_select_hinted_windows() {
+        let hints = hints(2);
+        assert_eq!(HintEvent::Select(0), hints.handle_pressed_keysym(XK_1));
+        assert_eq!(HintEvent::Select(1), hints.handle_pressed_keysym(XK_2));
+        assert_eq!(HintEvent::Ignore, hints.handle_pressed_keysym(XK_3));
+        assert_eq!(HintEvent::Ignore, hints.handle_pressed_keysym(XK_Shift_L));
+        assert_eq!(HintEvent::Close, hints.handle_pressed_keysym(XK_Escape));
+        assert_eq!(HintEvent::Select(0), hints.handle_key_press(9));
+        assert_eq!(HintEvent::Close, hints.handle_key_press(8));
+    }
+
+    #[test]
+    fn modifier_release_closes() {
+        let hints = hints(1);
+        assert_eq!(HintEvent::Ignore, hints.handle_key_release(8));
+        assert_eq!(HintEvent::Ignore, hints.handle_key_release(9));
+        assert_eq!(HintEvent::Close, hints.handle_key_release(10));
+    }
+
+    #[test]
+    fn labels_at_most_nine_windows() {
+        let hints = hints(12);
+        assert_eq!(MAX_WINDOW_HINTS, hints.len());
+        let labels = hints
+            .labelled()
+            .map(|(label, target)| (label, target.window))
+            .collect::<Vec<_>>();
+        assert_eq!(("1", 0), labels[0]);
+        assert_eq!(("9", 8), labels[8]);
+    }
+}
@@ -0,0 +1,193 @@
+use core::fmt::Formatter;
+
+use smallmap::Map;
+use xcb_rust_protocol::proto::xproto::Window;
+
+use crate::geometry::Dimensions;
+use crate::state::State;
+
+/// An assumption about [`State`] that doesn't hold, any of these is a bug in the WM
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InvariantViolation {
+    /// A window is listed in more than one workspace, or more than once in the same workspace
+    DuplicateWindow {
+        window: Window,
+        first_ws: usize,
+        second_ws: usize,
+    },
+    /// A window listed in a workspace is indexed to another workspace, or not indexed at all
+    MisindexedWindow {
+        window: Window,
+        ws_ind: usize,
+        indexed_ws: Option<usize>,
+    },
+    /// A window is indexed to a workspace that doesn't list it
+    MissingWindow { window: Window, indexed_ws: usize },
+    FocusedMonitorOutOfRange {
+        focused_mon: usize,
+        num_monitors: usize,
+    },
+    HostedWorkspaceOutOfRange {
+        mon_ind: usize,
+        ws_ind: usize,
+        num_workspaces: usize,
+    },
+    WorkspaceHostedTwice {
+        ws_ind: usize,
+        first_mon: usize,
+        second_mon: usize,
+    },
+    /// A monitor hosts a workspace from another monitor's set
+    WorkspaceOutsideMonitorSet { mon_ind: usize, ws_ind: usize },
+    /// A window marked for death is still managed
+    DyingWindowManaged { window: Window },
+    /// The area windows are tiled in on a monitor has no size
+    EmptyTilingArea {
+        mon_ind: usize,
+        dimensions: Dimensions,
+    },
+}
+
+impl core::fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvariantViolation::DuplicateWindow {
+                window,
+                first_ws,
+                second_ws,
+            } => f.write_fmt(format_args!(
+                "Window {window} is in both workspace {first_ws} and {second_ws}"
+            )),
+            InvariantViolation::MisindexedWindow {
+                window,
+                ws_ind,
+                indexed_ws,
+            } => f.write_fmt(format_args!(
+                "Window {window} is in workspace {ws_ind} but indexed to {indexed_ws:?}"
+            )),
+            InvariantViolation::MissingWindow { window, indexed_ws } => f.write_fmt(format_args!(
+                "Window {window} is indexed to workspace {indexed_ws} but isn't in it"
+            )),
+            InvariantViolation::FocusedMonitorOutOfRange {
+                focused_mon,
+                num_monitors,
+            } => f.write_fmt(format_args!(
+                "Focused monitor {focused_mon} out of range with {num_monitors} monitors"
+            )),
+            InvariantViolation::HostedWorkspaceOutOfRange {
+                mon_ind,
+                ws_ind,
+                num_workspaces,
+            } => f.write_fmt(format_args!(
+                "Monitor {mon_ind} hosts workspace {ws_ind} out of range with {num_workspaces} workspaces"
+            )),
+            InvariantViolation::WorkspaceHostedTwice {
+                ws_ind,
+                first_mon,
+                second_mon,
+            } => f.write_fmt(format_args!(
+                "Workspace {ws_ind} is hosted on both monitor {first_mon} and {second_mon}"
+            )),
+            InvariantViolation::WorkspaceOutsideMonitorSet { mon_ind, ws_ind } => {
+                f.write_fmt(format_args!(
+                    "Monitor {mon_ind} hosts workspace {ws_ind} from another monitor's set"
+                ))
+            }
+            InvariantViolation::DyingWindowManaged { window } => f.write_fmt(format_args!(
+                "Window {window} is marked for death but still managed"
+            )),
+            InvariantViolation::EmptyTilingArea {
+                mon_ind,
+                dimensions,
+            } => f.write_fmt(format_args!(
+                "Monitor {mon_ind} has an empty tiling area {dimensions:?}"
+            )),
+        }
+    }
+}
+
+impl State {
+    /// Validate assumptions that the rest of the WM relies on, returning the first one that doesn't hold.
+    /// Walks every managed window so it's meant for debugging, not for every build.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut seen: Map<Window, usize> = Map::new();
+        for ws_ind in 0..self.workspaces.num_workspaces() {
+            for managed in self.workspaces.iter_all_managed_windows_in_ws(ws_ind) {
+                let window = managed.window;
+                if let Some(first_ws) = seen.get(&window) {
+                    return Err(InvariantViolation::DuplicateWindow {
+                        window,
+                        first_ws: *first_ws,
+                        second_ws: ws_ind,
+                    });
+                }
+                seen.insert(window, ws_ind);
+                let indexed_ws = self.workspaces.find_ws_containing_window(window);
+                if indexed_ws != Some(ws_ind) {
+                    return Err(InvariantViolation::MisindexedWindow {
+                        window,
+                        ws_ind,
+                        indexed_ws,
+                    });
+                }
+            }
+        }
+        for window in self.workspaces.get_all_managed_windows() {
+            if !seen.contains_key(&window) {
+                return Err(InvariantViolation::MissingWindow {
+                    window,
+                    indexed_ws: self
+                        .workspaces
+                        .find_ws_containing_window(window)
+                        .unwrap_or_default(),
+                });
+            }
+        }
+        if self.focused_mon >= self.monitors.len() {
+            return Err(InvariantViolation::FocusedMonitorOutOfRange {
+                focused_mon: self.focused_mon,
+                num_monitors: self.monitors.len(),
+            });
+        }
+        for (mon_ind, mon) in self.monitors.iter().enumerate() {
+            let ws_ind = mon.hosted_workspace;
+            if ws_ind >= self.workspaces.num_workspaces() {
+                return Err(InvariantViolation::HostedWorkspaceOutOfRange {
+                    mon_ind,
+                    ws_ind,
+                    num_workspaces: self.workspaces.num_workspaces(),
+                });
+            }
+            if let Some(first_mon) = self.monitors[..mon_ind]
+                .iter()
+                .position(|other| other.hosted_workspace == ws_ind)
+            {
+                return Err(InvariantViolation::WorkspaceHostedTwice {
+                    ws_ind,
+                    first_mon,
+                    second_mon: mon_ind,
+                });
+            }
+            if self
+                .workspaces
+                .index_in_set(self.workspaces.set_of_monitor(mon_ind), ws_ind)
+                .is_none()
+            {
+                return Err(InvariantViolation::WorkspaceOutsideMonitorSet { mon_ind, ws_ind });
+            }
+            let dimensions = self.maximized_dimensions(mon_ind);
+            if dimensions.width <= 0 || dimensions.height <= 0 {
+                return Err(InvariantViolation::EmptyTilingArea {
+                    mon_ind,
+                    dimensions,
+                });
+            }
+        }
+        for dying in &self.dying_windows {
+            if self.workspaces.get_managed_win(dying.win).is_some() {
+                return Err(InvariantViolation::DyingWindowManaged { window: dying.win });
+            }
+        }
+        Ok(())
+    }
+}
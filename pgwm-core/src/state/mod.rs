@@ -5,46 +5,124 @@ use core::time::Duration;
 use heapless::binary_heap::Min;
 use smallmap::Map;
 use tiny_std::time::Instant;
+use tiny_std::UnixStr;
 use xcb_rust_protocol::proto::xproto::Timestamp;
-use xcb_rust_protocol::proto::xproto::{Screen, Window};
+use xcb_rust_protocol::proto::xproto::{ButtonIndexEnum, Screen, Window};
+use xcb_rust_protocol::NONE;
 
+use crate::colors::gradient::GradientCache;
 use crate::colors::Colors;
-use crate::config::key_map::KeyBoardMappingKey;
+use crate::config::cfg::Cfg;
+use crate::config::edges::{Edge, EdgeAction};
+use crate::config::key_map::{KeyBoardMappingKey, KeyboardMapping, LOCK_MODS};
 use crate::config::mouse_map::{MouseActionKey, MouseTarget};
-use crate::config::Action;
+use crate::config::{Action, LastWindowClosed, MonitorDirection};
 use crate::error::Result;
-use crate::geometry::draw::Mode;
-use crate::geometry::Dimensions;
-use crate::render::DoubleBufferedRenderPicture;
-use crate::state::bar_geometry::BarGeometry;
+use crate::geometry::draw::{Mode, OldDrawMode};
+use crate::geometry::{scale_px, snap_line, Dimensions};
+use crate::render::image::Image;
+use crate::render::{DoubleBufferedRenderPicture, RenderPicture};
+use crate::state::bar_geometry::{format_urgent, BarGeometry};
+use crate::state::hints::WindowHints;
+use crate::state::picker::WindowPicker;
+use crate::state::slide::WorkspaceSlide;
+use crate::state::transition::BorderTransitions;
+use crate::state::workspace::ArrangeKind;
 use crate::{
-    config::{BINARY_HEAP_LIMIT, DYING_WINDOW_CACHE},
+    config::{
+        BINARY_HEAP_LIMIT, CLIENT_WINDOW_KILL_AFTER, DROP_TO_TILE_EDGE_MARGIN, DYING_WINDOW_CACHE,
+        FLOATING_SNAP_THRESHOLD, FOCUS_VERIFY_MS, TITLE_NOTICE_MS, WS_WINDOW_LIMIT,
+        _AUTOHIDE_BAR_TRIGGER_HEIGHT, _DRAG_SNAP_EDGE_LIMIT, _IDLE_RECHECK_MS,
+        _URGENT_DISPLAY_LIMIT, _WM_NAME_LIMIT,
+    },
     state::workspace::Workspaces,
 };
 
 pub mod bar_geometry;
+pub mod hints;
+pub mod invariants;
+pub mod picker;
+pub mod placement;
 pub mod properties;
+pub mod slide;
+pub mod snapshot;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+pub mod transition;
 pub mod workspace;
 
+/// The input focus following the pointer's root window, set when nothing is focused
+const POINTER_ROOT: Window = 1;
+
 #[allow(clippy::struct_excessive_bools)]
 pub struct State {
     pub wm_check_win: Window,
     pub intern_created_windows: Map<Window, ()>,
     pub dying_windows: heapless::Vec<WinMarkedForDeath, DYING_WINDOW_CACHE>,
     pub drag_window: Option<(Window, DragPosition)>,
+    pub window_picker: Option<WindowPicker>,
+    /// Overlay listing the key bindings, only mapped while shown
+    pub keybind_help_win: DoubleBufferedRenderPicture,
+    pub keybind_help_shown: bool,
+    /// Numbered badges drawn over the hinted windows, one per digit, only mapped while hints are shown
+    pub window_hint_wins: Vec<DoubleBufferedRenderPicture>,
+    pub window_hints: Option<WindowHints>,
+    /// Urgent windows, in the order they became urgent
+    pub urgent_windows: Vec<Window>,
     pub focused_mon: usize,
     pub input_focus: Option<Window>,
+    /// A globally active window that was asked to take focus, checked once it's had time to answer
+    pub pending_focus: Option<FocusRequest>,
     pub screen: Screen,
     pub sequences_to_ignore: heapless::BinaryHeap<u16, Min, BINARY_HEAP_LIMIT>,
+    /// Unmaps requested by the WM whose `UnmapNotify` hasn't arrived yet, by window and request sequence
+    pub pending_unmaps: Vec<(Window, u16)>,
     pub monitors: Vec<Monitor>,
     pub workspaces: Workspaces,
     pub colors: Colors,
+    /// Unscaled, each monitor scales it by [`Monitor::scale`]
     pub window_border_width: u32,
+    /// Unscaled, each monitor scales it by [`Monitor::scale`]
     pub window_padding: i16,
     pub pointer_grabbed: bool,
     pub mouse_mapping: Map<MouseActionKey, Action>,
-    pub key_mapping: Map<KeyBoardMappingKey, Action>,
+    pub key_mapping: Map<KeyBoardMappingKey, KeyboardMapping>,
     pub last_timestamp: Timestamp,
+    /// Keycode and time of the last key release, an auto-repeated press comes right after a release at the same time
+    pub last_key_release: Option<(u8, Timestamp)>,
+    /// Screen sized pixmap set as the root window's background, painted with each monitor's
+    /// workspace background. Only created if any workspace has a background configured.
+    pub root_background: Option<RenderPicture>,
+    /// Decoded workspace background images by path, loaded once at startup
+    pub background_images: Vec<(&'static UnixStr, Image)>,
+    /// The pointer resting against a screen edge that has an action configured
+    pub edge_dwell: Option<EdgeDwell>,
+    /// Monitor and window of a tab being dragged in the tab bar
+    pub dragged_tab: Option<(usize, Window)>,
+    /// Whether [`crate::config::IDLE_ACTION`] is kept from running, see `Action::InhibitIdle`
+    pub idle_inhibited: bool,
+    /// Whether the current idle period has been handled, runs once per period
+    pub idle_handled: bool,
+    /// When to next check how long input has been idle, `None` while idle actions are disabled
+    pub next_idle_check: Option<Instant>,
+    /// When to next reset the screensaver, only set while a fullscreen window is shown
+    pub next_screensaver_reset: Option<Instant>,
+    /// A message shown in place of the window title, like a failed spawn
+    pub title_notice: Option<TitleNotice>,
+    /// Border colors shown on windows and the transitions between them, see [`crate::config::BORDER_TRANSITION_FRAMES`]
+    pub border_transitions: BorderTransitions,
+    /// Allocated border transition colors
+    pub border_gradients: GradientCache,
+    /// Windows of a workspace that was just switched to sliding into place
+    pub workspace_slide: Option<WorkspaceSlide>,
+    /// Position of a status check to switch to its next format, see [`Action::CycleStatusFormat`]
+    pub status_format_cycle: Option<usize>,
+    /// Position of a status check and the millis it should run every, see [`crate::ipc::IpcCommand::SetStatusInterval`]
+    pub status_interval_change: Option<(usize, u64)>,
+    /// Mapped override-redirect windows, like menus and tooltips, and the monitor they're on
+    pub popups: Vec<(Window, usize)>,
+    /// The configuration the WM was started with
+    pub cfg: &'static Cfg,
 }
 
 impl State {
@@ -72,6 +150,27 @@ impl State {
         should_ignore
     }
 
+    /// Remember an unmap requested by the WM, so that its `UnmapNotify` isn't taken for the client withdrawing
+    pub fn push_unmap(&mut self, window: Window, sequence: u16) {
+        self.pending_unmaps.push((window, sequence));
+    }
+
+    /// Whether an `UnmapNotify` comes from the WM hiding the window rather than the client withdrawing it.
+    /// The server sends it while handling the unmap request, so it has that request's sequence, once
+    /// to the window and once to its parent.
+    /// Synthetic ones are sent by clients withdrawing a window that's already unmapped, see ICCCM 4.1.4.
+    pub fn is_wm_unmap(&mut self, window: Window, sequence: u16, synthetic: bool) -> bool {
+        // Unmaps requested before this event are done with, including those that didn't cause one,
+        // like unmapping an unmapped window. Same wrapping comparison as in `should_ignore_sequence`
+        self.pending_unmaps
+            .retain(|(_, pending)| pending.wrapping_sub(sequence) <= u16::MAX / 2);
+        !synthetic
+            && self
+                .pending_unmaps
+                .iter()
+                .any(|pending| *pending == (window, sequence))
+    }
+
     /// Unless you're using a mad amount of monitors this will be fast
     #[must_use]
     pub fn find_monitor_focusing_window(&self, window: Window) -> Option<usize> {
@@ -172,6 +271,296 @@ impl State {
         None
     }
 
+    /// The height at the top of a monitor that windows are kept out of for the bar.
+    /// An autohiding bar is drawn over windows, so only a strip to reveal it from is kept.
+    #[must_use]
+    pub fn bar_reserved_height(&self, mon_ind: usize) -> i16 {
+        let mon = &self.monitors[mon_ind];
+        if mon.autohide_bar {
+            _AUTOHIDE_BAR_TRIGGER_HEIGHT
+        } else if mon.show_bar {
+            mon.bar_height
+        } else {
+            0
+        }
+    }
+
+    /// The monitor a workspace is shown on, the focused monitor if it isn't hosted since that's where it'd be shown
+    #[must_use]
+    pub fn monitor_showing(&self, ws_ind: usize) -> usize {
+        self.find_monitor_hosting_workspace(ws_ind)
+            .unwrap_or(self.focused_mon)
+    }
+
+    /// The padding around tiled windows on a workspace, `window_padding` unless `USER_WORKSPACES`
+    /// overrides it for the workspace, scaled for the monitor it's shown on
+    #[must_use]
+    pub fn padding_of(&self, ws_ind: usize) -> i16 {
+        let padding = self
+            .workspaces
+            .get_ws(ws_ind)
+            .padding
+            .unwrap_or(self.window_padding);
+        scale_px(padding, self.monitors[self.monitor_showing(ws_ind)].scale)
+    }
+
+    /// The decoded background image of a workspace, if it has one that could be loaded
+    #[must_use]
+    pub fn background_image_of(&self, ws_ind: usize) -> Option<&Image> {
+        let path = self.workspaces.get_ws(ws_ind).background_image?;
+        self.background_images
+            .iter()
+            .find_map(|(loaded, image)| (*loaded == path).then_some(image))
+    }
+
+    /// The border width of windows on a workspace, `window_border_width` unless `USER_WORKSPACES`
+    /// overrides it for the workspace, scaled for the monitor it's shown on
+    #[must_use]
+    pub fn border_width_of(&self, ws_ind: usize) -> u32 {
+        let border = self
+            .workspaces
+            .get_ws(ws_ind)
+            .border_width
+            .unwrap_or(self.window_border_width);
+        scale_px(
+            border as i16,
+            self.monitors[self.monitor_showing(ws_ind)].scale,
+        ) as u32
+    }
+
+    /// The border width of windows that aren't managed, scaled for the focused monitor
+    #[must_use]
+    pub fn unmanaged_border_width(&self) -> u32 {
+        scale_px(
+            self.window_border_width as i16,
+            self.monitors[self.focused_mon].scale,
+        ) as u32
+    }
+
+    /// Whether [`SMART_GAPS`](crate::config::SMART_GAPS) leaves out the padding and borders of tiled windows
+    /// on a workspace, which it does while the workspace has a single tiled window
+    #[must_use]
+    pub fn smart_gaps_apply(&self, ws_ind: usize) -> bool {
+        self.cfg.smart_gaps && self.workspaces.get_all_tiled_windows(ws_ind).len() == 1
+    }
+
+    /// The padding around tiled windows on a workspace, see [`State::padding_of`] and [`State::smart_gaps_apply`]
+    #[must_use]
+    pub fn tiled_padding_of(&self, ws_ind: usize) -> i16 {
+        if self.smart_gaps_apply(ws_ind) {
+            0
+        } else {
+            self.padding_of(ws_ind)
+        }
+    }
+
+    /// The border width of tiled windows on a workspace, see [`State::border_width_of`] and [`State::smart_gaps_apply`]
+    #[must_use]
+    pub fn tiled_border_width_of(&self, ws_ind: usize) -> u32 {
+        if self.smart_gaps_apply(ws_ind) {
+            0
+        } else {
+            self.border_width_of(ws_ind)
+        }
+    }
+
+    /// The border width of a window, by the workspace it's on, see [`State::unmanaged_border_width`]
+    /// if it's not managed
+    #[must_use]
+    pub fn border_width_of_window(&self, window: Window) -> u32 {
+        self.workspaces
+            .find_ws_containing_window(window)
+            .map_or_else(
+                || self.unmanaged_border_width(),
+                |ws_ind| self.border_width_of(ws_ind),
+            )
+    }
+
+    /// The dimensions a maximized window takes up on a monitor, the monitor minus the bar (if shown),
+    /// padding, and the window's borders.
+    #[must_use]
+    pub fn maximized_dimensions(&self, mon_ind: usize) -> Dimensions {
+        let mon = &self.monitors[mon_ind];
+        let bar_height = self.bar_reserved_height(mon_ind);
+        let padding = self.padding_of(mon.hosted_workspace);
+        let border = self.border_width_of(mon.hosted_workspace) as i16;
+        Dimensions::new(
+            mon.dimensions.width - 2 * padding - 2 * border,
+            mon.dimensions.height - bar_height - 2 * padding - 2 * border,
+            mon.dimensions.x + padding,
+            mon.dimensions.y + bar_height + padding,
+        )
+    }
+
+    /// Where among the tiled windows of a monitor's workspace a window dropped at `position` should be tiled,
+    /// the index of the tile under the pointer, or last if it's between tiles.
+    /// `None` if the workspace isn't tiled, or the position is within [`DROP_TO_TILE_EDGE_MARGIN`] of the
+    /// tiling area's edges.
+    pub fn tiling_drop_index(&self, mon_ind: usize, position: (i16, i16)) -> Result<Option<usize>> {
+        let mon = &self.monitors[mon_ind];
+        let ws_ind = mon.hosted_workspace;
+        let Mode::Tiled(layout) = self.workspaces.get_draw_mode(ws_ind) else {
+            return Ok(None);
+        };
+        let area = self.maximized_dimensions(mon_ind);
+        let (x, y) = position;
+        if x < area.x + DROP_TO_TILE_EDGE_MARGIN
+            || x > area.x + area.width - DROP_TO_TILE_EDGE_MARGIN
+            || y < area.y + DROP_TO_TILE_EDGE_MARGIN
+            || y > area.y + area.height - DROP_TO_TILE_EDGE_MARGIN
+        {
+            return Ok(None);
+        }
+        let num_tiled = self.workspaces.get_all_tiled_windows(ws_ind).len();
+        if num_tiled == 0 {
+            return Ok(Some(0));
+        }
+        let tiling_modifiers = &self.workspaces.get_ws(ws_ind).tiling_modifiers;
+        let tiles = layout.calculate_dimensions(
+            mon.dimensions.width as u32,
+            mon.dimensions.height as u32,
+            self.tiled_padding_of(ws_ind),
+            self.tiled_border_width_of(ws_ind),
+            self.bar_reserved_height(mon_ind),
+            true,
+            num_tiled,
+            tiling_modifiers.vertically_tiled.as_slice(),
+            tiling_modifiers.left_leader,
+            tiling_modifiers.center_leader,
+        )?;
+        let border = self.tiled_border_width_of(ws_ind) as i16;
+        Ok(Some(
+            tiles
+                .iter()
+                .position(|tile| {
+                    let tile_x = mon.dimensions.x + tile.x;
+                    let tile_y = mon.dimensions.y + tile.y;
+                    x >= tile_x
+                        && x < tile_x + tile.width + 2 * border
+                        && y >= tile_y
+                        && y < tile_y + tile.height + 2 * border
+                })
+                .unwrap_or(num_tiled),
+        ))
+    }
+
+    /// The area a fullscreen window covers, the monitor it's on, or the union of the monitors set through
+    /// [`_NET_WM_FULLSCREEN_MONITORS`](https://specifications.freedesktop.org/wm-spec/1.3/ar01s05.html)
+    /// as long as they all exist.
+    #[must_use]
+    pub fn fullscreen_dimensions(&self, mon_ind: usize, window: Window) -> Dimensions {
+        let mon_dimensions = self.monitors[mon_ind].dimensions;
+        let Some([top, bottom, left, right]) = self
+            .workspaces
+            .get_managed_win(window)
+            .and_then(|mw| mw.fullscreen_monitors)
+        else {
+            return mon_dimensions;
+        };
+        let dimensions_of = |ind: usize| self.monitors.get(ind).map(|mon| mon.dimensions);
+        let (Some(top), Some(bottom), Some(left), Some(right)) = (
+            dimensions_of(top),
+            dimensions_of(bottom),
+            dimensions_of(left),
+            dimensions_of(right),
+        ) else {
+            return mon_dimensions;
+        };
+        let width = right.x + right.width - left.x;
+        let height = bottom.y + bottom.height - top.y;
+        if width <= 0 || height <= 0 {
+            return mon_dimensions;
+        }
+        Dimensions::new(width, height, left.x, top.y)
+    }
+
+    /// Tiled indices of the windows that have a tab on a tabbed workspace, in tab order.
+    /// Windows skipping the taskbar only get a tab while they're the one shown.
+    #[must_use]
+    pub fn tabs(&self, ws_ind: usize) -> heapless::Vec<usize, WS_WINDOW_LIMIT> {
+        let Mode::Tabbed(shown) = self.workspaces.get_draw_mode(ws_ind) else {
+            return heapless::Vec::new();
+        };
+        self.workspaces
+            .get_all_tiled_windows(ws_ind)
+            .iter()
+            .enumerate()
+            .filter(|(ind, mw)| *ind == shown || !mw.properties.net_wm_state.skip_taskbar)
+            .map(|(ind, _)| ind)
+            .collect()
+    }
+
+    /// The tiled index of the window whose tab is at `x` in a monitor's tab bar, clamped to the first and last tab
+    #[must_use]
+    pub fn tab_at(&self, mon_ind: usize, x: i16) -> Option<usize> {
+        let mon = &self.monitors[mon_ind];
+        let tabs = self.tabs(mon.hosted_workspace);
+        if tabs.is_empty() {
+            return None;
+        }
+        let padding = if self.cfg.pad_while_tabbed {
+            self.tiled_padding_of(mon.hosted_workspace)
+        } else {
+            0
+        };
+        let tab_width = ((mon.dimensions.width - 2 * padding) / tabs.len() as i16).max(1);
+        let ind = (x.max(0) / tab_width) as usize;
+        tabs.get(ind.min(tabs.len() - 1)).copied()
+    }
+
+    /// The area of the monitor a workspace is shown on that isn't covered by the bar.
+    /// Workspaces that aren't hosted are measured against the focused monitor, where they'd be shown.
+    #[must_use]
+    pub fn workarea(&self, ws_ind: usize) -> Dimensions {
+        let mon_ind = self.monitor_showing(ws_ind);
+        let mon = &self.monitors[mon_ind];
+        let bar_height = self.bar_reserved_height(mon_ind);
+        Dimensions::new(
+            mon.dimensions.width,
+            mon.dimensions.height - bar_height,
+            mon.dimensions.x,
+            mon.dimensions.y + bar_height,
+        )
+    }
+
+    /// The [`_NET_FRAME_EXTENTS`](https://specifications.freedesktop.org/wm-spec/1.3/ar01s05.html)
+    /// of a window as left, right, top, bottom. The border on every side, plus the tab bar on top if the
+    /// window is tiled on a tabbed workspace. Fullscreen windows have no frame, and windows that
+    /// aren't managed yet are assumed to get a border only.
+    #[must_use]
+    pub fn frame_extents(&self, window: Window) -> [u32; 4] {
+        let Some(ws_ind) = self.workspaces.find_ws_containing_window(window) else {
+            return [self.unmanaged_border_width(); 4];
+        };
+        let tabbed = match self.workspaces.get_draw_mode(ws_ind) {
+            Mode::Fullscreen {
+                window: fullscreen, ..
+            } if fullscreen == window => return [0; 4],
+            Mode::Tabbed(_)
+            | Mode::Fullscreen {
+                last_draw_mode: OldDrawMode::Tabbed(_),
+                ..
+            } => true,
+            _ => false,
+        };
+        let mw = self.workspaces.get_managed_win(window);
+        let tiled = mw.map_or(false, |mw| mw.arrange == ArrangeKind::NoFloat);
+        // Tiled client-side-decorations cover where the border would be
+        let border = if !tiled {
+            self.border_width_of(ws_ind)
+        } else if mw.map_or(false, |mw| mw.properties.gtk_frame_extents.is_some()) {
+            0
+        } else {
+            self.tiled_border_width_of(ws_ind)
+        };
+        let tab_bar = if tabbed && tiled {
+            self.monitors[self.monitor_showing(ws_ind)].tab_bar_height as u32
+        } else {
+            0
+        };
+        [border, border, border + tab_bar, border]
+    }
+
     #[must_use]
     pub fn any_monitors_showing_status(&self) -> bool {
         self.monitors.iter().any(|mon| mon.show_bar)
@@ -193,15 +582,416 @@ impl State {
             .flatten()
     }
 
+    /// The action bound to a key press, `None` if it's an auto-repeated press of a mapping that doesn't repeat
     #[must_use]
-    pub fn get_key_action(&self, code: u8, mods: u16) -> Option<&Action> {
-        self.key_mapping.get(&KeyBoardMappingKey::new(code, mods))
+    pub fn get_key_action(&self, code: u8, mods: u16, time: Timestamp) -> Option<&Action> {
+        let mapping = self
+            .key_mapping
+            .get(&KeyBoardMappingKey::from_event(code, mods))?;
+        if !mapping.repeat && self.is_key_repeat(code, time) {
+            return None;
+        }
+        Some(&mapping.action)
+    }
+
+    /// Whether a key press is auto-repeated, it comes right after a release of the same key at the same time
+    #[must_use]
+    pub fn is_key_repeat(&self, code: u8, time: Timestamp) -> bool {
+        self.last_key_release == Some((code, time))
     }
 
     #[must_use]
     pub fn get_mouse_action(&self, detail: u8, state: u16, target: MouseTarget) -> Option<&Action> {
+        let state = state & !LOCK_MODS;
+        let section = target.section();
         self.mouse_mapping
             .get(&MouseActionKey::new(detail, state, target))
+            .or_else(|| {
+                section.and_then(|section| {
+                    self.mouse_mapping
+                        .get(&MouseActionKey::new(detail, state, section))
+                })
+            })
+    }
+
+    /// Returns true if the window wasn't already tracked as urgent
+    pub fn push_urgent(&mut self, window: Window) -> bool {
+        if self.urgent_windows.contains(&window) {
+            false
+        } else {
+            self.urgent_windows.push(window);
+            true
+        }
+    }
+
+    /// Returns true if the window was tracked as urgent
+    pub fn remove_urgent(&mut self, window: Window) -> bool {
+        let len = self.urgent_windows.len();
+        self.urgent_windows.retain(|win| *win != window);
+        len != self.urgent_windows.len()
+    }
+
+    /// The text of the bar's urgent section, the number of urgent windows and the workspaces containing them
+    #[must_use]
+    pub fn urgent_display(&self) -> heapless::String<_URGENT_DISPLAY_LIMIT> {
+        let mut urgent_workspaces: Vec<usize> = self
+            .urgent_windows
+            .iter()
+            .filter_map(|win| self.workspaces.find_ws_containing_window(*win))
+            .collect();
+        urgent_workspaces.sort_unstable();
+        urgent_workspaces.dedup();
+        format_urgent(
+            self.urgent_windows.len(),
+            urgent_workspaces
+                .into_iter()
+                .map(|ws_ind| self.workspaces.get_ws(ws_ind).name),
+        )
+    }
+
+    /// The action of a clicked bar shortcut, shortcuts are activated by a left click without modifiers
+    #[must_use]
+    pub fn get_shortcut_action(
+        &self,
+        mon_ind: usize,
+        shortcut_ind: usize,
+        detail: u8,
+        state: u16,
+    ) -> Option<&Action> {
+        (detail == ButtonIndexEnum::ONE.0 && state & !LOCK_MODS == 0)
+            .then(|| {
+                self.monitors[mon_ind]
+                    .bar_geometry
+                    .shortcuts
+                    .components
+                    .get(shortcut_ind)
+                    .map(|shortcut| &shortcut.action)
+            })
+            .flatten()
+    }
+
+    /// Left clicking a status check without modifiers switches it to its next format,
+    /// media checks have their own actions, see [`crate::status::checker::MediaFormat`]
+    #[must_use]
+    pub fn get_status_action(&self, check_ind: usize, detail: u8, state: u16) -> Option<Action> {
+        if state & !LOCK_MODS != 0 {
+            return None;
+        }
+        #[cfg(feature = "status-bar")]
+        if let Some(crate::status::checker::CheckType::Media(fmt)) = self
+            .cfg
+            .status_checks
+            .get(check_ind)
+            .map(|check| &check.check_type)
+        {
+            return fmt.click_action(detail);
+        }
+        (detail == ButtonIndexEnum::ONE.0).then_some(Action::CycleStatusFormat(check_ind))
+    }
+
+    /// When the first window marked for death needs attention, windows are handled in order
+    #[must_use]
+    pub fn next_dying_window_deadline(&self) -> Option<Instant> {
+        self.dying_windows
+            .first()
+            .map(|dying| dying.next_deadline(CLIENT_WINDOW_KILL_AFTER))
+    }
+
+    /// Track the pointer against the screen edges, returns an action that's due immediately.
+    /// Actions with a dwell time are taken through [`State::pop_due_edge_action`] once due.
+    pub fn update_edge_dwell(
+        &mut self,
+        edge_actions: &[EdgeAction],
+        position: (i16, i16),
+        now: Instant,
+    ) -> Option<Action> {
+        let Some(edge_action) = Edge::at(
+            position.0,
+            position.1,
+            self.screen.width_in_pixels,
+            self.screen.height_in_pixels,
+        )
+        .and_then(|edge| edge_actions.iter().find(|ea| ea.edge == edge)) else {
+            self.edge_dwell = None;
+            return None;
+        };
+        if let Some(dwell) = self.edge_dwell.as_mut() {
+            if dwell.edge == edge_action.edge {
+                dwell.position = position;
+                return None;
+            }
+        }
+        let due = now
+            .add(Duration::from_millis(edge_action.dwell_ms))
+            .unwrap();
+        self.edge_dwell = Some(EdgeDwell {
+            edge: edge_action.edge,
+            action: edge_action.action,
+            position,
+            due,
+            ran: false,
+        });
+        self.pop_due_edge_action(now).map(|(action, _)| action)
+    }
+
+    /// Take the action of the edge the pointer rests against if it's due and hasn't run yet,
+    /// along with the last known pointer position
+    pub fn pop_due_edge_action(&mut self, now: Instant) -> Option<(Action, (i16, i16))> {
+        let dwell = self.edge_dwell.as_mut()?;
+        if dwell.ran || dwell.due > now {
+            return None;
+        }
+        dwell.ran = true;
+        Some((dwell.action, dwell.position))
+    }
+
+    /// When an edge action that hasn't run yet will be due
+    #[must_use]
+    pub fn next_edge_dwell_deadline(&self) -> Option<Instant> {
+        self.edge_dwell
+            .as_ref()
+            .filter(|dwell| !dwell.ran)
+            .map(|dwell| dwell.due)
+    }
+
+    /// Track how long input has been idle, returns whether the idle action should run, which happens
+    /// once each time input has been idle for `idle_after`. A period that started while inhibited
+    /// doesn't run when the inhibit is lifted, input has to resume first.
+    pub fn update_idle(&mut self, idle_after: Duration, idle_for: Duration, now: Instant) -> bool {
+        if idle_after.is_zero() {
+            self.next_idle_check = None;
+            return false;
+        }
+        if idle_for < idle_after {
+            self.idle_handled = false;
+            self.next_idle_check = now.add(idle_after - idle_for);
+            return false;
+        }
+        // Checking back regularly to notice when input resumes
+        self.next_idle_check = now.add(Duration::from_millis(_IDLE_RECHECK_MS));
+        let run = !self.idle_handled && !self.idle_inhibited;
+        self.idle_handled = true;
+        run
+    }
+
+    /// Whether the screensaver should be reset, which is every `reset_every` while a fullscreen window
+    /// is shown on any monitor. A `reset_every` of zero never resets.
+    pub fn update_screensaver_reset(&mut self, reset_every: Duration, now: Instant) -> bool {
+        if reset_every.is_zero() || !self.shows_fullscreen() {
+            self.next_screensaver_reset = None;
+            return false;
+        }
+        if self.next_screensaver_reset.is_some_and(|due| due > now) {
+            return false;
+        }
+        self.next_screensaver_reset = now.add(reset_every);
+        true
+    }
+
+    #[must_use]
+    pub fn shows_fullscreen(&self) -> bool {
+        self.monitors.iter().any(|mon| {
+            !mon.showing_desktop
+                && matches!(
+                    self.workspaces.get_draw_mode(mon.hosted_workspace),
+                    Mode::Fullscreen { .. }
+                )
+        })
+    }
+
+    /// Where to draw a window on a monitor that should end up at `(x, y)`, off to the side if its workspace
+    /// is sliding into place
+    pub fn slide_position(&mut self, mon_ind: usize, window: Window, x: i32, y: i32) -> (i32, i32) {
+        self.workspace_slide
+            .as_mut()
+            .map_or((x, y), |slide| slide.place(mon_ind, window, x, y))
+    }
+
+    /// Show a notice on a monitor for [`TITLE_NOTICE_MS`], returns the notice it replaced
+    pub fn show_title_notice(
+        &mut self,
+        text: &str,
+        mon_ind: usize,
+        now: Instant,
+    ) -> Option<TitleNotice> {
+        self.show_title_notice_for(text, mon_ind, Duration::from_millis(TITLE_NOTICE_MS), now)
+    }
+
+    /// Show a notice on a monitor for `shown_for`, returns the notice it replaced
+    pub fn show_title_notice_for(
+        &mut self,
+        text: &str,
+        mon_ind: usize,
+        shown_for: Duration,
+        now: Instant,
+    ) -> Option<TitleNotice> {
+        let mut notice = TitleNotice {
+            text: heapless::String::new(),
+            mon_ind,
+            until: now.add(shown_for)?,
+        };
+        for c in text.chars() {
+            if notice.text.push(c).is_err() {
+                break;
+            }
+        }
+        self.title_notice.replace(notice)
+    }
+
+    /// Remove the title notice if it's been shown long enough
+    pub fn take_expired_title_notice(&mut self, now: Instant) -> Option<TitleNotice> {
+        if self.title_notice.as_ref()?.until <= now {
+            self.title_notice.take()
+        } else {
+            None
+        }
+    }
+
+    /// Check that `window` took focus after [`FOCUS_VERIFY_MS`], replaces any unchecked request
+    pub fn request_focus_verification(
+        &mut self,
+        window: Window,
+        previous: Option<Window>,
+        now: Instant,
+    ) {
+        self.pending_focus = now
+            .add(Duration::from_millis(FOCUS_VERIFY_MS))
+            .map(|verify_at| FocusRequest {
+                window,
+                previous,
+                verify_at,
+            });
+    }
+
+    /// Remove the focus request if it's time to check it, requests for a window that has
+    /// since lost focus to something else are dropped
+    pub fn take_due_focus_request(&mut self, now: Instant) -> Option<FocusRequest> {
+        if self.pending_focus.as_ref()?.verify_at > now {
+            return None;
+        }
+        self.pending_focus
+            .take()
+            .filter(|request| self.input_focus == Some(request.window))
+    }
+
+    /// Whether the window the X server says has the input focus means that `request` was declined,
+    /// the keyboard going nowhere or staying with the previous window.
+    /// Any other window is accepted, clients may hand focus to one of their own children.
+    #[must_use]
+    pub fn focus_declined(&self, request: &FocusRequest, focus: Window) -> bool {
+        focus == NONE
+            || focus == POINTER_ROOT
+            || focus == self.screen.root
+            || Some(focus) == request.previous
+    }
+
+    /// The notice to show in place of the window title on a monitor
+    #[must_use]
+    pub fn title_notice_on(&self, mon_ind: usize) -> Option<&str> {
+        self.title_notice
+            .as_ref()
+            .filter(|notice| notice.mon_ind == mon_ind)
+            .map(|notice| notice.text.as_str())
+    }
+
+    #[must_use]
+    pub fn find_monitor_index_of_bar_win(&self, window: Window) -> Option<usize> {
+        self.monitors
+            .iter()
+            .position(|mon| mon.bar_win.window.drawable == window)
+    }
+
+    /// The workspace after, or before, the one hosted on a monitor within the monitor's set,
+    /// skipping workspaces hosted on other monitors
+    #[must_use]
+    pub fn cycle_workspace(&self, mon_ind: usize, forward: bool) -> Option<usize> {
+        let set = self.workspaces.set_of_monitor(mon_ind);
+        let set_size = self.workspaces.set_size();
+        let current = self
+            .workspaces
+            .index_in_set(set, self.monitors[mon_ind].hosted_workspace)?;
+        (1..set_size)
+            .filter_map(|step| {
+                let num = if forward {
+                    (current + step) % set_size
+                } else {
+                    (current + set_size - step) % set_size
+                };
+                self.workspaces.in_set(set, num)
+            })
+            .find(|ws_ind| self.find_monitor_hosting_workspace(*ws_ind).is_none())
+    }
+
+    /// The monitor a workspace should be shown on when requested on `mon_ind`, with a set of
+    /// workspaces per monitor that's the monitor owning the set, `None` if that monitor is gone
+    #[must_use]
+    pub fn monitor_for_workspace(&self, ws_ind: usize, mon_ind: usize) -> Option<usize> {
+        if self.workspaces.num_sets() > 1 {
+            let owner = self.workspaces.set_of(ws_ind);
+            (owner < self.monitors.len()).then_some(owner)
+        } else {
+            Some(mon_ind)
+        }
+    }
+
+    /// The workspace a monitor should switch to according to `policy` if its hosted workspace has no windows
+    #[must_use]
+    pub fn workspace_after_last_closed(
+        &self,
+        mon_ind: usize,
+        policy: LastWindowClosed,
+    ) -> Option<usize> {
+        let mon = &self.monitors[mon_ind];
+        let has_windows = |ws_ind: usize| {
+            self.workspaces
+                .iter_all_managed_windows_in_ws(ws_ind)
+                .next()
+                .is_some()
+        };
+        if has_windows(mon.hosted_workspace) {
+            return None;
+        }
+        match policy {
+            LastWindowClosed::Stay => None,
+            LastWindowClosed::MostRecentNonEmpty => {
+                mon.recent_workspaces.iter().copied().find(|ws_ind| {
+                    self.find_monitor_hosting_workspace(*ws_ind).is_none() && has_windows(*ws_ind)
+                })
+            }
+            LastWindowClosed::Workspace(num) => self
+                .workspaces
+                .in_set(self.workspaces.set_of_monitor(mon_ind), num)
+                .filter(|ws_ind| *ws_ind != mon.hosted_workspace),
+        }
+    }
+
+    /// The monitor next to, or previous to, a monitor, wrapping around
+    #[must_use]
+    pub fn monitor_in_direction(&self, mon_ind: usize, direction: MonitorDirection) -> usize {
+        let len = self.monitors.len();
+        match direction {
+            MonitorDirection::Next => (mon_ind + 1) % len,
+            MonitorDirection::Previous => (mon_ind + len - 1) % len,
+        }
+    }
+
+    /// Tracks a mapped override-redirect window at `origin`, ignored if it's not on any monitor
+    pub fn track_popup(&mut self, window: Window, origin: (i16, i16)) {
+        self.untrack_popup(window);
+        if let Some(mon_ind) = self.find_monitor_at(origin) {
+            self.popups.push((window, mon_ind));
+        }
+    }
+
+    pub fn untrack_popup(&mut self, window: Window) {
+        self.popups.retain(|(popup, _)| *popup != window);
+    }
+
+    /// Whether an override-redirect window is mapped on a monitor, they're drawn above managed windows
+    /// and often grab the pointer themselves
+    #[must_use]
+    pub fn popup_open_on(&self, mon_ind: usize) -> bool {
+        self.popups.iter().any(|(_, on_mon)| *on_mon == mon_ind)
     }
 
     pub fn update_focused_mon(&mut self, new_focus: usize) -> Option<usize> {
@@ -218,12 +1008,41 @@ pub struct Monitor {
     pub tab_bar_win: DoubleBufferedRenderPicture,
     pub bar_geometry: BarGeometry,
     pub dimensions: Dimensions,
+    /// Scale of the bar heights, fonts, padding and borders on this monitor, by its DPI,
+    /// see [`crate::config::SCALE_FACTOR`]
+    pub scale: f32,
+    /// [`crate::config::STATUS_BAR_HEIGHT`] scaled
+    pub bar_height: i16,
+    /// [`crate::config::TAB_BAR_HEIGHT`] scaled
+    pub tab_bar_height: i16,
     pub hosted_workspace: usize,
+    /// Workspaces previously hosted, most recent first, not including the hosted one
+    pub recent_workspaces: Vec<usize>,
     pub last_focus: Option<Window>,
     pub show_bar: bool,
+    pub autohide_bar: bool,
+    /// The hosted workspace's windows are unmapped to show the desktop, see [`Action::ToggleShowDesktop`]
+    pub showing_desktop: bool,
     pub window_title_display: heapless::String<256>,
 }
 
+impl Monitor {
+    /// Hosts a new workspace, remembering the previously hosted one
+    pub fn host_workspace(&mut self, ws_ind: usize) {
+        if self.hosted_workspace != ws_ind {
+            let previous = core::mem::replace(&mut self.hosted_workspace, ws_ind);
+            self.recent_workspaces
+                .retain(|recent| *recent != ws_ind && *recent != previous);
+            self.recent_workspaces.insert(0, previous);
+        }
+    }
+
+    #[must_use]
+    pub fn previous_workspace(&self) -> Option<usize> {
+        self.recent_workspaces.first().copied()
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct DrawArea {
     pub width: i16,
@@ -235,29 +1054,87 @@ pub struct DragPosition {
     origin_y: i16,
     event_origin_x: i16,
     event_origin_y: i16,
+    width: i16,
+    height: i16,
+    snap_x: heapless::Vec<i16, _DRAG_SNAP_EDGE_LIMIT>,
+    snap_y: heapless::Vec<i16, _DRAG_SNAP_EDGE_LIMIT>,
 }
 
 impl DragPosition {
+    /// `origin` is the dragged window's outer dimensions (including borders)
     #[must_use]
     #[inline]
-    pub fn new(origin_x: i16, origin_y: i16, event_origin_x: i16, event_origin_y: i16) -> Self {
+    pub fn new(origin: Dimensions, event_origin_x: i16, event_origin_y: i16) -> Self {
         DragPosition {
-            origin_x,
-            origin_y,
+            origin_x: origin.x,
+            origin_y: origin.y,
             event_origin_x,
             event_origin_y,
+            width: origin.width,
+            height: origin.height,
+            snap_x: heapless::Vec::new(),
+            snap_y: heapless::Vec::new(),
         }
     }
 
+    /// Add all four edges of an area that the dragged window should snap to.
+    /// Edges past the limit are silently dropped, it's a nicety, not a requirement.
+    pub fn add_snap_area(&mut self, area: Dimensions) {
+        let _ = self.snap_x.push(area.x);
+        let _ = self.snap_x.push(area.x + area.width);
+        let _ = self.snap_y.push(area.y);
+        let _ = self.snap_y.push(area.y + area.height);
+    }
+
+    /// Where the pointer was when the drag started
+    #[must_use]
+    #[inline]
+    pub fn event_origin(&self) -> (i16, i16) {
+        (self.event_origin_x, self.event_origin_y)
+    }
+
+    /// Add a horizontal line that the dragged window should snap to, like the bottom of the bar
+    pub fn add_snap_y(&mut self, y: i16) {
+        let _ = self.snap_y.push(y);
+    }
+
     #[must_use]
     #[inline]
     pub fn current_position(&self, cursor_x: i16, cursor_y: i16) -> (i16, i16) {
         let x = self.origin_x + cursor_x - self.event_origin_x;
         let y = self.origin_y + cursor_y - self.event_origin_y;
-        (x, y)
+        (
+            snap_line(x, self.width, &self.snap_x, FLOATING_SNAP_THRESHOLD),
+            snap_line(y, self.height, &self.snap_y, FLOATING_SNAP_THRESHOLD),
+        )
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct TitleNotice {
+    pub text: heapless::String<_WM_NAME_LIMIT>,
+    pub mon_ind: usize,
+    pub until: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FocusRequest {
+    pub window: Window,
+    /// What had focus before, focused again if the window declines
+    pub previous: Option<Window>,
+    pub verify_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeDwell {
+    pub edge: Edge,
+    pub action: Action,
+    pub position: (i16, i16),
+    pub due: Instant,
+    /// Runs once per contact with the edge
+    pub ran: bool,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct WinMarkedForDeath {
     pub win: Window,
@@ -288,175 +1165,45 @@ impl WinMarkedForDeath {
     pub fn should_destroy(&self) -> bool {
         self.die_at <= Instant::now()
     }
+
+    /// When this window next needs attention, to be destroyed or, if that's already been sent, killed
+    #[must_use]
+    pub fn next_deadline(&self, kill_after: u64) -> Instant {
+        if self.sent_destroy {
+            self.die_at.add(Duration::from_millis(kill_after)).unwrap()
+        } else {
+            self.die_at
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use alloc::vec;
+    use core::ops::Add;
+    use core::time::Duration;
 
-    use smallmap::Map;
-    use xcb_rust_protocol::proto::xproto::{BackingStoreEnum, EventMask, Screen};
-    use xcb_rust_protocol::CURRENT_TIME;
+    use tiny_std::time::Instant;
+    use xcb_rust_protocol::proto::xproto::ModMask;
 
-    use crate::colors::{Color, Colors};
-    use crate::config::{COLORS, USER_WORKSPACES};
-    use crate::geometry::{Dimensions, Line};
-    use crate::render::{DoubleBufferedRenderPicture, RenderPicture};
-    use crate::state::bar_geometry::{
-        BarGeometry, ShortcutSection, WindowTitleSection, WorkspaceSection,
+    use crate::config::cfg::Cfg;
+    use crate::config::edges::{Edge, EdgeAction};
+    use crate::config::key_map::{KeyBoardMappingKey, KeyboardMapping, LOCK_MODS};
+    use crate::config::mouse_map::{MouseActionKey, MouseTarget};
+    use crate::config::{
+        Action, LastWindowClosed, MonitorDirection, CLIENT_WINDOW_KILL_AFTER,
+        FLOATING_SNAP_THRESHOLD, FOCUS_VERIFY_MS, STATUS_BAR_HEIGHT, TITLE_NOTICE_MS,
+        URGENT_SECTION_ICON, USER_WORKSPACES, _AUTOHIDE_BAR_TRIGGER_HEIGHT, _IDLE_RECHECK_MS,
     };
-    use crate::state::properties::{WindowProperties, WmName};
+    use crate::geometry::draw::Mode;
+    use crate::geometry::{Dimensions, Line};
+    use crate::state::bar_geometry::ShortcutComponent;
+    use crate::state::invariants::InvariantViolation;
+    use crate::state::properties::WmName;
+    use crate::state::snapshot::StateSnapshot;
+    use crate::state::test_support::{create_base_state, test_properties};
     use crate::state::workspace::{ArrangeKind, FocusStyle, ManagedWindow, Workspaces};
-    use crate::state::{Monitor, State};
-
-    fn create_base_state() -> State {
-        let monitor0 = Monitor {
-            bar_win: DoubleBufferedRenderPicture {
-                window: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-                pixmap: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-            },
-            tab_bar_win: DoubleBufferedRenderPicture {
-                window: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-                pixmap: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-            },
-            bar_geometry: BarGeometry {
-                workspace: WorkspaceSection {
-                    position: Line::new(0, 0),
-                    components: vec![],
-                },
-                shortcuts: ShortcutSection {
-                    position: Line::new(0, 0),
-                    components: vec![],
-                },
-                #[cfg(feature = "status-bar")]
-                status: crate::state::bar_geometry::StatusSection {
-                    position: Line::new(0, 0),
-                    first_sep_len: 0,
-                    sep_len: 0,
-                    components: heapless::Vec::default(),
-                },
-                window_title_section: WindowTitleSection {
-                    position: Line::new(0, 0),
-                    display: heapless::String::default(),
-                    last_draw_width: 0,
-                },
-            },
-            dimensions: Dimensions::new(1000, 1000, 0, 0),
-            hosted_workspace: 0,
-            last_focus: None,
-            show_bar: false,
-            window_title_display: heapless::String::default(),
-        };
-        let monitor1 = Monitor {
-            bar_geometry: BarGeometry {
-                workspace: WorkspaceSection {
-                    position: Line::new(0, 0),
-                    components: vec![],
-                },
-                shortcuts: ShortcutSection {
-                    position: Line::new(0, 0),
-                    components: vec![],
-                },
-                #[cfg(feature = "status-bar")]
-                status: crate::state::bar_geometry::StatusSection {
-                    position: Line::new(0, 0),
-                    first_sep_len: 0,
-                    sep_len: 0,
-                    components: heapless::Vec::default(),
-                },
-                window_title_section: WindowTitleSection {
-                    position: Line::new(0, 0),
-                    display: heapless::String::default(),
-                    last_draw_width: 0,
-                },
-            },
-            bar_win: DoubleBufferedRenderPicture {
-                window: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-                pixmap: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-            },
-            tab_bar_win: DoubleBufferedRenderPicture {
-                window: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-                pixmap: RenderPicture {
-                    drawable: 0,
-                    picture: 0,
-                    format: 0,
-                },
-            },
-            dimensions: Dimensions::new(1000, 1000, 1000, 0),
-            hosted_workspace: 1,
-            last_focus: None,
-            show_bar: false,
-            window_title_display: heapless::String::default(),
-        };
-        let pixels: [Color; COLORS.len()] = [Color {
-            pixel: 0,
-            bgra8: [0, 0, 0, 0],
-        }; COLORS.len()];
-        State {
-            wm_check_win: 0,
-            intern_created_windows: Map::default(),
-            dying_windows: heapless::Vec::default(),
-            drag_window: None,
-            focused_mon: 0,
-            input_focus: None,
-            screen: Screen {
-                root: 0,
-                default_colormap: 0,
-                white_pixel: 0,
-                black_pixel: 0,
-                current_input_masks: EventMask::NO_EVENT,
-                width_in_pixels: 0,
-                height_in_pixels: 0,
-                width_in_millimeters: 0,
-                height_in_millimeters: 0,
-                min_installed_maps: 0,
-                max_installed_maps: 0,
-                root_visual: 0,
-                backing_stores: BackingStoreEnum::NOT_USEFUL,
-                save_unders: 0,
-                root_depth: 0,
-                allowed_depths: vec![],
-            },
-            sequences_to_ignore: heapless::BinaryHeap::default(),
-            monitors: vec![monitor0, monitor1],
-            workspaces: Workspaces::create_empty(&USER_WORKSPACES).unwrap(),
-            colors: Colors { inner: pixels },
-            window_border_width: 0,
-            window_padding: 0,
-            pointer_grabbed: false,
-            mouse_mapping: Map::default(),
-            key_mapping: Map::default(),
-            last_timestamp: CURRENT_TIME,
-        }
-    }
+    use crate::state::{DragPosition, WinMarkedForDeath};
 
     #[test]
     fn can_find_monitor_from_different_sources() {
@@ -472,19 +1219,7 @@ mod tests {
             15,
             ArrangeKind::NoFloat,
             FocusStyle::Passive,
-            WindowProperties::new(
-                None,
-                Default::default(),
-                None,
-                None,
-                Default::default(),
-                None,
-                None,
-                Default::default(),
-                Default::default(),
-                WmName::NetWmName(Default::default()),
-                None,
-            ),
+            test_properties(),
         );
         state
             .workspaces
@@ -514,6 +1249,250 @@ mod tests {
         assert!(state.find_monitor_at((2001, 0)).is_none());
     }
 
+    #[test]
+    fn captures_snapshot() {
+        let mut state = create_base_state();
+        let mut properties = test_properties();
+        properties.name = WmName::NetWmName(heapless::String::try_from("vim").unwrap());
+        let _ = properties
+            .class
+            .push(heapless::String::try_from("xterm").unwrap());
+        state
+            .workspaces
+            .add_child_to_ws(
+                15,
+                1,
+                ArrangeKind::NoFloat,
+                FocusStyle::Passive,
+                &properties,
+            )
+            .unwrap();
+        state
+            .workspaces
+            .add_attached(
+                15,
+                16,
+                ArrangeKind::FloatingActive,
+                FocusStyle::Passive,
+                &properties,
+            )
+            .unwrap();
+        state.monitors[1].last_focus = Some(16);
+        state.focused_mon = 1;
+        let snapshot = StateSnapshot::capture(&state);
+        assert_eq!(1, snapshot.focused_mon);
+        assert_eq!(2, snapshot.monitors.len());
+        assert_eq!(Some(16), snapshot.monitors[1].last_focus);
+        assert_eq!(USER_WORKSPACES.len(), snapshot.workspaces.len());
+        let ws = &snapshot.workspaces[1];
+        assert_eq!(USER_WORKSPACES[1].name, ws.name);
+        let windows = ws
+            .windows
+            .iter()
+            .map(|win| (win.window, win.attached_to, win.class.as_str()))
+            .collect::<alloc::vec::Vec<_>>();
+        assert_eq!(vec![(15, None, "xterm"), (16, Some(15), "xterm")], windows);
+        assert_eq!("vim", ws.windows[0].name);
+        assert_eq!(snapshot, StateSnapshot::decode(&snapshot.encode()).unwrap());
+    }
+
+    #[test]
+    fn tracks_urgent_windows_in_order() {
+        let mut state = create_base_state();
+        let properties = test_properties();
+        for (window, ws_ind) in [(15, 2), (16, 0), (17, 2)] {
+            state
+                .workspaces
+                .add_child_to_ws(
+                    window,
+                    ws_ind,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+        assert_eq!("", state.urgent_display());
+        assert!(state.push_urgent(17));
+        assert!(state.push_urgent(16));
+        assert!(!state.push_urgent(17));
+        assert!(state.push_urgent(15));
+        assert_eq!(vec![17, 16, 15], state.urgent_windows);
+        let names = [0, 2].map(|ws_ind| state.workspaces.get_ws(ws_ind).name);
+        assert_eq!(
+            alloc::format!("{URGENT_SECTION_ICON} 3 {} {}", names[0], names[1]),
+            state.urgent_display().as_str()
+        );
+        assert!(state.remove_urgent(17));
+        assert!(!state.remove_urgent(17));
+        assert_eq!(vec![16, 15], state.urgent_windows);
+    }
+
+    #[test]
+    fn edge_actions_run_once_after_dwell() {
+        let mut state = create_base_state();
+        state.screen.width_in_pixels = 2000;
+        state.screen.height_in_pixels = 1000;
+        let edge_actions = [
+            EdgeAction::new(Edge::Top, 0, Action::RevealBar),
+            EdgeAction::new(Edge::Left, 400, Action::PrevWorkspace),
+        ];
+        let start = Instant::now();
+        let after = |millis| start.add(Duration::from_millis(millis)).unwrap();
+        // Top runs on contact, and only once
+        assert_eq!(
+            Some(Action::RevealBar),
+            state.update_edge_dwell(&edge_actions, (50, 0), start)
+        );
+        assert_eq!(
+            None,
+            state.update_edge_dwell(&edge_actions, (60, 0), after(10))
+        );
+        assert_eq!(None, state.next_edge_dwell_deadline());
+        // Left waits for the dwell time
+        assert_eq!(None, state.update_edge_dwell(&edge_actions, (0, 50), start));
+        assert_eq!(Some(after(400)), state.next_edge_dwell_deadline());
+        assert_eq!(None, state.pop_due_edge_action(after(399)));
+        assert_eq!(
+            Some((Action::PrevWorkspace, (0, 50))),
+            state.pop_due_edge_action(after(400))
+        );
+        assert_eq!(None, state.pop_due_edge_action(after(800)));
+        // Leaving the edge cancels, no action configured on the right
+        assert_eq!(None, state.update_edge_dwell(&edge_actions, (0, 50), start));
+        assert_eq!(
+            None,
+            state.update_edge_dwell(&edge_actions, (50, 50), start)
+        );
+        assert_eq!(None, state.next_edge_dwell_deadline());
+        assert_eq!(
+            None,
+            state.update_edge_dwell(&edge_actions, (1999, 50), start)
+        );
+        assert_eq!(None, state.next_edge_dwell_deadline());
+    }
+
+    #[test]
+    fn workarea_excludes_shown_bar() {
+        let mut state = create_base_state();
+        let bar = state.monitors[0].bar_height;
+        state.monitors[0].show_bar = true;
+        let mon0 = state.monitors[0].dimensions;
+        let mon1 = state.monitors[1].dimensions;
+        assert_eq!(
+            Dimensions::new(mon0.width, mon0.height - bar, mon0.x, mon0.y + bar),
+            state.workarea(0)
+        );
+        assert_eq!(mon1, state.workarea(1));
+        // Not hosted, measured on the focused monitor
+        state.focused_mon = 1;
+        assert_eq!(mon1, state.workarea(2));
+    }
+
+    #[test]
+    fn autohiding_bar_only_reserves_trigger_strip() {
+        let mut state = create_base_state();
+        state.monitors[0].show_bar = true;
+        assert_eq!(state.monitors[0].bar_height, state.bar_reserved_height(0));
+        state.monitors[0].autohide_bar = true;
+        assert_eq!(_AUTOHIDE_BAR_TRIGGER_HEIGHT, state.bar_reserved_height(0));
+        // Same whether shown or hidden, revealing the bar doesn't retile
+        state.monitors[0].show_bar = false;
+        assert_eq!(_AUTOHIDE_BAR_TRIGGER_HEIGHT, state.bar_reserved_height(0));
+        assert_eq!(0, state.bar_reserved_height(1));
+    }
+
+    #[test]
+    fn frame_extents_follow_draw_mode() {
+        let mut state = create_base_state();
+        state.window_border_width = 2;
+        state.monitors[0].tab_bar_height = 10;
+        let properties = test_properties();
+        for window in [15, 16] {
+            state
+                .workspaces
+                .add_child_to_ws(
+                    window,
+                    0,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+        // Not managed
+        assert_eq!([2; 4], state.frame_extents(17));
+        assert_eq!([2; 4], state.frame_extents(15));
+        state.workspaces.set_draw_mode(0, Mode::Tabbed(0));
+        assert_eq!([2, 2, 12, 2], state.frame_extents(15));
+        state.workspaces.set_fullscreened(0, 15).unwrap();
+        assert_eq!([0; 4], state.frame_extents(15));
+        assert_eq!([2, 2, 12, 2], state.frame_extents(16));
+        state
+            .workspaces
+            .get_managed_win_mut(16)
+            .unwrap()
+            .properties
+            .gtk_frame_extents = Some([20, 20, 10, 30]);
+        assert_eq!([0, 0, 10, 0], state.frame_extents(16));
+    }
+
+    #[test]
+    fn checks_invariants() {
+        let mut state = create_base_state();
+        assert_eq!(Ok(()), state.check_invariants());
+        let properties = test_properties();
+        state
+            .workspaces
+            .add_child_to_ws(
+                15,
+                2,
+                ArrangeKind::NoFloat,
+                FocusStyle::Passive,
+                &properties,
+            )
+            .unwrap();
+        assert_eq!(Ok(()), state.check_invariants());
+        state
+            .dying_windows
+            .push(WinMarkedForDeath::new(15, 0))
+            .unwrap();
+        assert_eq!(
+            Err(InvariantViolation::DyingWindowManaged { window: 15 }),
+            state.check_invariants()
+        );
+        state.dying_windows.clear();
+        state.monitors[1].hosted_workspace = 0;
+        assert_eq!(
+            Err(InvariantViolation::WorkspaceHostedTwice {
+                ws_ind: 0,
+                first_mon: 0,
+                second_mon: 1
+            }),
+            state.check_invariants()
+        );
+        state.monitors[1].hosted_workspace = 1;
+        state.focused_mon = 2;
+        assert_eq!(
+            Err(InvariantViolation::FocusedMonitorOutOfRange {
+                focused_mon: 2,
+                num_monitors: 2
+            }),
+            state.check_invariants()
+        );
+        state.focused_mon = 0;
+        state.workspaces.ensure_sets(&USER_WORKSPACES, 2);
+        assert_eq!(
+            Err(InvariantViolation::WorkspaceOutsideMonitorSet {
+                mon_ind: 1,
+                ws_ind: 1
+            }),
+            state.check_invariants()
+        );
+        state.monitors[1].hosted_workspace = state.workspaces.set_size();
+        assert_eq!(Ok(()), state.check_invariants());
+    }
+
     #[test]
     fn will_ignore_sequences() {
         // Wrapping ignores sequences which always increase linearly (not considering wrapping)
@@ -528,4 +1507,548 @@ mod tests {
         // When processing a sequence with a higher number we dropped the lower one the prevent leakage
         assert!(!state.should_ignore_sequence(55));
     }
+
+    #[test]
+    fn tells_wm_unmaps_from_withdrawals() {
+        let mut state = create_base_state();
+        state.push_unmap(1, 10);
+        state.push_unmap(2, 11);
+        state.push_unmap(3, 12);
+        // Another window unmapped by its client while handling the WM's request
+        assert!(!state.is_wm_unmap(4, 10, false));
+        assert!(state.is_wm_unmap(1, 10, false));
+        // Sent to both the window and its parent
+        assert!(state.is_wm_unmap(1, 10, false));
+        // Window 2 was already unmapped, so its unmap never caused an event and is dropped
+        assert!(state.is_wm_unmap(3, 12, false));
+        assert_eq!(vec![(3, 12)], state.pending_unmaps);
+        state.push_unmap(5, u16::MAX);
+        state.push_unmap(5, 0);
+        // Clients withdrawing an unmapped window always count
+        assert!(!state.is_wm_unmap(5, u16::MAX, true));
+        assert!(state.is_wm_unmap(5, 0, false));
+    }
+
+    #[test]
+    fn dying_window_deadline_moves_to_kill_after_destroy() {
+        let mut state = create_base_state();
+        assert!(state.next_dying_window_deadline().is_none());
+        let dying = WinMarkedForDeath::new(1, 0);
+        state.dying_windows.push(dying).unwrap();
+        let destroy_at = state.next_dying_window_deadline().unwrap();
+        assert!(destroy_at <= Instant::now());
+        state.dying_windows[0].sent_destroy = true;
+        let kill_at = state.next_dying_window_deadline().unwrap();
+        assert_eq!(
+            destroy_at
+                .add(Duration::from_millis(CLIENT_WINDOW_KILL_AFTER))
+                .unwrap(),
+            kill_at
+        );
+    }
+
+    #[test]
+    fn drag_snaps_to_added_edges() {
+        let mut drag = DragPosition::new(Dimensions::new(100, 100, 300, 300), 0, 0);
+        // No edges, no snap
+        assert_eq!((250, 250), drag.current_position(-50, -50));
+        drag.add_snap_area(Dimensions::new(1000, 1000, 0, 0));
+        drag.add_snap_y(STATUS_BAR_HEIGHT);
+        let near = FLOATING_SNAP_THRESHOLD / 2;
+        // Left edge snaps to monitor left edge, top edge to the bottom of the bar
+        assert_eq!(
+            (0, STATUS_BAR_HEIGHT),
+            drag.current_position(-300 + near, -300 + STATUS_BAR_HEIGHT + near)
+        );
+        // Right and bottom edges snap to the monitor's right and bottom edges
+        assert_eq!((900, 900), drag.current_position(600 - near, 600 - near));
+        // Outside of threshold nothing happens
+        let far = FLOATING_SNAP_THRESHOLD + 1;
+        assert_eq!((300 + far, 300 + far), drag.current_position(far, far));
+    }
+
+    #[test]
+    fn drop_index_follows_tile_under_pointer() {
+        let mut state = create_base_state();
+        // Nothing tiled, goes first
+        assert_eq!(Some(0), state.tiling_drop_index(1, (1500, 500)).unwrap());
+        let properties = test_properties();
+        for win in 0..2 {
+            state
+                .workspaces
+                .add_child_to_ws(
+                    win,
+                    1,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+        // Left leader, one tile on the left half, one on the right
+        assert_eq!(Some(0), state.tiling_drop_index(1, (1250, 500)).unwrap());
+        assert_eq!(Some(1), state.tiling_drop_index(1, (1750, 500)).unwrap());
+        // Too close to the edges
+        assert_eq!(None, state.tiling_drop_index(1, (1010, 500)).unwrap());
+        assert_eq!(None, state.tiling_drop_index(1, (1500, 990)).unwrap());
+        state.workspaces.set_draw_mode(1, Mode::Tabbed(0));
+        assert_eq!(None, state.tiling_drop_index(1, (1250, 500)).unwrap());
+    }
+
+    #[test]
+    fn fullscreen_spans_requested_monitors() {
+        let mut state = create_base_state();
+        let properties = test_properties();
+        state
+            .workspaces
+            .add_child_to_ws(0, 0, ArrangeKind::NoFloat, FocusStyle::Passive, &properties)
+            .unwrap();
+        let mon0 = state.monitors[0].dimensions;
+        assert_eq!(mon0, state.fullscreen_dimensions(0, 0));
+        state
+            .workspaces
+            .get_managed_win_mut(0)
+            .unwrap()
+            .fullscreen_monitors = Some([0, 0, 0, 1]);
+        assert_eq!(
+            Dimensions::new(2000, 1000, 0, 0),
+            state.fullscreen_dimensions(0, 0)
+        );
+        // Monitors that don't exist fall back to the window's monitor
+        state
+            .workspaces
+            .get_managed_win_mut(0)
+            .unwrap()
+            .fullscreen_monitors = Some([0, 0, 0, 5]);
+        assert_eq!(mon0, state.fullscreen_dimensions(0, 0));
+        // Backwards range
+        state
+            .workspaces
+            .get_managed_win_mut(0)
+            .unwrap()
+            .fullscreen_monitors = Some([0, 0, 1, 0]);
+        assert_eq!(mon0, state.fullscreen_dimensions(0, 0));
+    }
+
+    #[test]
+    fn finds_tab_under_pointer() {
+        let mut state = create_base_state();
+        let mut properties = test_properties();
+        for win in 0..3 {
+            // Ends up in the middle since windows are pushed to the front
+            properties.net_wm_state.skip_taskbar = win == 1;
+            state
+                .workspaces
+                .add_child_to_ws(
+                    win,
+                    1,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+        assert!(state.tabs(1).is_empty());
+        assert_eq!(None, state.tab_at(1, 100));
+        state.workspaces.set_draw_mode(1, Mode::Tabbed(0));
+        assert_eq!(&[0, 2], state.tabs(1).as_slice());
+        assert_eq!(Some(0), state.tab_at(1, 100));
+        assert_eq!(Some(2), state.tab_at(1, 900));
+        // Clamped to the first and last tab when dragged outside
+        assert_eq!(Some(0), state.tab_at(1, -50));
+        assert_eq!(Some(2), state.tab_at(1, 5000));
+        // Shown windows always have a tab
+        state.workspaces.set_draw_mode(1, Mode::Tabbed(1));
+        assert_eq!(&[0, 1, 2], state.tabs(1).as_slice());
+    }
+
+    #[test]
+    fn maximized_dimensions_respects_bar_padding_and_borders() {
+        let mut state = create_base_state();
+        assert_eq!(
+            Dimensions::new(1000, 1000, 1000, 0),
+            state.maximized_dimensions(1)
+        );
+        state.monitors[1].show_bar = true;
+        state.window_padding = 5;
+        state.window_border_width = 2;
+        assert_eq!(
+            Dimensions::new(
+                1000 - 10 - 4,
+                1000 - STATUS_BAR_HEIGHT - 10 - 4,
+                1005,
+                STATUS_BAR_HEIGHT + 5
+            ),
+            state.maximized_dimensions(1)
+        );
+    }
+
+    #[test]
+    fn smart_gaps_only_apply_to_a_lone_tiled_window() {
+        let mut state = create_base_state();
+        state.window_padding = 5;
+        state.window_border_width = 2;
+        let properties = test_properties();
+        for (window, arrange) in [
+            (15, ArrangeKind::NoFloat),
+            (16, ArrangeKind::FloatingInactive(0.0, 0.0)),
+        ] {
+            state
+                .workspaces
+                .add_child_to_ws(window, 0, arrange, FocusStyle::Passive, &properties)
+                .unwrap();
+        }
+        assert!(!state.smart_gaps_apply(0));
+        assert_eq!([2; 4], state.frame_extents(15));
+        state.cfg = alloc::boxed::Box::leak(alloc::boxed::Box::new(Cfg {
+            smart_gaps: true,
+            ..Cfg::DEFAULT
+        }));
+        assert!(state.smart_gaps_apply(0));
+        assert_eq!(0, state.tiled_padding_of(0));
+        assert_eq!(0, state.tiled_border_width_of(0));
+        assert_eq!([0; 4], state.frame_extents(15));
+        // Floating windows keep their border
+        assert_eq!([2; 4], state.frame_extents(16));
+        state
+            .workspaces
+            .add_child_to_ws(
+                17,
+                0,
+                ArrangeKind::NoFloat,
+                FocusStyle::Passive,
+                &properties,
+            )
+            .unwrap();
+        assert!(!state.smart_gaps_apply(0));
+        assert_eq!(5, state.tiled_padding_of(0));
+        assert_eq!([2; 4], state.frame_extents(15));
+    }
+
+    #[test]
+    fn workspaces_override_padding_and_borders() {
+        let mut state = create_base_state();
+        state.window_padding = 5;
+        state.window_border_width = 2;
+        let mut user_workspaces = USER_WORKSPACES;
+        user_workspaces[1] = user_workspaces[1]
+            .clone()
+            .with_padding(0)
+            .with_border_width(1);
+        state.workspaces = Workspaces::create_empty(&user_workspaces).unwrap();
+        assert_eq!(5, state.padding_of(0));
+        assert_eq!(2, state.border_width_of(0));
+        assert_eq!(0, state.padding_of(1));
+        assert_eq!(1, state.border_width_of(1));
+        // Monitor 1 hosts workspace 1
+        assert_eq!(
+            Dimensions::new(1000 - 2, 1000 - 2, 1000, 0),
+            state.maximized_dimensions(1)
+        );
+        state.monitors[1].scale = 2.0;
+        assert_eq!(2, state.border_width_of(1));
+        // Scaled by the monitor the workspace is shown on, the focused one if it isn't shown
+        assert_eq!(5, state.padding_of(0));
+        state.monitors[0].scale = 1.5;
+        assert_eq!(3, state.border_width_of(0));
+        assert_eq!(8, state.padding_of(0));
+        assert_eq!(8, state.padding_of(2));
+    }
+
+    #[test]
+    fn cycle_workspace_skips_workspaces_on_other_monitors() {
+        let mut state = create_base_state();
+        let last = state.workspaces.num_workspaces() - 1;
+        // Monitor 1 hosts workspace 1
+        assert_eq!(Some(2), state.cycle_workspace(0, true));
+        assert_eq!(Some(last), state.cycle_workspace(0, false));
+        state.monitors[0].hosted_workspace = last;
+        assert_eq!(Some(0), state.cycle_workspace(0, true));
+        assert_eq!(Some(last - 1), state.cycle_workspace(0, false));
+    }
+
+    #[test]
+    fn cycle_workspace_stays_within_monitor_set() {
+        let mut state = create_base_state();
+        let set_size = state.workspaces.set_size();
+        state.workspaces.ensure_sets(&USER_WORKSPACES, 2);
+        state.monitors[1].hosted_workspace = set_size;
+        assert_eq!(Some(set_size + 1), state.cycle_workspace(1, true));
+        assert_eq!(Some(2 * set_size - 1), state.cycle_workspace(1, false));
+        // Monitor 1 no longer hosts anything from the first set
+        assert_eq!(Some(1), state.cycle_workspace(0, true));
+        assert_eq!(Some(1), state.monitor_for_workspace(set_size + 3, 0));
+        assert_eq!(Some(0), state.monitor_for_workspace(3, 1));
+        state.workspaces.ensure_sets(&USER_WORKSPACES, 3);
+        assert_eq!(None, state.monitor_for_workspace(2 * set_size, 0));
+    }
+
+    #[test]
+    fn monitor_in_direction_wraps() {
+        let state = create_base_state();
+        assert_eq!(1, state.monitor_in_direction(0, MonitorDirection::Next));
+        assert_eq!(0, state.monitor_in_direction(1, MonitorDirection::Next));
+        assert_eq!(1, state.monitor_in_direction(0, MonitorDirection::Previous));
+        assert_eq!(0, state.monitor_in_direction(1, MonitorDirection::Previous));
+    }
+
+    #[test]
+    fn mouse_action_falls_back_to_section() {
+        let mut state = create_base_state();
+        state.mouse_mapping.insert(
+            MouseActionKey::new(4, 0, MouseTarget::WorkspaceBar),
+            Action::NextWorkspace,
+        );
+        state.mouse_mapping.insert(
+            MouseActionKey::new(1, 0, MouseTarget::WorkspaceBarComponent(2)),
+            Action::ToggleWorkspace(2),
+        );
+        assert_eq!(
+            Some(&Action::NextWorkspace),
+            state.get_mouse_action(4, 0, MouseTarget::WorkspaceBarComponent(2))
+        );
+        assert_eq!(
+            Some(&Action::ToggleWorkspace(2)),
+            state.get_mouse_action(1, 0, MouseTarget::WorkspaceBarComponent(2))
+        );
+        assert!(state
+            .get_mouse_action(1, 0, MouseTarget::WorkspaceBarComponent(3))
+            .is_none());
+        assert!(state
+            .get_mouse_action(4, 0, MouseTarget::ClientWindow)
+            .is_none());
+    }
+
+    #[test]
+    fn shortcut_action_on_plain_left_click() {
+        let mut state = create_base_state();
+        state.monitors[0]
+            .bar_geometry
+            .shortcuts
+            .components
+            .push(ShortcutComponent {
+                position: Line::new(0, 10),
+                write_offset: 0,
+                text: "",
+                action: Action::ToggleBar,
+                hover_text: None,
+            });
+        assert_eq!(
+            Some(&Action::ToggleBar),
+            state.get_shortcut_action(0, 0, 1, LOCK_MODS)
+        );
+        assert!(state.get_shortcut_action(0, 0, 3, 0).is_none());
+        assert!(state
+            .get_shortcut_action(0, 0, 1, ModMask::SHIFT.0)
+            .is_none());
+        assert!(state.get_shortcut_action(0, 1, 1, 0).is_none());
+    }
+
+    #[test]
+    fn monitor_remembers_previous_workspace() {
+        let mut state = create_base_state();
+        let mon = &mut state.monitors[0];
+        assert!(mon.previous_workspace().is_none());
+        // Hosting the same workspace doesn't overwrite history
+        mon.host_workspace(0);
+        assert!(mon.previous_workspace().is_none());
+        mon.host_workspace(3);
+        assert_eq!(3, mon.hosted_workspace);
+        assert_eq!(Some(0), mon.previous_workspace());
+        mon.host_workspace(3);
+        assert_eq!(Some(0), mon.previous_workspace());
+        // Back and forth
+        mon.host_workspace(0);
+        assert_eq!(0, mon.hosted_workspace);
+        assert_eq!(Some(3), mon.previous_workspace());
+        // History is kept most recent first without duplicates or the hosted workspace
+        mon.host_workspace(5);
+        mon.host_workspace(3);
+        assert_eq!(vec![5, 0], mon.recent_workspaces);
+    }
+
+    #[test]
+    fn finds_workspace_after_last_closed() {
+        let mut state = create_base_state();
+        let properties = test_properties();
+        state.monitors[0].recent_workspaces = vec![2, 3, 1];
+        for (window, ws_ind) in [(1, 3), (2, 1)] {
+            state
+                .workspaces
+                .add_child_to_ws(
+                    window,
+                    ws_ind,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+        assert!(state
+            .workspace_after_last_closed(0, LastWindowClosed::Stay)
+            .is_none());
+        // 2 is empty and 1 is hosted on the other monitor
+        assert_eq!(
+            Some(3),
+            state.workspace_after_last_closed(0, LastWindowClosed::MostRecentNonEmpty)
+        );
+        assert_eq!(
+            Some(4),
+            state.workspace_after_last_closed(0, LastWindowClosed::Workspace(4))
+        );
+        assert!(state
+            .workspace_after_last_closed(0, LastWindowClosed::Workspace(0))
+            .is_none());
+        assert!(state
+            .workspace_after_last_closed(0, LastWindowClosed::Workspace(100))
+            .is_none());
+        state
+            .workspaces
+            .add_child_to_ws(3, 0, ArrangeKind::NoFloat, FocusStyle::Passive, &properties)
+            .unwrap();
+        assert!(state
+            .workspace_after_last_closed(0, LastWindowClosed::MostRecentNonEmpty)
+            .is_none());
+    }
+
+    #[test]
+    fn runs_idle_action_once_per_period() {
+        let mut state = create_base_state();
+        let idle_after = Duration::from_secs(600);
+        let now = Instant::now();
+        assert!(!state.update_idle(idle_after, Duration::from_secs(60), now));
+        assert_eq!(now.add(Duration::from_secs(540)), state.next_idle_check);
+        assert!(state.update_idle(idle_after, Duration::from_secs(600), now));
+        assert_eq!(
+            now.add(Duration::from_millis(_IDLE_RECHECK_MS)),
+            state.next_idle_check
+        );
+        // Still the same period
+        assert!(!state.update_idle(idle_after, Duration::from_secs(700), now));
+        // Input resumed, then idle again
+        assert!(!state.update_idle(idle_after, Duration::from_secs(1), now));
+        assert!(state.update_idle(idle_after, Duration::from_secs(600), now));
+        // An inhibited period doesn't run, not even once the inhibit is lifted
+        assert!(!state.update_idle(idle_after, Duration::from_secs(1), now));
+        state.idle_inhibited = true;
+        assert!(!state.update_idle(idle_after, Duration::from_secs(600), now));
+        state.idle_inhibited = false;
+        assert!(!state.update_idle(idle_after, Duration::from_secs(700), now));
+        // Disabled
+        assert!(!state.update_idle(Duration::ZERO, Duration::from_secs(700), now));
+        assert!(state.next_idle_check.is_none());
+    }
+
+    #[test]
+    fn resets_screensaver_while_fullscreen_is_shown() {
+        let mut state = create_base_state();
+        let reset_every = Duration::from_secs(30);
+        let now = Instant::now();
+        assert!(!state.update_screensaver_reset(reset_every, now));
+        // Fullscreened on a workspace that isn't shown
+        state.workspaces.set_fullscreened(2, 5).unwrap();
+        assert!(!state.update_screensaver_reset(reset_every, now));
+        state.workspaces.set_fullscreened(1, 6).unwrap();
+        assert!(state.update_screensaver_reset(reset_every, now));
+        assert_eq!(now.add(reset_every), state.next_screensaver_reset);
+        assert!(!state.update_screensaver_reset(reset_every, now));
+        assert!(state.update_screensaver_reset(reset_every, now.add(reset_every).unwrap()));
+        assert!(!state.update_screensaver_reset(Duration::ZERO, now));
+        assert!(state.next_screensaver_reset.is_none());
+        state.workspaces.unset_fullscreened(1);
+        assert!(!state.update_screensaver_reset(reset_every, now));
+        assert!(state.next_screensaver_reset.is_none());
+    }
+
+    #[test]
+    fn ignores_repeats_of_mappings_without_repeat() {
+        let mut state = create_base_state();
+        state.key_mapping.insert(
+            KeyBoardMappingKey::new(10, ModMask::FOUR.0),
+            KeyboardMapping::new(ModMask::FOUR, 0x66, Action::ToggleFullscreen).without_repeat(),
+        );
+        state.key_mapping.insert(
+            KeyBoardMappingKey::new(11, ModMask::FOUR.0),
+            KeyboardMapping::new(ModMask::FOUR, 0x6a, Action::FocusNextWindow),
+        );
+        let mods = ModMask::FOUR.0;
+        assert_eq!(
+            Some(&Action::ToggleFullscreen),
+            state.get_key_action(10, mods, 100)
+        );
+        // Held, the server sends a release and a press at the same time
+        state.last_key_release = Some((10, 600));
+        assert_eq!(None, state.get_key_action(10, mods, 600));
+        // Released and pressed again
+        assert_eq!(
+            Some(&Action::ToggleFullscreen),
+            state.get_key_action(10, mods, 800)
+        );
+        state.last_key_release = Some((11, 600));
+        assert_eq!(
+            Some(&Action::FocusNextWindow),
+            state.get_key_action(11, mods, 600)
+        );
+    }
+
+    #[test]
+    fn tracks_popups_per_monitor() {
+        let mut state = create_base_state();
+        state.track_popup(10, (10, 10));
+        state.track_popup(11, (1500, 10));
+        assert!(state.popup_open_on(0));
+        assert!(state.popup_open_on(1));
+        // Moved when mapped again
+        state.track_popup(11, (20, 10));
+        state.untrack_popup(10);
+        assert!(state.popup_open_on(0));
+        assert!(!state.popup_open_on(1));
+        state.untrack_popup(11);
+        assert!(!state.popup_open_on(0));
+        // Off screen
+        state.track_popup(12, (-5000, 0));
+        assert!(state.popups.is_empty());
+    }
+
+    #[test]
+    fn title_notice_expires() {
+        let mut state = create_base_state();
+        let now = Instant::now();
+        assert!(state.show_title_notice("Failed", 1, now).is_none());
+        assert_eq!(Some("Failed"), state.title_notice_on(1));
+        assert!(state.title_notice_on(0).is_none());
+        assert!(state.take_expired_title_notice(now).is_none());
+        let replaced = state.show_title_notice("Failed again", 0, now).unwrap();
+        assert_eq!(1, replaced.mon_ind);
+        let expiry = now.add(Duration::from_millis(TITLE_NOTICE_MS)).unwrap();
+        let expired = state.take_expired_title_notice(expiry).unwrap();
+        assert_eq!("Failed again", expired.text.as_str());
+        assert!(state.title_notice.is_none());
+    }
+
+    #[test]
+    fn declined_focus_requests_are_due_after_a_delay() {
+        let mut state = create_base_state();
+        state.screen.root = 5;
+        let now = Instant::now();
+        state.input_focus = Some(11);
+        state.request_focus_verification(10, Some(9), now);
+        state.request_focus_verification(11, Some(10), now);
+        assert!(state.take_due_focus_request(now).is_none());
+        let due = now.add(Duration::from_millis(FOCUS_VERIFY_MS)).unwrap();
+        let request = state.take_due_focus_request(due).unwrap();
+        assert_eq!(11, request.window);
+        assert!(state.pending_focus.is_none());
+        for focus in [0, 1, 5, 10] {
+            assert!(state.focus_declined(&request, focus));
+        }
+        // Handed to the window itself or one of its children
+        assert!(!state.focus_declined(&request, 11));
+        assert!(!state.focus_declined(&request, 12));
+        // Focus moved on before the check
+        state.request_focus_verification(11, Some(10), now);
+        state.input_focus = Some(12);
+        assert!(state.take_due_focus_request(due).is_none());
+        assert!(state.pending_focus.is_none());
+    }
 }
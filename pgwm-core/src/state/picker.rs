@@ -0,0 +1,268 @@
+use alloc::vec::Vec;
+
+use x11_keysyms::{XK_BackSpace, XK_Down, XK_Escape, XK_Return, XK_Tab, XK_Up};
+use xcb_rust_protocol::proto::xproto::Window;
+
+use crate::config::key_map::KeysymTable;
+use crate::config::WINDOW_PICKER_LABEL;
+use crate::config::{_WINDOW_PICKER_FILTER_LIMIT, _WM_NAME_LIMIT};
+use crate::format_heapless;
+use crate::state::workspace::Workspaces;
+
+/// An open window picker, lists all managed windows filtered by what's been typed
+pub struct WindowPicker {
+    /// The monitor whose bar the picker is drawn on
+    pub mon_ind: usize,
+    candidates: Vec<PickerCandidate>,
+    filter: heapless::String<_WINDOW_PICKER_FILTER_LIMIT>,
+    selected: usize,
+    keysyms: KeysymTable,
+}
+
+#[derive(Debug, Clone)]
+pub struct PickerCandidate {
+    pub window: Window,
+    pub label: heapless::String<_WM_NAME_LIMIT>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PickerEvent {
+    Ignore,
+    Redraw,
+    Cancel,
+    Select(Window),
+}
+
+impl WindowPicker {
+    #[must_use]
+    pub fn new(mon_ind: usize, candidates: Vec<PickerCandidate>, keysyms: KeysymTable) -> Self {
+        Self {
+            mon_ind,
+            candidates,
+            filter: heapless::String::new(),
+            selected: 0,
+            keysyms,
+        }
+    }
+
+    /// Create a picker listing every managed window as its workspace followed by [`WINDOW_PICKER_LABEL`],
+    /// except for those that ask to be skipped by taskbars or pagers
+    #[must_use]
+    pub fn from_workspaces(workspaces: &Workspaces, mon_ind: usize, keysyms: KeysymTable) -> Self {
+        let mut candidates = Vec::new();
+        for ws_ind in 0..workspaces.num_workspaces() {
+            let ws_name = workspaces.get_ws(ws_ind).name;
+            for mw in workspaces.iter_all_managed_windows_in_ws(ws_ind) {
+                let net_wm_state = mw.properties.net_wm_state;
+                if net_wm_state.skip_taskbar || net_wm_state.skip_pager {
+                    continue;
+                }
+                candidates.push(PickerCandidate {
+                    window: mw.window,
+                    label: format_heapless!(
+                        "{ws_name} {}",
+                        mw.properties.label(WINDOW_PICKER_LABEL)
+                    ),
+                });
+            }
+        }
+        Self::new(mon_ind, candidates, keysyms)
+    }
+
+    pub fn matching(&self) -> impl Iterator<Item = &PickerCandidate> {
+        self.candidates
+            .iter()
+            .filter(|c| contains_ignore_ascii_case(&c.label, &self.filter))
+    }
+
+    /// Handle a pressed key, the keycode is resolved to a keysym through the keyboard mapping
+    pub fn handle_key(&mut self, code: u8, shifted: bool) -> PickerEvent {
+        self.keysyms
+            .keysym(code, shifted)
+            .map_or(PickerEvent::Ignore, |sym| self.handle_keysym(sym))
+    }
+
+    pub fn handle_keysym(&mut self, keysym: u32) -> PickerEvent {
+        match keysym {
+            XK_Escape => PickerEvent::Cancel,
+            XK_Return => self
+                .matching()
+                .nth(self.selected)
+                .map_or(PickerEvent::Cancel, |c| PickerEvent::Select(c.window)),
+            XK_BackSpace => {
+                if self.filter.pop().is_some() {
+                    self.selected = 0;
+                    PickerEvent::Redraw
+                } else {
+                    PickerEvent::Ignore
+                }
+            }
+            XK_Down | XK_Tab => self.move_selection(true),
+            XK_Up => self.move_selection(false),
+            // Latin-1 printable keysyms map directly to their ascii representation
+            0x20..=0x7e => {
+                if self.filter.push(keysym as u8 as char).is_ok() {
+                    self.selected = 0;
+                    PickerEvent::Redraw
+                } else {
+                    PickerEvent::Ignore
+                }
+            }
+            _ => PickerEvent::Ignore,
+        }
+    }
+
+    fn move_selection(&mut self, forward: bool) -> PickerEvent {
+        let num_matching = self.matching().count();
+        if num_matching == 0 {
+            return PickerEvent::Ignore;
+        }
+        self.selected = if forward {
+            (self.selected + 1) % num_matching
+        } else {
+            (self.selected + num_matching - 1) % num_matching
+        };
+        PickerEvent::Redraw
+    }
+
+    /// What to draw in the bar, the filter followed by matching windows, the selected one in brackets
+    #[must_use]
+    pub fn display(&self) -> heapless::String<_WM_NAME_LIMIT> {
+        let mut display: heapless::String<_WM_NAME_LIMIT> = format_heapless!("{}> ", self.filter);
+        for (ind, candidate) in self.matching().enumerate() {
+            let written = if ind == self.selected {
+                core::fmt::write(&mut display, format_args!("[{}] ", candidate.label))
+            } else {
+                core::fmt::write(&mut display, format_args!("{} ", candidate.label))
+            };
+            if written.is_err() {
+                break;
+            }
+        }
+        display
+    }
+}
+
+fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    needle.is_empty()
+        || haystack
+            .as_bytes()
+            .windows(needle.len())
+            .any(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use x11_keysyms::{XK_BackSpace, XK_Down, XK_Escape, XK_Return, XK_Up, XK_a, XK_f};
+
+    use crate::config::key_map::KeysymTable;
+    use crate::config::USER_WORKSPACES;
+    use crate::state::picker::{PickerCandidate, PickerEvent, WindowPicker};
+    use crate::state::properties::{WindowProperties, WmName};
+    use crate::state::workspace::{ArrangeKind, FocusStyle, Workspaces};
+
+    fn picker() -> WindowPicker {
+        let candidates = [
+            "1 Alacritty term",
+            "2 firefox Web Browser",
+            "2 Alacritty vim",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(ind, label)| PickerCandidate {
+            window: ind as u32,
+            label: heapless::String::try_from(*label).unwrap(),
+        })
+        .collect();
+        WindowPicker::new(0, candidates, KeysymTable::new(vec![], 1, 8))
+    }
+
+    fn matching_windows(picker: &WindowPicker) -> Vec<u32> {
+        picker.matching().map(|c| c.window).collect()
+    }
+
+    #[test]
+    fn filters_case_insensitive() {
+        let mut picker = picker();
+        assert_eq!(vec![0, 1, 2], matching_windows(&picker));
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_a));
+        assert_eq!(vec![0, 2], matching_windows(&picker));
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_BackSpace));
+        assert_eq!(PickerEvent::Ignore, picker.handle_keysym(XK_BackSpace));
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_f));
+        assert_eq!(vec![1], matching_windows(&picker));
+        assert_eq!(PickerEvent::Select(1), picker.handle_keysym(XK_Return));
+    }
+
+    #[test]
+    fn selection_wraps_and_resets_on_filter() {
+        let mut picker = picker();
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_Up));
+        assert_eq!(PickerEvent::Select(2), picker.handle_keysym(XK_Return));
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_Down));
+        assert_eq!(PickerEvent::Select(0), picker.handle_keysym(XK_Return));
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_Down));
+        assert_eq!(PickerEvent::Redraw, picker.handle_keysym(XK_a));
+        assert_eq!(PickerEvent::Select(0), picker.handle_keysym(XK_Return));
+        assert_eq!(PickerEvent::Cancel, picker.handle_keysym(XK_Escape));
+    }
+
+    #[test]
+    fn no_match_cancels() {
+        let mut picker = picker();
+        for sym in "zzz".bytes() {
+            picker.handle_keysym(sym as u32);
+        }
+        assert_eq!(0, picker.matching().count());
+        assert_eq!(PickerEvent::Ignore, picker.handle_keysym(XK_Down));
+        assert_eq!(PickerEvent::Cancel, picker.handle_keysym(XK_Return));
+    }
+
+    #[test]
+    fn displays_filter_and_selection() {
+        let mut picker = picker();
+        picker.handle_keysym(XK_a);
+        picker.handle_keysym(XK_Down);
+        assert_eq!("a> 1 Alacritty term [2 Alacritty vim] ", picker.display());
+    }
+
+    #[test]
+    fn from_workspaces_skips_taskbar_and_pager_windows() {
+        let mut workspaces = Workspaces::create_empty(&USER_WORKSPACES).unwrap();
+        let mut properties = WindowProperties::new(
+            None,
+            Default::default(),
+            None,
+            None,
+            Default::default(),
+            None,
+            None,
+            Default::default(),
+            Default::default(),
+            WmName::NetWmName(Default::default()),
+            None,
+            Default::default(),
+            None,
+        );
+        for (window, skip_taskbar, skip_pager) in
+            [(15, false, false), (16, true, false), (17, false, true)]
+        {
+            properties.net_wm_state.skip_taskbar = skip_taskbar;
+            properties.net_wm_state.skip_pager = skip_pager;
+            workspaces
+                .add_child_to_ws(
+                    window,
+                    0,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &properties,
+                )
+                .unwrap();
+        }
+        let picker = WindowPicker::from_workspaces(&workspaces, 0, KeysymTable::new(vec![], 1, 8));
+        assert_eq!(vec![15], matching_windows(&picker));
+    }
+}
@@ -0,0 +1,242 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use tiny_std::io::Write;
+use tiny_std::UnixStr;
+
+use crate::config::_WM_CLASS_NAME_LIMIT;
+use crate::error::Result;
+use crate::state::workspace::FloatingGeometry;
+
+/// Where the user last put a window of some class
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Placement {
+    /// Index of the workspace in its set, so that it means the same thing for every monitor's set
+    pub workspace: usize,
+    /// Where the window was left floating, `None` if it was tiled
+    pub floating: Option<FloatingGeometry>,
+}
+
+/// The latest [`Placement`] of up to `capacity` window classes, the least recently placed class
+/// is forgotten first. Kept in a file so that it outlives the WM.
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct PlacementHistory {
+    // Least recently placed first
+    entries: Vec<(heapless::String<_WM_CLASS_NAME_LIMIT>, Placement)>,
+    capacity: usize,
+}
+
+impl PlacementHistory {
+    /// An empty history, nothing is ever remembered if `capacity` is 0
+    #[must_use]
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+        }
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    #[must_use]
+    pub fn get(&self, class: &str) -> Option<Placement> {
+        self.entries
+            .iter()
+            .find(|(cls, _)| cls.as_str() == class)
+            .map(|(_, placement)| *placement)
+    }
+
+    /// Remembers where a window of `class` was put, returns whether anything changed
+    pub fn record(&mut self, class: &str, placement: Placement) -> bool {
+        // Tabs and newlines would break the file format, no sane class has them
+        if !self.is_enabled() || class.is_empty() || class.contains(['\t', '\n']) {
+            return false;
+        }
+        let Ok(class) = heapless::String::try_from(class) else {
+            return false;
+        };
+        if let Some(ind) = self.entries.iter().position(|(cls, _)| *cls == class) {
+            let (_, previous) = self.entries.remove(ind);
+            if previous == placement && ind == self.entries.len() {
+                self.entries.push((class, placement));
+                return false;
+            }
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push((class, placement));
+        true
+    }
+
+    /// `$XDG_STATE_HOME/pgwm/placements`, or `$HOME/.local/state/pgwm/placements` if `XDG_STATE_HOME` isn't set
+    #[must_use]
+    pub fn dir_path() -> Option<String> {
+        if let Ok(state_home) = tiny_std::env::var("XDG_STATE_HOME") {
+            return Some(format!("{state_home}/pgwm"));
+        }
+        tiny_std::env::var("HOME")
+            .ok()
+            .map(|home| format!("{home}/.local/state/pgwm"))
+    }
+
+    /// The history kept in the history file, empty if there is none or if it's disabled.
+    /// Lines that can't be read are skipped, it's better to forget a placement than to not start.
+    #[must_use]
+    pub fn load(capacity: usize) -> Self {
+        let mut history = Self::new(capacity);
+        if !history.is_enabled() {
+            return history;
+        }
+        let Some(dir) = Self::dir_path() else {
+            return history;
+        };
+        let path = format!("{dir}/placements\0");
+        if let Some(content) = UnixStr::try_from_str(&path)
+            .ok()
+            .and_then(|path| tiny_std::fs::read(path).ok())
+        {
+            history.parse(&String::from_utf8_lossy(&content));
+        }
+        history
+    }
+
+    /// Writes the history to the history file, creating its directory if needed
+    pub fn save(&self) -> Result<()> {
+        let Some(dir) = Self::dir_path() else {
+            return Ok(());
+        };
+        let terminated_dir = format!("{dir}\0");
+        let terminated_path = format!("{dir}/placements\0");
+        let (Ok(dir), Ok(path)) = (
+            UnixStr::try_from_str(&terminated_dir),
+            UnixStr::try_from_str(&terminated_path),
+        ) else {
+            return Ok(());
+        };
+        tiny_std::fs::create_dir_all(dir)?;
+        let mut file = tiny_std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(self.serialize().as_bytes())?;
+        Ok(())
+    }
+
+    /// One line per class, least recently placed first, as `class workspace [rel_x rel_y width height]`
+    /// separated by tabs
+    #[must_use]
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        for (class, placement) in &self.entries {
+            let _ = write!(out, "{class}\t{}", placement.workspace);
+            if let Some(floating) = placement.floating {
+                let _ = write!(
+                    out,
+                    "\t{}\t{}\t{}\t{}",
+                    floating.rel_x, floating.rel_y, floating.width, floating.height
+                );
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Records every valid line of what [`PlacementHistory::serialize`] produces, in order
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let mut fields = line.split('\t');
+            let (Some(class), Some(Ok(workspace))) =
+                (fields.next(), fields.next().map(str::parse::<usize>))
+            else {
+                continue;
+            };
+            let floating = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (None, ..) => None,
+                (Some(rel_x), Some(rel_y), Some(width), Some(height)) => {
+                    match (rel_x.parse(), rel_y.parse(), width.parse(), height.parse()) {
+                        (Ok(rel_x), Ok(rel_y), Ok(width), Ok(height)) => Some(FloatingGeometry {
+                            rel_x,
+                            rel_y,
+                            width,
+                            height,
+                        }),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            self.record(
+                class,
+                Placement {
+                    workspace,
+                    floating,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::placement::{Placement, PlacementHistory};
+    use crate::state::workspace::FloatingGeometry;
+
+    const TILED: Placement = Placement {
+        workspace: 3,
+        floating: None,
+    };
+    const FLOATING: Placement = Placement {
+        workspace: 1,
+        floating: Some(FloatingGeometry {
+            rel_x: 0.25,
+            rel_y: 0.5,
+            width: 800,
+            height: 600,
+        }),
+    };
+
+    #[test]
+    fn forgets_least_recently_placed() {
+        let mut history = PlacementHistory::new(2);
+        assert!(history.record("firefox", TILED));
+        assert!(history.record("gimp", FLOATING));
+        // Placed again where it already was and most recent, nothing to save
+        assert!(!history.record("gimp", FLOATING));
+        // Moves firefox to most recent
+        assert!(history.record("firefox", FLOATING));
+        assert!(history.record("mpv", TILED));
+        assert_eq!(None, history.get("gimp"));
+        assert_eq!(Some(FLOATING), history.get("firefox"));
+        assert_eq!(Some(TILED), history.get("mpv"));
+
+        let mut disabled = PlacementHistory::new(0);
+        assert!(!disabled.record("firefox", TILED));
+        assert_eq!(None, disabled.get("firefox"));
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let mut history = PlacementHistory::new(8);
+        history.record("firefox", TILED);
+        history.record("gimp", FLOATING);
+        let content = history.serialize();
+        assert_eq!("firefox\t3\ngimp\t1\t0.25\t0.5\t800\t600\n", content);
+        let mut parsed = PlacementHistory::new(8);
+        parsed.parse(&content);
+        assert_eq!(history, parsed);
+
+        let mut parsed = PlacementHistory::new(8);
+        parsed.parse("broken\nfirefox\t3\ngimp\t1\t0.25\nmpv\tx\n");
+        assert_eq!(Some(TILED), parsed.get("firefox"));
+        assert_eq!(None, parsed.get("gimp"));
+        assert_eq!(None, parsed.get("mpv"));
+    }
+}
@@ -2,6 +2,7 @@ use xcb_rust_protocol::helpers::properties::{WmHints, WmSizeHints};
 use xcb_rust_protocol::proto::xproto::Window;
 
 use crate::config::{_WM_CLASS_NAME_LIMIT, _WM_NAME_LIMIT};
+use crate::geometry::Dimensions;
 
 #[derive(Debug, Clone)]
 pub struct WindowProperties {
@@ -16,6 +17,11 @@ pub struct WindowProperties {
     pub protocols: heapless::Vec<Protocol, 4>,
     pub name: WmName,
     pub transient_for: Option<Window>,
+    /// Subwindows whose colormaps need installing when the window is focused, highest priority first
+    pub colormap_windows: heapless::Vec<Window, 8>,
+    /// The invisible shadow a client-side-decorated window draws around itself as left, right, top, bottom,
+    /// from `_GTK_FRAME_EXTENTS`
+    pub gtk_frame_extents: Option<[u32; 4]>,
 }
 
 impl WindowProperties {
@@ -32,6 +38,8 @@ impl WindowProperties {
         protocols: heapless::Vec<Protocol, 4>,
         name: WmName,
         transient_for: Option<Window>,
+        colormap_windows: heapless::Vec<Window, 8>,
+        gtk_frame_extents: Option<[u32; 4]>,
     ) -> Self {
         Self {
             wm_state,
@@ -45,8 +53,53 @@ impl WindowProperties {
             protocols,
             name,
             transient_for,
+            colormap_windows,
+            gtk_frame_extents,
         }
     }
+
+    /// Where to put a client-side-decorated window so that what's visible of it covers `dimensions`
+    /// and the border around them, its shadow spilling over into the padding.
+    /// `None` if it doesn't draw a shadow
+    #[must_use]
+    pub fn csd_dimensions(&self, dimensions: Dimensions, border_width: u32) -> Option<Dimensions> {
+        let [left, right, top, bottom] = self.gtk_frame_extents?;
+        let border = border_width as i16;
+        Some(Dimensions {
+            width: dimensions.width + border * 2 + left as i16 + right as i16,
+            height: dimensions.height + border * 2 + top as i16 + bottom as i16,
+            x: dimensions.x - left as i16,
+            y: dimensions.y - top as i16,
+        })
+    }
+
+    /// Fill in a label template, `{title}` is replaced by the window's name, `{instance}` and `{class}`
+    /// by the first and last part of its `WM_CLASS`, anything else is kept as is
+    #[must_use]
+    pub fn label(&self, template: &str) -> heapless::String<_WM_NAME_LIMIT> {
+        let title = self.name.as_str();
+        let instance = self.class.first().map_or("", |c| c.as_str());
+        let class = self.class.last().map_or("", |c| c.as_str());
+        let mut label = heapless::String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let _ = label.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let (value, placeholder) = if rest.starts_with("{title}") {
+                (title, "{title}")
+            } else if rest.starts_with("{instance}") {
+                (instance, "{instance}")
+            } else if rest.starts_with("{class}") {
+                (class, "{class}")
+            } else {
+                ("{", "{")
+            };
+            let _ = label.push_str(value);
+            rest = &rest[placeholder.len()..];
+        }
+        let _ = label.push_str(rest);
+        label
+    }
 }
 
 /// [`_NET_WM_STATE`](https://specifications.freedesktop.org/wm-spec/1.3/ar01s05.html)
@@ -148,4 +201,75 @@ impl WmName {
             WmName::NetWmName(n) | WmName::WmName(n) => n.clone(),
         }
     }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            WmName::NetWmName(n) | WmName::WmName(n) => n.as_str(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Dimensions;
+    use crate::state::properties::{NetWmState, WindowProperties, WmName};
+
+    #[test]
+    fn places_csd_shadows_outside_the_tile() {
+        let mut properties = WindowProperties::new(
+            None,
+            NetWmState::default(),
+            None,
+            None,
+            heapless::Vec::new(),
+            None,
+            None,
+            heapless::Vec::new(),
+            heapless::Vec::new(),
+            WmName::NetWmName(heapless::String::new()),
+            None,
+            heapless::Vec::new(),
+            None,
+        );
+        let tile = Dimensions::new(400, 300, 10, 20);
+        assert_eq!(None, properties.csd_dimensions(tile, 2));
+        properties.gtk_frame_extents = Some([20, 20, 10, 30]);
+        // The visible part covers the tile and its 2 pixel border
+        assert_eq!(
+            Some(Dimensions::new(444, 344, -10, 10)),
+            properties.csd_dimensions(tile, 2)
+        );
+    }
+
+    #[test]
+    fn fills_label_templates() {
+        let mut class = heapless::Vec::new();
+        let _ = class.push(heapless::String::try_from("xterm").unwrap());
+        let _ = class.push(heapless::String::try_from("XTerm").unwrap());
+        let properties = WindowProperties::new(
+            None,
+            NetWmState::default(),
+            None,
+            None,
+            heapless::Vec::new(),
+            None,
+            None,
+            class,
+            heapless::Vec::new(),
+            WmName::NetWmName(heapless::String::try_from("~/crate").unwrap()),
+            None,
+            heapless::Vec::new(),
+            None,
+        );
+        assert_eq!(
+            "XTerm: ~/crate",
+            properties.label("{class}: {title}").as_str()
+        );
+        assert_eq!(
+            "xterm {unknown} {",
+            properties.label("{instance} {unknown} {").as_str()
+        );
+        assert_eq!("~/crate", properties.label("{title}").as_str());
+    }
 }
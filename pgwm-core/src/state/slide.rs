@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+use core::ops::Add;
+use core::time::Duration;
+
+use tiny_std::time::Instant;
+use xcb_rust_protocol::proto::xproto::Window;
+
+/// Windows of a workspace that was just switched to sliding into place, drawn `offset` pixels to the side
+/// and moved back a bit every frame
+pub struct WorkspaceSlide {
+    mon_ind: usize,
+    offset: i32,
+    frames: usize,
+    frame: usize,
+    /// Windows that stay put, like pinned windows that were already shown
+    still: Vec<Window>,
+    /// Where each window drawn while sliding ends up
+    targets: Vec<(Window, i32, i32)>,
+    next_frame: Option<Instant>,
+}
+
+impl WorkspaceSlide {
+    /// A slide on a monitor over `frames` frames, the first one due after `frame_time`
+    #[must_use]
+    pub fn new(
+        mon_ind: usize,
+        offset: i32,
+        frames: usize,
+        still: Vec<Window>,
+        now: Instant,
+        frame_time: Duration,
+    ) -> Self {
+        Self {
+            mon_ind,
+            offset,
+            frames,
+            frame: 0,
+            still,
+            targets: Vec::new(),
+            next_frame: now.add(frame_time),
+        }
+    }
+
+    /// Where to draw a window that should end up at `(x, y)`, remembering it so that it can be moved
+    /// there over the coming frames. A window drawn on another monitor is no longer moved.
+    pub fn place(&mut self, mon_ind: usize, window: Window, x: i32, y: i32) -> (i32, i32) {
+        if mon_ind != self.mon_ind {
+            self.forget(window);
+            return (x, y);
+        }
+        if self.still.contains(&window) {
+            return (x, y);
+        }
+        if let Some(target) = self.targets.iter_mut().find(|(win, ..)| *win == window) {
+            *target = (window, x, y);
+        } else {
+            self.targets.push((window, x, y));
+        }
+        (x + self.current_offset(), y)
+    }
+
+    /// Stop moving a window, like when it's no longer managed
+    pub fn forget(&mut self, window: Window) {
+        self.targets.retain(|(win, ..)| *win != window);
+    }
+
+    #[must_use]
+    pub fn next_frame(&self) -> Option<Instant> {
+        self.next_frame
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.next_frame.is_none()
+    }
+
+    /// Where to move the sliding windows if a frame is due, the last frame puts them where they end up
+    pub fn take_due_frame(
+        &mut self,
+        now: Instant,
+        frame_time: Duration,
+    ) -> Vec<(Window, i32, i32)> {
+        if self.next_frame.filter(|due| *due <= now).is_none() {
+            return Vec::new();
+        }
+        self.frame += 1;
+        self.next_frame = if self.frame < self.frames {
+            now.add(frame_time)
+        } else {
+            None
+        };
+        self.positions()
+    }
+
+    /// Skip to the end, where to move the sliding windows so that they're where they end up
+    pub fn finish(&mut self) -> Vec<(Window, i32, i32)> {
+        self.frame = self.frames;
+        self.next_frame = None;
+        self.positions()
+    }
+
+    fn positions(&self) -> Vec<(Window, i32, i32)> {
+        let offset = self.current_offset();
+        self.targets
+            .iter()
+            .map(|(window, x, y)| (*window, x + offset, *y))
+            .collect()
+    }
+
+    fn current_offset(&self) -> i32 {
+        if self.frame >= self.frames {
+            0
+        } else {
+            self.offset * (self.frames - self.frame) as i32 / self.frames as i32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use core::ops::Add;
+    use core::time::Duration;
+
+    use tiny_std::time::Instant;
+
+    use crate::state::slide::WorkspaceSlide;
+
+    #[test]
+    fn slides_into_place() {
+        let frame_time = Duration::from_millis(10);
+        let now = Instant::now();
+        let mut slide = WorkspaceSlide::new(1, 100, 4, vec![3], now, frame_time);
+        assert_eq!((150, 20), slide.place(1, 1, 50, 20));
+        // Other monitors and still windows are drawn where they end up
+        assert_eq!((50, 20), slide.place(0, 2, 50, 20));
+        assert_eq!((50, 20), slide.place(1, 3, 50, 20));
+        assert_eq!((170, 20), slide.place(1, 4, 70, 20));
+        // Moved to another monitor before it's done
+        assert_eq!((70, 20), slide.place(0, 4, 70, 20));
+        assert!(slide.take_due_frame(now, frame_time).is_empty());
+        let mut due = slide.next_frame().unwrap();
+        assert_eq!(vec![(1, 125, 20)], slide.take_due_frame(due, frame_time));
+        // Drawn again while sliding, at the current offset
+        assert_eq!((85, 20), slide.place(1, 1, 10, 20));
+        for expected in [60, 35, 10] {
+            due = slide.next_frame().unwrap();
+            assert_eq!(
+                vec![(1, expected, 20)],
+                slide.take_due_frame(due, frame_time)
+            );
+        }
+        assert!(slide.is_finished());
+        assert!(slide
+            .take_due_frame(due.add(frame_time).unwrap(), frame_time)
+            .is_empty());
+    }
+
+    #[test]
+    fn finishes_early() {
+        let now = Instant::now();
+        let mut slide = WorkspaceSlide::new(0, -100, 4, vec![], now, Duration::from_millis(10));
+        assert_eq!((-90, 0), slide.place(0, 1, 10, 0));
+        slide.forget(1);
+        assert_eq!((-100, 0), slide.place(0, 2, 0, 0));
+        assert_eq!(vec![(2, 0, 0)], slide.finish());
+        assert!(slide.is_finished());
+    }
+}
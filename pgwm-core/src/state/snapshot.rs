@@ -0,0 +1,452 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use xcb_rust_protocol::proto::xproto::Window;
+
+use crate::error::{Error, Result};
+use crate::geometry::draw::{Mode, OldDrawMode};
+use crate::geometry::layout::Layout;
+use crate::geometry::Dimensions;
+use crate::state::workspace::ArrangeKind;
+use crate::state::State;
+
+/// Start of every encoded snapshot, followed by [`SNAPSHOT_VERSION`]
+const SNAPSHOT_MAGIC: &[u8; 4] = b"PGWS";
+/// Bumped whenever the encoding changes, older snapshots are rejected instead of misread
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// A copy of what's shown where: the monitors, the workspaces, and the windows on them.
+/// Encoded into a compact byte buffer with [`StateSnapshot::encode`], so that persisting the layout over a restart,
+/// crash dumps, and state queries can share one format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSnapshot {
+    pub focused_mon: usize,
+    pub input_focus: Option<Window>,
+    pub monitors: Vec<MonitorSnapshot>,
+    pub workspaces: Vec<WorkspaceSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorSnapshot {
+    pub dimensions: Dimensions,
+    pub hosted_workspace: usize,
+    pub last_focus: Option<Window>,
+    pub show_bar: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceSnapshot {
+    pub name: String,
+    pub draw_mode: Mode,
+    /// Top level windows first in tiling order, each directly followed by the windows attached to it
+    pub windows: Vec<WindowSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowSnapshot {
+    pub window: Window,
+    /// The top level window this one is attached to, like a dialog to its main window
+    pub attached_to: Option<Window>,
+    pub arrange: ArrangeKind,
+    pub pinned_monitor: Option<usize>,
+    /// The first `WM_CLASS` name, empty if it has none
+    pub class: String,
+    pub name: String,
+}
+
+impl StateSnapshot {
+    #[must_use]
+    pub fn capture(state: &State) -> Self {
+        let monitors = state
+            .monitors
+            .iter()
+            .map(|mon| MonitorSnapshot {
+                dimensions: mon.dimensions,
+                hosted_workspace: mon.hosted_workspace,
+                last_focus: mon.last_focus,
+                show_bar: mon.show_bar,
+            })
+            .collect();
+        let workspaces = (0..state.workspaces.num_workspaces())
+            .map(|ws_ind| {
+                let ws = state.workspaces.get_ws(ws_ind);
+                let mut windows = Vec::new();
+                for child in &ws.children {
+                    let root = child.managed.window;
+                    for (managed, attached_to) in core::iter::once((&child.managed, None))
+                        .chain(child.attached.iter().map(|att| (att, Some(root))))
+                    {
+                        windows.push(WindowSnapshot {
+                            window: managed.window,
+                            attached_to,
+                            arrange: managed.arrange,
+                            pinned_monitor: managed.pinned_monitor,
+                            class: managed
+                                .properties
+                                .class
+                                .first()
+                                .map(|class| String::from(class.as_str()))
+                                .unwrap_or_default(),
+                            name: String::from(managed.properties.name.get_cloned().as_str()),
+                        });
+                    }
+                }
+                WorkspaceSnapshot {
+                    name: String::from(ws.name),
+                    draw_mode: ws.draw_mode,
+                    windows,
+                }
+            })
+            .collect();
+        Self {
+            focused_mon: state.focused_mon,
+            input_focus: state.input_focus,
+            monitors,
+            workspaces,
+        }
+    }
+
+    /// Little endian throughout, counts and indices as u32, strings as a u16 length followed by utf8
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Encoder(Vec::new());
+        out.0.extend_from_slice(SNAPSHOT_MAGIC);
+        out.u8(SNAPSHOT_VERSION);
+        out.index(self.focused_mon);
+        out.u32_opt(self.input_focus);
+        out.index(self.monitors.len());
+        for mon in &self.monitors {
+            for val in [
+                mon.dimensions.x,
+                mon.dimensions.y,
+                mon.dimensions.width,
+                mon.dimensions.height,
+            ] {
+                out.0.extend_from_slice(&val.to_le_bytes());
+            }
+            out.index(mon.hosted_workspace);
+            out.u32_opt(mon.last_focus);
+            out.u8(u8::from(mon.show_bar));
+        }
+        out.index(self.workspaces.len());
+        for ws in &self.workspaces {
+            out.str(&ws.name);
+            out.mode(ws.draw_mode);
+            out.index(ws.windows.len());
+            for win in &ws.windows {
+                out.u32(win.window);
+                out.u32_opt(win.attached_to);
+                out.arrange(win.arrange);
+                out.u32_opt(win.pinned_monitor.map(|mon| mon as u32));
+                out.str(&win.class);
+                out.str(&win.name);
+            }
+        }
+        out.0
+    }
+
+    /// Read a snapshot written by [`StateSnapshot::encode`]
+    /// # Errors
+    /// If the bytes aren't a complete snapshot of the current version
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut input = Decoder(bytes);
+        if input.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err(Error::Snapshot("not a snapshot"));
+        }
+        if input.u8()? != SNAPSHOT_VERSION {
+            return Err(Error::Snapshot("unsupported version"));
+        }
+        let focused_mon = input.index()?;
+        let input_focus = input.u32_opt()?;
+        let num_monitors = input.index()?;
+        let mut monitors = Vec::new();
+        for _ in 0..num_monitors {
+            let (x, y, width, height) = (input.i16()?, input.i16()?, input.i16()?, input.i16()?);
+            monitors.push(MonitorSnapshot {
+                dimensions: Dimensions::new(width, height, x, y),
+                hosted_workspace: input.index()?,
+                last_focus: input.u32_opt()?,
+                show_bar: input.u8()? != 0,
+            });
+        }
+        let num_workspaces = input.index()?;
+        let mut workspaces = Vec::new();
+        for _ in 0..num_workspaces {
+            let name = input.str()?;
+            let draw_mode = input.mode()?;
+            let num_windows = input.index()?;
+            let mut windows = Vec::new();
+            for _ in 0..num_windows {
+                windows.push(WindowSnapshot {
+                    window: input.u32()?,
+                    attached_to: input.u32_opt()?,
+                    arrange: input.arrange()?,
+                    pinned_monitor: input.u32_opt()?.map(|mon| mon as usize),
+                    class: input.str()?,
+                    name: input.str()?,
+                });
+            }
+            workspaces.push(WorkspaceSnapshot {
+                name,
+                draw_mode,
+                windows,
+            });
+        }
+        if !input.0.is_empty() {
+            return Err(Error::Snapshot("trailing bytes"));
+        }
+        Ok(Self {
+            focused_mon,
+            input_focus,
+            monitors,
+            workspaces,
+        })
+    }
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u8(&mut self, val: u8) {
+        self.0.push(val);
+    }
+
+    fn u32(&mut self, val: u32) {
+        self.0.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn index(&mut self, val: usize) {
+        self.u32(val as u32);
+    }
+
+    fn u32_opt(&mut self, val: Option<u32>) {
+        match val {
+            Some(val) => {
+                self.u8(1);
+                self.u32(val);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn str(&mut self, val: &str) {
+        // Names are bounded well below this, cut at a char boundary just in case
+        let mut end = val.len().min(u16::MAX as usize);
+        while !val.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.0.extend_from_slice(&(end as u16).to_le_bytes());
+        self.0.extend_from_slice(&val.as_bytes()[..end]);
+    }
+
+    fn old_mode(&mut self, mode: OldDrawMode) {
+        match mode {
+            OldDrawMode::Tiled(layout) => {
+                self.u8(0);
+                self.u8(layout as u8);
+            }
+            OldDrawMode::Tabbed(shown) => {
+                self.u8(1);
+                self.index(shown);
+            }
+        }
+    }
+
+    fn mode(&mut self, mode: Mode) {
+        match mode {
+            Mode::Tiled(layout) => self.old_mode(OldDrawMode::Tiled(layout)),
+            Mode::Tabbed(shown) => self.old_mode(OldDrawMode::Tabbed(shown)),
+            Mode::Fullscreen {
+                window,
+                last_draw_mode,
+            } => {
+                self.u8(2);
+                self.u32(window);
+                self.old_mode(last_draw_mode);
+            }
+        }
+    }
+
+    fn arrange(&mut self, arrange: ArrangeKind) {
+        match arrange {
+            ArrangeKind::NoFloat => self.u8(0),
+            ArrangeKind::FloatingActive => self.u8(1),
+            ArrangeKind::FloatingInactive(rel_x, rel_y) => {
+                self.u8(2);
+                self.0.extend_from_slice(&rel_x.to_le_bytes());
+                self.0.extend_from_slice(&rel_y.to_le_bytes());
+            }
+        }
+    }
+}
+
+struct Decoder<'a>(&'a [u8]);
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(Error::Snapshot("truncated"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn index(&mut self) -> Result<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn u32_opt(&mut self) -> Result<Option<u32>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.u32()?)),
+            _ => Err(Error::Snapshot("invalid option")),
+        }
+    }
+
+    fn str(&mut self) -> Result<String> {
+        let len = u16::from_le_bytes(self.array()?) as usize;
+        core::str::from_utf8(self.take(len)?)
+            .map(String::from)
+            .map_err(|_| Error::Snapshot("invalid utf8"))
+    }
+
+    fn old_mode_with_tag(&mut self, tag: u8) -> Result<OldDrawMode> {
+        match tag {
+            0 => match self.u8()? {
+                0 => Ok(OldDrawMode::Tiled(Layout::LeftLeader)),
+                1 => Ok(OldDrawMode::Tiled(Layout::CenterLeader)),
+                _ => Err(Error::Snapshot("invalid layout")),
+            },
+            1 => Ok(OldDrawMode::Tabbed(self.index()?)),
+            _ => Err(Error::Snapshot("invalid draw mode")),
+        }
+    }
+
+    fn mode(&mut self) -> Result<Mode> {
+        let tag = self.u8()?;
+        if tag == 2 {
+            let window = self.u32()?;
+            let last_tag = self.u8()?;
+            return Ok(Mode::Fullscreen {
+                window,
+                last_draw_mode: self.old_mode_with_tag(last_tag)?,
+            });
+        }
+        Ok(match self.old_mode_with_tag(tag)? {
+            OldDrawMode::Tiled(layout) => Mode::Tiled(layout),
+            OldDrawMode::Tabbed(shown) => Mode::Tabbed(shown),
+        })
+    }
+
+    fn arrange(&mut self) -> Result<ArrangeKind> {
+        match self.u8()? {
+            0 => Ok(ArrangeKind::NoFloat),
+            1 => Ok(ArrangeKind::FloatingActive),
+            2 => Ok(ArrangeKind::FloatingInactive(self.f32()?, self.f32()?)),
+            _ => Err(Error::Snapshot("invalid arrange kind")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+
+    use crate::geometry::draw::{Mode, OldDrawMode};
+    use crate::geometry::layout::Layout;
+    use crate::geometry::Dimensions;
+    use crate::state::snapshot::{
+        MonitorSnapshot, StateSnapshot, WindowSnapshot, WorkspaceSnapshot,
+    };
+    use crate::state::workspace::ArrangeKind;
+
+    fn window(window: u32, attached_to: Option<u32>, arrange: ArrangeKind) -> WindowSnapshot {
+        WindowSnapshot {
+            window,
+            attached_to,
+            arrange,
+            pinned_monitor: None,
+            class: String::from("xterm"),
+            name: String::from("~/crate ✓"),
+        }
+    }
+
+    #[test]
+    fn round_trips_snapshots() {
+        let snapshot = StateSnapshot {
+            focused_mon: 1,
+            input_focus: Some(7),
+            monitors: vec![
+                MonitorSnapshot {
+                    dimensions: Dimensions::new(1920, 1080, 0, 0),
+                    hosted_workspace: 0,
+                    last_focus: None,
+                    show_bar: true,
+                },
+                MonitorSnapshot {
+                    dimensions: Dimensions::new(1280, 1024, 1920, -20),
+                    hosted_workspace: 3,
+                    last_focus: Some(7),
+                    show_bar: false,
+                },
+            ],
+            workspaces: vec![
+                WorkspaceSnapshot {
+                    name: String::from("web"),
+                    draw_mode: Mode::Tabbed(1),
+                    windows: vec![],
+                },
+                WorkspaceSnapshot {
+                    name: String::from("dev"),
+                    draw_mode: Mode::Fullscreen {
+                        window: 7,
+                        last_draw_mode: OldDrawMode::Tiled(Layout::CenterLeader),
+                    },
+                    windows: vec![
+                        window(7, None, ArrangeKind::NoFloat),
+                        window(8, Some(7), ArrangeKind::FloatingInactive(0.25, 0.5)),
+                        WindowSnapshot {
+                            pinned_monitor: Some(1),
+                            ..window(9, None, ArrangeKind::FloatingActive)
+                        },
+                    ],
+                },
+            ],
+        };
+        let encoded = snapshot.encode();
+        assert_eq!(snapshot, StateSnapshot::decode(&encoded).unwrap());
+        for len in 0..encoded.len() {
+            assert!(StateSnapshot::decode(&encoded[..len]).is_err());
+        }
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(StateSnapshot::decode(&trailing).is_err());
+        let mut newer = encoded;
+        newer[4] += 1;
+        assert!(StateSnapshot::decode(&newer).is_err());
+    }
+}
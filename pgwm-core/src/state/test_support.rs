@@ -0,0 +1,254 @@
+//! Fixtures for tests of code that works on a [`State`], shared with the tests of `pgwm-app`
+//! through the `test-support` feature
+use alloc::vec;
+use alloc::vec::Vec;
+
+use smallmap::Map;
+use xcb_rust_protocol::proto::xproto::{BackingStoreEnum, EventMask, Screen};
+use xcb_rust_protocol::CURRENT_TIME;
+
+use crate::colors::gradient::GradientCache;
+use crate::colors::{Color, Colors};
+use crate::config::cfg::Cfg;
+use crate::config::{COLORS, STATUS_BAR_HEIGHT, TAB_BAR_HEIGHT, USER_WORKSPACES};
+use crate::geometry::{Dimensions, Line};
+use crate::render::{DoubleBufferedRenderPicture, RenderPicture};
+use crate::state::bar_geometry::{
+    BarGeometry, ShortcutSection, UrgentSection, WindowTitleSection, WorkspaceSection,
+};
+use crate::state::properties::{WindowProperties, WmName};
+use crate::state::transition::BorderTransitions;
+use crate::state::workspace::Workspaces;
+use crate::state::{Monitor, State};
+
+/// Properties of a window that sets none of them
+#[must_use]
+pub fn test_properties() -> WindowProperties {
+    WindowProperties::new(
+        None,
+        Default::default(),
+        None,
+        None,
+        Default::default(),
+        None,
+        None,
+        Default::default(),
+        Default::default(),
+        WmName::NetWmName(Default::default()),
+        None,
+        Default::default(),
+        None,
+    )
+}
+
+/// Two 1000x1000 monitors side by side hosting the first two workspaces, every color is pixel 0
+#[must_use]
+pub fn create_base_state() -> State {
+    let monitor0 = Monitor {
+        bar_win: DoubleBufferedRenderPicture {
+            window: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+            pixmap: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+        },
+        tab_bar_win: DoubleBufferedRenderPicture {
+            window: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+            pixmap: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+        },
+        bar_geometry: BarGeometry {
+            workspace: WorkspaceSection {
+                position: Line::new(0, 0),
+                components: vec![],
+                drawn_backgrounds: vec![],
+            },
+            urgent: UrgentSection {
+                position: Line::new(0, 0),
+            },
+            shortcuts: ShortcutSection {
+                position: Line::new(0, 0),
+                components: vec![],
+                hovered: None,
+            },
+            #[cfg(feature = "status-bar")]
+            status: crate::state::bar_geometry::StatusSection {
+                position: Line::new(0, 0),
+                first_sep_len: 0,
+                sep_len: 0,
+                components: heapless::Vec::default(),
+            },
+            window_title_section: WindowTitleSection {
+                position: Line::new(0, 0),
+                display: heapless::String::default(),
+                last_draw_width: 0,
+            },
+        },
+        dimensions: Dimensions::new(1000, 1000, 0, 0),
+        scale: 1.0,
+        bar_height: STATUS_BAR_HEIGHT,
+        tab_bar_height: TAB_BAR_HEIGHT,
+        hosted_workspace: 0,
+        recent_workspaces: vec![],
+        last_focus: None,
+        show_bar: false,
+        autohide_bar: false,
+        showing_desktop: false,
+        window_title_display: heapless::String::default(),
+    };
+    let monitor1 = Monitor {
+        bar_geometry: BarGeometry {
+            workspace: WorkspaceSection {
+                position: Line::new(0, 0),
+                components: vec![],
+                drawn_backgrounds: vec![],
+            },
+            urgent: UrgentSection {
+                position: Line::new(0, 0),
+            },
+            shortcuts: ShortcutSection {
+                position: Line::new(0, 0),
+                components: vec![],
+                hovered: None,
+            },
+            #[cfg(feature = "status-bar")]
+            status: crate::state::bar_geometry::StatusSection {
+                position: Line::new(0, 0),
+                first_sep_len: 0,
+                sep_len: 0,
+                components: heapless::Vec::default(),
+            },
+            window_title_section: WindowTitleSection {
+                position: Line::new(0, 0),
+                display: heapless::String::default(),
+                last_draw_width: 0,
+            },
+        },
+        bar_win: DoubleBufferedRenderPicture {
+            window: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+            pixmap: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+        },
+        tab_bar_win: DoubleBufferedRenderPicture {
+            window: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+            pixmap: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+        },
+        dimensions: Dimensions::new(1000, 1000, 1000, 0),
+        scale: 1.0,
+        bar_height: STATUS_BAR_HEIGHT,
+        tab_bar_height: TAB_BAR_HEIGHT,
+        hosted_workspace: 1,
+        recent_workspaces: vec![],
+        last_focus: None,
+        show_bar: false,
+        autohide_bar: false,
+        showing_desktop: false,
+        window_title_display: heapless::String::default(),
+    };
+    let pixels: [Color; COLORS.len()] = [Color {
+        pixel: 0,
+        bgra8: [0, 0, 0, 0],
+    }; COLORS.len()];
+    State {
+        wm_check_win: 0,
+        intern_created_windows: Map::default(),
+        dying_windows: heapless::Vec::default(),
+        drag_window: None,
+        window_picker: None,
+        keybind_help_win: DoubleBufferedRenderPicture {
+            window: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+            pixmap: RenderPicture {
+                drawable: 0,
+                picture: 0,
+                format: 0,
+            },
+        },
+        keybind_help_shown: false,
+        window_hint_wins: vec![],
+        window_hints: None,
+        urgent_windows: vec![],
+        focused_mon: 0,
+        input_focus: None,
+        pending_focus: None,
+        screen: Screen {
+            root: 0,
+            default_colormap: 0,
+            white_pixel: 0,
+            black_pixel: 0,
+            current_input_masks: EventMask::NO_EVENT,
+            width_in_pixels: 0,
+            height_in_pixels: 0,
+            width_in_millimeters: 0,
+            height_in_millimeters: 0,
+            min_installed_maps: 0,
+            max_installed_maps: 0,
+            root_visual: 0,
+            backing_stores: BackingStoreEnum::NOT_USEFUL,
+            save_unders: 0,
+            root_depth: 0,
+            allowed_depths: vec![],
+        },
+        sequences_to_ignore: heapless::BinaryHeap::default(),
+        pending_unmaps: vec![],
+        monitors: vec![monitor0, monitor1],
+        workspaces: Workspaces::create_empty(&USER_WORKSPACES).unwrap(),
+        colors: Colors {
+            inner: pixels,
+            class_borders: vec![],
+        },
+        window_border_width: 0,
+        window_padding: 0,
+        pointer_grabbed: false,
+        mouse_mapping: Map::default(),
+        key_mapping: Map::default(),
+        last_timestamp: CURRENT_TIME,
+        last_key_release: None,
+        root_background: None,
+        background_images: Vec::new(),
+        edge_dwell: None,
+        dragged_tab: None,
+        idle_inhibited: false,
+        idle_handled: false,
+        next_idle_check: None,
+        next_screensaver_reset: None,
+        title_notice: None,
+        border_transitions: BorderTransitions::default(),
+        border_gradients: GradientCache::new(0),
+        workspace_slide: None,
+        status_format_cycle: None,
+        status_interval_change: None,
+        popups: vec![],
+        cfg: &Cfg::DEFAULT,
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::vec::Vec;
+use core::ops::Add;
+use core::time::Duration;
+
+use tiny_std::time::Instant;
+use xcb_rust_protocol::proto::xproto::Window;
+
+use crate::colors::Color;
+
+/// The border color each window was last given, and the frames left of the transitions that are running
+#[derive(Default)]
+pub struct BorderTransitions {
+    borders: Vec<WindowBorder>,
+    next_frame: Option<Instant>,
+}
+
+struct WindowBorder {
+    window: Window,
+    shown: Color,
+    frames: Vec<Color>,
+}
+
+impl BorderTransitions {
+    /// The border color last set on a window, `None` if it hasn't been set since it was managed
+    #[must_use]
+    pub fn shown(&self, window: Window) -> Option<Color> {
+        self.borders
+            .iter()
+            .find(|border| border.window == window)
+            .map(|border| border.shown)
+    }
+
+    /// Record a border color set at once, stopping any transition on the window
+    pub fn set(&mut self, window: Window, color: Color) {
+        self.replace(WindowBorder {
+            window,
+            shown: color,
+            frames: Vec::new(),
+        });
+    }
+
+    /// Show `frames` on the window one at a time, one every `frame_time` starting after `frame_time`.
+    /// `shown` is the color the window currently has, a running transition on the window is replaced.
+    pub fn start(
+        &mut self,
+        window: Window,
+        shown: Color,
+        frames: Vec<Color>,
+        now: Instant,
+        frame_time: Duration,
+    ) {
+        let running = !frames.is_empty();
+        self.replace(WindowBorder {
+            window,
+            shown,
+            // Popped from the back
+            frames: frames.into_iter().rev().collect(),
+        });
+        if running && self.next_frame.is_none() {
+            self.next_frame = now.add(frame_time);
+        }
+    }
+
+    fn replace(&mut self, border: WindowBorder) {
+        if let Some(existing) = self.borders.iter_mut().find(|b| b.window == border.window) {
+            *existing = border;
+        } else {
+            self.borders.push(border);
+        }
+    }
+
+    /// Forget a window that's no longer managed
+    pub fn forget(&mut self, window: Window) {
+        self.borders.retain(|border| border.window != window);
+    }
+
+    /// When the next frame of a running transition is due
+    #[must_use]
+    pub fn next_frame(&self) -> Option<Instant> {
+        self.next_frame
+    }
+
+    /// The border pixel to set on each window with a running transition, if a frame is due
+    pub fn take_due_frames(&mut self, now: Instant, frame_time: Duration) -> Vec<(Window, u32)> {
+        if self.next_frame.filter(|due| *due <= now).is_none() {
+            return Vec::new();
+        }
+        let mut frames = Vec::new();
+        for border in &mut self.borders {
+            if let Some(frame) = border.frames.pop() {
+                border.shown = frame;
+                frames.push((border.window, frame.pixel));
+            }
+        }
+        self.next_frame = if self.borders.iter().any(|border| !border.frames.is_empty()) {
+            now.add(frame_time)
+        } else {
+            None
+        };
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use core::ops::Add;
+    use core::time::Duration;
+
+    use tiny_std::time::Instant;
+
+    use crate::colors::Color;
+    use crate::state::transition::BorderTransitions;
+
+    fn color(pixel: u32) -> Color {
+        Color {
+            pixel,
+            bgra8: [pixel as u8; 4],
+        }
+    }
+
+    #[test]
+    fn steps_through_frames() {
+        let frame_time = Duration::from_millis(10);
+        let now = Instant::now();
+        let later = now.add(frame_time).unwrap();
+        let mut transitions = BorderTransitions::default();
+        assert!(transitions.shown(1).is_none());
+        transitions.set(2, color(5));
+        assert!(transitions.next_frame().is_none());
+        transitions.start(1, color(0), vec![color(1), color(2)], now, frame_time);
+        assert_eq!(Some(later), transitions.next_frame());
+        assert!(transitions.take_due_frames(now, frame_time).is_empty());
+        assert_eq!(vec![(1, 1)], transitions.take_due_frames(later, frame_time));
+        assert_eq!(1, transitions.shown(1).unwrap().pixel);
+        // Replaced before it finishes
+        transitions.start(1, color(1), vec![color(4)], later, frame_time);
+        let last = transitions.next_frame().unwrap();
+        assert_eq!(vec![(1, 4)], transitions.take_due_frames(last, frame_time));
+        assert!(transitions.next_frame().is_none());
+        assert_eq!(4, transitions.shown(1).unwrap().pixel);
+        assert_eq!(5, transitions.shown(2).unwrap().pixel);
+        transitions.forget(1);
+        assert!(transitions.shown(1).is_none());
+        assert!(transitions.take_due_frames(last, frame_time).is_empty());
+    }
+}
@@ -1,13 +1,19 @@
 use alloc::vec::Vec;
 
 use smallmap::Map;
+use tiny_std::UnixStr;
 use xcb_rust_protocol::proto::xproto::Window;
 
+use crate::colors::RGBA;
 use crate::config::workspaces::UserWorkspace;
-use crate::config::{DefaultDraw, TilingModifiers, WM_TILING_MODIFIERS, WS_WINDOW_LIMIT};
+use crate::config::{
+    DefaultDraw, TilingModifiers, FAKE_FULLSCREEN_CLASSES, WM_TILING_MODIFIERS, WS_WINDOW_LIMIT,
+};
 use crate::error::Result;
 use crate::geometry::draw::{Mode, OldDrawMode};
 use crate::geometry::layout::Layout;
+use crate::geometry::Dimensions;
+use crate::state::placement::{Placement, PlacementHistory};
 use crate::state::properties::WindowProperties;
 use crate::util::vec_ops::push_to_front;
 
@@ -20,34 +26,84 @@ pub struct Workspaces {
     win_to_ws: Map<Window, usize>,
     // Hot read
     name_to_ws: Map<&'static str, usize>,
+    // Cold, user marked windows
+    marks: Map<Window, &'static str>,
+    // Attached windows to the window they're attached to, which can itself be attached.
+    // All attached windows are stored with the top level window at the root of the tree
+    transient_parents: Map<Window, Window>,
+    // How many workspaces are in each set, there's a single set unless each monitor has its own
+    set_size: usize,
+    // Cold, where the user last put windows of a class
+    placements: PlacementHistory,
 }
 
 impl Workspaces {
     pub fn create_empty(init_workspaces: &[UserWorkspace]) -> Result<Self> {
-        let mut v = Vec::<Workspace>::new();
         let mut name_to_ws = Map::new();
         for (i, ws) in init_workspaces.iter().enumerate() {
-            v.push(Workspace {
-                draw_mode: match ws.default_draw {
-                    DefaultDraw::LeftLeader => Mode::Tiled(Layout::LeftLeader),
-                    DefaultDraw::CenterLeader => Mode::Tiled(Layout::CenterLeader),
-                    DefaultDraw::Tabbed => Mode::Tabbed(0),
-                },
-                name: ws.name,
-                children: heapless::Vec::new(), // Realloc is what's going to take time here
-                tiling_modifiers: WM_TILING_MODIFIERS,
-            });
             for mapped in ws.mapped_class_names {
                 name_to_ws.insert(*mapped, i);
             }
         }
         Ok(Workspaces {
-            spaces: v,
+            spaces: init_workspaces.iter().map(Workspace::new).collect(),
             win_to_ws: Map::new(),
             name_to_ws,
+            marks: Map::new(),
+            transient_parents: Map::new(),
+            set_size: init_workspaces.len(),
+            placements: PlacementHistory::new(0),
         })
     }
 
+    /// Adds sets of `init_workspaces` until there are at least `num_sets`,
+    /// used to give each monitor its own set of workspaces
+    pub fn ensure_sets(&mut self, init_workspaces: &[UserWorkspace], num_sets: usize) {
+        while !init_workspaces.is_empty() && self.num_sets() < num_sets {
+            self.spaces
+                .extend(init_workspaces.iter().map(Workspace::new));
+        }
+    }
+
+    #[must_use]
+    pub fn set_size(&self) -> usize {
+        self.set_size
+    }
+
+    #[must_use]
+    pub fn num_sets(&self) -> usize {
+        self.spaces.len() / self.set_size.max(1)
+    }
+
+    /// The set of workspaces that a monitor's bar and workspace actions refer to
+    #[must_use]
+    pub fn set_of_monitor(&self, mon_ind: usize) -> usize {
+        if self.num_sets() > 1 {
+            mon_ind
+        } else {
+            0
+        }
+    }
+
+    #[must_use]
+    pub fn set_of(&self, ws_ind: usize) -> usize {
+        ws_ind / self.set_size.max(1)
+    }
+
+    /// The workspace at `num` in a set
+    #[must_use]
+    pub fn in_set(&self, set: usize, num: usize) -> Option<usize> {
+        let ws_ind = set * self.set_size + num;
+        (num < self.set_size && ws_ind < self.spaces.len()).then_some(ws_ind)
+    }
+
+    /// Where a workspace is in a set, `None` if it belongs to another set
+    #[must_use]
+    pub fn index_in_set(&self, set: usize, ws_ind: usize) -> Option<usize> {
+        (self.set_of(ws_ind) == set && ws_ind < self.spaces.len())
+            .then(|| ws_ind - set * self.set_size)
+    }
+
     #[must_use]
     pub fn get_all_managed_windows(&self) -> Vec<Window> {
         self.win_to_ws.keys().copied().collect()
@@ -60,9 +116,65 @@ impl Workspaces {
         self.spaces[ws_ind].iter_all_windows()
     }
 
+    /// The workspace in `set` that windows with this class name are mapped to
+    #[must_use]
+    pub fn find_ws_for_window_class_name(&self, wm_class: &str, set: usize) -> Option<usize> {
+        self.name_to_ws
+            .get(wm_class)
+            .and_then(|num| self.in_set(set, *num))
+    }
+
+    /// The workspace in `set` that a window with this class name was last put on by the user,
+    /// and its geometry if it was left floating
+    #[must_use]
+    pub fn find_placement_for_window_class_name(
+        &self,
+        wm_class: &str,
+        set: usize,
+    ) -> Option<(usize, Option<FloatingGeometry>)> {
+        let placement = self.placements.get(wm_class)?;
+        self.in_set(set, placement.workspace)
+            .map(|ws_ind| (ws_ind, placement.floating))
+    }
+
+    /// Remembers the workspace that a window was put on by the user, and `floating` if it was left floating,
+    /// for the next window of its class. Returns whether the placement history changed
+    pub fn remember_placement(
+        &mut self,
+        window: Window,
+        floating: Option<FloatingGeometry>,
+    ) -> bool {
+        let Some(ws_ind) = self.find_ws_containing_window(window) else {
+            return false;
+        };
+        let Some(class) = self
+            .get_managed_win(window)
+            .and_then(|mw| mw.properties.class.last().cloned())
+        else {
+            return false;
+        };
+        let workspace = ws_ind - self.set_of(ws_ind) * self.set_size;
+        self.placements.record(
+            &class,
+            Placement {
+                workspace,
+                floating,
+            },
+        )
+    }
+
     #[must_use]
-    pub fn find_ws_for_window_class_name(&self, wm_class: &str) -> Option<usize> {
-        self.name_to_ws.get(wm_class).copied()
+    pub fn placement_history(&self) -> &PlacementHistory {
+        &self.placements
+    }
+
+    pub fn set_placement_history(&mut self, placements: PlacementHistory) {
+        self.placements = placements;
+    }
+
+    #[must_use]
+    pub fn num_workspaces(&self) -> usize {
+        self.spaces.len()
     }
 
     #[must_use]
@@ -84,6 +196,68 @@ impl Workspaces {
             .and_then(|ws_ind| self.spaces[*ws_ind].find_managed_window_mut(window))
     }
 
+    /// Toggles the maximized state of a window.
+    /// Returns `None` if the window isn't managed, `Some(None)` if the window should now be maximized,
+    /// and `Some(Some(previous))` if the window was maximized and should be restored to `previous`.
+    pub fn toggle_maximized(
+        &mut self,
+        window: Window,
+        current: Dimensions,
+    ) -> Option<Option<PreMaximize>> {
+        self.get_managed_win_mut(window).map(|mw| {
+            if let Some(previous) = mw.pre_maximize.take() {
+                Some(previous)
+            } else {
+                mw.pre_maximize = Some(PreMaximize {
+                    dimensions: current,
+                    tiled: mw.arrange == ArrangeKind::NoFloat,
+                });
+                None
+            }
+        })
+    }
+
+    /// Toggles a mark on a managed window, a mark can only be on one window at a time
+    /// so marking a window removes that mark from any other window.
+    /// Returns whether the window is marked after the toggle.
+    pub fn toggle_mark(&mut self, window: Window, mark: &'static str) -> bool {
+        if !self.win_to_ws.contains_key(&window) {
+            return false;
+        }
+        if self.marks.get(&window).filter(|m| **m == mark).is_some() {
+            self.marks.remove(&window);
+            false
+        } else {
+            if let Some(previous) = self.find_marked(mark) {
+                self.marks.remove(&previous);
+            }
+            self.marks.insert(window, mark);
+            true
+        }
+    }
+
+    pub fn unmark(&mut self, window: Window) {
+        self.marks.remove(&window);
+    }
+
+    #[must_use]
+    pub fn get_mark(&self, window: Window) -> Option<&'static str> {
+        self.marks.get(&window).copied()
+    }
+
+    #[must_use]
+    pub fn find_marked(&self, mark: &str) -> Option<Window> {
+        self.marks
+            .keys()
+            .copied()
+            .find(|win| self.marks.get(win).filter(|m| **m == mark).is_some())
+    }
+
+    /// Removes all marks, returning the windows that were marked
+    pub fn take_marked(&mut self) -> Vec<Window> {
+        core::mem::take(&mut self.marks).keys().copied().collect()
+    }
+
     pub fn update_size_modifier(&mut self, window: Window, resize: f32) -> bool {
         self.win_to_ws.get(&window).map_or(false, |ws_ind| {
             let ws = &mut self.spaces[*ws_ind];
@@ -168,11 +342,24 @@ impl Workspaces {
         if ws.draw_mode == draw_mode {
             false
         } else {
+            ws.remember_mode_state(draw_mode);
             ws.draw_mode = draw_mode;
             true
         }
     }
 
+    /// Switches between tiled and tabbed, back to the layout or shown tab that the workspace last had in that mode.
+    /// Size modifiers only apply when tiled and are kept as they are. Does nothing if fullscreened.
+    pub fn toggle_tabbed(&mut self, num: usize) -> bool {
+        let ws = &self.spaces[num];
+        let draw_mode = match ws.draw_mode {
+            Mode::Tiled(_) => Mode::Tabbed(ws.last_tab_focus),
+            Mode::Tabbed(_) => Mode::Tiled(ws.last_tiled_layout),
+            Mode::Fullscreen { .. } => return false,
+        };
+        self.set_draw_mode(num, draw_mode)
+    }
+
     pub fn cycle_tiling_mode(&mut self, num: usize) {
         let ws = &mut self.spaces[num];
         if let Mode::Tiled(layout) = ws.draw_mode {
@@ -210,6 +397,13 @@ impl Workspaces {
         self.spaces[num].send_to_front(win);
     }
 
+    /// Move a tiled window so that it's tiled at `tiled_ind`, or last if out of range
+    pub fn move_to_tiling_index(&mut self, window: Window, tiled_ind: usize) -> bool {
+        self.win_to_ws.get(&window).copied().map_or(false, |ws| {
+            self.spaces[ws].move_to_tiling_index(window, tiled_ind)
+        })
+    }
+
     pub fn toggle_floating(&mut self, window: Window, num: usize, floating: ArrangeKind) -> bool {
         if let Some(mw) = self.spaces[num]
             .iter_all_windows_mut()
@@ -248,17 +442,131 @@ impl Workspaces {
         properties: &WindowProperties,
     ) -> Result<bool> {
         if let Some(ws) = self.win_to_ws.get(&parent).copied() {
-            self.win_to_ws.insert(attached, ws);
-            self.spaces[ws].add_attached(
-                parent,
+            let root = self.find_transient_root(parent);
+            if self.spaces[ws].add_attached(
+                root,
                 attached,
                 arrange,
                 focus_style,
                 properties.clone(),
-            )?;
-            Ok(true)
-        } else {
-            Ok(false)
+            )? {
+                self.win_to_ws.insert(attached, ws);
+                self.transient_parents.insert(attached, parent);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Moves the top level windows of workspace `from` that are pinned to monitor `mon_ind`, along with
+    /// the windows attached to them, to workspace `to`. Returns the moved top level windows
+    pub fn move_pinned(&mut self, from: usize, to: usize, mon_ind: usize) -> Result<Vec<Window>> {
+        let mut moved = Vec::new();
+        if from == to {
+            return Ok(moved);
+        }
+        let mut ind = 0;
+        while ind < self.spaces[from].children.len() {
+            if self.spaces[from].children[ind].managed.pinned_monitor != Some(mon_ind) {
+                ind += 1;
+                continue;
+            }
+            let child = crate::util::vec_ops::remove(&mut self.spaces[from].children, ind);
+            let window = child.managed.window;
+            if let Mode::Fullscreen {
+                window: fs_window,
+                last_draw_mode,
+            } = self.spaces[from].draw_mode
+            {
+                if fs_window == window {
+                    self.spaces[from].draw_mode = last_draw_mode.to_draw_mode();
+                }
+            }
+            self.win_to_ws.insert(window, to);
+            for attached in &child.attached {
+                self.win_to_ws.insert(attached.window, to);
+            }
+            push_to_front(&mut self.spaces[to].children, child)?;
+            moved.push(window);
+        }
+        Ok(moved)
+    }
+
+    /// The top level window that a window is attached to, possibly through other attached windows,
+    /// or the window itself if it isn't attached
+    #[must_use]
+    pub fn find_transient_root(&self, window: Window) -> Window {
+        let mut root = window;
+        // Bounded in case clients have made a cycle
+        for _ in 0..WS_WINDOW_LIMIT {
+            if let Some(parent) = self.transient_parents.get(&root) {
+                root = *parent;
+            } else {
+                break;
+            }
+        }
+        root
+    }
+
+    /// The window if it's attached, followed by everything attached to it at any depth, in the order
+    /// they should be raised so that every window ends up above the one it's attached to.
+    /// Windows attached to the same parent are ordered oldest first so that the newest ends up on top.
+    #[must_use]
+    pub fn transient_stacking_order(
+        &self,
+        window: Window,
+    ) -> heapless::Vec<Window, WS_WINDOW_LIMIT> {
+        let mut order = heapless::Vec::new();
+        if self.transient_parents.contains_key(&window) {
+            let _ = order.push(window);
+        }
+        if let Some(attached) = self.find_all_attached_managed(self.find_transient_root(window)) {
+            Self::push_transients(&self.transient_parents, attached, window, &mut order);
+        }
+        order
+    }
+
+    /// All windows in a workspace in the order they should be raised, each top level window
+    /// followed by what's attached to it, see [`Self::transient_stacking_order`]
+    #[must_use]
+    pub fn stacking_order_in_ws(&self, ws_ind: usize) -> Vec<Window> {
+        let mut order = Vec::new();
+        for ch in &self.spaces[ws_ind].children {
+            order.push(ch.managed.window);
+            order.extend(self.transient_stacking_order(ch.managed.window));
+        }
+        order
+    }
+
+    fn push_transients(
+        transient_parents: &Map<Window, Window>,
+        attached: &heapless::Vec<ManagedWindow, WS_WINDOW_LIMIT>,
+        parent: Window,
+        order: &mut heapless::Vec<Window, WS_WINDOW_LIMIT>,
+    ) {
+        // Attached windows are pushed to the front, so the oldest is last
+        for mw in attached.iter().rev() {
+            if transient_parents.get(&mw.window) == Some(&parent)
+                && !order.contains(&mw.window)
+                && order.push(mw.window).is_ok()
+            {
+                Self::push_transients(transient_parents, attached, mw.window, order);
+            }
+        }
+    }
+
+    /// Stops tracking a window as attached, anything attached to it is moved to its parent
+    fn forget_transient(&mut self, window: Window) {
+        if let Some(parent) = self.transient_parents.remove(&window) {
+            let orphans = self
+                .transient_parents
+                .keys()
+                .copied()
+                .filter(|attached| self.transient_parents.get(attached) == Some(&window))
+                .collect::<Vec<Window>>();
+            for orphan in orphans {
+                self.transient_parents.insert(orphan, parent);
+            }
         }
     }
 
@@ -281,6 +589,7 @@ impl Workspaces {
     }
 
     pub fn delete_child_from_ws(&mut self, window: Window) -> DeleteResult {
+        self.forget_transient(window);
         self.win_to_ws
             .remove(&window)
             .map_or(DeleteResult::None, |ind| {
@@ -291,6 +600,7 @@ impl Workspaces {
                 {
                     for child in &ws_child.attached {
                         self.win_to_ws.remove(&child.window);
+                        self.transient_parents.remove(&child.window);
                     }
                 }
                 let dr = self.spaces[ind].delete_child(window);
@@ -372,6 +682,46 @@ impl Workspaces {
             .and_then(|ws| self.spaces[ws].find_prev(cur))
     }
 
+    /// Every managed window with `class` as its instance or class name, in workspace order
+    #[must_use]
+    pub fn with_class(&self, class: &str) -> Vec<Window> {
+        self.spaces
+            .iter()
+            .flat_map(Workspace::iter_all_windows)
+            .filter(|mw| {
+                mw.properties
+                    .class
+                    .iter()
+                    .any(|name| name.as_str() == class)
+            })
+            .map(|mw| mw.window)
+            .collect()
+    }
+
+    /// Every managed window in the same group as `window`, see [`ManagedWindow::group`],
+    /// in workspace order. Empty if `window` isn't managed
+    #[must_use]
+    pub fn group_members(&self, window: Window) -> Vec<Window> {
+        let Some(group) = self.get_managed_win(window).map(ManagedWindow::group) else {
+            return Vec::new();
+        };
+        self.spaces
+            .iter()
+            .flat_map(Workspace::iter_all_windows)
+            .filter(|mw| mw.group() == group)
+            .map(|mw| mw.window)
+            .collect()
+    }
+
+    /// The member of `cur`'s group after it, wrapping around, `None` if it's alone in its group
+    #[must_use]
+    pub fn next_in_group(&self, cur: Window) -> Option<Window> {
+        let members = self.group_members(cur);
+        let ind = members.iter().position(|win| *win == cur)?;
+        let next = members[(ind + 1) % members.len()];
+        (next != cur).then_some(next)
+    }
+
     #[must_use]
     pub fn get_draw_mode(&self, num: usize) -> Mode {
         let ws = &self.spaces[num];
@@ -416,9 +766,54 @@ pub struct Workspace {
     // realloc
     pub children: heapless::Vec<Child, WS_WINDOW_LIMIT>,
     pub tiling_modifiers: TilingModifiers,
+    pub background: Option<RGBA>,
+    pub background_image: Option<&'static UnixStr>,
+    /// Unscaled overrides of the padding and border width, see [`State::padding_of`](crate::state::State::padding_of)
+    pub padding: Option<i16>,
+    pub border_width: Option<u32>,
+    /// The layout when last tiled, restored when switching back from tabbed
+    pub last_tiled_layout: Layout,
+    /// The shown tab when last tabbed, restored when switching back from tiled
+    pub last_tab_focus: usize,
 }
 
 impl Workspace {
+    fn new(ws: &UserWorkspace) -> Self {
+        Workspace {
+            draw_mode: match ws.default_draw {
+                DefaultDraw::LeftLeader => Mode::Tiled(Layout::LeftLeader),
+                DefaultDraw::CenterLeader => Mode::Tiled(Layout::CenterLeader),
+                DefaultDraw::Tabbed => Mode::Tabbed(0),
+            },
+            name: ws.name,
+            children: heapless::Vec::new(), // Realloc is what's going to take time here
+            tiling_modifiers: WM_TILING_MODIFIERS,
+            background: ws.background,
+            background_image: ws.background_image,
+            padding: ws.padding,
+            border_width: ws.border_width,
+            last_tiled_layout: match ws.default_draw {
+                DefaultDraw::CenterLeader => Layout::CenterLeader,
+                DefaultDraw::LeftLeader | DefaultDraw::Tabbed => Layout::LeftLeader,
+            },
+            last_tab_focus: 0,
+        }
+    }
+
+    /// Remembers the layout or shown tab of the current mode when switching to another kind of mode
+    fn remember_mode_state(&mut self, next: Mode) {
+        match self.draw_mode {
+            Mode::Tiled(layout) if !matches!(next, Mode::Tiled(_)) => {
+                self.last_tiled_layout = layout;
+            }
+            Mode::Tabbed(shown) if !matches!(next, Mode::Tabbed(_)) => {
+                // A tab that's since been closed can't be shown again
+                self.last_tab_focus = if shown < self.num_tiled() { shown } else { 0 };
+            }
+            _ => {}
+        }
+    }
+
     fn add_child(
         &mut self,
         window: Window,
@@ -436,13 +831,7 @@ impl Workspace {
         push_to_front(
             &mut self.children,
             Child {
-                managed: ManagedWindow {
-                    window,
-                    wants_focus: false,
-                    arrange,
-                    focus_style,
-                    properties,
-                },
+                managed: ManagedWindow::new(window, arrange, focus_style, properties),
                 attached: heapless::Vec::new(),
             },
         )
@@ -506,7 +895,7 @@ impl Workspace {
         arrange: ArrangeKind,
         focus_style: FocusStyle,
         properties: WindowProperties,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         if let Some(ind) = self
             .children
             .iter()
@@ -516,21 +905,17 @@ impl Workspace {
             for att in &mut ws_child.attached {
                 if att.window == attached {
                     att.arrange = arrange;
-                    return Ok(());
+                    return Ok(true);
                 }
             }
             push_to_front(
                 &mut ws_child.attached,
-                ManagedWindow {
-                    window: attached,
-                    wants_focus: false,
-                    arrange,
-                    focus_style,
-                    properties,
-                },
+                ManagedWindow::new(attached, arrange, focus_style, properties),
             )?;
+            Ok(true)
+        } else {
+            Ok(false)
         }
-        Ok(())
     }
 
     fn find_all_attached_managed(
@@ -550,6 +935,7 @@ impl Workspace {
                     false
                 } else {
                     mw.arrange = ArrangeKind::NoFloat;
+                    mw.pre_maximize = None;
                     true
                 }
             })
@@ -637,6 +1023,31 @@ impl Workspace {
         }
     }
 
+    fn move_to_tiling_index(&mut self, window: Window, tiled_ind: usize) -> bool {
+        let Some(from) = self.children.iter().position(|ch| {
+            ch.managed.window == window && ch.managed.arrange == ArrangeKind::NoFloat
+        }) else {
+            return false;
+        };
+        // Child indices of the other tiled windows, as they'll be with the moved window taken out
+        let others = self
+            .children
+            .iter()
+            .enumerate()
+            .filter(|(ind, ch)| *ind != from && ch.managed.arrange == ArrangeKind::NoFloat)
+            .map(|(ind, _)| if ind > from { ind - 1 } else { ind })
+            .collect::<heapless::Vec<usize, WS_WINDOW_LIMIT>>();
+        let to = if let Some(ind) = others.get(tiled_ind) {
+            *ind
+        } else if let Some(last) = others.last() {
+            last + 1
+        } else {
+            from
+        };
+        crate::util::vec_ops::move_item(&mut self.children, from, to);
+        from != to
+    }
+
     fn find_next(&self, cur: Window) -> Option<&ManagedWindow> {
         let all = self
             .iter_all_windows()
@@ -719,6 +1130,33 @@ pub struct ManagedWindow {
     pub arrange: ArrangeKind,
     pub focus_style: FocusStyle,
     pub properties: WindowProperties,
+    // How the window was drawn before it was maximized, if it is maximized
+    pub pre_maximize: Option<PreMaximize>,
+    // Where the window was last floating, if it has been floating and then tiled
+    pub last_floating: Option<FloatingGeometry>,
+    // Monitors spanned while fullscreen as top, bottom, left, right, set through `_NET_WM_FULLSCREEN_MONITORS`
+    pub fullscreen_monitors: Option<[usize; 4]>,
+    // Told it's fullscreen when it asks to be, while staying in the layout
+    pub fake_fullscreen: bool,
+    // Monitor the window stays on when its workspace is moved to another monitor
+    pub pinned_monitor: Option<usize>,
+}
+
+/// Geometry of a floating window, position relative to its monitor like [`ArrangeKind::FloatingInactive`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FloatingGeometry {
+    pub rel_x: f32,
+    pub rel_y: f32,
+    pub width: i16,
+    pub height: i16,
+}
+
+/// How a window was drawn before it was maximized, to restore it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PreMaximize {
+    pub dimensions: Dimensions,
+    /// Tiled windows are floated while maximized and go back to being tiled when restored
+    pub tiled: bool,
 }
 
 #[cfg(test)]
@@ -748,14 +1186,33 @@ impl ManagedWindow {
         focus_style: FocusStyle,
         properties: WindowProperties,
     ) -> Self {
+        let fake_fullscreen = properties
+            .class
+            .iter()
+            .any(|name| FAKE_FULLSCREEN_CLASSES.contains(&name.as_str()));
         ManagedWindow {
             window,
             wants_focus: false,
             arrange,
             focus_style,
             properties,
+            pre_maximize: None,
+            last_floating: None,
+            fullscreen_monitors: None,
+            fake_fullscreen,
+            pinned_monitor: None,
         }
     }
+
+    /// The window identifying the application this window belongs to, its `WM_CLIENT_LEADER`,
+    /// or its `WM_HINTS` window group if it has no leader, or itself if it has neither
+    #[must_use]
+    pub fn group(&self) -> Window {
+        self.properties
+            .leader
+            .or_else(|| self.properties.hints.and_then(|hints| hints.window_group))
+            .unwrap_or(self.window)
+    }
 }
 
 #[cfg(test)]
@@ -763,11 +1220,17 @@ mod tests {
     use alloc::vec;
     use alloc::vec::Vec;
 
+    use xcb_rust_protocol::proto::xproto::Window;
+
     use crate::config::USER_WORKSPACES;
     use crate::geometry::draw::Mode;
     use crate::geometry::layout::Layout;
+    use crate::geometry::Dimensions;
+    use crate::state::placement::PlacementHistory;
     use crate::state::properties::{WindowProperties, WmName};
-    use crate::state::workspace::{ArrangeKind, DeleteResult, FocusStyle, Workspaces};
+    use crate::state::workspace::{
+        ArrangeKind, DeleteResult, FloatingGeometry, FocusStyle, PreMaximize, Workspaces,
+    };
 
     fn default_properties() -> WindowProperties {
         WindowProperties {
@@ -782,6 +1245,8 @@ mod tests {
             protocols: heapless::Vec::default(),
             name: WmName::NetWmName(heapless::String::default()),
             transient_for: None,
+            colormap_windows: heapless::Vec::default(),
+            gtk_frame_extents: None,
         }
     }
 
@@ -921,6 +1386,60 @@ mod tests {
         assert_eq!(workspaces, empty_workspaces());
     }
 
+    #[test]
+    fn tracks_nested_transients() {
+        let mut workspaces = empty_workspaces();
+        workspaces
+            .add_child_to_ws(
+                0,
+                0,
+                ArrangeKind::NoFloat,
+                FocusStyle::Passive,
+                &default_properties(),
+            )
+            .unwrap();
+        // 1 and 3 are attached to 0, 2 is attached to 1
+        for (parent, attached) in [(0, 1), (1, 2), (0, 3)] {
+            assert!(workspaces
+                .add_attached(
+                    parent,
+                    attached,
+                    ArrangeKind::FloatingInactive(0.0, 0.0),
+                    FocusStyle::Passive,
+                    &default_properties(),
+                )
+                .unwrap());
+        }
+        assert!(workspaces.get_managed_win(2).is_some());
+        assert_eq!(Some(0), workspaces.find_ws_containing_window(2));
+        assert_eq!(0, workspaces.find_transient_root(2));
+        assert_eq!(0, workspaces.find_transient_root(0));
+        assert_eq!(3, workspaces.find_all_attached_managed(0).unwrap().len());
+        assert_eq!(
+            &[1, 2, 3],
+            workspaces.transient_stacking_order(0).as_slice()
+        );
+        assert_eq!(&[1, 2], workspaces.transient_stacking_order(1).as_slice());
+        assert_eq!(&[2], workspaces.transient_stacking_order(2).as_slice());
+        assert_eq!(vec![0, 1, 2, 3], workspaces.stacking_order_in_ws(0));
+
+        // Windows attached to a removed window move to its parent
+        assert!(matches!(
+            workspaces.delete_child_from_ws(1),
+            DeleteResult::AttachedFloating((0, _))
+        ));
+        assert_eq!(&[2, 3], workspaces.transient_stacking_order(0).as_slice());
+        assert_eq!(0, workspaces.find_transient_root(2));
+
+        assert!(matches!(
+            workspaces.delete_child_from_ws(0),
+            DeleteResult::TiledTopLevel(_)
+        ));
+        assert!(workspaces.get_managed_win(2).is_none());
+        assert_eq!(2, workspaces.find_transient_root(2));
+        assert_eq!(workspaces, empty_workspaces());
+    }
+
     #[test]
     fn finds_ws_containing_window() {
         let mut workspaces = empty_workspaces();
@@ -977,6 +1496,43 @@ mod tests {
         assert_eq!(workspaces, empty_workspaces());
     }
 
+    #[test]
+    fn toggling_tabbed_restores_mode_state() {
+        let mut workspaces = empty_workspaces();
+        for window in 0..3 {
+            workspaces
+                .add_child_to_ws(
+                    window,
+                    0,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &default_properties(),
+                )
+                .unwrap();
+        }
+        workspaces.cycle_tiling_mode(0);
+        assert!(workspaces.update_size_modifier(1, 0.1));
+        let modifiers = workspaces.get_ws(0).tiling_modifiers;
+        assert!(workspaces.toggle_tabbed(0));
+        assert_eq!(Mode::Tabbed(0), workspaces.get_draw_mode(0));
+        assert!(workspaces.switch_tab_focus_index(0, 2));
+        assert!(workspaces.toggle_tabbed(0));
+        assert_eq!(
+            Mode::Tiled(Layout::CenterLeader),
+            workspaces.get_draw_mode(0)
+        );
+        assert_eq!(modifiers, workspaces.get_ws(0).tiling_modifiers);
+        assert!(workspaces.toggle_tabbed(0));
+        assert_eq!(Mode::Tabbed(2), workspaces.get_draw_mode(0));
+        // The shown tab is closed, windows are added to the front so that's the first window
+        workspaces.delete_child_from_ws(0);
+        assert!(workspaces.toggle_tabbed(0));
+        assert!(workspaces.toggle_tabbed(0));
+        assert_eq!(Mode::Tabbed(0), workspaces.get_draw_mode(0));
+        workspaces.set_fullscreened(0, 0).unwrap();
+        assert!(!workspaces.toggle_tabbed(0));
+    }
+
     #[test]
     fn can_find_first_tiled() {
         let mut workspaces = empty_workspaces();
@@ -1265,4 +1821,304 @@ mod tests {
         assert!(!workspaces.is_managed_floating(0));
         assert_eq!(workspaces, empty_workspaces());
     }
+
+    #[test]
+    fn can_toggle_maximized() {
+        let mut workspaces = empty_workspaces();
+        let previous = Dimensions::new(100, 100, 50, 50);
+        assert!(workspaces.toggle_maximized(0, previous).is_none());
+        workspaces
+            .add_child_to_ws(
+                0,
+                0,
+                ArrangeKind::FloatingInactive(0.0, 0.0),
+                FocusStyle::Passive,
+                &default_properties(),
+            )
+            .unwrap();
+        assert_eq!(Some(None), workspaces.toggle_maximized(0, previous));
+        let floating = PreMaximize {
+            dimensions: previous,
+            tiled: false,
+        };
+        assert_eq!(
+            Some(floating),
+            workspaces.get_managed_win(0).unwrap().pre_maximize
+        );
+        // Current dimensions are disregarded when restoring
+        assert_eq!(
+            Some(Some(floating)),
+            workspaces.toggle_maximized(0, Dimensions::new(1000, 1000, 0, 0))
+        );
+        assert!(workspaces
+            .get_managed_win(0)
+            .unwrap()
+            .pre_maximize
+            .is_none());
+        // Unfloating drops the maximized state
+        assert_eq!(Some(None), workspaces.toggle_maximized(0, previous));
+        assert_eq!(Some(true), workspaces.un_float_window(0));
+        assert!(workspaces
+            .get_managed_win(0)
+            .unwrap()
+            .pre_maximize
+            .is_none());
+        // Tiled windows remember that they were tiled
+        assert_eq!(Some(None), workspaces.toggle_maximized(0, previous));
+        assert_eq!(
+            Some(Some(PreMaximize {
+                dimensions: previous,
+                tiled: true,
+            })),
+            workspaces.toggle_maximized(0, previous)
+        );
+        workspaces.delete_child_from_ws(0);
+        assert_eq!(workspaces, empty_workspaces());
+    }
+
+    #[test]
+    fn can_mark_and_unmark() {
+        let mut workspaces = empty_workspaces();
+        // Unmanaged can't be marked
+        assert!(!workspaces.toggle_mark(0, "a"));
+        for win in 0..3 {
+            workspaces
+                .add_child_to_ws(
+                    win,
+                    win as usize,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &default_properties(),
+                )
+                .unwrap();
+        }
+        assert!(workspaces.toggle_mark(0, "a"));
+        assert_eq!(Some("a"), workspaces.get_mark(0));
+        assert_eq!(Some(0), workspaces.find_marked("a"));
+        // Moving a mark
+        assert!(workspaces.toggle_mark(1, "a"));
+        assert!(workspaces.get_mark(0).is_none());
+        assert_eq!(Some(1), workspaces.find_marked("a"));
+        // Re-marking replaces
+        assert!(workspaces.toggle_mark(1, "b"));
+        assert!(workspaces.find_marked("a").is_none());
+        assert_eq!(Some(1), workspaces.find_marked("b"));
+        // Toggling off
+        assert!(!workspaces.toggle_mark(1, "b"));
+        assert!(workspaces.get_mark(1).is_none());
+
+        assert!(workspaces.toggle_mark(0, "a"));
+        assert!(workspaces.toggle_mark(2, "c"));
+        let mut marked = workspaces.take_marked();
+        marked.sort_unstable();
+        assert_eq!(vec![0, 2], marked);
+        assert!(workspaces.take_marked().is_empty());
+        assert!(workspaces.toggle_mark(2, "c"));
+        workspaces.unmark(2);
+        assert!(workspaces.get_mark(2).is_none());
+        for win in 0..3 {
+            workspaces.delete_child_from_ws(win);
+        }
+        assert_eq!(workspaces, empty_workspaces());
+    }
+
+    #[test]
+    fn moves_to_tiling_index() {
+        let mut workspaces = empty_workspaces();
+        for win in 0..4 {
+            workspaces
+                .add_child_to_ws(
+                    win,
+                    0,
+                    if win == 1 {
+                        ArrangeKind::FloatingActive
+                    } else {
+                        ArrangeKind::NoFloat
+                    },
+                    FocusStyle::Passive,
+                    &default_properties(),
+                )
+                .unwrap();
+        }
+        let tiled = |workspaces: &Workspaces| {
+            workspaces
+                .get_all_tiled_windows(0)
+                .iter()
+                .map(|mw| mw.window)
+                .collect::<Vec<Window>>()
+        };
+        // Pushed to front on add
+        assert_eq!(vec![3, 2, 0], tiled(&workspaces));
+        assert!(workspaces.move_to_tiling_index(3, 1));
+        assert_eq!(vec![2, 3, 0], tiled(&workspaces));
+        assert!(workspaces.move_to_tiling_index(0, 0));
+        assert_eq!(vec![0, 2, 3], tiled(&workspaces));
+        // Out of range goes last
+        assert!(workspaces.move_to_tiling_index(0, 10));
+        assert_eq!(vec![2, 3, 0], tiled(&workspaces));
+        assert!(!workspaces.move_to_tiling_index(0, 2));
+        // Floating windows aren't tiled
+        assert!(!workspaces.move_to_tiling_index(1, 0));
+        assert!(!workspaces.move_to_tiling_index(15, 0));
+    }
+
+    #[test]
+    fn maps_workspaces_per_set() {
+        let mut workspaces = empty_workspaces();
+        let set_size = USER_WORKSPACES.len();
+        assert_eq!(1, workspaces.num_sets());
+        // A single set is shared by every monitor
+        assert_eq!(0, workspaces.set_of_monitor(1));
+        assert_eq!(
+            Some(2),
+            workspaces.find_ws_for_window_class_name("Navigator", 0)
+        );
+        workspaces.ensure_sets(&USER_WORKSPACES, 3);
+        assert_eq!(3, workspaces.num_sets());
+        assert_eq!(3 * set_size, workspaces.num_workspaces());
+        // Never shrinks
+        workspaces.ensure_sets(&USER_WORKSPACES, 2);
+        assert_eq!(3, workspaces.num_sets());
+        assert_eq!(1, workspaces.set_of_monitor(1));
+        assert_eq!(Some(set_size + 4), workspaces.in_set(1, 4));
+        assert_eq!(None, workspaces.in_set(1, set_size));
+        assert_eq!(None, workspaces.in_set(3, 0));
+        assert_eq!(1, workspaces.set_of(set_size + 4));
+        assert_eq!(Some(4), workspaces.index_in_set(1, set_size + 4));
+        assert_eq!(None, workspaces.index_in_set(2, set_size + 4));
+        assert_eq!(
+            Some(2 * set_size + 2),
+            workspaces.find_ws_for_window_class_name("Navigator", 2)
+        );
+        assert_eq!(
+            USER_WORKSPACES[4].name,
+            workspaces.get_ws(2 * set_size + 4).name
+        );
+    }
+
+    #[test]
+    fn remembers_placements_per_set() {
+        let mut workspaces = empty_workspaces();
+        workspaces.ensure_sets(&USER_WORKSPACES, 2);
+        let set_size = USER_WORKSPACES.len();
+        let mut properties = default_properties();
+        properties
+            .class
+            .push(heapless::String::try_from("Navigator").unwrap())
+            .unwrap();
+        properties
+            .class
+            .push(heapless::String::try_from("firefox").unwrap())
+            .unwrap();
+        workspaces
+            .add_child_to_ws(
+                0,
+                set_size + 5,
+                ArrangeKind::NoFloat,
+                FocusStyle::Passive,
+                &properties,
+            )
+            .unwrap();
+        // Disabled
+        assert!(!workspaces.remember_placement(0, None));
+        workspaces.set_placement_history(PlacementHistory::new(4));
+        let floating = FloatingGeometry {
+            rel_x: 0.1,
+            rel_y: 0.2,
+            width: 300,
+            height: 200,
+        };
+        assert!(workspaces.remember_placement(0, Some(floating)));
+        // Not managed
+        assert!(!workspaces.remember_placement(1, None));
+        // Keyed on the class, not the instance
+        assert_eq!(
+            None,
+            workspaces.find_placement_for_window_class_name("Navigator", 0)
+        );
+        assert_eq!(
+            Some((5, Some(floating))),
+            workspaces.find_placement_for_window_class_name("firefox", 0)
+        );
+        assert_eq!(
+            Some((set_size + 5, Some(floating))),
+            workspaces.find_placement_for_window_class_name("firefox", 1)
+        );
+    }
+
+    #[test]
+    fn moves_pinned_windows() {
+        let mut workspaces = empty_workspaces();
+        for window in 0..3 {
+            workspaces
+                .add_child_to_ws(
+                    window,
+                    0,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    &default_properties(),
+                )
+                .unwrap();
+        }
+        assert!(workspaces
+            .add_attached(
+                1,
+                3,
+                ArrangeKind::FloatingActive,
+                FocusStyle::Passive,
+                &default_properties(),
+            )
+            .unwrap());
+        workspaces.get_managed_win_mut(1).unwrap().pinned_monitor = Some(0);
+        workspaces.get_managed_win_mut(2).unwrap().pinned_monitor = Some(1);
+        workspaces.set_fullscreened(0, 1).unwrap();
+        assert_eq!(vec![1], workspaces.move_pinned(0, 4, 0).unwrap());
+        assert_eq!(Some(4), workspaces.find_ws_containing_window(1));
+        // Attached windows follow
+        assert_eq!(Some(4), workspaces.find_ws_containing_window(3));
+        assert_eq!(2, workspaces.iter_all_managed_windows_in_ws(4).count());
+        assert_eq!(Some(0), workspaces.find_ws_containing_window(0));
+        assert_eq!(Some(0), workspaces.find_ws_containing_window(2));
+        assert_eq!(2, workspaces.iter_all_managed_windows_in_ws(0).count());
+        assert!(!matches!(
+            workspaces.get_draw_mode(0),
+            Mode::Fullscreen { .. }
+        ));
+        assert!(workspaces.move_pinned(0, 4, 2).unwrap().is_empty());
+        assert!(workspaces.move_pinned(4, 4, 0).unwrap().is_empty());
+    }
+    #[test]
+    fn finds_group_members() {
+        let mut workspaces = empty_workspaces();
+        // An application with a leader on workspace 0 and 2, a lone window on 1
+        let mut class = heapless::Vec::new();
+        let _ = class.push(heapless::String::try_from("Navigator").unwrap());
+        let _ = class.push(heapless::String::try_from("Firefox").unwrap());
+        let grouped = WindowProperties {
+            leader: Some(10),
+            class,
+            ..default_properties()
+        };
+        let lone = default_properties();
+        for (window, ws_ind, properties) in [(0, 2, &grouped), (1, 1, &lone), (2, 0, &grouped)] {
+            workspaces
+                .add_child_to_ws(
+                    window,
+                    ws_ind,
+                    ArrangeKind::NoFloat,
+                    FocusStyle::Passive,
+                    properties,
+                )
+                .unwrap();
+        }
+        assert_eq!(vec![2, 0], workspaces.group_members(0));
+        assert_eq!(Some(2), workspaces.next_in_group(0));
+        assert_eq!(Some(0), workspaces.next_in_group(2));
+        assert_eq!(vec![1], workspaces.group_members(1));
+        assert!(workspaces.next_in_group(1).is_none());
+        assert!(workspaces.group_members(3).is_empty());
+        assert_eq!(vec![2, 0], workspaces.with_class("Firefox"));
+        assert_eq!(vec![2, 0], workspaces.with_class("Navigator"));
+        assert!(workspaces.with_class("firefox").is_empty());
+    }
 }
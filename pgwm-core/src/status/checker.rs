@@ -1,19 +1,24 @@
 use alloc::string::ToString;
-use core::cmp::Ordering;
 use core::ops::Add;
 use core::time::Duration;
 
 use heapless::String;
 use smallmap::{Collapse, Map};
 use tiny_std::time::Instant;
+use tiny_std::UnixStr;
+use xcb_rust_protocol::proto::xproto::ButtonIndexEnum;
 
-use crate::config::{STATUS_CHECKS, _STATUS_BAR_CHECK_CONTENT_LIMIT};
+use crate::config::{Action, _STATUS_BAR_CHECK_CONTENT_LIMIT};
 use crate::format_heapless;
 use crate::status::cpu::LoadChecker;
 use crate::status::net::{ThroughputChecker, ThroughputPerSec};
+use crate::status::ping::{PingChecker, ECHO_REQUEST_LEN};
 use crate::status::sys::bat::parse_battery_percentage;
+use crate::status::sys::cpu::parse_load_avg;
 use crate::status::sys::mem::{parse_raw, Data};
+use crate::status::sys::privacy::{read_capture, Capture};
 use crate::status::time::ClockFormatter;
+use crate::util::deadline::DeadlineWheel;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Check {
@@ -29,6 +34,11 @@ pub enum CheckType {
     Net(NetFormat),
     Mem(MemFormat),
     Date(DateFormat),
+    Keyboard(KeyboardFormat),
+    Privacy(PrivacyFormat),
+    Media(MediaFormat),
+    Ping(PingFormat),
+    External(ExternalFormat),
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -91,12 +101,42 @@ impl BatFormat {
 pub struct CpuFormat {
     icon: &'static str,
     decimals: usize,
+    mode: CpuMode,
+}
+
+/// What the cpu check shows
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CpuMode {
+    /// Load percentage over all cores
+    Total,
+    /// A bar per core, as many as fits in the check's content
+    PerCore,
+    /// The 1, 5, and 15 minute load averages
+    LoadAvg,
 }
 
 impl CpuFormat {
+    const CORE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
     #[must_use]
     pub const fn new(icon: &'static str, decimals: usize) -> Self {
-        Self { icon, decimals }
+        Self {
+            icon,
+            decimals,
+            mode: CpuMode::Total,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_mode(mut self, mode: CpuMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn mode(&self) -> CpuMode {
+        self.mode
     }
 
     fn format_cpu(&self, load_percentage: f64) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
@@ -114,21 +154,46 @@ impl CpuFormat {
         )
     }
 
-    #[must_use]
-    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+    fn format_cores(&self, core_percentages: &[f64]) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        let mut content = format_heapless!("{} ", self.icon);
+        for perc in core_percentages {
+            let ind = ((perc / 100f64 * Self::CORE_BARS.len() as f64) as usize)
+                .min(Self::CORE_BARS.len() - 1);
+            if content.push(Self::CORE_BARS[ind]).is_err() {
+                break;
+            }
+        }
+        content
+    }
+
+    fn format_load_avg(&self, avgs: [f64; 3]) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
         let chars = if self.decimals > 0 {
-            self.decimals + 4
+            self.decimals + 3
         } else {
-            3
+            2
         };
         format_heapless!(
-            "{} {:N$.D$}%",
+            "{} {:N$.D$} {:N$.D$} {:N$.D$}",
             self.icon,
-            99.999_999_999,
+            avgs[0],
+            avgs[1],
+            avgs[2],
             N = chars,
             D = self.decimals
         )
     }
+
+    #[must_use]
+    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        match self.mode {
+            CpuMode::Total => self.format_cpu(99.999_999_999),
+            CpuMode::PerCore => {
+                let cores = crate::status::sys::cpu::read_core_count().unwrap_or(1);
+                self.format_cores(&alloc::vec![100f64; cores])
+            }
+            CpuMode::LoadAvg => self.format_load_avg([99.999_999_999; 3]),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -136,6 +201,25 @@ pub struct NetFormat {
     icon_up: &'static str,
     icon_down: &'static str,
     decimals: usize,
+    interfaces: &'static [&'static str],
+    unit: NetUnit,
+    scale: NetScale,
+}
+
+/// Whether throughput is shown in bytes or bits per second
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NetUnit {
+    Bytes,
+    Bits,
+}
+
+/// The prefix throughput is shown with, [`NetScale::Auto`] picks the largest one that keeps the value above 1
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NetScale {
+    Auto,
+    Kilo,
+    Mega,
+    Giga,
 }
 
 impl NetFormat {
@@ -145,16 +229,65 @@ impl NetFormat {
             icon_up,
             icon_down,
             decimals,
+            interfaces: &[],
+            unit: NetUnit::Bytes,
+            scale: NetScale::Auto,
         }
     }
+
+    /// Only sum the throughput of these interfaces, fe. `&["wlan0"]`, instead of every interface except loopback.
+    /// Interfaces that aren't there, like a plugged out usb adapter, count as no throughput until they show up
+    #[must_use]
+    pub const fn with_interfaces(mut self, interfaces: &'static [&'static str]) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_unit(mut self, unit: NetUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_scale(mut self, scale: NetScale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn interfaces(&self) -> &'static [&'static str] {
+        self.interfaces
+    }
+
+    /// The value to show for a throughput in bytes per second, and its unit
+    fn scale_throughput(&self, bytes_per_sec: f64) -> (&'static str, f64) {
+        const DIVISORS: [f64; 5] = [1e3, 1e6, 1e9, 1e12, 1e15];
+        let (value, units) = match self.unit {
+            NetUnit::Bytes => (bytes_per_sec, ["KB", "MB", "GB", "TB", "PB"]),
+            NetUnit::Bits => (bytes_per_sec * 8f64, ["Kb", "Mb", "Gb", "Tb", "Pb"]),
+        };
+        let ind = match self.scale {
+            NetScale::Auto => DIVISORS
+                .iter()
+                .position(|divisor| value < divisor * 1000f64)
+                .unwrap_or(DIVISORS.len() - 1),
+            NetScale::Kilo => 0,
+            NetScale::Mega => 1,
+            NetScale::Giga => 2,
+        };
+        (units[ind], value / DIVISORS[ind])
+    }
+
     fn format_net(&self, net_stats: ThroughputPerSec) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
-        let (up_short, up_val) = compress_to_display(net_stats.up);
+        let (up_short, up_val) = self.scale_throughput(net_stats.up);
         let chars = if self.decimals > 0 {
             self.decimals + 4
         } else {
             3
         };
-        let (dn_short, dn_val) = compress_to_display(net_stats.down);
+        let (dn_short, dn_val) = self.scale_throughput(net_stats.down);
         format_heapless!(
             "{} {:N$.D$}{} {} {:N$.D$}{}",
             self.icon_down,
@@ -175,13 +308,19 @@ impl NetFormat {
         } else {
             3
         };
+        // A fixed scale can't keep the value below 1000
+        let max = if self.scale == NetScale::Auto {
+            999.999_999_999f64
+        } else {
+            99_999.999_999_999f64
+        };
         format_heapless!(
             "{} {:N$.D$}{} {} {:N$.D$}{}",
             self.icon_down,
-            999.999_999_999f64,
+            max,
             "GB",
             self.icon_up,
-            999.999_999_999f64,
+            max,
             "GB",
             N = chars,
             D = self.decimals
@@ -208,12 +347,41 @@ fn compress_to_display(val: f64) -> (&'static str, f64) {
 pub struct MemFormat {
     icon: &'static str,
     decimals: usize,
+    mode: MemMode,
+    swap_icon: Option<&'static str>,
+}
+
+/// Whether the mem check shows memory that's free to use or memory that's taken
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MemMode {
+    /// `MemAvailable`, what can be allocated without swapping, including reclaimable caches
+    Available,
+    /// `MemTotal` minus `MemAvailable`
+    Used,
 }
 
 impl MemFormat {
     #[must_use]
     pub const fn new(icon: &'static str, decimals: usize) -> Self {
-        Self { icon, decimals }
+        Self {
+            icon,
+            decimals,
+            mode: MemMode::Available,
+            swap_icon: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_mode(mut self, mode: MemMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Show swap in use after the memory, behind `icon`
+    #[must_use]
+    pub const fn with_swap(mut self, icon: &'static str) -> Self {
+        self.swap_icon = Some(icon);
+        self
     }
 
     fn format_mem(&self, mem_info: Data) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
@@ -222,15 +390,34 @@ impl MemFormat {
         } else {
             3
         };
-        let (mem_unit, mem_val) = compress_to_display(mem_info.available as f64 * 1000f64);
-        format_heapless!(
+        let mem_kb = match self.mode {
+            MemMode::Available => mem_info.available,
+            MemMode::Used => mem_info.total.saturating_sub(mem_info.available),
+        };
+        let (mem_unit, mem_val) = compress_to_display(mem_kb as f64 * 1000f64);
+        let mut content = format_heapless!(
             "{} {:N$.D$}{}",
             self.icon,
             mem_val,
             mem_unit,
             N = chars,
             D = self.decimals,
-        )
+        );
+        if let Some(swap_icon) = self.swap_icon {
+            let (swap_unit, swap_val) = compress_to_display(mem_info.swapped as f64 * 1000f64);
+            let _ = core::fmt::write(
+                &mut content,
+                format_args!(
+                    " {} {:N$.D$}{}",
+                    swap_icon,
+                    swap_val,
+                    swap_unit,
+                    N = chars,
+                    D = self.decimals,
+                ),
+            );
+        }
+        content
     }
 
     #[must_use]
@@ -240,14 +427,28 @@ impl MemFormat {
         } else {
             3
         };
-        format_heapless!(
+        let mut content = format_heapless!(
             "{} {:N$.D$}{}",
             self.icon,
             999.999_999_999f64,
             "GB",
             N = chars,
             D = self.decimals,
-        )
+        );
+        if let Some(swap_icon) = self.swap_icon {
+            let _ = core::fmt::write(
+                &mut content,
+                format_args!(
+                    " {} {:N$.D$}{}",
+                    swap_icon,
+                    999.999_999_999f64,
+                    "GB",
+                    N = chars,
+                    D = self.decimals,
+                ),
+            );
+        }
+        content
     }
 }
 
@@ -255,6 +456,7 @@ impl MemFormat {
 pub struct DateFormat {
     icon: &'static str,
     clock_formatter: ClockFormatter,
+    alternatives: &'static [ClockFormatter],
 }
 
 impl DateFormat {
@@ -263,62 +465,305 @@ impl DateFormat {
         Self {
             icon,
             clock_formatter,
+            alternatives: &[],
         }
     }
 
+    /// Formats to cycle through after the first one when the check is clicked, fe. UTC or another time zone
     #[must_use]
-    pub fn format_date(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
-        let output = self
-            .clock_formatter
+    pub const fn with_alternatives(mut self, alternatives: &'static [ClockFormatter]) -> Self {
+        self.alternatives = alternatives;
+        self
+    }
+
+    /// The first format and its alternatives
+    #[must_use]
+    pub fn num_formats(&self) -> usize {
+        1 + self.alternatives.len()
+    }
+
+    /// Formats the date with the format at `format_ind`, 0 being the first format and the rest alternatives
+    #[must_use]
+    pub fn format_date(&self, format_ind: usize) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        let clock_formatter = format_ind
+            .checked_sub(1)
+            .and_then(|ind| self.alternatives.get(ind))
+            .unwrap_or(&self.clock_formatter);
+        let output = clock_formatter
             .format_now()
             .unwrap_or_else(|_| "Failed to format get date".to_string());
         format_heapless!("{} {}", self.icon, output)
     }
 }
 
+/// Shows the keyboard layout of the active XKB group, needs the WM to be built with the `xkb` feature
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KeyboardFormat {
+    icon: &'static str,
+    max_chars: usize,
+}
+
+impl KeyboardFormat {
+    #[must_use]
+    pub const fn new(icon: &'static str, max_chars: usize) -> Self {
+        Self { icon, max_chars }
+    }
+
+    fn format_layout(&self, layout: &[u8]) -> Option<String<_STATUS_BAR_CHECK_CONTENT_LIMIT>> {
+        let layout = core::str::from_utf8(layout)
+            .ok()
+            .filter(|layout| !layout.is_empty())?;
+        Some(format_heapless!(
+            "{} {:.N$}",
+            self.icon,
+            layout,
+            N = self.max_chars
+        ))
+    }
+
+    #[must_use]
+    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        format_heapless!("{} {:W<N$}", self.icon, "", N = self.max_chars)
+    }
+}
+
+/// Shows which of the microphone and camera are in use, nothing if neither is.
+/// Drawn in [`crate::config::STATUS_BAR_ALERT_TEXT`] while something's in use
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PrivacyFormat {
+    mic_icon: &'static str,
+    camera_icon: &'static str,
+}
+
+impl PrivacyFormat {
+    #[must_use]
+    pub const fn new(mic_icon: &'static str, camera_icon: &'static str) -> Self {
+        Self {
+            mic_icon,
+            camera_icon,
+        }
+    }
+
+    fn format_capture(&self, capture: Capture) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        match (capture.audio, capture.camera) {
+            (true, true) => format_heapless!("{} {}", self.mic_icon, self.camera_icon),
+            (true, false) => format_heapless!("{}", self.mic_icon),
+            (false, true) => format_heapless!("{}", self.camera_icon),
+            (false, false) => String::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        self.format_capture(Capture {
+            audio: true,
+            camera: true,
+        })
+    }
+}
+
+/// Shows the latest line written to a fifo, like the artist and title of what's playing from
+/// `playerctl --follow metadata --format '{{artist}} - {{title}}' > <fifo>`. The fifo is created with `mkfifo`
+/// and kept open, an empty line hides the check. Left click plays or pauses, and right click skips to the next
+/// track, through `controller play-pause` and `controller next`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MediaFormat {
+    icon: &'static str,
+    fifo: &'static UnixStr,
+    max_chars: usize,
+    controller: &'static UnixStr,
+}
+
+impl MediaFormat {
+    const PLAY_PAUSE: &'static [&'static UnixStr] = &[UnixStr::from_str_checked("play-pause\0")];
+    const NEXT: &'static [&'static UnixStr] = &[UnixStr::from_str_checked("next\0")];
+
+    #[must_use]
+    pub const fn new(icon: &'static str, fifo: &'static UnixStr, max_chars: usize) -> Self {
+        Self {
+            icon,
+            fifo,
+            max_chars,
+            controller: UnixStr::from_str_checked("playerctl\0"),
+        }
+    }
+
+    /// Control playback with another `playerctl` compatible program than `playerctl`
+    #[must_use]
+    pub const fn with_controller(mut self, controller: &'static UnixStr) -> Self {
+        self.controller = controller;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn fifo(&self) -> &'static UnixStr {
+        self.fifo
+    }
+
+    /// What clicking the check with `button` does
+    #[must_use]
+    pub fn click_action(&self, button: u8) -> Option<Action> {
+        if button == ButtonIndexEnum::ONE.0 {
+            Some(Action::Spawn(self.controller, Self::PLAY_PAUSE))
+        } else if button == ButtonIndexEnum::THREE.0 {
+            Some(Action::Spawn(self.controller, Self::NEXT))
+        } else {
+            None
+        }
+    }
+
+    /// The latest complete line of what's been written since the last check, `None` if there's none
+    fn format_media(&self, written: &[u8]) -> Option<String<_STATUS_BAR_CHECK_CONTENT_LIMIT>> {
+        let written = core::str::from_utf8(written).ok()?;
+        let latest = written.strip_suffix('\n')?.rsplit('\n').next()?.trim();
+        if latest.is_empty() {
+            return Some(String::new());
+        }
+        let mut content = format_heapless!("{} ", self.icon);
+        // Cut off at whichever comes first of the max chars and the content limit
+        for c in latest.chars().take(self.max_chars) {
+            if content.push(c).is_err() {
+                break;
+            }
+        }
+        Some(content)
+    }
+
+    #[must_use]
+    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        format_heapless!("{} {:W<N$}", self.icon, "", N = self.max_chars)
+    }
+}
+
+/// Shows the round trip time of pinging an IPv4 address, or that it's unreachable if there's been no reply
+/// by the next ping. No DNS lookups are made so that the check says something about the connection even when
+/// name resolution is down. Needs unprivileged ICMP sockets, which `net.ipv4.ping_group_range` allows by default on
+/// most distributions
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PingFormat {
+    icon: &'static str,
+    target: [u8; 4],
+}
+
+impl PingFormat {
+    #[must_use]
+    pub const fn new(icon: &'static str, target: [u8; 4]) -> Self {
+        Self { icon, target }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn target(&self) -> [u8; 4] {
+        self.target
+    }
+
+    fn format_ping(&self, round_trip: Option<Duration>) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        match round_trip {
+            Some(round_trip) => {
+                format_heapless!("{} {:4}ms", self.icon, round_trip.as_millis().min(9999))
+            }
+            None => format_heapless!("{} {:>4}", self.icon, "--"),
+        }
+    }
+
+    #[must_use]
+    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        self.format_ping(Some(Duration::from_millis(9999)))
+    }
+}
+
+/// Where an [`ExternalFormat`] check gets its content from
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExternalSource {
+    /// The latest line written to a fifo created with `mkfifo`, like the output of
+    /// `xmobar`-style generators piped into it
+    Fifo(&'static UnixStr),
+    /// The root window's name, the `xsetroot -name` protocol that `dwmblocks` and `slstatus` use
+    RootName,
+}
+
+/// Shows what an external status generator produces as is, in place of a built-in check.
+/// An empty line or name hides the check
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExternalFormat {
+    source: ExternalSource,
+    max_chars: usize,
+}
+
+impl ExternalFormat {
+    #[must_use]
+    pub const fn new(source: ExternalSource, max_chars: usize) -> Self {
+        Self { source, max_chars }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn source(&self) -> ExternalSource {
+        self.source
+    }
+
+    /// The latest complete line written to a fifo since the last check or the whole root name,
+    /// `None` if a fifo has no new line
+    fn format_external(&self, written: &[u8]) -> Option<String<_STATUS_BAR_CHECK_CONTENT_LIMIT>> {
+        let written = core::str::from_utf8(written).ok()?;
+        let latest = match self.source {
+            ExternalSource::Fifo(_) => written.strip_suffix('\n')?.rsplit('\n').next()?,
+            ExternalSource::RootName => written,
+        };
+        let mut content = String::new();
+        // Cut off at whichever comes first of the max chars and the content limit
+        for c in latest.trim().chars().take(self.max_chars) {
+            if content.push(c).is_err() {
+                break;
+            }
+        }
+        Some(content)
+    }
+
+    #[must_use]
+    pub fn max_length_content(&self) -> String<_STATUS_BAR_CHECK_CONTENT_LIMIT> {
+        format_heapless!("{:W<N$}", "", N = self.max_chars)
+    }
+}
+
 pub struct Checker<'a> {
     cpu_checker: LoadChecker,
     net_checker: ThroughputChecker,
+    ping_checker: PingChecker,
     checks_by_key: Map<NextCheck, PackagedCheck<'a>>,
+    deadlines: DeadlineWheel<NextCheck>,
 }
 
 #[derive(PartialEq, Eq)]
 struct PackagedCheck<'a> {
-    next_time: Instant,
+    /// Millis between checks, starts out as the configured interval
+    interval: u64,
+    /// When the check was last due, next deadlines are counted from here to avoid drifting
+    last_due: Instant,
     check: &'a Check,
     position: usize,
+    /// Which of the check's formats is shown, see [`Checker::cycle_format`]
+    format_ind: usize,
 }
 
 impl PackagedCheck<'_> {
-    fn update_check_time(&mut self) {
-        // Using this instead of SystemTime now avoids de-syncs between checks and unnecessary system calls
-        self.next_time = self
-            .next_time
-            .add(Duration::from_millis(self.check.interval))
-            .unwrap();
-    }
-}
-
-impl PartialOrd<Self> for PackagedCheck<'_> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for PackagedCheck<'_> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.next_time.cmp(&other.next_time)
+    fn next_deadline(&self, now: Instant) -> Instant {
+        let interval = Duration::from_millis(self.interval);
+        // Using the last deadline instead of now avoids de-syncs between checks,
+        // but if we've fallen a full interval behind there's no use in catching up
+        match self.last_due.add(interval) {
+            Some(next) if next > now => next,
+            _ => now.add(interval).unwrap(),
+        }
     }
 }
 
 pub struct CheckResult {
     pub content: Option<String<_STATUS_BAR_CHECK_CONTENT_LIMIT>>,
     pub position: usize,
-    pub next_check: Instant,
-}
-
-pub struct CheckSubmitAdvice {
-    pub submit_indices: heapless::FnvIndexSet<NextCheck, { STATUS_CHECKS.len() }>,
+    /// Whether the content should stand out, drawn in the status bar's alert color
+    pub alert: bool,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -328,6 +773,12 @@ pub enum NextCheck {
     NET = 2,
     MEM = 3,
     Date = 4,
+    Keyboard = 5,
+    Privacy = 6,
+    Media = 7,
+    Ping = 8,
+    ExternalFifo = 9,
+    RootName = 10,
 }
 
 impl Collapse for NextCheck {
@@ -337,21 +788,104 @@ impl Collapse for NextCheck {
 }
 
 impl<'a> Checker<'a> {
-    pub fn get_all_check_submits(
-        &mut self,
-    ) -> heapless::Vec<(NextCheck, Instant), { STATUS_CHECKS.len() }> {
-        let mut all = heapless::Vec::new();
-        for (next, check) in self.checks_by_key.iter_mut() {
-            let _ = all.push((*next, check.next_time));
+    /// When the next check is due, checks that have been started but not completed aren't counted
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.next_deadline()
+    }
+
+    /// Take the next check that's due at `now`, it won't be due again until it's been completed
+    pub fn pop_due(&mut self, now: Instant) -> Option<NextCheck> {
+        let (next, due) = self.deadlines.pop_due(now)?;
+        if let Some(packaged) = self.checks_by_key.get_mut(&next) {
+            packaged.last_due = due;
+        }
+        Some(next)
+    }
+
+    /// Change how often a check runs, if it's waiting for its next run it's rescheduled
+    /// to run one new interval from now
+    pub fn set_interval(&mut self, check: NextCheck, interval: u64) {
+        if let Some(packaged) = self.checks_by_key.get_mut(&check) {
+            packaged.interval = interval;
+            if self.deadlines.cancel(check).is_some() {
+                let now = Instant::now();
+                packaged.last_due = now;
+                self.deadlines.schedule(check, packaged.next_deadline(now));
+            }
         }
-        all
     }
+
+    /// The check shown at `position` in the status bar
+    #[must_use]
+    pub fn check_at(&self, position: usize) -> Option<NextCheck> {
+        self.checks_by_key
+            .iter()
+            .find(|(_, packaged)| packaged.position == position)
+            .map(|(check, _)| *check)
+    }
+
+    #[must_use]
+    pub fn interval(&self, check: NextCheck) -> Option<u64> {
+        self.checks_by_key
+            .get(&check)
+            .map(|packaged| packaged.interval)
+    }
+
+    /// Switch the check at `position` in the status bar to its next format, it's due right away to show it.
+    /// Returns whether the check has more than one format to switch between
+    pub fn cycle_format(&mut self, position: usize) -> bool {
+        // Dates are the only checks with alternative formats
+        let Some(packaged) = self
+            .checks_by_key
+            .get_mut(&NextCheck::Date)
+            .filter(|packaged| packaged.position == position)
+        else {
+            return false;
+        };
+        let CheckType::Date(fmt) = &packaged.check.check_type else {
+            return false;
+        };
+        let formats = fmt.num_formats();
+        if formats < 2 {
+            return false;
+        }
+        packaged.format_ind = (packaged.format_ind + 1) % formats;
+        if self.deadlines.cancel(NextCheck::Date).is_some() {
+            let now = Instant::now();
+            packaged.last_due = now;
+            self.deadlines.schedule(NextCheck::Date, now);
+        }
+        true
+    }
+
+    /// The echo request to send for a ping check that's been completed as due
+    pub fn ping_request(&mut self) -> [u8; ECHO_REQUEST_LEN] {
+        self.ping_checker.next_request()
+    }
+
+    /// Show the round trip time if this is the reply to the latest ping,
+    /// the ping check is already scheduled from when it was due
+    pub fn handle_ping_reply(&mut self, reply: &[u8]) -> Option<CheckResult> {
+        let packaged = self.checks_by_key.get(&NextCheck::Ping)?;
+        let CheckType::Ping(fmt) = &packaged.check.check_type else {
+            return None;
+        };
+        let round_trip = self.ping_checker.parse_reply(reply)?;
+        Some(CheckResult {
+            content: Some(fmt.format_ping(Some(round_trip))),
+            position: packaged.position,
+            alert: false,
+        })
+    }
+
     pub fn handle_completed(
         &mut self,
         completed: NextCheck,
         content: &[u8],
     ) -> Option<CheckResult> {
         let packaged = self.checks_by_key.get_mut(&completed)?;
+        let mut alert = false;
         let content = match &packaged.check.check_type {
             CheckType::Battery(limits) => parse_battery_percentage(content).ok().and_then(|bat| {
                 limits
@@ -359,89 +893,90 @@ impl<'a> Checker<'a> {
                     .iter()
                     .find_map(|limit| limit.format_bat(bat))
             }),
-            CheckType::Cpu(fmt) => self
-                .cpu_checker
-                .parse_load(content)
-                .ok()
-                .map(|cpu| fmt.format_cpu(cpu)),
+            CheckType::Cpu(fmt) => match fmt.mode {
+                CpuMode::Total => self
+                    .cpu_checker
+                    .parse_load(content)
+                    .ok()
+                    .map(|cpu| fmt.format_cpu(cpu)),
+                CpuMode::PerCore => self
+                    .cpu_checker
+                    .parse_core_loads(content)
+                    .ok()
+                    .map(|cores| fmt.format_cores(&cores)),
+                CpuMode::LoadAvg => parse_load_avg(content)
+                    .ok()
+                    .map(|avgs| fmt.format_load_avg(avgs)),
+            },
             CheckType::Net(fmt) => self
                 .net_checker
-                .parse_throughput(content)
+                .parse_throughput(content, fmt.interfaces())
                 .ok()
                 .map(|tp| fmt.format_net(tp)),
             CheckType::Mem(fmt) => parse_raw(content).ok().map(|mem| fmt.format_mem(mem)),
-            CheckType::Date(fmt) => Some(fmt.format_date()),
+            CheckType::Date(fmt) => Some(fmt.format_date(packaged.format_ind)),
+            CheckType::Keyboard(fmt) => fmt.format_layout(content),
+            CheckType::Privacy(fmt) => {
+                let capture = read_capture();
+                alert = capture.any();
+                Some(fmt.format_capture(capture))
+            }
+            CheckType::Media(fmt) => fmt.format_media(content),
+            // Replies come through `handle_ping_reply`, a ping that's due with one still unanswered was lost
+            CheckType::Ping(fmt) => self
+                .ping_checker
+                .awaiting_reply()
+                .then(|| fmt.format_ping(None)),
+            CheckType::External(fmt) => fmt.format_external(content),
         };
-        packaged.update_check_time();
+        self.deadlines
+            .schedule(completed, packaged.next_deadline(Instant::now()));
         Some(CheckResult {
             content,
             position: packaged.position,
-            next_check: packaged.next_time,
+            alert,
         })
     }
 
     pub fn new(checks: &'a mut [Check]) -> Self {
         let mut checks_by_key = Map::new();
+        let mut deadlines = DeadlineWheel::new();
         let sync_start_time = Instant::now();
         for (position, check) in checks.iter().enumerate() {
-            match check.check_type {
-                CheckType::Battery(_) => {
-                    checks_by_key.insert(
-                        NextCheck::BAT,
-                        PackagedCheck {
-                            next_time: sync_start_time,
-                            check,
-                            position,
-                        },
-                    );
-                }
-                CheckType::Cpu(_) => {
-                    checks_by_key.insert(
-                        NextCheck::CPU,
-                        PackagedCheck {
-                            next_time: sync_start_time,
-                            check,
-                            position,
-                        },
-                    );
-                }
-                CheckType::Net(_) => {
-                    checks_by_key.insert(
-                        NextCheck::NET,
-                        PackagedCheck {
-                            next_time: sync_start_time,
-                            check,
-                            position,
-                        },
-                    );
-                }
-                CheckType::Mem(_) => {
-                    checks_by_key.insert(
-                        NextCheck::MEM,
-                        PackagedCheck {
-                            next_time: sync_start_time,
-                            check,
-                            position,
-                        },
-                    );
-                }
-                CheckType::Date(_) => {
-                    checks_by_key.insert(
-                        NextCheck::Date,
-                        PackagedCheck {
-                            next_time: sync_start_time,
-                            check,
-                            position,
-                        },
-                    );
-                }
-            }
+            let key = match check.check_type {
+                CheckType::Battery(_) => NextCheck::BAT,
+                CheckType::Cpu(_) => NextCheck::CPU,
+                CheckType::Net(_) => NextCheck::NET,
+                CheckType::Mem(_) => NextCheck::MEM,
+                CheckType::Date(_) => NextCheck::Date,
+                CheckType::Keyboard(_) => NextCheck::Keyboard,
+                CheckType::Privacy(_) => NextCheck::Privacy,
+                CheckType::Media(_) => NextCheck::Media,
+                CheckType::Ping(_) => NextCheck::Ping,
+                CheckType::External(fmt) => match fmt.source() {
+                    ExternalSource::Fifo(_) => NextCheck::ExternalFifo,
+                    ExternalSource::RootName => NextCheck::RootName,
+                },
+            };
+            checks_by_key.insert(
+                key,
+                PackagedCheck {
+                    interval: check.interval,
+                    last_due: sync_start_time,
+                    check,
+                    position,
+                    format_ind: 0,
+                },
+            );
+            deadlines.schedule(key, sync_start_time);
         }
 
         Checker {
             cpu_checker: LoadChecker::default(),
             net_checker: ThroughputChecker::default(),
+            ping_checker: PingChecker::default(),
             checks_by_key,
+            deadlines,
         }
     }
 }
@@ -452,8 +987,19 @@ mod checker_tests {
     use core::time::Duration;
 
     use tiny_std::time::Instant;
+    use tiny_std::UnixStr;
+    use xcb_rust_protocol::proto::xproto::ButtonIndexEnum;
 
-    use crate::status::checker::{Check, CheckType, Checker, CpuFormat};
+    use crate::config::Action;
+    use crate::status::checker::{
+        Check, CheckType, Checker, CpuFormat, CpuMode, DateFormat, ExternalFormat, ExternalSource,
+        KeyboardFormat, MediaFormat, MemFormat, MemMode, NetFormat, NetScale, NetUnit, NextCheck,
+        PingFormat, PrivacyFormat,
+    };
+    use crate::status::net::ThroughputPerSec;
+    use crate::status::sys::mem::Data;
+    use crate::status::sys::privacy::Capture;
+    use crate::status::time::{ClockFormatter, Format, FormatChunk};
 
     #[test]
     #[cfg(unix)]
@@ -463,17 +1009,247 @@ mod checker_tests {
         let interval = Duration::from_millis(10_000);
         let _ = checks.push(Check {
             interval: interval.as_millis() as u64,
-            check_type: CheckType::Cpu(CpuFormat {
-                icon: "hello",
-                decimals: 2,
-            }),
+            check_type: CheckType::Cpu(CpuFormat::new("hello", 2)),
         });
         let now = Instant::now();
         let mut checker = Checker::new(&mut checks);
-        let mut run_checks = checker.get_all_check_submits();
-        assert_eq!(1, run_checks.len());
-        let (_next, when) = run_checks.pop().unwrap();
+        let when = checker.next_deadline().unwrap();
         // If this test takes more than 10 seconds there are other issues
         assert!(when < now.add(2 * interval).unwrap());
+        assert_eq!(Some(NextCheck::CPU), checker.pop_due(Instant::now()));
+        assert_eq!(None, checker.pop_due(Instant::now()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reschedules_on_completion_and_interval_change() {
+        let mut checks: heapless::Vec<Check, 1> = heapless::Vec::new();
+        let interval = Duration::from_millis(10_000);
+        let _ = checks.push(Check {
+            interval: interval.as_millis() as u64,
+            check_type: CheckType::Cpu(CpuFormat::new("hello", 2)),
+        });
+        let mut checker = Checker::new(&mut checks);
+        assert_eq!(Some(NextCheck::CPU), checker.pop_due(Instant::now()));
+        // In flight, not scheduled until completed
+        assert_eq!(None, checker.next_deadline());
+        let result = checker.handle_completed(NextCheck::CPU, &[]).unwrap();
+        assert_eq!(0, result.position);
+        let next = checker.next_deadline().unwrap();
+        assert!(next > Instant::now());
+        assert_eq!(None, checker.pop_due(Instant::now()));
+
+        checker.set_interval(NextCheck::CPU, 20_000);
+        assert_eq!(Some(20_000), checker.interval(NextCheck::CPU));
+        assert!(checker.next_deadline().unwrap() > next);
+        // Unknown checks are ignored
+        checker.set_interval(NextCheck::BAT, 1);
+        assert_eq!(None, checker.interval(NextCheck::BAT));
+        assert_eq!(Some(NextCheck::CPU), checker.check_at(0));
+        assert_eq!(None, checker.check_at(1));
+    }
+
+    #[test]
+    fn formats_keyboard_layout() {
+        let fmt = KeyboardFormat::new("kb", 3);
+        assert_eq!("kb us", fmt.format_layout(b"us").unwrap().as_str());
+        assert_eq!("kb dvo", fmt.format_layout(b"dvorak").unwrap().as_str());
+        assert!(fmt.format_layout(b"").is_none());
+        assert_eq!("kb WWW", fmt.max_length_content().as_str());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn cycles_date_formats() {
+        const UTC: &[ClockFormatter] = &[ClockFormatter::new(
+            Format::new(&[FormatChunk::Value("utc")]),
+            time::UtcOffset::UTC,
+        )];
+        let mut checks: heapless::Vec<Check, 2> = heapless::Vec::new();
+        let _ = checks.push(Check {
+            interval: 10_000,
+            check_type: CheckType::Cpu(CpuFormat::new("cpu", 2)),
+        });
+        let _ = checks.push(Check {
+            interval: 10_000,
+            check_type: CheckType::Date(
+                DateFormat::new(
+                    "date",
+                    ClockFormatter::new(
+                        Format::new(&[FormatChunk::Value("local")]),
+                        time::UtcOffset::UTC,
+                    ),
+                )
+                .with_alternatives(UTC),
+            ),
+        });
+        let mut checker = Checker::new(&mut checks);
+        let date = |checker: &mut Checker| {
+            let result = checker.handle_completed(NextCheck::Date, &[]).unwrap();
+            assert_eq!(1, result.position);
+            result.content.unwrap()
+        };
+        while checker.pop_due(Instant::now()).is_some() {}
+        assert_eq!("date local", date(&mut checker).as_str());
+        assert_eq!(None, checker.pop_due(Instant::now()));
+        assert!(checker.cycle_format(1));
+        // Due right away to show the new format
+        assert_eq!(Some(NextCheck::Date), checker.pop_due(Instant::now()));
+        assert_eq!("date utc", date(&mut checker).as_str());
+        assert!(checker.cycle_format(1));
+        assert_eq!(Some(NextCheck::Date), checker.pop_due(Instant::now()));
+        assert_eq!("date local", date(&mut checker).as_str());
+        // Only dates have formats to cycle
+        assert!(!checker.cycle_format(0));
+        assert!(!checker.cycle_format(2));
+    }
+
+    #[test]
+    fn formats_cpu_modes() {
+        let fmt = CpuFormat::new("cpu", 1);
+        assert_eq!("cpu  42.5%", fmt.format_cpu(42.5).as_str());
+        let fmt = fmt.with_mode(CpuMode::PerCore);
+        assert_eq!("cpu ▁▅█", fmt.format_cores(&[0.0, 50.0, 100.0]).as_str());
+        // Cores that don't fit are left out
+        let many = fmt.format_cores(&[100.0; 64]);
+        assert!(many.starts_with("cpu █"));
+        assert!(many.len() <= 64);
+        let fmt = fmt.with_mode(CpuMode::LoadAvg);
+        assert_eq!(
+            "cpu  0.5  1.6 12.1",
+            fmt.format_load_avg([0.52, 1.58, 12.09]).as_str()
+        );
+        assert_eq!("cpu 100.0 100.0 100.0", fmt.max_length_content().as_str());
+    }
+
+    #[test]
+    fn formats_mem_modes() {
+        let data = Data {
+            total: 16_000_000,
+            available: 10_000_000,
+            swapped: 1_500,
+        };
+        let fmt = MemFormat::new("mem", 1);
+        assert_eq!("mem  10.0GB", fmt.format_mem(data).as_str());
+        let fmt = fmt.with_mode(MemMode::Used).with_swap("swp");
+        assert_eq!("mem   6.0GB swp   1.5MB", fmt.format_mem(data).as_str());
+        assert_eq!(
+            "mem 1000.0GB swp 1000.0GB",
+            fmt.max_length_content().as_str()
+        );
+    }
+
+    #[test]
+    fn formats_capture() {
+        let fmt = PrivacyFormat::new("mic", "cam");
+        let capture = |audio, camera| Capture { audio, camera };
+        assert_eq!("", fmt.format_capture(capture(false, false)).as_str());
+        assert_eq!("mic", fmt.format_capture(capture(true, false)).as_str());
+        assert_eq!("cam", fmt.format_capture(capture(false, true)).as_str());
+        assert_eq!("mic cam", fmt.max_length_content().as_str());
+    }
+
+    #[test]
+    fn formats_media() {
+        let fmt = MediaFormat::new("m", UnixStr::from_str_checked("/tmp/media\0"), 8);
+        // Nothing new
+        assert!(fmt.format_media(b"").is_none());
+        assert_eq!(
+            "m Band - S",
+            fmt.format_media(b"Old - Song\nBand - Song\n")
+                .unwrap()
+                .as_str()
+        );
+        // Only complete lines are shown
+        assert!(fmt.format_media(b"Band - So").is_none());
+        assert_eq!("", fmt.format_media(b"\n").unwrap().as_str());
+        assert_eq!("m WWWWWWWW", fmt.max_length_content().as_str());
+        let Some(Action::Spawn(cmd, args)) = fmt.click_action(ButtonIndexEnum::THREE.0) else {
+            panic!("Expected right click to spawn");
+        };
+        assert_eq!("playerctl\0", cmd.as_str().unwrap());
+        assert_eq!("next\0", args[0].as_str().unwrap());
+        assert!(fmt.click_action(ButtonIndexEnum::TWO.0).is_none());
+    }
+
+    #[test]
+    fn formats_external() {
+        let fifo = ExternalFormat::new(
+            ExternalSource::Fifo(UnixStr::from_str_checked("/tmp/status\0")),
+            12,
+        );
+        assert!(fifo.format_external(b"").is_none());
+        assert_eq!(
+            "cpu 3% | bat",
+            fifo.format_external(b"cpu 2%\ncpu 3% | bat 80%\n")
+                .unwrap()
+                .as_str()
+        );
+        assert!(fifo.format_external(b"cpu 4%").is_none());
+        let root = ExternalFormat::new(ExternalSource::RootName, 64);
+        assert_eq!(
+            "vol 40% | 12:00",
+            root.format_external(b" vol 40% | 12:00").unwrap().as_str()
+        );
+        assert_eq!("", root.format_external(b"").unwrap().as_str());
+        assert_eq!("WWWWWWWWWWWW", fifo.max_length_content().as_str());
+        let mut checks = [
+            Check {
+                interval: 1000,
+                check_type: CheckType::External(fifo),
+            },
+            Check {
+                interval: 1000,
+                check_type: CheckType::External(root),
+            },
+        ];
+        let mut checker = Checker::new(&mut checks);
+        let result = checker
+            .handle_completed(NextCheck::RootName, b"12:00")
+            .unwrap();
+        assert_eq!(1, result.position);
+        assert_eq!("12:00", result.content.unwrap().as_str());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn shows_lost_pings() {
+        let mut checks: heapless::Vec<Check, 1> = heapless::Vec::new();
+        let _ = checks.push(Check {
+            // Due again right after completing
+            interval: 0,
+            check_type: CheckType::Ping(PingFormat::new("p", [127, 0, 0, 1])),
+        });
+        let mut checker = Checker::new(&mut checks);
+        assert_eq!(Some(NextCheck::Ping), checker.pop_due(Instant::now()));
+        let due = checker.handle_completed(NextCheck::Ping, &[]).unwrap();
+        assert!(due.content.is_none());
+        let request = checker.ping_request();
+        let mut reply = request;
+        reply[0] = 0;
+        let replied = checker.handle_ping_reply(&reply).unwrap();
+        assert!(replied.content.unwrap().ends_with("ms"));
+        checker.ping_request();
+        assert_eq!(Some(NextCheck::Ping), checker.pop_due(Instant::now()));
+        let lost = checker.handle_completed(NextCheck::Ping, &[]).unwrap();
+        assert_eq!("p   --", lost.content.unwrap().as_str());
+        assert_eq!(
+            "p 9999ms",
+            PingFormat::new("p", [0; 4]).max_length_content().as_str()
+        );
+    }
+
+    #[test]
+    fn formats_net_units() {
+        let tp = ThroughputPerSec {
+            down: 2_500_000.0,
+            up: 900.0,
+        };
+        let fmt = NetFormat::new("up", "dn", 1);
+        assert_eq!("dn   2.5MB up   0.9KB", fmt.format_net(tp).as_str());
+        let fmt = fmt.with_unit(NetUnit::Bits);
+        assert_eq!("dn  20.0Mb up   7.2Kb", fmt.format_net(tp).as_str());
+        let fmt = fmt.with_scale(NetScale::Kilo);
+        assert_eq!("dn 20000.0Kb up   7.2Kb", fmt.format_net(tp).as_str());
     }
 }
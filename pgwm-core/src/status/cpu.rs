@@ -1,9 +1,12 @@
+use alloc::vec::Vec;
+
 use crate::error::Error;
 use crate::status::sys::cpu::Load;
 
 #[derive(Default)]
 pub struct LoadChecker {
     prev_load: Load,
+    prev_cores: Vec<Load>,
 }
 
 impl LoadChecker {
@@ -23,6 +26,22 @@ impl LoadChecker {
         self.prev_load = cur_ld;
         Ok(load_perc * 100f64)
     }
+
+    /// Load percentage of each core since the last check, a core that wasn't there last check
+    /// is compared to its time since boot
+    pub fn parse_core_loads(&mut self, buf: &[u8]) -> Result<Vec<f64>, Error> {
+        let cur_cores = crate::status::sys::cpu::parse_per_core(buf)?;
+        let default = Load::default();
+        let loads = cur_cores
+            .iter()
+            .enumerate()
+            .map(|(ind, cur)| {
+                calculate_load(self.prev_cores.get(ind).unwrap_or(&default), cur) * 100f64
+            })
+            .collect();
+        self.prev_cores = cur_cores;
+        Ok(loads)
+    }
 }
 
 fn calculate_load(prev: &Load, cur: &Load) -> f64 {
@@ -1,5 +1,6 @@
 pub mod checker;
 pub mod cpu;
 pub mod net;
+pub mod ping;
 pub mod sys;
 pub mod time;
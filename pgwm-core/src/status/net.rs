@@ -1,11 +1,13 @@
+use alloc::vec::Vec;
+
 use tiny_std::time::Instant;
 
 use crate::error::Error;
-use crate::status::sys::net::Data;
+use crate::status::sys::net::{Data, InterfaceData};
 
 #[derive(Clone)]
 pub struct ThroughputChecker {
-    prev_data: Data,
+    prev_data: Vec<InterfaceData>,
     prev_check: Instant,
 }
 
@@ -16,52 +18,108 @@ pub struct ThroughputPerSec {
 }
 
 impl ThroughputChecker {
-    pub fn get_throughput(&mut self) -> Result<ThroughputPerSec, Error> {
-        let net_stats = crate::status::sys::net::read_net_stats()?;
-        let now = Instant::now();
-        let time_passed = now
-            .duration_since(self.prev_check)
-            .map_or(1f64, |d| d.as_secs_f64());
-
-        let in_diff = (net_stats.bytes_in - self.prev_data.bytes_in) as f64 / time_passed;
-        let out_diff = (net_stats.bytes_out - self.prev_data.bytes_out) as f64 / time_passed;
-        self.prev_data = net_stats;
-
-        self.prev_check = now;
-        Ok(ThroughputPerSec {
-            down: in_diff,
-            up: out_diff,
-        })
+    /// Throughput since the last check summed over `interfaces`, or over every interface except loopback if empty
+    pub fn get_throughput(&mut self, interfaces: &[&str]) -> Result<ThroughputPerSec, Error> {
+        let buf = tiny_std::fs::read(crate::status::sys::net::NET_DEV_FILE)?;
+        self.parse_throughput(&buf, interfaces)
     }
 
-    pub fn parse_throughput(&mut self, raw_data: &[u8]) -> Result<ThroughputPerSec, Error> {
-        let net_stats = crate::status::sys::net::parse_raw(raw_data)?;
+    /// Throughput since the last check summed over `interfaces`, or over every interface except loopback if empty.
+    /// Counters are compared per interface, so an interface that's plugged in only counts from its second check
+    /// and one that's removed or reset doesn't count at all.
+    pub fn parse_throughput(
+        &mut self,
+        raw_data: &[u8],
+        interfaces: &[&str],
+    ) -> Result<ThroughputPerSec, Error> {
+        let net_stats = crate::status::sys::net::parse_dev(raw_data)?;
         let now = Instant::now();
         let time_passed = now
             .duration_since(self.prev_check)
             .map_or(1f64, |d| d.as_secs_f64());
 
-        let in_diff = (net_stats.bytes_in - self.prev_data.bytes_in) as f64 / time_passed;
-        let out_diff = (net_stats.bytes_out - self.prev_data.bytes_out) as f64 / time_passed;
+        let mut bytes_in = 0;
+        let mut bytes_out = 0;
+        for interface in net_stats.iter().filter(|interface| {
+            if interfaces.is_empty() {
+                interface.name.as_str() != "lo"
+            } else {
+                interfaces.contains(&interface.name.as_str())
+            }
+        }) {
+            if let Some(prev) = self
+                .prev_data
+                .iter()
+                .find(|prev| prev.name == interface.name)
+            {
+                let Data {
+                    bytes_in: prev_in,
+                    bytes_out: prev_out,
+                } = prev.data;
+                bytes_in += interface.data.bytes_in.saturating_sub(prev_in);
+                bytes_out += interface.data.bytes_out.saturating_sub(prev_out);
+            }
+        }
         self.prev_data = net_stats;
 
         self.prev_check = now;
         Ok(ThroughputPerSec {
-            down: in_diff,
-            up: out_diff,
+            down: bytes_in as f64 / time_passed,
+            up: bytes_out as f64 / time_passed,
         })
     }
 }
 
 impl Default for ThroughputChecker {
     fn default() -> Self {
-        let throughput = Data {
-            bytes_in: 0,
-            bytes_out: 0,
-        };
         Self {
-            prev_data: throughput,
+            prev_data: Vec::new(),
             prev_check: Instant::now(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::String;
+
+    use crate::status::net::ThroughputChecker;
+
+    fn dev(lines: &str) -> String {
+        format!("Inter-|   Receive  |  Transmit\n face |bytes    packets|bytes    packets\n{lines}")
+    }
+
+    #[test]
+    fn sums_selected_interfaces() {
+        let mut checker = ThroughputChecker::default();
+        let first = dev("lo: 100 0 0 0 0 0 0 0 100 0 0 0 0 0 0 0
+eth0: 1000 0 0 0 0 0 0 0 500 0 0 0 0 0 0 0
+wlan0: 2000 0 0 0 0 0 0 0 700 0 0 0 0 0 0 0
+");
+        // Nothing to compare against yet
+        let tp = checker.parse_throughput(first.as_bytes(), &[]).unwrap();
+        assert!(tp.down.abs() < f64::EPSILON && tp.up.abs() < f64::EPSILON);
+        // wlan0 was reset, usb0 was plugged in
+        let second = dev("lo: 10100 0 0 0 0 0 0 0 10100 0 0 0 0 0 0 0
+eth0: 3000 0 0 0 0 0 0 0 1500 0 0 0 0 0 0 0
+wlan0: 10 0 0 0 0 0 0 0 10 0 0 0 0 0 0 0
+usb0: 99999 0 0 0 0 0 0 0 99999 0 0 0 0 0 0 0
+");
+        let mut only_eth = checker.clone();
+        let tp = checker.parse_throughput(second.as_bytes(), &[]).unwrap();
+        let tp_eth = only_eth
+            .parse_throughput(second.as_bytes(), &["eth0"])
+            .unwrap();
+        // Loopback, the reset interface, and the new one are left out of the sum
+        assert!(tp.down > 0.0);
+        assert!((tp.down / tp.up - 2.0).abs() < 0.01);
+        assert!((tp_eth.down / tp_eth.up - 2.0).abs() < 0.01);
+        let third = dev("usb0: 100999 0 0 0 0 0 0 0 99999 0 0 0 0 0 0 0\n");
+        let tp = checker
+            .parse_throughput(third.as_bytes(), &["usb0"])
+            .unwrap();
+        assert!(tp.down > 0.0);
+        assert!(tp.up.abs() < f64::EPSILON);
+    }
+}
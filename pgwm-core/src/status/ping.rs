@@ -0,0 +1,71 @@
+use core::time::Duration;
+
+use tiny_std::time::Instant;
+
+/// An ICMP echo request header without payload
+pub const ECHO_REQUEST_LEN: usize = 8;
+const ECHO_REQUEST: u8 = 8;
+const ECHO_REPLY: u8 = 0;
+
+/// Keeps track of the echo request that's waiting for a reply.
+/// Meant for unprivileged ICMP sockets, `socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP)`, where the kernel
+/// fills in the identifier and checksum, and replies are read without their IP header
+#[derive(Default)]
+pub struct PingChecker {
+    seq: u16,
+    sent: Option<Instant>,
+}
+
+impl PingChecker {
+    /// A new echo request to send, any earlier request is no longer waited for
+    pub fn next_request(&mut self) -> [u8; ECHO_REQUEST_LEN] {
+        self.seq = self.seq.wrapping_add(1);
+        self.sent = Some(Instant::now());
+        let [seq_hi, seq_lo] = self.seq.to_be_bytes();
+        [ECHO_REQUEST, 0, 0, 0, 0, 0, seq_hi, seq_lo]
+    }
+
+    /// Whether a request has been sent without getting a reply
+    #[must_use]
+    pub fn awaiting_reply(&self) -> bool {
+        self.sent.is_some()
+    }
+
+    /// The round trip time if this is the reply to the latest request
+    pub fn parse_reply(&mut self, reply: &[u8]) -> Option<Duration> {
+        let [ty, _code, _, _, _, _, seq_hi, seq_lo, ..] = *reply else {
+            return None;
+        };
+        if ty != ECHO_REPLY || u16::from_be_bytes([seq_hi, seq_lo]) != self.seq {
+            return None;
+        }
+        let sent = self.sent.take()?;
+        Instant::now().duration_since(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::status::ping::PingChecker;
+
+    #[test]
+    fn matches_replies_to_the_latest_request() {
+        let mut checker = PingChecker::default();
+        assert!(!checker.awaiting_reply());
+        let first = checker.next_request();
+        assert_eq!([8, 0, 0, 0, 0, 0, 0, 1], first);
+        let second = checker.next_request();
+        assert_eq!(2, second[7]);
+        assert!(checker.awaiting_reply());
+        // A late reply to the first request, and a request that isn't a reply
+        assert!(checker.parse_reply(&[0, 0, 0, 0, 0, 0, 0, 1]).is_none());
+        assert!(checker.parse_reply(&[8, 0, 0, 0, 0, 0, 0, 2]).is_none());
+        assert!(checker.parse_reply(&[0, 0]).is_none());
+        assert!(checker
+            .parse_reply(&[0, 0, 0xab, 0xcd, 0, 7, 0, 2, 1, 2, 3])
+            .is_some());
+        assert!(!checker.awaiting_reply());
+        // Only counted once
+        assert!(checker.parse_reply(&[0, 0, 0, 0, 0, 0, 0, 2]).is_none());
+    }
+}
@@ -1,8 +1,11 @@
+use alloc::vec::Vec;
+
 use crate::error::Error;
 use crate::status::sys::find_byte;
 use tiny_std::UnixStr;
 
 pub const CPU_LOAD_FILE: &UnixStr = UnixStr::from_str_checked("/proc/stat\0");
+pub const LOAD_AVG_FILE: &UnixStr = UnixStr::from_str_checked("/proc/loadavg\0");
 
 #[derive(Debug, Default)]
 pub struct Load {
@@ -48,9 +51,69 @@ pub fn parse_raw(content: &[u8]) -> Result<Load, Error> {
     })
 }
 
+/// Load of each core, in the order they're listed, the aggregate is left out.
+/// Stops at the first line that isn't a cpu, so a read that's cut off after the cpu lines is fine
+pub fn parse_per_core(content: &[u8]) -> Result<Vec<Load>, Error> {
+    let mut cores = Vec::new();
+    for line in content.split(|b| *b == b'\n') {
+        let line = line.trim_ascii_start();
+        let Some(rest) = line.strip_prefix(b"cpu") else {
+            break;
+        };
+        if rest.first().is_some_and(u8::is_ascii_digit) {
+            cores.push(parse_raw(line)?);
+        }
+    }
+    if cores.is_empty() {
+        return Err(Error::ProcStatParseError);
+    }
+    Ok(cores)
+}
+
+pub fn read_core_count() -> Result<usize, Error> {
+    let buf = tiny_std::fs::read(CPU_LOAD_FILE)?;
+    parse_per_core(&buf).map(|cores| cores.len())
+}
+
+/// The 1, 5, and 15 minute load averages
+pub fn parse_load_avg(content: &[u8]) -> Result<[f64; 3], Error> {
+    let content = core::str::from_utf8(content).map_err(|_| Error::LoadAvgParseError)?;
+    let mut fields = content.split_ascii_whitespace();
+    let mut avgs = [0f64; 3];
+    for avg in &mut avgs {
+        *avg = fields.next().ok_or(Error::LoadAvgParseError)?.parse()?;
+    }
+    Ok(avgs)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_raw;
+    use super::{parse_load_avg, parse_per_core, parse_raw};
+
+    #[test]
+    fn parses_per_core() {
+        let input = b"cpu  81196 0 15968 1477813 10828 1617 874 0 0 0
+cpu0 13050 0 2577 247458 1336 210 66 0 0 0
+cpu1 14339 0 2606 244422 2949 216 176 0 0 0
+intr 3669331 9 0 0 0 0 0 0 0 1 0 0
+cpu2 1 1 1 1 1 1 1 1 1 1
+";
+        let cores = parse_per_core(input).unwrap();
+        assert_eq!(2, cores.len());
+        assert_eq!(15915f64, cores[0].busy);
+        assert_eq!(247458f64, cores[0].idle);
+        assert_eq!(244422f64, cores[1].idle);
+        assert!(parse_per_core(b"intr 1 2 3\n").is_err());
+    }
+
+    #[test]
+    fn parses_load_avg() {
+        let avgs = parse_load_avg(b"0.52 1.58 12.09 1/467 12345\n").unwrap();
+        assert!((avgs[0] - 0.52).abs() < f64::EPSILON);
+        assert!((avgs[1] - 1.58).abs() < f64::EPSILON);
+        assert!((avgs[2] - 12.09).abs() < f64::EPSILON);
+        assert!(parse_load_avg(b"0.52 1.58").is_err());
+    }
 
     #[test]
     fn praw() {
@@ -2,6 +2,7 @@ pub mod bat;
 pub mod cpu;
 pub mod mem;
 pub mod net;
+pub mod privacy;
 
 #[inline]
 fn find_in_haystack(haystack: &[u8], needle: &[u8]) -> Option<usize> {
@@ -1,8 +1,14 @@
+use alloc::vec::Vec;
+
 use crate::error::Error;
 use crate::status::sys::{find_byte, find_in_haystack};
 use tiny_std::UnixStr;
 
 pub const NET_STAT_FILE: &UnixStr = UnixStr::from_str_checked("/proc/net/netstat\0");
+/// Counters per network interface, lists the interfaces present when it's read
+pub const NET_DEV_FILE: &UnixStr = UnixStr::from_str_checked("/proc/net/dev\0");
+/// Linux interface names are at most 15 bytes
+pub const _INTERFACE_NAME_LIMIT: usize = 16;
 
 #[allow(unsafe_code)]
 pub fn read_net_stats() -> Result<Data, Error> {
@@ -41,15 +47,70 @@ pub fn parse_raw(raw_data: &[u8]) -> Result<Data, Error> {
     Err(Error::NetStatParseError)
 }
 
+/// Parses the counters of each interface in `/proc/net/dev`, after two header lines there's a line per interface
+/// formatted as `name: <8 receive counters> <8 transmit counters>` with the byte count first in each group.
+/// Lines that can't be parsed are skipped, the last one may be cut off if the read didn't fit it.
+pub fn parse_dev(raw_data: &[u8]) -> Result<Vec<InterfaceData>, Error> {
+    let content = core::str::from_utf8(raw_data).map_err(|_| Error::NetStatParseError)?;
+    let mut lines = content.lines();
+    if !lines
+        .next()
+        .is_some_and(|header| header.starts_with("Inter-"))
+    {
+        return Err(Error::NetStatParseError);
+    }
+    lines.next();
+    Ok(lines
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let name = heapless::String::try_from(name.trim()).ok()?;
+            let mut counters = counters.split_ascii_whitespace();
+            let bytes_in = counters.next()?.parse().ok()?;
+            let bytes_out = counters.nth(7)?.parse().ok()?;
+            Some(InterfaceData {
+                name,
+                data: Data {
+                    bytes_in,
+                    bytes_out,
+                },
+            })
+        })
+        .collect())
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Data {
     pub bytes_in: u64,
     pub bytes_out: u64,
 }
 
+#[derive(Clone, Debug)]
+pub struct InterfaceData {
+    pub name: heapless::String<_INTERFACE_NAME_LIMIT>,
+    pub data: Data,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_raw;
+    use super::{parse_dev, parse_raw};
+
+    #[test]
+    fn test_read_dev() {
+        let input = b"Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 2776770   13462    0    0    0     0          0         0  2776770   13462    0    0    0     0       0          0
+wlp2s0: 145351181  161224    0    0    0     0          0      1855 7537882   52291    0    0    0     0       0          0
+docker0:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0
+enp0s31f6: 12";
+        let interfaces = parse_dev(input).unwrap();
+        assert_eq!(3, interfaces.len());
+        assert_eq!("lo", interfaces[0].name.as_str());
+        assert_eq!("wlp2s0", interfaces[1].name.as_str());
+        assert_eq!(145_351_181, interfaces[1].data.bytes_in);
+        assert_eq!(7_537_882, interfaces[1].data.bytes_out);
+        assert_eq!(0, interfaces[2].data.bytes_out);
+        assert!(parse_dev(b"IpExt: 0 0").is_err());
+    }
 
     #[test]
     fn test_read_raw() {
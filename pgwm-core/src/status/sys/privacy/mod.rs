@@ -0,0 +1,83 @@
+use alloc::format;
+
+use tiny_std::UnixStr;
+
+/// Cards and capture devices per card that are looked for under `/proc/asound`
+const MAX_SOUND_CARDS: usize = 8;
+const MAX_CAPTURE_DEVICES: usize = 8;
+
+/// Open handles on `uvcvideo` devices, the `/dev/video*` of usb and most built in cameras
+pub const CAMERA_REFCOUNT_FILE: &UnixStr =
+    UnixStr::from_str_checked("/sys/module/uvcvideo/refcnt\0");
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Capture {
+    pub audio: bool,
+    pub camera: bool,
+}
+
+impl Capture {
+    #[inline]
+    #[must_use]
+    pub fn any(self) -> bool {
+        self.audio || self.camera
+    }
+}
+
+/// Whether a microphone or a camera is in use right now.
+/// Sound servers like pipewire and pulseaudio keep capture devices open while something's recording through them,
+/// so this picks up capture through those as well as capture straight from alsa
+#[must_use]
+pub fn read_capture() -> Capture {
+    Capture {
+        audio: audio_capture_active(),
+        camera: tiny_std::fs::read(CAMERA_REFCOUNT_FILE)
+            .is_ok_and(|refcount| parse_refcount(&refcount)),
+    }
+}
+
+fn audio_capture_active() -> bool {
+    (0..MAX_SOUND_CARDS).any(|card| {
+        (0..MAX_CAPTURE_DEVICES).any(|device| {
+            let path = format!("/proc/asound/card{card}/pcm{device}c/sub0/status\0");
+            UnixStr::try_from_str(&path)
+                .ok()
+                .and_then(|path| tiny_std::fs::read(path).ok())
+                .is_some_and(|status| parse_pcm_status(&status))
+        })
+    })
+}
+
+/// A substream that's capturing reports `state: RUNNING`, one that isn't open just reports `closed`
+#[must_use]
+pub fn parse_pcm_status(status: &[u8]) -> bool {
+    status
+        .split(|b| *b == b'\n')
+        .any(|line| line.trim_ascii() == b"state: RUNNING")
+}
+
+#[must_use]
+pub fn parse_refcount(refcount: &[u8]) -> bool {
+    atoi::atoi::<u64>(refcount.trim_ascii()).is_some_and(|count| count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_pcm_status, parse_refcount};
+
+    #[test]
+    fn parses_pcm_status() {
+        assert!(!parse_pcm_status(b"closed\n"));
+        assert!(parse_pcm_status(
+            b"state: RUNNING\nowner_pid   : 1234\ntrigger_time: 1.0\n"
+        ));
+        assert!(!parse_pcm_status(b"state: PREPARED\nowner_pid   : 1234\n"));
+    }
+
+    #[test]
+    fn parses_refcount() {
+        assert!(!parse_refcount(b"0\n"));
+        assert!(parse_refcount(b"2\n"));
+        assert!(!parse_refcount(b""));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::util::requests::split_requests;
+
+/// Response type of an X11 error
+const X11_ERROR: u8 = 0;
+/// Response type of an X11 reply
+const X11_REPLY: u8 = 1;
+/// Event code of `GenericEvent`, which like replies can be longer than 32 bytes
+const X11_GENERIC_EVENT: u8 = 35;
+/// Everything the server sends is at least this long
+const X11_RESPONSE_LEN: usize = 32;
+/// Length of the header that every [`CaptureToken`] is written with
+pub const CAPTURE_HEADER_LEN: usize = 20;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(u8)]
+pub enum CaptureKind {
+    Request = 0,
+    Reply = 1,
+    Event = 2,
+    Error = 3,
+    /// The connection setup sent by the WM
+    Setup = 4,
+    /// The server's answer to the connection setup
+    SetupReply = 5,
+}
+
+/// One message sent to, or received from, the X server.
+/// Written to a capture as a [`CAPTURE_HEADER_LEN`] byte header followed by the payload,
+/// see [`CaptureToken::encode_header`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CaptureToken<'a> {
+    pub kind: CaptureKind,
+    /// Major opcode of a request, code of an event (without the sent bit), code of an error,
+    /// 0 for a reply or the connection setup
+    pub code: u8,
+    /// Minor opcode of a request, detail byte of an event or reply, major opcode of the request that failed
+    pub detail: u8,
+    pub sequence: u16,
+    /// Nanos since the capture started
+    pub nanos: u64,
+    /// The whole message, empty if the capture was made without payloads
+    pub payload: &'a [u8],
+}
+
+impl<'a> CaptureToken<'a> {
+    /// Kind, code, detail, a zero byte, the sequence as a little endian u16, two zero bytes,
+    /// the payload length as a little endian u32, then the nanos as a little endian u64
+    #[must_use]
+    pub fn encode_header(&self) -> [u8; CAPTURE_HEADER_LEN] {
+        let mut out = [0u8; CAPTURE_HEADER_LEN];
+        out[0] = self.kind as u8;
+        out[1] = self.code;
+        out[2] = self.detail;
+        out[4..6].copy_from_slice(&self.sequence.to_le_bytes());
+        out[8..12].copy_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        out[12..20].copy_from_slice(&self.nanos.to_le_bytes());
+        out
+    }
+
+    /// The first token of `bytes` and how many bytes it took up,
+    /// `None` if it's not a complete token
+    #[must_use]
+    pub fn decode(bytes: &'a [u8]) -> Option<(Self, usize)> {
+        let header = bytes.get(..CAPTURE_HEADER_LEN)?;
+        let kind = match header[0] {
+            0 => CaptureKind::Request,
+            1 => CaptureKind::Reply,
+            2 => CaptureKind::Event,
+            3 => CaptureKind::Error,
+            4 => CaptureKind::Setup,
+            5 => CaptureKind::SetupReply,
+            _ => return None,
+        };
+        let len = u32::from_le_bytes([header[8], header[9], header[10], header[11]]) as usize;
+        let mut nanos = [0u8; 8];
+        nanos.copy_from_slice(&header[12..20]);
+        let end = CAPTURE_HEADER_LEN + len;
+        let payload = bytes.get(CAPTURE_HEADER_LEN..end)?;
+        Some((
+            Self {
+                kind,
+                code: header[1],
+                detail: header[2],
+                sequence: u16::from_le_bytes([header[4], header[5]]),
+                nanos: u64::from_le_bytes(nanos),
+                payload,
+            },
+            end,
+        ))
+    }
+}
+
+/// Split written bytes into requests, the first gets `next_sequence` which is then advanced past the last.
+/// Stops at anything that doesn't look like a complete request.
+pub fn capture_requests<'a, F: FnMut(CaptureToken<'a>)>(
+    bytes: &'a [u8],
+    next_sequence: &mut u16,
+    nanos: u64,
+    mut on_token: F,
+) {
+    split_requests(bytes, |request| {
+        on_token(CaptureToken {
+            kind: CaptureKind::Request,
+            code: request[0],
+            detail: request[1],
+            sequence: *next_sequence,
+            nanos,
+            payload: request,
+        });
+        *next_sequence = next_sequence.wrapping_add(1);
+    });
+}
+
+/// Split read bytes into replies, events, and errors.
+/// Stops at anything that doesn't look like a complete response.
+pub fn capture_responses<'a, F: FnMut(CaptureToken<'a>)>(
+    mut bytes: &'a [u8],
+    nanos: u64,
+    mut on_token: F,
+) {
+    while bytes.len() >= X11_RESPONSE_LEN {
+        let response_type = bytes[0] & 0x7f;
+        let (kind, code, detail) = match response_type {
+            X11_ERROR => (CaptureKind::Error, bytes[1], bytes[10]),
+            X11_REPLY => (CaptureKind::Reply, 0, bytes[1]),
+            _ => (CaptureKind::Event, response_type, bytes[1]),
+        };
+        let len = if response_type == X11_REPLY || response_type == X11_GENERIC_EVENT {
+            X11_RESPONSE_LEN
+                + u32::from_ne_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize * 4
+        } else {
+            X11_RESPONSE_LEN
+        };
+        if len > bytes.len() {
+            return;
+        }
+        on_token(CaptureToken {
+            kind,
+            code,
+            detail,
+            sequence: u16::from_ne_bytes([bytes[2], bytes[3]]),
+            nanos,
+            payload: &bytes[..len],
+        });
+        bytes = &bytes[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::util::capture::{
+        capture_requests, capture_responses, CaptureKind, CaptureToken, CAPTURE_HEADER_LEN,
+    };
+
+    #[test]
+    fn round_trips_tokens() {
+        let error = CaptureToken {
+            kind: CaptureKind::Error,
+            code: 3,
+            detail: 12,
+            sequence: 0xbeef,
+            nanos: 1_234_567_890_123,
+            payload: &[],
+        };
+        let request = CaptureToken {
+            kind: CaptureKind::Request,
+            code: 8,
+            detail: 0,
+            sequence: 0xbef0,
+            nanos: 1_234_567_890_200,
+            payload: &[8, 0, 2, 0, 1, 2, 3, 4],
+        };
+        let mut bytes = Vec::new();
+        for token in [error, request] {
+            bytes.extend_from_slice(&token.encode_header());
+            bytes.extend_from_slice(token.payload);
+        }
+        let (first, used) = CaptureToken::decode(&bytes).unwrap();
+        assert_eq!(error, first);
+        assert_eq!(CAPTURE_HEADER_LEN, used);
+        let (second, rest) = CaptureToken::decode(&bytes[used..]).unwrap();
+        assert_eq!(request, second);
+        assert_eq!(bytes.len(), used + rest);
+        // Cut off payload
+        assert!(CaptureToken::decode(&bytes[used..bytes.len() - 1]).is_none());
+        let mut bad = bytes;
+        bad[0] = 6;
+        assert!(CaptureToken::decode(&bad).is_none());
+    }
+
+    #[test]
+    fn splits_requests() {
+        let mut bytes = Vec::new();
+        // MapWindow, 2 units
+        bytes.extend_from_slice(&[8, 0]);
+        bytes.extend_from_slice(&2u16.to_ne_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        // An extension request with minor opcode 4, 3 units
+        bytes.extend_from_slice(&[140, 4]);
+        bytes.extend_from_slice(&3u16.to_ne_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        // Incomplete
+        bytes.extend_from_slice(&[8, 0]);
+        bytes.extend_from_slice(&2u16.to_ne_bytes());
+        let mut next_sequence = u16::MAX;
+        let mut tokens = Vec::new();
+        capture_requests(&bytes, &mut next_sequence, 5, |token| tokens.push(token));
+        assert_eq!(
+            vec![
+                CaptureToken {
+                    kind: CaptureKind::Request,
+                    code: 8,
+                    detail: 0,
+                    sequence: u16::MAX,
+                    nanos: 5,
+                    payload: &bytes[..8],
+                },
+                CaptureToken {
+                    kind: CaptureKind::Request,
+                    code: 140,
+                    detail: 4,
+                    sequence: 0,
+                    nanos: 5,
+                    payload: &bytes[8..20],
+                },
+            ],
+            tokens
+        );
+        assert_eq!(1, next_sequence);
+    }
+
+    #[test]
+    fn splits_responses() {
+        let mut bytes = Vec::new();
+        // A sent MapRequest event
+        let mut event = [0u8; 32];
+        event[0] = 20 | 0x80;
+        event[2..4].copy_from_slice(&7u16.to_ne_bytes());
+        bytes.extend_from_slice(&event);
+        // A reply with 2 extra units
+        let mut reply = [0u8; 40];
+        reply[0] = 1;
+        reply[1] = 9;
+        reply[2..4].copy_from_slice(&8u16.to_ne_bytes());
+        reply[4..8].copy_from_slice(&2u32.to_ne_bytes());
+        bytes.extend_from_slice(&reply);
+        // A BadWindow error from a ConfigureWindow
+        let mut error = [0u8; 32];
+        error[1] = 3;
+        error[2..4].copy_from_slice(&9u16.to_ne_bytes());
+        error[10] = 12;
+        bytes.extend_from_slice(&error);
+        let mut tokens = Vec::new();
+        capture_responses(&bytes, 0, |token| tokens.push(token));
+        let summary = tokens
+            .iter()
+            .map(|t| (t.kind, t.code, t.detail, t.sequence, t.payload.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (CaptureKind::Event, 20, 0, 7, 32),
+                (CaptureKind::Reply, 0, 9, 8, 40),
+                (CaptureKind::Error, 3, 12, 9, 32),
+            ],
+            summary
+        );
+    }
+}
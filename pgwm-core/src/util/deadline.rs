@@ -0,0 +1,105 @@
+use alloc::vec::Vec;
+
+use tiny_std::time::Instant;
+
+/// Keyed deadlines, kept sorted so that the earliest is always first.
+/// Each key is scheduled at most once, scheduling it again moves its deadline.
+#[derive(Debug, Clone)]
+pub struct DeadlineWheel<K> {
+    deadlines: Vec<(K, Instant)>,
+}
+
+impl<K: Copy + Eq> DeadlineWheel<K> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            deadlines: Vec::new(),
+        }
+    }
+
+    /// Schedule `key` to be due at `at`, replacing any previous deadline for that key
+    pub fn schedule(&mut self, key: K, at: Instant) {
+        self.cancel(key);
+        let ind = self
+            .deadlines
+            .partition_point(|(_, existing)| *existing <= at);
+        self.deadlines.insert(ind, (key, at));
+    }
+
+    /// Remove the deadline for `key`, returning it if it was scheduled
+    pub fn cancel(&mut self, key: K) -> Option<Instant> {
+        let ind = self.deadlines.iter().position(|(k, _)| *k == key)?;
+        Some(self.deadlines.remove(ind).1)
+    }
+
+    #[must_use]
+    pub fn deadline_of(&self, key: K) -> Option<Instant> {
+        self.deadlines
+            .iter()
+            .find_map(|(k, at)| (*k == key).then_some(*at))
+    }
+
+    /// The earliest deadline, when the next key will be due
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.first().map(|(_, at)| *at)
+    }
+
+    /// Take the earliest key which is due at `now`, it's unscheduled until scheduled again
+    pub fn pop_due(&mut self, now: Instant) -> Option<(K, Instant)> {
+        if self.next_deadline()? <= now {
+            Some(self.deadlines.remove(0))
+        } else {
+            None
+        }
+    }
+}
+
+impl<K: Copy + Eq> Default for DeadlineWheel<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ops::Add;
+    use core::time::Duration;
+
+    use tiny_std::time::Instant;
+
+    use crate::util::deadline::DeadlineWheel;
+
+    fn after(start: Instant, millis: u64) -> Instant {
+        start.add(Duration::from_millis(millis)).unwrap()
+    }
+
+    #[test]
+    fn pops_in_deadline_order() {
+        let start = Instant::now();
+        let mut wheel = DeadlineWheel::new();
+        wheel.schedule(1, after(start, 30));
+        wheel.schedule(2, after(start, 10));
+        wheel.schedule(3, after(start, 20));
+        assert_eq!(Some(after(start, 10)), wheel.next_deadline());
+        assert_eq!(None, wheel.pop_due(after(start, 5)).map(|(k, _)| k));
+        assert_eq!(Some(2), wheel.pop_due(after(start, 25)).map(|(k, _)| k));
+        assert_eq!(Some(3), wheel.pop_due(after(start, 25)).map(|(k, _)| k));
+        assert_eq!(None, wheel.pop_due(after(start, 25)).map(|(k, _)| k));
+        assert_eq!(Some(after(start, 30)), wheel.next_deadline());
+    }
+
+    #[test]
+    fn rescheduling_moves_deadline() {
+        let start = Instant::now();
+        let mut wheel = DeadlineWheel::new();
+        wheel.schedule(1, after(start, 10));
+        wheel.schedule(2, after(start, 20));
+        wheel.schedule(1, after(start, 30));
+        assert_eq!(Some(after(start, 20)), wheel.next_deadline());
+        assert_eq!(Some(after(start, 30)), wheel.deadline_of(1));
+        assert_eq!(Some(after(start, 20)), wheel.cancel(2));
+        assert_eq!(None, wheel.cancel(2));
+        assert_eq!(Some(after(start, 30)), wheel.next_deadline());
+    }
+}
@@ -0,0 +1,103 @@
+use alloc::format;
+use alloc::string::String;
+
+/// Expands a leading `~` to `$HOME`, and `$NAME` or `${NAME}` anywhere to the variable's value.
+/// Like in a shell unset variables expand to nothing, a `$` not followed by a name starting with a letter
+/// or underscore is kept as is.
+/// Returns `None` if there's nothing to expand.
+#[must_use]
+pub fn expand_env<'a, F: Fn(&str) -> Option<&'a str>>(input: &str, lookup: F) -> Option<String> {
+    let tilde = input == "~" || input.starts_with("~/");
+    if !tilde && !input.contains('$') {
+        return None;
+    }
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    if tilde {
+        out.push_str(lookup("HOME").unwrap_or_default());
+        rest = &rest[1..];
+    }
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len())
+            } else {
+                0
+            };
+            (&after[..end], end)
+        };
+        if name.is_empty() {
+            out.push('$');
+        } else {
+            out.push_str(lookup(name).unwrap_or_default());
+        }
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Where to look for a command in `path`, a value of `$PATH`, in order and null terminated.
+/// A command containing a `/` is a path and shouldn't be looked up.
+pub fn path_candidates<'a>(cmd: &'a str, path: &'a str) -> impl Iterator<Item = String> + 'a {
+    path.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(move |dir| format!("{}/{cmd}\0", dir.trim_end_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::util::env::{expand_env, path_candidates};
+
+    fn lookup(name: &str) -> Option<&'static str> {
+        match name {
+            "HOME" => Some("/home/user"),
+            "TERMINAL" => Some("alacritty"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_home_and_variables() {
+        assert_eq!(None, expand_env("/usr/bin/xterm", lookup));
+        assert_eq!(None, expand_env("not~home", lookup));
+        assert_eq!(Some("/home/user".into()), expand_env("~", lookup));
+        assert_eq!(
+            Some("/home/user/.local/bin/run".into()),
+            expand_env("~/.local/bin/run", lookup)
+        );
+        assert_eq!(
+            Some("/home/user/bin/alacritty".into()),
+            expand_env("$HOME/bin/$TERMINAL", lookup)
+        );
+        assert_eq!(
+            Some("alacritty-msg".into()),
+            expand_env("${TERMINAL}-msg", lookup)
+        );
+        // Unset expands to nothing
+        assert_eq!(Some("a//b".into()), expand_env("a/$UNSET/b", lookup));
+        // Not a name
+        assert_eq!(Some("cost $5 $".into()), expand_env("cost $5 $", lookup));
+        assert_eq!(Some("${HOME".into()), expand_env("${HOME", lookup));
+    }
+
+    #[test]
+    fn lists_path_candidates() {
+        assert_eq!(
+            vec!["/home/user/bin/alacritty\0", "/usr/bin/alacritty\0"],
+            path_candidates("alacritty", "/home/user/bin/::/usr/bin").collect::<Vec<_>>()
+        );
+        assert_eq!(0, path_candidates("alacritty", "").count());
+    }
+}
@@ -1,2 +1,7 @@
+pub mod capture;
+pub mod deadline;
+pub mod env;
 pub mod macros;
+pub mod proc;
+pub mod requests;
 pub mod vec_ops;
@@ -0,0 +1,23 @@
+/// The most recently started child of a process, parsed from `/proc/<pid>/task/<pid>/children`
+/// which lists the pids of a thread's children separated by spaces, oldest first
+#[must_use]
+pub fn youngest_child(children: &[u8]) -> Option<u32> {
+    core::str::from_utf8(children)
+        .ok()?
+        .split_ascii_whitespace()
+        .filter_map(|pid| pid.parse().ok())
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::proc::youngest_child;
+
+    #[test]
+    fn finds_youngest_child() {
+        assert_eq!(Some(4120), youngest_child(b"3991 4002 4120 "));
+        assert_eq!(Some(17), youngest_child(b"17"));
+        assert_eq!(None, youngest_child(b""));
+        assert_eq!(None, youngest_child(b"\xff\xfe"));
+    }
+}
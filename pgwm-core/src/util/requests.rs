@@ -0,0 +1,337 @@
+use alloc::format;
+use alloc::string::String;
+
+/// How many sent requests are remembered, errors usually arrive shortly after their request
+const RECENT_REQUEST_LIMIT: usize = 256;
+
+/// Core protocol request names by major opcode, `NoOperation` (127) is the only one past the end
+const REQUEST_NAMES: [&str; 120] = [
+    "",
+    "CreateWindow",
+    "ChangeWindowAttributes",
+    "GetWindowAttributes",
+    "DestroyWindow",
+    "DestroySubwindows",
+    "ChangeSaveSet",
+    "ReparentWindow",
+    "MapWindow",
+    "MapSubwindows",
+    "UnmapWindow",
+    "UnmapSubwindows",
+    "ConfigureWindow",
+    "CirculateWindow",
+    "GetGeometry",
+    "QueryTree",
+    "InternAtom",
+    "GetAtomName",
+    "ChangeProperty",
+    "DeleteProperty",
+    "GetProperty",
+    "ListProperties",
+    "SetSelectionOwner",
+    "GetSelectionOwner",
+    "ConvertSelection",
+    "SendEvent",
+    "GrabPointer",
+    "UngrabPointer",
+    "GrabButton",
+    "UngrabButton",
+    "ChangeActivePointerGrab",
+    "GrabKeyboard",
+    "UngrabKeyboard",
+    "GrabKey",
+    "UngrabKey",
+    "AllowEvents",
+    "GrabServer",
+    "UngrabServer",
+    "QueryPointer",
+    "GetMotionEvents",
+    "TranslateCoordinates",
+    "WarpPointer",
+    "SetInputFocus",
+    "GetInputFocus",
+    "QueryKeymap",
+    "OpenFont",
+    "CloseFont",
+    "QueryFont",
+    "QueryTextExtents",
+    "ListFonts",
+    "ListFontsWithInfo",
+    "SetFontPath",
+    "GetFontPath",
+    "CreatePixmap",
+    "FreePixmap",
+    "CreateGC",
+    "ChangeGC",
+    "CopyGC",
+    "SetDashes",
+    "SetClipRectangles",
+    "FreeGC",
+    "ClearArea",
+    "CopyArea",
+    "CopyPlane",
+    "PolyPoint",
+    "PolyLine",
+    "PolySegment",
+    "PolyRectangle",
+    "PolyArc",
+    "FillPoly",
+    "PolyFillRectangle",
+    "PolyFillArc",
+    "PutImage",
+    "GetImage",
+    "PolyText8",
+    "PolyText16",
+    "ImageText8",
+    "ImageText16",
+    "CreateColormap",
+    "FreeColormap",
+    "CopyColormapAndFree",
+    "InstallColormap",
+    "UninstallColormap",
+    "ListInstalledColormaps",
+    "AllocColor",
+    "AllocNamedColor",
+    "AllocColorCells",
+    "AllocColorPlanes",
+    "FreeColors",
+    "StoreColors",
+    "StoreNamedColor",
+    "QueryColors",
+    "LookupColor",
+    "CreateCursor",
+    "CreateGlyphCursor",
+    "FreeCursor",
+    "RecolorCursor",
+    "QueryBestSize",
+    "QueryExtension",
+    "ListExtensions",
+    "ChangeKeyboardMapping",
+    "GetKeyboardMapping",
+    "ChangeKeyboardControl",
+    "GetKeyboardControl",
+    "Bell",
+    "ChangePointerControl",
+    "GetPointerControl",
+    "SetScreenSaver",
+    "GetScreenSaver",
+    "ChangeHosts",
+    "ListHosts",
+    "SetAccessControl",
+    "SetCloseDownMode",
+    "KillClient",
+    "RotateProperties",
+    "ForceScreenSaver",
+    "SetPointerMapping",
+    "GetPointerMapping",
+    "SetModifierMapping",
+    "GetModifierMapping",
+];
+
+/// Core protocol error names by error code
+const ERROR_NAMES: [&str; 18] = [
+    "",
+    "BadRequest",
+    "BadValue",
+    "BadWindow",
+    "BadPixmap",
+    "BadAtom",
+    "BadCursor",
+    "BadFont",
+    "BadMatch",
+    "BadDrawable",
+    "BadAccess",
+    "BadAlloc",
+    "BadColormap",
+    "BadGContext",
+    "BadIDChoice",
+    "BadName",
+    "BadLength",
+    "BadImplementation",
+];
+
+/// Split bytes written to the X server into requests, stops at anything that doesn't look like a complete one
+pub(crate) fn split_requests<'a, F: FnMut(&'a [u8])>(mut bytes: &'a [u8], mut on_request: F) {
+    while bytes.len() >= 4 {
+        let mut len = usize::from(u16::from_ne_bytes([bytes[2], bytes[3]])) * 4;
+        if len == 0 && bytes.len() >= 8 {
+            // BIG-REQUESTS, the real length follows
+            len = u32::from_ne_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize * 4;
+        }
+        if len == 0 || len > bytes.len() {
+            return;
+        }
+        on_request(&bytes[..len]);
+        bytes = &bytes[len..];
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SentRequest {
+    pub sequence: u16,
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl SentRequest {
+    /// The request's name if it's a core request, otherwise its opcodes
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self.major {
+            127 => String::from("NoOperation"),
+            major if usize::from(major) < REQUEST_NAMES.len() && major != 0 => {
+                String::from(REQUEST_NAMES[usize::from(major)])
+            }
+            major => format!("Request {major}.{}", self.minor),
+        }
+    }
+}
+
+/// The most recently sent requests, so that an error can be traced back to the request that caused it
+pub struct RecentRequests {
+    ring: [Option<SentRequest>; RECENT_REQUEST_LIMIT],
+    next_slot: usize,
+    /// Requests are numbered by the server in the order they're received, starting at 1
+    next_sequence: u16,
+    /// The connection setup isn't a request and is skipped
+    setup_written: bool,
+}
+
+impl RecentRequests {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            ring: [None; RECENT_REQUEST_LIMIT],
+            next_slot: 0,
+            next_sequence: 1,
+            setup_written: false,
+        }
+    }
+
+    /// Remember requests that were just written to the X server
+    pub fn record_written(&mut self, bytes: &[u8]) {
+        if !self.setup_written {
+            self.setup_written = true;
+            return;
+        }
+        split_requests(bytes, |request| {
+            self.ring[self.next_slot] = Some(SentRequest {
+                sequence: self.next_sequence,
+                major: request[0],
+                minor: request[1],
+            });
+            self.next_slot = (self.next_slot + 1) % RECENT_REQUEST_LIMIT;
+            self.next_sequence = self.next_sequence.wrapping_add(1);
+        });
+    }
+
+    #[must_use]
+    pub fn find(&self, sequence: u16) -> Option<SentRequest> {
+        self.ring
+            .iter()
+            .flatten()
+            .find(|sent| sent.sequence == sequence)
+            .copied()
+    }
+
+    /// Describe a raw X error, naming the request that caused it if it's still remembered
+    #[must_use]
+    pub fn describe_error(&self, raw: &[u8]) -> Option<String> {
+        if raw.len() < 32 || raw[0] != 0 {
+            return None;
+        }
+        let code = raw[1];
+        let error = ERROR_NAMES
+            .get(usize::from(code))
+            .filter(|name| !name.is_empty())
+            .map_or_else(|| format!("Error {code}"), |name| String::from(*name));
+        let sequence = u16::from_ne_bytes([raw[2], raw[3]]);
+        let bad_value = u32::from_ne_bytes([raw[4], raw[5], raw[6], raw[7]]);
+        let request = self.find(sequence).map_or_else(
+            || {
+                // The error still says which request it was, just not exactly when it was sent
+                SentRequest {
+                    sequence,
+                    major: raw[10],
+                    minor: raw[8],
+                }
+                .name()
+                    + " (not recently sent)"
+            },
+            |sent| sent.name(),
+        );
+        Some(format!(
+            "{error} for {bad_value} from {request}, sequence {sequence}"
+        ))
+    }
+}
+
+impl Default for RecentRequests {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::util::requests::RecentRequests;
+
+    fn request(major: u8, minor: u8, units: u16) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&[major, minor]);
+        bytes.extend_from_slice(&units.to_ne_bytes());
+        bytes.resize(usize::from(units) * 4, 0);
+        bytes
+    }
+
+    fn error(code: u8, sequence: u16, bad_value: u32, major: u8) -> [u8; 32] {
+        let mut raw = [0u8; 32];
+        raw[1] = code;
+        raw[2..4].copy_from_slice(&sequence.to_ne_bytes());
+        raw[4..8].copy_from_slice(&bad_value.to_ne_bytes());
+        raw[10] = major;
+        raw
+    }
+
+    #[test]
+    fn names_the_request_behind_an_error() {
+        let mut recent = RecentRequests::new();
+        // Connection setup
+        recent.record_written(&[0x6c, 0, 11, 0]);
+        let mut written = request(8, 0, 2);
+        written.extend(request(12, 0, 5));
+        recent.record_written(&written);
+        recent.record_written(&request(140, 3, 2));
+        assert_eq!(2, recent.find(2).unwrap().sequence);
+        assert_eq!(
+            "BadWindow for 77 from ConfigureWindow, sequence 2",
+            recent.describe_error(&error(3, 2, 77, 12)).unwrap()
+        );
+        assert_eq!(
+            "BadMatch for 0 from Request 140.3, sequence 3",
+            recent.describe_error(&error(8, 3, 0, 140)).unwrap()
+        );
+        assert_eq!(
+            "Error 200 for 5 from MapWindow (not recently sent), sequence 40",
+            recent.describe_error(&error(200, 40, 5, 8)).unwrap()
+        );
+        // Not an error
+        let mut event = error(3, 2, 77, 12);
+        event[0] = 20;
+        assert!(recent.describe_error(&event).is_none());
+    }
+
+    #[test]
+    fn forgets_old_requests() {
+        let mut recent = RecentRequests::new();
+        recent.record_written(&[0x6c, 0, 11, 0]);
+        for _ in 0..300 {
+            recent.record_written(&request(8, 0, 2));
+        }
+        assert!(recent.find(1).is_none());
+        assert!(recent.find(300).is_some());
+        assert!(recent.find(301).is_none());
+    }
+}
@@ -22,8 +22,23 @@ pub fn remove<T, const N: usize>(target: &mut heapless::Vec<T, N>, ind: usize) -
     out
 }
 
+/// Moves the item at `from` so that it ends up at `to`, shifting the items in between
+#[inline]
+pub fn move_item<T, const N: usize>(target: &mut heapless::Vec<T, N>, from: usize, to: usize) {
+    if from < to {
+        for i in from..to {
+            target.swap(i, i + 1);
+        }
+    } else {
+        for i in (to..from).rev() {
+            target.swap(i, i + 1);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::move_item;
     use super::push_to_front;
     use super::remove;
 
@@ -52,4 +67,19 @@ mod tests {
         assert_eq!(2, heapless_vec[1]);
         assert_eq!(3, heapless_vec[2]);
     }
+
+    #[test]
+    fn move_item_test() {
+        let mut heapless_vec: heapless::Vec<i32, 4> = heapless::Vec::new();
+        let _ = heapless_vec.push(0);
+        let _ = heapless_vec.push(1);
+        let _ = heapless_vec.push(2);
+        let _ = heapless_vec.push(3);
+        move_item(&mut heapless_vec, 0, 2);
+        assert_eq!(&[1, 2, 0, 3], heapless_vec.as_slice());
+        move_item(&mut heapless_vec, 3, 0);
+        assert_eq!(&[3, 1, 2, 0], heapless_vec.as_slice());
+        move_item(&mut heapless_vec, 1, 1);
+        assert_eq!(&[3, 1, 2, 0], heapless_vec.as_slice());
+    }
 }
@@ -0,0 +1,329 @@
+//! Runs pgwm against a headless `Xvfb` server, so that what it does on a real X server can be tested end to end.
+//!
+//! Needs on the path:
+//! - `Xvfb` to run the server.
+//! - `xterm` as the client that gets managed.
+//! - `xprop` and `xwininfo` (`x11-utils` on most distributions) to read back properties and geometry,
+//!   what the WM did is checked the way a user would see it rather than through a second protocol client.
+//!
+//! Also needs the fonts the WM is configured with, and a WM binary built with `build_wm.sh`.
+//! That's looked for in `$CARGO_TARGET_DIR`, or the workspace's `target` directory if it isn't set,
+//! `$PGWM_BIN` overrides it. The WM can't be built as a plain dependency of the tests because of the
+//! flags `build_wm.sh` builds it with.
+//!
+//! Tests skip themselves, printing what's missing, when any of that isn't there.
+//! Run them with `cargo test -p pgwm-xvfb -- --test-threads 1 --nocapture` to see if they did.
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Size of the `Xvfb` screen
+pub const SCREEN_WIDTH: i32 = 1280;
+pub const SCREEN_HEIGHT: i32 = 720;
+
+/// How long to wait for anything to show up before failing
+const TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The display numbers tried, high enough to not collide with a desktop session
+const DISPLAYS: core::ops::Range<u32> = 99..199;
+
+/// Everything a session runs besides the WM
+const PROGRAMS: [&str; 4] = ["Xvfb", "xterm", "xprop", "xwininfo"];
+
+/// The target `build_wm.sh` builds for, the WM binary ends up in a directory named after it
+const WM_TARGET: &str = "x86_64-unknown-linux-gnu";
+
+/// Where a window is on the screen, border excluded
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Geometry {
+    #[must_use]
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+}
+
+/// An `Xvfb` server with pgwm managing it, both are killed along with every spawned client on drop
+pub struct Session {
+    display: String,
+    xvfb: Child,
+    wm: Option<Child>,
+    clients: Vec<(u32, Child)>,
+}
+
+impl Session {
+    /// Start a session if everything it needs is there, otherwise print what's missing and return `None`,
+    /// so that a test can skip itself
+    ///
+    /// # Panics
+    /// Same as [`Session::start`]
+    #[must_use]
+    pub fn start_if_available() -> Option<Self> {
+        let missing = missing_dependencies();
+        if missing.is_empty() {
+            Some(Self::start())
+        } else {
+            eprintln!("Skipping, missing {}", missing.join(", "));
+            None
+        }
+    }
+
+    /// Start `Xvfb` on a free display and wait until pgwm has become its WM
+    ///
+    /// # Panics
+    /// If either can't be started, or pgwm doesn't advertise itself in time
+    #[must_use]
+    pub fn start() -> Self {
+        let display_num = free_display();
+        let display = format!(":{display_num}");
+        let screen = format!("{SCREEN_WIDTH}x{SCREEN_HEIGHT}x24");
+        let xvfb = Command::new("Xvfb")
+            .args([
+                display.as_str(),
+                "-screen",
+                "0",
+                &screen,
+                "-nolisten",
+                "tcp",
+            ])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start Xvfb, is it installed?");
+        let mut session = Self {
+            display,
+            xvfb,
+            wm: None,
+            clients: Vec::new(),
+        };
+        let socket = PathBuf::from(format!("/tmp/.X11-unix/X{display_num}"));
+        session.wait_for("Xvfb to accept connections", |_| socket.exists());
+        let wm = wm_binary();
+        session.wm = Some(
+            Command::new(&wm)
+                .env("DISPLAY", &session.display)
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap_or_else(|e| panic!("Failed to start {}: {e}", wm.display())),
+        );
+        session.wait_for("pgwm to become the WM", |session| {
+            !session.root_windows("_NET_SUPPORTING_WM_CHECK").is_empty()
+        });
+        session
+    }
+
+    /// Spawn an `xterm` with `class` as its `WM_CLASS` class, and wait until it's managed
+    ///
+    /// # Panics
+    /// If `xterm` can't be started, or it isn't managed in time
+    pub fn spawn_client(&mut self, class: &str) -> u32 {
+        let child = Command::new("xterm")
+            .args(["-class", class])
+            .env("DISPLAY", &self.display)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start xterm, is it installed?");
+        let quoted = format!("\"{class}\"");
+        let mut found = None;
+        self.wait_for("the client to be managed", |session| {
+            found = session
+                .root_windows("_NET_CLIENT_LIST")
+                .into_iter()
+                .find(|win| session.property(*win, "WM_CLASS").contains(&quoted));
+            found.is_some()
+        });
+        let window = found.unwrap();
+        self.clients.push((window, child));
+        window
+    }
+
+    /// Kill the client that owns `window`, and wait until it's no longer managed
+    ///
+    /// # Panics
+    /// If the window wasn't spawned through [`Session::spawn_client`], or it's still managed after the timeout
+    pub fn close_client(&mut self, window: u32) {
+        let ind = self
+            .clients
+            .iter()
+            .position(|(win, _)| *win == window)
+            .expect("Tried to close a window that wasn't spawned by the session");
+        let (_, mut child) = self.clients.remove(ind);
+        let _ = child.kill();
+        let _ = child.wait();
+        self.wait_for("the client to be unmanaged", |session| {
+            !session.root_windows("_NET_CLIENT_LIST").contains(&window)
+        });
+    }
+
+    /// The windows a root window property lists, empty if it isn't set
+    #[must_use]
+    pub fn root_windows(&self, property: &str) -> Vec<u32> {
+        self.windows_on(&["-root"], property)
+    }
+
+    /// The windows a property of `window` lists, empty if it isn't set
+    #[must_use]
+    pub fn windows(&self, window: u32, property: &str) -> Vec<u32> {
+        self.windows_on(&["-id", &format!("{window:#x}")], property)
+    }
+
+    fn windows_on(&self, target: &[&str], property: &str) -> Vec<u32> {
+        self.property_on(target, property)
+            .split_whitespace()
+            .filter_map(|word| parse_hex(word.trim_end_matches(',')))
+            .filter(|window| *window != 0)
+            .collect()
+    }
+
+    /// The atoms the WM lists in `_NET_SUPPORTED`
+    #[must_use]
+    pub fn supported(&self) -> Vec<String> {
+        self.property_on(&["-root"], "_NET_SUPPORTED")
+            .split(", ")
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// A property of a window as `xprop` shows it, like `"xterm", "Class"` for `WM_CLASS`
+    #[must_use]
+    pub fn property(&self, window: u32, property: &str) -> String {
+        self.property_on(&["-id", &format!("{window:#x}")], property)
+    }
+
+    fn property_on(&self, target: &[&str], property: &str) -> String {
+        let mut args = target.to_vec();
+        args.extend(["-notype", property]);
+        let out = self.xprop(&args);
+        out.split_once(" = ")
+            .or_else(|| out.split_once(": "))
+            .map(|(_, value)| value.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    /// # Panics
+    /// If `xwininfo` can't tell where the window is
+    #[must_use]
+    pub fn geometry(&self, window: u32) -> Geometry {
+        let out = self.run(
+            "xwininfo",
+            &["-display", &self.display, "-id", &format!("{window:#x}")],
+        );
+        Geometry {
+            x: xwininfo_field(&out, "Absolute upper-left X:"),
+            y: xwininfo_field(&out, "Absolute upper-left Y:"),
+            width: xwininfo_field(&out, "Width:"),
+            height: xwininfo_field(&out, "Height:"),
+        }
+    }
+
+    /// The focused window according to `_NET_ACTIVE_WINDOW`
+    #[must_use]
+    pub fn active_window(&self) -> Option<u32> {
+        self.root_windows("_NET_ACTIVE_WINDOW").first().copied()
+    }
+
+    /// Poll `done` until it's true
+    ///
+    /// # Panics
+    /// If it isn't within the timeout, or the WM has exited while waiting
+    pub fn wait_for<F: FnMut(&Self) -> bool>(&mut self, what: &str, mut done: F) {
+        let start = Instant::now();
+        while !done(self) {
+            if let Some(status) = self.wm.as_mut().and_then(|wm| wm.try_wait().ok().flatten()) {
+                panic!("pgwm exited with {status} while waiting for {what}");
+            }
+            assert!(start.elapsed() < TIMEOUT, "Timed out waiting for {what}");
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn xprop(&self, args: &[&str]) -> String {
+        let mut all = vec!["-display", self.display.as_str()];
+        all.extend_from_slice(args);
+        self.run("xprop", &all)
+    }
+
+    #[allow(clippy::unused_self)]
+    fn run(&self, program: &str, args: &[&str]) -> String {
+        let out = Command::new(program)
+            .args(args)
+            .stderr(Stdio::null())
+            .output()
+            .unwrap_or_else(|e| panic!("Failed to run {program}, is it installed? {e}"));
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let wm = self.wm.iter_mut();
+        let clients = self.clients.iter_mut().map(|(_, child)| child);
+        for child in clients.chain(wm).chain(core::iter::once(&mut self.xvfb)) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// What of [`PROGRAMS`] isn't on the path, and the WM binary if it isn't built
+fn missing_dependencies() -> Vec<String> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut missing = PROGRAMS
+        .into_iter()
+        .filter(|program| !std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let wm = wm_binary();
+    if !wm.is_file() {
+        missing.push(format!(
+            "the WM at {}, build it with build_wm.sh or point PGWM_BIN at it",
+            wm.display()
+        ));
+    }
+    missing
+}
+
+/// The first display without a lock file, `Xvfb` creates one when it starts
+fn free_display() -> u32 {
+    DISPLAYS
+        .into_iter()
+        .find(|num| !Path::new(&format!("/tmp/.X{num}-lock")).exists())
+        .expect("No free display")
+}
+
+fn xwininfo_field<T: FromStr>(out: &str, name: &str) -> T {
+    out.lines()
+        .find_map(|line| line.trim().strip_prefix(name))
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or_else(|| panic!("No {name} in xwininfo output {out}"))
+}
+
+/// `$PGWM_BIN`, or the debug build of `build_wm.sh` in `$CARGO_TARGET_DIR` or the workspace's `target`
+fn wm_binary() -> PathBuf {
+    if let Some(bin) = std::env::var_os("PGWM_BIN") {
+        return PathBuf::from(bin);
+    }
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(
+            || Path::new(env!("CARGO_MANIFEST_DIR")).join("../target"),
+            PathBuf::from,
+        )
+        .join(WM_TARGET)
+        .join("debug/pgwm")
+}
+
+fn parse_hex(word: &str) -> Option<u32> {
+    u32::from_str_radix(word.strip_prefix("0x")?, 16).ok()
+}
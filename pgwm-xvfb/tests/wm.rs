@@ -0,0 +1,73 @@
+use pgwm_xvfb::{Session, SCREEN_WIDTH};
+
+#[test]
+fn advertises_ewmh_support() {
+    let Some(session) = Session::start_if_available() else {
+        return;
+    };
+    let check = session.root_windows("_NET_SUPPORTING_WM_CHECK")[0];
+    assert_eq!(
+        vec![check],
+        session.windows(check, "_NET_SUPPORTING_WM_CHECK")
+    );
+    assert_eq!("\"pgwm\"", session.property(check, "_NET_WM_NAME"));
+    let supported = session.supported();
+    for atom in [
+        "_NET_CLIENT_LIST",
+        "_NET_ACTIVE_WINDOW",
+        "_NET_WM_STATE_FULLSCREEN",
+    ] {
+        assert!(
+            supported.iter().any(|name| name == atom),
+            "{atom} isn't supported"
+        );
+    }
+}
+
+#[test]
+fn tiles_windows_side_by_side() {
+    let Some(mut session) = Session::start_if_available() else {
+        return;
+    };
+    let first = session.spawn_client("PgwmFirst");
+    let only = session.geometry(first);
+    let second = session.spawn_client("PgwmSecond");
+    session.wait_for("the first window to make room", |session| {
+        session.geometry(first) != only
+    });
+    let (first, second) = (session.geometry(first), session.geometry(second));
+    let (left, right) = if first.x < second.x {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    assert!(left.right() <= right.x, "{left:?} overlaps {right:?}");
+    // Padding and borders aside, together they take up the monitor's width
+    let used = left.width + right.width;
+    assert!(
+        used > SCREEN_WIDTH * 9 / 10 && used < SCREEN_WIDTH,
+        "{left:?} and {right:?} don't fill the width"
+    );
+    // Below the status bar
+    assert!(left.y > 0 && left.y == right.y);
+    assert_eq!(left.height, right.height);
+}
+
+#[test]
+fn focuses_new_windows_and_falls_back_on_close() {
+    let Some(mut session) = Session::start_if_available() else {
+        return;
+    };
+    let first = session.spawn_client("PgwmFirst");
+    session.wait_for("the first window to be focused", |session| {
+        session.active_window() == Some(first)
+    });
+    let second = session.spawn_client("PgwmSecond");
+    session.wait_for("the second window to be focused", |session| {
+        session.active_window() == Some(second)
+    });
+    session.close_client(second);
+    session.wait_for("focus to go back to the first window", |session| {
+        session.active_window() == Some(first)
+    });
+}
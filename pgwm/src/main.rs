@@ -1,9 +1,14 @@
 #![no_std]
 #![no_main]
 
-use pgwm_app::main_loop;
+use pgwm_app::{check_config, install_session, main_loop};
 
 #[no_mangle]
 fn main() -> i32 {
-    main_loop()
+    let mut args = tiny_std::env::args().skip(1).filter_map(Result::ok);
+    match args.next() {
+        Some("--install-session") => install_session(args),
+        Some("--check-config") => check_config(),
+        _ => main_loop(),
+    }
 }